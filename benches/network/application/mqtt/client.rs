@@ -61,6 +61,7 @@ fn setup_client(client_id: &str) -> Client<NetConnection> {
         client_id,
         keep_alive_seconds: 10,
         clean_session: true,
+        max_packet_size: None,
     };
 
     Client::connect(conn, opts).expect("Failed to connect")
@@ -0,0 +1,85 @@
+//! Shared helpers for the example binaries.
+//!
+//! `TcpConnection` adapts `std::net::TcpStream` to the crate's `Connection`
+//! traits so the protocol clients can run on a desktop host.
+
+#![allow(dead_code)]
+
+use libiot::network::error::Error;
+use libiot::network::{Close, Connection, Read, Write};
+use std::io::{ErrorKind, Read as _, Write as _};
+use std::net::{Shutdown, TcpStream};
+use std::time::Duration;
+
+/// A blocking TCP connection with a read timeout.
+pub struct TcpConnection {
+    stream: TcpStream,
+}
+
+impl TcpConnection {
+    /// Connect to `address` (e.g. "example.com:80").
+    pub fn connect(address: &str, read_timeout: Duration) -> std::io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_read_timeout(Some(read_timeout))?;
+        stream.set_nodelay(true)?;
+        Ok(Self { stream })
+    }
+}
+
+fn map_error(e: std::io::Error, fallback: Error) -> Error {
+    match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => Error::Timeout,
+        ErrorKind::ConnectionRefused => Error::ConnectionRefused,
+        ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe => {
+            Error::ConnectionClosed
+        }
+        _ => fallback,
+    }
+}
+
+impl Read for TcpConnection {
+    type Error = Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.stream
+            .read(buf)
+            .map_err(|e| map_error(e, Error::ReadError))
+    }
+}
+
+impl Write for TcpConnection {
+    type Error = Error;
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.stream
+            .write_all(buf)
+            .map(|()| buf.len())
+            .map_err(|e| map_error(e, Error::WriteError))
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.stream
+            .flush()
+            .map_err(|e| map_error(e, Error::WriteError))
+    }
+}
+
+impl Close for TcpConnection {
+    type Error = Error;
+
+    fn close(self) -> Result<(), Self::Error> {
+        self.stream
+            .shutdown(Shutdown::Both)
+            .map_err(|e| map_error(e, Error::ConnectionClosed))
+    }
+}
+
+impl Connection for TcpConnection {}
+
+/// Build a single HTTP header.
+pub fn header(name: &str, value: &str) -> libiot::network::application::http::client::Header {
+    libiot::network::application::http::client::Header {
+        name: heapless::String::try_from(name).expect("header name too long"),
+        value: heapless::String::try_from(value).expect("header value too long"),
+    }
+}
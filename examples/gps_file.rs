@@ -0,0 +1,79 @@
+//! Parse a log of NMEA sentences and track the GPS fix.
+//!
+//! ```text
+//! cargo run --example gps_file --features std -- [path/to/log.nmea]
+//! ```
+//!
+//! Each line of the file is parsed as one sentence. Without a path, a small
+//! built-in log is used.
+
+use libiot::gps::{GpsState, NmeaParser, NmeaSentence};
+use libiot::system::clock::MonotonicClock;
+use std::time::Instant;
+
+const SAMPLE_LOG: &str = "\
+$GPGGA,123519,,N,,E,0,00,,,M,,M,,*60
+$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47
+$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68
+$GPTXT,01,01,02,ANTSTATUS=OK*3B
+";
+
+/// Milliseconds since the example started.
+struct StdClock(Instant);
+
+impl MonotonicClock for StdClock {
+    fn now_ms(&self) -> u64 {
+        self.0.elapsed().as_millis() as u64
+    }
+}
+
+fn main() {
+    let log = match std::env::args().nth(1) {
+        Some(path) => std::fs::read_to_string(&path).expect("failed to read log"),
+        None => SAMPLE_LOG.to_string(),
+    };
+
+    let mut state = GpsState::new(StdClock(Instant::now()));
+
+    for (number, line) in log.lines().enumerate() {
+        // `lines()` strips the terminator the parser expects
+        let sentence = format!("{}\r\n", line.trim_end());
+        match NmeaParser::parse_bytes(sentence.as_bytes()) {
+            Ok(parsed) => {
+                let fix = state.update(&parsed);
+                match parsed {
+                    NmeaSentence::Gpgga(gga) => println!(
+                        "{:>4}: GGA {:02}:{:02}:{:02} sats={} fix={}",
+                        number + 1,
+                        gga.time.hour,
+                        gga.time.minute,
+                        gga.time.second,
+                        gga.satellites_used,
+                        fix
+                    ),
+                    NmeaSentence::Gprmc(rmc) => println!(
+                        "{:>4}: RMC {:04}-{:02}-{:02} speed={}kn fix={}",
+                        number + 1,
+                        rmc.date.year,
+                        rmc.date.month,
+                        rmc.date.day,
+                        rmc.speed_knots,
+                        fix
+                    ),
+                    other => println!("{:>4}: {:?}", number + 1, other.sentence_type()),
+                }
+            }
+            Err(e) => println!("{:>4}: skipped ({e:?})", number + 1),
+        }
+    }
+
+    match (state.latitude(), state.longitude()) {
+        (Some(lat), Some(lon)) => println!(
+            "last fix: {:.5}, {:.5} (time to first fix {} ms)",
+            lat.to_decimal_degrees(),
+            lon.to_decimal_degrees(),
+            state.time_to_first_fix_ms().unwrap_or_default()
+        ),
+        _ => println!("no fix in log"),
+    }
+}
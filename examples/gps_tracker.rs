@@ -0,0 +1,65 @@
+//! Publish GPS fixes from an NMEA log as JSON telemetry over MQTT.
+//!
+//! ```text
+//! cargo run --example gps_tracker --features std -- [broker:port] [topic] [path/to/log.nmea]
+//! ```
+//!
+//! Defaults to the public `test.mosquitto.org:1883` broker and a small
+//! built-in log. Every valid RMC fix is published as one JSON document.
+
+mod common;
+
+use common::TcpConnection;
+use libiot::gps::telemetry::Telemetry;
+use libiot::gps::{NmeaParser, NmeaSentence};
+use libiot::network::application::mqtt::client::{Client, Options, QoS};
+use std::time::Duration;
+
+const SAMPLE_LOG: &str = "\
+$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47
+$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68
+";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let broker = args
+        .next()
+        .unwrap_or_else(|| "test.mosquitto.org:1883".to_string());
+    let topic = args
+        .next()
+        .unwrap_or_else(|| "libiot/example/tracker".to_string());
+    let log = match args.next() {
+        Some(path) => std::fs::read_to_string(&path).expect("failed to read log"),
+        None => SAMPLE_LOG.to_string(),
+    };
+
+    let connection =
+        TcpConnection::connect(&broker, Duration::from_secs(2)).expect("failed to connect");
+    let options = Options {
+        client_id: "libiot-tracker",
+        keep_alive_seconds: 60,
+        clean_session: true,
+        max_packet_size: None,
+    };
+    let mut client = Client::connect(connection, options).expect("MQTT connect failed");
+    println!("connected to {broker}");
+
+    let mut published = 0;
+    for line in log.lines() {
+        // `lines()` strips the terminator the parser expects
+        let sentence = format!("{}\r\n", line.trim_end());
+        let Ok(NmeaSentence::Gprmc(rmc)) = NmeaParser::parse_bytes(sentence.as_bytes()) else {
+            continue;
+        };
+        let Some(report) = Telemetry::from_rmc(&rmc) else {
+            println!("skipping RMC without a fix");
+            continue;
+        };
+        report
+            .publish(&mut client, &topic, QoS::AtMostOnce)
+            .expect("publish failed");
+        published += 1;
+        println!("published {}", report.to_json().unwrap());
+    }
+    println!("done, {published} fix(es) published to {topic}");
+}
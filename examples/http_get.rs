@@ -0,0 +1,50 @@
+//! Perform an HTTP GET over TCP and print the response.
+//!
+//! ```text
+//! cargo run --example http_get --features std -- [host[:port]] [path]
+//! ```
+//!
+//! Defaults to `http://httpbin.org:80/get`.
+
+mod common;
+
+use common::{TcpConnection, header};
+use libiot::network::application::http::client::{Client, Method, Request};
+use std::time::Duration;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let host = args.next().unwrap_or_else(|| "httpbin.org".to_string());
+    let path = args.next().unwrap_or_else(|| "/get".to_string());
+    let address = if host.contains(':') {
+        host.clone()
+    } else {
+        format!("{host}:80")
+    };
+
+    let connection =
+        TcpConnection::connect(&address, Duration::from_secs(5)).expect("failed to connect");
+    let mut client = Client::new(connection);
+
+    let mut headers = heapless::Vec::new();
+    headers.push(header("Host", &host)).unwrap();
+    headers.push(header("Connection", "close")).unwrap();
+
+    let request = Request {
+        method: Method::Get,
+        path: &path,
+        headers,
+        body: None,
+    };
+
+    let response = client.request(&request).expect("request failed");
+    println!("HTTP {}", response.status_code);
+    for h in &response.headers {
+        println!("{}: {}", h.name, h.value);
+    }
+    if response.headers_truncated {
+        println!("(some headers were truncated)");
+    }
+    println!();
+    println!("{}", String::from_utf8_lossy(&response.body));
+}
@@ -0,0 +1,71 @@
+//! Publish to and subscribe from an MQTT broker.
+//!
+//! ```text
+//! cargo run --example mqtt_pubsub --features std -- [broker:port] [topic]
+//! ```
+//!
+//! Defaults to the public `test.mosquitto.org:1883` broker. The example
+//! subscribes to the topic, publishes a few messages to it and prints every
+//! message that comes back.
+
+mod common;
+
+use common::TcpConnection;
+use libiot::network::application::mqtt::client::{Client, Options, QoS};
+use libiot::network::error::Error;
+use std::time::Duration;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let broker = args
+        .next()
+        .unwrap_or_else(|| "test.mosquitto.org:1883".to_string());
+    let topic = args
+        .next()
+        .unwrap_or_else(|| "libiot/example/pubsub".to_string());
+
+    let connection =
+        TcpConnection::connect(&broker, Duration::from_secs(2)).expect("failed to connect");
+    let options = Options {
+        client_id: "libiot-example",
+        keep_alive_seconds: 60,
+        clean_session: true,
+        max_packet_size: None,
+    };
+    let mut client = Client::connect(connection, options).expect("MQTT connect failed");
+    println!("connected to {broker}");
+
+    client
+        .subscribe(&topic, QoS::AtMostOnce)
+        .expect("subscribe failed");
+    println!("subscribed to {topic}");
+
+    for i in 0..3 {
+        let payload = format!("hello #{i}");
+        client
+            .publish(&topic, payload.as_bytes(), QoS::AtMostOnce)
+            .expect("publish failed");
+        println!("published {payload:?}");
+    }
+
+    // Give the broker a few read timeouts' worth of time to echo the messages
+    let mut received = 0;
+    let mut idle_polls = 0;
+    while received < 3 && idle_polls < 5 {
+        match client.poll_ref() {
+            Ok(Some(message)) => {
+                received += 1;
+                println!(
+                    "received on {}: {}",
+                    message.topic,
+                    String::from_utf8_lossy(message.payload)
+                );
+            }
+            Ok(None) => {}
+            // The read timed out without data
+            Err(Error::ReadError) => idle_polls += 1,
+            Err(e) => panic!("poll failed: {e:?}"),
+        }
+    }
+    println!("done, {received} message(s) received");
+}
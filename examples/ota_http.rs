@@ -0,0 +1,110 @@
+//! Download a firmware image over HTTP with the OTA driver.
+//!
+//! ```text
+//! cargo run --example ota_http --features std -- <host:port> <path> <size> [crc32] [out.bin]
+//! ```
+//!
+//! The server must honor `Range` requests (answering `206 Partial Content`)
+//! and keep the connection alive between requests; most static file servers
+//! such as nginx do. The image is written to a RAM-backed storage and saved
+//! to `out.bin` (default `firmware.bin`) once verified.
+
+mod common;
+
+use common::TcpConnection;
+use libiot::network::application::http::client::Client;
+use libiot::ota::{Config, HttpSource, Ota, Progress};
+use libiot::storage::error::Error as StorageError;
+use libiot::storage::{BlockingErase, ReadStorage, Storage};
+use std::time::Duration;
+
+/// Storage backed by a heap buffer, standing in for a flash partition.
+struct VecStorage(Vec<u8>);
+
+impl VecStorage {
+    fn range(&self, offset: u32, len: usize) -> Result<core::ops::Range<usize>, StorageError> {
+        let start = offset as usize;
+        let end = start.checked_add(len).ok_or(StorageError::OutOfBounds)?;
+        if end > self.0.len() {
+            return Err(StorageError::OutOfBounds);
+        }
+        Ok(start..end)
+    }
+}
+
+impl ReadStorage for VecStorage {
+    type Error = StorageError;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let range = self.range(offset, bytes.len())?;
+        bytes.copy_from_slice(&self.0[range]);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl Storage for VecStorage {
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let range = self.range(offset, bytes.len())?;
+        self.0[range].copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl BlockingErase for VecStorage {
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        let len = to.checked_sub(from).ok_or(StorageError::OutOfBounds)? as usize;
+        let range = self.range(from, len)?;
+        self.0[range].fill(0xFF);
+        Ok(())
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 3 {
+        eprintln!("usage: ota_http <host:port> <path> <size> [crc32] [out.bin]");
+        std::process::exit(2);
+    }
+    let address = &args[0];
+    let path = &args[1];
+    let size: usize = args[2].parse().expect("size must be a number");
+    let crc32 = args
+        .get(3)
+        .map(|c| u32::from_str_radix(c.trim_start_matches("0x"), 16).expect("crc32 must be hex"));
+    let out = args.get(4).map(String::as_str).unwrap_or("firmware.bin");
+
+    let connection =
+        TcpConnection::connect(address, Duration::from_secs(10)).expect("failed to connect");
+    let mut http = Client::new(connection);
+    let mut storage = VecStorage(vec![0xFF; size]);
+
+    let mut ota = Ota::new(Config {
+        verify_crc32: crc32.is_some(),
+        ..Config::default()
+    })
+    .expect("invalid OTA config");
+    let source = HttpSource {
+        host: address,
+        path,
+        size,
+        crc32,
+        expected_sha256: None,
+    };
+
+    let mut report = |progress: Progress| {
+        println!(
+            "{:?}: {}/{} bytes",
+            progress.state, progress.bytes_downloaded, progress.bytes_total
+        );
+    };
+    let result = ota.run_http(&mut http, &mut storage, 0, &source, None, Some(&mut report));
+    println!("OTA finished in state {:?}", ota.state());
+    result.expect("OTA failed");
+
+    std::fs::write(out, &storage.0).expect("failed to save image");
+    println!("wrote {size} bytes to {out}");
+}
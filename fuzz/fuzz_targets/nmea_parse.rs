@@ -0,0 +1,26 @@
+//! Fuzz the NMEA parser with arbitrary bytes.
+//!
+//! Run with `cargo +nightly fuzz run nmea_parse` from the repository root.
+//! Any panic (slice out of bounds, arithmetic overflow, ...) is a finding.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libiot::gps::NmeaParser;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = NmeaParser::parse_bytes(data);
+
+    // Also exercise the `&str` helpers directly, without the length and
+    // framing checks `parse` performs first.
+    if let Ok(s) = core::str::from_utf8(data) {
+        let _ = NmeaParser::parse(s, false);
+        let _ = NmeaParser::get_sentence_type(s);
+        let _ = NmeaParser::calculate_checksum(s);
+        let _ = NmeaParser::has_checksum(s);
+        let _ = NmeaParser::split_fields(s);
+        let _ = NmeaParser::parse_position(s);
+        let _ = NmeaParser::parse_time(s);
+        let _ = NmeaParser::parse_date(s);
+    }
+});
@@ -0,0 +1,117 @@
+//! Crate-wide error type for applications that use several modules.
+//!
+//! Each module keeps its own error enum so that firmware using only one
+//! module does not depend on the others' failure modes. An application that
+//! combines them, such as a gateway that reads GPS, stores fixes and serves
+//! MCP calls, can instead return [`Result`] from its own functions and let
+//! `?` convert each module error into [`Error`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::gps::{NmeaParser, NmeaSentence};
+//! use libiot::storage::{Storage, error::Error as StorageError};
+//! use libiot::storage::slice::SliceStorage;
+//!
+//! fn log_fix<S>(line: &str, storage: &mut S) -> libiot::Result<()>
+//! where
+//!     S: Storage<Error = StorageError>,
+//! {
+//!     if let NmeaSentence::Gprmc(rmc) = NmeaParser::parse(line, true)? {
+//!         storage.write(0, &[rmc.time.hour, rmc.time.minute, rmc.time.second])?;
+//!     }
+//!     Ok(())
+//! }
+//!
+//! let mut storage = SliceStorage::new(&[0; 16]);
+//! let fix = "$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n";
+//! assert_eq!(
+//!     log_fix(fix, &mut storage),
+//!     Err(libiot::Error::Storage(StorageError::Unsupported))
+//! );
+//! assert!(matches!(log_fix("$GPRMC,bad", &mut storage), Err(libiot::Error::Nmea(_))));
+//! ```
+
+use crate::gps::NmeaError;
+use crate::network::application::mcp::McpError;
+use crate::{network, ota, storage};
+
+/// Any error produced by this crate.
+///
+/// Each variant wraps the error of one module unchanged, so callers can
+/// still match on the underlying cause.
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A transport or protocol operation failed.
+    Network(network::error::Error),
+    /// A storage device operation failed.
+    Storage(storage::error::Error),
+    /// An over-the-air update failed.
+    Ota(ota::Error),
+    /// An MCP message or function call failed.
+    Mcp(McpError),
+    /// An NMEA sentence could not be parsed.
+    Nmea(NmeaError),
+}
+
+/// `Result` with the crate-wide [`Error`].
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl From<network::error::Error> for Error {
+    fn from(e: network::error::Error) -> Self {
+        Error::Network(e)
+    }
+}
+
+impl From<storage::error::Error> for Error {
+    fn from(e: storage::error::Error) -> Self {
+        Error::Storage(e)
+    }
+}
+
+impl From<ota::Error> for Error {
+    fn from(e: ota::Error) -> Self {
+        Error::Ota(e)
+    }
+}
+
+impl From<McpError> for Error {
+    fn from(e: McpError) -> Self {
+        Error::Mcp(e)
+    }
+}
+
+impl From<NmeaError> for Error {
+    fn from(e: NmeaError) -> Self {
+        Error::Nmea(e)
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Network(e) => write!(f, "network: {e}"),
+            Error::Storage(e) => write!(f, "storage: {e:?}"),
+            Error::Ota(e) => write!(f, "ota: {e:?}"),
+            Error::Mcp(e) => write!(f, "mcp: {e:?}"),
+            Error::Nmea(e) => write!(f, "nmea: {e:?}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::Network(e) => defmt::write!(f, "Network({})", e),
+            Error::Storage(e) => defmt::write!(f, "Storage({})", e),
+            Error::Ota(e) => defmt::write!(f, "Ota({})", e),
+            Error::Mcp(e) => defmt::write!(f, "Mcp({})", e),
+            Error::Nmea(e) => defmt::write!(f, "Nmea({})", e),
+        }
+    }
+}
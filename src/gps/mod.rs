@@ -1,7 +1,29 @@
 //! GPS NMEA 0183 sentence parser
 //!
 //! This module provides a lightweight NMEA parser for embedded systems,
-//! supporting common GPS sentence types like GPGGA, GPRMC, and GPGLL.
+//! supporting common GPS sentence types like GPGGA, GPRMC, GPGLL, GPVTG and
+//! GPGSV, from GPS and multi-constellation (GLONASS, Galileo, BeiDou, QZSS)
+//! talkers.
+//! Parsed sentences can be folded into a [`GpsState`] to track the latest fix
+//! along with time-to-first-fix and fix age, and speeds into a
+//! [`MotionDetector`] for a debounced moving/stationary signal. The
+//! [`telemetry`] helpers turn an RMC fix into compact JSON for MQTT, and
+//! [`format_decimal_degrees`] prints coordinates on targets without float
+//! formatting. [`NmeaReader`] frames sentences from a raw serial byte stream,
+//! and [`NmeaBuilder`] produces sentences, e.g. receiver commands, to send.
+//! [`PositionF32`] and [`NmeaParser::parse_position_f32`] avoid `f64` on
+//! FPUs that only support single precision.
+//! With the `serde` feature the parsed sentence types implement `Serialize`
+//! and `Deserialize`, for use with `serde_json_core` or other `no_std`
+//! formats.
+
+pub mod motion;
+pub mod reader;
+pub mod state;
+pub mod telemetry;
+pub use motion::{Motion, MotionDetector};
+pub use reader::{NmeaReader, ReaderStats};
+pub use state::GpsState;
 
 /// Maximum length of an NMEA sentence including \r\n
 pub const NMEA_MAX_LENGTH: usize = 82;
@@ -16,6 +38,7 @@ pub const NMEA_END_CHAR_2: u8 = b'\n';
 
 /// NMEA sentence types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NmeaType {
     /// Unknown sentence type
     Unknown,
@@ -35,6 +58,63 @@ pub enum NmeaType {
     Gpvtg,
 }
 
+impl NmeaType {
+    /// Sentence identifier, e.g. `"GPGGA"` (`"UNKNOWN"` for [`NmeaType::Unknown`])
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NmeaType::Unknown => "UNKNOWN",
+            NmeaType::Gpgga => "GPGGA",
+            NmeaType::Gpgll => "GPGLL",
+            NmeaType::Gpgsa => "GPGSA",
+            NmeaType::Gpgsv => "GPGSV",
+            NmeaType::Gprmc => "GPRMC",
+            NmeaType::Gptxt => "GPTXT",
+            NmeaType::Gpvtg => "GPVTG",
+        }
+    }
+}
+
+/// Talker IDs accepted in sentence identifiers
+const TALKERS: [&str; 8] = ["GP", "GN", "GL", "GA", "GB", "BD", "GQ", "QZ"];
+
+/// Parse a sentence identifier such as `"GPGGA"`, ignoring ASCII case.
+///
+/// Identifiers from the combined (`GN`) and other constellation talkers
+/// (`GL`, `GA`, `GB`/`BD`, `GQ`/`QZ`) map to the same type as their `GP`
+/// counterpart. Anything else, including `"UNKNOWN"`, is rejected with
+/// `NmeaError::UnsupportedSentence`.
+impl TryFrom<&str> for NmeaType {
+    type Error = NmeaError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let talker = value.get(..2).ok_or(NmeaError::UnsupportedSentence)?;
+        if !TALKERS.iter().any(|t| t.eq_ignore_ascii_case(talker)) {
+            return Err(NmeaError::UnsupportedSentence);
+        }
+        let sentence = &value[2..];
+        [
+            NmeaType::Gpgga,
+            NmeaType::Gpgll,
+            NmeaType::Gpgsa,
+            NmeaType::Gpgsv,
+            NmeaType::Gprmc,
+            NmeaType::Gptxt,
+            NmeaType::Gpvtg,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str()[2..].eq_ignore_ascii_case(sentence))
+        .ok_or(NmeaError::UnsupportedSentence)
+    }
+}
+
+impl core::str::FromStr for NmeaType {
+    type Err = NmeaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
 /// Cardinal direction
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CardinalDirection {
@@ -74,8 +154,52 @@ impl CardinalDirection {
     }
 }
 
+/// Serialized as its single-character form, `"N"`, `"E"`, `"S"` or `"W"`;
+/// `Unknown` is the empty string
+#[cfg(feature = "serde")]
+impl serde::Serialize for CardinalDirection {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut buf = [0u8; 4];
+        match self {
+            CardinalDirection::Unknown => serializer.serialize_str(""),
+            direction => serializer.serialize_str(direction.to_char().encode_utf8(&mut buf)),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CardinalDirection {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DirectionVisitor;
+
+        impl serde::de::Visitor<'_> for DirectionVisitor {
+            type Value = CardinalDirection;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str("a cardinal direction character")
+            }
+
+            fn visit_char<E: serde::de::Error>(self, c: char) -> Result<Self::Value, E> {
+                Ok(CardinalDirection::from_char(c))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                let mut chars = v.chars();
+                match (chars.next(), chars.next()) {
+                    (None, _) => Ok(CardinalDirection::Unknown),
+                    (Some(c), None) => Ok(CardinalDirection::from_char(c)),
+                    _ => Err(E::invalid_value(serde::de::Unexpected::Str(v), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_str(DirectionVisitor)
+    }
+}
+
 /// GPS position (latitude or longitude)
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     /// Degrees component of the position
     pub degrees: i32,
@@ -115,8 +239,64 @@ impl Default for Position {
     }
 }
 
+/// Single-precision GPS position for FPUs without double-precision support
+///
+/// Same layout as [`Position`] with `f32` minutes, so parsing with
+/// [`NmeaParser::parse_position_f32`] and converting with
+/// [`to_decimal_degrees`](Self::to_decimal_degrees) never touch `f64`, which
+/// on e.g. a Cortex-M4F would be emulated in software.
+///
+/// `f32` keeps about 7 significant digits. Minutes (below 60) are held to
+/// roughly 4e-6', about 1 cm on the ground, but decimal degrees of a
+/// longitude above 128° only resolve to about 1.5e-5°, around 1.5 m at the
+/// equator. Parsed sentences such as [`Gprmc`] keep using the `f64`
+/// [`Position`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PositionF32 {
+    /// Degrees component of the position
+    pub degrees: i32,
+    /// Minutes component of the position (decimal)
+    pub minutes: f32,
+    /// Cardinal direction (N/S for latitude, E/W for longitude)
+    pub cardinal: CardinalDirection,
+}
+
+impl PositionF32 {
+    /// Create a new position
+    pub fn new(degrees: i32, minutes: f32, cardinal: CardinalDirection) -> Self {
+        Self {
+            degrees,
+            minutes,
+            cardinal,
+        }
+    }
+
+    /// Convert to decimal degrees
+    pub fn to_decimal_degrees(&self) -> f32 {
+        let decimal = self.degrees as f32 + self.minutes / 60.0;
+        match self.cardinal {
+            CardinalDirection::South | CardinalDirection::West => -decimal,
+            _ => decimal,
+        }
+    }
+}
+
+impl Default for PositionF32 {
+    fn default() -> Self {
+        Self {
+            degrees: 0,
+            minutes: 0.0,
+            cardinal: CardinalDirection::Unknown,
+        }
+    }
+}
+
 /// Time structure for NMEA sentences
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Ordering is chronological (hour, then minute, then second).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NmeaTime {
     /// Hour (0-23)
     pub hour: u8,
@@ -137,7 +317,10 @@ impl Default for NmeaTime {
 }
 
 /// Date structure for NMEA sentences
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Ordering is chronological (year, then month, then day).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NmeaDate {
     /// Day of month (1-31)
     pub day: u8,
@@ -157,8 +340,60 @@ impl Default for NmeaDate {
     }
 }
 
+impl PartialOrd for NmeaDate {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NmeaDate {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        (self.year, self.month, self.day).cmp(&(other.year, other.month, other.day))
+    }
+}
+
+/// Combined UTC date and time of a fix
+///
+/// Ordering is chronological (date first, then time of day).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NmeaDateTime {
+    /// Date component
+    pub date: NmeaDate,
+    /// Time component
+    pub time: NmeaTime,
+}
+
+impl NmeaDateTime {
+    /// Create a new date/time pair
+    pub fn new(date: NmeaDate, time: NmeaTime) -> Self {
+        Self { date, time }
+    }
+
+    /// Seconds since the Unix epoch (1970-01-01T00:00:00Z)
+    ///
+    /// Dates before 1970 saturate to 0.
+    pub fn unix_timestamp(&self) -> u64 {
+        // Days from civil date, with March as the first month of the year
+        let (month, day) = (self.date.month as i64, self.date.day as i64);
+        let year = self.date.year as i64 - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - 719_468;
+
+        let seconds = days * 86_400
+            + self.time.hour as i64 * 3600
+            + self.time.minute as i64 * 60
+            + self.time.second as i64;
+        seconds.max(0) as u64
+    }
+}
+
 /// Base NMEA sentence structure
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NmeaBase {
     /// Type of NMEA sentence
     pub sentence_type: NmeaType,
@@ -168,6 +403,7 @@ pub struct NmeaBase {
 
 /// GPGGA sentence - Global Positioning System Fix Data
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gpgga {
     /// Base sentence information
     pub base: NmeaBase,
@@ -220,8 +456,30 @@ impl Default for Gpgga {
     }
 }
 
+impl Gpgga {
+    /// Estimated horizontal accuracy in meters using [`DEFAULT_UERE_M`],
+    /// or `None` if the sentence carries no HDOP
+    pub fn estimated_horizontal_accuracy_m(&self) -> Option<f32> {
+        (self.hdop > 0.0).then(|| estimated_horizontal_accuracy_m(self.hdop, DEFAULT_UERE_M))
+    }
+}
+
+/// Typical user-equivalent range error of a standalone GPS receiver, in meters
+pub const DEFAULT_UERE_M: f32 = 5.0;
+
+/// Rough horizontal accuracy in meters from a horizontal dilution of precision
+///
+/// Computed as `hdop * uere`, where `uere` is the user-equivalent range error
+/// of the receiver in meters ([`DEFAULT_UERE_M`] if unknown). This is a
+/// statistical estimate (roughly one sigma) for thresholding, not a bound on
+/// the actual position error.
+pub fn estimated_horizontal_accuracy_m(hdop: f32, uere: f32) -> f32 {
+    hdop * uere
+}
+
 /// GPRMC sentence - Recommended Minimum Course
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gprmc {
     /// Base sentence information
     pub base: NmeaBase,
@@ -267,6 +525,7 @@ impl Default for Gprmc {
 
 /// GPGLL sentence - Geographic Position - Latitude/Longitude
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gpgll {
     /// Base sentence information
     pub base: NmeaBase,
@@ -295,8 +554,199 @@ impl Default for Gpgll {
     }
 }
 
+/// Mode indicator appended to sentences by NMEA 2.3 and later receivers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PositioningMode {
+    /// Autonomous fix (`A`)
+    Autonomous,
+    /// Differential fix (`D`)
+    Differential,
+    /// Dead reckoning estimate (`E`)
+    Estimated,
+    /// Data not valid (`N`)
+    NotValid,
+}
+
+impl PositioningMode {
+    /// Parse a mode indicator character
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'A' => Some(PositioningMode::Autonomous),
+            'D' => Some(PositioningMode::Differential),
+            'E' => Some(PositioningMode::Estimated),
+            'N' => Some(PositioningMode::NotValid),
+            _ => None,
+        }
+    }
+}
+
+/// Satellite navigation system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Constellation {
+    /// GPS (United States)
+    Gps,
+    /// GLONASS (Russia)
+    Glonass,
+    /// Galileo (European Union)
+    Galileo,
+    /// BeiDou (China)
+    BeiDou,
+    /// QZSS (Japan)
+    Qzss,
+    /// Satellite-based augmentation (WAAS, EGNOS, MSAS, ...)
+    Sbas,
+}
+
+impl Constellation {
+    /// Constellation of a single-system talker ID such as `"GL"`
+    ///
+    /// Returns `None` for the combined `GN` talker, whose satellites are
+    /// told apart by ID (see [`from_prn`](Self::from_prn)), and for unknown
+    /// talkers.
+    pub fn from_talker(talker: &str) -> Option<Self> {
+        match talker {
+            "GP" => Some(Constellation::Gps),
+            "GL" => Some(Constellation::Glonass),
+            "GA" => Some(Constellation::Galileo),
+            "GB" | "BD" => Some(Constellation::BeiDou),
+            "GQ" | "QZ" => Some(Constellation::Qzss),
+            _ => None,
+        }
+    }
+
+    /// Constellation of a satellite ID in a combined or `GP` sentence
+    ///
+    /// Receivers that report several systems under one talker number the
+    /// satellites from disjoint ranges. This follows the extended numbering
+    /// used by NMEA 4.x receivers:
+    ///
+    /// | Satellite ID | Constellation |
+    /// |--------------|---------------|
+    /// | 1-32         | GPS           |
+    /// | 33-64        | SBAS          |
+    /// | 65-96        | GLONASS       |
+    /// | 193-202      | QZSS          |
+    /// | 301-336      | Galileo       |
+    /// | 401-437      | BeiDou        |
+    ///
+    /// Other IDs return `None`.
+    pub fn from_prn(prn: u16) -> Option<Self> {
+        match prn {
+            1..=32 => Some(Constellation::Gps),
+            33..=64 => Some(Constellation::Sbas),
+            65..=96 => Some(Constellation::Glonass),
+            193..=202 => Some(Constellation::Qzss),
+            301..=336 => Some(Constellation::Galileo),
+            401..=437 => Some(Constellation::BeiDou),
+            _ => None,
+        }
+    }
+}
+
+/// Maximum number of satellites described by one GSV sentence
+pub const GSV_MAX_SATELLITES: usize = 4;
+
+/// One satellite entry of a GSV sentence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GsvSatellite {
+    /// Satellite ID (PRN)
+    pub prn: u16,
+    /// System the satellite belongs to, if known
+    pub constellation: Option<Constellation>,
+    /// Elevation in degrees (0-90), if reported
+    pub elevation_degrees: Option<u8>,
+    /// Azimuth in degrees true (0-359), if reported
+    pub azimuth_degrees: Option<u16>,
+    /// Signal-to-noise ratio in dB-Hz, `None` when not tracking
+    pub snr_db: Option<u8>,
+}
+
+/// GPGSV sentence - Satellites in View
+///
+/// A full sky view is spread over `total_messages` sentences of up to
+/// [`GSV_MAX_SATELLITES`] satellites each.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gpgsv {
+    /// Base sentence information
+    pub base: NmeaBase,
+    /// Constellation of the talker, `None` for combined (`GN`) sentences
+    pub constellation: Option<Constellation>,
+    /// Number of sentences in this cycle
+    pub total_messages: u8,
+    /// Index of this sentence, starting at 1
+    pub message_number: u8,
+    /// Total number of satellites in view
+    pub satellites_in_view: u8,
+    /// Satellites described by this sentence
+    pub satellites: heapless::Vec<GsvSatellite, GSV_MAX_SATELLITES>,
+}
+
+impl Default for Gpgsv {
+    fn default() -> Self {
+        Self {
+            base: NmeaBase {
+                sentence_type: NmeaType::Gpgsv,
+                errors: 0,
+            },
+            constellation: None,
+            total_messages: 0,
+            message_number: 0,
+            satellites_in_view: 0,
+            satellites: heapless::Vec::new(),
+        }
+    }
+}
+
+/// GPVTG sentence - Track Made Good and Ground Speed
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gpvtg {
+    /// Base sentence information
+    pub base: NmeaBase,
+    /// Track made good in degrees (true north)
+    pub track_true_degrees: f32,
+    /// Track made good in degrees (magnetic north)
+    pub track_magnetic_degrees: f32,
+    /// Speed over ground in knots
+    pub speed_knots: f32,
+    /// Speed over ground in km/h
+    pub speed_kmh: f32,
+    /// Mode indicator, absent from sentences before NMEA 2.3
+    pub mode: Option<PositioningMode>,
+}
+
+impl Gpvtg {
+    /// Whether the receiver flagged the data as valid
+    ///
+    /// Sentences without a mode indicator are assumed valid.
+    pub fn is_valid(&self) -> bool {
+        self.mode != Some(PositioningMode::NotValid)
+    }
+}
+
+impl Default for Gpvtg {
+    fn default() -> Self {
+        Self {
+            base: NmeaBase {
+                sentence_type: NmeaType::Gpvtg,
+                errors: 0,
+            },
+            track_true_degrees: 0.0,
+            track_magnetic_degrees: 0.0,
+            speed_knots: 0.0,
+            speed_kmh: 0.0,
+            mode: None,
+        }
+    }
+}
+
 /// Parsed NMEA sentence
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NmeaSentence {
     /// GPGGA sentence
     Gpgga(Gpgga),
@@ -304,6 +754,10 @@ pub enum NmeaSentence {
     Gprmc(Gprmc),
     /// GPGLL sentence
     Gpgll(Gpgll),
+    /// GPVTG sentence
+    Gpvtg(Gpvtg),
+    /// GPGSV sentence
+    Gpgsv(Gpgsv),
     /// Unknown or unsupported sentence
     Unknown,
 }
@@ -315,6 +769,8 @@ impl NmeaSentence {
             NmeaSentence::Gpgga(_) => NmeaType::Gpgga,
             NmeaSentence::Gprmc(_) => NmeaType::Gprmc,
             NmeaSentence::Gpgll(_) => NmeaType::Gpgll,
+            NmeaSentence::Gpvtg(_) => NmeaType::Gpvtg,
+            NmeaSentence::Gpgsv(_) => NmeaType::Gpgsv,
             NmeaSentence::Unknown => NmeaType::Unknown,
         }
     }
@@ -325,6 +781,8 @@ impl NmeaSentence {
             NmeaSentence::Gpgga(s) => s.base.errors,
             NmeaSentence::Gprmc(s) => s.base.errors,
             NmeaSentence::Gpgll(s) => s.base.errors,
+            NmeaSentence::Gpvtg(s) => s.base.errors,
+            NmeaSentence::Gpgsv(s) => s.base.errors,
             NmeaSentence::Unknown => 0,
         }
     }
@@ -332,6 +790,7 @@ impl NmeaSentence {
 
 /// NMEA parsing errors
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum NmeaError {
     /// Sentence length is invalid (too short or too long)
     InvalidLength,
@@ -349,6 +808,21 @@ pub enum NmeaError {
     UnsupportedSentence,
 }
 
+#[cfg(feature = "defmt")]
+impl defmt::Format for NmeaError {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            NmeaError::InvalidLength => defmt::write!(f, "InvalidLength"),
+            NmeaError::InvalidStart => defmt::write!(f, "InvalidStart"),
+            NmeaError::InvalidEnd => defmt::write!(f, "InvalidEnd"),
+            NmeaError::InvalidPrefix => defmt::write!(f, "InvalidPrefix"),
+            NmeaError::InvalidChecksum => defmt::write!(f, "InvalidChecksum"),
+            NmeaError::ParseError => defmt::write!(f, "ParseError"),
+            NmeaError::UnsupportedSentence => defmt::write!(f, "UnsupportedSentence"),
+        }
+    }
+}
+
 /// NMEA parser utilities
 #[derive(Debug)]
 pub struct NmeaParser;
@@ -360,15 +834,11 @@ impl NmeaParser {
             return NmeaType::Unknown;
         }
 
-        let prefix = &sentence[1..6];
-        match prefix {
-            "GPGGA" | "GNGGA" => NmeaType::Gpgga,
-            "GPRMC" | "GNRMC" => NmeaType::Gprmc,
-            "GPGLL" | "GNGLL" => NmeaType::Gpgll,
-            "GPGSA" | "GNGSA" => NmeaType::Gpgsa,
-            "GPGSV" | "GNGSV" => NmeaType::Gpgsv,
-            "GPTXT" | "GNTXT" => NmeaType::Gptxt,
-            "GPVTG" | "GNVTG" => NmeaType::Gpvtg,
+        // Identifiers on the wire are always uppercase
+        match sentence.get(1..6) {
+            Some(prefix) if prefix.bytes().all(|b| b.is_ascii_uppercase()) => {
+                NmeaType::try_from(prefix).unwrap_or(NmeaType::Unknown)
+            }
             _ => NmeaType::Unknown,
         }
     }
@@ -379,7 +849,7 @@ impl NmeaParser {
         let mut checksum = 0u8;
 
         // Start after '$' and stop at '*' or end
-        let start = if bytes[0] == b'$' { 1 } else { 0 };
+        let start = if bytes.first() == Some(&b'$') { 1 } else { 0 };
 
         for &byte in &bytes[start..] {
             if byte == b'*' || byte == NMEA_END_CHAR_1 {
@@ -393,7 +863,8 @@ impl NmeaParser {
 
     /// Check if sentence has a checksum
     pub fn has_checksum(sentence: &str) -> bool {
-        sentence.len() >= 5 && sentence.chars().nth(sentence.len() - 5) == Some('*')
+        let bytes = sentence.as_bytes();
+        bytes.len() >= 5 && bytes[bytes.len() - 5] == b'*'
     }
 
     /// Validate NMEA sentence
@@ -439,7 +910,7 @@ impl NmeaParser {
         // Check checksum if requested and present
         if check_checksum && Self::has_checksum(sentence) {
             let expected_checksum = Self::calculate_checksum(sentence);
-            let checksum_str = &sentence[len - 4..len - 2];
+            let checksum_str = sentence.get(len - 4..len - 2).unwrap_or("");
             if let Ok(actual_checksum) = u8::from_str_radix(checksum_str, 16) {
                 if expected_checksum != actual_checksum {
                     return Err(NmeaError::InvalidChecksum);
@@ -454,6 +925,17 @@ impl NmeaParser {
 
     /// Parse position from NMEA format (e.g., "4916.45")
     pub fn parse_position(value: &str) -> Result<(i32, f64), NmeaError> {
+        Self::parse_position_as(value)
+    }
+
+    /// Parse position from NMEA format with single-precision minutes
+    ///
+    /// See [`PositionF32`] for the precision this keeps.
+    pub fn parse_position_f32(value: &str) -> Result<(i32, f32), NmeaError> {
+        Self::parse_position_as(value)
+    }
+
+    fn parse_position_as<F: core::str::FromStr>(value: &str) -> Result<(i32, F), NmeaError> {
         if value.is_empty() {
             return Err(NmeaError::ParseError);
         }
@@ -466,14 +948,14 @@ impl NmeaParser {
 
             // Minutes start 2 digits before the decimal point
             let minutes_start = dot_pos - 2;
-            let degrees_str = &value[..minutes_start];
-            let minutes_str = &value[minutes_start..];
+            let degrees_str = value.get(..minutes_start).ok_or(NmeaError::ParseError)?;
+            let minutes_str = value.get(minutes_start..).ok_or(NmeaError::ParseError)?;
 
             let degrees = degrees_str
                 .parse::<i32>()
                 .map_err(|_| NmeaError::ParseError)?;
             let minutes = minutes_str
-                .parse::<f64>()
+                .parse::<F>()
                 .map_err(|_| NmeaError::ParseError)?;
 
             Ok((degrees, minutes))
@@ -549,14 +1031,14 @@ impl NmeaParser {
         let end = if Self::has_checksum(sentence) {
             sentence.len() - 5 // Remove "*XX\r\n"
         } else {
-            sentence.len() - 2 // Remove "\r\n"
+            sentence.len().saturating_sub(2) // Remove "\r\n"
         };
 
         if start >= end {
             return Ok(heapless::Vec::new());
         }
 
-        let data_part = &sentence[start..end];
+        let data_part = sentence.get(start..end).ok_or(NmeaError::ParseError)?;
         let mut fields = heapless::Vec::new();
 
         for field in data_part.split(',') {
@@ -566,6 +1048,19 @@ impl NmeaParser {
         Ok(fields)
     }
 
+    /// Parse NMEA sentence from raw bytes
+    ///
+    /// Entry point for untrusted serial input: the buffer does not need to be
+    /// valid UTF-8 (invalid data yields `ParseError`), and the checksum is
+    /// always verified when present. Never panics on arbitrary input.
+    pub fn parse_bytes(bytes: &[u8]) -> Result<NmeaSentence, NmeaError> {
+        if bytes.len() > NMEA_MAX_LENGTH {
+            return Err(NmeaError::InvalidLength);
+        }
+        let sentence = core::str::from_utf8(bytes).map_err(|_| NmeaError::ParseError)?;
+        Self::parse(sentence, true)
+    }
+
     /// Parse NMEA sentence
     pub fn parse(sentence: &str, check_checksum: bool) -> Result<NmeaSentence, NmeaError> {
         // Validate sentence
@@ -582,10 +1077,82 @@ impl NmeaParser {
             NmeaType::Gpgga => Ok(NmeaSentence::Gpgga(Self::parse_gpgga(&fields)?)),
             NmeaType::Gprmc => Ok(NmeaSentence::Gprmc(Self::parse_gprmc(&fields)?)),
             NmeaType::Gpgll => Ok(NmeaSentence::Gpgll(Self::parse_gpgll(&fields)?)),
+            NmeaType::Gpvtg => Ok(NmeaSentence::Gpvtg(Self::parse_gpvtg(&fields)?)),
+            NmeaType::Gpgsv => {
+                // Validation guarantees an ASCII address
+                let talker = sentence.get(1..3).unwrap_or_default();
+                Ok(NmeaSentence::Gpgsv(Self::parse_gpgsv(talker, &fields)?))
+            }
             _ => Err(NmeaError::UnsupportedSentence),
         }
     }
 
+    /// Parse GPGSV sentence
+    ///
+    /// Satellites are attributed to the talker's constellation, or by ID for
+    /// `GP` (which also carries SBAS) and combined `GN` sentences.
+    fn parse_gpgsv(talker: &str, fields: &[&str]) -> Result<Gpgsv, NmeaError> {
+        let mut gpgsv = Gpgsv {
+            constellation: Constellation::from_talker(talker),
+            ..Gpgsv::default()
+        };
+        let mut errors = 0u32;
+
+        for (i, &field) in fields.iter().take(3).enumerate() {
+            let value = match i {
+                0 => &mut gpgsv.total_messages,
+                1 => &mut gpgsv.message_number,
+                _ => &mut gpgsv.satellites_in_view,
+            };
+            match field.parse() {
+                Ok(parsed) => *value = parsed,
+                Err(_) => errors += 1,
+            }
+        }
+
+        // Blocks of ID, elevation, azimuth, SNR; NMEA 4.10 appends a signal ID
+        for block in fields.get(3..).unwrap_or_default().chunks(4) {
+            let [prn, elevation, azimuth, snr] = block else {
+                continue;
+            };
+            if prn.is_empty() {
+                continue;
+            }
+            let Ok(prn) = prn.parse::<u16>() else {
+                errors += 1;
+                continue;
+            };
+            let satellite = GsvSatellite {
+                prn,
+                constellation: match gpgsv.constellation {
+                    Some(Constellation::Gps) | None => Constellation::from_prn(prn),
+                    talker => talker,
+                },
+                elevation_degrees: Self::parse_optional(elevation, &mut errors),
+                azimuth_degrees: Self::parse_optional(azimuth, &mut errors),
+                snr_db: Self::parse_optional(snr, &mut errors),
+            };
+            if gpgsv.satellites.push(satellite).is_err() {
+                errors += 1;
+            }
+        }
+
+        gpgsv.base.errors = errors;
+        Ok(gpgsv)
+    }
+
+    /// Parse an optional numeric field, counting malformed values in `errors`
+    fn parse_optional<T: core::str::FromStr>(field: &str, errors: &mut u32) -> Option<T> {
+        if field.is_empty() {
+            return None;
+        }
+        let parsed = field.parse().ok();
+        if parsed.is_none() {
+            *errors += 1;
+        }
+        parsed
+    }
+
     /// Parse GPGGA sentence
     fn parse_gpgga(fields: &[&str]) -> Result<Gpgga, NmeaError> {
         let mut gpgga = Gpgga::default();
@@ -826,4 +1393,190 @@ impl NmeaParser {
         gpgll.base.errors = errors;
         Ok(gpgll)
     }
+
+    /// Parse GPVTG sentence
+    ///
+    /// The unit fields (`T`, `M`, `N`, `K`) are fixed and skipped. Empty
+    /// fields, common for the magnetic track, are left at zero.
+    fn parse_gpvtg(fields: &[&str]) -> Result<Gpvtg, NmeaError> {
+        let mut gpvtg = Gpvtg::default();
+        let mut errors = 0u32;
+
+        for (i, &field) in fields.iter().enumerate() {
+            if field.is_empty() {
+                continue;
+            }
+
+            let value = match i {
+                // Track (true), track (magnetic), speed in knots, speed in km/h
+                0 => &mut gpvtg.track_true_degrees,
+                2 => &mut gpvtg.track_magnetic_degrees,
+                4 => &mut gpvtg.speed_knots,
+                6 => &mut gpvtg.speed_kmh,
+                8 => {
+                    // Mode indicator
+                    gpvtg.mode = PositioningMode::from_char(field.chars().next().unwrap_or('\0'));
+                    if gpvtg.mode.is_none() {
+                        errors += 1;
+                    }
+                    continue;
+                }
+                _ => continue, // Units and extra fields
+            };
+            match field.parse() {
+                Ok(parsed) => *value = parsed,
+                Err(_) => errors += 1,
+            }
+        }
+
+        gpvtg.base.errors = errors;
+        Ok(gpvtg)
+    }
+}
+
+/// NMEA sentence builder, the inverse of [`NmeaParser`]
+///
+/// Frames data fields into a sentence ready to send, e.g. configuration
+/// commands for a receiver. See [`build_sentence`] for the accepted input.
+#[derive(Debug)]
+pub struct NmeaBuilder;
+
+impl NmeaBuilder {
+    /// Build a sentence from its full address, e.g. `"GPGLL"` or `"PMTK220"`
+    ///
+    /// `NmeaBuilder::build("PMTK220", &["100"])` gives `$PMTK220,100*2F\r\n`.
+    pub fn build(
+        prefix: &str,
+        fields: &[&str],
+    ) -> Result<heapless::String<NMEA_MAX_LENGTH>, NmeaError> {
+        build_sentence("", prefix, fields)
+    }
+
+    /// Build a sentence of a supported type
+    ///
+    /// Returns `NmeaError::UnsupportedSentence` for [`NmeaType::Unknown`].
+    pub fn build_type(
+        sentence_type: NmeaType,
+        fields: &[&str],
+    ) -> Result<heapless::String<NMEA_MAX_LENGTH>, NmeaError> {
+        if sentence_type == NmeaType::Unknown {
+            return Err(NmeaError::UnsupportedSentence);
+        }
+        Self::build(sentence_type.as_str(), fields)
+    }
+}
+
+/// Build a complete NMEA sentence from its address and data fields
+///
+/// Produces `$<talker><sentence_type>,<field>,...*XX\r\n` with the checksum
+/// computed by [`NmeaParser::calculate_checksum`], e.g. receiver commands such
+/// as `build_sentence("P", "MTK220", &["100"])` (`$PMTK220,100*2F\r\n`) or
+/// test fixtures for any sentence type.
+///
+/// The address (talker and sentence type) must be ASCII alphanumeric, and
+/// fields must not contain the delimiters `$`, `*`, `,`, `\r` or `\n`.
+/// Returns `NmeaError::InvalidPrefix` or `NmeaError::ParseError` if they do,
+/// and `NmeaError::InvalidLength` if the sentence exceeds [`NMEA_MAX_LENGTH`].
+pub fn build_sentence(
+    talker: &str,
+    sentence_type: &str,
+    fields: &[&str],
+) -> Result<heapless::String<NMEA_MAX_LENGTH>, NmeaError> {
+    let address_valid = |part: &str| part.bytes().all(|b| b.is_ascii_alphanumeric());
+    if talker.len() + sentence_type.len() == 0
+        || !address_valid(talker)
+        || !address_valid(sentence_type)
+    {
+        return Err(NmeaError::InvalidPrefix);
+    }
+    if fields.iter().any(|field| {
+        field
+            .bytes()
+            .any(|b| matches!(b, b'$' | b'*' | b',' | b'\r' | b'\n'))
+    }) {
+        return Err(NmeaError::ParseError);
+    }
+
+    let mut sentence = heapless::String::new();
+    let mut push = |part: &str| {
+        sentence
+            .push_str(part)
+            .map_err(|_| NmeaError::InvalidLength)
+    };
+    push("$")?;
+    push(talker)?;
+    push(sentence_type)?;
+    for field in fields {
+        push(",")?;
+        push(field)?;
+    }
+
+    let checksum = NmeaParser::calculate_checksum(&sentence);
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let suffix = [
+        b'*',
+        HEX[(checksum >> 4) as usize],
+        HEX[(checksum & 0x0F) as usize],
+        NMEA_END_CHAR_1,
+        NMEA_END_CHAR_2,
+    ];
+    // The suffix is ASCII
+    sentence
+        .push_str(core::str::from_utf8(&suffix).unwrap_or_default())
+        .map_err(|_| NmeaError::InvalidLength)?;
+    Ok(sentence)
+}
+
+/// Format a position as signed decimal degrees without floating-point
+/// formatting
+///
+/// Appends e.g. `-123.185333` to `out` for 123° 11.12' W with 6 `decimals`,
+/// the same digits as formatting [`Position::to_decimal_degrees`] with
+/// `{:.6}` (up to the rounding of exact ties), but computed on integers so it
+/// stays cheap on targets without an FPU or float `core::fmt` support. The
+/// only float operation is one multiply that scales the `f64` minutes field
+/// to an integer. `decimals` is capped at 9; 0 prints whole degrees. The
+/// sign comes from the cardinal direction, as degrees parsed from a sentence
+/// are never negative.
+///
+/// Returns `NmeaError::ParseError` if the minutes are outside 0..=60, and
+/// `NmeaError::InvalidLength` if `out` runs out of capacity, in which case it
+/// may hold a partial number.
+pub fn format_decimal_degrees<const N: usize>(
+    pos: &Position,
+    out: &mut heapless::String<N>,
+    decimals: u8,
+) -> Result<(), NmeaError> {
+    use core::fmt::Write;
+
+    // Units of 1e-8 minute: finer than 9 decimals of a degree, and small
+    // enough that the rounding product below fits in a u64
+    const MINUTE_SCALE: u64 = 100_000_000;
+
+    if !(0.0..=60.0).contains(&pos.minutes) {
+        return Err(NmeaError::ParseError);
+    }
+    let decimals = decimals.min(9) as u32;
+    let unit = 10u64.pow(decimals);
+    let minutes = (pos.minutes * MINUTE_SCALE as f64 + 0.5) as u64;
+
+    // minutes / 60 in units of 10^-decimals degree, rounded to nearest
+    let per_degree = 60 * MINUTE_SCALE;
+    let fraction = (minutes * unit * 2 + per_degree) / (2 * per_degree);
+    let whole = u64::from(pos.degrees.unsigned_abs()) + fraction / unit;
+    let fraction = fraction % unit;
+
+    let negative = matches!(
+        pos.cardinal,
+        CardinalDirection::South | CardinalDirection::West
+    );
+    if negative && (whole != 0 || fraction != 0) {
+        out.push('-').map_err(|_| NmeaError::InvalidLength)?;
+    }
+    write!(out, "{whole}").map_err(|_| NmeaError::InvalidLength)?;
+    if decimals > 0 {
+        write!(out, ".{fraction:0width$}", width = decimals as usize)
+            .map_err(|_| NmeaError::InvalidLength)?;
+    }
+    Ok(())
 }
@@ -0,0 +1,100 @@
+//! Speed-based motion detection
+//!
+//! `MotionDetector` turns successive ground speeds into a debounced
+//! moving/stationary signal, e.g. to put a tracker to sleep while parked.
+//! Two thresholds give hysteresis, and a candidate state must persist for a
+//! minimum dwell time (measured with a `MonotonicClock`) before it is
+//! reported, so GPS speed jitter around a threshold does not cause flapping.
+
+use super::NmeaSentence;
+use crate::system::clock::MonotonicClock;
+
+/// Kilometres per hour in one knot
+pub const KMH_PER_KNOT: f32 = 1.852;
+
+/// Motion state reported by `MotionDetector`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    /// Speed has stayed above the start threshold for the dwell time
+    Moving,
+    /// Speed has stayed below the stop threshold for the dwell time
+    Stationary,
+}
+
+/// Debounced moving/stationary detector with hysteresis
+#[derive(Debug, Clone)]
+pub struct MotionDetector<C: MonotonicClock> {
+    clock: C,
+    start_kmh: f32,
+    stop_kmh: f32,
+    min_dwell_ms: u64,
+    state: Motion,
+    candidate_since: Option<u64>,
+}
+
+impl<C: MonotonicClock> MotionDetector<C> {
+    /// Create a detector, initially `Stationary`
+    ///
+    /// Motion starts once speed exceeds `start_kmh` and stops once it drops
+    /// below `stop_kmh`, each sustained for `min_dwell_ms`. Returns `None`
+    /// unless `0 <= stop_kmh < start_kmh`.
+    pub fn new(clock: C, start_kmh: f32, stop_kmh: f32, min_dwell_ms: u64) -> Option<Self> {
+        if !(stop_kmh >= 0.0 && stop_kmh < start_kmh) {
+            return None;
+        }
+        Some(Self {
+            clock,
+            start_kmh,
+            stop_kmh,
+            min_dwell_ms,
+            state: Motion::Stationary,
+            candidate_since: None,
+        })
+    }
+
+    /// Current debounced state
+    pub fn state(&self) -> Motion {
+        self.state
+    }
+
+    /// Feed a speed in km/h; returns the new state on a transition
+    pub fn update_kmh(&mut self, speed_kmh: f32) -> Option<Motion> {
+        let crossing = match self.state {
+            Motion::Stationary => speed_kmh > self.start_kmh,
+            Motion::Moving => speed_kmh < self.stop_kmh,
+        };
+        if !crossing {
+            self.candidate_since = None;
+            return None;
+        }
+
+        let now = self.clock.now_ms();
+        let since = *self.candidate_since.get_or_insert(now);
+        if now.saturating_sub(since) < self.min_dwell_ms {
+            return None;
+        }
+
+        self.candidate_since = None;
+        self.state = match self.state {
+            Motion::Stationary => Motion::Moving,
+            Motion::Moving => Motion::Stationary,
+        };
+        Some(self.state)
+    }
+
+    /// Feed a speed in knots; returns the new state on a transition
+    pub fn update_knots(&mut self, speed_knots: f32) -> Option<Motion> {
+        self.update_kmh(speed_knots * KMH_PER_KNOT)
+    }
+
+    /// Feed a parsed sentence carrying ground speed
+    ///
+    /// Only valid RMC and VTG sentences carry speed; anything else is ignored.
+    pub fn update(&mut self, sentence: &NmeaSentence) -> Option<Motion> {
+        match sentence {
+            NmeaSentence::Gprmc(rmc) if rmc.status => self.update_knots(rmc.speed_knots),
+            NmeaSentence::Gpvtg(vtg) if vtg.is_valid() => self.update_knots(vtg.speed_knots),
+            _ => None,
+        }
+    }
+}
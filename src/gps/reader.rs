@@ -0,0 +1,145 @@
+//! Byte-at-a-time NMEA framing
+//!
+//! `NmeaReader` accumulates serial input, typically fed from a UART interrupt
+//! in arbitrary chunks, and hands each complete `$...\r\n` line to
+//! `NmeaParser::parse_bytes`. Bytes outside a sentence are discarded, so the
+//! reader resynchronises on the next `$` after noise or a dropped byte.
+//!
+//! An allow-list restricts full parsing to the sentence types the
+//! application uses. Other lines are classified by their address alone and
+//! dropped without checksum validation or field parsing, which matters on a
+//! busy receiver that sends several GSV/GSA sentences per fix.
+
+use super::{
+    NMEA_END_CHAR_2, NMEA_MAX_LENGTH, NMEA_PREFIX_LENGTH, NmeaError, NmeaParser, NmeaSentence,
+    NmeaType,
+};
+
+/// Counters of what an `NmeaReader` did with complete lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReaderStats {
+    /// Lines parsed successfully
+    pub parsed: u32,
+    /// Lines that failed validation or parsing, including over-length lines
+    pub errors: u32,
+    /// Lines dropped by the allow-list without being parsed
+    pub skipped: u32,
+}
+
+/// Streaming NMEA sentence framer with a fixed line buffer
+#[derive(Debug, Clone)]
+pub struct NmeaReader {
+    buffer: [u8; NMEA_MAX_LENGTH],
+    len: usize,
+    // Bit per `NmeaType` discriminant
+    allowed: u16,
+    stats: ReaderStats,
+}
+
+impl NmeaReader {
+    /// Create an empty reader that parses every sentence type
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; NMEA_MAX_LENGTH],
+            len: 0,
+            allowed: u16::MAX,
+            stats: ReaderStats {
+                parsed: 0,
+                errors: 0,
+                skipped: 0,
+            },
+        }
+    }
+
+    /// Only parse the given sentence types
+    ///
+    /// Lines of any other type, including [`NmeaType::Unknown`] unless it is
+    /// listed, are skipped: `push` returns `None` for them and they are
+    /// counted in [`ReaderStats::skipped`].
+    pub fn set_allowed(&mut self, types: &[NmeaType]) {
+        self.allowed = types.iter().fold(0, |mask, &kind| mask | type_bit(kind));
+    }
+
+    /// Parse every sentence type again (the default)
+    pub fn allow_all(&mut self) {
+        self.allowed = u16::MAX;
+    }
+
+    /// Whether lines of `kind` are parsed
+    pub fn is_allowed(&self, kind: NmeaType) -> bool {
+        self.allowed & type_bit(kind) != 0
+    }
+
+    /// Counters since creation or the last [`reset_stats`](Self::reset_stats)
+    pub fn stats(&self) -> ReaderStats {
+        self.stats
+    }
+
+    /// Zero the counters
+    pub fn reset_stats(&mut self) {
+        self.stats = ReaderStats::default();
+    }
+
+    /// Discard any partially received sentence
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    /// Feed one byte; returns the parse result once a line is complete
+    ///
+    /// Bytes before a `$` are dropped, and a `$` in the middle of a line
+    /// restarts the sentence. A line ends at `\n`; it is then validated and
+    /// parsed (checksum included) and the buffer is cleared, unless its type
+    /// is not allowed, in which case it is skipped and `None` is returned. A
+    /// line longer than `NMEA_MAX_LENGTH` yields
+    /// `Err(NmeaError::InvalidLength)`, after which its remaining bytes are
+    /// dropped up to the next `$`.
+    pub fn push(&mut self, byte: u8) -> Option<Result<NmeaSentence, NmeaError>> {
+        if byte == b'$' {
+            self.len = 0;
+        } else if self.len == 0 {
+            return None;
+        }
+
+        if self.len == NMEA_MAX_LENGTH {
+            self.len = 0;
+            self.stats.errors += 1;
+            return Some(Err(NmeaError::InvalidLength));
+        }
+        self.buffer[self.len] = byte;
+        self.len += 1;
+
+        if byte != NMEA_END_CHAR_2 {
+            return None;
+        }
+        let line = &self.buffer[..self.len];
+        self.len = 0;
+        if self.allowed != u16::MAX && !self.is_allowed(classify(line)) {
+            self.stats.skipped += 1;
+            return None;
+        }
+        let result = NmeaParser::parse_bytes(line);
+        match result {
+            Ok(_) => self.stats.parsed += 1,
+            Err(_) => self.stats.errors += 1,
+        }
+        Some(result)
+    }
+}
+
+impl Default for NmeaReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn type_bit(kind: NmeaType) -> u16 {
+    1 << kind as u16
+}
+
+/// Sentence type from the address alone
+fn classify(line: &[u8]) -> NmeaType {
+    line.get(..=NMEA_PREFIX_LENGTH)
+        .and_then(|address| core::str::from_utf8(address).ok())
+        .map_or(NmeaType::Unknown, NmeaParser::get_sentence_type)
+}
@@ -0,0 +1,148 @@
+//! Fix tracking on top of parsed NMEA sentences
+//!
+//! `GpsState` folds a stream of `NmeaSentence`s into the latest known fix and
+//! stamps each accepted fix with a `MonotonicClock`, so callers can tell how
+//! long the receiver took to acquire its first fix and how stale the current
+//! one is.
+
+use super::{
+    DEFAULT_UERE_M, NmeaDate, NmeaSentence, NmeaTime, Position, estimated_horizontal_accuracy_m,
+};
+use crate::system::clock::MonotonicClock;
+
+/// Latest GPS fix along with its timing information
+#[derive(Debug, Clone)]
+pub struct GpsState<C: MonotonicClock> {
+    clock: C,
+    started_at: u64,
+    first_fix_at: Option<u64>,
+    last_fix_at: Option<u64>,
+    latitude: Position,
+    longitude: Position,
+    time: NmeaTime,
+    date: Option<NmeaDate>,
+    satellites_used: Option<u8>,
+    hdop: Option<f32>,
+}
+
+impl<C: MonotonicClock> GpsState<C> {
+    /// Create an empty state; time to first fix is measured from this call
+    pub fn new(clock: C) -> Self {
+        let started_at = clock.now_ms();
+        Self {
+            clock,
+            started_at,
+            first_fix_at: None,
+            last_fix_at: None,
+            latitude: Position::default(),
+            longitude: Position::default(),
+            time: NmeaTime::default(),
+            date: None,
+            satellites_used: None,
+            hdop: None,
+        }
+    }
+
+    /// Feed a parsed sentence; returns `true` if it carried a valid fix
+    ///
+    /// GGA sentences count as a fix when their fix quality is non-zero, RMC
+    /// and GLL sentences when their status is active. Sentences without a
+    /// valid fix leave the state untouched.
+    pub fn update(&mut self, sentence: &NmeaSentence) -> bool {
+        match sentence {
+            NmeaSentence::Gpgga(gga) if gga.position_fix > 0 => {
+                self.latitude = gga.latitude;
+                self.longitude = gga.longitude;
+                self.time = gga.time;
+                self.satellites_used = Some(gga.satellites_used);
+                self.hdop = Some(gga.hdop);
+            }
+            NmeaSentence::Gprmc(rmc) if rmc.status => {
+                self.latitude = rmc.latitude;
+                self.longitude = rmc.longitude;
+                self.time = rmc.time;
+                self.date = Some(rmc.date);
+            }
+            NmeaSentence::Gpgll(gll) if gll.status => {
+                self.latitude = gll.latitude;
+                self.longitude = gll.longitude;
+                self.time = gll.time;
+            }
+            _ => return false,
+        }
+
+        let now = self.clock.now_ms();
+        self.first_fix_at.get_or_insert(now);
+        self.last_fix_at = Some(now);
+        true
+    }
+
+    /// Check whether at least one valid fix has been received
+    pub fn has_fix(&self) -> bool {
+        self.last_fix_at.is_some()
+    }
+
+    /// Milliseconds from creation until the first valid fix, or `None` if no fix yet
+    pub fn time_to_first_fix_ms(&self) -> Option<u64> {
+        self.first_fix_at
+            .map(|at| at.saturating_sub(self.started_at))
+    }
+
+    /// Milliseconds since the most recent valid fix, or `None` if no fix yet
+    pub fn fix_age_ms(&self) -> Option<u64> {
+        self.last_fix_at.map(|at| self.clock.elapsed_ms(at))
+    }
+
+    /// Latitude of the latest fix, or `None` if no fix yet
+    pub fn latitude(&self) -> Option<Position> {
+        self.has_fix().then_some(self.latitude)
+    }
+
+    /// Longitude of the latest fix, or `None` if no fix yet
+    pub fn longitude(&self) -> Option<Position> {
+        self.has_fix().then_some(self.longitude)
+    }
+
+    /// UTC time of the latest fix, or `None` if no fix yet
+    pub fn time(&self) -> Option<NmeaTime> {
+        self.has_fix().then_some(self.time)
+    }
+
+    /// UTC date from the latest RMC fix, if one has been received
+    pub fn date(&self) -> Option<NmeaDate> {
+        self.date
+    }
+
+    /// Satellites used in the latest GGA fix, if one has been received
+    pub fn satellites_used(&self) -> Option<u8> {
+        self.satellites_used
+    }
+
+    /// Horizontal dilution of precision from the latest GGA fix, if one has been received
+    pub fn hdop(&self) -> Option<f32> {
+        self.hdop
+    }
+
+    /// Estimated horizontal accuracy in meters from the latest HDOP, using
+    /// [`DEFAULT_UERE_M`]; see [`estimated_horizontal_accuracy_m`]
+    pub fn estimated_horizontal_accuracy_m(&self) -> Option<f32> {
+        self.hdop
+            .filter(|&hdop| hdop > 0.0)
+            .map(|hdop| estimated_horizontal_accuracy_m(hdop, DEFAULT_UERE_M))
+    }
+
+    /// Discard the current fix and restart time-to-first-fix measurement
+    pub fn reset(&mut self) {
+        self.started_at = self.clock.now_ms();
+        self.first_fix_at = None;
+        self.last_fix_at = None;
+        self.date = None;
+        self.satellites_used = None;
+        self.hdop = None;
+    }
+
+    /// Get a reference to the clock
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+}
@@ -0,0 +1,105 @@
+//! GPS fix telemetry over MQTT
+//!
+//! Turns a parsed [`Gprmc`] into a compact JSON document and publishes it
+//! with the MQTT client, the usual shape of a GPS tracker:
+//!
+//! ```json
+//! {"lat":49.274167,"lon":-123.185333,"spd":0.9,"ts":785285686}
+//! ```
+//!
+//! Coordinates are signed decimal degrees rounded to six places (about 0.1 m),
+//! speed is km/h rounded to 0.1, and `ts` is the UTC fix time in seconds
+//! since the Unix epoch.
+
+use heapless::String;
+use serde::Serialize;
+
+use super::motion::KMH_PER_KNOT;
+use super::{Gprmc, NmeaDateTime, NmeaError};
+use crate::network::Connection;
+use crate::network::application::mqtt::client::{Client, QoS};
+use crate::network::error::Error;
+
+/// Capacity of the JSON produced by [`Telemetry::to_json`]
+pub const TELEMETRY_MAX_LEN: usize = 96;
+
+/// One position report, serialized with short keys to save bandwidth
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Telemetry {
+    /// Latitude in decimal degrees, negative in the southern hemisphere
+    #[serde(rename = "lat")]
+    pub latitude: f64,
+    /// Longitude in decimal degrees, negative west of Greenwich
+    #[serde(rename = "lon")]
+    pub longitude: f64,
+    /// Speed over ground in km/h
+    #[serde(rename = "spd")]
+    pub speed_kmh: f32,
+    /// UTC time of the fix, in seconds since the Unix epoch
+    #[serde(rename = "ts")]
+    pub timestamp: u64,
+}
+
+impl Telemetry {
+    /// Build a report from an RMC sentence
+    ///
+    /// Returns `None` if the receiver flagged the fix as invalid.
+    pub fn from_rmc(rmc: &Gprmc) -> Option<Self> {
+        if !rmc.status {
+            return None;
+        }
+        Some(Self {
+            latitude: round(rmc.latitude.to_decimal_degrees(), 1e6),
+            longitude: round(rmc.longitude.to_decimal_degrees(), 1e6),
+            speed_kmh: round((rmc.speed_knots * KMH_PER_KNOT) as f64, 10.0) as f32,
+            timestamp: NmeaDateTime::new(rmc.date, rmc.time).unix_timestamp(),
+        })
+    }
+
+    /// Serialize the report as JSON
+    ///
+    /// Returns `NmeaError::InvalidLength` if it does not fit in
+    /// [`TELEMETRY_MAX_LEN`] bytes, which cannot happen for in-range values.
+    pub fn to_json(&self) -> Result<String<TELEMETRY_MAX_LEN>, NmeaError> {
+        serde_json_core::to_string(self).map_err(|_| NmeaError::InvalidLength)
+    }
+
+    /// Publish the report as JSON on `topic`
+    pub fn publish<C: Connection>(
+        &self,
+        client: &mut Client<C>,
+        topic: &str,
+        qos: QoS,
+    ) -> Result<(), Error> {
+        let json = self.to_json().map_err(|_| Error::WriteError)?;
+        client.publish(topic, json.as_bytes(), qos)
+    }
+}
+
+/// Publish the fix in `rmc` on `topic`, if it is valid
+///
+/// Returns `Ok(false)` without publishing when the receiver has no fix.
+pub fn publish_rmc<C: Connection>(
+    client: &mut Client<C>,
+    topic: &str,
+    rmc: &Gprmc,
+    qos: QoS,
+) -> Result<bool, Error> {
+    match Telemetry::from_rmc(rmc) {
+        Some(report) => report.publish(client, topic, qos).map(|()| true),
+        None => Ok(false),
+    }
+}
+
+/// Round half away from zero to a multiple of `1 / scale`
+///
+/// `f64::round` needs `std`, so this truncates through an integer instead.
+fn round(value: f64, scale: f64) -> f64 {
+    let scaled = value * scale;
+    let nudged = if scaled < 0.0 {
+        scaled - 0.5
+    } else {
+        scaled + 0.5
+    };
+    nudged as i64 as f64 / scale
+}
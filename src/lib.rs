@@ -91,6 +91,7 @@
 //!     client_id: "my_device",
 //!     keep_alive_seconds: 60,
 //!     clean_session: true,
+//!     max_packet_size: None,
 //! };
 //!
 //! // let mut client = Client::connect(connection, options)?;
@@ -106,14 +107,28 @@
 //!
 //! ## Optional Features
 //!
-//! - `std`: Enable standard library support (default: disabled)
-//! - `async`: Enable async/await support for non-blocking operations
-//! - `defmt`: Enable defmt logging support for embedded debugging
+//! The core is `no_std` and allocation-free; everything else is opt-in.
+//!
+//! | Feature        | Requires           | Enables                                                          |
+//! |----------------|--------------------|------------------------------------------------------------------|
+//! | `alloc`        | a global allocator | Boxed MCP handlers (`DynFunctionRegistry`), shell closure commands |
+//! | `std`          | `alloc`            | Standard library support, used by the examples                   |
+//! | `async`        |                    | async/await variants of the storage and network traits           |
+//! | `defmt`        |                    | `defmt::Format` for error and status types                       |
+//! | `serial`       |                    | AT modem transport over `embedded-io` serial ports               |
+//! | `mqtt-session` |                    | MQTT session persistence to `Storage`                            |
+//! | `serde`        |                    | `Serialize`/`Deserialize` for parsed GPS sentences               |
+//!
+//! With no features enabled nothing in the crate allocates: buffers are
+//! fixed-size `heapless` collections sized by the constants in each module.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![deny(missing_docs)]
 #![doc(html_root_url = "https://shishir-dey.github.io/libiot/")]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 /// Network abstraction layer providing protocol implementations and connection management.
 ///
 /// This module contains implementations for various network protocols commonly used
@@ -136,3 +151,9 @@ pub mod system;
 pub mod ota;
 
 pub mod gps;
+
+/// Small helpers shared between modules, such as checksums.
+pub mod util;
+
+pub mod error;
+pub use error::{Error, Result};
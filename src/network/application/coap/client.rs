@@ -0,0 +1,304 @@
+//! CoAP request/response client over a [`UdpSocket`].
+//!
+//! [`Client`] sends each request as a Confirmable message and retransmits it
+//! until the server acknowledges it (RFC 7252 §4.2): the first retransmission
+//! follows [`ACK_TIMEOUT_MS`], and each one after that waits twice as long,
+//! up to [`MAX_RETRANSMIT`] retransmissions. The response is either carried
+//! in the ACK itself (piggybacked) or, after an empty ACK, sent separately
+//! with the request's token; a Confirmable separate response is
+//! acknowledged before it is returned.
+//!
+//! The socket is polled: [`UdpSocket::recv_from`] should return promptly,
+//! either because the socket is non-blocking or because it has a short read
+//! timeout, and any error it returns is treated as no datagram yet. Time is
+//! measured with a [`MonotonicClock`].
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use libiot::network::application::coap::{Client, Code};
+//! # use libiot::network::UdpSocket;
+//! # use libiot::system::clock::MonotonicClock;
+//! # struct Socket;
+//! # impl UdpSocket for Socket {
+//! #     type Error = ();
+//! #     fn send_to(&mut self, _remote: &str, buf: &[u8]) -> Result<usize, ()> { Ok(buf.len()) }
+//! #     fn recv_from(&mut self, _buf: &mut [u8]) -> Result<(usize, &str), ()> { Err(()) }
+//! # }
+//! # struct Clock;
+//! # impl MonotonicClock for Clock {
+//! #     fn now_ms(&self) -> u64 { 0 }
+//! # }
+//!
+//! let mut client: Client<_, _> = Client::new(Socket, "192.0.2.1:5683", Clock, 0x1234_5678);
+//!
+//! let response = client.get("/sensors/temperature").unwrap();
+//! if response.code == Code::CONTENT {
+//!     // parse response.payload
+//! }
+//! client.put("/actuators/led", b"on").unwrap();
+//! ```
+
+use super::message::{CoapOption, Code, Message, MessageType};
+use crate::network::UdpSocket;
+use crate::network::error::Error;
+use crate::system::clock::MonotonicClock;
+
+/// Time to wait for an ACK before the first retransmission.
+pub const ACK_TIMEOUT_MS: u64 = 2_000;
+
+/// How many times a Confirmable request is retransmitted before giving up.
+pub const MAX_RETRANSMIT: u32 = 4;
+
+/// Longest wait for a separate response after its request was acknowledged.
+pub const SEPARATE_RESPONSE_TIMEOUT_MS: u64 = 93_000;
+
+/// A CoAP client sending Confirmable requests to one server.
+///
+/// `N` is the size of the buffer that holds the encoded request and the
+/// received response; RFC 7252 suggests 1152 bytes for a message whose size
+/// is not known in advance.
+pub struct Client<S: UdpSocket, K: MonotonicClock, const N: usize = 1152> {
+    socket: S,
+    server: &'static str,
+    clock: K,
+    ack_timeout_ms: u64,
+    message_id: u16,
+    // xorshift32 state for request tokens
+    rng: u32,
+    buffer: [u8; N],
+}
+
+/// The response to a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Response<'a> {
+    /// Response code, e.g. [`Code::CONTENT`].
+    pub code: Code,
+    /// Payload, empty if there is none.
+    pub payload: &'a [u8],
+}
+
+/// Whether a datagram answers the outstanding request.
+enum Received {
+    /// Not related to the request
+    Other,
+    /// An empty ACK: the response will follow separately
+    Acknowledged,
+    /// The server rejected the request with a Reset
+    Reset,
+    /// The response, and whether it must be acknowledged
+    Response { confirmable: bool },
+}
+
+impl<S: UdpSocket, K: MonotonicClock, const N: usize> Client<S, K, N> {
+    /// Create a client sending requests from `socket` to `server`.
+    ///
+    /// `seed` initializes the message ids and tokens; pass a different value
+    /// on every boot, e.g. from a hardware RNG, so that a restarted client
+    /// does not reuse the ids and tokens of the previous run.
+    pub fn new(socket: S, server: &'static str, clock: K, seed: u32) -> Self {
+        Self {
+            socket,
+            server,
+            clock,
+            ack_timeout_ms: ACK_TIMEOUT_MS,
+            message_id: seed as u16,
+            // xorshift32 must not start at zero
+            rng: if seed == 0 { 0x9E37_79B9 } else { seed },
+            buffer: [0; N],
+        }
+    }
+
+    /// Set the time to wait for an ACK before the first retransmission.
+    ///
+    /// Defaults to [`ACK_TIMEOUT_MS`].
+    pub fn with_ack_timeout(mut self, ack_timeout_ms: u64) -> Self {
+        self.ack_timeout_ms = ack_timeout_ms;
+        self
+    }
+
+    /// Send a GET request for `path`.
+    ///
+    /// See [`request`](Self::request).
+    pub fn get(&mut self, path: &str) -> Result<Response<'_>, Error> {
+        self.request(Code::GET, path, &[])
+    }
+
+    /// Send a POST request with `payload` to `path`.
+    ///
+    /// See [`request`](Self::request).
+    pub fn post(&mut self, path: &str, payload: &[u8]) -> Result<Response<'_>, Error> {
+        self.request(Code::POST, path, payload)
+    }
+
+    /// Send a PUT request with `payload` to `path`.
+    ///
+    /// See [`request`](Self::request).
+    pub fn put(&mut self, path: &str, payload: &[u8]) -> Result<Response<'_>, Error> {
+        self.request(Code::PUT, path, payload)
+    }
+
+    /// Send a DELETE request for `path`.
+    ///
+    /// See [`request`](Self::request).
+    pub fn delete(&mut self, path: &str) -> Result<Response<'_>, Error> {
+        self.request(Code::DELETE, path, &[])
+    }
+
+    /// Send a Confirmable request and wait for its response.
+    ///
+    /// `path` is split at `/` into Uri-Path options, and anything after a
+    /// `?` at `&` into Uri-Query options. The response's
+    /// [`code`](Response::code) tells whether the request succeeded.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ConnectionClosed`] - The request was not acknowledged after
+    ///   [`MAX_RETRANSMIT`] retransmissions
+    /// * [`Error::Timeout`] - The request was acknowledged, but no response
+    ///   followed within [`SEPARATE_RESPONSE_TIMEOUT_MS`]
+    /// * [`Error::ConnectionRefused`] - The server answered with a Reset
+    /// * [`Error::WriteError`] - The request does not fit the buffer or has
+    ///   too many path and query segments, or sending failed
+    pub fn request(
+        &mut self,
+        code: Code,
+        path: &str,
+        payload: &[u8],
+    ) -> Result<Response<'_>, Error> {
+        let message_id = self.message_id;
+        self.message_id = self.message_id.wrapping_add(1);
+        let token = self.next_random().to_be_bytes();
+
+        let mut timeout_ms = self.ack_timeout_ms;
+        let mut retransmits = 0;
+        self.send_request(code, path, payload, message_id, &token)?;
+        let mut sent_at = self.clock.now_ms();
+        let mut acked_at = None;
+
+        let len = loop {
+            if let Ok((len, _)) = self.socket.recv_from(&mut self.buffer) {
+                match self.classify(len, message_id, &token) {
+                    Received::Other => {}
+                    Received::Acknowledged => acked_at = Some(self.clock.now_ms()),
+                    Received::Reset => return Err(Error::ConnectionRefused),
+                    Received::Response { confirmable } => {
+                        if confirmable {
+                            self.acknowledge(len)?;
+                        }
+                        break len;
+                    }
+                }
+            }
+
+            match acked_at {
+                Some(acked_at) => {
+                    if self.clock.elapsed_ms(acked_at) >= SEPARATE_RESPONSE_TIMEOUT_MS {
+                        return Err(Error::Timeout);
+                    }
+                }
+                None if self.clock.elapsed_ms(sent_at) >= timeout_ms => {
+                    if retransmits == MAX_RETRANSMIT {
+                        return Err(Error::ConnectionClosed);
+                    }
+                    retransmits += 1;
+                    timeout_ms *= 2;
+                    self.send_request(code, path, payload, message_id, &token)?;
+                    sent_at = self.clock.now_ms();
+                }
+                None => {}
+            }
+        };
+
+        let response = Message::decode(&self.buffer[..len])?;
+        Ok(Response {
+            code: response.code,
+            payload: response.payload,
+        })
+    }
+
+    /// The underlying socket.
+    pub fn socket(&self) -> &S {
+        &self.socket
+    }
+
+    /// The underlying socket, mutably.
+    pub fn socket_mut(&mut self) -> &mut S {
+        &mut self.socket
+    }
+
+    /// Encode the request into the buffer and send it.
+    fn send_request(
+        &mut self,
+        code: Code,
+        path: &str,
+        payload: &[u8],
+        message_id: u16,
+        token: &[u8],
+    ) -> Result<(), Error> {
+        let mut request = Message::new(MessageType::Confirmable, code, message_id);
+        request.token = token;
+        request.payload = payload;
+
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+            request.add_option(CoapOption::URI_PATH, segment.as_bytes())?;
+        }
+        for argument in query.split('&').filter(|argument| !argument.is_empty()) {
+            request.add_option(CoapOption::URI_QUERY, argument.as_bytes())?;
+        }
+
+        let len = request.encode(&mut self.buffer)?;
+        self.socket
+            .send_to(self.server, &self.buffer[..len])
+            .map_err(|_| Error::WriteError)?;
+        Ok(())
+    }
+
+    /// Work out whether the received datagram answers the request.
+    fn classify(&self, len: usize, message_id: u16, token: &[u8]) -> Received {
+        let Ok(message) = Message::decode(&self.buffer[..len]) else {
+            return Received::Other;
+        };
+        let response = message.token == token && message.code.is_response();
+        match message.message_type {
+            MessageType::Acknowledgement if message.message_id == message_id => {
+                if response {
+                    Received::Response { confirmable: false }
+                } else if message.code == Code::EMPTY {
+                    Received::Acknowledged
+                } else {
+                    Received::Other
+                }
+            }
+            MessageType::Reset if message.message_id == message_id => Received::Reset,
+            MessageType::Confirmable | MessageType::NonConfirmable if response => {
+                Received::Response {
+                    confirmable: message.message_type == MessageType::Confirmable,
+                }
+            }
+            _ => Received::Other,
+        }
+    }
+
+    /// Send an empty ACK for the Confirmable response in the buffer.
+    fn acknowledge(&mut self, len: usize) -> Result<(), Error> {
+        let message_id = Message::decode(&self.buffer[..len])?.message_id;
+        let mut ack = [0u8; 4];
+        Message::new(MessageType::Acknowledgement, Code::EMPTY, message_id).encode(&mut ack)?;
+        self.socket
+            .send_to(self.server, &ack)
+            .map_err(|_| Error::WriteError)?;
+        Ok(())
+    }
+
+    /// Advance the xorshift32 generator.
+    fn next_random(&mut self) -> u32 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        x
+    }
+}
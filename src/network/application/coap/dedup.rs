@@ -0,0 +1,158 @@
+//! Duplicate detection for received CoAP messages.
+//!
+//! RFC 7252 §4.5 requires a recipient to acknowledge every copy of a
+//! Confirmable message but to process it only once, and permits the same for
+//! Non-confirmable messages. Duplicates are recognised by their message id
+//! (together with the sender's endpoint, which a single-peer client can
+//! ignore) for `EXCHANGE_LIFETIME` after the first copy.
+//!
+//! [`MessageIdCache`] keeps that history in a fixed-size ring. When it is
+//! full the oldest entry is evicted, so size it for the number of messages
+//! expected from a peer within [`EXCHANGE_LIFETIME_MS`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::network::application::coap::dedup::{Delivery, MessageIdCache};
+//!
+//! let mut cache = MessageIdCache::<8>::new();
+//!
+//! // First copy of a CON notification: process it, then acknowledge it
+//! assert_eq!(cache.receive(0x7d34, 1_000), Delivery::New);
+//! cache.mark_acked(0x7d34);
+//!
+//! // The ACK was lost and the server retransmits: ACK again, don't process
+//! assert_eq!(cache.receive(0x7d34, 3_000), Delivery::Duplicate { acked: true });
+//! ```
+
+use heapless::Deque;
+
+/// Time from sending a Confirmable message to when its message id may be
+/// safely reused, with the default transmission parameters (RFC 7252 §4.8.2).
+pub const EXCHANGE_LIFETIME_MS: u64 = 247_000;
+
+/// Outcome of checking a received message id against the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// First copy of this message: deliver it to the application.
+    New,
+    /// A copy was already received within the exchange lifetime; do not
+    /// deliver it again.
+    Duplicate {
+        /// Whether the first copy has already been acknowledged. If so, a
+        /// duplicate CON must be answered with the same ACK again; if not, the
+        /// ACK is still outstanding and will be sent once processing finishes.
+        acked: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    message_id: u16,
+    received_at: u64,
+    acked: bool,
+}
+
+/// Remembers up to `N` recently received message ids.
+///
+/// Entries are kept in arrival order and expire [`EXCHANGE_LIFETIME_MS`]
+/// after the first copy was received. Timestamps are caller-supplied
+/// milliseconds from a [`MonotonicClock`](crate::system::clock::MonotonicClock)
+/// and must not decrease between calls.
+#[derive(Debug, Clone)]
+pub struct MessageIdCache<const N: usize> {
+    entries: Deque<Entry, N>,
+    lifetime_ms: u64,
+}
+
+impl<const N: usize> MessageIdCache<N> {
+    /// Create an empty cache using [`EXCHANGE_LIFETIME_MS`].
+    pub const fn new() -> Self {
+        Self::with_lifetime(EXCHANGE_LIFETIME_MS)
+    }
+
+    /// Create an empty cache with a custom exchange lifetime, e.g. when
+    /// non-default transmission parameters are in use.
+    pub const fn with_lifetime(lifetime_ms: u64) -> Self {
+        Self {
+            entries: Deque::new(),
+            lifetime_ms,
+        }
+    }
+
+    /// Record a received message id and report whether it is a duplicate.
+    ///
+    /// New ids are remembered as not yet acknowledged, evicting the oldest
+    /// entry if the cache is full.
+    pub fn receive(&mut self, message_id: u16, now_ms: u64) -> Delivery {
+        self.expire(now_ms);
+
+        if let Some(entry) = self.entries.iter().find(|e| e.message_id == message_id) {
+            return Delivery::Duplicate { acked: entry.acked };
+        }
+
+        if self.entries.is_full() {
+            self.entries.pop_front();
+        }
+        // Room was made above
+        let _ = self.entries.push_back(Entry {
+            message_id,
+            received_at: now_ms,
+            acked: false,
+        });
+        Delivery::New
+    }
+
+    /// Record that the ACK for `message_id` has been sent.
+    ///
+    /// Returns `false` if the id is not in the cache (never received, expired
+    /// or evicted).
+    pub fn mark_acked(&mut self, message_id: u16) -> bool {
+        match self.entries.iter_mut().find(|e| e.message_id == message_id) {
+            Some(entry) => {
+                entry.acked = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Message ids received but not yet acknowledged, oldest first.
+    pub fn pending_acks(&self) -> impl Iterator<Item = u16> + '_ {
+        self.entries
+            .iter()
+            .filter(|e| !e.acked)
+            .map(|e| e.message_id)
+    }
+
+    /// Drop entries older than the exchange lifetime.
+    pub fn expire(&mut self, now_ms: u64) {
+        while let Some(front) = self.entries.front() {
+            if now_ms.saturating_sub(front.received_at) < self.lifetime_ms {
+                break;
+            }
+            self.entries.pop_front();
+        }
+    }
+
+    /// Number of remembered message ids.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no message ids are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forget all message ids.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl<const N: usize> Default for MessageIdCache<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
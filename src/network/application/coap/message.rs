@@ -0,0 +1,422 @@
+//! CoAP message encoding and decoding.
+//!
+//! A CoAP message (RFC 7252 §3) is a 4-byte header holding the version,
+//! [`MessageType`], token length, [`Code`] and message id, followed by the
+//! token, the options and, after a `0xFF` marker, the payload. Each option
+//! is written as the difference from the previous option number, so options
+//! must appear in ascending order; deltas and lengths of 13 or more use one
+//! or two extension bytes.
+//!
+//! [`Message`] borrows its token, option values and payload, from the
+//! caller's data when building a message and from the received datagram
+//! when decoding one, so neither direction copies.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::network::application::coap::{Code, CoapOption, Message, MessageType};
+//!
+//! let mut request = Message::new(MessageType::Confirmable, Code::GET, 0x7d34);
+//! request.add_option(CoapOption::URI_PATH, b"temperature").unwrap();
+//!
+//! let mut buf = [0u8; 64];
+//! let len = request.encode(&mut buf).unwrap();
+//! assert_eq!(&buf[..4], &[0x40, 0x01, 0x7d, 0x34]);
+//!
+//! let decoded = Message::decode(&buf[..len]).unwrap();
+//! assert_eq!(decoded, request);
+//! assert_eq!(decoded.option(CoapOption::URI_PATH), Some(&b"temperature"[..]));
+//! ```
+
+use crate::network::error::Error;
+use heapless::Vec;
+
+/// The only protocol version defined by RFC 7252.
+pub const VERSION: u8 = 1;
+
+/// Longest token allowed in a message.
+pub const MAX_TOKEN_LEN: usize = 8;
+
+/// Most options a [`Message`] can hold.
+pub const MAX_OPTIONS: usize = 16;
+
+/// Marks the end of the options and the start of the payload.
+const PAYLOAD_MARKER: u8 = 0xFF;
+
+/// How a message is to be delivered (RFC 7252 §4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    /// Requires an acknowledgement; retransmitted until one arrives.
+    Confirmable,
+    /// Does not require an acknowledgement.
+    NonConfirmable,
+    /// Acknowledges a Confirmable message, possibly carrying the response.
+    Acknowledgement,
+    /// Tells the sender that a message could not be processed.
+    Reset,
+}
+
+impl MessageType {
+    fn bits(self) -> u8 {
+        match self {
+            Self::Confirmable => 0,
+            Self::NonConfirmable => 1,
+            Self::Acknowledgement => 2,
+            Self::Reset => 3,
+        }
+    }
+
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0x03 {
+            0 => Self::Confirmable,
+            1 => Self::NonConfirmable,
+            2 => Self::Acknowledgement,
+            _ => Self::Reset,
+        }
+    }
+}
+
+/// A request method or response code, written `class.detail` (e.g. 2.05).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Code(pub u8);
+
+impl Code {
+    /// 0.00, used by empty messages such as a bare ACK or a ping.
+    pub const EMPTY: Self = Self::new(0, 0);
+    /// 0.01 GET
+    pub const GET: Self = Self::new(0, 1);
+    /// 0.02 POST
+    pub const POST: Self = Self::new(0, 2);
+    /// 0.03 PUT
+    pub const PUT: Self = Self::new(0, 3);
+    /// 0.04 DELETE
+    pub const DELETE: Self = Self::new(0, 4);
+    /// 2.01 Created
+    pub const CREATED: Self = Self::new(2, 1);
+    /// 2.02 Deleted
+    pub const DELETED: Self = Self::new(2, 2);
+    /// 2.03 Valid
+    pub const VALID: Self = Self::new(2, 3);
+    /// 2.04 Changed
+    pub const CHANGED: Self = Self::new(2, 4);
+    /// 2.05 Content
+    pub const CONTENT: Self = Self::new(2, 5);
+    /// 4.00 Bad Request
+    pub const BAD_REQUEST: Self = Self::new(4, 0);
+    /// 4.01 Unauthorized
+    pub const UNAUTHORIZED: Self = Self::new(4, 1);
+    /// 4.04 Not Found
+    pub const NOT_FOUND: Self = Self::new(4, 4);
+    /// 4.05 Method Not Allowed
+    pub const METHOD_NOT_ALLOWED: Self = Self::new(4, 5);
+    /// 5.00 Internal Server Error
+    pub const INTERNAL_SERVER_ERROR: Self = Self::new(5, 0);
+    /// 5.03 Service Unavailable
+    pub const SERVICE_UNAVAILABLE: Self = Self::new(5, 3);
+
+    /// Build a code from its class (0 to 7) and detail (0 to 31).
+    pub const fn new(class: u8, detail: u8) -> Self {
+        Self((class << 5) | (detail & 0x1F))
+    }
+
+    /// The class: 0 for requests, 2 for success, 4 and 5 for errors.
+    pub const fn class(self) -> u8 {
+        self.0 >> 5
+    }
+
+    /// The detail within the class.
+    pub const fn detail(self) -> u8 {
+        self.0 & 0x1F
+    }
+
+    /// Whether this is a request method.
+    pub const fn is_request(self) -> bool {
+        self.class() == 0 && self.detail() != 0
+    }
+
+    /// Whether this is a response code.
+    pub const fn is_response(self) -> bool {
+        matches!(self.class(), 2..=5)
+    }
+}
+
+/// One option: a registered number and its raw value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoapOption<'a> {
+    /// Option number, see the associated constants.
+    pub number: u16,
+    /// Value bytes; integers are big-endian without leading zeros.
+    pub value: &'a [u8],
+}
+
+impl CoapOption<'_> {
+    /// If-Match
+    pub const IF_MATCH: u16 = 1;
+    /// Uri-Host
+    pub const URI_HOST: u16 = 3;
+    /// ETag
+    pub const ETAG: u16 = 4;
+    /// If-None-Match
+    pub const IF_NONE_MATCH: u16 = 5;
+    /// Observe (RFC 7641)
+    pub const OBSERVE: u16 = 6;
+    /// Uri-Port
+    pub const URI_PORT: u16 = 7;
+    /// Location-Path
+    pub const LOCATION_PATH: u16 = 8;
+    /// Uri-Path, one option per path segment
+    pub const URI_PATH: u16 = 11;
+    /// Content-Format
+    pub const CONTENT_FORMAT: u16 = 12;
+    /// Max-Age
+    pub const MAX_AGE: u16 = 14;
+    /// Uri-Query, one option per query argument
+    pub const URI_QUERY: u16 = 15;
+    /// Accept
+    pub const ACCEPT: u16 = 17;
+    /// Location-Query
+    pub const LOCATION_QUERY: u16 = 20;
+    /// Block2 (RFC 7959)
+    pub const BLOCK2: u16 = 23;
+    /// Block1 (RFC 7959)
+    pub const BLOCK1: u16 = 27;
+    /// Size2 (RFC 7959)
+    pub const SIZE2: u16 = 28;
+    /// Proxy-Uri
+    pub const PROXY_URI: u16 = 35;
+    /// Proxy-Scheme
+    pub const PROXY_SCHEME: u16 = 39;
+    /// Size1
+    pub const SIZE1: u16 = 60;
+}
+
+/// A CoAP message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message<'a> {
+    /// Protocol version, always [`VERSION`] for decoded messages.
+    pub version: u8,
+    /// Delivery type.
+    pub message_type: MessageType,
+    /// Token matching a response to its request, up to 8 bytes.
+    pub token: &'a [u8],
+    /// Request method or response code.
+    pub code: Code,
+    /// Id matching an ACK or RST to its message and detecting duplicates.
+    pub message_id: u16,
+    /// Options in ascending order of number.
+    pub options: Vec<CoapOption<'a>, MAX_OPTIONS>,
+    /// Payload, empty if there is none.
+    pub payload: &'a [u8],
+}
+
+impl<'a> Message<'a> {
+    /// Create a message without token, options or payload.
+    pub fn new(message_type: MessageType, code: Code, message_id: u16) -> Self {
+        Self {
+            version: VERSION,
+            message_type,
+            token: &[],
+            code,
+            message_id,
+            options: Vec::new(),
+            payload: &[],
+        }
+    }
+
+    /// Add an option, after any options with the same number.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::WriteError`] - The message already holds [`MAX_OPTIONS`]
+    ///   options
+    pub fn add_option(&mut self, number: u16, value: &'a [u8]) -> Result<(), Error> {
+        let index = self
+            .options
+            .partition_point(|option| option.number <= number);
+        self.options
+            .insert(index, CoapOption { number, value })
+            .map_err(|_| Error::WriteError)
+    }
+
+    /// The value of the first option with `number`.
+    pub fn option(&self, number: u16) -> Option<&'a [u8]> {
+        self.options_with(number).next()
+    }
+
+    /// The values of all options with `number`, in order.
+    pub fn options_with(&self, number: u16) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.options
+            .iter()
+            .filter(move |option| option.number == number)
+            .map(|option| option.value)
+    }
+
+    /// Write the message to `buf` and return its length.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::WriteError`] - `buf` is too small
+    /// * [`Error::ProtocolError`] - The token is longer than 8 bytes, or the
+    ///   options are not in ascending order
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        if self.token.len() > MAX_TOKEN_LEN {
+            return Err(Error::ProtocolError);
+        }
+
+        let mut writer = Writer { buf, len: 0 };
+        writer.push(&[
+            ((self.version & 0x03) << 6) | (self.message_type.bits() << 4) | self.token.len() as u8,
+            self.code.0,
+        ])?;
+        writer.push(&self.message_id.to_be_bytes())?;
+        writer.push(self.token)?;
+
+        let mut previous = 0;
+        for option in &self.options {
+            let delta = option
+                .number
+                .checked_sub(previous)
+                .ok_or(Error::ProtocolError)?;
+            previous = option.number;
+            let (delta_nibble, delta_ext) = extend(delta as usize)?;
+            let (len_nibble, len_ext) = extend(option.value.len())?;
+            writer.push(&[(delta_nibble << 4) | len_nibble])?;
+            writer.push(delta_ext.as_slice())?;
+            writer.push(len_ext.as_slice())?;
+            writer.push(option.value)?;
+        }
+
+        if !self.payload.is_empty() {
+            writer.push(&[PAYLOAD_MARKER])?;
+            writer.push(self.payload)?;
+        }
+        Ok(writer.len)
+    }
+
+    /// Parse a message from a received datagram.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ProtocolError`] - `buf` is not a well-formed CoAP message:
+    ///   it is truncated, has an unknown version, a token longer than 8
+    ///   bytes, a reserved option nibble, an empty payload after the payload
+    ///   marker, or anything after the header of an empty (0.00) message.
+    ///   It is also returned for more than [`MAX_OPTIONS`] options.
+    pub fn decode(buf: &'a [u8]) -> Result<Self, Error> {
+        let mut reader = Reader { buf, pos: 0 };
+        let header = reader.take(4)?;
+        let version = header[0] >> 6;
+        let token_len = (header[0] & 0x0F) as usize;
+        if version != VERSION || token_len > MAX_TOKEN_LEN {
+            return Err(Error::ProtocolError);
+        }
+
+        let mut message = Message::new(
+            MessageType::from_bits(header[0] >> 4),
+            Code(header[1]),
+            u16::from_be_bytes([header[2], header[3]]),
+        );
+        if message.code == Code::EMPTY && buf.len() != 4 {
+            return Err(Error::ProtocolError);
+        }
+        message.token = reader.take(token_len)?;
+
+        let mut number = 0u16;
+        while let Some(byte) = reader.byte() {
+            if byte == PAYLOAD_MARKER {
+                message.payload = reader.rest();
+                if message.payload.is_empty() {
+                    return Err(Error::ProtocolError);
+                }
+                break;
+            }
+            let delta = reader.extended(byte >> 4)?;
+            let len = reader.extended(byte & 0x0F)?;
+            number = u16::try_from(number as usize + delta).map_err(|_| Error::ProtocolError)?;
+            let value = reader.take(len)?;
+            message
+                .options
+                .push(CoapOption { number, value })
+                .map_err(|_| Error::ProtocolError)?;
+        }
+        Ok(message)
+    }
+}
+
+/// Split an option delta or length into its nibble and extension bytes.
+fn extend(value: usize) -> Result<(u8, Vec<u8, 2>), Error> {
+    let mut ext = Vec::new();
+    let nibble = match value {
+        0..=12 => value as u8,
+        13..=268 => {
+            let _ = ext.push((value - 13) as u8);
+            13
+        }
+        269..=65_804 => {
+            let _ = ext.extend_from_slice(&((value - 269) as u16).to_be_bytes());
+            14
+        }
+        _ => return Err(Error::ProtocolError),
+    };
+    Ok((nibble, ext))
+}
+
+/// Appends to a caller's buffer.
+struct Writer<'b> {
+    buf: &'b mut [u8],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn push(&mut self, data: &[u8]) -> Result<(), Error> {
+        let end = self.len + data.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(Error::WriteError)?
+            .copy_from_slice(data);
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Reads from a received datagram.
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let data = self
+            .buf
+            .get(self.pos..self.pos + len)
+            .ok_or(Error::ProtocolError)?;
+        self.pos += len;
+        Ok(data)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.buf.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.buf[self.pos..];
+        self.pos = self.buf.len();
+        rest
+    }
+
+    /// Resolve an option delta or length nibble and its extension bytes.
+    fn extended(&mut self, nibble: u8) -> Result<usize, Error> {
+        match nibble {
+            13 => Ok(self.take(1)?[0] as usize + 13),
+            14 => {
+                let ext = self.take(2)?;
+                Ok(u16::from_be_bytes([ext[0], ext[1]]) as usize + 269)
+            }
+            15 => Err(Error::ProtocolError),
+            nibble => Ok(nibble as usize),
+        }
+    }
+}
@@ -1 +1,31 @@
+//! CoAP (Constrained Application Protocol, RFC 7252) building blocks.
+//!
+//! CoAP runs over unreliable datagram transports, so the same message can
+//! reach a peer more than once: the network may duplicate it, or the sender
+//! may retransmit a Confirmable (CON) message whose acknowledgement was lost.
+//! This module provides the message format, in [`message`], a client that
+//! retransmits Confirmable requests until they are acknowledged, in
+//! [`client`], and the receiver-side bookkeeping needed to handle duplicates
+//! correctly, in [`dedup`].
 
+/// Request/response client.
+///
+/// Contains [`Client`], which sends Confirmable requests over a
+/// [`UdpSocket`](crate::network::UdpSocket) and waits for their responses.
+pub mod client;
+
+/// Message-id cache for duplicate detection and ACK tracking.
+///
+/// Contains [`MessageIdCache`](dedup::MessageIdCache), which remembers recently
+/// received message ids for `EXCHANGE_LIFETIME` so duplicates are not
+/// delivered twice and retransmitted CON messages get their ACK repeated.
+pub mod dedup;
+
+/// Message encoding and decoding.
+///
+/// Contains [`Message`], which borrows its token, options and payload, and
+/// encodes to or decodes from a datagram buffer.
+pub mod message;
+
+pub use client::{Client, Response};
+pub use message::{CoapOption, Code, Message, MessageType};
@@ -0,0 +1,47 @@
+//! Standard base64 encoding (RFC 4648, with padding) into fixed-capacity strings.
+
+use heapless::String;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Append the base64 encoding of `input` to `out`.
+///
+/// Taking an iterator lets callers encode several pieces, such as
+/// `user`, `:` and `password`, without first joining them in a buffer.
+/// Returns `Err(())` if `out` runs out of capacity; `out` then holds a
+/// partial encoding.
+pub(crate) fn encode_into<const N: usize>(
+    input: impl IntoIterator<Item = u8>,
+    out: &mut String<N>,
+) -> Result<(), ()> {
+    let mut input = input.into_iter();
+    loop {
+        let mut group = [0u8; 3];
+        let mut len = 0;
+        for byte in group.iter_mut() {
+            match input.next() {
+                Some(b) => {
+                    *byte = b;
+                    len += 1;
+                }
+                None => break,
+            }
+        }
+        if len == 0 {
+            return Ok(());
+        }
+
+        let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        for i in 0..4 {
+            let c = if i <= len {
+                ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize] as char
+            } else {
+                '='
+            };
+            out.push(c)?;
+        }
+        if len < 3 {
+            return Ok(());
+        }
+    }
+}
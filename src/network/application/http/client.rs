@@ -6,20 +6,23 @@
 //! # Features
 //!
 //! - HTTP/1.1 protocol support
-//! - GET and POST methods
+//! - GET and POST methods, plus any other method token (see [`Method::Custom`])
 //! - Custom headers
-//! - Request/response body handling
-//! - Connection reuse
+//! - Request/response body handling, including `Transfer-Encoding: chunked`
+//! - Connection reuse with keep-alive (see [`Client::is_reusable`])
 //! - Fixed-size buffers for predictable memory usage
+//! - Conditional requests with `If-None-Match`/`ETag` (see [`Client::request_conditional`])
+//! - Per-request size and timing statistics (see [`Client::last_stats`])
+//! - Streaming of bodies of any size (see [`Client::request_streaming`])
 //!
 //! # Limitations
 //!
 //! - Only supports HTTP/1.1 (no HTTP/2 or HTTP/3)
-//! - Limited to GET and POST methods
 //! - Maximum header count and sizes are compile-time constants
-//! - Response body size is limited by buffer capacity
-//! - No automatic redirect handling
-//! - No persistent connection management
+//! - Response body size is limited by buffer capacity, unless streamed
+//! - Redirects are only followed within the same server (see [`Client::with_max_redirects`])
+//! - No reconnection once the server closes the connection; re-establish the
+//!   transport and call [`Client::reset`]
 //!
 //! # Examples
 //!
@@ -101,13 +104,15 @@
 //! // let response = client.request(&request)?;
 //! ```
 
+use super::stream::{ChunkState, Framing, StreamingResponse};
 use crate::network::Connection;
 use crate::network::error::Error;
+use crate::system::clock::MonotonicClock;
 use core::fmt::Write;
 use heapless::{String, Vec};
 
 /// Maximum number of headers allowed per request/response.
-const MAX_HEADERS: usize = 16;
+pub(super) const MAX_HEADERS: usize = 16;
 
 /// Maximum length for header names in bytes.
 const MAX_HEADER_NAME_LEN: usize = 64;
@@ -115,10 +120,27 @@ const MAX_HEADER_NAME_LEN: usize = 64;
 /// Maximum length for header values in bytes.
 const MAX_HEADER_VALUE_LEN: usize = 256;
 
+/// Size of the buffer the status line and headers are read into.
+const RESPONSE_BUF_LEN: usize = 2048;
+
+/// Response headers that are kept even when the header capacity is exhausted.
+///
+/// These are the headers the crate itself relies on (body framing, redirects
+/// and partial content for OTA downloads). See [`Client::with_critical_headers`].
+pub const DEFAULT_CRITICAL_HEADERS: &[&str] = &[
+    "Content-Length",
+    "Content-Type",
+    "Content-Range",
+    "Transfer-Encoding",
+    "Location",
+    "ETag",
+];
+
 /// HTTP request methods supported by the client.
 ///
-/// Currently supports the most common HTTP methods used in IoT applications.
-/// Additional methods can be added as needed.
+/// The most common HTTP methods used in IoT applications have their own
+/// variants. Any other verb (e.g. WebDAV's `PROPFIND`) can be sent with
+/// [`Method::Custom`].
 ///
 /// # Examples
 ///
@@ -137,6 +159,12 @@ pub enum Method {
     Get,
     /// HTTP POST method for sending data.
     Post,
+    /// Any other method token, sent verbatim on the request line.
+    ///
+    /// The token must consist of uppercase letters and other HTTP token
+    /// characters; see [`Method::custom`]. Requests with an invalid token
+    /// are rejected with [`Error::ProtocolError`].
+    Custom(&'static str),
 }
 
 impl Method {
@@ -156,8 +184,83 @@ impl Method {
         match self {
             Method::Get => "GET",
             Method::Post => "POST",
+            Method::Custom(token) => token,
         }
     }
+
+    /// Create a custom method, validating the token.
+    ///
+    /// Returns `None` unless `token` is non-empty and made only of uppercase
+    /// ASCII letters, digits, and the HTTP token symbols
+    /// `` !#$%&'*+-.^_`|~ ``.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::network::application::http::client::Method;
+    ///
+    /// assert_eq!(Method::custom("PROPFIND"), Some(Method::Custom("PROPFIND")));
+    /// assert_eq!(Method::custom("propfind"), None);
+    /// assert_eq!(Method::custom("BAD VERB"), None);
+    /// ```
+    pub fn custom(token: &'static str) -> Option<Self> {
+        let method = Method::Custom(token);
+        method.is_valid().then_some(method)
+    }
+
+    /// Check whether the method token can be sent on a request line.
+    pub fn is_valid(&self) -> bool {
+        let token = self.as_str();
+        !token.is_empty()
+            && token.bytes().all(|b| {
+                b.is_ascii_uppercase() || b.is_ascii_digit() || b"!#$%&'*+-.^_`|~".contains(&b)
+            })
+    }
+}
+
+/// Standard methods other than GET and POST, parsed into [`Method::Custom`]
+const STANDARD_METHODS: &[&str] = &[
+    "PUT", "DELETE", "HEAD", "PATCH", "OPTIONS", "CONNECT", "TRACE",
+];
+
+/// Parse a standard HTTP method name, ignoring ASCII case.
+///
+/// `GET` and `POST` map to their variants and the other RFC 9110 methods
+/// (`PUT`, `DELETE`, `HEAD`, `PATCH`, `OPTIONS`, `CONNECT`, `TRACE`) to
+/// [`Method::Custom`]. Other tokens need a `'static` string and go through
+/// [`Method::custom`]; here they are rejected with [`Error::ProtocolError`].
+///
+/// ```rust
+/// use libiot::network::application::http::client::Method;
+///
+/// assert_eq!(Method::try_from("get"), Ok(Method::Get));
+/// assert_eq!("DELETE".parse(), Ok(Method::Custom("DELETE")));
+/// assert!(Method::try_from("PROPFIND").is_err());
+/// ```
+impl TryFrom<&str> for Method {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value.eq_ignore_ascii_case("GET") {
+            return Ok(Method::Get);
+        }
+        if value.eq_ignore_ascii_case("POST") {
+            return Ok(Method::Post);
+        }
+        STANDARD_METHODS
+            .iter()
+            .find(|name| value.eq_ignore_ascii_case(name))
+            .map(|name| Method::Custom(name))
+            .ok_or(Error::ProtocolError)
+    }
+}
+
+impl core::str::FromStr for Method {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
 }
 
 /// An HTTP header consisting of a name-value pair.
@@ -179,7 +282,7 @@ impl Method {
 /// assert_eq!(header.name.as_str(), "Content-Type");
 /// assert_eq!(header.value.as_str(), "application/json");
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Header {
     /// The header name (e.g., "Content-Type", "Authorization").
     pub name: String<MAX_HEADER_NAME_LEN>,
@@ -187,6 +290,71 @@ pub struct Header {
     pub value: String<MAX_HEADER_VALUE_LEN>,
 }
 
+impl Header {
+    /// Create a header from a name and value, failing if either is too long.
+    fn new(name: &str, value: &str) -> Result<Self, Error> {
+        Ok(Header {
+            name: String::try_from(name).map_err(|_| Error::WriteError)?,
+            value: String::try_from(value).map_err(|_| Error::WriteError)?,
+        })
+    }
+
+    /// An `Authorization` header for HTTP Basic authentication.
+    ///
+    /// `user:password` is base64-encoded as required by RFC 7617. The
+    /// encoded credentials are not secret: send them over TLS only.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::WriteError`] if the encoded value exceeds
+    /// `MAX_HEADER_VALUE_LEN` bytes, i.e. `user` and `password` together are
+    /// longer than 185 bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::network::application::http::client::Header;
+    ///
+    /// let header = Header::basic_auth("Aladdin", "open sesame").unwrap();
+    /// assert_eq!(header.name.as_str(), "Authorization");
+    /// assert_eq!(header.value.as_str(), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+    /// ```
+    pub fn basic_auth(user: &str, password: &str) -> Result<Self, Error> {
+        let mut header = Header::new("Authorization", "Basic ")?;
+        let credentials = user.bytes().chain(*b":").chain(password.bytes());
+        super::base64::encode_into(credentials, &mut header.value)
+            .map_err(|_| Error::WriteError)?;
+        Ok(header)
+    }
+
+    /// An `Authorization` header carrying an OAuth 2.0 bearer token.
+    ///
+    /// The token is sent as given, so it must already be in its transport
+    /// form (e.g. a JWT).
+    ///
+    /// # Errors
+    ///
+    /// [`Error::WriteError`] if `Bearer ` plus `token` exceeds
+    /// `MAX_HEADER_VALUE_LEN` bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::network::application::http::client::Header;
+    ///
+    /// let header = Header::bearer("mF_9.B5f-4.1JqM").unwrap();
+    /// assert_eq!(header.value.as_str(), "Bearer mF_9.B5f-4.1JqM");
+    /// ```
+    pub fn bearer(token: &str) -> Result<Self, Error> {
+        let mut header = Header::new("Authorization", "Bearer ")?;
+        header
+            .value
+            .push_str(token)
+            .map_err(|_| Error::WriteError)?;
+        Ok(header)
+    }
+}
+
 /// An HTTP request to be sent by the client.
 ///
 /// Contains all the information needed to construct a complete HTTP request,
@@ -205,6 +373,7 @@ pub struct Header {
 ///     body: None,
 /// };
 /// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Request<'a> {
     /// The HTTP method to use for this request.
     pub method: Method,
@@ -216,6 +385,128 @@ pub struct Request<'a> {
     pub body: Option<&'a [u8]>,
 }
 
+/// An HTTP response status code.
+///
+/// A thin wrapper over the numeric code with names for the codes IoT
+/// clients commonly handle and helpers for the five status classes. Any
+/// code the server sends can be represented, named or not.
+///
+/// # Examples
+///
+/// ```rust
+/// use libiot::network::application::http::client::StatusCode;
+///
+/// let status = StatusCode::from(206);
+/// assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+/// assert!(status.is_success());
+/// assert_eq!(status.as_u16(), 206);
+///
+/// match StatusCode::from(404) {
+///     StatusCode::NOT_FOUND => { /* nothing to download */ }
+///     status if status.is_server_error() => { /* retry later */ }
+///     _ => {}
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StatusCode(u16);
+
+impl StatusCode {
+    /// 200 OK
+    pub const OK: StatusCode = StatusCode(200);
+    /// 201 Created
+    pub const CREATED: StatusCode = StatusCode(201);
+    /// 202 Accepted
+    pub const ACCEPTED: StatusCode = StatusCode(202);
+    /// 204 No Content
+    pub const NO_CONTENT: StatusCode = StatusCode(204);
+    /// 206 Partial Content, the answer to a satisfied range request
+    pub const PARTIAL_CONTENT: StatusCode = StatusCode(206);
+    /// 301 Moved Permanently
+    pub const MOVED_PERMANENTLY: StatusCode = StatusCode(301);
+    /// 302 Found
+    pub const FOUND: StatusCode = StatusCode(302);
+    /// 303 See Other
+    pub const SEE_OTHER: StatusCode = StatusCode(303);
+    /// 304 Not Modified, the answer to a conditional request for an unchanged resource
+    pub const NOT_MODIFIED: StatusCode = StatusCode(304);
+    /// 307 Temporary Redirect
+    pub const TEMPORARY_REDIRECT: StatusCode = StatusCode(307);
+    /// 308 Permanent Redirect
+    pub const PERMANENT_REDIRECT: StatusCode = StatusCode(308);
+    /// 400 Bad Request
+    pub const BAD_REQUEST: StatusCode = StatusCode(400);
+    /// 401 Unauthorized
+    pub const UNAUTHORIZED: StatusCode = StatusCode(401);
+    /// 403 Forbidden
+    pub const FORBIDDEN: StatusCode = StatusCode(403);
+    /// 404 Not Found
+    pub const NOT_FOUND: StatusCode = StatusCode(404);
+    /// 416 Range Not Satisfiable
+    pub const RANGE_NOT_SATISFIABLE: StatusCode = StatusCode(416);
+    /// 429 Too Many Requests
+    pub const TOO_MANY_REQUESTS: StatusCode = StatusCode(429);
+    /// 500 Internal Server Error
+    pub const INTERNAL_SERVER_ERROR: StatusCode = StatusCode(500);
+    /// 502 Bad Gateway
+    pub const BAD_GATEWAY: StatusCode = StatusCode(502);
+    /// 503 Service Unavailable
+    pub const SERVICE_UNAVAILABLE: StatusCode = StatusCode(503);
+
+    /// The numeric code.
+    pub const fn as_u16(self) -> u16 {
+        self.0
+    }
+
+    /// Whether the code is 1xx.
+    pub const fn is_informational(self) -> bool {
+        matches!(self.0, 100..=199)
+    }
+
+    /// Whether the code is 2xx.
+    pub const fn is_success(self) -> bool {
+        matches!(self.0, 200..=299)
+    }
+
+    /// Whether the code is 3xx.
+    pub const fn is_redirect(self) -> bool {
+        matches!(self.0, 300..=399)
+    }
+
+    /// Whether the code is 4xx.
+    pub const fn is_client_error(self) -> bool {
+        matches!(self.0, 400..=499)
+    }
+
+    /// Whether the code is 5xx.
+    pub const fn is_server_error(self) -> bool {
+        matches!(self.0, 500..=599)
+    }
+}
+
+impl From<u16> for StatusCode {
+    fn from(code: u16) -> Self {
+        StatusCode(code)
+    }
+}
+
+impl From<StatusCode> for u16 {
+    fn from(status: StatusCode) -> Self {
+        status.0
+    }
+}
+
+impl PartialEq<u16> for StatusCode {
+    fn eq(&self, other: &u16) -> bool {
+        self.0 == *other
+    }
+}
+
+impl core::fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// An HTTP response received from the server.
 ///
 /// Contains the response status code, headers, and body data returned by the server.
@@ -233,16 +524,118 @@ pub struct Request<'a> {
 /// //     println!("Success! Body length: {}", response.body.len());
 /// // }
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Response {
     /// HTTP status code (e.g., 200, 404, 500).
     pub status_code: u16,
     /// Response headers sent by the server.
     pub headers: Vec<Header, MAX_HEADERS>,
+    /// Set when not every header could be stored as received.
+    ///
+    /// This happens when the server sent more than `MAX_HEADERS` headers (the
+    /// excess non-critical ones are dropped), when a header value was longer
+    /// than `MAX_HEADER_VALUE_LEN` (the value is cut short), or when a header
+    /// name was longer than `MAX_HEADER_NAME_LEN` (the header is dropped).
+    pub headers_truncated: bool,
     /// Response body data with a maximum size of 2048 bytes.
     pub body: Vec<u8, 2048>,
 }
 
+impl Response {
+    /// The status code as a [`StatusCode`].
+    pub fn status(&self) -> StatusCode {
+        StatusCode(self.status_code)
+    }
+
+    /// Look up a header value by name, ignoring ASCII case.
+    ///
+    /// Returns the value of the first matching header.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::network::application::http::client::{Header, Response};
+    ///
+    /// let mut response = Response {
+    ///     status_code: 200,
+    ///     headers: heapless::Vec::new(),
+    ///     headers_truncated: false,
+    ///     body: heapless::Vec::new(),
+    /// };
+    /// response.headers.push(Header {
+    ///     name: "content-length".try_into().unwrap(),
+    ///     value: "42".try_into().unwrap(),
+    /// }).unwrap();
+    ///
+    /// assert_eq!(response.header("Content-Length"), Some("42"));
+    /// assert_eq!(response.content_length(), Some(42));
+    /// assert_eq!(response.header("ETag"), None);
+    /// ```
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// The body length declared by the `Content-Length` header.
+    ///
+    /// Returns `None` if the header is absent or not a valid length.
+    pub fn content_length(&self) -> Option<usize> {
+        self.header("Content-Length")?.parse().ok()
+    }
+
+    /// The `ETag` the server assigned to this version of the resource.
+    pub fn etag(&self) -> Option<&str> {
+        self.header("ETag")
+    }
+}
+
+/// Entity tag identifying a version of a resource, as sent in `ETag`.
+///
+/// Stored verbatim, including quotes and any weak `W/` prefix, so it can be
+/// echoed back in `If-None-Match`.
+pub type ETag = String<MAX_HEADER_VALUE_LEN>;
+
+/// Outcome of a [`Client::request_conditional`] call.
+// `Response` is returned by value like from `Client::request`; boxing would
+// need an allocator.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conditional {
+    /// The server answered `304 Not Modified`: the stored copy is current.
+    NotModified,
+    /// The server sent a full response (any status other than 304).
+    Modified(Response),
+}
+
+/// Size and timing of the most recent successful request.
+///
+/// Useful for diagnostics, e.g. to pick an OTA chunk size that keeps each
+/// request within a time budget. See [`Client::last_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResponseStats {
+    /// Bytes of response body received.
+    pub body_bytes: usize,
+    /// Bytes of status line and headers, including the blank line ending them.
+    pub header_bytes: usize,
+    /// Time from sending the request to receiving the full response, in
+    /// milliseconds. Only measured by [`Client::request_timed`].
+    pub elapsed_ms: Option<u64>,
+}
+
+/// Status line and headers of a response, before its body is read.
+pub(super) struct ResponseHead {
+    pub(super) status_code: u16,
+    pub(super) headers: Vec<Header, MAX_HEADERS>,
+    pub(super) headers_truncated: bool,
+    content_length: Option<usize>,
+    chunked: bool,
+    /// Whether the server will accept another request on this connection.
+    pub(super) keep_alive: bool,
+    /// Bytes of status line and headers, including the blank line ending them.
+    pub(super) header_bytes: usize,
+    /// Bytes read into the response buffer, headers included.
+    pub(super) buffered: usize,
+}
+
 /// HTTP client for making requests over any connection type.
 ///
 /// The client is generic over the connection type, allowing it to work with
@@ -280,6 +673,10 @@ pub struct Response {
 /// ```
 pub struct Client<C: Connection> {
     connection: C,
+    critical_headers: &'static [&'static str],
+    last_stats: Option<ResponseStats>,
+    max_redirects: u8,
+    reusable: bool,
 }
 
 impl<C: Connection> Client<C> {
@@ -323,7 +720,91 @@ impl<C: Connection> Client<C> {
     /// let mut http_client = Client::new(tcp_connection);
     /// ```
     pub fn new(connection: C) -> Self {
-        Self { connection }
+        Self {
+            connection,
+            critical_headers: DEFAULT_CRITICAL_HEADERS,
+            last_stats: None,
+            max_redirects: 0,
+            reusable: true,
+        }
+    }
+
+    /// Set the response headers that must survive header capacity overflow.
+    ///
+    /// When a response carries more headers than can be stored, headers whose
+    /// name matches one of `names` (case-insensitively) replace previously
+    /// stored non-critical headers instead of being dropped. Defaults to
+    /// [`DEFAULT_CRITICAL_HEADERS`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use libiot::network::application::http::client::Client;
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection;
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, _buf: &[u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    ///
+    /// let client = Client::new(MockConnection)
+    ///     .with_critical_headers(&["Content-Length", "Location", "ETag"]);
+    /// ```
+    pub fn with_critical_headers(mut self, names: &'static [&'static str]) -> Self {
+        self.critical_headers = names;
+        self
+    }
+
+    /// Follow up to `max` redirects per request (default 0, i.e. none).
+    ///
+    /// On a 301, 302, 303, 307 or 308 response with a usable `Location`,
+    /// the request is re-issued to the new location over the same
+    /// connection. A 303 turns the request into a body-less GET (a HEAD
+    /// stays a HEAD); the other statuses keep the method and body. Once `max`
+    /// redirects have been followed, the last redirect response is returned
+    /// as is, which also ends redirect loops.
+    ///
+    /// Because the connection is reused, only locations on the same server
+    /// are followed: absolute paths such as `/v2/fw.bin`, and absolute URLs
+    /// whose authority matches the request's `Host` header. Redirects
+    /// elsewhere, or to relative paths, are returned to the caller.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use libiot::network::application::http::client::Client;
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection;
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, _buf: &[u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    ///
+    /// let client = Client::new(MockConnection).with_max_redirects(3);
+    /// ```
+    pub fn with_max_redirects(mut self, max: u8) -> Self {
+        self.max_redirects = max;
+        self
     }
 
     /// Send an HTTP request and receive the response.
@@ -348,7 +829,8 @@ impl<C: Connection> Client<C> {
     /// * [`Error::WriteError`] - Failed to send the request
     /// * [`Error::ReadError`] - Failed to read the response
     /// * [`Error::ConnectionClosed`] - Connection was closed unexpectedly
-    /// * [`Error::ProtocolError`] - Invalid HTTP response format
+    /// * [`Error::ProtocolError`] - Invalid HTTP response format, malformed chunked
+    ///   body, body larger than 2048 bytes, or invalid custom method token
     ///
     /// # Examples
     ///
@@ -391,6 +873,258 @@ impl<C: Connection> Client<C> {
     /// // }
     /// ```
     pub fn request(&mut self, request: &Request) -> Result<Response, Error> {
+        self.request_with_clock(request, None)
+    }
+
+    /// Send a request like [`request`](Self::request), timing it with `clock`.
+    ///
+    /// The elapsed time is reported in [`ResponseStats::elapsed_ms`] of
+    /// [`last_stats`](Self::last_stats).
+    pub fn request_timed<K: MonotonicClock>(
+        &mut self,
+        request: &Request,
+        clock: &K,
+    ) -> Result<Response, Error> {
+        self.request_with_clock(request, Some(clock))
+    }
+
+    /// Whether another request can be sent over the connection.
+    ///
+    /// Requests are sent with `Connection: keep-alive` (unless the request
+    /// sets its own `Connection` header) and each response body is read to
+    /// its end, so consecutive requests share one connection. This turns
+    /// `false` once the server answers with `Connection: close`, with an
+    /// HTTP/1.0 response that does not ask to keep the connection open, or
+    /// with a body that ends by closing the connection. It also turns
+    /// `false` when a request fails part way or a
+    /// [`StreamingResponse`] is dropped before its body was read in full.
+    /// Further requests then fail with [`Error::ConnectionClosed`]; open a
+    /// new connection and client to continue.
+    pub fn is_reusable(&self) -> bool {
+        self.reusable
+    }
+
+    /// Allow requests again after [`is_reusable`](Self::is_reusable) turned
+    /// `false`.
+    ///
+    /// Only call this once the transport underneath has been re-established
+    /// and carries no bytes of an earlier response, e.g. a modem socket that
+    /// was closed and reopened; otherwise the next response is read from the
+    /// middle of an old one.
+    pub fn reset(&mut self) {
+        self.reusable = true;
+    }
+
+    /// Statistics of the last request, or `None` if it failed or none was made.
+    ///
+    /// [`ResponseStats::elapsed_ms`] is `None` unless the request was sent
+    /// with [`request_timed`](Self::request_timed).
+    pub fn last_stats(&self) -> Option<ResponseStats> {
+        self.last_stats
+    }
+
+    fn request_with_clock(
+        &mut self,
+        request: &Request,
+        clock: Option<&dyn MonotonicClock>,
+    ) -> Result<Response, Error> {
+        self.last_stats = None;
+        let start = clock.map(|clock| clock.now_ms());
+        let (mut response, mut header_bytes) = self.exchange(request)?;
+        let (mut method, mut body) = (request.method, request.body);
+        for _ in 0..self.max_redirects {
+            let Some(location) =
+                redirect_location(response.status_code, &response.headers, request)
+                    .filter(|_| self.reusable)
+            else {
+                break;
+            };
+            (method, body) = redirected_method(response.status_code, method, body);
+            (response, header_bytes) = self.exchange(&Request {
+                method,
+                path: &location,
+                headers: request.headers.clone(),
+                body,
+            })?;
+        }
+        self.last_stats = Some(ResponseStats {
+            body_bytes: response.body.len(),
+            header_bytes,
+            elapsed_ms: clock
+                .zip(start)
+                .map(|(clock, start)| clock.elapsed_ms(start)),
+        });
+        Ok(response)
+    }
+
+    /// Send a request and read the response, also returning the header size.
+    fn exchange(&mut self, request: &Request) -> Result<(Response, usize), Error> {
+        self.send_request(request)?;
+
+        let mut response_buf = [0u8; RESPONSE_BUF_LEN];
+        let head = self.read_head(&mut response_buf)?;
+        let header_bytes = head.header_bytes;
+        let mut stream = self.body_stream(request.method, head, response_buf);
+
+        let mut body: Vec<u8, 2048> = Vec::new();
+        let mut temp_buf = [0; 256];
+        loop {
+            // One byte more than fits, so an oversized body is detected
+            let space = (body.capacity() - body.len() + 1).min(temp_buf.len());
+            let n = stream.read_body_chunk(&mut temp_buf[..space])?;
+            if n == 0 {
+                break;
+            }
+            // Body is larger than our buffer.
+            body.extend_from_slice(&temp_buf[..n])
+                .map_err(|_| Error::ProtocolError)?;
+        }
+
+        let StreamingResponse {
+            status_code,
+            headers,
+            headers_truncated,
+            ..
+        } = stream;
+        let response = Response {
+            status_code,
+            headers,
+            headers_truncated,
+            body,
+        };
+        Ok((response, header_bytes))
+    }
+
+    /// Send a request and return its response with the body still unread.
+    ///
+    /// Only the status line and headers are read here. The body is then
+    /// pulled through [`StreamingResponse::read_body_chunk`] into buffers the
+    /// caller provides, so it can be of any size: a firmware image can be
+    /// written to flash piece by piece without range requests.
+    ///
+    /// The body is delimited by `Content-Length`, by
+    /// `Transfer-Encoding: chunked`, or otherwise by the server closing the
+    /// connection. Redirects are followed as configured with
+    /// [`with_max_redirects`](Self::with_max_redirects), discarding the
+    /// redirect bodies. [`last_stats`](Self::last_stats) is cleared, since
+    /// the body size is only known once it has been read.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`request`](Self::request), except that a body of any size is
+    /// accepted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use libiot::network::application::http::client::{Client, Method, Request};
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection;
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, _buf: &[u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # fn write_to_flash(_offset: usize, _data: &[u8]) {}
+    ///
+    /// let mut client = Client::new(MockConnection);
+    /// let request = Request {
+    ///     method: Method::Get,
+    ///     path: "/firmware.bin",
+    ///     headers: heapless::Vec::new(),
+    ///     body: None,
+    /// };
+    ///
+    /// let mut response = client.request_streaming(&request)?;
+    /// let mut offset = 0;
+    /// let mut chunk = [0u8; 512];
+    /// loop {
+    ///     let n = response.read_body_chunk(&mut chunk)?;
+    ///     if n == 0 {
+    ///         break;
+    ///     }
+    ///     write_to_flash(offset, &chunk[..n]);
+    ///     offset += n;
+    /// }
+    /// # Ok::<(), libiot::network::error::Error>(())
+    /// ```
+    pub fn request_streaming(
+        &mut self,
+        request: &Request,
+    ) -> Result<StreamingResponse<'_, C>, Error> {
+        self.last_stats = None;
+        self.send_request(request)?;
+
+        let mut buffer = [0u8; RESPONSE_BUF_LEN];
+        let mut head = self.read_head(&mut buffer)?;
+        let (mut method, mut body) = (request.method, request.body);
+        for _ in 0..self.max_redirects {
+            let Some(location) = redirect_location(head.status_code, &head.headers, request)
+                .filter(|_| head.keep_alive)
+            else {
+                break;
+            };
+            // Read past the redirect's body so the connection can carry the next request
+            let mut redirect = self.body_stream(method, head, buffer);
+            while redirect.read_body_chunk(&mut [0; 256])? != 0 {}
+
+            (method, body) = redirected_method(redirect.status_code, method, body);
+            self.send_request(&Request {
+                method,
+                path: &location,
+                headers: request.headers.clone(),
+                body,
+            })?;
+            head = self.read_head(&mut buffer)?;
+        }
+        Ok(self.body_stream(method, head, buffer))
+    }
+
+    /// Wrap the connection in a reader for the body that follows `head`.
+    fn body_stream(
+        &mut self,
+        method: Method,
+        head: ResponseHead,
+        buffer: [u8; RESPONSE_BUF_LEN],
+    ) -> StreamingResponse<'_, C> {
+        let framing =
+            if method.as_str() == "HEAD" || matches!(head.status_code, 100..=199 | 204 | 304) {
+                Framing::Done
+            } else if head.chunked {
+                Framing::Chunked(ChunkState::Size)
+            } else if let Some(len) = head.content_length {
+                Framing::Length(len)
+            } else {
+                Framing::UntilClose
+            };
+
+        StreamingResponse::new(
+            head,
+            &mut self.connection,
+            &mut self.reusable,
+            buffer,
+            framing,
+        )
+    }
+
+    /// Serialize `request` and write it to the connection.
+    fn send_request(&mut self, request: &Request) -> Result<(), Error> {
+        if !self.reusable {
+            return Err(Error::ConnectionClosed);
+        }
+        if !request.method.is_valid() {
+            return Err(Error::ProtocolError);
+        }
+
         // --- Build Request ---
         let mut request_buf: Vec<u8, 2048> = Vec::new();
 
@@ -408,10 +1142,14 @@ impl<C: Connection> Client<C> {
 
         // Headers
         let mut has_user_agent = false;
+        let mut has_connection = false;
         for header in &request.headers {
             if header.name.eq_ignore_ascii_case("User-Agent") {
                 has_user_agent = true;
             }
+            if header.name.eq_ignore_ascii_case("Connection") {
+                has_connection = true;
+            }
             request_buf
                 .extend_from_slice(header.name.as_bytes())
                 .map_err(|_| Error::WriteError)?;
@@ -431,6 +1169,11 @@ impl<C: Connection> Client<C> {
                 .extend_from_slice(b"User-Agent:;\r\n")
                 .map_err(|_| Error::WriteError)?;
         }
+        if !has_connection {
+            request_buf
+                .extend_from_slice(b"Connection: keep-alive\r\n")
+                .map_err(|_| Error::WriteError)?;
+        }
 
         // Body
         if let Some(body) = request.body {
@@ -456,13 +1199,19 @@ impl<C: Connection> Client<C> {
         }
 
         // --- Send Request ---
+        // Until the response has been read in full the stream position is unknown
+        self.reusable = false;
         self.connection
             .write(&request_buf)
             .map_err(|_| Error::WriteError)?;
-        self.connection.flush().map_err(|_| Error::WriteError)?;
+        self.connection.flush().map_err(|_| Error::WriteError)
+    }
 
-        // --- Receive Response ---
-        let mut response_buf = [0u8; 2048];
+    /// Read and parse the status line and headers into `response_buf`.
+    ///
+    /// Any body bytes that arrived with the headers are left in
+    /// `response_buf[head.header_bytes..head.buffered]`.
+    fn read_head(&mut self, response_buf: &mut [u8]) -> Result<ResponseHead, Error> {
         let mut total_read = 0;
         loop {
             match self.connection.read(&mut response_buf[total_read..]) {
@@ -490,7 +1239,6 @@ impl<C: Connection> Client<C> {
         // Find where headers end and body begins
         let header_end_pos = find_slice(response_data, b"\r\n\r\n").ok_or(Error::ProtocolError)?;
         let header_data = &response_data[..header_end_pos];
-        let body_data = &response_data[header_end_pos + 4..];
 
         let header_str = core::str::from_utf8(header_data).map_err(|_| Error::ProtocolError)?;
         let mut lines = header_str.lines();
@@ -498,7 +1246,7 @@ impl<C: Connection> Client<C> {
         // Parse status line
         let status_line = lines.next().ok_or(Error::ProtocolError)?;
         let mut status_parts = status_line.splitn(3, ' ');
-        status_parts.next(); // Skip HTTP version
+        let version = status_parts.next().ok_or(Error::ProtocolError)?;
         let status_code_str = status_parts.next().ok_or(Error::ProtocolError)?;
         let status_code = status_code_str
             .parse::<u16>()
@@ -506,7 +1254,11 @@ impl<C: Connection> Client<C> {
 
         // Parse headers
         let mut response_headers: Vec<Header, MAX_HEADERS> = Vec::new();
+        let mut headers_truncated = false;
         let mut content_length: Option<usize> = None;
+        let mut chunked = false;
+        // HTTP/1.1 connections persist unless closed explicitly; 1.0 ones the reverse
+        let mut keep_alive = version != "HTTP/1.0";
 
         for line in lines {
             if line.is_empty() {
@@ -519,54 +1271,225 @@ impl<C: Connection> Client<C> {
             if name.eq_ignore_ascii_case("Content-Length") {
                 content_length = value.parse::<usize>().ok();
             }
-
-            response_headers
-                .push(Header {
-                    name: String::try_from(name).map_err(|_| Error::ProtocolError)?,
-                    value: String::try_from(value).map_err(|_| Error::ProtocolError)?,
-                })
-                .map_err(|_| Error::ProtocolError)?;
-        }
-
-        let mut body = Vec::from_slice(body_data).map_err(|_| Error::ProtocolError)?;
-        if let Some(len) = content_length {
-            while body.len() < len {
-                if body.len() == body.capacity() {
-                    // Body is larger than our buffer.
-                    return Err(Error::ProtocolError);
+            if name.eq_ignore_ascii_case("Connection") {
+                for option in value.split(',').map(str::trim) {
+                    if option.eq_ignore_ascii_case("close") {
+                        keep_alive = false;
+                    } else if option.eq_ignore_ascii_case("keep-alive") {
+                        keep_alive = true;
+                    }
                 }
+            }
+            if name.eq_ignore_ascii_case("Transfer-Encoding") {
+                // Chunked is always the final coding when present
+                chunked = value
+                    .rsplit(',')
+                    .next()
+                    .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"));
+            }
 
-                // Read more data into a temporary buffer, then extend our body vec.
-                let mut temp_buf = [0; 256];
-                let remaining_len = len - body.len();
-                let read_len = core::cmp::min(remaining_len, temp_buf.len());
-                if read_len == 0 {
-                    break;
+            let Ok(name) = String::try_from(name) else {
+                headers_truncated = true;
+                continue;
+            };
+            let value = match String::try_from(value) {
+                Ok(value) => value,
+                Err(_) => {
+                    headers_truncated = true;
+                    truncate_value(value)
                 }
+            };
+            let header = Header { name, value };
 
-                match self.connection.read(&mut temp_buf[..read_len]) {
-                    Ok(0) => return Err(Error::ConnectionClosed), // Prematurely closed
-                    Ok(n) => {
-                        if body.extend_from_slice(&temp_buf[..n]).is_err() {
-                            return Err(Error::ProtocolError); // Should not happen given capacity check
-                        }
+            if response_headers.is_full() {
+                headers_truncated = true;
+                if !self.is_critical(&header.name) {
+                    continue;
+                }
+                // Make room by evicting the most recent non-critical header
+                match response_headers
+                    .iter()
+                    .rposition(|h| !self.is_critical(&h.name))
+                {
+                    Some(pos) => {
+                        response_headers.remove(pos);
                     }
-                    Err(_) => return Err(Error::ReadError),
+                    None => continue,
                 }
             }
-
-            // Truncate to ensure we have exactly `len` bytes.
-            if body.len() > len {
-                body.truncate(len);
-            }
+            // Cannot fail: capacity was checked above
+            let _ = response_headers.push(header);
         }
 
-        Ok(Response {
+        Ok(ResponseHead {
             status_code,
             headers: response_headers,
-            body,
+            headers_truncated,
+            content_length,
+            chunked,
+            keep_alive,
+            header_bytes: header_end_pos + 4,
+            buffered: total_read,
         })
     }
+
+    /// Send a request conditionally on the resource having changed.
+    ///
+    /// When `etag` holds a value it is sent as `If-None-Match`, in addition to
+    /// the request's own headers. A `304 Not Modified` answer is returned as
+    /// [`Conditional::NotModified`] instead of an empty-bodied response, so
+    /// polling an unchanged resource (a config document, an OTA manifest)
+    /// costs only the headers.
+    ///
+    /// On a `200 OK`, `etag` is replaced with the response's `ETag`, or
+    /// cleared if the server sent none. Other statuses leave it untouched and
+    /// are returned as [`Conditional::Modified`] for the caller to inspect.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`request`](Self::request). [`Error::WriteError`] is also
+    /// returned if the request already carries the maximum number of headers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use libiot::network::application::http::client::{
+    ///     Client, Conditional, ETag, Method, Request,
+    /// };
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection;
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, _buf: &[u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    ///
+    /// let mut client = Client::new(MockConnection);
+    /// let mut etag: Option<ETag> = None;
+    /// let request = Request {
+    ///     method: Method::Get,
+    ///     path: "/config.json",
+    ///     headers: heapless::Vec::new(),
+    ///     body: None,
+    /// };
+    ///
+    /// match client.request_conditional(&request, &mut etag) {
+    ///     Ok(Conditional::NotModified) => { /* keep the current config */ }
+    ///     Ok(Conditional::Modified(response)) => { /* apply response.body */ }
+    ///     Err(_) => { /* retry later */ }
+    /// }
+    /// ```
+    pub fn request_conditional(
+        &mut self,
+        request: &Request,
+        etag: &mut Option<ETag>,
+    ) -> Result<Conditional, Error> {
+        let response = match etag.as_deref() {
+            Some(tag) => {
+                let mut headers = request.headers.clone();
+                headers
+                    .push(Header::new("If-None-Match", tag)?)
+                    .map_err(|_| Error::WriteError)?;
+                self.request(&Request {
+                    method: request.method,
+                    path: request.path,
+                    headers,
+                    body: request.body,
+                })?
+            }
+            None => self.request(request)?,
+        };
+
+        match response.status() {
+            StatusCode::NOT_MODIFIED => Ok(Conditional::NotModified),
+            StatusCode::OK => {
+                *etag = response.etag().and_then(|tag| String::try_from(tag).ok());
+                Ok(Conditional::Modified(response))
+            }
+            _ => Ok(Conditional::Modified(response)),
+        }
+    }
+
+    /// Check whether a header name is in the configured critical set.
+    fn is_critical(&self, name: &str) -> bool {
+        self.critical_headers
+            .iter()
+            .any(|critical| critical.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Value of the first header named `name`, ignoring ASCII case.
+pub(super) fn find_header<'a>(headers: &'a [Header], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
+}
+
+/// The path to re-issue `request` at after a redirect response, if it can be followed.
+///
+/// See [`Client::with_max_redirects`] for which locations qualify.
+fn redirect_location(
+    status_code: u16,
+    headers: &[Header],
+    request: &Request,
+) -> Option<String<MAX_HEADER_VALUE_LEN>> {
+    if !matches!(status_code, 301 | 302 | 303 | 307 | 308) {
+        return None;
+    }
+    let location = find_header(headers, "Location")?;
+
+    let absolute = location
+        .strip_prefix("http://")
+        .or_else(|| location.strip_prefix("https://"))
+        .or_else(|| location.strip_prefix("//"));
+    let Some(rest) = absolute else {
+        return location
+            .starts_with('/')
+            .then(|| String::try_from(location).ok())?;
+    };
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    if !find_header(&request.headers, "Host")?.eq_ignore_ascii_case(authority) {
+        return None;
+    }
+    let mut target = String::new();
+    if !path.starts_with('/') {
+        target.push('/').ok()?;
+    }
+    target.push_str(path).ok()?;
+    Some(target)
+}
+
+/// Method and body of the request that follows a redirect with `status_code`.
+fn redirected_method(
+    status_code: u16,
+    method: Method,
+    body: Option<&[u8]>,
+) -> (Method, Option<&[u8]>) {
+    match status_code {
+        303 if method.as_str() != "HEAD" => (Method::Get, None),
+        _ => (method, body),
+    }
+}
+
+/// Cut a header value down to `MAX_HEADER_VALUE_LEN` bytes on a character boundary.
+fn truncate_value(value: &str) -> String<MAX_HEADER_VALUE_LEN> {
+    let mut end = MAX_HEADER_VALUE_LEN;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    // Cannot fail: `end` is within capacity
+    String::try_from(&value[..end]).unwrap_or_default()
 }
 
 /// Find the first occurrence of a slice in another slice and return its starting position.
@@ -55,3 +55,16 @@
 /// Contains the main [`Client`](client::Client) struct and all related types
 /// for making HTTP requests and handling responses.
 pub mod client;
+
+pub(crate) mod base64;
+
+/// Percent-encoded query strings for request paths.
+///
+/// Contains [`QueryBuilder`](query::QueryBuilder).
+pub mod query;
+
+/// Incremental reading of response bodies.
+///
+/// Contains [`StreamingResponse`](stream::StreamingResponse), returned by
+/// [`Client::request_streaming`](client::Client::request_streaming).
+pub mod stream;
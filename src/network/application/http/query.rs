@@ -0,0 +1,104 @@
+//! Request paths with percent-encoded query strings.
+//!
+//! [`QueryBuilder`] appends `key=value` pairs to a path, percent-encoding
+//! every byte outside the RFC 3986 unreserved set (`A-Z a-z 0-9 - . _ ~`).
+//! Spaces become `%20` and multibyte UTF-8 characters are encoded byte by
+//! byte, so the result is plain ASCII suitable for [`Request::path`].
+//!
+//! [`Request::path`]: super::client::Request::path
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::network::application::http::client::{Method, Request};
+//! use libiot::network::application::http::query::QueryBuilder;
+//!
+//! let mut query = QueryBuilder::<128>::new("/api/readings").unwrap();
+//! query.append("sensor", "temp 1").unwrap();
+//! query.append("since", "2024-01-01T00:00").unwrap();
+//! assert_eq!(
+//!     query.as_str(),
+//!     "/api/readings?sensor=temp%201&since=2024-01-01T00%3A00"
+//! );
+//!
+//! let request = Request {
+//!     method: Method::Get,
+//!     path: query.as_str(),
+//!     headers: heapless::Vec::new(),
+//!     body: None,
+//! };
+//! ```
+
+use crate::network::error::Error;
+use heapless::String;
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+/// A request path being extended with query parameters.
+///
+/// `N` is the capacity of the whole path, query included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryBuilder<const N: usize> {
+    path: String<N>,
+}
+
+impl<const N: usize> QueryBuilder<N> {
+    /// Start from `path`, which is copied verbatim.
+    ///
+    /// If `path` already has a query, appended pairs extend it.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::WriteError`] if `path` is longer than `N` bytes.
+    pub fn new(path: &str) -> Result<Self, Error> {
+        Ok(Self {
+            path: String::try_from(path).map_err(|_| Error::WriteError)?,
+        })
+    }
+
+    /// Append `key=value`, percent-encoding both.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::WriteError`] if the encoded pair does not fit; the builder is
+    /// then left as it was before the call.
+    pub fn append(&mut self, key: &str, value: &str) -> Result<&mut Self, Error> {
+        let len = self.path.len();
+        let separator = if self.path.contains('?') { '&' } else { '?' };
+        let result = self
+            .path
+            .push(separator)
+            .and_then(|_| encode_into(key, &mut self.path))
+            .and_then(|_| self.path.push('='))
+            .and_then(|_| encode_into(value, &mut self.path));
+        if result.is_err() {
+            self.path.truncate(len);
+            return Err(Error::WriteError);
+        }
+        Ok(self)
+    }
+
+    /// The path with its query string.
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+
+    /// Take the path with its query string.
+    pub fn into_string(self) -> String<N> {
+        self.path
+    }
+}
+
+/// Append `input` to `out`, percent-encoding all but unreserved characters.
+fn encode_into<const N: usize>(input: &str, out: &mut String<N>) -> Result<(), ()> {
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char)?;
+        } else {
+            out.push('%')?;
+            out.push(HEX[(byte >> 4) as usize] as char)?;
+            out.push(HEX[(byte & 0x0F) as usize] as char)?;
+        }
+    }
+    Ok(())
+}
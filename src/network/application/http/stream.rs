@@ -0,0 +1,290 @@
+//! Incremental reading of HTTP response bodies.
+//!
+//! [`StreamingResponse`] is returned by
+//! [`Client::request_streaming`](super::client::Client::request_streaming)
+//! once the status line and headers have been parsed. The body is then read
+//! on demand into caller-provided buffers, so its size is not limited by any
+//! buffer inside the client.
+//!
+//! Three framings are supported:
+//!
+//! - `Content-Length`: exactly that many bytes are returned.
+//! - `Transfer-Encoding: chunked`: chunk-size lines and chunk delimiters are
+//!   stripped, and trailers after the final chunk are consumed and ignored.
+//! - Neither: the body runs until the server closes the connection.
+
+use super::client::{Header, MAX_HEADERS, ResponseHead, StatusCode, find_header};
+use crate::network::Connection;
+use crate::network::error::Error;
+use core::ops::Range;
+use heapless::Vec;
+
+/// Longest chunk-size line prefix that is kept for parsing.
+///
+/// Only the hex size and any whitespace before a `;` must fit; chunk
+/// extensions after the `;` are skipped without being stored.
+const MAX_CHUNK_SIZE_LINE: usize = 32;
+
+/// How the end of the body is recognised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Framing {
+    /// This many body bytes remain.
+    Length(usize),
+    /// Chunked transfer coding.
+    Chunked(ChunkState),
+    /// The body ends when the connection is closed.
+    UntilClose,
+    /// The body has been read completely.
+    Done,
+}
+
+/// Position within a chunked body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ChunkState {
+    /// Expecting a chunk-size line.
+    Size,
+    /// This many bytes of the current chunk remain.
+    Data(usize),
+    /// Expecting the CRLF that closes a chunk's data.
+    DataEnd,
+    /// The zero-length chunk was read; trailers follow.
+    Trailers,
+}
+
+/// An HTTP response whose body is read incrementally.
+///
+/// The status and headers are available as fields. The body is read with
+/// [`read_body_chunk`](Self::read_body_chunk) until it returns `Ok(0)`. The
+/// response borrows the client's connection, so no other request can be
+/// made until it is dropped. The client can only send another request over
+/// the connection once the body has been read in full; see
+/// [`Client::is_reusable`](super::client::Client::is_reusable).
+pub struct StreamingResponse<'a, C: Connection> {
+    /// HTTP status code (e.g., 200, 404, 500).
+    pub status_code: u16,
+    /// Response headers sent by the server.
+    pub headers: Vec<Header, MAX_HEADERS>,
+    /// Set when not every header could be stored as received.
+    ///
+    /// See [`Response::headers_truncated`](super::client::Response::headers_truncated).
+    pub headers_truncated: bool,
+    connection: &'a mut C,
+    // The client's flag for whether it can send another request
+    reusable: &'a mut bool,
+    keep_alive: bool,
+    // Bytes read from the connection but not yet consumed
+    buffer: [u8; 2048],
+    pending: Range<usize>,
+    framing: Framing,
+}
+
+impl<'a, C: Connection> StreamingResponse<'a, C> {
+    /// Read the body that follows `head`, whose first bytes are in `buffer`.
+    ///
+    /// `reusable` is set once the body has been read completely, to whether
+    /// the connection can carry another request.
+    pub(super) fn new(
+        head: ResponseHead,
+        connection: &'a mut C,
+        reusable: &'a mut bool,
+        buffer: [u8; 2048],
+        framing: Framing,
+    ) -> Self {
+        let mut response = Self {
+            status_code: head.status_code,
+            headers: head.headers,
+            headers_truncated: head.headers_truncated,
+            connection,
+            reusable,
+            // A body delimited by closing the connection leaves nothing to reuse
+            keep_alive: head.keep_alive && framing != Framing::UntilClose,
+            buffer,
+            pending: head.header_bytes..head.buffered,
+            framing,
+        };
+        if framing == Framing::Done {
+            response.finish();
+        }
+        response
+    }
+
+    /// The status code as a [`StatusCode`].
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from(self.status_code)
+    }
+
+    /// Look up a header value by name, ignoring ASCII case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Read the next body bytes into `buf`, returning how many were written.
+    ///
+    /// Returns `Ok(0)` once the body has been read completely (and for an
+    /// empty `buf`). A call may return fewer bytes than fit in `buf` even
+    /// when more of the body follows.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ConnectionClosed`] - The connection closed before the
+    ///   declared length or the final chunk was received
+    /// * [`Error::ReadError`] - Failed to read from the connection
+    /// * [`Error::ProtocolError`] - Malformed chunk-size line or chunk delimiter
+    pub fn read_body_chunk(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self.framing {
+                Framing::Done => return Ok(0),
+                Framing::Length(0) => {
+                    self.finish();
+                    return Ok(0);
+                }
+                Framing::Length(remaining) => {
+                    let n = self.read_data(buf, remaining)?;
+                    self.framing = Framing::Length(remaining - n);
+                    return Ok(n);
+                }
+                Framing::UntilClose => {
+                    let n = self.read_raw(buf)?;
+                    if n == 0 {
+                        self.finish();
+                    }
+                    return Ok(n);
+                }
+                Framing::Chunked(ChunkState::Size) => {
+                    let size = self.read_chunk_size()?;
+                    self.framing = Framing::Chunked(match size {
+                        0 => ChunkState::Trailers,
+                        size => ChunkState::Data(size),
+                    });
+                }
+                Framing::Chunked(ChunkState::Data(remaining)) => {
+                    let n = self.read_data(buf, remaining)?;
+                    self.framing = Framing::Chunked(match remaining - n {
+                        0 => ChunkState::DataEnd,
+                        remaining => ChunkState::Data(remaining),
+                    });
+                    return Ok(n);
+                }
+                Framing::Chunked(ChunkState::DataEnd) => {
+                    if self.skip_line()? != 0 {
+                        return Err(Error::ProtocolError);
+                    }
+                    self.framing = Framing::Chunked(ChunkState::Size);
+                }
+                Framing::Chunked(ChunkState::Trailers) => {
+                    while self.skip_line()? != 0 {}
+                    self.finish();
+                }
+            }
+        }
+    }
+
+    /// Whether the whole body has been read.
+    pub fn is_complete(&self) -> bool {
+        self.framing == Framing::Done
+    }
+
+    /// Mark the body as read, releasing the connection for the next request.
+    fn finish(&mut self) {
+        self.framing = Framing::Done;
+        *self.reusable = self.keep_alive;
+    }
+
+    /// Read up to `remaining` bytes of data that must not be cut short.
+    fn read_data(&mut self, buf: &mut [u8], remaining: usize) -> Result<usize, Error> {
+        let len = buf.len().min(remaining);
+        match self.read_raw(&mut buf[..len])? {
+            0 => Err(Error::ConnectionClosed),
+            n => Ok(n),
+        }
+    }
+
+    /// Read from the pending bytes, or from the connection once they are used up.
+    fn read_raw(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if !self.pending.is_empty() {
+            let n = buf.len().min(self.pending.len());
+            let start = self.pending.start;
+            buf[..n].copy_from_slice(&self.buffer[start..start + n]);
+            self.pending.start += n;
+            return Ok(n);
+        }
+        self.connection.read(buf).map_err(|_| Error::ReadError)
+    }
+
+    /// Read one byte, refilling the pending bytes from the connection if needed.
+    fn read_byte(&mut self) -> Result<u8, Error> {
+        if self.pending.is_empty() {
+            match self.connection.read(&mut self.buffer) {
+                Ok(0) => return Err(Error::ConnectionClosed),
+                Ok(n) => self.pending = 0..n,
+                Err(_) => return Err(Error::ReadError),
+            }
+        }
+        let byte = self.buffer[self.pending.start];
+        self.pending.start += 1;
+        Ok(byte)
+    }
+
+    /// Read a line up to `\n`, keeping its first bytes in `line`.
+    ///
+    /// A trailing `\r` is not stored. Returns the full length of the line
+    /// without its terminator, which may exceed what `line` holds.
+    fn read_line<const N: usize>(&mut self, line: &mut Vec<u8, N>) -> Result<usize, Error> {
+        let mut len = 0;
+        let mut cr = false;
+        loop {
+            let byte = self.read_byte()?;
+            if byte == b'\n' {
+                return Ok(len);
+            }
+            if cr {
+                // A `\r` inside the line
+                len += 1;
+                let _ = line.push(b'\r');
+            }
+            cr = byte == b'\r';
+            if !cr {
+                len += 1;
+                let _ = line.push(byte);
+            }
+        }
+    }
+
+    /// Consume a line, returning its length without the terminator.
+    fn skip_line(&mut self) -> Result<usize, Error> {
+        self.read_line::<0>(&mut Vec::new())
+    }
+
+    /// Read a chunk-size line and parse its hexadecimal size.
+    fn read_chunk_size(&mut self) -> Result<usize, Error> {
+        let mut line: Vec<u8, MAX_CHUNK_SIZE_LINE> = Vec::new();
+        let len = self.read_line(&mut line)?;
+        let size = match line.iter().position(|&b| b == b';') {
+            Some(end) => &line[..end],
+            // Without a `;` the whole line is the size, so it must fit
+            None if len > line.len() => return Err(Error::ProtocolError),
+            None => &line[..],
+        };
+        parse_chunk_size(size)
+    }
+}
+
+/// Parse a chunk size: hex digits, optionally followed by spaces or tabs.
+fn parse_chunk_size(field: &[u8]) -> Result<usize, Error> {
+    let digits = field
+        .iter()
+        .rposition(|b| !matches!(b, b' ' | b'\t'))
+        .map_or(&field[..0], |last| &field[..=last]);
+    if digits.is_empty() {
+        return Err(Error::ProtocolError);
+    }
+    digits.iter().try_fold(0usize, |size, &b| {
+        let digit = (b as char).to_digit(16).ok_or(Error::ProtocolError)?;
+        size.checked_mul(16)
+            .and_then(|size| size.checked_add(digit as usize))
+            .ok_or(Error::ProtocolError)
+    })
+}
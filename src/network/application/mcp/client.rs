@@ -2,8 +2,30 @@
 
 use super::*;
 use crate::network::{Connection, error::Error as NetworkError};
+use crate::system::clock::MonotonicClock;
+use core::fmt::Write;
+use core::ops::Range;
 use heapless::Vec;
 
+/// How messages are delimited on the connection
+///
+/// Bytes received after the end of a message are kept for the next call to
+/// [`McpClient::process_message`], so several messages may arrive in one read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// A message is a JSON object, ending at the `}` that closes it.
+    ///
+    /// Bytes before the opening `{`, such as stray closing braces, are
+    /// skipped. This is the default.
+    #[default]
+    Object,
+    /// A message is one line of JSON, ending at `\n` (newline-delimited JSON).
+    ///
+    /// Exactly the bytes of the line are parsed, so malformed input is
+    /// confined to its own line. A trailing `\r` and empty lines are ignored.
+    Lines,
+}
+
 /// MCP Client that works over any connection type
 pub struct McpClient<C, H>
 where
@@ -13,6 +35,13 @@ where
     connection: C,
     registry: FunctionRegistry<H>,
     buffer: Vec<u8, 1024>,
+    // Position of the current message in `buffer`
+    frame: Range<usize>,
+    // Bytes at the start of `buffer` used up by the current message
+    consumed: usize,
+    framing: Framing,
+    handshake: Option<Capabilities>,
+    initialized: bool,
 }
 
 impl<C, H> McpClient<C, H>
@@ -26,51 +55,128 @@ where
             connection,
             registry,
             buffer: Vec::new(),
+            frame: 0..0,
+            consumed: 0,
+            framing: Framing::Object,
+            handshake: None,
+            initialized: false,
         }
     }
 
+    /// Delimit messages with `framing` instead of [`Framing::Object`]
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Answer `initialize` messages with the protocol version and `capabilities`
+    ///
+    /// When enabled, a message calling the reserved [`INITIALIZE_FUNCTION`] is
+    /// answered with an [`InitializeResponse`] instead of being dispatched to
+    /// the registry. Function calls are still accepted without a prior
+    /// handshake, so hosts that skip it keep working.
+    pub fn with_handshake(mut self, capabilities: Capabilities) -> Self {
+        self.handshake = Some(capabilities);
+        self
+    }
+
+    /// Check whether the host has completed the `initialize` handshake
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
     /// Process incoming MCP messages and return responses
+    ///
+    /// A call to the reserved [`TOOLS_LIST_FUNCTION`] is answered with the
+    /// enabled functions and their descriptions, e.g.
+    /// `{"status":"ok","tools":[{"name":"ping","description":"..."}]}`.
+    /// When the listing does not fit in one response it is split into pages:
+    /// the response then carries a `"nextCursor"`, which the host passes back
+    /// as the `arguments` of its next `tools/list` call to get the rest.
+    ///
+    /// JSON-RPC 2.0 requests, such as
+    /// `{"jsonrpc":"2.0","id":1,"method":"ping","params":{}}`, are answered
+    /// with a JSON-RPC response carrying the same `id`; see the
+    /// [`jsonrpc`](super::jsonrpc) module. There the `tools/list` cursor is
+    /// passed as `"params":{"cursor":"..."}`.
     pub fn process_message(&mut self) -> Result<(), NetworkError> {
-        // Clear buffer for new message
-        self.buffer.clear();
+        if self.receive()? {
+            self.answer(None)?;
+        }
+        Ok(())
+    }
+
+    /// Process incoming MCP messages, bounding handler execution time
+    ///
+    /// Like [`process_message`](Self::process_message), but the handler runs
+    /// against a fresh [`Deadline`] from `watchdog` and a call that exceeds it
+    /// is answered with a `"timeout"` error. The limit is only enforced at
+    /// handler checkpoints and on return; see the [`watchdog`](super::watchdog)
+    /// module.
+    pub fn process_message_with_watchdog<K: MonotonicClock>(
+        &mut self,
+        watchdog: &Watchdog<K>,
+    ) -> Result<(), NetworkError> {
+        if self.receive()? {
+            self.answer(Some(&watchdog.start()))?;
+        }
+        Ok(())
+    }
+
+    /// Read the next message into the buffer
+    ///
+    /// Returns `true` if a message was received. A message still arriving
+    /// when the connection has no more data stays buffered for the next call.
+    fn receive(&mut self) -> Result<bool, NetworkError> {
+        // Drop the previous message, keeping the bytes received after it
+        let len = self.buffer.len();
+        self.buffer.copy_within(self.consumed..len, 0);
+        self.buffer.truncate(len - self.consumed);
+        self.consumed = 0;
+        self.frame = 0..0;
 
-        // Read incoming data
         let mut temp_buf = [0u8; 256];
         loop {
-            match self.connection.read(&mut temp_buf) {
-                Ok(0) => break, // No more data
-                Ok(n) => {
-                    if self.buffer.extend_from_slice(&temp_buf[..n]).is_err() {
-                        return Err(NetworkError::ReadError);
-                    }
+            if let Some((frame, consumed)) = self.next_frame() {
+                self.frame = frame;
+                self.consumed = consumed;
+                return Ok(true);
+            }
 
-                    // Check if we have a complete JSON message
-                    if self.has_complete_message() {
-                        break;
-                    }
-                }
-                Err(_) => return Err(NetworkError::ReadError),
+            let n = self
+                .connection
+                .read(&mut temp_buf)
+                .map_err(|_| NetworkError::ReadError)?;
+            if n == 0 {
+                return Ok(false);
+            }
+            if self.buffer.extend_from_slice(&temp_buf[..n]).is_err() {
+                // A message too long for the buffer can never be answered
+                self.buffer.clear();
+                return Err(NetworkError::ReadError);
             }
         }
+    }
 
-        if self.buffer.is_empty() {
-            return Ok(());
+    /// Find the first complete message in the buffer
+    ///
+    /// Returns its position and the number of bytes it uses up, including
+    /// any bytes skipped before it and its delimiter.
+    fn next_frame(&self) -> Option<(Range<usize>, usize)> {
+        match self.framing {
+            Framing::Object => self.next_object(),
+            Framing::Lines => self.next_line(),
         }
-
-        // Parse and handle the message
-        let response = self.handle_message();
-
-        // Send response back
-        self.send_response(&response)
     }
 
-    /// Check if buffer contains a complete JSON message
-    fn has_complete_message(&self) -> bool {
+    /// Find the first complete JSON object in the buffer
+    fn next_object(&self) -> Option<(Range<usize>, usize)> {
+        let mut start = 0;
         let mut brace_count = 0;
         let mut in_string = false;
         let mut escape_next = false;
 
-        for &byte in &self.buffer {
+        for (i, &byte) in self.buffer.iter().enumerate() {
             if escape_next {
                 escape_next = false;
                 continue;
@@ -78,29 +184,203 @@ where
 
             match byte {
                 b'\\' if in_string => escape_next = true,
-                b'"' => in_string = !in_string,
-                b'{' if !in_string => brace_count += 1,
-                b'}' if !in_string => {
-                    if brace_count > 0 {
-                        brace_count -= 1;
-                        if brace_count == 0 {
-                            return true;
-                        }
+                b'"' if brace_count > 0 => in_string = !in_string,
+                b'{' if !in_string => {
+                    if brace_count == 0 {
+                        start = i;
                     }
-                    // If brace_count is 0 and we encounter '}', ignore it
-                    // as it indicates malformed JSON (extra closing brace)
+                    brace_count += 1;
                 }
+                b'}' if !in_string && brace_count > 0 => {
+                    brace_count -= 1;
+                    if brace_count == 0 {
+                        return Some((start..i + 1, i + 1));
+                    }
+                }
+                // A '}' with no open brace is malformed JSON (an extra
+                // closing brace); ignore it
                 _ => {}
             }
         }
 
-        false
+        None
+    }
+
+    /// Find the first non-empty line in the buffer
+    fn next_line(&self) -> Option<(Range<usize>, usize)> {
+        let mut start = 0;
+        while let Some(len) = self.buffer[start..].iter().position(|&b| b == b'\n') {
+            let end = start + len;
+            let line = &self.buffer[start..end];
+            if line.iter().any(|b| !b.is_ascii_whitespace()) {
+                let end = if line.ends_with(b"\r") { end - 1 } else { end };
+                return Some((start..end, start + len + 1));
+            }
+            start = end + 1;
+        }
+        None
+    }
+
+    /// The current message
+    fn message(&self) -> &[u8] {
+        &self.buffer[self.frame.clone()]
+    }
+
+    /// Answer the buffered message, running a handler within `deadline` if given
+    fn answer(&mut self, deadline: Option<&Deadline<'_>>) -> Result<(), NetworkError> {
+        if core::str::from_utf8(self.message()).is_ok_and(jsonrpc::is_jsonrpc) {
+            return self.answer_jsonrpc(deadline);
+        }
+
+        if let Some(cursor) = self
+            .buffered_message()
+            .filter(|message| message.function == TOOLS_LIST_FUNCTION)
+            .map(|message| parse_cursor(message.arguments))
+        {
+            return match cursor {
+                Some(skip) => self.send_tool_list(skip, None),
+                None => self.send_response(&McpResponse {
+                    status: ResponseStatus::InvalidArgs,
+                    error: Some(heapless::String::try_from("Invalid cursor").unwrap_or_default()),
+                    result: None,
+                }),
+            };
+        }
+
+        if let Some(capabilities) = self.handshake {
+            if self.is_initialize_message() {
+                self.initialized = true;
+                return self.send_response(&InitializeResponse {
+                    status: ResponseStatus::Ok,
+                    protocol_version: MCP_PROTOCOL_VERSION,
+                    capabilities,
+                });
+            }
+        }
+
+        let response = self.handle_message(deadline);
+        self.send_response(&response)
+    }
+
+    /// Answer the buffered JSON-RPC request
+    fn answer_jsonrpc(&mut self, deadline: Option<&Deadline<'_>>) -> Result<(), NetworkError> {
+        let text = core::str::from_utf8(&self.buffer[self.frame.clone()]).unwrap_or_default();
+        let request = match jsonrpc::Request::parse(text) {
+            Ok(request) => request,
+            Err(error) => {
+                return self.send_response(&jsonrpc::Response::<()>::error(None, error));
+            }
+        };
+        // Keep the id past the borrow of the buffer
+        let mut id = heapless::String::<{ jsonrpc::MAX_ID_LEN }>::new();
+        if let Some(raw) = request.id {
+            if id.push_str(raw).is_err() {
+                let error = jsonrpc::RpcError::INVALID_REQUEST;
+                return self.send_response(&jsonrpc::Response::<()>::error(None, error));
+            }
+        }
+        let notification = request.id.is_none();
+
+        match request.method {
+            TOOLS_LIST_FUNCTION if !notification => {
+                let cursor = match request.param("cursor") {
+                    Some(cursor) => cursor
+                        .strip_prefix('"')
+                        .and_then(|cursor| cursor.strip_suffix('"'))
+                        .and_then(parse_cursor),
+                    None => Some(0),
+                };
+                match cursor {
+                    Some(skip) => self.send_tool_list(skip, Some(&id)),
+                    None => self.send_response(&jsonrpc::Response::<()>::error(
+                        Some(&id),
+                        jsonrpc::RpcError::INVALID_PARAMS,
+                    )),
+                }
+            }
+            INITIALIZE_FUNCTION if self.handshake.is_some() => {
+                self.initialized = true;
+                if notification {
+                    return Ok(());
+                }
+                let result = InitializeResult {
+                    protocol_version: MCP_PROTOCOL_VERSION,
+                    capabilities: self.handshake.unwrap_or_default(),
+                };
+                self.send_response(&jsonrpc::Response::result(Some(&id), result))
+            }
+            method => {
+                let response = match deadline {
+                    Some(deadline) => {
+                        self.registry
+                            .execute_with_deadline(method, request.params, deadline)
+                    }
+                    None => self.registry.execute(method, request.params),
+                };
+                if notification {
+                    return Ok(());
+                }
+                self.send_response(&jsonrpc::Response::for_call(Some(&id), &response))
+            }
+        }
     }
 
-    /// Parse and handle an MCP message
-    fn handle_message(&mut self) -> McpResponse {
+    /// Check if the buffered message is an `initialize` request
+    fn is_initialize_message(&self) -> bool {
+        self.buffered_message()
+            .is_some_and(|message| message.function == INITIALIZE_FUNCTION)
+    }
+
+    /// Parse the buffered message, if it is a well-formed function call
+    fn buffered_message(&self) -> Option<McpMessage<'_>> {
+        let text = core::str::from_utf8(self.message()).ok()?;
+        serde_json_core::from_str::<McpMessage>(text)
+            .ok()
+            .map(|(message, _)| message)
+    }
+
+    /// Send the page of the tool listing that starts after `skip` tools
+    ///
+    /// The page holds as many tools as fit in one response. With an `id` the
+    /// page is sent as the result of that JSON-RPC request.
+    fn send_tool_list(&mut self, skip: usize, id: Option<&str>) -> Result<(), NetworkError> {
+        let total = self.registry.tools().count();
+        let mut take = total.saturating_sub(skip);
+        let mut response_buf = [0u8; 512];
+        let len = loop {
+            let mut next_cursor = heapless::String::new();
+            if skip + take < total {
+                write!(next_cursor, "{}", skip + take).map_err(|_| NetworkError::WriteError)?;
+            }
+            let page = ToolsListResponse {
+                status: id.is_none().then_some(ResponseStatus::Ok),
+                tools: ToolList {
+                    registry: &self.registry,
+                    skip,
+                    take,
+                },
+                next_cursor: (!next_cursor.is_empty()).then_some(next_cursor),
+            };
+            let serialized = match id {
+                Some(id) => serde_json_core::to_slice(
+                    &jsonrpc::Response::result(Some(id), page),
+                    &mut response_buf,
+                ),
+                None => serde_json_core::to_slice(&page, &mut response_buf),
+            };
+            match serialized {
+                Ok(len) => break len,
+                Err(_) if take > 1 => take -= 1,
+                Err(_) => return Err(NetworkError::WriteError),
+            }
+        };
+        self.send_bytes(&response_buf[..len])
+    }
+
+    /// Parse and handle an MCP message, within `deadline` if given
+    fn handle_message(&mut self, deadline: Option<&Deadline<'_>>) -> McpResponse {
         // Try to parse the JSON message
-        let message_str = match core::str::from_utf8(&self.buffer) {
+        let message_str = match core::str::from_utf8(&self.buffer[self.frame.clone()]) {
             Ok(s) => s,
             Err(_) => {
                 return McpResponse {
@@ -115,7 +395,14 @@ where
         match serde_json_core::from_str::<McpMessage>(message_str) {
             Ok((message, _)) => {
                 // Execute the function
-                self.registry.execute(message.function, message.arguments)
+                match deadline {
+                    Some(deadline) => self.registry.execute_with_deadline(
+                        message.function,
+                        message.arguments,
+                        deadline,
+                    ),
+                    None => self.registry.execute(message.function, message.arguments),
+                }
             }
             Err(_) => McpResponse {
                 status: ResponseStatus::Error,
@@ -126,24 +413,25 @@ where
     }
 
     /// Send response back over the connection
-    fn send_response(&mut self, response: &McpResponse) -> Result<(), NetworkError> {
+    fn send_response<T: serde::Serialize>(&mut self, response: &T) -> Result<(), NetworkError> {
         // Serialize response to JSON
         let mut response_buf = [0u8; 512];
         match serde_json_core::to_slice(response, &mut response_buf) {
-            Ok(len) => {
-                // Send the response
-                self.connection
-                    .write(&response_buf[..len])
-                    .map_err(|_| NetworkError::WriteError)?;
-                self.connection
-                    .flush()
-                    .map_err(|_| NetworkError::WriteError)?;
-                Ok(())
-            }
+            Ok(len) => self.send_bytes(&response_buf[..len]),
             Err(_) => Err(NetworkError::WriteError),
         }
     }
 
+    /// Write a serialized response and flush it
+    fn send_bytes(&mut self, response: &[u8]) -> Result<(), NetworkError> {
+        self.connection
+            .write(response)
+            .map_err(|_| NetworkError::WriteError)?;
+        self.connection
+            .flush()
+            .map_err(|_| NetworkError::WriteError)
+    }
+
     /// Get a mutable reference to the function registry
     pub fn registry_mut(&mut self) -> &mut FunctionRegistry<H> {
         &mut self.registry
@@ -159,3 +447,57 @@ where
         &mut self.connection
     }
 }
+
+/// Position in the tool listing from a `tools/list` cursor; empty means the start
+fn parse_cursor(cursor: &str) -> Option<usize> {
+    match cursor.trim() {
+        "" => Some(0),
+        cursor => cursor.parse().ok(),
+    }
+}
+
+/// Result of a JSON-RPC `initialize` request
+#[derive(Serialize)]
+struct InitializeResult<'a> {
+    #[serde(rename = "protocolVersion")]
+    protocol_version: &'a str,
+    capabilities: Capabilities,
+}
+
+/// One page of the answer to a `tools/list` message
+#[derive(Serialize)]
+#[serde(bound(serialize = "H: McpHandler"))]
+struct ToolsListResponse<'a, H> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<ResponseStatus>,
+    tools: ToolList<'a, H>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<heapless::String<10>>,
+}
+
+/// The enabled tools of a registry from `skip`, at most `take` of them
+struct ToolList<'a, H> {
+    registry: &'a FunctionRegistry<H>,
+    skip: usize,
+    take: usize,
+}
+
+impl<H: McpHandler> Serialize for ToolList<'_, H> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(None)?;
+        for (name, description) in self.registry.tools().skip(self.skip).take(self.take) {
+            seq.serialize_element(&Tool { name, description })?;
+        }
+        seq.end()
+    }
+}
+
+/// Listing entry of one tool
+#[derive(Serialize)]
+struct Tool<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    description: &'a str,
+}
@@ -1,7 +1,7 @@
 //! GPIO pin control handler for MCP
 
-use super::super::{HandlerResult, McpError, McpHandler};
-use heapless::{FnvIndexMap, String};
+use super::super::{HandlerResult, HandlerValue, McpError, McpHandler};
+use heapless::FnvIndexMap;
 use serde::{Deserialize, Serialize};
 
 /// GPIO pin control handler
@@ -31,6 +31,10 @@ impl GpioHandler {
 }
 
 impl McpHandler for GpioHandler {
+    fn description(&self) -> &str {
+        r#"Set a GPIO pin with {"pin","state"} or read it with {"pin"}"#
+    }
+
     fn call(&mut self, args: &str) -> HandlerResult {
         // Parse GPIO arguments
         let (gpio_args, _): (GpioArgs, _) =
@@ -48,17 +52,7 @@ impl McpHandler for GpioHandler {
                     state: new_state,
                 };
 
-                let mut buf = [0u8; 64];
-                let serialized_len = serde_json_core::to_slice(&result, &mut buf)
-                    .map_err(|_| McpError::ExecutionError)?;
-
-                Ok(Some(
-                    String::try_from(
-                        core::str::from_utf8(&buf[..serialized_len])
-                            .map_err(|_| McpError::ExecutionError)?,
-                    )
-                    .map_err(|_| McpError::BufferOverflow)?,
-                ))
+                HandlerValue::json(&result).map(Some)
             }
             None => {
                 // Read GPIO pin state
@@ -73,17 +67,7 @@ impl McpHandler for GpioHandler {
                     state,
                 };
 
-                let mut buf = [0u8; 64];
-                let serialized_len = serde_json_core::to_slice(&result, &mut buf)
-                    .map_err(|_| McpError::ExecutionError)?;
-
-                Ok(Some(
-                    String::try_from(
-                        core::str::from_utf8(&buf[..serialized_len])
-                            .map_err(|_| McpError::ExecutionError)?,
-                    )
-                    .map_err(|_| McpError::BufferOverflow)?,
-                ))
+                HandlerValue::json(&result).map(Some)
             }
         }
     }
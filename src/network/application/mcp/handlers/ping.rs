@@ -1,15 +1,19 @@
 //! Simple ping handler for MCP connectivity testing
 
-use super::super::{HandlerResult, McpError, McpHandler};
+use super::super::{HandlerResult, HandlerValue, McpError, McpHandler};
 use heapless::String;
 
 /// Simple ping handler for connectivity testing
 pub struct PingHandler;
 
 impl McpHandler for PingHandler {
+    fn description(&self) -> &str {
+        "Check that the device is responsive"
+    }
+
     fn call(&mut self, _args: &str) -> HandlerResult {
-        Ok(Some(
+        Ok(Some(HandlerValue::Json(
             String::try_from(r#"{"message":"pong"}"#).map_err(|_| McpError::BufferOverflow)?,
-        ))
+        )))
     }
 }
@@ -1,6 +1,6 @@
 //! System information handler for MCP
 
-use super::super::{HandlerResult, McpError, McpHandler};
+use super::super::{HandlerResult, HandlerValue, McpError, McpHandler};
 use heapless::String;
 use serde::Serialize;
 
@@ -25,6 +25,10 @@ impl SystemInfoHandler {
 }
 
 impl McpHandler for SystemInfoHandler {
+    fn description(&self) -> &str {
+        "Report device identity, uptime and memory usage"
+    }
+
     fn call(&mut self, _args: &str) -> HandlerResult {
         // In real implementation, these would be actual system readings
         let info = SystemInfo {
@@ -33,16 +37,6 @@ impl McpHandler for SystemInfoHandler {
             free_memory: 32768, // Placeholder
         };
 
-        let mut buf = [0u8; 128];
-        let serialized_len =
-            serde_json_core::to_slice(&info, &mut buf).map_err(|_| McpError::ExecutionError)?;
-
-        Ok(Some(
-            String::try_from(
-                core::str::from_utf8(&buf[..serialized_len])
-                    .map_err(|_| McpError::ExecutionError)?,
-            )
-            .map_err(|_| McpError::BufferOverflow)?,
-        ))
+        HandlerValue::json(&info).map(Some)
     }
 }
@@ -1,6 +1,6 @@
 //! Temperature sensor reading handler for MCP
 
-use super::super::{HandlerResult, McpError, McpHandler};
+use super::super::{HandlerResult, HandlerValue, McpError, McpHandler};
 use heapless::String;
 use serde::{Deserialize, Serialize};
 
@@ -40,6 +40,10 @@ impl TemperatureSensorHandler {
 }
 
 impl McpHandler for TemperatureSensorHandler {
+    fn description(&self) -> &str {
+        r#"Read the temperature sensor, optionally {"unit":"fahrenheit"}"#
+    }
+
     fn call(&mut self, args: &str) -> HandlerResult {
         let temp_args: TempArgs = if args.trim().is_empty() {
             TempArgs { unit: None }
@@ -60,16 +64,6 @@ impl McpHandler for TemperatureSensorHandler {
             unit: String::try_from(unit).map_err(|_| McpError::BufferOverflow)?,
         };
 
-        let mut buf = [0u8; 128];
-        let serialized_len =
-            serde_json_core::to_slice(&result, &mut buf).map_err(|_| McpError::ExecutionError)?;
-
-        Ok(Some(
-            String::try_from(
-                core::str::from_utf8(&buf[..serialized_len])
-                    .map_err(|_| McpError::ExecutionError)?,
-            )
-            .map_err(|_| McpError::BufferOverflow)?,
-        ))
+        HandlerValue::json(&result).map(Some)
     }
 }
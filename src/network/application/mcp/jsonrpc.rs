@@ -0,0 +1,370 @@
+//! JSON-RPC 2.0 envelope for MCP messages
+//!
+//! MCP hosts speak JSON-RPC 2.0: a request names a `method`, passes its
+//! arguments as `params` and carries an `id` that the response echoes back
+//! with either a `result` or an `error` object with a numeric code.
+//! [`McpClient`](super::McpClient) recognises such a request by its
+//! `"jsonrpc"` member and routes `method` to the registry, with the raw
+//! `params` text as the handler arguments. Messages without a `"jsonrpc"`
+//! member are still handled in the simple `function`/`arguments` format.
+//!
+//! A request without an `id` is a notification: it is executed, but not
+//! answered. Batches (arrays of requests) are not supported.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::network::application::mcp::jsonrpc::{Request, Response, RpcError};
+//!
+//! let request = Request::parse(r#"{"jsonrpc":"2.0","id":7,"method":"gpio","params":{"pin":13}}"#)
+//!     .unwrap();
+//! assert_eq!(request.id, Some("7"));
+//! assert_eq!(request.method, "gpio");
+//! assert_eq!(request.params, r#"{"pin":13}"#);
+//! assert_eq!(request.param("pin"), Some("13"));
+//!
+//! let response = Response::<()>::error(request.id, RpcError::METHOD_NOT_FOUND);
+//! let mut buf = [0u8; 128];
+//! let len = serde_json_core::to_slice(&response, &mut buf).unwrap();
+//! assert_eq!(
+//!     &buf[..len],
+//!     br#"{"jsonrpc":"2.0","id":7,"error":{"code":-32601,"message":"Method not found"}}"#
+//! );
+//! ```
+
+use super::{HandlerValue, McpResponse, ResponseStatus};
+use serde::Serialize;
+use serde::ser::SerializeStruct;
+
+/// Longest request `id`, as JSON text, that the client echoes back.
+pub const MAX_ID_LEN: usize = 32;
+
+/// A JSON-RPC 2.0 request.
+///
+/// Values are borrowed from the message text without unescaping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Request<'a> {
+    /// The request `id` as JSON text (a number, a quoted string or `null`),
+    /// or `None` for a notification.
+    pub id: Option<&'a str>,
+    /// The method to call, without quotes.
+    pub method: &'a str,
+    /// The `params` object or array as JSON text, empty if absent.
+    pub params: &'a str,
+}
+
+impl<'a> Request<'a> {
+    /// Parse a request object.
+    ///
+    /// # Errors
+    ///
+    /// * [`RpcError::PARSE_ERROR`] - `text` is not a well-formed JSON object
+    /// * [`RpcError::INVALID_REQUEST`] - `jsonrpc` is not `"2.0"`, or `method`,
+    ///   `id` or `params` has the wrong type
+    pub fn parse(text: &'a str) -> Result<Self, RpcError<'static>> {
+        let mut version = None;
+        let mut request = Request {
+            id: None,
+            method: "",
+            params: "",
+        };
+        let mut method = None;
+        for member in Members::new(text).ok_or(RpcError::PARSE_ERROR)? {
+            let (key, value) = member.ok_or(RpcError::PARSE_ERROR)?;
+            match key {
+                "jsonrpc" => version = Some(value),
+                "id" => request.id = Some(value),
+                "method" => method = Some(value),
+                "params" => request.params = value,
+                _ => {}
+            }
+        }
+
+        if version != Some("\"2.0\"") {
+            return Err(RpcError::INVALID_REQUEST);
+        }
+        request.method = method.and_then(unquote).ok_or(RpcError::INVALID_REQUEST)?;
+        let valid_id = |id: &str| {
+            id == "null"
+                || id.starts_with(['"', '-'])
+                || id.starts_with(|c: char| c.is_ascii_digit())
+        };
+        if !request.id.is_none_or(valid_id) {
+            return Err(RpcError::INVALID_REQUEST);
+        }
+        if !(request.params.is_empty() || request.params.starts_with(['{', '['])) {
+            return Err(RpcError::INVALID_REQUEST);
+        }
+        Ok(request)
+    }
+
+    /// A member of the `params` object as JSON text.
+    ///
+    /// String values keep their quotes. Returns `None` if `params` is not an
+    /// object or has no member `name`.
+    pub fn param(&self, name: &str) -> Option<&'a str> {
+        Members::new(self.params)?
+            .map_while(|member| member)
+            .find(|&(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// A JSON-RPC 2.0 error object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RpcError<'a> {
+    /// Numeric error code; the reserved codes are the associated constants.
+    pub code: i32,
+    /// Short description of the error.
+    pub message: &'a str,
+}
+
+impl RpcError<'static> {
+    /// The message is not well-formed JSON.
+    pub const PARSE_ERROR: Self = Self {
+        code: -32700,
+        message: "Parse error",
+    };
+    /// The message is JSON but not a valid request.
+    pub const INVALID_REQUEST: Self = Self {
+        code: -32600,
+        message: "Invalid request",
+    };
+    /// No function is registered under the method name.
+    pub const METHOD_NOT_FOUND: Self = Self {
+        code: -32601,
+        message: "Method not found",
+    };
+    /// The handler rejected its arguments.
+    pub const INVALID_PARAMS: Self = Self {
+        code: -32602,
+        message: "Invalid params",
+    };
+    /// The handler failed.
+    pub const INTERNAL_ERROR: Self = Self {
+        code: -32603,
+        message: "Internal error",
+    };
+}
+
+/// A JSON-RPC 2.0 response carrying either a `result` or an `error`.
+///
+/// Serializes as `{"jsonrpc":"2.0","id":...,"result":...}` or
+/// `{"jsonrpc":"2.0","id":...,"error":{"code":...,"message":...}}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response<'a, T> {
+    /// The request `id` as JSON text, or `None` to send `null` when the
+    /// request's `id` could not be read.
+    pub id: Option<&'a str>,
+    /// The result or error.
+    pub outcome: Result<T, RpcError<'a>>,
+}
+
+impl<'a, T> Response<'a, T> {
+    /// A successful response.
+    pub fn result(id: Option<&'a str>, result: T) -> Self {
+        Self {
+            id,
+            outcome: Ok(result),
+        }
+    }
+
+    /// An error response.
+    pub fn error(id: Option<&'a str>, error: RpcError<'a>) -> Self {
+        Self {
+            id,
+            outcome: Err(error),
+        }
+    }
+}
+
+impl<'a> Response<'a, Option<&'a HandlerValue>> {
+    /// The response to a function call answered by the registry.
+    ///
+    /// A call without a result value answers `null`. Failures map to
+    /// [`RpcError::METHOD_NOT_FOUND`], [`RpcError::INVALID_PARAMS`] or
+    /// [`RpcError::INTERNAL_ERROR`], keeping the registry's error message.
+    pub fn for_call(id: Option<&'a str>, response: &'a McpResponse) -> Self {
+        let error = match response.status {
+            ResponseStatus::Ok => return Self::result(id, response.result.as_ref()),
+            ResponseStatus::NotFound => RpcError::METHOD_NOT_FOUND,
+            ResponseStatus::InvalidArgs => RpcError::INVALID_PARAMS,
+            ResponseStatus::Error => RpcError::INTERNAL_ERROR,
+        };
+        Self::error(
+            id,
+            RpcError {
+                code: error.code,
+                message: response.error.as_deref().unwrap_or(error.message),
+            },
+        )
+    }
+}
+
+impl<T: Serialize> Serialize for Response<'_, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut response = serializer.serialize_struct("Response", 3)?;
+        response.serialize_field("jsonrpc", "2.0")?;
+        response.serialize_field("id", &self.id.map(RawJson))?;
+        match &self.outcome {
+            Ok(result) => response.serialize_field("result", result)?,
+            Err(error) => response.serialize_field("error", error)?,
+        }
+        response.end()
+    }
+}
+
+/// JSON text emitted verbatim, see [`HandlerValue::Json`].
+struct RawJson<'a>(&'a str);
+
+impl Serialize for RawJson<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0.as_bytes())
+    }
+}
+
+/// Check whether `text` is a JSON object with a `"jsonrpc"` member.
+pub fn is_jsonrpc(text: &str) -> bool {
+    Members::new(text).is_some_and(|mut members| {
+        members
+            .by_ref()
+            .map_while(|member| member)
+            .any(|(key, _)| key == "jsonrpc")
+    })
+}
+
+/// The contents of a JSON string, without unescaping.
+fn unquote(value: &str) -> Option<&str> {
+    value.strip_prefix('"')?.strip_suffix('"')
+}
+
+/// The members of a JSON object as raw key and value text.
+///
+/// Yields `None` once, then stops, if the object is malformed.
+struct Members<'a> {
+    text: &'a str,
+    pos: usize,
+    done: bool,
+}
+
+impl<'a> Members<'a> {
+    /// Start reading the object `text`; `None` if it does not start with `{`.
+    fn new(text: &'a str) -> Option<Self> {
+        let mut members = Self {
+            text,
+            pos: 0,
+            done: false,
+        };
+        members.skip_whitespace();
+        if members.peek()? != b'{' {
+            return None;
+        }
+        members.pos += 1;
+        members.skip_whitespace();
+        if members.peek() == Some(b'}') {
+            members.done = true;
+        }
+        Some(members)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\r' | b'\n')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `byte`, after any whitespace.
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        (self.peek()? == byte).then(|| self.pos += 1)
+    }
+
+    /// Consume a string, including its quotes.
+    fn skip_string(&mut self) -> Option<()> {
+        self.expect(b'"')?;
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    /// Consume a value of any type and return its text.
+    fn value(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek()? {
+            b'"' => self.skip_string()?,
+            b'{' | b'[' => {
+                let mut depth = 0usize;
+                loop {
+                    match self.peek()? {
+                        b'"' => self.skip_string()?,
+                        b'{' | b'[' => {
+                            depth += 1;
+                            self.pos += 1;
+                        }
+                        b'}' | b']' => {
+                            depth -= 1;
+                            self.pos += 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => self.pos += 1,
+                    }
+                }
+            }
+            _ => {
+                while !matches!(
+                    self.peek(),
+                    None | Some(b',' | b'}' | b']' | b' ' | b'\t' | b'\r' | b'\n')
+                ) {
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    return None;
+                }
+            }
+        }
+        self.text.get(start..self.pos)
+    }
+
+    /// Read one `"key": value` member and the `,` or `}` after it.
+    fn member(&mut self) -> Option<(&'a str, &'a str)> {
+        let key = unquote(self.value()?)?;
+        self.expect(b':')?;
+        let value = self.value()?;
+        self.skip_whitespace();
+        match self.peek()? {
+            b',' => self.pos += 1,
+            b'}' => self.done = true,
+            _ => return None,
+        }
+        Some((key, value))
+    }
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = Option<(&'a str, &'a str)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let member = self.member();
+        if member.is_none() {
+            self.done = true;
+        }
+        Some(member)
+    }
+}
@@ -42,6 +42,14 @@
 //! - **Extensible**: Easy to add custom functions and handlers
 //! - **Connection Agnostic**: Works with any transport implementing [`Connection`](crate::network::Connection)
 //! - **JSON Communication**: Standard JSON message format for compatibility
+//! - **JSON-RPC 2.0**: Also accepts `jsonrpc`/`id`/`method`/`params` requests,
+//!   see [`jsonrpc`]
+//! - **Typed Results**: Handlers return a [`HandlerValue`] serialized with its JSON type
+//! - **Optional Handshake**: Answers `initialize` with protocol version and capabilities
+//! - **Tool Discovery**: Answers `tools/list` with the registered function names
+//!   and descriptions
+//! - **Handler Watchdog**: Optional per-call time limit, see [`watchdog`]
+//! - **Typed Arguments**: [`TypedHandler`] parses arguments into a struct
 //!
 //! # Usage Examples
 //!
@@ -80,7 +88,7 @@
 //!     fn call(&mut self, args: &str) -> HandlerResult {
 //!         // Parse arguments and return current temperature
 //!         let result = format!("Temperature: {:.1}°C", self.current_temp);
-//!         Ok(Some(String::try_from(result.as_str()).map_err(|_| McpError::BufferOverflow)?))
+//!         Ok(Some(String::try_from(result.as_str()).map_err(|_| McpError::BufferOverflow)?.into()))
 //!     }
 //! }
 //! ```
@@ -128,8 +136,13 @@ use serde::{Deserialize, Serialize};
 
 pub mod client;
 pub mod handlers;
+pub mod jsonrpc;
+pub mod typed;
+pub mod watchdog;
 
-pub use client::McpClient;
+pub use client::{Framing, McpClient};
+pub use typed::TypedHandler;
+pub use watchdog::{Deadline, Watchdog};
 
 /// Maximum length for function names in characters.
 ///
@@ -155,6 +168,53 @@ pub const MAX_RESPONSE_LEN: usize = 128;
 /// a single MCP client. Increase if more functions are needed.
 pub const MAX_FUNCTIONS: usize = 16;
 
+/// MCP protocol version advertised during the `initialize` handshake.
+pub const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Reserved function name that starts a session handshake.
+///
+/// See [`McpClient::with_handshake`].
+pub const INITIALIZE_FUNCTION: &str = "initialize";
+
+/// Reserved function name that lists the registered functions.
+///
+/// Answered by [`McpClient`] without reaching the registry; see
+/// [`McpClient::process_message`].
+pub const TOOLS_LIST_FUNCTION: &str = "tools/list";
+
+/// Capabilities advertised in response to an `initialize` message.
+///
+/// # Examples
+///
+/// ```rust
+/// use libiot::network::application::mcp::Capabilities;
+///
+/// let caps = Capabilities { streaming: false };
+/// assert_eq!(caps, Capabilities::default());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    /// Whether responses may be streamed in several parts.
+    pub streaming: bool,
+}
+
+/// Response sent to an `initialize` message.
+///
+/// Serializes as
+/// `{"status":"ok","protocolVersion":"2024-11-05","capabilities":{"streaming":false}}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct InitializeResponse<'a> {
+    /// Always [`ResponseStatus::Ok`].
+    pub status: ResponseStatus,
+
+    /// Protocol version spoken by this device.
+    #[serde(rename = "protocolVersion")]
+    pub protocol_version: &'a str,
+
+    /// Capabilities supported by this device.
+    pub capabilities: Capabilities,
+}
+
 /// Core MCP message structure for function calls.
 ///
 /// This represents an incoming request from an AI model to execute a specific
@@ -198,13 +258,13 @@ pub struct McpMessage<'a> {
 /// # Examples
 ///
 /// ```rust
-/// use libiot::network::application::mcp::{McpResponse, ResponseStatus};
+/// use libiot::network::application::mcp::{HandlerValue, McpResponse, ResponseStatus};
 /// use heapless::String;
 ///
 /// let success_response = McpResponse {
 ///     status: ResponseStatus::Ok,
 ///     error: None,
-///     result: Some(String::try_from("Operation completed").unwrap()),
+///     result: Some(HandlerValue::from("Operation completed")),
 /// };
 ///
 /// let error_response = McpResponse {
@@ -227,10 +287,11 @@ pub struct McpResponse {
 
     /// Optional result data from successful function execution.
     ///
-    /// This field contains the actual return value from the function handler.
-    /// It's omitted from JSON when the function doesn't return data.
+    /// This field contains the actual return value from the function handler,
+    /// serialized with its JSON type (see [`HandlerValue`]). It's omitted from
+    /// JSON when the function doesn't return data.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub result: Option<String<MAX_RESPONSE_LEN>>,
+    pub result: Option<HandlerValue>,
 }
 
 /// Status codes for MCP function execution responses.
@@ -281,6 +342,141 @@ pub enum ResponseStatus {
     InvalidArgs,
 }
 
+/// Value returned by an MCP function handler.
+///
+/// The client serializes the value into the response's `result` field with
+/// its natural JSON type, so a handler returning `HandlerValue::Int(42)`
+/// produces `"result":42` rather than the string `"result":"42"`.
+///
+/// # Examples
+///
+/// ```rust
+/// use libiot::network::application::mcp::{HandlerValue, McpResponse, ResponseStatus};
+///
+/// let response = McpResponse {
+///     status: ResponseStatus::Ok,
+///     error: None,
+///     result: Some(HandlerValue::Float(21.5)),
+/// };
+/// let mut buf = [0u8; 64];
+/// let len = serde_json_core::to_slice(&response, &mut buf).unwrap();
+/// assert_eq!(&buf[..len], br#"{"status":"ok","result":21.5}"#);
+///
+/// // Plain strings still work through `From<&str>`
+/// assert_eq!(HandlerValue::from("ready"), HandlerValue::Str("ready".try_into().unwrap()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum HandlerValue {
+    /// JSON `null`
+    Null,
+    /// JSON boolean
+    Bool(bool),
+    /// JSON integer
+    Int(i64),
+    /// JSON number; non-finite values serialize as `null`
+    Float(f32),
+    /// JSON string, escaped on output
+    Str(String<MAX_RESPONSE_LEN>),
+    /// Pre-serialized JSON (object, array, ...) emitted verbatim.
+    ///
+    /// The text is not validated, so it must be well-formed JSON.
+    ///
+    /// Only `serde-json-core` emits it verbatim: the value is written
+    /// through `Serializer::serialize_bytes`, which that crate copies to the
+    /// output as-is. Other serializers, `serde_json` included, write an
+    /// array of byte values instead, so responses carrying this variant
+    /// must be serialized with `serde_json_core`, as
+    /// [`McpClient`] does.
+    Json(String<MAX_RESPONSE_LEN>),
+}
+
+impl HandlerValue {
+    /// Serialize `value` into a [`HandlerValue::Json`].
+    ///
+    /// Returns `McpError::BufferOverflow` if the JSON does not fit in
+    /// [`MAX_RESPONSE_LEN`] bytes.
+    pub fn json<T: Serialize>(value: &T) -> Result<Self, McpError> {
+        let mut buf = [0u8; MAX_RESPONSE_LEN];
+        let len =
+            serde_json_core::to_slice(value, &mut buf).map_err(|_| McpError::BufferOverflow)?;
+        let text = core::str::from_utf8(&buf[..len]).map_err(|_| McpError::ExecutionError)?;
+        String::try_from(text)
+            .map(Self::Json)
+            .map_err(|_| McpError::BufferOverflow)
+    }
+
+    /// Text of a [`Str`](Self::Str) or [`Json`](Self::Json) value.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            HandlerValue::Str(text) | HandlerValue::Json(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Serializes as the JSON value the variant describes. [`HandlerValue::Json`]
+/// is only emitted verbatim by `serde-json-core`; see its documentation.
+impl Serialize for HandlerValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            HandlerValue::Null => serializer.serialize_unit(),
+            HandlerValue::Bool(value) => serializer.serialize_bool(*value),
+            HandlerValue::Int(value) => serializer.serialize_i64(*value),
+            HandlerValue::Float(value) => serializer.serialize_f32(*value),
+            HandlerValue::Str(value) => serializer.serialize_str(value),
+            HandlerValue::Json(raw) => serializer.serialize_bytes(raw.as_bytes()),
+        }
+    }
+}
+
+impl From<bool> for HandlerValue {
+    fn from(value: bool) -> Self {
+        HandlerValue::Bool(value)
+    }
+}
+
+impl From<i32> for HandlerValue {
+    fn from(value: i32) -> Self {
+        HandlerValue::Int(value.into())
+    }
+}
+
+impl From<i64> for HandlerValue {
+    fn from(value: i64) -> Self {
+        HandlerValue::Int(value)
+    }
+}
+
+impl From<u32> for HandlerValue {
+    fn from(value: u32) -> Self {
+        HandlerValue::Int(value.into())
+    }
+}
+
+impl From<f32> for HandlerValue {
+    fn from(value: f32) -> Self {
+        HandlerValue::Float(value)
+    }
+}
+
+impl From<String<MAX_RESPONSE_LEN>> for HandlerValue {
+    fn from(value: String<MAX_RESPONSE_LEN>) -> Self {
+        HandlerValue::Str(value)
+    }
+}
+
+/// Strings longer than [`MAX_RESPONSE_LEN`] bytes are truncated at the last
+/// character boundary that fits.
+impl From<&str> for HandlerValue {
+    fn from(value: &str) -> Self {
+        let mut end = value.len().min(MAX_RESPONSE_LEN);
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        HandlerValue::Str(String::try_from(&value[..end]).unwrap_or_default())
+    }
+}
+
 /// Result type for MCP function handlers.
 ///
 /// This type alias simplifies the return type for function implementations.
@@ -290,12 +486,16 @@ pub enum ResponseStatus {
 /// # Examples
 ///
 /// ```rust
-/// use libiot::network::application::mcp::{HandlerResult, McpError};
-/// use heapless::String;
+/// use libiot::network::application::mcp::{HandlerResult, HandlerValue, McpError};
 ///
 /// fn example_handler() -> HandlerResult {
 ///     // Success with result data
-///     Ok(Some(String::try_from("Success!").unwrap()))
+///     Ok(Some("Success!".into()))
+/// }
+///
+/// fn typed_handler() -> HandlerResult {
+///     // Numbers, booleans and pre-serialized JSON keep their JSON type
+///     Ok(Some(HandlerValue::Int(42)))
 /// }
 ///
 /// fn another_handler() -> HandlerResult {
@@ -308,7 +508,7 @@ pub enum ResponseStatus {
 ///     Err(McpError::InvalidArguments)
 /// }
 /// ```
-pub type HandlerResult = Result<Option<String<MAX_RESPONSE_LEN>>, McpError>;
+pub type HandlerResult = Result<Option<HandlerValue>, McpError>;
 
 /// Error types for MCP operations.
 ///
@@ -328,9 +528,11 @@ pub type HandlerResult = Result<Option<String<MAX_RESPONSE_LEN>>, McpError>;
 ///     McpError::InvalidArguments => println!("Bad function arguments"),
 ///     McpError::ExecutionError => println!("Function execution failed"),
 ///     McpError::BufferOverflow => println!("Response too large for buffer"),
+///     _ => println!("Other MCP error"),
 /// }
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum McpError {
     /// JSON parsing failed.
     ///
@@ -361,6 +563,18 @@ pub enum McpError {
     /// The response message, function name, or arguments exceed the maximum
     /// allowed size for the embedded buffers.
     BufferOverflow,
+
+    /// Function execution exceeded its time limit.
+    ///
+    /// Returned by [`Deadline::check`] once a [`Watchdog`] limit has passed.
+    /// This is converted to an `Error` response with a `"timeout"` message.
+    Timeout,
+
+    /// A function with this name is already registered.
+    ///
+    /// Returned by [`FunctionRegistry::register`]; use
+    /// [`FunctionRegistry::register_or_replace`] to swap the handler instead.
+    AlreadyRegistered,
 }
 
 /// Function handler trait for MCP functions.
@@ -391,7 +605,7 @@ pub enum McpError {
 ///         // Simple increment function
 ///         self.count += 1;
 ///         let result = format!("Count: {}", self.count);
-///         Ok(Some(String::try_from(result.as_str()).map_err(|_| McpError::BufferOverflow)?))
+///         Ok(Some(String::try_from(result.as_str()).map_err(|_| McpError::BufferOverflow)?.into()))
 ///     }
 /// }
 /// ```
@@ -418,8 +632,70 @@ pub trait McpHandler {
     /// or are invalid. Return `McpError::ExecutionError` for runtime failures.
     /// Return `McpError::BufferOverflow` if the response is too large.
     fn call(&mut self, args: &str) -> HandlerResult;
+
+    /// Execute the function within a time limit.
+    ///
+    /// Called instead of [`call`](Self::call) when a [`Watchdog`] is in use.
+    /// Handlers that may block, e.g. polling hardware, should override this
+    /// and call [`Deadline::check`] regularly, returning its error. The
+    /// default ignores the deadline and calls [`call`](Self::call).
+    fn call_with_deadline(&mut self, args: &str, deadline: &Deadline<'_>) -> HandlerResult {
+        let _ = deadline;
+        self.call(args)
+    }
+
+    /// Human-readable summary of what the function does.
+    ///
+    /// Sent to the host in the `tools/list` listing so that a model can
+    /// decide when to call the function. Empty by default, in which case
+    /// only the name is listed.
+    fn description(&self) -> &str {
+        ""
+    }
 }
 
+/// Boxed handlers forward to the handler they own.
+///
+/// This lets a single registry hold handlers of different types, see
+/// [`DynFunctionRegistry`]. Available with the `alloc` feature.
+#[cfg(feature = "alloc")]
+impl<H: McpHandler + ?Sized> McpHandler for alloc::boxed::Box<H> {
+    fn call(&mut self, args: &str) -> HandlerResult {
+        (**self).call(args)
+    }
+
+    fn call_with_deadline(&mut self, args: &str, deadline: &Deadline<'_>) -> HandlerResult {
+        (**self).call_with_deadline(args, deadline)
+    }
+
+    fn description(&self) -> &str {
+        (**self).description()
+    }
+}
+
+/// A registry of heap-allocated handlers of any type.
+///
+/// [`FunctionRegistry`] stores one handler type inline; boxing the handlers
+/// trades that for the ability to mix them. Available with the `alloc`
+/// feature.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// use libiot::network::application::mcp::{DynFunctionRegistry, ResponseStatus};
+/// use libiot::network::application::mcp::handlers::{GpioHandler, PingHandler};
+///
+/// let mut registry = DynFunctionRegistry::new();
+/// registry.register("ping", Box::new(PingHandler)).unwrap();
+/// registry.register("gpio", Box::new(GpioHandler::new())).unwrap();
+///
+/// assert_eq!(registry.execute("ping", "").status, ResponseStatus::Ok);
+/// # }
+/// ```
+#[cfg(feature = "alloc")]
+pub type DynFunctionRegistry = FunctionRegistry<alloc::boxed::Box<dyn McpHandler>>;
+
 /// Function registry for compile-time function registration.
 ///
 /// The registry manages a collection of MCP functions that can be called
@@ -447,7 +723,13 @@ pub trait McpHandler {
 /// let response = registry.execute("my_function", "{}");
 /// ```
 pub struct FunctionRegistry<H> {
-    handlers: FnvIndexMap<String<MAX_FUNCTION_NAME_LEN>, H, MAX_FUNCTIONS>,
+    handlers: FnvIndexMap<String<MAX_FUNCTION_NAME_LEN>, Entry<H>, MAX_FUNCTIONS>,
+}
+
+/// A registered handler together with its runtime state.
+struct Entry<H> {
+    handler: H,
+    enabled: bool,
 }
 
 impl<H: McpHandler> FunctionRegistry<H> {
@@ -488,6 +770,7 @@ impl<H: McpHandler> FunctionRegistry<H> {
     /// # Returns
     ///
     /// * `Ok(())` - Function registered successfully
+    /// * `Err(McpError::AlreadyRegistered)` - A function with this name exists
     /// * `Err(McpError::BufferOverflow)` - Name too long or registry full
     ///
     /// # Examples
@@ -500,7 +783,7 @@ impl<H: McpHandler> FunctionRegistry<H> {
     ///     fn call(&mut self, args: &str) -> HandlerResult {
     ///         // Echo back the arguments
     ///         Ok(Some(heapless::String::try_from(args).map_err(|_|
-    ///             libiot::network::application::mcp::McpError::BufferOverflow)?))
+    ///             libiot::network::application::mcp::McpError::BufferOverflow)?.into()))
     ///     }
     /// }
     ///
@@ -508,13 +791,159 @@ impl<H: McpHandler> FunctionRegistry<H> {
     /// registry.register("echo", EchoHandler).unwrap();
     /// ```
     pub fn register(&mut self, name: &str, handler: H) -> Result<(), McpError> {
+        if self.is_registered(name) {
+            return Err(McpError::AlreadyRegistered);
+        }
+        self.register_or_replace(name, handler)
+    }
+
+    /// Register a function handler, replacing any handler with the same name.
+    ///
+    /// A replaced function keeps its place in the registration order but
+    /// starts out enabled. Replacing reuses the function's slot, so it
+    /// succeeds even when the registry is full; only a new name needs a
+    /// free slot.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Function registered or replaced
+    /// * `Err(McpError::BufferOverflow)` - Name too long, or a new name and
+    ///   the registry is full
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::network::application::mcp::{
+    ///     FunctionRegistry, HandlerResult, HandlerValue, McpError, McpHandler,
+    /// };
+    ///
+    /// struct Mode(&'static str);
+    /// impl McpHandler for Mode {
+    ///     fn call(&mut self, _args: &str) -> HandlerResult { Ok(Some(self.0.into())) }
+    /// }
+    ///
+    /// let mut registry = FunctionRegistry::new();
+    /// registry.register("mode", Mode("idle")).unwrap();
+    /// assert_eq!(registry.register("mode", Mode("busy")), Err(McpError::AlreadyRegistered));
+    ///
+    /// registry.register_or_replace("mode", Mode("busy")).unwrap();
+    /// assert_eq!(registry.execute("mode", "").result, Some(HandlerValue::from("busy")));
+    /// ```
+    pub fn register_or_replace(&mut self, name: &str, handler: H) -> Result<(), McpError> {
         let key = String::try_from(name).map_err(|_| McpError::BufferOverflow)?;
         self.handlers
-            .insert(key, handler)
+            .insert(
+                key,
+                Entry {
+                    handler,
+                    enabled: true,
+                },
+            )
             .map_err(|_| McpError::BufferOverflow)?;
         Ok(())
     }
 
+    /// Enable or disable a registered function at runtime.
+    ///
+    /// A disabled function stays registered but is not invoked: calls to it
+    /// return a [`ResponseStatus::Error`] response with a `"disabled"` error
+    /// message. This is useful for gating capabilities that are temporarily
+    /// unavailable (e.g. an actuator during a safety interlock) without
+    /// tearing down the registry. Functions are enabled when registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of a previously registered function
+    /// * `enabled` - `true` to allow calls, `false` to reject them
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The function's state was updated
+    /// * `Err(McpError::FunctionNotFound)` - No function with this name is registered
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::network::application::mcp::{FunctionRegistry, ResponseStatus};
+    /// use libiot::network::application::mcp::handlers::PingHandler;
+    ///
+    /// let mut registry = FunctionRegistry::new();
+    /// registry.register("ping", PingHandler).unwrap();
+    ///
+    /// registry.set_enabled("ping", false).unwrap();
+    /// assert_eq!(registry.execute("ping", "{}").status, ResponseStatus::Error);
+    ///
+    /// registry.set_enabled("ping", true).unwrap();
+    /// assert_eq!(registry.execute("ping", "{}").status, ResponseStatus::Ok);
+    /// ```
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), McpError> {
+        let entry = self.find_mut(name).ok_or(McpError::FunctionNotFound)?;
+        entry.enabled = enabled;
+        Ok(())
+    }
+
+    /// Remove a registered function, freeing its slot.
+    ///
+    /// The remaining functions keep their registration order. Returns
+    /// `false` if no function with this name is registered.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let len = self.handlers.len();
+        self.handlers.retain(|key, _| key.as_str() != name);
+        self.handlers.len() < len
+    }
+
+    /// Check whether a function with this name is registered.
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.handlers.keys().any(|key| key.as_str() == name)
+    }
+
+    /// Check whether a registered function is currently enabled.
+    ///
+    /// Returns `None` if no function with this name is registered.
+    pub fn is_enabled(&self, name: &str) -> Option<bool> {
+        self.handlers
+            .iter()
+            .find(|(key, _)| key.as_str() == name)
+            .map(|(_, entry)| entry.enabled)
+    }
+
+    /// Names of the registered functions, in registration order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::network::application::mcp::FunctionRegistry;
+    /// use libiot::network::application::mcp::handlers::PingHandler;
+    ///
+    /// let mut registry = FunctionRegistry::new();
+    /// registry.register("ping", PingHandler).unwrap();
+    /// registry.register("ping2", PingHandler).unwrap();
+    ///
+    /// assert!(registry.list_names().eq(["ping", "ping2"]));
+    /// ```
+    pub fn list_names(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(|name| name.as_str())
+    }
+
+    /// Name and [description](McpHandler::description) of each enabled
+    /// function, in registration order.
+    ///
+    /// This is what a `tools/list` message is answered with.
+    pub fn tools(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.handlers
+            .iter()
+            .filter(|(_, entry)| entry.enabled)
+            .map(|(name, entry)| (name.as_str(), entry.handler.description()))
+    }
+
+    /// Look up a registered entry by comparing string contents.
+    fn find_mut(&mut self, name: &str) -> Option<&mut Entry<H>> {
+        self.handlers
+            .iter_mut()
+            .find(|(key, _)| key.as_str() == name)
+            .map(|(_, entry)| entry)
+    }
+
     /// Execute a function by name with provided arguments.
     ///
     /// Looks up the function handler by name and executes it with the given
@@ -530,7 +959,9 @@ impl<H: McpHandler> FunctionRegistry<H> {
     ///
     /// An [`McpResponse`] containing the execution result, status, and any
     /// error information. The response is always returned, even for errors,
-    /// to provide structured feedback to the AI model.
+    /// to provide structured feedback to the AI model. Functions disabled via
+    /// [`set_enabled`](Self::set_enabled) are not invoked and yield an
+    /// `Error` status with a `"disabled"` message.
     ///
     /// # Examples
     ///
@@ -548,36 +979,75 @@ impl<H: McpHandler> FunctionRegistry<H> {
     /// assert_eq!(not_found.status, ResponseStatus::NotFound);
     /// ```
     pub fn execute(&mut self, function: &str, args: &str) -> McpResponse {
-        // Find the handler by comparing string contents
-        let mut found_handler = None;
-        for (key, _) in &self.handlers {
-            if key.as_str() == function {
-                found_handler = Some(key.clone());
-                break;
+        self.dispatch(function, args, None)
+    }
+
+    /// Execute a registered function within a time limit.
+    ///
+    /// Like [`execute`](Self::execute), but the handler is invoked through
+    /// [`McpHandler::call_with_deadline`]. If it fails with
+    /// [`McpError::Timeout`], or returns after the deadline has expired, the
+    /// response has status `Error` and the message `"timeout"`. See the
+    /// [`watchdog`] module for what this can and cannot guarantee.
+    pub fn execute_with_deadline(
+        &mut self,
+        function: &str,
+        args: &str,
+        deadline: &Deadline<'_>,
+    ) -> McpResponse {
+        self.dispatch(function, args, Some(deadline))
+    }
+
+    fn dispatch(
+        &mut self,
+        function: &str,
+        args: &str,
+        deadline: Option<&Deadline<'_>>,
+    ) -> McpResponse {
+        let entry = match self.find_mut(function) {
+            Some(entry) => entry,
+            None => {
+                return McpResponse {
+                    status: ResponseStatus::NotFound,
+                    error: Some(String::try_from("Function not found").unwrap_or_default()),
+                    result: None,
+                };
             }
+        };
+        if !entry.enabled {
+            return McpResponse {
+                status: ResponseStatus::Error,
+                error: Some(String::try_from("disabled").unwrap_or_default()),
+                result: None,
+            };
         }
 
-        match found_handler.and_then(|key| self.handlers.get_mut(&key)) {
-            Some(handler) => match handler.call(args) {
-                Ok(result) => McpResponse {
-                    status: ResponseStatus::Ok,
-                    error: None,
-                    result,
-                },
-                Err(McpError::InvalidArguments) => McpResponse {
-                    status: ResponseStatus::InvalidArgs,
-                    error: Some(String::try_from("Invalid arguments").unwrap_or_default()),
-                    result: None,
-                },
-                Err(_) => McpResponse {
-                    status: ResponseStatus::Error,
-                    error: Some(String::try_from("Execution failed").unwrap_or_default()),
-                    result: None,
-                },
+        let result = match deadline {
+            Some(deadline) => match entry.handler.call_with_deadline(args, deadline) {
+                Ok(_) if deadline.expired() => Err(McpError::Timeout),
+                result => result,
+            },
+            None => entry.handler.call(args),
+        };
+        match result {
+            Ok(result) => McpResponse {
+                status: ResponseStatus::Ok,
+                error: None,
+                result,
+            },
+            Err(McpError::InvalidArguments) => McpResponse {
+                status: ResponseStatus::InvalidArgs,
+                error: Some(String::try_from("Invalid arguments").unwrap_or_default()),
+                result: None,
+            },
+            Err(McpError::Timeout) => McpResponse {
+                status: ResponseStatus::Error,
+                error: Some(String::try_from("timeout").unwrap_or_default()),
+                result: None,
             },
-            None => McpResponse {
-                status: ResponseStatus::NotFound,
-                error: Some(String::try_from("Function not found").unwrap_or_default()),
+            Err(_) => McpResponse {
+                status: ResponseStatus::Error,
+                error: Some(String::try_from("Execution failed").unwrap_or_default()),
                 result: None,
             },
         }
@@ -599,6 +1069,8 @@ impl defmt::Format for McpError {
             McpError::InvalidArguments => defmt::write!(f, "InvalidArguments"),
             McpError::ExecutionError => defmt::write!(f, "ExecutionError"),
             McpError::BufferOverflow => defmt::write!(f, "BufferOverflow"),
+            McpError::Timeout => defmt::write!(f, "Timeout"),
+            McpError::AlreadyRegistered => defmt::write!(f, "AlreadyRegistered"),
         }
     }
 }
@@ -0,0 +1,104 @@
+//! Handlers with strongly typed arguments
+//!
+//! [`TypedHandler`] wraps a closure that takes a parameter struct. The
+//! arguments are deserialized into the struct before the closure runs, and
+//! a message whose arguments do not match is answered with `InvalidArgs`
+//! without calling it.
+//!
+//! # Deserialization constraints
+//!
+//! Arguments are parsed with `serde_json_core`, which never allocates:
+//!
+//! - The argument type must be [`DeserializeOwned`], since the argument text
+//!   only lives for the duration of the call. Use `heapless::String<N>` rather
+//!   than `&str` for text fields; text longer than `N` bytes is rejected.
+//! - Escape sequences in strings are decoded through a scratch buffer of
+//!   [`MAX_ARGS_LEN`] bytes on the stack, which bounds the longest string.
+//! - Unknown fields are ignored, and fields of type `Option` may be missing.
+//!   Empty arguments are read as `{}`, so a struct whose fields are all
+//!   optional accepts a call without arguments.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::network::application::mcp::{FunctionRegistry, ResponseStatus, TypedHandler};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct SetPin {
+//!     pin: u8,
+//!     state: bool,
+//! }
+//!
+//! let mut registry = FunctionRegistry::new();
+//! registry
+//!     .register(
+//!         "set_pin",
+//!         TypedHandler::new(|args: SetPin| {
+//!             // gpio.set(args.pin, args.state);
+//!             Ok(Some(args.state.into()))
+//!         }),
+//!     )
+//!     .unwrap();
+//!
+//! let response = registry.execute("set_pin", r#"{"pin": 13, "state": true}"#);
+//! assert_eq!(response.status, ResponseStatus::Ok);
+//!
+//! let response = registry.execute("set_pin", r#"{"pin": "13"}"#);
+//! assert_eq!(response.status, ResponseStatus::InvalidArgs);
+//! ```
+
+use super::{HandlerResult, MAX_ARGS_LEN, McpError, McpHandler};
+use core::marker::PhantomData;
+use serde::de::DeserializeOwned;
+
+/// An [`McpHandler`] that deserializes its arguments into `T` and calls `F`.
+///
+/// See the [module documentation](self) for what `T` can contain.
+pub struct TypedHandler<T, F> {
+    handler: F,
+    description: &'static str,
+    args: PhantomData<fn(T)>,
+}
+
+impl<T, F> TypedHandler<T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> HandlerResult,
+{
+    /// Wrap `handler`, which is called with the parsed arguments.
+    pub fn new(handler: F) -> Self {
+        Self {
+            handler,
+            description: "",
+            args: PhantomData,
+        }
+    }
+
+    /// Set the description listed for the function in `tools/list`.
+    pub fn with_description(mut self, description: &'static str) -> Self {
+        self.description = description;
+        self
+    }
+}
+
+impl<T, F> McpHandler for TypedHandler<T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> HandlerResult,
+{
+    fn call(&mut self, args: &str) -> HandlerResult {
+        let args = match args.trim() {
+            "" => "{}",
+            args => args,
+        };
+        let mut scratch = [0u8; MAX_ARGS_LEN];
+        let (args, _) = serde_json_core::from_str_escaped::<T>(args, &mut scratch)
+            .map_err(|_| McpError::InvalidArguments)?;
+        (self.handler)(args)
+    }
+
+    fn description(&self) -> &str {
+        self.description
+    }
+}
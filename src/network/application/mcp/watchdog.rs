@@ -0,0 +1,149 @@
+//! Execution time limits for MCP handlers
+//!
+//! Handlers run synchronously inside `process_message`, so a handler that
+//! waits forever on unresponsive hardware would stall the device. A
+//! [`Watchdog`] bounds each call, but only cooperatively: nothing can
+//! interrupt a running handler. The bound is enforced at two points:
+//!
+//! - **Checkpoints**: handlers that may block override
+//!   [`McpHandler::call_with_deadline`] and call [`Deadline::check`] inside
+//!   their loops. Once the limit has passed it returns
+//!   `Err(McpError::Timeout)`, which the handler propagates.
+//! - **On return**: a call that comes back after the limit has passed is
+//!   answered with a `"timeout"` error even if it succeeded, because the
+//!   host can no longer rely on the result arriving in time. Its side
+//!   effects have still happened.
+//!
+//! A handler that never reaches a checkpoint cannot be stopped this way.
+//! For that case, give the watchdog a kick hook that feeds a hardware
+//! watchdog. The hook runs at every checkpoint, so a handler that keeps
+//! checking in keeps the device alive, while one that hangs lets the
+//! hardware watchdog reset the device.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::network::application::mcp::{
+//!     Deadline, FunctionRegistry, HandlerResult, McpHandler, ResponseStatus, Watchdog,
+//! };
+//! # use libiot::system::clock::MonotonicClock;
+//! # use core::cell::Cell;
+//! # struct TickClock(Cell<u64>);
+//! # impl MonotonicClock for TickClock {
+//! #     fn now_ms(&self) -> u64 { self.0.set(self.0.get() + 10); self.0.get() }
+//! # }
+//! # let clock = TickClock(Cell::new(0));
+//!
+//! /// Polls a sensor that may never become ready
+//! struct SensorHandler;
+//!
+//! impl McpHandler for SensorHandler {
+//!     fn call(&mut self, _args: &str) -> HandlerResult {
+//!         Ok(None)
+//!     }
+//!
+//!     fn call_with_deadline(&mut self, _args: &str, deadline: &Deadline<'_>) -> HandlerResult {
+//!         loop {
+//!             // if sensor_ready() { return Ok(Some(read_sensor().into())); }
+//!             deadline.check()?;
+//!         }
+//!     }
+//! }
+//!
+//! let mut registry = FunctionRegistry::new();
+//! registry.register("sensor", SensorHandler).unwrap();
+//!
+//! let watchdog = Watchdog::new(&clock, 100);
+//! let response = registry.execute_with_deadline("sensor", "{}", &watchdog.start());
+//! assert_eq!(response.status, ResponseStatus::Error);
+//! assert_eq!(response.error.as_deref(), Some("timeout"));
+//! ```
+
+use super::McpError;
+use crate::system::clock::MonotonicClock;
+
+/// Per-call execution time limit for MCP handlers
+///
+/// Holds the clock and limit; each call gets a fresh [`Deadline`] from
+/// [`start`](Self::start).
+#[derive(Debug, Clone, Copy)]
+pub struct Watchdog<K: MonotonicClock> {
+    clock: K,
+    limit_ms: u64,
+    kick: Option<fn()>,
+}
+
+impl<K: MonotonicClock> Watchdog<K> {
+    /// Limit each handler call to `limit_ms` milliseconds of `clock` time
+    pub fn new(clock: K, limit_ms: u64) -> Self {
+        Self {
+            clock,
+            limit_ms,
+            kick: None,
+        }
+    }
+
+    /// Call `kick` at every checkpoint, e.g. to feed a hardware watchdog
+    pub fn with_kick(mut self, kick: fn()) -> Self {
+        self.kick = Some(kick);
+        self
+    }
+
+    /// Maximum duration of one handler call in milliseconds
+    pub fn limit_ms(&self) -> u64 {
+        self.limit_ms
+    }
+
+    /// Start timing a handler call
+    pub fn start(&self) -> Deadline<'_> {
+        Deadline {
+            clock: &self.clock,
+            start_ms: self.clock.now_ms(),
+            limit_ms: self.limit_ms,
+            kick: self.kick,
+        }
+    }
+}
+
+/// The time budget of a single handler call
+///
+/// Passed to [`McpHandler::call_with_deadline`](super::McpHandler::call_with_deadline).
+pub struct Deadline<'a> {
+    clock: &'a dyn MonotonicClock,
+    start_ms: u64,
+    limit_ms: u64,
+    kick: Option<fn()>,
+}
+
+impl Deadline<'_> {
+    /// Whether the call has run past its limit
+    pub fn expired(&self) -> bool {
+        self.clock.elapsed_ms(self.start_ms) > self.limit_ms
+    }
+
+    /// Milliseconds left before the limit, zero once expired
+    pub fn remaining_ms(&self) -> u64 {
+        self.limit_ms
+            .saturating_sub(self.clock.elapsed_ms(self.start_ms))
+    }
+
+    /// Checkpoint: run the kick hook, then fail with `McpError::Timeout` if expired
+    pub fn check(&self) -> Result<(), McpError> {
+        if let Some(kick) = self.kick {
+            kick();
+        }
+        if self.expired() {
+            return Err(McpError::Timeout);
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for Deadline<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Deadline")
+            .field("start_ms", &self.start_ms)
+            .field("limit_ms", &self.limit_ms)
+            .finish_non_exhaustive()
+    }
+}
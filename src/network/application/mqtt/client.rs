@@ -55,6 +55,7 @@
 //!     client_id: "sensor_device_01",
 //!     keep_alive_seconds: 60,
 //!     clean_session: true,
+//!     max_packet_size: None,
 //! };
 //!
 //! // let mut client = Client::connect(connection, options)?;
@@ -111,6 +112,37 @@ const PUBLISH: u8 = 0x30;
 const SUBSCRIBE: u8 = 0x82;
 /// MQTT SUBACK packet type identifier.
 const SUBACK: u8 = 0x90;
+/// SUBACK return code for a rejected topic filter.
+const SUBACK_FAILURE: u8 = 0x80;
+/// MQTT PUBACK packet type identifier (QoS 1).
+const PUBACK: u8 = 0x40;
+/// MQTT PUBREC packet type identifier (QoS 2, step 1).
+const PUBREC: u8 = 0x50;
+/// MQTT PUBREL packet type identifier (QoS 2, step 2).
+const PUBREL: u8 = 0x62;
+/// MQTT PUBCOMP packet type identifier (QoS 2, step 3).
+const PUBCOMP: u8 = 0x70;
+/// MQTT UNSUBSCRIBE packet type identifier.
+const UNSUBSCRIBE: u8 = 0xA2;
+/// MQTT UNSUBACK packet type identifier.
+const UNSUBACK: u8 = 0xB0;
+/// MQTT PINGREQ packet type identifier.
+const PINGREQ: u8 = 0xC0;
+/// MQTT PINGRESP packet type identifier.
+const PINGRESP: u8 = 0xD0;
+/// MQTT DISCONNECT packet type identifier.
+const DISCONNECT: u8 = 0xE0;
+
+/// Maximum number of inbound QoS 2 messages awaiting PUBREL at once.
+///
+/// Each slot holds a full [`PublishPacket`] until the broker releases it.
+pub const MAX_INBOUND_QOS2: usize = 4;
+
+/// Default number of topic filters remembered for [`Client::resubscribe_all`].
+///
+/// The cap is the client's `SUBSCRIPTIONS` const generic; it also limits the
+/// number of filters in one [`Client::subscribe_many`] call.
+pub const MAX_SUBSCRIPTIONS: usize = 8;
 
 /// An incoming MQTT publish message.
 ///
@@ -118,6 +150,11 @@ const SUBACK: u8 = 0x90;
 /// subscribed to one or more topics. It contains both the topic name and
 /// the message payload.
 ///
+/// # Type Parameters
+///
+/// * `PAYLOAD` - Payload capacity in bytes (default 1024)
+/// * `TOPIC` - Topic capacity in bytes (default 256)
+///
 /// # Examples
 ///
 /// ```rust
@@ -134,17 +171,73 @@ const SUBACK: u8 = 0x90;
 /// assert_eq!(&packet.payload[..], b"23.5");
 /// ```
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct PublishPacket {
+pub struct PublishPacket<const PAYLOAD: usize = 1024, const TOPIC: usize = 256> {
     /// The topic on which the message was published.
     ///
-    /// Maximum length is 256 characters to fit within embedded memory constraints.
-    pub topic: String<256>,
+    /// Holds at most `TOPIC` bytes.
+    pub topic: String<TOPIC>,
+
+    /// The message payload data.
+    ///
+    /// Holds at most `PAYLOAD` bytes. For larger payloads, consider chunking
+    /// the data across multiple messages.
+    pub payload: Vec<u8, PAYLOAD>,
+}
 
+/// A borrowed view of an incoming MQTT publish message.
+///
+/// Returned by [`Client::poll_ref`], it lends the topic and payload straight
+/// out of the client's receive buffer, so no copy is made. The view is valid
+/// until the next call on the client; use [`to_packet`](Self::to_packet) to
+/// keep the message longer.
+///
+/// # Examples
+///
+/// ```rust
+/// use libiot::network::application::mqtt::client::{PublishPacket, PublishRef};
+///
+/// let message = PublishRef {
+///     topic: "sensors/temperature",
+///     payload: b"23.5",
+/// };
+///
+/// let owned: PublishPacket = message.to_packet().unwrap();
+/// assert_eq!(PublishRef::from(&owned), message);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PublishRef<'a> {
+    /// The topic on which the message was published.
+    pub topic: &'a str,
     /// The message payload data.
+    pub payload: &'a [u8],
+}
+
+impl PublishRef<'_> {
+    /// Copy the message into an owned [`PublishPacket`].
     ///
-    /// Maximum size is 1024 bytes to balance functionality with memory usage.
-    /// For larger payloads, consider chunking the data across multiple messages.
-    pub payload: Vec<u8, 1024>,
+    /// # Errors
+    ///
+    /// * [`Error::ProtocolError`] - The topic or payload exceeds the capacity of
+    ///   [`PublishPacket`]
+    pub fn to_packet<const PAYLOAD: usize, const TOPIC: usize>(
+        &self,
+    ) -> Result<PublishPacket<PAYLOAD, TOPIC>, Error> {
+        Ok(PublishPacket {
+            topic: String::try_from(self.topic).map_err(|_| Error::ProtocolError)?,
+            payload: Vec::from_slice(self.payload).map_err(|_| Error::ProtocolError)?,
+        })
+    }
+}
+
+impl<'a, const PAYLOAD: usize, const TOPIC: usize> From<&'a PublishPacket<PAYLOAD, TOPIC>>
+    for PublishRef<'a>
+{
+    fn from(packet: &'a PublishPacket<PAYLOAD, TOPIC>) -> Self {
+        Self {
+            topic: &packet.topic,
+            payload: &packet.payload,
+        }
+    }
 }
 
 // Protocol constants defined by MQTT 3.1.1 specification
@@ -195,6 +288,47 @@ pub enum QoS {
     ExactlyOnce = 2,
 }
 
+/// Parse a QoS level from `"0"`, `"1"` or `"2"`, or from the variant name
+/// (`"AtMostOnce"`, `"at_most_once"`, ...) ignoring ASCII case.
+///
+/// Returns [`Error::ProtocolError`] for anything else.
+///
+/// ```rust
+/// use libiot::network::application::mqtt::client::QoS;
+///
+/// assert_eq!(QoS::try_from("1"), Ok(QoS::AtLeastOnce));
+/// assert_eq!("exactly_once".parse(), Ok(QoS::ExactlyOnce));
+/// assert!(QoS::try_from("3").is_err());
+/// ```
+impl TryFrom<&str> for QoS {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let names = [
+            (QoS::AtMostOnce, "0", "AtMostOnce", "at_most_once"),
+            (QoS::AtLeastOnce, "1", "AtLeastOnce", "at_least_once"),
+            (QoS::ExactlyOnce, "2", "ExactlyOnce", "exactly_once"),
+        ];
+        names
+            .into_iter()
+            .find(|(_, level, camel, snake)| {
+                value == *level
+                    || value.eq_ignore_ascii_case(camel)
+                    || value.eq_ignore_ascii_case(snake)
+            })
+            .map(|(qos, ..)| qos)
+            .ok_or(Error::ProtocolError)
+    }
+}
+
+impl core::str::FromStr for QoS {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
 /// Configuration options for MQTT client connection.
 ///
 /// These options control how the client connects to the MQTT broker and
@@ -210,9 +344,10 @@ pub enum QoS {
 ///     client_id: "my_iot_device",
 ///     keep_alive_seconds: 60,
 ///     clean_session: true,
+///     max_packet_size: None,
 /// };
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Options<'a> {
     /// The client identifier, must be unique within the broker.
     ///
@@ -230,7 +365,9 @@ pub struct Options<'a> {
     ///
     /// This defines the maximum time interval between messages sent or received.
     /// It enables the client and broker to detect when the other has disconnected.
-    /// A value of 0 disables keep-alive.
+    /// A value of 0 disables keep-alive. The client does not ping on its
+    /// own; call [`Client::ping`] when nothing else has been sent for this
+    /// long.
     ///
     /// # Recommended Values
     /// - IoT devices: 60-300 seconds
@@ -246,6 +383,19 @@ pub struct Options<'a> {
     /// Clean sessions are simpler but don't preserve subscriptions across reconnections.
     /// Persistent sessions maintain state but require more broker resources.
     pub clean_session: bool,
+
+    /// The largest inbound packet to accept, as its remaining length (the
+    /// packet size after the fixed header).
+    ///
+    /// [`Client::poll`] rejects a larger packet with
+    /// [`Error::ProtocolError`] as soon as its fixed header is read, without
+    /// buffering any of it. `None` accepts anything that fits in the client's
+    /// receive buffer (`PAYLOAD` bytes, 1024 by default), which also caps any
+    /// limit set here.
+    ///
+    /// MQTT 3.1.1 has no way to announce this limit to the broker, so it is
+    /// enforced on the client side only.
+    pub max_packet_size: Option<usize>,
 }
 
 /// An MQTT 3.1.1 client for publish-subscribe messaging.
@@ -257,6 +407,17 @@ pub struct Options<'a> {
 /// # Type Parameters
 ///
 /// * `C` - The connection type implementing [`Connection`]
+/// * `PAYLOAD` - Size in bytes of the receive buffer and of the buffer
+///   [`publish`](Client::publish) assembles packets in (default 1024). Each
+///   holds a PUBLISH body: the topic with its 2-byte length, the packet
+///   identifier for QoS 1 and 2, then the payload. Also the payload capacity
+///   of the [`PublishPacket`]s returned by [`poll`](Client::poll).
+/// * `TOPIC` - Capacity in bytes of received topics and stored topic filters
+///   (default 256)
+///
+/// The defaults suit most devices. A small sensor can shrink them, e.g.
+/// `Client<C, 64, 16>`, and a gateway can grow them, e.g.
+/// `Client<C, 4096, 512>`.
 ///
 /// # Examples
 ///
@@ -284,31 +445,93 @@ pub struct Options<'a> {
 ///     client_id: "sensor_node_1",
 ///     keep_alive_seconds: 120,
 ///     clean_session: true,
+///     max_packet_size: None,
 /// };
 ///
 /// // let client = Client::connect(connection, options)?;
 /// ```
-pub struct Client<C: Connection> {
+pub struct Client<
+    C: Connection,
+    const PAYLOAD: usize = 1024,
+    const TOPIC: usize = 256,
+    const SUBSCRIPTIONS: usize = MAX_SUBSCRIPTIONS,
+> {
     connection: C,
     is_connected: bool,
+    /// Inbound QoS 2 messages that have been PUBREC'd but not yet released.
+    inbound_qos2: Vec<(u16, PublishPacket<PAYLOAD, TOPIC>), MAX_INBOUND_QOS2>,
+    /// Slot in `inbound_qos2` lent out by the last `poll_ref`, freed on the next poll.
+    released_qos2: Option<usize>,
+    /// Receive buffer for the body of the last packet read by `poll_ref`.
+    rx_buf: Vec<u8, PAYLOAD>,
+    /// Topic filters acknowledged by the broker, restored on reconnect.
+    subscriptions: Vec<(String<TOPIC>, QoS), SUBSCRIPTIONS>,
+    /// Limit on inbound remaining lengths, from [`Options::max_packet_size`].
+    max_packet_size: Option<usize>,
+    /// Identifier of the last outbound QoS > 0 PUBLISH, SUBSCRIBE or
+    /// UNSUBSCRIBE, 0 before the first.
+    last_packet_id: u16,
+    /// Keep-alive interval sent in CONNECT, from [`Options::keep_alive_seconds`].
+    keep_alive_seconds: u16,
 }
 
 impl<C: Connection> Client<C> {
-    /// Returns whether the MQTT session is currently considered connected.
-    pub fn is_connected(&self) -> bool {
-        self.is_connected
-    }
-
-    fn ensure_connected(&self) -> Result<(), Error> {
-        if self.is_connected {
-            Ok(())
-        } else {
-            Err(Error::NotOpen)
-        }
+    /// Wrap a connection on which an MQTT session is already established.
+    ///
+    /// Unlike [`connect`](Client::connect), no CONNECT/CONNACK exchange is
+    /// performed: the client is immediately considered connected. This is
+    /// useful for wrapping a socket that was authenticated elsewhere (e.g. by
+    /// a reconnect helper or a modem that manages the MQTT session) and for
+    /// unit-testing publish/subscribe without scripting the handshake.
+    ///
+    /// The client has the default buffer sizes; see
+    /// [`from_connected_with_buffers`](Client::from_connected_with_buffers)
+    /// for others.
+    ///
+    /// # Invariant
+    ///
+    /// The caller guarantees that the broker has accepted a session on
+    /// `connection`. If it has not, subsequent operations will fail with
+    /// protocol or connection errors from the broker side.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - A network connection with a live MQTT session
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::network::application::mqtt::client::{Client, QoS};
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection;
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> { Ok(buf.len()) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    ///
+    /// let mut client = Client::from_connected(MockConnection);
+    /// assert!(client.is_connected());
+    /// client.publish("status", b"online", QoS::AtMostOnce).unwrap();
+    /// ```
+    pub fn from_connected(connection: C) -> Self {
+        Self::from_connected_with_buffers(connection)
     }
 
     /// Establish an MQTT connection with the broker.
     ///
+    /// The client has the default buffer sizes; see
+    /// [`connect_with_buffers`](Client::connect_with_buffers) for others.
+    ///
     /// This function performs the MQTT connection handshake by sending a CONNECT
     /// packet and waiting for a CONNACK response. If successful, it returns a
     /// connected client ready for publishing and subscribing.
@@ -368,6 +591,7 @@ impl<C: Connection> Client<C> {
     ///     client_id: "weather_station",
     ///     keep_alive_seconds: 60,
     ///     clean_session: true,
+    ///     max_packet_size: None,
     /// };
     ///
     /// // match Client::connect(tcp_connection, options) {
@@ -378,73 +602,164 @@ impl<C: Connection> Client<C> {
     /// //     Err(e) => println!("Connection failed: {:?}", e),
     /// // }
     /// ```
-    pub fn connect(mut connection: C, options: Options) -> Result<Self, Error> {
-        // --- Variable Header ---
-        let mut vh: Vec<u8, 10> = Vec::new();
-        vh.extend_from_slice(&(PROTOCOL_NAME.len() as u16).to_be_bytes())
-            .unwrap();
-        vh.extend_from_slice(PROTOCOL_NAME).unwrap();
-        vh.push(PROTOCOL_LEVEL).unwrap();
+    pub fn connect(connection: C, options: Options) -> Result<Self, Error> {
+        Self::connect_with_buffers(connection, options)
+    }
+}
 
-        let mut connect_flags = 0;
-        if options.clean_session {
-            connect_flags |= 0x02;
+impl<C: Connection, const PAYLOAD: usize, const TOPIC: usize, const SUBSCRIPTIONS: usize>
+    Client<C, PAYLOAD, TOPIC, SUBSCRIPTIONS>
+{
+    /// [`from_connected`](Client::from_connected) for a client with custom
+    /// buffer sizes.
+    ///
+    /// ```rust
+    /// use libiot::network::application::mqtt::client::{Client, QoS};
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection;
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> { Ok(buf.len()) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    ///
+    /// let mut client = Client::<_, 128, 32>::from_connected_with_buffers(MockConnection);
+    /// client.publish("status", b"online", QoS::AtMostOnce).unwrap();
+    /// ```
+    pub fn from_connected_with_buffers(connection: C) -> Self {
+        Self {
+            connection,
+            is_connected: true,
+            inbound_qos2: Vec::new(),
+            released_qos2: None,
+            rx_buf: Vec::new(),
+            subscriptions: Vec::new(),
+            max_packet_size: None,
+            last_packet_id: 0,
+            keep_alive_seconds: 0,
         }
-        vh.push(connect_flags).unwrap();
-        vh.extend_from_slice(&options.keep_alive_seconds.to_be_bytes())
-            .unwrap();
+    }
 
-        // --- Payload ---
-        let mut payload: Vec<u8, 256> = Vec::new();
-        let client_id_bytes = options.client_id.as_bytes();
-        payload
-            .extend_from_slice(&(client_id_bytes.len() as u16).to_be_bytes())
-            .unwrap();
-        payload.extend_from_slice(client_id_bytes).unwrap();
+    /// [`connect`](Client::connect) for a client with custom buffer sizes.
+    pub fn connect_with_buffers(mut connection: C, options: Options) -> Result<Self, Error> {
+        handshake(&mut connection, &options)?;
+        Ok(Self {
+            max_packet_size: options.max_packet_size,
+            keep_alive_seconds: options.keep_alive_seconds,
+            ..Self::from_connected_with_buffers(connection)
+        })
+    }
+
+    /// Returns whether the MQTT session is currently considered connected.
+    pub fn is_connected(&self) -> bool {
+        self.is_connected
+    }
 
-        let remaining_len = vh.len() + payload.len();
+    /// Returns a shared reference to the underlying connection.
+    ///
+    /// Useful for inspecting transport state, such as the peer address or
+    /// link quality, without tearing down the session.
+    pub fn connection(&self) -> &C {
+        &self.connection
+    }
 
-        // --- Fixed Header ---
-        let mut fixed_header: Vec<u8, 5> = Vec::new();
-        fixed_header.push(CONNECT).unwrap();
-        encode_remaining_length(&mut fixed_header, remaining_len)
-            .map_err(|_| Error::ProtocolError)?;
+    /// Returns a mutable reference to the underlying connection.
+    ///
+    /// Writing or reading MQTT bytes through this reference bypasses the
+    /// client and will desynchronize the session; use it for transport-level
+    /// configuration only.
+    pub fn connection_mut(&mut self) -> &mut C {
+        &mut self.connection
+    }
 
-        // Write packet to the connection
-        connection
-            .write(&fixed_header)
-            .map_err(|_| Error::WriteError)?;
-        connection.write(&vh).map_err(|_| Error::WriteError)?;
-        connection.write(&payload).map_err(|_| Error::WriteError)?;
-        connection.flush().map_err(|_| Error::WriteError)?;
-
-        // Wait for and parse CONNACK
-        let mut connack_buf = [0u8; 4];
-        let mut total_read = 0;
-        while total_read < connack_buf.len() {
-            match connection.read(&mut connack_buf[total_read..]) {
-                Ok(0) => return Err(Error::ConnectionClosed),
-                Ok(n) => total_read += n,
-                Err(_) => return Err(Error::ReadError),
-            }
-        }
+    /// Topic filters currently subscribed to, with their requested QoS.
+    ///
+    /// This is the table maintained by [`subscribe`](Self::subscribe) and
+    /// [`unsubscribe`](Self::unsubscribe) and replayed by
+    /// [`resubscribe_all`](Self::resubscribe_all).
+    pub fn subscriptions(&self) -> impl Iterator<Item = (&str, QoS)> + '_ {
+        self.subscriptions
+            .iter()
+            .map(|(filter, qos)| (filter.as_str(), *qos))
+    }
 
-        if connack_buf[0] != CONNACK {
+    /// The keep-alive interval agreed in CONNECT, in seconds.
+    ///
+    /// The broker may drop the session if it hears nothing from the client
+    /// for one and a half times this interval, so a client that is otherwise
+    /// idle should call [`ping`](Self::ping) at least this often. Zero means
+    /// keep-alive is disabled; it is also what a client created with
+    /// [`from_connected`](Client::from_connected) reports, since the interval
+    /// of that session is not known.
+    pub fn keep_alive_seconds(&self) -> u16 {
+        self.keep_alive_seconds
+    }
+
+    /// Send a PINGREQ and wait for the broker's PINGRESP.
+    ///
+    /// Keeps an idle session alive (see
+    /// [`keep_alive_seconds`](Self::keep_alive_seconds)) and confirms the
+    /// broker is still reachable.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotOpen`] - The client is not connected
+    /// * [`Error::WriteError`] - Failed to send the PINGREQ
+    /// * [`Error::ReadError`] - Failed to read the PINGRESP
+    /// * [`Error::ConnectionClosed`] - Connection closed before the PINGRESP
+    /// * [`Error::ProtocolError`] - The next packet was not a PINGRESP
+    pub fn ping(&mut self) -> Result<(), Error> {
+        self.ensure_connected()?;
+
+        write_all(&mut self.connection, &[PINGREQ, 0x00])?;
+        self.connection.flush().map_err(|_| Error::WriteError)?;
+
+        let mut pingresp_buf = [0u8; 2];
+        read_exact(&mut self.connection, &mut pingresp_buf).map_err(|e| self.track_closed(e))?;
+        if pingresp_buf != [PINGRESP, 0x00] {
             return Err(Error::ProtocolError);
         }
+        Ok(())
+    }
 
-        if connack_buf[1] != 2 {
-            return Err(Error::ProtocolError);
+    /// End the MQTT session cleanly and hand back the connection.
+    ///
+    /// Sends DISCONNECT so the broker discards the session's Last Will
+    /// instead of publishing it, e.g. before the device goes to sleep. MQTT
+    /// 3.1.1 defines no acknowledgement for DISCONNECT, so nothing is read;
+    /// the broker closes its side once the packet arrives. The returned
+    /// connection can then be closed or used for a later
+    /// [`connect`](Client::connect).
+    ///
+    /// If the client is already disconnected nothing is sent and the
+    /// connection is returned as is.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::WriteError`] - Failed to send the DISCONNECT; the
+    ///   connection is dropped
+    pub fn disconnect(mut self) -> Result<C, Error> {
+        if self.is_connected {
+            write_all(&mut self.connection, &[DISCONNECT, 0x00])?;
+            self.connection.flush().map_err(|_| Error::WriteError)?;
         }
+        Ok(self.connection)
+    }
 
-        // Check connection acknowledgement status
-        match connack_buf[3] {
-            0 => Ok(Self {
-                connection,
-                is_connected: true,
-            }),
-            1..=5 => Err(Error::ConnectionRefused),
-            _ => Err(Error::ProtocolError),
+    fn ensure_connected(&self) -> Result<(), Error> {
+        if self.is_connected {
+            Ok(())
+        } else {
+            Err(Error::NotOpen)
         }
     }
 
@@ -468,7 +783,27 @@ impl<C: Connection> Client<C> {
     /// # Errors
     ///
     /// * [`Error::WriteError`] - Failed to send the publish packet
-    /// * [`Error::ProtocolError`] - Invalid topic name or payload too large
+    /// * [`Error::ProtocolError`] - Invalid topic name or payload too large,
+    ///   or the broker answered with an unexpected packet or identifier
+    /// * [`Error::ReadError`] - Failed to read an acknowledgement
+    /// * [`Error::ConnectionClosed`] - Connection closed before the exchange
+    ///   completed
+    ///
+    /// # Acknowledgement
+    ///
+    /// QoS 1 and 2 messages carry a packet identifier, taken from a counter
+    /// that runs from 1 to 65535 and wraps back to 1. This call blocks until
+    /// the broker has acknowledged that identifier:
+    ///
+    /// - **QoS 1**: PUBLISH, then the broker's PUBACK. `Ok(())` means the
+    ///   broker has taken responsibility for the message.
+    /// - **QoS 2**: PUBLISH, the broker's PUBREC, a PUBREL from the client,
+    ///   then the broker's PUBCOMP. `Ok(())` means the message has been
+    ///   delivered exactly once.
+    ///
+    /// Each acknowledgement must be the next packet received: an inbound
+    /// PUBLISH arriving in between fails the call with
+    /// [`Error::ProtocolError`].
     ///
     /// # Topic Naming Rules
     ///
@@ -499,7 +834,7 @@ impl<C: Connection> Client<C> {
     /// #     type Error = ();
     /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
     /// # }
-    /// # let mut client = Client { connection: MockConnection, is_connected: true };
+    /// # let mut client = Client::from_connected(MockConnection);
     ///
     /// // Publish sensor readings
     /// // client.publish("sensors/temperature", b"23.5", QoS::AtMostOnce)?;
@@ -510,28 +845,88 @@ impl<C: Connection> Client<C> {
     /// // client.publish("devices/sensor01/data", json_data, QoS::AtLeastOnce)?;
     /// ```
     pub fn publish(&mut self, topic: &str, payload: &[u8], qos: QoS) -> Result<(), Error> {
+        self.publish_with(topic, payload, qos, false)
+    }
+
+    /// Publish a message the broker keeps as the topic's retained message.
+    ///
+    /// Works like [`publish`](Self::publish) with the RETAIN flag set. The
+    /// broker stores the message, replacing any earlier retained message on
+    /// the topic, and delivers it immediately to every client that
+    /// subscribes later, which suits device status or shadow topics where a
+    /// new subscriber needs the current value rather than the next change.
+    /// Publishing an empty retained payload clears the stored message.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`publish`](Self::publish).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use libiot::network::application::mqtt::client::{Client, QoS};
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection;
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> { Ok(buf.len()) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # let mut client = Client::from_connected(MockConnection);
+    ///
+    /// client
+    ///     .publish_retained("devices/sensor01/status", b"online", QoS::AtMostOnce)
+    ///     .unwrap();
+    /// ```
+    pub fn publish_retained(&mut self, topic: &str, payload: &[u8], qos: QoS) -> Result<(), Error> {
+        self.publish_with(topic, payload, qos, true)
+    }
+
+    fn publish_with(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: QoS,
+        retain: bool,
+    ) -> Result<(), Error> {
         self.ensure_connected()?;
 
         let mut fixed_header: Vec<u8, 5> = Vec::new();
-        let mut packet: Vec<u8, 1024> = Vec::new();
+        let mut packet: Vec<u8, PAYLOAD> = Vec::new();
 
         // --- Variable Header ---
         let topic_bytes = topic.as_bytes();
+        let topic_len = u16::try_from(topic_bytes.len()).map_err(|_| Error::ProtocolError)?;
         packet
-            .extend_from_slice(&(topic_bytes.len() as u16).to_be_bytes())
-            .unwrap();
-        packet.extend_from_slice(topic_bytes).unwrap();
+            .extend_from_slice(&topic_len.to_be_bytes())
+            .and_then(|_| packet.extend_from_slice(topic_bytes))
+            .map_err(|_| Error::ProtocolError)?;
+        let packet_id = self.publish_packet_id(qos);
+        if let Some(id) = packet_id {
+            packet
+                .extend_from_slice(&id.to_be_bytes())
+                .map_err(|_| Error::ProtocolError)?;
+        }
 
         // --- Payload ---
-        packet.extend_from_slice(payload).unwrap();
+        packet
+            .extend_from_slice(payload)
+            .map_err(|_| Error::ProtocolError)?;
 
         // --- Fixed Header ---
-        let mut flags = PUBLISH;
-        if qos == QoS::AtLeastOnce || qos == QoS::ExactlyOnce {
-            flags |= (qos as u8) << 1;
-        }
-        fixed_header.push(flags).unwrap();
-        encode_remaining_length(&mut fixed_header, packet.len()).unwrap();
+        fixed_header.push(publish_flags(qos, retain)).unwrap();
+        fixed_header
+            .extend_from_slice(&encode_remaining_length(packet.len())?)
+            .unwrap();
 
         // Write to connection
         self.connection
@@ -542,6 +937,145 @@ impl<C: Connection> Client<C> {
             .map_err(|_| Error::WriteError)?;
         self.connection.flush().map_err(|_| Error::WriteError)?;
 
+        self.complete_publish(qos, packet_id)
+    }
+
+    /// Publish a message whose payload is assembled from several chunks.
+    ///
+    /// Works like [`publish`](Self::publish), but the payload is written to
+    /// the connection chunk by chunk instead of being copied into a single
+    /// buffer first. This lets a payload composed from fragments (e.g. a JSON
+    /// document built from a prefix, a formatted value and a suffix) be sent
+    /// without a scratch buffer.
+    ///
+    /// The total payload length must be known before anything is written, so
+    /// the iterator must be [`Clone`]: it is walked once to sum the chunk
+    /// lengths for the remaining-length field, then again to write them.
+    /// Iterators over slices, arrays and `Vec`s of `&[u8]` are all cheap to
+    /// clone.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotOpen`] - The client is not connected
+    /// * [`Error::ProtocolError`] - Topic longer than 65535 bytes or packet
+    ///   larger than the maximum remaining length
+    /// * [`Error::WriteError`] - Failed to send the packet
+    ///
+    /// QoS 1 and 2 publishes wait for the broker's acknowledgements and can
+    /// fail like [`publish`](Self::publish).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use libiot::network::application::mqtt::client::{Client, QoS};
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection;
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> { Ok(buf.len()) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # let mut client = Client::from_connected(MockConnection);
+    ///
+    /// let reading = b"23.5";
+    /// let parts: [&[u8]; 3] = [br#"{"temp":"#, reading, b"}"];
+    /// client
+    ///     .publish_chunks("sensors/temperature", parts.iter().copied(), QoS::AtMostOnce)
+    ///     .unwrap();
+    /// ```
+    pub fn publish_chunks<'p, I>(&mut self, topic: &str, chunks: I, qos: QoS) -> Result<(), Error>
+    where
+        I: Iterator<Item = &'p [u8]> + Clone,
+    {
+        self.ensure_connected()?;
+
+        let topic_bytes = topic.as_bytes();
+        let topic_len = u16::try_from(topic_bytes.len()).map_err(|_| Error::ProtocolError)?;
+        let payload_len = chunks
+            .clone()
+            .try_fold(0usize, |total, chunk| total.checked_add(chunk.len()))
+            .ok_or(Error::ProtocolError)?;
+        let packet_id = self.publish_packet_id(qos);
+        let id_len = if packet_id.is_some() { 2 } else { 0 };
+        let remaining_len = (2 + topic_bytes.len() + id_len)
+            .checked_add(payload_len)
+            .ok_or(Error::ProtocolError)?;
+
+        // --- Fixed Header ---
+        let mut fixed_header: Vec<u8, 5> = Vec::new();
+        fixed_header.push(publish_flags(qos, false)).unwrap();
+        fixed_header
+            .extend_from_slice(&encode_remaining_length(remaining_len)?)
+            .unwrap();
+
+        // --- Variable Header, then the payload chunks ---
+        write_all(&mut self.connection, &fixed_header)?;
+        write_all(&mut self.connection, &topic_len.to_be_bytes())?;
+        write_all(&mut self.connection, topic_bytes)?;
+        if let Some(id) = packet_id {
+            write_all(&mut self.connection, &id.to_be_bytes())?;
+        }
+        for chunk in chunks {
+            write_all(&mut self.connection, chunk)?;
+        }
+        self.connection.flush().map_err(|_| Error::WriteError)?;
+
+        self.complete_publish(qos, packet_id)
+    }
+
+    /// Allocate the packet identifier for an outbound PUBLISH, if its QoS
+    /// needs one.
+    fn publish_packet_id(&mut self, qos: QoS) -> Option<u16> {
+        (qos != QoS::AtMostOnce).then(|| self.next_packet_id())
+    }
+
+    /// Allocate a packet identifier. Identifiers count up from 1 and skip 0
+    /// when they wrap.
+    fn next_packet_id(&mut self) -> u16 {
+        self.last_packet_id = self.last_packet_id.wrapping_add(1).max(1);
+        self.last_packet_id
+    }
+
+    /// Run the sender side of the QoS 1 or QoS 2 acknowledgement exchange.
+    fn complete_publish(&mut self, qos: QoS, packet_id: Option<u16>) -> Result<(), Error> {
+        let Some(packet_id) = packet_id else {
+            return Ok(());
+        };
+        match qos {
+            QoS::AtMostOnce => Ok(()),
+            QoS::AtLeastOnce => self.expect_ack(PUBACK, packet_id),
+            QoS::ExactlyOnce => {
+                self.expect_ack(PUBREC, packet_id)?;
+                self.send_ack(PUBREL, packet_id)?;
+                self.expect_ack(PUBCOMP, packet_id)
+            }
+        }
+    }
+
+    /// Mark the session disconnected if `e` means the peer closed the connection.
+    fn track_closed(&mut self, e: Error) -> Error {
+        if e == Error::ConnectionClosed {
+            self.is_connected = false;
+        }
+        e
+    }
+
+    /// Read a two-byte acknowledgement and check its type and identifier.
+    fn expect_ack(&mut self, packet_type: u8, packet_id: u16) -> Result<(), Error> {
+        let mut ack_buf = [0u8; 4];
+        read_exact(&mut self.connection, &mut ack_buf).map_err(|e| self.track_closed(e))?;
+        if ack_buf[0] != packet_type || ack_buf[1] != 2 || read_u16(&ack_buf, 2)? != packet_id {
+            return Err(Error::ProtocolError);
+        }
         Ok(())
     }
 
@@ -566,7 +1100,14 @@ impl<C: Connection> Client<C> {
     /// * [`Error::WriteError`] - Failed to send the subscribe packet
     /// * [`Error::ReadError`] - Failed to read SUBACK response
     /// * [`Error::ConnectionClosed`] - Connection closed during operation
-    /// * [`Error::ProtocolError`] - Invalid SUBACK packet or topic filter
+    /// * [`Error::ProtocolError`] - Invalid SUBACK packet or topic filter,
+    ///   the broker rejected the filter, or the subscription table already
+    ///   holds `SUBSCRIPTIONS` filters
+    ///
+    /// Acknowledged filters are recorded so [`resubscribe_all`](Self::resubscribe_all)
+    /// can restore them; subscribing to a recorded filter again updates its QoS.
+    /// To subscribe to several filters in one round trip use
+    /// [`subscribe_many`](Self::subscribe_many).
     ///
     /// # Topic Filter Wildcards
     ///
@@ -599,7 +1140,7 @@ impl<C: Connection> Client<C> {
     /// #     type Error = ();
     /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
     /// # }
-    /// # let mut client = Client { connection: MockConnection, is_connected: true };
+    /// # let mut client = Client::from_connected(MockConnection);
     ///
     /// // Subscribe to specific topic
     /// // client.subscribe("devices/sensor01/temperature", QoS::AtLeastOnce)?;
@@ -611,61 +1152,301 @@ impl<C: Connection> Client<C> {
     /// // client.subscribe("commands/#", QoS::ExactlyOnce)?;
     /// ```
     pub fn subscribe(&mut self, topic: &str, qos: QoS) -> Result<(), Error> {
-        self.ensure_connected()?;
-
-        let mut fixed_header: Vec<u8, 5> = Vec::new();
-        let mut packet: Vec<u8, 1024> = Vec::new();
-
-        // --- Variable Header (Packet Identifier) ---
-        let packet_id: u16 = 1; // Using a fixed packet ID for simplicity
-        packet.extend_from_slice(&packet_id.to_be_bytes()).unwrap();
+        self.subscribe_many(&[(topic, qos)])
+    }
 
-        // --- Payload ---
+    /// Subscribe to several topic filters with a single SUBSCRIBE packet.
+    ///
+    /// All filters and their requested QoS go into one packet, and the
+    /// broker answers with one SUBACK return code per filter, so the whole
+    /// set costs one round trip. Filters the broker accepts are recorded as
+    /// with [`subscribe`](Self::subscribe). Does nothing if `topics` is empty.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ProtocolError`] - More than `SUBSCRIPTIONS` filters,
+    ///   an invalid filter, or not enough room left in the subscription
+    ///   table; nothing is sent in these cases
+    /// * [`Error::ProtocolError`] - The broker rejected at least one filter
+    ///   (return code `0x80`). The accepted filters are still active and
+    ///   recorded.
+    /// * Otherwise the same as [`subscribe`](Self::subscribe)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use libiot::network::application::mqtt::client::{Client, QoS};
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection;
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, _buf: &[u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # let mut client = Client::from_connected(MockConnection);
+    ///
+    /// // client.subscribe_many(&[
+    /// //     ("commands/#", QoS::AtLeastOnce),
+    /// //     ("config/device01", QoS::ExactlyOnce),
+    /// //     ("broadcast", QoS::AtMostOnce),
+    /// // ])?;
+    /// ```
+    pub fn subscribe_many(&mut self, topics: &[(&str, QoS)]) -> Result<(), Error> {
+        self.ensure_connected()?;
+        if topics.is_empty() {
+            return Ok(());
+        }
+
+        // Validate everything before sending, so a bad filter costs nothing
+        let mut filters: Vec<String<TOPIC>, SUBSCRIPTIONS> = Vec::new();
+        for (topic, _) in topics {
+            let filter = String::try_from(*topic).map_err(|_| Error::ProtocolError)?;
+            filters.push(filter).map_err(|_| Error::ProtocolError)?;
+        }
+        let added = filters
+            .iter()
+            .enumerate()
+            .filter(|(i, filter)| {
+                !filters[..*i].contains(filter)
+                    && !self.subscriptions.iter().any(|(f, _)| f == *filter)
+            })
+            .count();
+        if self.subscriptions.len() + added > SUBSCRIPTIONS {
+            return Err(Error::ProtocolError);
+        }
+
+        let return_codes = self.send_subscribe(topics.iter().copied())?;
+
+        for ((filter, (_, qos)), code) in filters.into_iter().zip(topics).zip(&return_codes) {
+            if *code == SUBACK_FAILURE {
+                continue;
+            }
+            match self.subscriptions.iter_mut().find(|(f, _)| *f == filter) {
+                Some(entry) => entry.1 = *qos,
+                // Capacity was checked before subscribing
+                None => self.subscriptions.push((filter, *qos)).unwrap(),
+            }
+        }
+        if return_codes.contains(&SUBACK_FAILURE) {
+            return Err(Error::ProtocolError);
+        }
+        Ok(())
+    }
+
+    /// Unsubscribe from a topic filter.
+    ///
+    /// Sends an UNSUBSCRIBE packet, waits for the broker's UNSUBACK and
+    /// removes the filter from the subscription table so it is no longer
+    /// restored by [`resubscribe_all`](Self::resubscribe_all). The packet
+    /// identifier comes from the same counter as QoS 1 and 2 publishes, and
+    /// the UNSUBACK must echo it.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic filter to unsubscribe from, exactly as subscribed
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::WriteError`] - Failed to send the unsubscribe packet
+    /// * [`Error::ReadError`] - Failed to read UNSUBACK response
+    /// * [`Error::ConnectionClosed`] - Connection closed during operation
+    /// * [`Error::ProtocolError`] - Invalid UNSUBACK packet, mismatched
+    ///   packet identifier, or a topic filter longer than `TOPIC` bytes
+    pub fn unsubscribe(&mut self, topic: &str) -> Result<(), Error> {
+        self.ensure_connected()?;
+
+        // Filters longer than `TOPIC` bytes cannot have been subscribed
         let topic_bytes = topic.as_bytes();
-        packet
-            .extend_from_slice(&(topic_bytes.len() as u16).to_be_bytes())
-            .unwrap();
-        packet.extend_from_slice(topic_bytes).unwrap();
-        packet.push(qos as u8).unwrap();
+        if topic_bytes.len() > TOPIC {
+            return Err(Error::ProtocolError);
+        }
+        let topic_len = u16::try_from(topic_bytes.len()).map_err(|_| Error::ProtocolError)?;
 
         // --- Fixed Header ---
-        fixed_header.push(SUBSCRIBE).unwrap();
-        encode_remaining_length(&mut fixed_header, packet.len()).unwrap();
+        let mut fixed_header: Vec<u8, 5> = Vec::new();
+        fixed_header.push(UNSUBSCRIBE).unwrap();
+        fixed_header
+            .extend_from_slice(&encode_remaining_length(2 + 2 + topic_bytes.len())?)
+            .unwrap();
 
-        // Write to connection
-        self.connection
-            .write(&fixed_header)
-            .map_err(|_| Error::WriteError)?;
-        self.connection
-            .write(&packet)
-            .map_err(|_| Error::WriteError)?;
+        // --- Variable Header (Packet Identifier), then the filter ---
+        let packet_id = self.next_packet_id();
+        write_all(&mut self.connection, &fixed_header)?;
+        write_all(&mut self.connection, &packet_id.to_be_bytes())?;
+        write_all(&mut self.connection, &topic_len.to_be_bytes())?;
+        write_all(&mut self.connection, topic_bytes)?;
         self.connection.flush().map_err(|_| Error::WriteError)?;
 
-        // Wait for SUBACK
-        let mut suback_buf = [0u8; 5];
-        let mut total_read = 0;
-        while total_read < suback_buf.len() {
-            match self.connection.read(&mut suback_buf[total_read..]) {
-                Ok(0) => {
-                    self.is_connected = false;
-                    return Err(Error::ConnectionClosed);
-                }
-                Ok(n) => total_read += n,
-                Err(_) => return Err(Error::ReadError),
-            }
+        // Wait for UNSUBACK
+        let mut unsuback_buf = [0u8; 4];
+        read_exact(&mut self.connection, &mut unsuback_buf).map_err(|e| self.track_closed(e))?;
+        if unsuback_buf[0] != UNSUBACK
+            || unsuback_buf[1] != 2
+            || read_u16(&unsuback_buf, 2)? != packet_id
+        {
+            return Err(Error::ProtocolError);
+        }
+
+        self.subscriptions
+            .retain(|(filter, _)| filter.as_str() != topic);
+        Ok(())
+    }
+
+    /// Restore every stored subscription in a single SUBSCRIBE packet.
+    ///
+    /// The packet is written straight to the connection, so a full table of
+    /// `TOPIC`-byte filters needs no buffer.
+    ///
+    /// Brokers forget subscriptions when a session is not resumed, so this
+    /// should be called after re-establishing a connection.
+    /// [`reconnect`](Self::reconnect) does so automatically when the broker
+    /// reports no session present. Does nothing if there are no subscriptions.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`subscribe`](Self::subscribe). Filters the broker rejects
+    /// stay in the table.
+    pub fn resubscribe_all(&mut self) -> Result<(), Error> {
+        self.ensure_connected()?;
+        if self.subscriptions.is_empty() {
+            return Ok(());
+        }
+
+        let packet_id = self.next_packet_id();
+        let filters = self
+            .subscriptions
+            .iter()
+            .map(|(filter, qos)| (filter.as_str(), *qos));
+        write_subscribe(&mut self.connection, packet_id, filters)?;
+        let return_codes = self.read_suback(packet_id, self.subscriptions.len())?;
+        if return_codes.contains(&SUBACK_FAILURE) {
+            return Err(Error::ProtocolError);
+        }
+        Ok(())
+    }
+
+    /// Re-establish the MQTT session over a new connection.
+    ///
+    /// Performs the CONNECT/CONNACK exchange on `connection` and, on success,
+    /// replaces the client's (dropped) connection with it, keeping the
+    /// subscription table. If the broker did not resume a previous session,
+    /// all stored subscriptions are restored with
+    /// [`resubscribe_all`](Self::resubscribe_all); inbound QoS 2 messages
+    /// still awaiting release are discarded along with the old session.
+    ///
+    /// On failure the client is left disconnected.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - A fresh network connection to the MQTT broker
+    /// * `options` - Connection configuration options
+    ///
+    /// # Errors
+    ///
+    /// Same as [`connect`](Client::connect) and
+    /// [`resubscribe_all`](Self::resubscribe_all).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use libiot::network::application::mqtt::client::{Client, Options, QoS};
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection;
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, _buf: &[u8]) -> Result<usize, Self::Error> { Ok(0) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # let mut client = Client::from_connected(MockConnection);
+    /// # let options = Options { client_id: "node", keep_alive_seconds: 60, clean_session: true, max_packet_size: None };
+    /// client.subscribe("commands/#", QoS::AtLeastOnce).unwrap();
+    ///
+    /// // ... the link drops, a new socket is opened ...
+    /// client.reconnect(MockConnection, options).unwrap();
+    /// // "commands/#" is subscribed again
+    /// ```
+    pub fn reconnect(&mut self, mut connection: C, options: Options) -> Result<(), Error> {
+        self.is_connected = false;
+        let session_present = handshake(&mut connection, &options)?;
+
+        self.connection = connection;
+        self.is_connected = true;
+        self.max_packet_size = options.max_packet_size;
+        self.keep_alive_seconds = options.keep_alive_seconds;
+        self.rx_buf.clear();
+        if let Some(pos) = self.released_qos2.take() {
+            self.inbound_qos2.swap_remove(pos);
         }
+        if !session_present {
+            self.inbound_qos2.clear();
+            self.resubscribe_all()?;
+        }
+        Ok(())
+    }
+
+    /// Send a SUBSCRIBE for `filters` and wait for the matching SUBACK.
+    fn send_subscribe<'t>(
+        &mut self,
+        filters: impl Iterator<Item = (&'t str, QoS)> + Clone,
+    ) -> Result<Vec<u8, SUBSCRIPTIONS>, Error> {
+        let packet_id = self.next_packet_id();
+        write_subscribe(&mut self.connection, packet_id, filters.clone())?;
+        self.read_suback(packet_id, filters.count())
+    }
 
-        if suback_buf[0] != SUBACK {
+    /// Wait for the SUBACK to the SUBSCRIBE with `packet_id` and return its
+    /// return codes, checking there is one per filter.
+    fn read_suback(
+        &mut self,
+        packet_id: u16,
+        filter_count: usize,
+    ) -> Result<Vec<u8, SUBSCRIPTIONS>, Error> {
+        // Wait for SUBACK: packet identifier, then one return code per filter
+        let mut header = [0u8; 1];
+        read_exact(&mut self.connection, &mut header).map_err(|e| self.track_closed(e))?;
+        if header[0] != SUBACK {
+            return Err(Error::ProtocolError);
+        }
+        let remaining_len = self.read_remaining_length()?;
+        let mut return_codes: Vec<u8, SUBSCRIPTIONS> = Vec::new();
+        if remaining_len != 2 + filter_count || return_codes.resize(filter_count, 0).is_err() {
             return Err(Error::ProtocolError);
         }
+        let mut id = [0u8; 2];
+        read_exact(&mut self.connection, &mut id).map_err(|e| self.track_closed(e))?;
+        read_exact(&mut self.connection, &mut return_codes).map_err(|e| self.track_closed(e))?;
 
         // Check packet identifier
-        let suback_packet_id = u16::from_be_bytes([suback_buf[2], suback_buf[3]]);
-        if suback_packet_id != packet_id {
+        if u16::from_be_bytes(id) != packet_id {
             return Err(Error::ProtocolError);
         }
 
-        Ok(())
+        // Granted QoS 0-2, or a failure
+        if return_codes
+            .iter()
+            .any(|&code| code > 2 && code != SUBACK_FAILURE)
+        {
+            return Err(Error::ProtocolError);
+        }
+        Ok(return_codes)
     }
 
     /// Poll the connection for incoming PUBLISH messages.
@@ -683,7 +1464,31 @@ impl<C: Connection> Client<C> {
     /// # Errors
     ///
     /// * [`Error::ReadError`] - Failed to read from the connection
-    /// * [`Error::ProtocolError`] - Received malformed MQTT packet
+    /// * [`Error::WriteError`] - Failed to send a QoS 1 or QoS 2
+    ///   acknowledgement
+    /// * [`Error::ProtocolError`] - Received malformed MQTT packet (including a
+    ///   topic that is not valid UTF-8 or contains U+0000), or more than
+    ///   [`MAX_INBOUND_QOS2`] QoS 2 messages are awaiting release
+    /// * [`Error::ProtocolError`] - The packet is larger than
+    ///   [`Options::max_packet_size`] or the receive buffer. The client is
+    ///   then marked disconnected, since the rest of the packet is left
+    ///   unread; reconnect to continue.
+    ///
+    /// # At-least-once Delivery
+    ///
+    /// An inbound QoS 1 PUBLISH is acknowledged with a PUBACK carrying its
+    /// packet identifier before it is returned, so the broker stops
+    /// redelivering it. The message is therefore acknowledged even if the
+    /// application fails to process it.
+    ///
+    /// # Exactly-once Delivery
+    ///
+    /// Inbound QoS 2 messages follow the receiver side of the four-way
+    /// handshake. On PUBLISH the message is stored and a PUBREC is sent, and
+    /// `Ok(None)` is returned. Retransmissions with the same packet identifier
+    /// are acknowledged again but not stored twice. The message is returned
+    /// exactly once, when the broker's PUBREL arrives, after the PUBCOMP has
+    /// been sent.
     ///
     /// # Usage Pattern
     ///
@@ -708,7 +1513,7 @@ impl<C: Connection> Client<C> {
     /// #     type Error = ();
     /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
     /// # }
-    /// # let mut client = Client { connection: MockConnection, is_connected: true };
+    /// # let mut client = Client::from_connected(MockConnection);
     ///
     /// // Message processing loop
     /// // loop {
@@ -740,92 +1545,505 @@ impl<C: Connection> Client<C> {
     /// This method is non-blocking and will return `Ok(None)` immediately if
     /// no data is available. For blocking behavior, call it in a loop with
     /// appropriate delays.
-    pub fn poll(&mut self) -> Result<Option<PublishPacket>, Error> {
+    pub fn poll(&mut self) -> Result<Option<PublishPacket<PAYLOAD, TOPIC>>, Error> {
+        match self.poll_ref()? {
+            Some(message) => message.to_packet().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Poll for an incoming PUBLISH message without copying it.
+    ///
+    /// Behaves exactly like [`poll`](Self::poll), including the QoS 2
+    /// handshake, but returns a [`PublishRef`] that borrows the topic and
+    /// payload from the client's internal receive buffer. The borrow ends
+    /// before the client can be used again, which is when the buffer is reused.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(message))` - A publish message was received
+    /// * `Ok(None)` - No message available at this time
+    /// * `Err(error)` - Network or protocol error occurred
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::network::application::mqtt::client::Client;
+    /// # use libiot::network::Connection;
+    /// # struct MockConnection(&'static [u8]);
+    /// # impl Connection for MockConnection {}
+    /// # impl libiot::network::Read for MockConnection {
+    /// #     type Error = ();
+    /// #     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+    /// #         let n = buf.len().min(self.0.len());
+    /// #         buf[..n].copy_from_slice(&self.0[..n]);
+    /// #         self.0 = &self.0[n..];
+    /// #         Ok(n)
+    /// #     }
+    /// # }
+    /// # impl libiot::network::Write for MockConnection {
+    /// #     type Error = ();
+    /// #     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> { Ok(buf.len()) }
+    /// #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # impl libiot::network::Close for MockConnection {
+    /// #     type Error = ();
+    /// #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+    /// # }
+    /// # let connection = MockConnection(&[0x30, 0x07, 0x00, 0x03, b'a', b'/', b'b', b'h', b'i']);
+    /// let mut client = Client::from_connected(connection);
+    ///
+    /// if let Some(message) = client.poll_ref().unwrap() {
+    ///     assert_eq!(message.topic, "a/b");
+    ///     assert_eq!(message.payload, b"hi");
+    /// }
+    /// ```
+    pub fn poll_ref(&mut self) -> Result<Option<PublishRef<'_>>, Error> {
+        self.poll_with(&mut |_, _| Ok(()))
+    }
+
+    /// [`poll_ref`](Self::poll_ref), calling `on_qos2` whenever the set of
+    /// pending inbound QoS 2 messages changes, before the acknowledgement
+    /// that commits to the change is sent.
+    fn poll_with(
+        &mut self,
+        on_qos2: &mut Qos2Hook<'_, C, PAYLOAD, TOPIC, SUBSCRIPTIONS>,
+    ) -> Result<Option<PublishRef<'_>>, Error> {
         self.ensure_connected()?;
 
+        // The QoS 2 message lent out by the previous call is no longer borrowed
+        if let Some(pos) = self.released_qos2.take() {
+            self.inbound_qos2.swap_remove(pos);
+        }
+
         let mut header_buf = [0u8; 1];
         match self.connection.read(&mut header_buf) {
-            Ok(0) => {
-                self.is_connected = false;
-                return Err(Error::ConnectionClosed);
-            }
+            Ok(0) => return Err(self.track_closed(Error::ConnectionClosed)),
             Ok(_) => {}
             Err(_) => return Err(Error::ReadError),
         }
 
-        if header_buf[0] & 0xF0 == PUBLISH {
-            let mut remaining_len_buf = [0u8; 4];
-            let mut remaining_len = 0;
-            let mut multiplier = 1;
-            let mut i = 0;
-            loop {
-                self.connection
-                    .read(&mut remaining_len_buf[i..i + 1])
-                    .map_err(|_| Error::ReadError)?;
-                remaining_len += (remaining_len_buf[i] as usize & 127) * multiplier;
-                multiplier *= 128;
-                if (remaining_len_buf[i] & 0x80) == 0 {
-                    break;
+        let remaining_len = self.read_remaining_length()?;
+        let limit = self.max_packet_size.map_or(self.rx_buf.capacity(), |max| {
+            max.min(self.rx_buf.capacity())
+        });
+        if remaining_len > limit {
+            // The body is left unread, so the stream cannot be resynchronized
+            self.is_connected = false;
+            return Err(Error::ProtocolError);
+        }
+        self.rx_buf.clear();
+        self.rx_buf
+            .resize(remaining_len, 0)
+            .map_err(|_| Error::ProtocolError)?;
+        read_exact(&mut self.connection, &mut self.rx_buf).map_err(|e| self.track_closed(e))?;
+
+        match header_buf[0] & 0xF0 {
+            PUBLISH => self.handle_publish(header_buf[0], on_qos2),
+            t if t == PUBREL & 0xF0 => self.handle_pubrel(on_qos2),
+            // A late PINGRESP carries nothing for the application
+            PINGRESP => Ok(None),
+            _ => Ok(None),
+        }
+    }
+
+    /// Parse the inbound PUBLISH in the receive buffer, running the receiver
+    /// side of QoS 2 if requested.
+    fn handle_publish(
+        &mut self,
+        header: u8,
+        on_qos2: &mut Qos2Hook<'_, C, PAYLOAD, TOPIC, SUBSCRIPTIONS>,
+    ) -> Result<Option<PublishRef<'_>>, Error> {
+        let qos = (header >> 1) & 0x03;
+
+        let topic_len = read_u16(&self.rx_buf, 0)? as usize;
+        let topic_end = 2 + topic_len;
+        let topic_bytes = self.rx_buf.get(2..topic_end).ok_or(Error::ProtocolError)?;
+        // Topics must be UTF-8 without U+0000; never trust the broker on this
+        let topic = core::str::from_utf8(topic_bytes).map_err(|_| Error::ProtocolError)?;
+        if topic.contains('\0') {
+            return Err(Error::ProtocolError);
+        }
+
+        let (packet_id, payload_start) = if qos > 0 {
+            (Some(read_u16(&self.rx_buf, topic_end)?), topic_end + 2)
+        } else {
+            (None, topic_end)
+        };
+
+        if let (2, Some(id)) = (qos, packet_id) {
+            // A retransmission keeps the first copy; only the PUBREC is repeated
+            if !self.inbound_qos2.iter().any(|(pending, _)| *pending == id) {
+                let publish = publish_ref(&self.rx_buf, topic_end, payload_start).to_packet()?;
+                self.inbound_qos2
+                    .push((id, publish))
+                    .map_err(|_| Error::ProtocolError)?;
+                if let Err(e) = on_qos2(self, None) {
+                    // Not stored, so the retransmission must run the hook again
+                    self.inbound_qos2.pop();
+                    return Err(e);
                 }
-                i += 1;
             }
+            self.send_ack(PUBREC, id)?;
+            return Ok(None);
+        }
+        if let (1, Some(id)) = (qos, packet_id) {
+            self.send_ack(PUBACK, id)?;
+        }
 
-            let mut packet_buf = Vec::<u8, 1024>::new();
-            packet_buf.resize(remaining_len, 0).unwrap();
-            self.connection
-                .read(&mut packet_buf)
-                .map_err(|_| Error::ReadError)?;
+        Ok(Some(publish_ref(&self.rx_buf, topic_end, payload_start)))
+    }
 
-            let topic_len = u16::from_be_bytes([packet_buf[0], packet_buf[1]]) as usize;
-            let topic =
-                String::from_utf8(Vec::from_slice(&packet_buf[2..2 + topic_len]).unwrap()).unwrap();
+    /// Complete an inbound QoS 2 exchange and release the stored message.
+    fn handle_pubrel(
+        &mut self,
+        on_qos2: &mut Qos2Hook<'_, C, PAYLOAD, TOPIC, SUBSCRIPTIONS>,
+    ) -> Result<Option<PublishRef<'_>>, Error> {
+        let id = read_u16(&self.rx_buf, 0)?;
+        let pos = self
+            .inbound_qos2
+            .iter()
+            .position(|(pending, _)| *pending == id);
+        if pos.is_some() {
+            on_qos2(self, Some(id))?;
+        }
+        // Acknowledge before releasing, so a failed write leaves the message
+        // pending for the broker's PUBREL retransmission.
+        self.send_ack(PUBCOMP, id)?;
+        let Some(pos) = pos else {
+            return Ok(None);
+        };
+        // Freed at the start of the next poll, once the borrow has ended
+        self.released_qos2 = Some(pos);
+        Ok(Some(PublishRef::from(&self.inbound_qos2[pos].1)))
+    }
 
-            let payload_start = 2 + topic_len;
-            let payload = Vec::from_slice(&packet_buf[payload_start..]).unwrap();
+    /// Inbound QoS 2 messages still awaiting PUBREL, leaving out the one
+    /// released by the last poll and `releasing`, if any.
+    #[cfg(feature = "mqtt-session")]
+    pub(super) fn pending_qos2(
+        &self,
+        releasing: Option<u16>,
+    ) -> impl Iterator<Item = &(u16, PublishPacket<PAYLOAD, TOPIC>)> + '_ {
+        self.inbound_qos2
+            .iter()
+            .enumerate()
+            .filter(move |(pos, (id, _))| {
+                Some(*pos) != self.released_qos2 && Some(*id) != releasing
+            })
+            .map(|(_, entry)| entry)
+    }
 
-            Ok(Some(PublishPacket { topic, payload }))
-        } else {
-            Ok(None)
+    /// Connect like [`connect`](Client::connect), starting from persisted
+    /// session state.
+    ///
+    /// If the broker does not resume the session the pending QoS 2 messages
+    /// are dropped and the subscriptions are replayed, as in
+    /// [`reconnect`](Self::reconnect).
+    #[cfg(feature = "mqtt-session")]
+    pub(super) fn resume(
+        mut connection: C,
+        options: Options,
+        subscriptions: Vec<(String<TOPIC>, QoS), SUBSCRIPTIONS>,
+        inbound_qos2: Vec<(u16, PublishPacket<PAYLOAD, TOPIC>), MAX_INBOUND_QOS2>,
+    ) -> Result<Self, Error> {
+        let session_present = handshake(&mut connection, &options)?;
+        let mut client = Self {
+            inbound_qos2,
+            subscriptions,
+            max_packet_size: options.max_packet_size,
+            keep_alive_seconds: options.keep_alive_seconds,
+            ..Self::from_connected_with_buffers(connection)
+        };
+        if !session_present {
+            client.inbound_qos2.clear();
+            client.resubscribe_all()?;
+        }
+        Ok(client)
+    }
+
+    /// [`poll`](Self::poll) with a callback run on every change to the
+    /// pending inbound QoS 2 set; see `poll_with`.
+    #[cfg(feature = "mqtt-session")]
+    pub(super) fn poll_persisting(
+        &mut self,
+        on_qos2: &mut Qos2Hook<'_, C, PAYLOAD, TOPIC, SUBSCRIPTIONS>,
+    ) -> Result<Option<PublishPacket<PAYLOAD, TOPIC>>, Error> {
+        match self.poll_with(on_qos2)? {
+            Some(message) => message.to_packet().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Send a two-byte acknowledgement packet (PUBREC, PUBCOMP, ...).
+    fn send_ack(&mut self, packet_type: u8, packet_id: u16) -> Result<(), Error> {
+        let id = packet_id.to_be_bytes();
+        self.connection
+            .write(&[packet_type, 0x02, id[0], id[1]])
+            .map_err(|_| Error::WriteError)?;
+        self.connection.flush().map_err(|_| Error::WriteError)
+    }
+
+    /// Read the variable-length remaining length field of a fixed header.
+    fn read_remaining_length(&mut self) -> Result<usize, Error> {
+        let mut field = [0u8; 4];
+        for i in 0..field.len() {
+            read_exact(&mut self.connection, &mut field[i..=i])
+                .map_err(|e| self.track_closed(e))?;
+            if let Some((len, _)) = decode_remaining_length(&field[..=i])? {
+                return Ok(len);
+            }
+        }
+        Err(Error::ProtocolError)
+    }
+}
+
+/// Callback run by `poll_with` when the pending inbound QoS 2 set changes.
+///
+/// Receives the client and, when a message is about to be released by
+/// PUBCOMP, its packet id (the message is still in the table at that point).
+type Qos2Hook<'h, C, const PAYLOAD: usize, const TOPIC: usize, const SUBSCRIPTIONS: usize> =
+    dyn FnMut(&Client<C, PAYLOAD, TOPIC, SUBSCRIPTIONS>, Option<u16>) -> Result<(), Error> + 'h;
+
+/// Write a SUBSCRIBE: packet identifier, then each filter and its QoS.
+///
+/// The packet goes out piece by piece, so its size is bounded only by the
+/// maximum remaining length.
+fn write_subscribe<'t, C: Connection>(
+    connection: &mut C,
+    packet_id: u16,
+    filters: impl Iterator<Item = (&'t str, QoS)> + Clone,
+) -> Result<(), Error> {
+    let mut remaining_len = 2usize;
+    for (filter, _) in filters.clone() {
+        u16::try_from(filter.len()).map_err(|_| Error::ProtocolError)?;
+        remaining_len = remaining_len
+            .checked_add(2 + filter.len() + 1)
+            .ok_or(Error::ProtocolError)?;
+    }
+
+    // --- Fixed Header ---
+    let mut fixed_header: Vec<u8, 5> = Vec::new();
+    fixed_header.push(SUBSCRIBE).unwrap();
+    fixed_header
+        .extend_from_slice(&encode_remaining_length(remaining_len)?)
+        .unwrap();
+    write_all(connection, &fixed_header)?;
+
+    // --- Variable Header (Packet Identifier) ---
+    write_all(connection, &packet_id.to_be_bytes())?;
+
+    // --- Payload ---
+    for (filter, qos) in filters {
+        // Lengths were checked above
+        write_all(connection, &(filter.len() as u16).to_be_bytes())?;
+        write_all(connection, filter.as_bytes())?;
+        write_all(connection, &[qos as u8])?;
+    }
+    connection.flush().map_err(|_| Error::WriteError)
+}
+
+/// First byte of an outbound PUBLISH: packet type, then DUP (bit 3, never
+/// set since the client does not retransmit), QoS (bits 2-1) and RETAIN
+/// (bit 0).
+fn publish_flags(qos: QoS, retain: bool) -> u8 {
+    PUBLISH | ((qos as u8) << 1) | retain as u8
+}
+
+/// Send CONNECT on `connection` and wait for a successful CONNACK.
+///
+/// Returns the broker's session-present flag.
+fn handshake<C: Connection>(connection: &mut C, options: &Options) -> Result<bool, Error> {
+    // --- Variable Header ---
+    let mut vh: Vec<u8, 10> = Vec::new();
+    vh.extend_from_slice(&(PROTOCOL_NAME.len() as u16).to_be_bytes())
+        .unwrap();
+    vh.extend_from_slice(PROTOCOL_NAME).unwrap();
+    vh.push(PROTOCOL_LEVEL).unwrap();
+
+    let mut connect_flags = 0;
+    if options.clean_session {
+        connect_flags |= 0x02;
+    }
+    vh.push(connect_flags).unwrap();
+    vh.extend_from_slice(&options.keep_alive_seconds.to_be_bytes())
+        .unwrap();
+
+    // --- Payload ---
+    let mut payload: Vec<u8, 256> = Vec::new();
+    let client_id_bytes = options.client_id.as_bytes();
+    payload
+        .extend_from_slice(&(client_id_bytes.len() as u16).to_be_bytes())
+        .unwrap();
+    payload.extend_from_slice(client_id_bytes).unwrap();
+
+    let remaining_len = vh.len() + payload.len();
+
+    // --- Fixed Header ---
+    let mut fixed_header: Vec<u8, 5> = Vec::new();
+    fixed_header.push(CONNECT).unwrap();
+    fixed_header
+        .extend_from_slice(&encode_remaining_length(remaining_len)?)
+        .unwrap();
+
+    // Write packet to the connection
+    connection
+        .write(&fixed_header)
+        .map_err(|_| Error::WriteError)?;
+    connection.write(&vh).map_err(|_| Error::WriteError)?;
+    connection.write(&payload).map_err(|_| Error::WriteError)?;
+    connection.flush().map_err(|_| Error::WriteError)?;
+
+    // Wait for and parse CONNACK
+    let mut connack_buf = [0u8; 4];
+    let mut total_read = 0;
+    while total_read < connack_buf.len() {
+        match connection.read(&mut connack_buf[total_read..]) {
+            Ok(0) => return Err(Error::ConnectionClosed),
+            Ok(n) => total_read += n,
+            Err(_) => return Err(Error::ReadError),
+        }
+    }
+
+    if connack_buf[0] != CONNACK {
+        return Err(Error::ProtocolError);
+    }
+
+    if connack_buf[1] != 2 {
+        return Err(Error::ProtocolError);
+    }
+
+    // Check connection acknowledgement status
+    match connack_buf[3] {
+        0 => Ok(connack_buf[2] & 0x01 != 0),
+        1..=5 => Err(Error::ConnectionRefused),
+        _ => Err(Error::ProtocolError),
+    }
+}
+
+/// Fill `buf` completely from the connection.
+fn read_exact<C: Connection>(connection: &mut C, buf: &mut [u8]) -> Result<(), Error> {
+    let mut total_read = 0;
+    while total_read < buf.len() {
+        match connection.read(&mut buf[total_read..]) {
+            Ok(0) => return Err(Error::ConnectionClosed),
+            Ok(n) => total_read += n,
+            Err(_) => return Err(Error::ReadError),
+        }
+    }
+    Ok(())
+}
+
+/// Write all of `buf` to the connection.
+fn write_all<C: Connection>(connection: &mut C, mut buf: &[u8]) -> Result<(), Error> {
+    while !buf.is_empty() {
+        match connection.write(buf) {
+            Ok(0) | Err(_) => return Err(Error::WriteError),
+            Ok(n) => buf = &buf[n..],
         }
     }
+    Ok(())
+}
+
+/// View a PUBLISH body whose topic (already UTF-8 checked) ends at
+/// `topic_end` and whose payload starts at `payload_start`.
+fn publish_ref(packet: &[u8], topic_end: usize, payload_start: usize) -> PublishRef<'_> {
+    PublishRef {
+        topic: core::str::from_utf8(&packet[2..topic_end]).unwrap_or_default(),
+        payload: packet.get(payload_start..).unwrap_or_default(),
+    }
 }
 
+/// Read a big-endian `u16` at `offset`, failing on truncated packets.
+fn read_u16(buf: &[u8], offset: usize) -> Result<u16, Error> {
+    match buf.get(offset..offset + 2) {
+        Some(bytes) => Ok(u16::from_be_bytes([bytes[0], bytes[1]])),
+        None => Err(Error::ProtocolError),
+    }
+}
+
+/// Largest value the remaining length field can carry (`0xFF,0xFF,0xFF,0x7F`).
+pub const MAX_REMAINING_LENGTH: usize = 268_435_455;
+
 /// Encode the remaining length field for an MQTT packet.
 ///
 /// The remaining length field is a variable-length encoding scheme used in MQTT
-/// to specify the number of bytes following the fixed header. This is an internal
-/// utility function used by the client implementation.
+/// to specify the number of bytes following the fixed header. It is the inverse
+/// of [`decode_remaining_length`].
 ///
 /// # Arguments
 ///
-/// * `buf` - Buffer to write the encoded length to
 /// * `len` - Length value to encode
 ///
 /// # Returns
 ///
-/// * `Ok(())` - Length encoded successfully
-/// * `Err(())` - Buffer too small or length too large
+/// * `Ok(bytes)` - The encoded field, one to four bytes long
+/// * `Err(Error::ProtocolError)` - `len` exceeds [`MAX_REMAINING_LENGTH`]
 ///
 /// # Encoding Rules
 ///
 /// The encoding uses up to 4 bytes where each byte encodes 7 bits of the length
-/// value. The most significant bit indicates if another byte follows.
+/// value, least significant group first. The most significant bit indicates if
+/// another byte follows.
 ///
-/// This allows encoding values from 0 to 268,435,455 (0xFF,0xFF,0xFF,0x7F).
-fn encode_remaining_length(buf: &mut Vec<u8, 5>, mut len: usize) -> Result<(), ()> {
+/// # Examples
+///
+/// ```rust
+/// use libiot::network::application::mqtt::client::encode_remaining_length;
+///
+/// assert_eq!(&encode_remaining_length(127).unwrap()[..], [0x7F]);
+/// assert_eq!(&encode_remaining_length(128).unwrap()[..], [0x80, 0x01]);
+/// ```
+pub fn encode_remaining_length(mut len: usize) -> Result<Vec<u8, 4>, Error> {
+    if len > MAX_REMAINING_LENGTH {
+        return Err(Error::ProtocolError);
+    }
+    let mut buf = Vec::new();
     loop {
-        if buf.is_full() {
-            return Err(());
-        }
         let mut byte = (len % 128) as u8;
         len /= 128;
         if len > 0 {
             byte |= 0x80;
         }
-        buf.push(byte).unwrap(); // `is_full` check above ensures this won't panic
+        // At most four bytes are needed once `len` is in range
+        buf.push(byte).map_err(|_| Error::ProtocolError)?;
         if len == 0 {
-            break;
+            return Ok(buf);
         }
     }
-    Ok(())
+}
+
+/// Decode the remaining length field at the start of `bytes`.
+///
+/// # Returns
+///
+/// * `Ok(Some((len, used)))` - The decoded length and the number of bytes
+///   the field occupied
+/// * `Ok(None)` - `bytes` ends before the field is complete
+/// * `Err(Error::ProtocolError)` - The field runs past four bytes
+///
+/// # Examples
+///
+/// ```rust
+/// use libiot::network::application::mqtt::client::decode_remaining_length;
+///
+/// assert_eq!(decode_remaining_length(&[0x80, 0x01, 0xAA]), Ok(Some((128, 2))));
+/// assert_eq!(decode_remaining_length(&[0x80]), Ok(None));
+/// assert!(decode_remaining_length(&[0xFF, 0xFF, 0xFF, 0xFF, 0x01]).is_err());
+/// ```
+pub fn decode_remaining_length(bytes: &[u8]) -> Result<Option<(usize, usize)>, Error> {
+    let mut len = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        if i == 4 {
+            return Err(Error::ProtocolError);
+        }
+        len |= (byte as usize & 0x7F) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(Some((len, i + 1)));
+        }
+    }
+    if bytes.len() >= 4 {
+        return Err(Error::ProtocolError);
+    }
+    Ok(None)
 }
@@ -49,6 +49,7 @@
 //!     client_id: "iot_device_123",
 //!     keep_alive_seconds: 60,
 //!     clean_session: true,
+//!     max_packet_size: None,
 //! };
 //!
 //! // let mut client = Client::connect(connection, options)?;
@@ -62,3 +63,13 @@
 /// for MQTT communication, including message structures, configuration options,
 /// and Quality of Service definitions.
 pub mod client;
+
+/// Topic filter matching with `+` and `#` wildcards.
+pub mod topic;
+pub use topic::topic_matches;
+
+/// Session state persistence for resuming QoS 2 exchanges across resets.
+///
+/// Available with the `mqtt-session` feature.
+#[cfg(feature = "mqtt-session")]
+pub mod session;
@@ -0,0 +1,583 @@
+//! Persistent MQTT session state.
+//!
+//! With `clean_session` set to `false` the broker keeps the session across
+//! reconnects, but the device forgets its half of it on a reset: the
+//! subscription table and the inbound QoS 2 messages that were PUBREC'd but
+//! not yet released by PUBREL. [`PersistentClient`] mirrors that state into a
+//! [`Storage`] region through a [`SessionStore`] and restores it on
+//! [`connect`](PersistentClient::connect), so an exactly-once message accepted
+//! before a reboot is still delivered, once, after it.
+//!
+//! State is written whenever it changes, and for QoS 2 before the PUBREC or
+//! PUBCOMP that commits to the change is sent. Unchanged state is not
+//! rewritten, so polling does not wear the medium. With `clean_session` set
+//! to `true` there is nothing to resume and the store is never touched.
+//!
+//! # Storage Format
+//!
+//! The region is split into two equal slots, each holding one record:
+//!
+//! | Bytes     | Content                                                 |
+//! |-----------|---------------------------------------------------------|
+//! | 0..4      | Magic and format version, `b"MQS\x02"`                  |
+//! | 4..8      | Sequence number, big-endian `u32`                       |
+//! | 8..12     | Body length, big-endian `u32`                           |
+//! | 12..16    | CRC-32 of the sequence, length and body, big-endian     |
+//! | 16..      | Subscription count, then `qos`, `len: u16`, filter each |
+//! |           | QoS 2 count, then `id: u16`, topic, payload each        |
+//!
+//! The valid record with the higher sequence number is the current session.
+//! A save goes to the other slot: its header is invalidated first, then the
+//! body and finally the header with the next sequence number are written.
+//! A reset in the middle of a save leaves a record that fails its check, so
+//! the previous session is restored instead, never a partial one. The
+//! region must allow overwrites (EEPROM, FRAM, or a flash driver that
+//! handles erasing itself); size it with [`MAX_SESSION_LEN`].
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use libiot::network::application::mqtt::client::{Options, QoS};
+//! use libiot::network::application::mqtt::session::{PersistentClient, SessionStore};
+//! # use libiot::network::Connection;
+//! # struct TcpConnection;
+//! # impl Connection for TcpConnection {}
+//! # impl libiot::network::Read for TcpConnection {
+//! #     type Error = ();
+//! #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+//! # }
+//! # impl libiot::network::Write for TcpConnection {
+//! #     type Error = ();
+//! #     fn write(&mut self, _buf: &[u8]) -> Result<usize, Self::Error> { Ok(0) }
+//! #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+//! # }
+//! # impl libiot::network::Close for TcpConnection {
+//! #     type Error = ();
+//! #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+//! # }
+//! # struct Eeprom;
+//! # impl libiot::storage::ReadStorage for Eeprom {
+//! #     type Error = ();
+//! #     fn read(&mut self, _offset: u32, _bytes: &mut [u8]) -> Result<(), ()> { Ok(()) }
+//! #     fn capacity(&self) -> usize { 16 * 1024 }
+//! # }
+//! # impl libiot::storage::Storage for Eeprom {
+//! #     fn write(&mut self, _offset: u32, _bytes: &[u8]) -> Result<(), ()> { Ok(()) }
+//! # }
+//!
+//! let options = Options {
+//!     client_id: "valve_ctrl",
+//!     keep_alive_seconds: 60,
+//!     clean_session: false,
+//!     max_packet_size: None,
+//! };
+//! let store = SessionStore::new(Eeprom, 0, 8 * 1024);
+//!
+//! let mut client = PersistentClient::connect(TcpConnection, options, store).unwrap();
+//! client.subscribe("valves/+/set", QoS::ExactlyOnce).unwrap();
+//! while let Ok(message) = client.poll() {
+//!     // ...
+//! #   break;
+//! }
+//! ```
+
+use heapless::{String, Vec};
+
+use super::client::{Client, MAX_INBOUND_QOS2, MAX_SUBSCRIPTIONS, Options, PublishPacket, QoS};
+use crate::network::Connection;
+use crate::network::error as net_err;
+use crate::storage::Storage;
+use crate::storage::error as storage_err;
+use crate::util::Crc32;
+
+/// Magic bytes and format version at the start of a stored session
+const MAGIC: [u8; 4] = *b"MQS\x02";
+
+/// Magic, sequence number, body length and CRC
+const HEADER_LEN: usize = 16;
+
+/// Bytes staged in RAM per storage write while saving
+const WRITE_CHUNK: usize = 64;
+
+/// Region size needed to store a session with every subscription and
+/// pending QoS 2 slot filled to capacity, in both slots.
+pub const MAX_SESSION_LEN: usize = 2
+    * (HEADER_LEN
+        + 1
+        + MAX_SUBSCRIPTIONS * (1 + 2 + 256)
+        + 1
+        + MAX_INBOUND_QOS2 * (2 + 2 + 256 + 2 + 1024));
+
+type Subscriptions = Vec<(String<256>, QoS), MAX_SUBSCRIPTIONS>;
+type PendingQos2 = Vec<(u16, PublishPacket), MAX_INBOUND_QOS2>;
+
+/// Body length and body CRC of a stored record
+type Record = (u32, u32);
+
+/// Errors from a [`PersistentClient`]
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The MQTT operation failed
+    Network(net_err::Error),
+    /// Reading or writing the session region failed, or the session does
+    /// not fit in it
+    Storage(storage_err::Error),
+}
+
+impl From<net_err::Error> for Error {
+    fn from(e: net_err::Error) -> Self {
+        Error::Network(e)
+    }
+}
+
+impl From<storage_err::Error> for Error {
+    fn from(e: storage_err::Error) -> Self {
+        Error::Storage(e)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::Network(e) => defmt::write!(f, "Network({})", e),
+            Error::Storage(e) => defmt::write!(f, "Storage({})", e),
+        }
+    }
+}
+
+/// A [`Storage`] region holding one serialized MQTT session.
+pub struct SessionStore<S> {
+    storage: S,
+    offset: u32,
+    len: usize,
+    /// Body length and CRC of the record last read or written
+    saved: Option<Record>,
+    /// Slot holding the current record, and its sequence number
+    current: Option<usize>,
+    sequence: u32,
+}
+
+impl<S: Storage> SessionStore<S> {
+    /// Use `len` bytes of `storage` starting at `offset`, split into two
+    /// slots of `len / 2` bytes.
+    pub fn new(storage: S, offset: u32, len: usize) -> Self {
+        Self {
+            storage,
+            offset,
+            len,
+            saved: None,
+            current: None,
+            sequence: 0,
+        }
+    }
+
+    /// Get a shared reference to the underlying storage.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Consume the store and return the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// Forget the stored session, so the next connect starts empty.
+    pub fn clear(&mut self) -> Result<(), Error> {
+        for slot in 0..2 {
+            self.storage
+                .write(self.slot_offset(slot), &[0; MAGIC.len()])
+                .map_err(|_| storage_err::Error::WriteError)?;
+        }
+        self.storage
+            .sync()
+            .map_err(|_| storage_err::Error::WriteError)?;
+        self.saved = None;
+        self.current = None;
+        Ok(())
+    }
+
+    /// Size of each slot
+    fn slot_len(&self) -> usize {
+        self.len / 2
+    }
+
+    /// Start of `slot`
+    fn slot_offset(&self, slot: usize) -> u32 {
+        self.offset + (slot * self.slot_len()) as u32
+    }
+
+    /// Write the client's session state to the slot not holding the current
+    /// record, leaving out the QoS 2 message `releasing`. Does nothing if it
+    /// matches what is already stored.
+    fn save<C: Connection>(
+        &mut self,
+        client: &Client<C>,
+        releasing: Option<u16>,
+    ) -> Result<(), Error> {
+        let mut body_crc = Crc32::new();
+        let mut body_len = 0;
+        encode(client, releasing, &mut |bytes| {
+            body_crc.update(bytes);
+            body_len += bytes.len();
+            Ok(())
+        })?;
+        let record = (body_len as u32, body_crc.finalize());
+        if self.saved == Some(record) {
+            return Ok(());
+        }
+        if HEADER_LEN + body_len > self.slot_len() {
+            return Err(storage_err::Error::OutOfBounds.into());
+        }
+
+        let slot = match self.current {
+            Some(0) => 1,
+            _ => 0,
+        };
+        let sequence = self.sequence.wrapping_add(1);
+        let start = self.slot_offset(slot);
+
+        // Invalidate the slot first, so a torn body is never taken for the
+        // record that was there before
+        self.storage
+            .write(start, &[0; MAGIC.len()])
+            .map_err(|_| storage_err::Error::WriteError)?;
+        self.storage
+            .sync()
+            .map_err(|_| storage_err::Error::WriteError)?;
+
+        let mut header = [0u8; HEADER_LEN];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..8].copy_from_slice(&sequence.to_be_bytes());
+        header[8..12].copy_from_slice(&record.0.to_be_bytes());
+        let mut crc = Crc32::new();
+        crc.update(&header[4..12]);
+        let mut writer = StagedWriter {
+            storage: &mut self.storage,
+            offset: start + HEADER_LEN as u32,
+            staged: Vec::new(),
+        };
+        encode(client, releasing, &mut |bytes| {
+            crc.update(bytes);
+            writer.write(bytes)
+        })?;
+        writer.flush()?;
+        self.storage
+            .sync()
+            .map_err(|_| storage_err::Error::WriteError)?;
+
+        header[12..].copy_from_slice(&crc.finalize().to_be_bytes());
+        self.storage
+            .write(start, &header)
+            .map_err(|_| storage_err::Error::WriteError)?;
+        self.storage
+            .sync()
+            .map_err(|_| storage_err::Error::WriteError)?;
+        self.saved = Some(record);
+        self.current = Some(slot);
+        self.sequence = sequence;
+        Ok(())
+    }
+
+    /// Read the newest stored session. A missing, corrupt or foreign record
+    /// reads as `None`; only storage failures are errors.
+    fn load(&mut self) -> Result<Option<(Subscriptions, PendingQos2)>, Error> {
+        let mut newest: Option<(usize, u32, Record)> = None;
+        for slot in 0..2 {
+            if let Some((sequence, record)) = self.check_slot(slot)? {
+                if newest.is_none_or(|(_, current, _)| sequence > current) {
+                    newest = Some((slot, sequence, record));
+                }
+            }
+        }
+        let Some((slot, sequence, record)) = newest else {
+            return Ok(None);
+        };
+
+        let offset = self.slot_offset(slot) + HEADER_LEN as u32;
+        let mut reader = Reader {
+            storage: &mut self.storage,
+            offset,
+            remaining: record.0 as usize,
+            failed: false,
+        };
+        let state = decode(&mut reader);
+        if reader.failed {
+            return Err(storage_err::Error::ReadError.into());
+        }
+        self.current = Some(slot);
+        self.sequence = sequence;
+        if state.is_some() {
+            self.saved = Some(record);
+        }
+        Ok(state)
+    }
+
+    /// Sequence number, body length and body CRC of the record in `slot`,
+    /// or `None` if it holds no valid record
+    fn check_slot(&mut self, slot: usize) -> Result<Option<(u32, Record)>, Error> {
+        let mut header = [0u8; HEADER_LEN];
+        self.storage
+            .read(self.slot_offset(slot), &mut header)
+            .map_err(|_| storage_err::Error::ReadError)?;
+        if header[..4] != MAGIC {
+            return Ok(None);
+        }
+        let word =
+            |i: usize| u32::from_be_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+        let (sequence, body_len, crc) = (word(4), word(8), word(12));
+        if HEADER_LEN + body_len as usize > self.slot_len() {
+            return Ok(None);
+        }
+
+        // The record CRC also covers the header; `save` compares bodies only
+        let mut check = Crc32::new();
+        let mut body_crc = Crc32::new();
+        check.update(&header[4..12]);
+        let start = self.slot_offset(slot) + HEADER_LEN as u32;
+        let mut chunk = [0u8; WRITE_CHUNK];
+        let mut pos = 0;
+        while pos < body_len as usize {
+            let n = chunk.len().min(body_len as usize - pos);
+            self.storage
+                .read(start + pos as u32, &mut chunk[..n])
+                .map_err(|_| storage_err::Error::ReadError)?;
+            check.update(&chunk[..n]);
+            body_crc.update(&chunk[..n]);
+            pos += n;
+        }
+        let record = (body_len, body_crc.finalize());
+        Ok((check.finalize() == crc).then_some((sequence, record)))
+    }
+}
+
+/// An MQTT [`Client`] whose session state survives a reset.
+///
+/// Wraps the operations that change session state so each change reaches
+/// the [`SessionStore`]. Read-only access to the inner client is available
+/// through [`client`](Self::client); [`into_parts`](Self::into_parts) gives
+/// up persistence and returns the plain client.
+pub struct PersistentClient<C: Connection, S> {
+    client: Client<C>,
+    store: SessionStore<S>,
+    persist: bool,
+}
+
+impl<C: Connection, S: Storage> PersistentClient<C, S> {
+    /// Connect to the broker, restoring the session saved in `store`.
+    ///
+    /// When the broker resumes the session, stored QoS 2 messages are
+    /// released by the broker's PUBREL as usual. When it does not, they are
+    /// dropped and the stored subscriptions are sent again, as with
+    /// [`Client::reconnect`]. With `options.clean_session` set this is
+    /// [`Client::connect`] and `store` is left untouched.
+    pub fn connect(connection: C, options: Options, store: SessionStore<S>) -> Result<Self, Error> {
+        if options.clean_session {
+            return Ok(Self {
+                client: Client::connect(connection, options)?,
+                store,
+                persist: false,
+            });
+        }
+
+        let mut store = store;
+        let (subscriptions, pending) = store.load()?.unwrap_or_default();
+        let client = Client::resume(connection, options, subscriptions, pending)?;
+        let mut persistent = Self {
+            client,
+            store,
+            persist: true,
+        };
+        persistent.persist()?;
+        Ok(persistent)
+    }
+
+    /// Re-establish the session over a new connection; see
+    /// [`Client::reconnect`].
+    pub fn reconnect(&mut self, connection: C, options: Options) -> Result<(), Error> {
+        self.persist = !options.clean_session;
+        self.client.reconnect(connection, options)?;
+        self.persist()
+    }
+
+    /// The wrapped client.
+    pub fn client(&self) -> &Client<C> {
+        &self.client
+    }
+
+    /// Publish a message; see [`Client::publish`].
+    pub fn publish(&mut self, topic: &str, payload: &[u8], qos: QoS) -> Result<(), Error> {
+        Ok(self.client.publish(topic, payload, qos)?)
+    }
+
+    /// Subscribe to a topic filter and store the new subscription table.
+    pub fn subscribe(&mut self, topic: &str, qos: QoS) -> Result<(), Error> {
+        self.client.subscribe(topic, qos)?;
+        self.persist()
+    }
+
+    /// Unsubscribe from a topic filter and store the new subscription table.
+    pub fn unsubscribe(&mut self, topic: &str) -> Result<(), Error> {
+        self.client.unsubscribe(topic)?;
+        self.persist()
+    }
+
+    /// Poll for an incoming message; see [`Client::poll`].
+    ///
+    /// An inbound QoS 2 message is stored before it is acknowledged with
+    /// PUBREC, and removed from storage before PUBCOMP. If the store cannot
+    /// be written the acknowledgement is withheld and the storage error is
+    /// returned; the broker's retransmission retries the exchange.
+    pub fn poll(&mut self) -> Result<Option<PublishPacket>, Error> {
+        let persist = self.persist;
+        let store = &mut self.store;
+        let mut failure = None;
+        let result = self.client.poll_persisting(&mut |client, releasing| {
+            if !persist {
+                return Ok(());
+            }
+            store.save(client, releasing).map_err(|e| {
+                failure = Some(e);
+                net_err::Error::WriteError
+            })
+        });
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        Ok(result?)
+    }
+
+    /// Split into the plain client and the store.
+    pub fn into_parts(self) -> (Client<C>, SessionStore<S>) {
+        (self.client, self.store)
+    }
+
+    fn persist(&mut self) -> Result<(), Error> {
+        if self.persist {
+            self.store.save(&self.client, None)?;
+        }
+        Ok(())
+    }
+}
+
+/// Feed the serialized session body to `sink`, piece by piece.
+fn encode<C: Connection>(
+    client: &Client<C>,
+    releasing: Option<u16>,
+    sink: &mut dyn FnMut(&[u8]) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let field = |sink: &mut dyn FnMut(&[u8]) -> Result<(), Error>, bytes: &[u8]| {
+        sink(&(bytes.len() as u16).to_be_bytes())?;
+        sink(bytes)
+    };
+
+    sink(&[client.subscriptions().count() as u8])?;
+    for (filter, qos) in client.subscriptions() {
+        sink(&[qos as u8])?;
+        field(sink, filter.as_bytes())?;
+    }
+
+    sink(&[client.pending_qos2(releasing).count() as u8])?;
+    for (id, packet) in client.pending_qos2(releasing) {
+        sink(&id.to_be_bytes())?;
+        field(sink, packet.topic.as_bytes())?;
+        field(sink, &packet.payload)?;
+    }
+    Ok(())
+}
+
+/// Parse a session body, or `None` if it is malformed or cannot be read.
+fn decode<S: Storage>(reader: &mut Reader<'_, S>) -> Option<(Subscriptions, PendingQos2)> {
+    let mut subscriptions = Vec::new();
+    for _ in 0..reader.u8()? {
+        let qos = match reader.u8()? {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            2 => QoS::ExactlyOnce,
+            _ => return None,
+        };
+        let filter = String::from_utf8(reader.field()?).ok()?;
+        subscriptions.push((filter, qos)).ok()?;
+    }
+
+    let mut pending = Vec::new();
+    for _ in 0..reader.u8()? {
+        let id = u16::from_be_bytes([reader.u8()?, reader.u8()?]);
+        let topic = String::from_utf8(reader.field()?).ok()?;
+        let payload = reader.field()?;
+        pending.push((id, PublishPacket { topic, payload })).ok()?;
+    }
+
+    (reader.remaining == 0).then_some((subscriptions, pending))
+}
+
+/// Sequential reader over a session body in storage.
+struct Reader<'a, S> {
+    storage: &'a mut S,
+    offset: u32,
+    remaining: usize,
+    /// Set when the storage itself failed, as opposed to a malformed body
+    failed: bool,
+}
+
+impl<S: Storage> Reader<'_, S> {
+    fn fill(&mut self, buf: &mut [u8]) -> Option<()> {
+        if buf.len() > self.remaining {
+            return None;
+        }
+        if self.storage.read(self.offset, buf).is_err() {
+            self.failed = true;
+            return None;
+        }
+        self.offset += buf.len() as u32;
+        self.remaining -= buf.len();
+        Some(())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        let mut byte = [0u8; 1];
+        self.fill(&mut byte)?;
+        Some(byte[0])
+    }
+
+    /// A length-prefixed field of at most `N` bytes.
+    fn field<const N: usize>(&mut self) -> Option<Vec<u8, N>> {
+        let len = u16::from_be_bytes([self.u8()?, self.u8()?]) as usize;
+        let mut bytes = Vec::new();
+        bytes.resize(len, 0).ok()?;
+        self.fill(&mut bytes)?;
+        Some(bytes)
+    }
+}
+
+/// Buffers small writes into `WRITE_CHUNK`-sized storage writes.
+struct StagedWriter<'a, S> {
+    storage: &'a mut S,
+    offset: u32,
+    staged: Vec<u8, WRITE_CHUNK>,
+}
+
+impl<S: Storage> StagedWriter<'_, S> {
+    fn write(&mut self, mut bytes: &[u8]) -> Result<(), Error> {
+        while !bytes.is_empty() {
+            let n = (self.staged.capacity() - self.staged.len()).min(bytes.len());
+            // Capacity was checked above
+            self.staged.extend_from_slice(&bytes[..n]).unwrap();
+            bytes = &bytes[n..];
+            if self.staged.is_full() {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.storage
+            .write(self.offset, &self.staged)
+            .map_err(|_| storage_err::Error::WriteError)?;
+        self.offset += self.staged.len() as u32;
+        self.staged.clear();
+        Ok(())
+    }
+}
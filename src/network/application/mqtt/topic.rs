@@ -0,0 +1,53 @@
+//! Topic filter matching.
+//!
+//! Applications that route incoming messages through their own dispatch
+//! table, keyed by the filters they subscribed with, need the same matching
+//! the broker applies. [`topic_matches`] implements the MQTT 3.1.1 rules
+//! (section 4.7).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::network::application::mqtt::topic_matches;
+//!
+//! assert!(topic_matches("sensors/+/temperature", "sensors/room1/temperature"));
+//! assert!(topic_matches("sensors/#", "sensors/room1/humidity"));
+//! assert!(!topic_matches("sensors/+", "sensors/room1/humidity"));
+//! ```
+
+/// Whether `topic` matches the subscription `filter`.
+///
+/// Levels are separated by `/` and compared exactly, except for the
+/// wildcards:
+///
+/// - `+` matches exactly one level, which may be empty.
+/// - `#` matches any number of levels, including none, so `sport/#` also
+///   matches `sport`. It must be the last level of the filter.
+///
+/// A filter whose first level is a wildcard does not match topics starting
+/// with `$`, such as the broker's `$SYS/...` statistics; those need a
+/// filter that names the `$` level explicitly.
+///
+/// Invalid input never matches: an empty filter or topic, a `#` that is not
+/// the last level, a wildcard sharing a level with other characters
+/// (`sport+`), or a topic containing a wildcard.
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    if filter.is_empty() || topic.is_empty() || topic.contains(['+', '#']) {
+        return false;
+    }
+    if filter.starts_with(['+', '#']) && topic.starts_with('$') {
+        return false;
+    }
+
+    let mut filter_levels = filter.split('/');
+    let mut topic_levels = topic.split('/');
+    loop {
+        match (filter_levels.next(), topic_levels.next()) {
+            (Some("#"), _) => return filter_levels.next().is_none(),
+            (Some("+"), Some(_)) => {}
+            (Some(f), Some(t)) if f == t && !f.contains(['+', '#']) => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
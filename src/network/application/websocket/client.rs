@@ -0,0 +1,451 @@
+//! WebSocket client (RFC 6455) over any [`Connection`].
+
+use super::sha1::Sha1;
+use crate::network::Connection;
+use crate::network::application::http::base64;
+use crate::network::error::Error;
+use heapless::String;
+
+/// GUID the server appends to the key when computing `Sec-WebSocket-Accept`.
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Longest handshake response, status line and headers included.
+const MAX_HANDSHAKE_LEN: usize = 1024;
+
+/// Largest payload a control frame may carry.
+const MAX_CONTROL_PAYLOAD: usize = 125;
+
+/// Status code reported for a Close frame that carries none.
+///
+/// RFC 6455 reserves it for this purpose; it is never sent.
+pub const CLOSE_NO_STATUS: u16 = 1005;
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// A frame received from the server.
+///
+/// Payloads borrow the client's receive buffer and are valid until the next
+/// call on the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame<'a> {
+    /// A text message, checked to be valid UTF-8.
+    Text(&'a str),
+    /// A binary message.
+    Binary(&'a [u8]),
+    /// A ping, already answered with a pong carrying the same payload.
+    Ping(&'a [u8]),
+    /// A pong, normally answering an earlier [`Client::ping`].
+    Pong(&'a [u8]),
+    /// The server is closing the connection.
+    ///
+    /// The client has already echoed the Close, so no more frames can be
+    /// sent; the server will close the underlying connection.
+    Close {
+        /// Status code, or [`CLOSE_NO_STATUS`] if the frame carried none.
+        code: u16,
+        /// Reason given by the server, possibly empty.
+        reason: &'a str,
+    },
+}
+
+/// The `Sec-WebSocket-Accept` value a server must answer `key` with.
+///
+/// This is the base64 encoding of the SHA-1 digest of `key` followed by the
+/// RFC 6455 GUID.
+///
+/// # Examples
+///
+/// ```rust
+/// use libiot::network::application::websocket::accept_key;
+///
+/// // The example from RFC 6455, section 1.3
+/// assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+/// ```
+pub fn accept_key(key: &str) -> String<28> {
+    let mut sha1 = Sha1::new();
+    sha1.update(key.as_bytes());
+    sha1.update(ACCEPT_GUID.as_bytes());
+    let mut accept = String::new();
+    // 20 bytes always encode to 28 characters
+    let _ = base64::encode_into(sha1.finalize(), &mut accept);
+    accept
+}
+
+/// A WebSocket client connection.
+///
+/// `N` is the largest payload, in bytes, of a frame that can be received;
+/// see [`read_frame`](Self::read_frame). Frames of any size can be sent.
+///
+/// Only unfragmented messages are supported: a received frame must have
+/// its FIN bit set.
+pub struct Client<C: Connection, const N: usize = 1024> {
+    connection: C,
+    // xorshift32 state for the handshake key and frame masks
+    rng: u32,
+    buffer: [u8; N],
+    // Set once a Close frame is sent; no frame may follow it
+    close_sent: bool,
+}
+
+impl<C: Connection, const N: usize> Client<C, N> {
+    /// Open a WebSocket over `connection` with the HTTP Upgrade handshake.
+    ///
+    /// `host` is sent as the `Host` header and `path` as the request target,
+    /// e.g. `"/chat"`. `seed` must be a fresh random value, such as one read
+    /// from a hardware RNG: the `Sec-WebSocket-Key` and the mask of every
+    /// frame sent are derived from it, and RFC 6455 requires both to be
+    /// unpredictable.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ConnectionRefused`] - The server answered with a status
+    ///   other than `101 Switching Protocols`
+    /// * [`Error::ProtocolError`] - The response is malformed, too long, or
+    ///   does not confirm the upgrade with the expected `Sec-WebSocket-Accept`
+    /// * [`Error::WriteError`], [`Error::ReadError`],
+    ///   [`Error::ConnectionClosed`] - The connection failed
+    pub fn connect(connection: C, host: &str, path: &str, seed: u32) -> Result<Self, Error> {
+        let mut client = Self {
+            connection,
+            // xorshift never leaves zero
+            rng: if seed == 0 { 0x9E37_79B9 } else { seed },
+            buffer: [0; N],
+            close_sent: false,
+        };
+
+        let mut nonce = [0u8; 16];
+        for chunk in nonce.chunks_exact_mut(4) {
+            chunk.copy_from_slice(&client.next_random().to_be_bytes());
+        }
+        let mut key: String<24> = String::new();
+        base64::encode_into(nonce, &mut key).map_err(|_| Error::WriteError)?;
+
+        for part in [
+            "GET ",
+            path,
+            " HTTP/1.1\r\nHost: ",
+            host,
+            "\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: ",
+            &key,
+            "\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        ] {
+            write_all(&mut client.connection, part.as_bytes())?;
+        }
+        client.connection.flush().map_err(|_| Error::WriteError)?;
+
+        client.read_handshake(&key)?;
+        Ok(client)
+    }
+
+    /// Send a text message in a single frame.
+    pub fn send_text(&mut self, text: &str) -> Result<(), Error> {
+        self.send_frame(OPCODE_TEXT, text.as_bytes())
+    }
+
+    /// Send a binary message in a single frame.
+    pub fn send_binary(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.send_frame(OPCODE_BINARY, data)
+    }
+
+    /// Send a ping, which the server answers with a pong.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::WriteError`] - `payload` is longer than 125 bytes, or
+    ///   writing failed
+    /// * [`Error::ConnectionClosed`] - The connection is closing
+    pub fn ping(&mut self, payload: &[u8]) -> Result<(), Error> {
+        if payload.len() > MAX_CONTROL_PAYLOAD {
+            return Err(Error::WriteError);
+        }
+        self.send_frame(OPCODE_PING, payload)
+    }
+
+    /// Close the WebSocket with a status `code` and `reason`.
+    ///
+    /// Sends a Close frame and reads until the server echoes it; data frames
+    /// that arrive in between are discarded. How long this waits is bounded
+    /// only by the connection's read timeout. Returns the connection, which
+    /// the caller can then close; RFC 6455 expects the server to close the
+    /// TCP connection first.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ProtocolError`] - `code` may not be sent: it is below 1000,
+    ///   reserved (1004, 1005, 1006, 1015), unassigned (1016 to 2999), or
+    ///   above 4999
+    /// * [`Error::WriteError`] - `reason` is longer than 123 bytes, or
+    ///   writing failed
+    /// * Any error from [`read_frame`](Self::read_frame) while waiting
+    pub fn close(mut self, code: u16, reason: &str) -> Result<C, Error> {
+        if !is_valid_close_code(code) {
+            return Err(Error::ProtocolError);
+        }
+        if reason.len() > MAX_CONTROL_PAYLOAD - 2 {
+            return Err(Error::WriteError);
+        }
+
+        if !self.close_sent {
+            let mut payload = [0u8; MAX_CONTROL_PAYLOAD];
+            payload[..2].copy_from_slice(&code.to_be_bytes());
+            payload[2..2 + reason.len()].copy_from_slice(reason.as_bytes());
+            self.send_frame(OPCODE_CLOSE, &payload[..2 + reason.len()])?;
+            while !matches!(self.read_frame()?, Frame::Close { .. }) {}
+        }
+        Ok(self.connection)
+    }
+
+    /// Read the next frame from the server.
+    ///
+    /// Control frames are returned like data frames, after being answered
+    /// as the protocol requires: a ping with a pong carrying the same
+    /// payload, and a Close with a Close echoing its status code.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ProtocolError`] - The frame is invalid: reserved bits or
+    ///   opcode, a masked or fragmented frame, a control frame over 125
+    ///   bytes, text that is not UTF-8, or a payload over `N` bytes. A data
+    ///   frame that is fragmented, too large, not UTF-8 or has a reserved
+    ///   opcode is consumed, so the next frame can still be read; after the
+    ///   other errors the connection should be dropped.
+    ///   A Close frame with a one-byte payload, a status code that may not
+    ///   be sent (see [`close`](Self::close)) or a reason that is not UTF-8
+    ///   is also rejected.
+    /// * [`Error::ReadError`], [`Error::ConnectionClosed`] - The connection
+    ///   failed
+    /// * [`Error::WriteError`] - Answering a ping or Close failed
+    pub fn read_frame(&mut self) -> Result<Frame<'_>, Error> {
+        let mut head = [0u8; 2];
+        read_exact(&mut self.connection, &mut head)?;
+        let fin = head[0] & 0x80 != 0;
+        let opcode = head[0] & 0x0F;
+        // Reserved bits without an extension, or a frame masked by the server
+        if head[0] & 0x70 != 0 || head[1] & 0x80 != 0 {
+            return Err(Error::ProtocolError);
+        }
+
+        let len = match head[1] & 0x7F {
+            126 => {
+                let mut len = [0u8; 2];
+                read_exact(&mut self.connection, &mut len)?;
+                u64::from(u16::from_be_bytes(len))
+            }
+            127 => {
+                let mut len = [0u8; 8];
+                read_exact(&mut self.connection, &mut len)?;
+                u64::from_be_bytes(len)
+            }
+            len => u64::from(len),
+        };
+        if opcode & 0x08 != 0 && (!fin || len > MAX_CONTROL_PAYLOAD as u64) {
+            return Err(Error::ProtocolError);
+        }
+        let len = match usize::try_from(len) {
+            Ok(len) if len <= N => len,
+            _ => {
+                self.discard(len)?;
+                return Err(Error::ProtocolError);
+            }
+        };
+        read_exact(&mut self.connection, &mut self.buffer[..len])?;
+        if !fin {
+            return Err(Error::ProtocolError);
+        }
+
+        match opcode {
+            OPCODE_PING if !self.close_sent => {
+                let mut payload = [0u8; MAX_CONTROL_PAYLOAD];
+                payload[..len].copy_from_slice(&self.buffer[..len]);
+                self.send_frame(OPCODE_PONG, &payload[..len])?;
+            }
+            OPCODE_CLOSE if !self.close_sent => {
+                let (code, _) = parse_close(&self.buffer[..len])?;
+                let code = code.to_be_bytes();
+                let echo = if len == 0 { &[][..] } else { &code[..] };
+                self.send_frame(OPCODE_CLOSE, echo)?;
+            }
+            _ => {}
+        }
+
+        let payload = &self.buffer[..len];
+        match opcode {
+            OPCODE_TEXT => core::str::from_utf8(payload)
+                .map(Frame::Text)
+                .map_err(|_| Error::ProtocolError),
+            OPCODE_BINARY => Ok(Frame::Binary(payload)),
+            OPCODE_CLOSE => {
+                parse_close(payload).map(|(code, reason)| Frame::Close { code, reason })
+            }
+            OPCODE_PING => Ok(Frame::Ping(payload)),
+            OPCODE_PONG => Ok(Frame::Pong(payload)),
+            // Continuation frames, which only follow a fragmented frame, and
+            // reserved opcodes
+            _ => Err(Error::ProtocolError),
+        }
+    }
+
+    /// Get the underlying connection
+    pub fn connection(&self) -> &C {
+        &self.connection
+    }
+
+    /// Get a mutable reference to the underlying connection
+    pub fn connection_mut(&mut self) -> &mut C {
+        &mut self.connection
+    }
+
+    /// Read the server's handshake response and check that it accepts `key`.
+    fn read_handshake(&mut self, key: &str) -> Result<(), Error> {
+        // Read a byte at a time so that no frame data after the headers is consumed
+        let mut response = [0u8; MAX_HANDSHAKE_LEN];
+        let mut len = 0;
+        while !response[..len].ends_with(b"\r\n\r\n") {
+            if len == response.len() {
+                return Err(Error::ProtocolError);
+            }
+            read_exact(&mut self.connection, &mut response[len..len + 1])?;
+            len += 1;
+        }
+        let response = core::str::from_utf8(&response[..len]).map_err(|_| Error::ProtocolError)?;
+
+        let mut lines = response.split("\r\n");
+        let status = lines
+            .next()
+            .and_then(|line| line.split(' ').nth(1))
+            .ok_or(Error::ProtocolError)?;
+        if status != "101" {
+            return Err(Error::ConnectionRefused);
+        }
+
+        let expected_accept = accept_key(key);
+        let (mut upgrade, mut connection, mut accept) = (false, false, false);
+        for (name, value) in lines.filter_map(|line| line.split_once(':')) {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("Upgrade") {
+                upgrade = value.eq_ignore_ascii_case("websocket");
+            } else if name.eq_ignore_ascii_case("Connection") {
+                connection = value
+                    .split(',')
+                    .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+            } else if name.eq_ignore_ascii_case("Sec-WebSocket-Accept") {
+                accept = value == expected_accept;
+            }
+        }
+        if upgrade && connection && accept {
+            Ok(())
+        } else {
+            Err(Error::ProtocolError)
+        }
+    }
+
+    /// Send one complete frame with a fresh mask.
+    fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<(), Error> {
+        if self.close_sent {
+            return Err(Error::ConnectionClosed);
+        }
+        self.close_sent = opcode == OPCODE_CLOSE;
+        let mask = self.next_random().to_be_bytes();
+        let mut header = [0u8; 14];
+        header[0] = 0x80 | opcode;
+        let mut header_len = 2;
+        match payload.len() {
+            len @ 0..=125 => header[1] = 0x80 | len as u8,
+            len @ 126..=0xFFFF => {
+                header[1] = 0x80 | 126;
+                header[2..4].copy_from_slice(&(len as u16).to_be_bytes());
+                header_len = 4;
+            }
+            len => {
+                header[1] = 0x80 | 127;
+                header[2..10].copy_from_slice(&(len as u64).to_be_bytes());
+                header_len = 10;
+            }
+        }
+        header[header_len..header_len + 4].copy_from_slice(&mask);
+        header_len += 4;
+        write_all(&mut self.connection, &header[..header_len])?;
+
+        // Mask through a small buffer; its length is a multiple of 4, so the
+        // mask lines up with the start of every chunk
+        let mut masked = [0u8; 64];
+        for chunk in payload.chunks(masked.len()) {
+            for (i, (out, byte)) in masked.iter_mut().zip(chunk).enumerate() {
+                *out = byte ^ mask[i % 4];
+            }
+            write_all(&mut self.connection, &masked[..chunk.len()])?;
+        }
+        self.connection.flush().map_err(|_| Error::WriteError)
+    }
+
+    /// Skip `len` payload bytes that do not fit the receive buffer.
+    fn discard(&mut self, mut len: u64) -> Result<(), Error> {
+        let mut scratch = [0u8; 64];
+        while len > 0 {
+            let n = len.min(scratch.len() as u64) as usize;
+            read_exact(&mut self.connection, &mut scratch[..n])?;
+            len -= n as u64;
+        }
+        Ok(())
+    }
+
+    /// Next value of the xorshift32 generator.
+    fn next_random(&mut self) -> u32 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        x
+    }
+}
+
+/// Split a Close payload into its status code and reason.
+fn parse_close(payload: &[u8]) -> Result<(u16, &str), Error> {
+    match payload {
+        [] => Ok((CLOSE_NO_STATUS, "")),
+        [high, low, reason @ ..] => {
+            let code = u16::from_be_bytes([*high, *low]);
+            let reason = core::str::from_utf8(reason).map_err(|_| Error::ProtocolError)?;
+            if is_valid_close_code(code) {
+                Ok((code, reason))
+            } else {
+                Err(Error::ProtocolError)
+            }
+        }
+        [_] => Err(Error::ProtocolError),
+    }
+}
+
+/// Whether `code` may appear in a Close frame (RFC 6455, section 7.4).
+fn is_valid_close_code(code: u16) -> bool {
+    matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999)
+}
+
+/// Fill `buf` completely from the connection.
+fn read_exact<C: Connection>(connection: &mut C, buf: &mut [u8]) -> Result<(), Error> {
+    let mut total_read = 0;
+    while total_read < buf.len() {
+        match connection.read(&mut buf[total_read..]) {
+            Ok(0) => return Err(Error::ConnectionClosed),
+            Ok(n) => total_read += n,
+            Err(_) => return Err(Error::ReadError),
+        }
+    }
+    Ok(())
+}
+
+/// Write all of `buf` to the connection.
+fn write_all<C: Connection>(connection: &mut C, mut buf: &[u8]) -> Result<(), Error> {
+    while !buf.is_empty() {
+        match connection.write(buf) {
+            Ok(0) | Err(_) => return Err(Error::WriteError),
+            Ok(n) => buf = &buf[n..],
+        }
+    }
+    Ok(())
+}
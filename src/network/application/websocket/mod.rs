@@ -1 +1,57 @@
+//! WebSocket client for embedded systems (RFC 6455).
+//!
+//! [`Client::connect`] upgrades an established connection, typically TCP or
+//! TLS, with the HTTP handshake and checks the server's
+//! `Sec-WebSocket-Accept`. Messages are then exchanged as single frames:
+//! outgoing frames are masked as the protocol requires of clients, and
+//! incoming frames are read into a fixed buffer whose size is the client's
+//! const generic parameter.
+//!
+//! Pings from the server are answered automatically while reading, and
+//! [`Client::ping`] checks that the server is still there.
+//! [`Client::close`] performs the closing handshake.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use libiot::network::application::websocket::{Client, Frame};
+//! # use libiot::network::Connection;
+//! # struct TcpConnection;
+//! # impl Connection for TcpConnection {}
+//! # impl libiot::network::Read for TcpConnection {
+//! #     type Error = ();
+//! #     fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> { Ok(0) }
+//! # }
+//! # impl libiot::network::Write for TcpConnection {
+//! #     type Error = ();
+//! #     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> { Ok(buf.len()) }
+//! #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+//! # }
+//! # impl libiot::network::Close for TcpConnection {
+//! #     type Error = ();
+//! #     fn close(self) -> Result<(), Self::Error> { Ok(()) }
+//! # }
+//! # fn hardware_random() -> u32 { 4 }
+//!
+//! let connection = TcpConnection;
+//! let mut ws: Client<_, 512> =
+//!     Client::connect(connection, "example.com", "/telemetry", hardware_random()).unwrap();
+//!
+//! ws.send_text(r#"{"temperature":21.5}"#).unwrap();
+//! match ws.read_frame().unwrap() {
+//!     Frame::Text(text) => { /* handle a command */ }
+//!     Frame::Close { code, reason } => { /* the server is going away */ }
+//!     _ => {}
+//! }
+//!
+//! let connection = ws.close(1000, "done").unwrap();
+//! ```
 
+/// WebSocket client and frame types.
+///
+/// Contains [`Client`](client::Client) and [`Frame`](client::Frame).
+pub mod client;
+
+mod sha1;
+
+pub use client::{CLOSE_NO_STATUS, Client, Frame, accept_key};
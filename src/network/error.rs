@@ -32,6 +32,7 @@
 /// }
 /// ```
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
 pub enum Error {
     /// An operation was attempted on a connection that is not open.
     ///
@@ -83,6 +84,30 @@ pub enum Error {
     /// - Protocol state violations
     /// - Invalid protocol parameters
     ProtocolError,
+
+    /// The operation could not complete without blocking.
+    ///
+    /// Returned by non-blocking transports (such as a UART with no pending
+    /// data) when the request cannot be satisfied right now. Unlike the other
+    /// variants this is not a failure; the operation should simply be retried
+    /// later.
+    WouldBlock,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Error::NotOpen => "connection not open",
+            Error::WriteError => "write failed",
+            Error::ReadError => "read failed",
+            Error::ConnectionRefused => "connection refused",
+            Error::Timeout => "operation timed out",
+            Error::ConnectionClosed => "connection closed",
+            Error::InvalidAddress => "invalid address",
+            Error::ProtocolError => "protocol error",
+            Error::WouldBlock => "operation would block",
+        })
+    }
 }
 
 #[cfg(feature = "defmt")]
@@ -97,6 +122,7 @@ impl defmt::Format for Error {
             Error::ConnectionClosed => defmt::write!(f, "ConnectionClosed"),
             Error::InvalidAddress => defmt::write!(f, "InvalidAddress"),
             Error::ProtocolError => defmt::write!(f, "ProtocolError"),
+            Error::WouldBlock => defmt::write!(f, "WouldBlock"),
         }
     }
 }
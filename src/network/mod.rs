@@ -70,6 +70,9 @@ pub mod application;
 /// OSI Layer 4: Transport layer implementations  
 pub mod transport;
 
+/// Retry and circuit-breaker helpers for fallible network operations
+pub mod retry;
+
 /// Re-exports of common traits for convenient importing
 pub mod prelude {
     #[cfg(feature = "async")]
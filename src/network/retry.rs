@@ -0,0 +1,248 @@
+//! Retry and circuit-breaker helpers for network operations.
+//!
+//! Network operations on embedded links fail transiently all the time: a
+//! modem drops a byte, a broker is restarting, a server times out. This
+//! module provides two small building blocks shared by protocol and user
+//! code instead of ad-hoc loops:
+//!
+//! - [`retry`] re-runs an operation according to a [`RetryPolicy`], with
+//!   exponential back-off between attempts.
+//! - [`CircuitBreaker`] stops calling an operation after a run of consecutive
+//!   failures and only lets a trial call through once a cooldown has elapsed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::network::error::Error;
+//! use libiot::network::retry::{RetryPolicy, retry};
+//! use libiot::system::clock::NoDelay;
+//!
+//! let mut attempts = 0;
+//! let result = retry(
+//!     || {
+//!         attempts += 1;
+//!         if attempts < 3 { Err(Error::Timeout) } else { Ok(attempts) }
+//!     },
+//!     &RetryPolicy::new(5),
+//!     &NoDelay,
+//! );
+//! assert_eq!(result, Ok(3));
+//! ```
+
+use crate::system::clock::{Delay, MonotonicClock};
+
+/// How often and how patiently to retry an operation.
+///
+/// The delay before retry `n` (1-based) is
+/// `initial_backoff_ms * multiplier^(n-1)`, capped at `max_backoff_ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. Zero behaves like one.
+    pub max_attempts: u32,
+    /// Delay before the first retry, in milliseconds.
+    pub initial_backoff_ms: u64,
+    /// Upper bound for any single delay, in milliseconds.
+    pub max_backoff_ms: u64,
+    /// Factor applied to the delay after each retry.
+    pub multiplier: u32,
+}
+
+impl RetryPolicy {
+    /// Retry up to `max_attempts` times in total, without waiting in between.
+    pub const fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff_ms: 0,
+            max_backoff_ms: 0,
+            multiplier: 1,
+        }
+    }
+
+    /// Use exponential back-off between attempts.
+    pub const fn with_backoff(
+        mut self,
+        initial_backoff_ms: u64,
+        max_backoff_ms: u64,
+        multiplier: u32,
+    ) -> Self {
+        self.initial_backoff_ms = initial_backoff_ms;
+        self.max_backoff_ms = max_backoff_ms;
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Delay to wait before retry number `retry` (1-based).
+    pub fn backoff_ms(&self, retry: u32) -> u64 {
+        let factor = (self.multiplier as u64).saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff_ms
+            .saturating_mul(factor)
+            .min(self.max_backoff_ms)
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, backing off 100 ms, then 200 ms (capped at 5 s).
+    fn default() -> Self {
+        Self::new(3).with_backoff(100, 5_000, 2)
+    }
+}
+
+/// Run `op` until it succeeds or the policy's attempts are exhausted.
+///
+/// Waits on `delay` between attempts as dictated by `policy`. Returns the
+/// first success, or the error of the last attempt.
+pub fn retry<T, E>(
+    mut op: impl FnMut() -> Result<T, E>,
+    policy: &RetryPolicy,
+    delay: &impl Delay,
+) -> Result<T, E> {
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt >= policy.max_attempts => return Err(e),
+            Err(_) => {
+                let wait = policy.backoff_ms(attempt);
+                if wait > 0 {
+                    delay.delay_ms(wait);
+                }
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// State of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls go through normally.
+    Closed,
+    /// Calls are rejected until the cooldown elapses.
+    Open,
+    /// The cooldown has elapsed; the next call is a trial.
+    HalfOpen,
+}
+
+/// Error returned by operations guarded by a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerError<E> {
+    /// The breaker is open; the operation was not attempted.
+    Open,
+    /// The operation ran and failed.
+    Failed(E),
+}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for BreakerError<E> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            BreakerError::Open => defmt::write!(f, "Open"),
+            BreakerError::Failed(e) => defmt::write!(f, "Failed({})", e),
+        }
+    }
+}
+
+/// Stops calling a failing operation for a cooldown period.
+///
+/// After `failure_threshold` consecutive failures the breaker opens and
+/// rejects calls with [`BreakerError::Open`]. Once `cooldown_ms` has passed
+/// it lets one trial call through: success closes it again, failure re-opens
+/// it for another cooldown.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker<C: MonotonicClock> {
+    clock: C,
+    failure_threshold: u32,
+    cooldown_ms: u64,
+    consecutive_failures: u32,
+    opened_at: Option<u64>,
+}
+
+impl<C: MonotonicClock> CircuitBreaker<C> {
+    /// Create a closed breaker.
+    ///
+    /// A `failure_threshold` of zero is treated as one.
+    pub fn new(clock: C, failure_threshold: u32, cooldown_ms: u64) -> Self {
+        Self {
+            clock,
+            failure_threshold: failure_threshold.max(1),
+            cooldown_ms,
+            consecutive_failures: 0,
+            opened_at: None,
+        }
+    }
+
+    /// Current state of the breaker.
+    pub fn state(&self) -> BreakerState {
+        match self.opened_at {
+            None => BreakerState::Closed,
+            Some(at) if self.clock.elapsed_ms(at) >= self.cooldown_ms => BreakerState::HalfOpen,
+            Some(_) => BreakerState::Open,
+        }
+    }
+
+    /// Number of failures recorded since the last success.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Record a successful call, closing the breaker.
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+    }
+
+    /// Record a failed call, opening the breaker once the threshold is reached.
+    pub fn record_failure(&mut self) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.opened_at.is_some() || self.consecutive_failures >= self.failure_threshold {
+            self.opened_at = Some(self.clock.now_ms());
+        }
+    }
+
+    /// Run `op` once if the breaker allows it, recording the outcome.
+    pub fn call<T, E>(&mut self, op: impl FnOnce() -> Result<T, E>) -> Result<T, BreakerError<E>> {
+        if self.state() == BreakerState::Open {
+            return Err(BreakerError::Open);
+        }
+        match op() {
+            Ok(value) => {
+                self.record_success();
+                Ok(value)
+            }
+            Err(e) => {
+                self.record_failure();
+                Err(BreakerError::Failed(e))
+            }
+        }
+    }
+
+    /// [`retry`] `op` through the breaker.
+    ///
+    /// Each attempt is recorded. Retrying stops early, returning the failure
+    /// that tripped it, as soon as the breaker opens; if the breaker is
+    /// already open nothing is attempted and [`BreakerError::Open`] is returned.
+    pub fn retry<T, E>(
+        &mut self,
+        mut op: impl FnMut() -> Result<T, E>,
+        policy: &RetryPolicy,
+        delay: &impl Delay,
+    ) -> Result<T, BreakerError<E>> {
+        let mut attempt = 1;
+        loop {
+            match self.call(&mut op) {
+                Ok(value) => return Ok(value),
+                Err(BreakerError::Open) => return Err(BreakerError::Open),
+                Err(e) if attempt >= policy.max_attempts || self.state() == BreakerState::Open => {
+                    return Err(e);
+                }
+                Err(_) => {
+                    let wait = policy.backoff_ms(attempt);
+                    if wait > 0 {
+                        delay.delay_ms(wait);
+                    }
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! AT-command modem transport.
+//!
+//! Cellular and Wi-Fi modules (SIMCom, Quectel, ...) expose TCP sockets
+//! through AT commands sent over a UART. This module turns such a modem into a
+//! [`Connect`] implementation: [`AtModem::connect`] issues the vendor's AT
+//! sequence to open a socket in transparent (data) mode and hands back an
+//! [`AtSocket`] whose [`Read`]/[`Write`] go straight to the remote peer.
+//!
+//! Since vendors differ slightly in syntax, the command set is described by
+//! [`AtCommands`]. Presets are provided for common modules and custom sets can
+//! be built for anything else.
+//!
+//! # Ownership
+//!
+//! In transparent mode the entire serial line *is* the socket, so the socket
+//! takes ownership of the serial connection while it is open. Use
+//! [`AtSocket::hang_up`] followed by [`AtSocket::into_inner`] and
+//! [`AtModem::attach`] to hand the serial line back to the modem for the next
+//! connection. Closing the socket through [`Close`] hangs up and drops the
+//! serial connection.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::network::transport::at_modem::{AtCommands, AtModem};
+//! use libiot::network::{Connect, Read, Write};
+//! # use libiot::network::{Close, Connection};
+//! # struct Uart { rx: &'static [u8] }
+//! # impl Connection for Uart {}
+//! # impl Read for Uart {
+//! #     type Error = ();
+//! #     fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+//! #         let n = buf.len().min(self.rx.len());
+//! #         buf[..n].copy_from_slice(&self.rx[..n]);
+//! #         self.rx = &self.rx[n..];
+//! #         Ok(n)
+//! #     }
+//! # }
+//! # impl Write for Uart {
+//! #     type Error = ();
+//! #     fn write(&mut self, buf: &[u8]) -> Result<usize, ()> { Ok(buf.len()) }
+//! #     fn flush(&mut self) -> Result<(), ()> { Ok(()) }
+//! # }
+//! # impl Close for Uart {
+//! #     type Error = ();
+//! #     fn close(self) -> Result<(), ()> { Ok(()) }
+//! # }
+//! # let uart = Uart { rx: b"OK\r\nOK\r\nOK\r\nCONNECT\r\nhello" };
+//!
+//! let mut modem = AtModem::new(uart, AtCommands::SIMCOM);
+//! let mut socket = modem.connect("example.com:80").unwrap();
+//!
+//! socket.write(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+//! let mut buf = [0u8; 5];
+//! let n = socket.read(&mut buf).unwrap();
+//! assert_eq!(&buf[..n], b"hello");
+//! ```
+
+use core::fmt::Write as _;
+
+use heapless::{String, Vec};
+
+use crate::network::error::Error;
+use crate::network::{Close, Connect, Connection, Read, Write};
+
+/// Maximum length of a rendered AT command, including the trailing `\r\n`.
+pub const MAX_COMMAND_LEN: usize = 128;
+
+/// Maximum length of a single response line kept while waiting for a result.
+const MAX_LINE_LEN: usize = 64;
+
+/// Default number of consecutive empty reads tolerated while waiting for a
+/// response before giving up with [`Error::Timeout`].
+pub const DEFAULT_MAX_IDLE_READS: u32 = 10_000;
+
+/// Response fragments that indicate a command failed.
+const ERROR_TOKENS: [&str; 3] = ["ERROR", "FAIL", "NO CARRIER"];
+
+/// Vendor-specific AT command set.
+///
+/// Commands are given without the trailing `\r\n`, which is appended when
+/// they are sent. The `open` command is a template in which `{host}` and
+/// `{port}` are substituted with the remote address passed to
+/// [`AtModem::connect`].
+#[derive(Debug, Clone, Copy)]
+pub struct AtCommands<'a> {
+    /// Commands sent before opening each socket; each must answer `OK`.
+    pub init: &'a [&'a str],
+    /// Template of the command that opens a TCP socket in transparent mode.
+    pub open: &'a str,
+    /// Response fragment signalling that data mode has been entered.
+    pub connected: &'a str,
+    /// Sequence that switches the modem from data mode back to command mode.
+    pub escape: &'a str,
+    /// Command that closes the socket once back in command mode.
+    pub close: &'a str,
+}
+
+impl AtCommands<'static> {
+    /// Command set for SIMCom modules (SIM800/SIM7000 family).
+    pub const SIMCOM: Self = Self {
+        init: &["ATE0", "AT+CIPMODE=1", "AT+CIPMUX=0"],
+        open: "AT+CIPSTART=\"TCP\",\"{host}\",\"{port}\"",
+        connected: "CONNECT",
+        escape: "+++",
+        close: "AT+CIPCLOSE",
+    };
+
+    /// Command set for Quectel modules (BG96/EC2x family).
+    pub const QUECTEL: Self = Self {
+        init: &["ATE0"],
+        open: "AT+QIOPEN=1,0,\"TCP\",\"{host}\",{port},0,2",
+        connected: "CONNECT",
+        escape: "+++",
+        close: "AT+QICLOSE=0",
+    };
+}
+
+/// An AT-command modem that opens TCP sockets over a serial connection.
+///
+/// # Type Parameters
+///
+/// * `C` - The serial connection to the modem, e.g. a
+///   `SerialConnection` from the `serial` transport
+pub struct AtModem<'a, C: Connection> {
+    serial: Option<C>,
+    commands: AtCommands<'a>,
+    max_idle_reads: u32,
+}
+
+impl<'a, C: Connection> AtModem<'a, C> {
+    /// Create a modem driver over a serial connection.
+    pub fn new(serial: C, commands: AtCommands<'a>) -> Self {
+        Self {
+            serial: Some(serial),
+            commands,
+            max_idle_reads: DEFAULT_MAX_IDLE_READS,
+        }
+    }
+
+    /// Set how many consecutive empty reads are tolerated while waiting for
+    /// a response before failing with [`Error::Timeout`].
+    pub fn with_max_idle_reads(mut self, max_idle_reads: u32) -> Self {
+        self.max_idle_reads = max_idle_reads;
+        self
+    }
+
+    /// Give the serial connection back to the modem after a socket was hung up.
+    pub fn attach(&mut self, serial: C) {
+        self.serial = Some(serial);
+    }
+
+    /// Check whether the modem currently owns its serial connection.
+    ///
+    /// Returns `false` while a socket opened by [`connect`](Connect::connect)
+    /// holds the serial line.
+    pub fn is_attached(&self) -> bool {
+        self.serial.is_some()
+    }
+}
+
+impl<'a, C: Connection> Connect for AtModem<'a, C> {
+    type Connection = AtSocket<'a, C>;
+    type Error = Error;
+
+    /// Open a TCP socket to `remote`, given as `host:port`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotOpen`] - The serial connection is held by another socket
+    /// * [`Error::InvalidAddress`] - `remote` is not a valid `host:port` pair
+    /// * [`Error::ProtocolError`] - An initialization command was rejected
+    /// * [`Error::ConnectionRefused`] - The modem failed to open the socket
+    /// * [`Error::Timeout`] - The modem stopped responding
+    fn connect(&mut self, remote: &str) -> Result<Self::Connection, Self::Error> {
+        let (host, port) = remote.rsplit_once(':').ok_or(Error::InvalidAddress)?;
+        if host.is_empty() || port.parse::<u16>().is_err() {
+            return Err(Error::InvalidAddress);
+        }
+        let open = render(self.commands.open, host, port)?;
+
+        let mut serial = self.serial.take().ok_or(Error::NotOpen)?;
+        let result = (|| {
+            for command in self.commands.init {
+                send_command(&mut serial, command)?;
+                expect(&mut serial, "OK", self.max_idle_reads).map_err(|e| match e {
+                    Error::ConnectionRefused => Error::ProtocolError,
+                    other => other,
+                })?;
+            }
+            send_command(&mut serial, &open)?;
+            expect(&mut serial, self.commands.connected, self.max_idle_reads)
+        })();
+
+        match result {
+            Ok(()) => Ok(AtSocket {
+                serial,
+                commands: self.commands,
+                max_idle_reads: self.max_idle_reads,
+            }),
+            Err(e) => {
+                self.serial = Some(serial);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// A TCP socket opened through an [`AtModem`], operating in data mode.
+pub struct AtSocket<'a, C: Connection> {
+    serial: C,
+    commands: AtCommands<'a>,
+    max_idle_reads: u32,
+}
+
+impl<'a, C: Connection> AtSocket<'a, C> {
+    /// Leave data mode and close the socket, keeping the serial connection.
+    ///
+    /// Most modems require a guard time of silence around the escape
+    /// sequence; callers should avoid writing to the socket immediately
+    /// before hanging up.
+    pub fn hang_up(&mut self) -> Result<(), Error> {
+        self.serial
+            .write(self.commands.escape.as_bytes())
+            .map_err(|_| Error::WriteError)?;
+        self.serial.flush().map_err(|_| Error::WriteError)?;
+        expect(&mut self.serial, "OK", self.max_idle_reads)?;
+        send_command(&mut self.serial, self.commands.close)?;
+        expect(&mut self.serial, "OK", self.max_idle_reads)
+    }
+
+    /// Consume the socket and return the serial connection.
+    ///
+    /// Call [`hang_up`](Self::hang_up) first if the modem should be returned
+    /// to command mode.
+    pub fn into_inner(self) -> C {
+        self.serial
+    }
+}
+
+impl<C: Connection> Read for AtSocket<'_, C> {
+    type Error = Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.serial.read(buf).map_err(|_| Error::ReadError)
+    }
+}
+
+impl<C: Connection> Write for AtSocket<'_, C> {
+    type Error = Error;
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.serial.write(buf).map_err(|_| Error::WriteError)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.serial.flush().map_err(|_| Error::WriteError)
+    }
+}
+
+impl<C: Connection> Close for AtSocket<'_, C> {
+    type Error = Error;
+
+    /// Hang up and drop the serial connection.
+    fn close(mut self) -> Result<(), Self::Error> {
+        self.hang_up()
+    }
+}
+
+impl<C: Connection> Connection for AtSocket<'_, C> {}
+
+/// Substitute `{host}` and `{port}` in a command template.
+fn render(template: &str, host: &str, port: &str) -> Result<String<MAX_COMMAND_LEN>, Error> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let (literal, tail) = rest.split_at(start);
+        out.push_str(literal).map_err(|_| Error::ProtocolError)?;
+        let (value, tail) = if let Some(tail) = tail.strip_prefix("{host}") {
+            (host, tail)
+        } else if let Some(tail) = tail.strip_prefix("{port}") {
+            (port, tail)
+        } else {
+            ("{", &tail[1..])
+        };
+        out.push_str(value).map_err(|_| Error::ProtocolError)?;
+        rest = tail;
+    }
+    out.push_str(rest).map_err(|_| Error::ProtocolError)?;
+    Ok(out)
+}
+
+/// Send a command terminated by `\r\n`.
+fn send_command<C: Connection>(serial: &mut C, command: &str) -> Result<(), Error> {
+    let mut line: String<MAX_COMMAND_LEN> = String::new();
+    write!(line, "{}\r\n", command).map_err(|_| Error::ProtocolError)?;
+    let mut sent = 0;
+    while sent < line.len() {
+        match serial.write(&line.as_bytes()[sent..]) {
+            Ok(0) | Err(_) => return Err(Error::WriteError),
+            Ok(n) => sent += n,
+        }
+    }
+    serial.flush().map_err(|_| Error::WriteError)
+}
+
+/// Read response lines until one contains `token`.
+///
+/// Bytes are consumed one at a time so that nothing past the final response
+/// line (i.e. data-mode payload) is swallowed. Lines reporting an error are
+/// turned into [`Error::ConnectionRefused`].
+fn expect<C: Connection>(serial: &mut C, token: &str, max_idle_reads: u32) -> Result<(), Error> {
+    let mut line: Vec<u8, MAX_LINE_LEN> = Vec::new();
+    let mut idle = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        match serial.read(&mut byte) {
+            Ok(0) => {
+                idle += 1;
+                if idle >= max_idle_reads {
+                    return Err(Error::Timeout);
+                }
+                continue;
+            }
+            Ok(_) => idle = 0,
+            Err(_) => return Err(Error::ReadError),
+        }
+
+        if byte[0] != b'\n' {
+            // Overlong lines are truncated; only their prefix is inspected.
+            let _ = line.push(byte[0]);
+            continue;
+        }
+
+        let text = core::str::from_utf8(&line).unwrap_or("").trim();
+        if ERROR_TOKENS.iter().any(|e| text.contains(e)) {
+            return Err(Error::ConnectionRefused);
+        }
+        if text.contains(token) {
+            return Ok(());
+        }
+        line.clear();
+    }
+}
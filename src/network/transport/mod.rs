@@ -1 +1,15 @@
+//! Transport layer implementations.
+//!
+//! This module contains [`Connection`](crate::network::Connection) implementations
+//! that sit directly on top of a physical or link-layer interface, allowing the
+//! application protocols in [`application`](crate::network::application) to run
+//! over it without additional glue code.
 
+/// Serial/UART transport built on `embedded-io`.
+///
+/// Available with the `serial` feature.
+#[cfg(feature = "serial")]
+pub mod serial;
+
+/// TCP sockets over AT-command cellular and Wi-Fi modems.
+pub mod at_modem;
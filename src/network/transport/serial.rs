@@ -0,0 +1,151 @@
+//! Serial/UART connection adapter.
+//!
+//! This module bridges any UART driver implementing the [`embedded-io`] traits
+//! to the crate's [`Read`], [`Write`] and [`Close`] traits, so that every
+//! application protocol in this crate (the shell, the NMEA parser, MQTT, HTTP
+//! through an AT modem, ...) can run over a serial line without extra glue.
+//!
+//! # Error Mapping
+//!
+//! Errors reported by the driver are translated into the crate's common
+//! [`Error`] type based on their [`embedded_io::ErrorKind`]:
+//!
+//! | `ErrorKind`                                          | [`Error`]                    |
+//! |------------------------------------------------------|------------------------------|
+//! | `TimedOut`                                           | [`Error::Timeout`]           |
+//! | `Interrupted`                                        | [`Error::WouldBlock`]        |
+//! | `ConnectionRefused`                                  | [`Error::ConnectionRefused`] |
+//! | `ConnectionReset`, `ConnectionAborted`, `BrokenPipe` | [`Error::ConnectionClosed`]  |
+//! | `NotConnected`                                       | [`Error::NotOpen`]           |
+//! | anything else                                        | `ReadError` / `WriteError`   |
+//!
+//! Drivers that also implement [`embedded_io::ReadReady`] can be polled
+//! without blocking through [`SerialConnection::try_read`], which reports
+//! [`Error::WouldBlock`] when no data is pending.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::network::transport::serial::SerialConnection;
+//! use libiot::network::{Read, Write};
+//! # struct Uart;
+//! # impl embedded_io::ErrorType for Uart { type Error = core::convert::Infallible; }
+//! # impl embedded_io::Read for Uart {
+//! #     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> { buf[0] = b'>'; Ok(1) }
+//! # }
+//! # impl embedded_io::Write for Uart {
+//! #     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> { Ok(buf.len()) }
+//! #     fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+//! # }
+//!
+//! let mut serial = SerialConnection::new(Uart);
+//! serial.write(b"AT\r\n").unwrap();
+//!
+//! let mut buf = [0u8; 16];
+//! let n = serial.read(&mut buf).unwrap();
+//! assert_eq!(&buf[..n], b">");
+//! ```
+//!
+//! [`embedded-io`]: https://docs.rs/embedded-io
+
+use embedded_io::ErrorKind;
+
+use crate::network::error::Error;
+use crate::network::{Close, Connection, Read, Write};
+
+/// A [`Connection`] over a UART implementing the `embedded-io` traits.
+///
+/// The adapter owns the underlying driver. Use [`inner_mut`](Self::inner_mut)
+/// to reconfigure it (e.g. change the baud rate) and
+/// [`into_inner`](Self::into_inner) to take it back.
+pub struct SerialConnection<T> {
+    inner: T,
+}
+
+impl<T> SerialConnection<T> {
+    /// Wrap a serial driver.
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    /// Get a shared reference to the underlying driver.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying driver.
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume the adapter and return the underlying driver.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: embedded_io::Read + embedded_io::ReadReady> SerialConnection<T> {
+    /// Read pending data without blocking.
+    ///
+    /// Returns [`Error::WouldBlock`] when the driver reports that no data is
+    /// available, instead of waiting for the next byte like
+    /// [`read`](Read::read) does.
+    pub fn try_read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        match self.inner.read_ready() {
+            Ok(true) => self.read(buf),
+            Ok(false) => Err(Error::WouldBlock),
+            Err(e) => Err(map_error(embedded_io::Error::kind(&e), Error::ReadError)),
+        }
+    }
+}
+
+impl<T: embedded_io::Read> Read for SerialConnection<T> {
+    type Error = Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.inner
+            .read(buf)
+            .map_err(|e| map_error(embedded_io::Error::kind(&e), Error::ReadError))
+    }
+}
+
+impl<T: embedded_io::Write> Write for SerialConnection<T> {
+    type Error = Error;
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.inner
+            .write(buf)
+            .map_err(|e| map_error(embedded_io::Error::kind(&e), Error::WriteError))
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner
+            .flush()
+            .map_err(|e| map_error(embedded_io::Error::kind(&e), Error::WriteError))
+    }
+}
+
+impl<T: embedded_io::Write> Close for SerialConnection<T> {
+    type Error = Error;
+
+    /// Flush any buffered output. A UART has no connection to tear down.
+    fn close(mut self) -> Result<(), Self::Error> {
+        Write::flush(&mut self)
+    }
+}
+
+impl<T: embedded_io::Read + embedded_io::Write> Connection for SerialConnection<T> {}
+
+/// Translate an `embedded-io` error kind into the crate's error type.
+fn map_error(kind: ErrorKind, fallback: Error) -> Error {
+    match kind {
+        ErrorKind::TimedOut => Error::Timeout,
+        ErrorKind::Interrupted => Error::WouldBlock,
+        ErrorKind::ConnectionRefused => Error::ConnectionRefused,
+        ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe => {
+            Error::ConnectionClosed
+        }
+        ErrorKind::NotConnected => Error::NotOpen,
+        _ => fallback,
+    }
+}
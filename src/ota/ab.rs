@@ -0,0 +1,327 @@
+//! A/B (dual-bank) partition bookkeeping
+//!
+//! [`AbPartitions`] tracks which of two image slots holds the last confirmed
+//! firmware and whether the other one holds a freshly downloaded image that
+//! still has to prove itself. OTA downloads go to the
+//! [`inactive_slot`](AbPartitions::inactive_slot), which is never the
+//! confirmed one, so a failed or interrupted update always leaves a bootable
+//! image behind.
+//!
+//! The state is a small versioned record with its own CRC32, kept in a
+//! separate metadata region. The region is split into two halves, each of
+//! which must be erasable on its own (e.g. two flash sectors); every update
+//! is written to the half that does not hold the current record, so losing
+//! power mid-write falls back to the previous state. If neither half holds a
+//! valid record (blank or corrupted flash), slot A is assumed confirmed.
+//!
+//! Resetting into the new image and rolling back when it fails to boot are
+//! left to the bootloader, which reads the same record.
+//!
+//! ```
+//! use libiot::ota::ab::{AbPartitions, Slot};
+//! use libiot::storage::Region;
+//! # use libiot::storage::{BlockingErase, ReadStorage, Storage};
+//! # struct Flash([u8; 0x3000]);
+//! # impl ReadStorage for Flash {
+//! #     type Error = ();
+//! #     fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+//! #         let offset = offset as usize;
+//! #         bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+//! #         Ok(())
+//! #     }
+//! #     fn capacity(&self) -> usize { self.0.len() }
+//! # }
+//! # impl Storage for Flash {
+//! #     fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+//! #         let offset = offset as usize;
+//! #         self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+//! #         Ok(())
+//! #     }
+//! # }
+//! # impl BlockingErase for Flash {
+//! #     fn erase(&mut self, from: u32, to: u32) -> Result<(), ()> {
+//! #         self.0[from as usize..to as usize].fill(0xFF);
+//! #         Ok(())
+//! #     }
+//! # }
+//! struct Bank(u32, u32);
+//!
+//! impl Region for Bank {
+//!     fn start(&self) -> u32 { self.0 }
+//!     fn end(&self) -> u32 { self.1 }
+//! }
+//!
+//! let mut flash = Flash([0xFF; 0x3000]);
+//! let banks = AbPartitions::new(
+//!     Bank(0x0000, 0x1000),
+//!     Bank(0x1000, 0x2000),
+//!     Bank(0x2000, 0x3000),
+//! );
+//!
+//! let target = banks.inactive_slot(&mut flash).unwrap();
+//! assert_eq!(target, Slot::B);
+//! // ... ota.run_http_in_region(.., banks.slot(target), banks.slot(target).start(), ..)
+//! banks.mark_pending(&mut flash, target).unwrap();
+//!
+//! // After rebooting into the new image and checking it works
+//! banks.confirm(&mut flash).unwrap();
+//! assert_eq!(banks.read(&mut flash).unwrap().confirmed, Slot::B);
+//! ```
+
+use super::{Crc32, Error};
+use crate::storage::error as storage_err;
+use crate::storage::{BlockingErase, ReadStorage, Region, Storage};
+
+/// Size of one encoded metadata record in bytes
+pub const RECORD_LEN: usize = 16;
+
+/// Identifies a metadata record ("LAB1" little-endian)
+const MAGIC: u32 = 0x3142_414C;
+
+/// Current record layout version
+const VERSION: u8 = 1;
+
+/// Encoding of "no pending slot"
+const NO_SLOT: u8 = 0xFF;
+
+/// One of the two image slots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    A,
+    B,
+}
+
+impl Slot {
+    /// The other slot
+    pub fn other(self) -> Self {
+        match self {
+            Slot::A => Slot::B,
+            Slot::B => Slot::A,
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Slot::A => 0,
+            Slot::B => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Slot::A),
+            1 => Some(Slot::B),
+            _ => None,
+        }
+    }
+}
+
+/// Boot state kept in the metadata region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootState {
+    /// Slot holding the last image that was confirmed to work
+    pub confirmed: Slot,
+    /// Slot holding a new image to try, if any
+    pub pending: Option<Slot>,
+    /// Incremented on every write; the newer of the two records wins
+    pub sequence: u32,
+}
+
+impl Default for BootState {
+    /// State assumed when no valid record exists: the factory image in slot A
+    fn default() -> Self {
+        Self {
+            confirmed: Slot::A,
+            pending: None,
+            sequence: 0,
+        }
+    }
+}
+
+impl BootState {
+    /// Serialize to the on-flash record
+    ///
+    /// Layout (little-endian): magic u32, version u8, confirmed u8,
+    /// pending u8 (0xFF for none), reserved u8, sequence u32, CRC32 u32 of
+    /// the preceding 12 bytes.
+    pub fn encode(&self) -> [u8; RECORD_LEN] {
+        let mut record = [0u8; RECORD_LEN];
+        record[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        record[4] = VERSION;
+        record[5] = self.confirmed.to_byte();
+        record[6] = self.pending.map_or(NO_SLOT, Slot::to_byte);
+        record[8..12].copy_from_slice(&self.sequence.to_le_bytes());
+        let mut crc = Crc32::new();
+        crc.update(&record[..12]);
+        record[12..16].copy_from_slice(&crc.finalize().to_le_bytes());
+        record
+    }
+
+    /// Parse an on-flash record, returning `None` if it is blank, corrupted
+    /// or of an unknown version
+    pub fn decode(record: &[u8; RECORD_LEN]) -> Option<Self> {
+        let word =
+            |i: usize| u32::from_le_bytes([record[i], record[i + 1], record[i + 2], record[i + 3]]);
+        let mut crc = Crc32::new();
+        crc.update(&record[..12]);
+        if word(0) != MAGIC || record[4] != VERSION || word(12) != crc.finalize() {
+            return None;
+        }
+        let confirmed = Slot::from_byte(record[5])?;
+        let pending = match record[6] {
+            NO_SLOT => None,
+            byte => Some(Slot::from_byte(byte)?),
+        };
+        if pending == Some(confirmed) {
+            return None;
+        }
+        Some(Self {
+            confirmed,
+            pending,
+            sequence: word(8),
+        })
+    }
+}
+
+/// Two image slots plus the metadata region that records which one to boot
+pub struct AbPartitions<R: Region> {
+    pub slot_a: R,
+    pub slot_b: R,
+    /// Holds two copies of the boot state, one per half
+    pub metadata: R,
+}
+
+impl<R: Region> AbPartitions<R> {
+    pub fn new(slot_a: R, slot_b: R, metadata: R) -> Self {
+        Self {
+            slot_a,
+            slot_b,
+            metadata,
+        }
+    }
+
+    /// Region of `slot`, to pass to `Ota::run_http_in_region` together with
+    /// its `start()` as the base offset
+    pub fn slot(&self, slot: Slot) -> &R {
+        match slot {
+            Slot::A => &self.slot_a,
+            Slot::B => &self.slot_b,
+        }
+    }
+
+    /// Current boot state, or the default (slot A confirmed) if the metadata
+    /// holds no valid record
+    pub fn read<S: ReadStorage>(&self, storage: &mut S) -> Result<BootState, Error> {
+        Ok(self.load(storage)?.0)
+    }
+
+    /// Slot a new image should be downloaded into: the one that does not
+    /// hold the confirmed image
+    ///
+    /// While an update is pending this is the pending slot, so confirm a
+    /// running update before starting the next one.
+    pub fn inactive_slot<S: ReadStorage>(&self, storage: &mut S) -> Result<Slot, Error> {
+        Ok(self.read(storage)?.confirmed.other())
+    }
+
+    /// Record that `slot` holds a new, fully verified image to try on the
+    /// next boot
+    ///
+    /// Returns `InvalidConfig` if `slot` holds the confirmed image.
+    pub fn mark_pending<S: Storage + BlockingErase>(
+        &self,
+        storage: &mut S,
+        slot: Slot,
+    ) -> Result<(), Error> {
+        let (state, half) = self.load(storage)?;
+        if slot == state.confirmed {
+            return Err(Error::InvalidConfig);
+        }
+        let next = BootState {
+            pending: Some(slot),
+            ..state
+        };
+        self.store(storage, next, half)
+    }
+
+    /// Make the pending image the confirmed one, typically once it has
+    /// booted and passed its self-test. Does nothing if no image is pending.
+    pub fn confirm<S: Storage + BlockingErase>(&self, storage: &mut S) -> Result<(), Error> {
+        let (state, half) = self.load(storage)?;
+        let Some(pending) = state.pending else {
+            return Ok(());
+        };
+        let next = BootState {
+            confirmed: pending,
+            pending: None,
+            ..state
+        };
+        self.store(storage, next, half)
+    }
+
+    /// Start offsets of the two metadata copies
+    fn halves(&self) -> Result<[u32; 2], Error> {
+        let start = self.metadata.start();
+        let half_len = self.metadata.end().saturating_sub(start) / 2;
+        if (half_len as usize) < RECORD_LEN {
+            return Err(Error::InvalidConfig);
+        }
+        Ok([start, start + half_len])
+    }
+
+    /// Newest valid record and the half it was read from
+    fn load<S: ReadStorage>(&self, storage: &mut S) -> Result<(BootState, Option<usize>), Error> {
+        let mut newest: Option<(BootState, usize)> = None;
+        for (half, offset) in self.halves()?.into_iter().enumerate() {
+            let mut record = [0u8; RECORD_LEN];
+            storage
+                .read(offset, &mut record)
+                .map_err(|_| Error::Storage(storage_err::Error::ReadError))?;
+            if let Some(state) = BootState::decode(&record) {
+                if newest.is_none_or(|(current, _)| state.sequence > current.sequence) {
+                    newest = Some((state, half));
+                }
+            }
+        }
+        Ok(match newest {
+            Some((state, half)) => (state, Some(half)),
+            None => (BootState::default(), None),
+        })
+    }
+
+    /// Write `state` into the half that does not hold the current record
+    fn store<S: Storage + BlockingErase>(
+        &self,
+        storage: &mut S,
+        state: BootState,
+        current: Option<usize>,
+    ) -> Result<(), Error> {
+        let halves = self.halves()?;
+        let half = match current {
+            Some(0) => 1,
+            _ => 0,
+        };
+        let start = halves[half];
+        let end = if half == 0 {
+            halves[1]
+        } else {
+            self.metadata.end()
+        };
+        let record = BootState {
+            sequence: state.sequence.wrapping_add(1),
+            ..state
+        }
+        .encode();
+
+        storage
+            .erase(start, end)
+            .map_err(|_| Error::Storage(storage_err::Error::EraseError))?;
+        storage
+            .write(start, &record)
+            .map_err(|_| Error::Storage(storage_err::Error::WriteError))?;
+        storage
+            .sync()
+            .map_err(|_| Error::Storage(storage_err::Error::WriteError))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+//! OTA job document parsing
+//!
+//! Turns an AWS-IoT-style OTA job document, typically received as the payload
+//! of an MQTT job notification, into the `HttpSource` and `Config` consumed by
+//! `Ota::run_http`. This is a thin serde layer: the parsed job borrows its
+//! strings from the input document, so the payload buffer must outlive it.
+//!
+//! Expected shape (unknown fields are ignored):
+//!
+//! ```json
+//! {
+//!   "afr_ota": {
+//!     "streamname": "AFR_OTA-1234",
+//!     "files": [{
+//!       "filepath": "/fw/app.bin",
+//!       "filesize": 65536,
+//!       "fileid": 0,
+//!       "update_data_url": "https://updates.example.com/fw/app.bin",
+//!       "crc32": 3735928559,
+//!       "sha256": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+//!     }]
+//!   }
+//! }
+//! ```
+//!
+//! Strings must not contain JSON escape sequences since they are borrowed
+//! directly from the document.
+//!
+//! `https://` URLs are only accepted by the `_tls` parsers, for callers that
+//! hand `Ota::run_http` an HTTP client over a TLS connection. The plain
+//! parsers reject them rather than fetch the image without the TLS the job
+//! asked for.
+
+use heapless::Vec;
+use serde::Deserialize;
+
+use super::{Config, Error, HttpSource};
+
+/// Maximum number of file entries accepted in a single job document
+pub const MAX_JOB_FILES: usize = 4;
+
+#[derive(Deserialize)]
+struct Document<'a> {
+    #[serde(borrow)]
+    afr_ota: OtaSection<'a>,
+}
+
+#[derive(Deserialize)]
+struct OtaSection<'a> {
+    #[serde(borrow, default)]
+    streamname: Option<&'a str>,
+    #[serde(borrow)]
+    files: Vec<FileEntry<'a>, MAX_JOB_FILES>,
+}
+
+#[derive(Deserialize)]
+struct FileEntry<'a> {
+    #[serde(borrow)]
+    filepath: &'a str,
+    filesize: usize,
+    #[serde(default)]
+    fileid: u32,
+    #[serde(borrow, default)]
+    update_data_url: Option<&'a str>,
+    #[serde(default)]
+    crc32: Option<u32>,
+    #[serde(borrow, default)]
+    sha256: Option<&'a str>,
+}
+
+/// A parsed OTA job, ready to drive `Ota::run_http`
+#[derive(Debug, Clone)]
+pub struct Job<'a> {
+    /// Stream name assigned by the cloud service, if any
+    pub stream: Option<&'a str>,
+    /// Identifier of the file within the job
+    pub file_id: u32,
+    /// Target path of the image on the device
+    pub target_path: &'a str,
+    /// Expected SHA-256 digest of the image as a hex string, if provided;
+    /// `source.expected_sha256` holds it decoded
+    pub sha256: Option<&'a str>,
+    /// Where to download the image from
+    pub source: HttpSource<'a>,
+    /// Whether the URL is `https://`, so the image must be downloaded over a
+    /// TLS connection; only ever set by the `_tls` parsers
+    pub tls: bool,
+    /// Download configuration derived from the job
+    pub config: Config,
+}
+
+impl<'a> Job<'a> {
+    /// Parse the first file entry of a job document.
+    ///
+    /// Returns `Error::Protocol` if the document is not valid JSON of the
+    /// expected shape, and `Error::InvalidConfig` if it carries no file, a
+    /// zero size, a `sha256` that is not 64 hex digits, or no usable
+    /// `http://` URL. `https://` URLs are rejected; see
+    /// [`parse_tls`](Self::parse_tls).
+    pub fn parse(document: &'a str) -> Result<Self, Error> {
+        Self::parse_file(document, None, false)
+    }
+
+    /// Parse the file entry with the given `fileid` from a job document.
+    pub fn parse_file_id(document: &'a str, file_id: u32) -> Result<Self, Error> {
+        Self::parse_file(document, Some(file_id), false)
+    }
+
+    /// Like [`parse`](Self::parse), but also accept `https://` URLs, for a
+    /// caller that downloads over TLS whenever [`tls`](Self::tls) is set.
+    pub fn parse_tls(document: &'a str) -> Result<Self, Error> {
+        Self::parse_file(document, None, true)
+    }
+
+    /// Like [`parse_file_id`](Self::parse_file_id), but also accept
+    /// `https://` URLs; see [`parse_tls`](Self::parse_tls).
+    pub fn parse_file_id_tls(document: &'a str, file_id: u32) -> Result<Self, Error> {
+        Self::parse_file(document, Some(file_id), true)
+    }
+
+    /// Parse a job document received as a raw (e.g. MQTT) payload.
+    pub fn from_bytes(payload: &'a [u8]) -> Result<Self, Error> {
+        Self::parse(core::str::from_utf8(payload).map_err(|_| Error::Protocol)?)
+    }
+
+    fn parse_file(document: &'a str, file_id: Option<u32>, allow_tls: bool) -> Result<Self, Error> {
+        let (doc, _): (Document<'a>, usize) =
+            serde_json_core::from_str(document).map_err(|_| Error::Protocol)?;
+        let stream = doc.afr_ota.streamname;
+        let file = doc
+            .afr_ota
+            .files
+            .into_iter()
+            .find(|f| file_id.is_none_or(|id| f.fileid == id))
+            .ok_or(Error::InvalidConfig)?;
+        if file.filesize == 0 {
+            return Err(Error::InvalidConfig);
+        }
+
+        let expected_sha256 = match file.sha256 {
+            Some(hex) => Some(parse_digest(hex).ok_or(Error::InvalidConfig)?),
+            None => None,
+        };
+        let url = file.update_data_url.ok_or(Error::InvalidConfig)?;
+        let (host, path, tls) = split_url(url).ok_or(Error::InvalidConfig)?;
+        if tls && !allow_tls {
+            return Err(Error::InvalidConfig);
+        }
+
+        Ok(Self {
+            stream,
+            file_id: file.fileid,
+            target_path: file.filepath,
+            sha256: file.sha256,
+            source: HttpSource {
+                host,
+                path,
+                size: file.filesize,
+                crc32: file.crc32,
+                expected_sha256,
+            },
+            tls,
+            config: Config {
+                verify_crc32: file.crc32.is_some(),
+                ..Config::default()
+            },
+        })
+    }
+}
+
+/// Decode a SHA-256 digest written as 64 hex digits
+fn parse_digest(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        if !pair.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+        *byte = u8::from_str_radix(core::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(digest)
+}
+
+/// Split an `http://` or `https://` URL into its authority and path
+/// (including any query string), and whether it is `https://`. The path
+/// defaults to "/".
+fn split_url(url: &str) -> Option<(&str, &str, bool)> {
+    let (rest, tls) = match url.strip_prefix("https://") {
+        Some(rest) => (rest, true),
+        None => (url.strip_prefix("http://")?, false),
+    };
+    let (host, path) = match rest.find('/') {
+        Some(idx) => rest.split_at(idx),
+        None => (rest, "/"),
+    };
+    if host.is_empty() {
+        return None;
+    }
+    Some((host, path, tls))
+}
@@ -9,42 +9,72 @@
 //! Design goals
 //! - Works with any `Storage + BlockingErase`
 //! - Uses `network::application::http::Client` for chunked HTTP range reads
-//! - Optional progress reporting via `network::application::mqtt::Client`
+//! - Optional progress reporting through a callback, e.g. published via
+//!   `network::application::mqtt::Client` with [`MqttProgress`]
 //! - Lightweight checksum verification (CRC32 by default). Users can inject
-//!   a custom verifier if desired.
+//!   a custom [`Verifier`], e.g. for signature checks.
+//! - Optional SHA-256 digest check, independent of the CRC32, for images
+//!   that must be protected against tampering as well as corruption
+//! - Optional parsing of cloud-pushed job documents (see [`job`])
+//! - A/B slot selection and boot metadata (see [`ab`])
+//! - Optional partition guard: `run_http_in_region` refuses images that would
+//!   spill outside the target `Region`
+//! - Cooperative pause/resume within a session (see [`Ota::pause`])
+//! - Download over MQTT instead of HTTP, for networks that only allow MQTT
+//!   (see [`Ota::run_mqtt`])
 //!
 //! Notes
-//! - This module does not manage bootloader/partition swaps. Users should
+//! - This module does not reset into the new image or roll it back. With a
+//!   dual-bank layout, [`ab::AbPartitions`] picks the slot to download into
+//!   and records the new image for the bootloader; otherwise users should
 //!   provide the proper target region and apply/commit the new image using
 //!   their boot process after a successful download and verification.
 //! - The bundled HTTP client limits response body capacity to 2048 bytes.
 //!   OTA here uses HTTP range requests with a configurable `chunk_size` that
 //!   must be <= 2048 to operate within these limits. Servers MUST honor
 //!   HTTP Range requests and return 206 Partial Content with a valid
-//!   `Content-Range` header. Full-body 200 responses are not accepted.
+//!   `Content-Range` header. Full-body 200 responses are only accepted with
+//!   `Config::allow_full_body`, which streams the body instead.
 
 #![allow(missing_docs)]
 #![deny(unsafe_code)]
 
-use crate::network::application::http::client::{Client as HttpClient, Header, Method, Request};
+use crate::network::application::http::client::{
+    Client as HttpClient, Header, Method, Request, Response, StatusCode,
+};
+use crate::network::application::http::stream::StreamingResponse;
 use crate::network::application::mqtt::client::{Client as MqttClient, QoS};
 use crate::network::error as net_err;
 use crate::storage::error as storage_err;
-use crate::storage::{BlockingErase, Storage};
+use crate::storage::{BlockingErase, Region, Storage};
 use heapless::{String, Vec};
 
+pub mod ab;
+pub mod job;
+mod sha256;
+
+pub use crate::util::Crc32;
+pub use sha256::Sha256;
+
+/// Most separate runs of received bytes `run_mqtt` tracks while chunks
+/// arrive out of order
+pub const MAX_MQTT_GAPS: usize = 8;
+
 /// Maximum header name/value lengths taken from HTTP client constraints
 const MAX_HEADER_NAME_LEN: usize = 64;
 const MAX_HEADER_VALUE_LEN: usize = 256;
 
 /// OTA-specific error type
 #[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Error {
     Network(net_err::Error),
     Storage(storage_err::Error),
     InvalidConfig,
     VerifyFailed,
     Canceled,
+    /// The download was paused; call `run_http` again to continue
+    Paused,
     Protocol,
 }
 
@@ -60,6 +90,21 @@ impl From<storage_err::Error> for Error {
     }
 }
 
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::Network(e) => defmt::write!(f, "Network({})", e),
+            Error::Storage(e) => defmt::write!(f, "Storage({})", e),
+            Error::InvalidConfig => defmt::write!(f, "InvalidConfig"),
+            Error::VerifyFailed => defmt::write!(f, "VerifyFailed"),
+            Error::Canceled => defmt::write!(f, "Canceled"),
+            Error::Paused => defmt::write!(f, "Paused"),
+            Error::Protocol => defmt::write!(f, "Protocol"),
+        }
+    }
+}
+
 /// OTA state machine
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum State {
@@ -71,6 +116,7 @@ pub enum State {
     Completed,
     Failed,
     Canceled,
+    Paused,
 }
 
 /// Where to fetch firmware from using HTTP
@@ -84,6 +130,25 @@ pub struct HttpSource<'a> {
     pub size: usize,
     /// Optional CRC32 of the entire image for verification
     pub crc32: Option<u32>,
+    /// Optional SHA-256 digest of the entire image, always checked if given
+    pub expected_sha256: Option<[u8; 32]>,
+}
+
+/// Where to receive firmware from over MQTT
+///
+/// The image is published on `topic` as chunks whose payload is the chunk's
+/// offset within the image as a 4-byte big-endian integer, followed by the
+/// chunk data.
+#[derive(Debug, Clone)]
+pub struct MqttSource<'a> {
+    /// Topic the chunks are published on, e.g. "devices/42/firmware"
+    pub topic: &'a str,
+    /// Total size of the firmware in bytes
+    pub size: usize,
+    /// Optional CRC32 of the entire image for verification
+    pub crc32: Option<u32>,
+    /// Optional SHA-256 digest of the entire image, always checked if given
+    pub expected_sha256: Option<[u8; 32]>,
 }
 
 /// OTA configuration
@@ -95,6 +160,17 @@ pub struct Config {
     pub erase_before_write: bool,
     /// Perform CRC32 verification if checksum is provided
     pub verify_crc32: bool,
+    /// How often a failed range request is retried before giving up
+    pub max_retries: u8,
+    /// Delay before each retry, passed to the hook installed with
+    /// `Ota::set_retry_delay`; `None` retries immediately
+    pub retry_delay_ms: Option<u32>,
+    /// Accept a server that ignores the Range header and answers 200 with
+    /// the whole image, streaming it in `chunk_size` slices. Ranged 206
+    /// responses are still requested and preferred. A full body is read in
+    /// one go, so a pause only takes effect once it has been stored. If the
+    /// transfer breaks off, the retry resumes after the last stored byte.
+    pub allow_full_body: bool,
 }
 
 impl Default for Config {
@@ -103,6 +179,9 @@ impl Default for Config {
             chunk_size: 1024,
             erase_before_write: true,
             verify_crc32: true,
+            max_retries: 2,
+            retry_delay_ms: None,
+            allow_full_body: false,
         }
     }
 }
@@ -115,47 +194,53 @@ pub struct Progress {
     pub state: State,
 }
 
-/// A simple CRC32 (IEEE) hasher implemented without external dependencies
-struct Crc32 {
-    table: [u32; 256],
-    value: u32,
+/// A custom image check, fed every chunk of a download
+///
+/// `run_http` passes each chunk to [`update`](Self::update) right after
+/// writing it to storage, and `run_mqtt` passes the image as it reads it
+/// back once complete. Either way, chunks arrive in ascending offset order,
+/// without gaps or repeats, so the verifier sees exactly the image as
+/// stored. Once the whole image is stored and the built-in CRC32 and SHA-256
+/// checks have passed, [`finalize`](Self::finalize) is called in the
+/// `Verifying` state; an error from it fails the download and is returned.
+///
+/// A paused download does not keep the verifier: pass the same one to the
+/// call that continues it, and a fresh one for every new download.
+///
+/// `finalize` takes `&mut self` so that verifiers can be passed as
+/// `&mut dyn Verifier`.
+///
+/// [`Crc32`] implements this trait; see [`Crc32::expecting`].
+pub trait Verifier {
+    /// Feed the next chunk of the image
+    fn update(&mut self, data: &[u8]);
+
+    /// Check the complete image, returning `Error::VerifyFailed` (or any
+    /// other error) if it must not be used
+    fn finalize(&mut self) -> Result<(), Error>;
 }
 
-impl Crc32 {
-    fn new() -> Self {
-        let mut table = [0u32; 256];
-        let poly: u32 = 0xEDB88320;
-        let mut i = 0u32;
-        while i < 256 {
-            let mut c = i;
-            let mut j = 0;
-            while j < 8 {
-                c = if (c & 1) != 0 {
-                    (c >> 1) ^ poly
-                } else {
-                    c >> 1
-                };
-                j += 1;
-            }
-            table[i as usize] = c;
-            i += 1;
-        }
-        Self {
-            table,
-            value: 0xFFFF_FFFF,
-        }
+impl Verifier for Crc32 {
+    fn update(&mut self, data: &[u8]) {
+        Crc32::update(self, data);
     }
 
-    fn update(&mut self, data: &[u8]) {
-        for &b in data {
-            let idx = (self.value ^ b as u32) & 0xFF;
-            self.value = self.table[idx as usize] ^ (self.value >> 8);
+    fn finalize(&mut self) -> Result<(), Error> {
+        match self.expected {
+            Some(expected) if Crc32::finalize(self) != expected => Err(Error::VerifyFailed),
+            _ => Ok(()),
         }
     }
+}
 
-    fn finalize(self) -> u32 {
-        self.value ^ 0xFFFF_FFFF
-    }
+/// Progress of a paused download, kept so it can continue without re-erasing
+#[derive(Debug, Clone, Copy)]
+struct Session {
+    base_offset: u32,
+    size: usize,
+    downloaded: usize,
+    crc: u32,
+    sha256: Sha256,
 }
 
 /// OTA driver. Create with a `Config`, then call `run_http` to perform the
@@ -164,6 +249,10 @@ pub struct Ota {
     cfg: Config,
     state: State,
     canceled: bool,
+    pause_requested: bool,
+    pause_check: Option<fn() -> bool>,
+    retry_delay: Option<fn(u8, u32)>,
+    session: Option<Session>,
 }
 
 impl Ota {
@@ -175,6 +264,10 @@ impl Ota {
             cfg,
             state: State::Idle,
             canceled: false,
+            pause_requested: false,
+            pause_check: None,
+            retry_delay: None,
+            session: None,
         })
     }
 
@@ -184,22 +277,172 @@ impl Ota {
 
     pub fn cancel(&mut self) {
         self.canceled = true;
+        self.session = None;
+    }
+
+    /// Request that the download pause at the next chunk boundary.
+    ///
+    /// Pausing is cooperative: `run_http` is blocking and holds the driver
+    /// for its whole duration, so it only looks for a pause between chunks.
+    /// There it stops, keeping the byte counter and CRC state, sets
+    /// `State::Paused` and returns `Error::Paused`, giving control back to
+    /// the caller (e.g. to free a metered link for other traffic). To
+    /// request a pause while a download is running, install a check with
+    /// [`set_pause_check`](Self::set_pause_check); this method is for
+    /// pausing between calls.
+    ///
+    /// After [`resume`](Self::resume), calling `run_http` again with the same
+    /// `base_offset` and source continues where it stopped, without erasing
+    /// again. Paused progress lives in RAM only; it does not survive a reset.
+    pub fn pause(&mut self) {
+        self.pause_requested = true;
+    }
+
+    /// Clear a pause request so the next `run_http` call continues the
+    /// paused download.
+    ///
+    /// Returns `true` if a paused download is waiting to be continued.
+    pub fn resume(&mut self) -> bool {
+        self.pause_requested = false;
+        self.session.is_some()
+    }
+
+    /// Install a function polled at every chunk boundary; when it returns
+    /// `true` the download pauses as described in [`pause`](Self::pause).
+    ///
+    /// Typically reads a flag set from an interrupt or another task. The
+    /// check is polled again on the next `run_http` call, so it must return
+    /// `false` again before the download can continue.
+    pub fn set_pause_check(&mut self, check: Option<fn() -> bool>) {
+        self.pause_check = check;
+    }
+
+    /// Install a function that blocks between retries of a failed range
+    /// request
+    ///
+    /// It is called with the retry number (starting at 1) and
+    /// `Config::retry_delay_ms`, so it can wait a fixed time or back off,
+    /// e.g. `delay_ms << (retry - 1)`. Nothing waits unless both the hook
+    /// and `retry_delay_ms` are set.
+    ///
+    /// Each retry reuses the HTTP client after
+    /// [`reset`](crate::network::application::http::client::Client::reset),
+    /// so the transport must recover from the failure by itself, or be
+    /// re-established from this hook.
+    pub fn set_retry_delay(&mut self, delay: Option<fn(u8, u32)>) {
+        self.retry_delay = delay;
+    }
+
+    /// Bytes stored so far by a paused download, if one is waiting
+    pub fn paused_at(&self) -> Option<usize> {
+        self.session.map(|session| session.downloaded)
+    }
+
+    fn pause_pending(&self) -> bool {
+        self.pause_requested || self.pause_check.is_some_and(|check| check())
+    }
+
+    /// Like `run_http`, but confined to a target partition.
+    ///
+    /// The image must fit entirely within `[region.start(), region.end())`
+    /// once placed at `base_offset`; otherwise `InvalidConfig` is returned
+    /// before anything is erased or written. Use this to make sure a bad job
+    /// cannot overwrite the running firmware or the bootloader.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_http_in_region<HC, S>(
+        &mut self,
+        http: &mut HttpClient<HC>,
+        storage: &mut S,
+        region: &dyn Region,
+        base_offset: u32,
+        source: &HttpSource,
+        verifier: Option<&mut dyn Verifier>,
+        mut on_progress: Option<&mut dyn FnMut(Progress)>,
+    ) -> Result<(), Error>
+    where
+        HC: crate::network::Connection,
+        S: Storage + BlockingErase,
+    {
+        let end = (base_offset as u64).checked_add(source.size as u64);
+        let fits =
+            base_offset >= region.start() && end.is_some_and(|end| end <= region.end() as u64);
+        if !fits {
+            self.state = State::Failed;
+            if let Some(on_progress) = on_progress.as_deref_mut() {
+                on_progress(Progress {
+                    bytes_total: source.size,
+                    bytes_downloaded: 0,
+                    state: State::Failed,
+                });
+            }
+            return Err(Error::InvalidConfig);
+        }
+        self.run_http(http, storage, base_offset, source, verifier, on_progress)
     }
 
     /// Download the firmware from the HTTP source into `storage` starting at
-    /// `base_offset`. If `mqtt` is provided, progress is published as small JSON
-    /// messages: {"bytes":N,"total":T,"state":"downloading"}
-    pub fn run_http<HC, S, MC>(
+    /// `base_offset`. If `verifier` is provided, it is fed every chunk and
+    /// must accept the image, see [`Verifier`].
+    ///
+    /// If `on_progress` is provided, it is called after every chunk and on
+    /// every state change, ending with the final state (`Completed`,
+    /// `Failed`, `Canceled` or `Paused`). Use [`MqttProgress::callback`] to
+    /// publish progress over MQTT.
+    pub fn run_http<HC, S>(
         &mut self,
         http: &mut HttpClient<HC>,
         storage: &mut S,
         base_offset: u32,
         source: &HttpSource,
-        mut mqtt: Option<&mut MqttProgress<'_, MC>>,
+        verifier: Option<&mut dyn Verifier>,
+        mut on_progress: Option<&mut dyn FnMut(Progress)>,
+    ) -> Result<(), Error>
+    where
+        HC: crate::network::Connection,
+        S: Storage + BlockingErase,
+    {
+        // Remember the last report, so a failure can be reported with the
+        // progress made up to it
+        let mut last = Progress {
+            bytes_total: source.size,
+            bytes_downloaded: 0,
+            state: self.state,
+        };
+        let result = self.download_http(
+            http,
+            storage,
+            base_offset,
+            source,
+            verifier,
+            &mut |progress| {
+                last = progress;
+                if let Some(on_progress) = on_progress.as_deref_mut() {
+                    on_progress(progress);
+                }
+            },
+        );
+        if result.is_err() && last.state != self.state {
+            if let Some(on_progress) = on_progress {
+                on_progress(Progress {
+                    state: self.state,
+                    ..last
+                });
+            }
+        }
+        result
+    }
+
+    fn download_http<HC, S>(
+        &mut self,
+        http: &mut HttpClient<HC>,
+        storage: &mut S,
+        base_offset: u32,
+        source: &HttpSource,
+        verifier: Option<&mut dyn Verifier>,
+        on_progress: &mut dyn FnMut(Progress),
     ) -> Result<(), Error>
     where
         HC: crate::network::Connection,
-        MC: crate::network::Connection,
         S: Storage + BlockingErase,
     {
         // Validate source size and bounds early
@@ -226,9 +469,42 @@ impl Ota {
             return Err(Error::Canceled);
         }
 
+        // Continue a paused download of the same image, or start over
+        let session = self
+            .session
+            .take()
+            .filter(|s| s.base_offset == base_offset && s.size == source.size);
+        if self.pause_pending() {
+            self.session = session;
+            self.state = State::Paused;
+            return Err(Error::Paused);
+        }
+
+        let mut download = match session {
+            Some(s) => Download {
+                base_offset,
+                size: source.size,
+                downloaded: s.downloaded,
+                crc: Crc32::resume(s.crc),
+                sha256: s.sha256,
+                verifier,
+                on_progress,
+            },
+            None => Download {
+                base_offset,
+                size: source.size,
+                downloaded: 0,
+                crc: Crc32::new(),
+                sha256: Sha256::new(),
+                verifier,
+                on_progress,
+            },
+        };
+
         // Erase (end-exclusive per BlockingErase contract)
-        if self.cfg.erase_before_write {
+        if self.cfg.erase_before_write && session.is_none() {
             self.state = State::Erasing;
+            download.report(State::Erasing);
             if self.canceled {
                 self.state = State::Canceled;
                 return Err(Error::Canceled);
@@ -241,196 +517,488 @@ impl Ota {
 
         // Download in ranges
         self.state = State::Downloading;
-        let mut downloaded: usize = 0;
-        let mut crc = Crc32::new();
+        download.report(State::Downloading);
 
-        while downloaded < source.size {
+        while download.downloaded < source.size {
             if self.canceled {
                 self.state = State::Canceled;
                 return Err(Error::Canceled);
             }
 
-            let remaining = source.size - downloaded;
-            let len = core::cmp::min(self.cfg.chunk_size, remaining);
-            let start = downloaded;
-            let end = start + len - 1; // inclusive
-
-            let mut headers: Vec<Header, 16> = Vec::new();
-            let host_header = Header {
-                name: String::<MAX_HEADER_NAME_LEN>::try_from("Host")
-                    .map_err(|_| Error::Protocol)?,
-                value: String::<MAX_HEADER_VALUE_LEN>::try_from(source.host)
-                    .map_err(|_| Error::Protocol)?,
-            };
-            headers.push(host_header).map_err(|_| Error::Protocol)?;
-
-            let mut range_value: String<80> = String::new();
-            // bytes=start-end
-            core::fmt::write(&mut range_value, format_args!("bytes={}-{}", start, end))
-                .map_err(|_| Error::Protocol)?;
-            let range_header = Header {
-                name: String::<MAX_HEADER_NAME_LEN>::try_from("Range")
-                    .map_err(|_| Error::Protocol)?,
-                value: String::<MAX_HEADER_VALUE_LEN>::try_from(range_value.as_str())
-                    .map_err(|_| Error::Protocol)?,
-            };
-            headers.push(range_header).map_err(|_| Error::Protocol)?;
+            // Yield at the chunk boundary, keeping progress for the next call
+            if self.pause_pending() {
+                self.session = Some(Session {
+                    base_offset,
+                    size: source.size,
+                    downloaded: download.downloaded,
+                    crc: download.crc.value(),
+                    sha256: download.sha256,
+                });
+                self.state = State::Paused;
+                return Err(Error::Paused);
+            }
 
-            let req = Request {
-                method: Method::Get,
-                path: source.path,
-                headers,
-                body: None,
-            };
+            // Retry transient network errors per chunk
+            let mut retries = 0;
+            loop {
+                // A streamed full body may have been stored in part before
+                // failing, so every attempt starts where the last one stopped
+                let remaining = source.size - download.downloaded;
+                let len = core::cmp::min(self.cfg.chunk_size, remaining);
+                let start = download.downloaded;
+                let end = start + len - 1; // inclusive
+                let req = range_request(source, start, end)?;
 
-            // Minimal retry loop for transient network errors per chunk
-            let mut attempt = 0;
-            let resp = loop {
-                match http.request(&req) {
-                    Ok(r) => break r,
+                let received = if self.cfg.allow_full_body {
+                    http.request_streaming(&req).and_then(|mut resp| {
+                        download.receive_streaming(storage, &mut resp, start, end)
+                    })
+                } else {
+                    http.request(&req)
+                        .map(|resp| download.receive_range(storage, &resp, start, end))
+                };
+                match received {
+                    Ok(stored) => {
+                        stored.map_err(|e| self.fail(e))?;
+                        break;
+                    }
+                    // The whole image is stored; only the end of the body was lost
+                    Err(_) if download.downloaded == source.size => break,
                     Err(e) => {
-                        attempt += 1;
-                        if attempt >= 3 {
+                        if retries >= self.cfg.max_retries {
                             self.state = State::Failed;
                             return Err(Error::Network(e));
                         }
-                        // simple immediate retry without backoff
-                        continue;
-                    }
-                }
-            };
-            match resp.status_code {
-                206 => {
-                    // Validate Content-Range matches the requested start..=end and total size
-                    let mut content_range_ok = false;
-                    let mut header_total: Option<usize> = None;
-                    for h in &resp.headers {
-                        if h.name.as_str().eq_ignore_ascii_case("Content-Range") {
-                            if let Some((rs, re, total)) = parse_content_range(h.value.as_str()) {
-                                header_total = total;
-                                if rs == start && re == end {
-                                    content_range_ok = true;
-                                }
-                            }
-                        }
-                    }
-                    if !content_range_ok {
-                        self.state = State::Failed;
-                        return Err(Error::Network(net_err::Error::ProtocolError));
-                    }
-                    if let Some(t) = header_total {
-                        if t != source.size {
-                            self.state = State::Failed;
-                            return Err(Error::Network(net_err::Error::ProtocolError));
+                        retries += 1;
+                        if let (Some(delay), Some(delay_ms)) =
+                            (self.retry_delay, self.cfg.retry_delay_ms)
+                        {
+                            delay(retries, delay_ms);
                         }
+                        // The failed exchange left the client closed
+                        http.reset();
                     }
                 }
-                _ => {
-                    // Require ranged transfers for OTA
-                    self.state = State::Failed;
-                    return Err(Error::Network(net_err::Error::ProtocolError));
-                }
             }
 
-            // Limit body length to requested len; client may read more if server ignores range
-            let chunk = &resp.body[..core::cmp::min(resp.body.len(), len)];
-            if chunk.is_empty() {
-                self.state = State::Failed;
-                return Err(Error::Network(net_err::Error::ReadError));
-            }
-            // For 206 responses, we expect exact length
-            if resp.status_code == 206 && chunk.len() != len {
-                self.state = State::Failed;
-                return Err(Error::Network(net_err::Error::ProtocolError));
-            }
-            let chunk = chunk;
+            // Continue until all requested ranges are downloaded
+        }
 
-            // Compute absolute write offset safely
-            let start_u32: u32 = (start as u64).try_into().map_err(|_| {
-                self.state = State::Failed;
-                Error::InvalidConfig
-            })?;
-            let abs_off = base_offset.checked_add(start_u32).ok_or_else(|| {
-                self.state = State::Failed;
-                Error::InvalidConfig
-            })?;
-            let base_offset_usize = base_offset as usize;
-            let abs_end_usize = base_offset_usize
-                .checked_add(start)
-                .and_then(|v| v.checked_add(chunk.len()))
-                .ok_or_else(|| {
-                    self.state = State::Failed;
-                    Error::InvalidConfig
-                })?;
-            if abs_end_usize > end_offset_usize {
-                self.state = State::Failed;
-                return Err(Error::InvalidConfig);
-            }
+        // Verify
+        self.state = State::Verifying;
+        download.report(State::Verifying);
+        let verified = self.check_image(
+            source.crc32,
+            source.expected_sha256,
+            &download.crc,
+            &download.sha256,
+            download.verifier.take(),
+        );
+        if let Err(e) = verified {
+            self.state = State::Failed;
+            return Err(e);
+        }
 
-            // Write to storage at base_offset + start
-            storage.write(abs_off, chunk).map_err(|_| {
+        // Finalize
+        self.state = State::Finalizing;
+        download.report(State::Finalizing);
+        storage.sync().map_err(|_| {
+            self.state = State::Failed;
+            Error::Storage(storage_err::Error::WriteError)
+        })?;
+
+        // Completed
+        self.state = State::Completed;
+        download.report(State::Completed);
+        Ok(())
+    }
+
+    /// Receive the firmware as chunks published on an MQTT topic and store
+    /// it in `storage` starting at `base_offset`.
+    ///
+    /// Subscribes to `source.topic` and polls `mqtt` until every byte of the
+    /// image has arrived; messages on other topics are skipped. Each chunk
+    /// is written at its offset, so chunks may arrive out of order, and
+    /// bytes that were already received are not written again, so
+    /// duplicates are harmless. Once the image is complete it is read back
+    /// in order and checked like in `run_http`, with `verifier` fed the
+    /// whole image in ascending offset order.
+    ///
+    /// Unlike `run_http` there is no pause: the broker does not hold chunks
+    /// back, so the download runs until it completes, is canceled, or `mqtt`
+    /// fails (e.g. its read times out). Chunks that leave more than
+    /// [`MAX_MQTT_GAPS`] separate runs of received bytes, or that reach past
+    /// `source.size`, fail the download with `Error::Protocol`. The
+    /// subscription is left in place.
+    pub fn run_mqtt<S, MC, const PAYLOAD: usize, const TOPIC: usize, const SUBSCRIPTIONS: usize>(
+        &mut self,
+        mqtt: &mut MqttClient<MC, PAYLOAD, TOPIC, SUBSCRIPTIONS>,
+        storage: &mut S,
+        base_offset: u32,
+        source: &MqttSource,
+        verifier: Option<&mut dyn Verifier>,
+    ) -> Result<(), Error>
+    where
+        MC: crate::network::Connection,
+        S: Storage + BlockingErase,
+    {
+        let end = (base_offset as u64)
+            .checked_add(source.size as u64)
+            .filter(|&end| source.size != 0 && end <= storage.capacity() as u64);
+        let Some(end) = end else {
+            self.state = State::Failed;
+            return Err(Error::InvalidConfig);
+        };
+        if self.canceled {
+            self.state = State::Canceled;
+            return Err(Error::Canceled);
+        }
+        self.session = None;
+
+        if self.cfg.erase_before_write {
+            self.state = State::Erasing;
+            storage.erase(base_offset, end as u32).map_err(|_| {
                 self.state = State::Failed;
-                Error::Storage(storage_err::Error::WriteError)
+                Error::Storage(storage_err::Error::EraseError)
             })?;
+        }
 
-            // Update CRC and counters
-            crc.update(chunk);
-            downloaded += chunk.len();
+        self.state = State::Downloading;
+        mqtt.subscribe(source.topic, QoS::AtLeastOnce)
+            .map_err(|e| self.fail(Error::Network(e)))?;
 
-            // Progress
-            if let Some(mp) = mqtt.as_deref_mut() {
-                let _ = mp.publish_progress(Progress {
-                    bytes_total: source.size,
-                    bytes_downloaded: downloaded,
-                    state: State::Downloading,
-                });
+        // Runs of received bytes as sorted, disjoint [start, end) ranges
+        let mut received: Vec<(usize, usize), MAX_MQTT_GAPS> = Vec::new();
+        while received.first() != Some(&(0, source.size)) {
+            if self.canceled {
+                self.state = State::Canceled;
+                return Err(Error::Canceled);
+            }
+            let message = match mqtt.poll_ref() {
+                Ok(Some(message)) if message.topic == source.topic => message,
+                Ok(_) => continue,
+                Err(e) => return Err(self.fail(Error::Network(e))),
+            };
+            let Some((offset, data)) = message.payload.split_first_chunk::<4>() else {
+                return Err(self.fail(Error::Protocol));
+            };
+            let start = u32::from_be_bytes(*offset) as usize;
+            let Some(chunk_end) = start
+                .checked_add(data.len())
+                .filter(|&end| end <= source.size)
+            else {
+                return Err(self.fail(Error::Protocol));
+            };
+
+            // Write only the parts of the chunk not received yet
+            let mut pos = start;
+            for &(run_start, run_end) in received.iter() {
+                if run_start > pos {
+                    let gap_end = run_start.min(chunk_end);
+                    storage
+                        .write(
+                            base_offset + pos as u32,
+                            &data[pos - start..gap_end - start],
+                        )
+                        .map_err(|_| self.fail(Error::Storage(storage_err::Error::WriteError)))?;
+                }
+                pos = pos.max(run_end);
+                if pos >= chunk_end {
+                    break;
+                }
+            }
+            if pos < chunk_end {
+                storage
+                    .write(base_offset + pos as u32, &data[pos - start..])
+                    .map_err(|_| self.fail(Error::Storage(storage_err::Error::WriteError)))?;
             }
 
-            // Continue until all requested ranges are downloaded
+            if !add_run(&mut received, start, chunk_end) {
+                return Err(self.fail(Error::Protocol));
+            }
         }
 
-        // Verify
+        // Verify the image as stored, in order
         self.state = State::Verifying;
-        if self.cfg.verify_crc32 {
-            if let Some(expected) = source.crc32 {
-                let actual = crc.finalize();
-                if actual != expected {
-                    self.state = State::Failed;
-                    if let Some(mp) = mqtt.as_deref_mut() {
-                        let _ = mp.publish_progress(Progress {
-                            bytes_total: source.size,
-                            bytes_downloaded: source.size,
-                            state: State::Failed,
-                        });
-                    }
-                    return Err(Error::VerifyFailed);
-                }
+        let mut crc = Crc32::new();
+        let mut sha256 = Sha256::new();
+        let mut verifier = verifier;
+        let mut buf = [0u8; 256];
+        let mut offset = 0;
+        while offset < source.size {
+            let len = buf.len().min(source.size - offset);
+            storage
+                .read(base_offset + offset as u32, &mut buf[..len])
+                .map_err(|_| self.fail(Error::Storage(storage_err::Error::ReadError)))?;
+            crc.update(&buf[..len]);
+            sha256.update(&buf[..len]);
+            if let Some(verifier) = verifier.as_deref_mut() {
+                verifier.update(&buf[..len]);
             }
+            offset += len;
         }
+        self.check_image(
+            source.crc32,
+            source.expected_sha256,
+            &crc,
+            &sha256,
+            verifier,
+        )
+        .map_err(|e| self.fail(e))?;
 
-        // Finalize
         self.state = State::Finalizing;
-        if let Some(mp) = mqtt.as_deref_mut() {
-            let _ = mp.publish_progress(Progress {
-                bytes_total: source.size,
-                bytes_downloaded: source.size,
-                state: State::Finalizing,
-            });
+        storage
+            .sync()
+            .map_err(|_| self.fail(Error::Storage(storage_err::Error::WriteError)))?;
+        self.state = State::Completed;
+        Ok(())
+    }
+
+    /// Check the digests of a complete image, then run `verifier`
+    fn check_image(
+        &self,
+        expected_crc32: Option<u32>,
+        expected_sha256: Option<[u8; 32]>,
+        crc: &Crc32,
+        sha256: &Sha256,
+        verifier: Option<&mut dyn Verifier>,
+    ) -> Result<(), Error> {
+        let crc_ok = !self.cfg.verify_crc32
+            || expected_crc32.is_none_or(|expected| crc.finalize() == expected);
+        let sha256_ok = expected_sha256.is_none_or(|expected| sha256.finalize() == expected);
+        if !(crc_ok && sha256_ok) {
+            return Err(Error::VerifyFailed);
         }
+        verifier.map_or(Ok(()), |verifier| verifier.finalize())
+    }
 
-        // Completed
-        self.state = State::Completed;
-        if let Some(mp) = mqtt.as_deref_mut() {
-            let _ = mp.publish_progress(Progress {
-                bytes_total: source.size,
-                bytes_downloaded: source.size,
-                state: State::Completed,
+    /// Enter `State::Failed`, passing `e` through
+    fn fail(&mut self, e: Error) -> Error {
+        self.state = State::Failed;
+        e
+    }
+}
+
+/// An HTTP download in progress: bytes stored so far and their digests
+struct Download<'v, 'p> {
+    base_offset: u32,
+    size: usize,
+    downloaded: usize,
+    crc: Crc32,
+    sha256: Sha256,
+    verifier: Option<&'v mut dyn Verifier>,
+    on_progress: &'p mut dyn FnMut(Progress),
+}
+
+impl Download<'_, '_> {
+    fn report(&mut self, state: State) {
+        (self.on_progress)(Progress {
+            bytes_total: self.size,
+            bytes_downloaded: self.downloaded,
+            state,
+        });
+    }
+
+    /// Write the next piece of the image and feed it to the digests
+    fn store<S: Storage>(&mut self, storage: &mut S, chunk: &[u8]) -> Result<(), Error> {
+        if chunk.len() > self.size - self.downloaded {
+            return Err(Error::InvalidConfig);
+        }
+        let offset = u32::try_from(self.downloaded)
+            .ok()
+            .and_then(|downloaded| self.base_offset.checked_add(downloaded))
+            .ok_or(Error::InvalidConfig)?;
+        storage
+            .write(offset, chunk)
+            .map_err(|_| Error::Storage(storage_err::Error::WriteError))?;
+
+        self.crc.update(chunk);
+        self.sha256.update(chunk);
+        if let Some(verifier) = self.verifier.as_deref_mut() {
+            verifier.update(chunk);
+        }
+        self.downloaded += chunk.len();
+        self.report(State::Downloading);
+        Ok(())
+    }
+
+    /// Store the body of a buffered response to the range `start..=end`
+    fn receive_range<S: Storage>(
+        &mut self,
+        storage: &mut S,
+        resp: &Response,
+        start: usize,
+        end: usize,
+    ) -> Result<(), Error> {
+        // Require ranged transfers
+        if resp.status() != StatusCode::PARTIAL_CONTENT {
+            return Err(Error::Network(net_err::Error::ProtocolError));
+        }
+        self.check_content_range(resp.header("Content-Range"), start, end)?;
+
+        // Limit body length to requested len; we expect it exactly
+        let len = end + 1 - start;
+        let chunk = &resp.body[..core::cmp::min(resp.body.len(), len)];
+        if chunk.is_empty() {
+            return Err(Error::Network(net_err::Error::ReadError));
+        }
+        if chunk.len() != len {
+            return Err(Error::Network(net_err::Error::ProtocolError));
+        }
+        self.store(storage, chunk)
+    }
+
+    /// Store the body of a streamed response to the range `start..=end`,
+    /// which may also be the whole image if the server ignored the range
+    ///
+    /// Like a buffered request, a transport error reading the body is
+    /// returned as the outer error so the exchange can be retried; what was
+    /// stored before it stays stored.
+    fn receive_streaming<S: Storage, C: crate::network::Connection>(
+        &mut self,
+        storage: &mut S,
+        resp: &mut StreamingResponse<'_, C>,
+        start: usize,
+        end: usize,
+    ) -> Result<Result<(), Error>, net_err::Error> {
+        let len = end + 1 - start;
+        let mut buf = [0u8; 2048];
+        let protocol_error = Ok(Err(Error::Network(net_err::Error::ProtocolError)));
+        match resp.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                if let Err(e) = self.check_content_range(resp.header("Content-Range"), start, end) {
+                    return Ok(Err(e));
+                }
+                let n = read_body(resp, &mut buf[..len])?;
+                if n == 0 {
+                    return Ok(Err(Error::Network(net_err::Error::ReadError)));
+                }
+                if n != len || read_body(resp, &mut [0])? != 0 {
+                    return protocol_error;
+                }
+                Ok(self.store(storage, &buf[..len]))
+            }
+            StatusCode::OK => {
+                let declared = resp.header("Content-Length").map(|v| v.trim().parse());
+                if declared.is_some_and(|declared| declared != Ok(self.size)) {
+                    return protocol_error;
+                }
+                // Skip what is stored already, in `len` slices like the rest
+                let mut skip = self.downloaded;
+                while skip > 0 {
+                    let n = read_body(resp, &mut buf[..skip.min(len)])?;
+                    if n == 0 {
+                        return protocol_error;
+                    }
+                    skip -= n;
+                }
+                while self.downloaded < self.size {
+                    let slice = len.min(self.size - self.downloaded);
+                    if read_body(resp, &mut buf[..slice])? != slice {
+                        return protocol_error;
+                    }
+                    if let Err(e) = self.store(storage, &buf[..slice]) {
+                        return Ok(Err(e));
+                    }
+                }
+                if read_body(resp, &mut [0])? != 0 {
+                    return protocol_error;
+                }
+                Ok(Ok(()))
+            }
+            _ => protocol_error,
+        }
+    }
+
+    /// Check that a Content-Range header matches the requested `start..=end`
+    /// and, if it states one, the image size
+    fn check_content_range(
+        &self,
+        content_range: Option<&str>,
+        start: usize,
+        end: usize,
+    ) -> Result<(), Error> {
+        let matches = content_range
+            .and_then(parse_content_range)
+            .is_some_and(|(rs, re, total)| {
+                rs == start && re == end && total.is_none_or(|t| t == self.size)
             });
+        if !matches {
+            return Err(Error::Network(net_err::Error::ProtocolError));
         }
         Ok(())
     }
 }
 
+/// Build the GET for the bytes `start..=end` of `source`
+fn range_request<'a>(
+    source: &HttpSource<'a>,
+    start: usize,
+    end: usize,
+) -> Result<Request<'a>, Error> {
+    let mut headers: Vec<Header, 16> = Vec::new();
+    let host_header = Header {
+        name: String::<MAX_HEADER_NAME_LEN>::try_from("Host").map_err(|_| Error::Protocol)?,
+        value: String::<MAX_HEADER_VALUE_LEN>::try_from(source.host)
+            .map_err(|_| Error::Protocol)?,
+    };
+    headers.push(host_header).map_err(|_| Error::Protocol)?;
+
+    let mut range_value: String<80> = String::new();
+    // bytes=start-end
+    core::fmt::write(&mut range_value, format_args!("bytes={}-{}", start, end))
+        .map_err(|_| Error::Protocol)?;
+    let range_header = Header {
+        name: String::<MAX_HEADER_NAME_LEN>::try_from("Range").map_err(|_| Error::Protocol)?,
+        value: String::<MAX_HEADER_VALUE_LEN>::try_from(range_value.as_str())
+            .map_err(|_| Error::Protocol)?,
+    };
+    headers.push(range_header).map_err(|_| Error::Protocol)?;
+
+    Ok(Request {
+        method: Method::Get,
+        path: source.path,
+        headers,
+        body: None,
+    })
+}
+
+/// Read from a streamed body until `buf` is full or the body ends
+fn read_body<C: crate::network::Connection>(
+    resp: &mut StreamingResponse<'_, C>,
+    buf: &mut [u8],
+) -> Result<usize, net_err::Error> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match resp.read_body_chunk(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Merge `[start, end)` into the sorted, disjoint runs in `runs`.
+///
+/// Returns `false` if it would need more runs than `runs` can hold.
+fn add_run<const N: usize>(runs: &mut Vec<(usize, usize), N>, start: usize, end: usize) -> bool {
+    if start == end {
+        return true;
+    }
+    // Runs before `first` end before `start`; runs from `last` on begin after `end`
+    let first = runs.partition_point(|&(_, run_end)| run_end < start);
+    let last = runs.partition_point(|&(run_start, _)| run_start <= end);
+    if first == last {
+        return runs.insert(first, (start, end)).is_ok();
+    }
+    let merged = (start.min(runs[first].0), end.max(runs[last - 1].1));
+    runs[first] = merged;
+    for _ in first + 1..last {
+        runs.remove(first + 1);
+    }
+    true
+}
+
 /// Parse an HTTP Content-Range header of the form:
 /// "bytes start-end/total" or "bytes start-end/*"
 /// Returns (start, end, Some(total)) if total is known, otherwise total is None.
@@ -481,6 +1049,17 @@ impl<'a, C: crate::network::Connection> MqttProgress<'a, C> {
         Self { client, topic }
     }
 
+    /// Progress callback for `Ota::run_http` publishing each report as a
+    /// small JSON message: {"bytes":N,"total":T,"state":"downloading"}
+    ///
+    /// Publish errors are ignored so that a lost broker connection does not
+    /// abort the download.
+    pub fn callback(&mut self) -> impl FnMut(Progress) + '_ {
+        move |progress| {
+            let _ = self.publish_progress(progress);
+        }
+    }
+
     fn publish_progress(&mut self, p: Progress) -> Result<(), Error> {
         // Build tiny JSON using serde-json-core
         #[derive(serde::Serialize)]
@@ -499,6 +1078,7 @@ impl<'a, C: crate::network::Connection> MqttProgress<'a, C> {
             State::Completed => "completed",
             State::Failed => "failed",
             State::Canceled => "canceled",
+            State::Paused => "paused",
         };
 
         let body = Body {
@@ -0,0 +1,504 @@
+//! Bad-block management for NAND flash.
+//!
+//! NAND devices ship with some bad blocks, marked by the manufacturer, and
+//! more blocks wear out in use. [`BadBlockTable`] hides both: it sets aside
+//! the top blocks of the device, and redirects every logical block that is
+//! bad to one of them, so the blocks it presents are all usable.
+//!
+//! # Reserved blocks
+//!
+//! With a pool of `N` reserve blocks, the last `N + 2` blocks of the device
+//! are reserved and the first `block_count - N - 2` are presented as logical
+//! blocks `0..`. The two highest reserved blocks that
+//! [`block_status`](NandFlash::block_status) reports as good hold two copies
+//! of the remap table; the others form the pool. Each bad block, whether marked at
+//! the factory or worn out later, uses up one reserve block, so size `N` for
+//! the bad blocks the device may have over its lifetime: NAND datasheets
+//! typically guarantee at least 98% good blocks, i.e. `N` of about 2% of
+//! the block count.
+//!
+//! # Remapping
+//!
+//! On [`new`](BadBlockTable::new), the table is loaded and every logical
+//! block that `block_status` does not report as good is remapped. When a
+//! write or an erase fails later with [`Error::WriteError`] or
+//! [`Error::EraseError`], the block is treated as worn out: it is
+//! replaced with a free reserve block, the data written to it before the
+//! failing offset is copied over, and the operation is retried there. A
+//! reserve block that fails in turn is retired and replaced the same way.
+//! The table is written back after every change. Any other error, such as
+//! [`Error::OutOfBounds`] for a misaligned erase, is returned unchanged and
+//! leaves the mapping alone.
+//!
+//! Only main-area data is copied when a block is replaced; the spare areas
+//! of the copied pages are left blank.
+//!
+//! # Table layout
+//!
+//! ```text
+//! magic "BBT1": u32 | sequence: u32 | remaps: u16 | retired: u16
+//! (logical: u32, physical: u32) per remap | physical: u32 per retired block
+//! crc32: u32
+//! ```
+//!
+//! All fields are little-endian, and the CRC32 covers everything before it.
+//! A table block that is blank or fails its check holds no table.
+//!
+//! The valid copy with the higher sequence number is the current table, and
+//! each change is written to the other copy with the next sequence number.
+//! A power loss while the table is saved therefore leaves the previous
+//! table intact. With neither copy valid the table is empty.
+
+use super::error::Error;
+use super::{BlockStatus, BlockStorage, BlockingErase, Geometry, NandFlash, ReadStorage, Storage};
+use crate::util::Crc32;
+use heapless::Vec;
+
+/// Identifies the remap table ("BBT1" little-endian)
+const MAGIC: u32 = 0x3154_4242;
+
+/// Size of the table header
+const HEADER_LEN: usize = 12;
+
+/// Bytes moved at a time when copying a block
+const COPY_CHUNK: usize = 64;
+
+/// A logical block stored in a reserve block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Remap {
+    logical: u32,
+    physical: u32,
+}
+
+/// Remaps and retired blocks, as stored in a table copy
+type Table<const N: usize> = (Vec<Remap, N>, Vec<u32, N>);
+
+/// NAND flash with bad blocks replaced by blocks from a reserve pool of `N`.
+///
+/// See the [module documentation](self) for the layout and the pool size.
+pub struct BadBlockTable<S, const N: usize> {
+    nand: S,
+    block_size: usize,
+    // Logical blocks, the blocks holding the table copies, the copy holding
+    // the current table and its sequence number
+    logical_blocks: usize,
+    table_blocks: [usize; 2],
+    current: Option<usize>,
+    sequence: u32,
+    remaps: Vec<Remap, N>,
+    // Reserve blocks that failed after being put in use
+    retired: Vec<u32, N>,
+}
+
+impl<S, const N: usize> BadBlockTable<S, N>
+where
+    S: NandFlash<Error = Error> + BlockingErase,
+{
+    /// Load the remap table from `nand` and remap any further blocks that
+    /// are not reported as good.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::OutOfBounds`] - The device has no more than `N + 2`
+    ///   blocks, or a block cannot hold the table
+    /// * [`Error::StorageFault`] - Fewer than two reserved blocks are good,
+    ///   or the pool ran out while remapping
+    /// * Any error from the device
+    pub fn new(mut nand: S) -> Result<Self, Error> {
+        let block_size = nand.block_size();
+        let block_count = nand.block_count();
+        let table_len = HEADER_LEN + 12 * N + 4;
+        if block_count <= N + 2 || block_size < table_len {
+            return Err(Error::OutOfBounds);
+        }
+
+        let logical_blocks = block_count - N - 2;
+        let mut table_blocks = Vec::<usize, 2>::new();
+        for block in (logical_blocks..block_count).rev() {
+            if table_blocks.is_full() {
+                break;
+            }
+            if matches!(nand.block_status(block)?, BlockStatus::Good) {
+                let _ = table_blocks.push(block);
+            }
+        }
+
+        let mut bbt = Self {
+            nand,
+            block_size,
+            logical_blocks,
+            table_blocks: table_blocks.into_array().map_err(|_| Error::StorageFault)?,
+            current: None,
+            sequence: 0,
+            remaps: Vec::new(),
+            retired: Vec::new(),
+        };
+        bbt.load()?;
+
+        for block in 0..logical_blocks {
+            let remapped = bbt.remaps.iter().any(|r| r.logical as usize == block);
+            if !remapped && !matches!(bbt.nand.block_status(block)?, BlockStatus::Good) {
+                bbt.replace(block, 0)?;
+            }
+        }
+        Ok(bbt)
+    }
+
+    /// Physical block holding logical block `block`.
+    pub fn physical_block(&self, block: usize) -> usize {
+        self.remaps
+            .iter()
+            .find(|r| r.logical as usize == block)
+            .map_or(block, |r| r.physical as usize)
+    }
+
+    /// Number of reserve blocks in use, including retired ones.
+    pub fn reserve_used(&self) -> usize {
+        self.remaps.len() + self.retired.len()
+    }
+
+    /// The underlying device.
+    pub fn inner(&self) -> &S {
+        &self.nand
+    }
+
+    /// The underlying device, mutably.
+    ///
+    /// Accessing logical blocks directly bypasses the remapping.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.nand
+    }
+
+    /// Release the underlying device.
+    pub fn into_inner(self) -> S {
+        self.nand
+    }
+
+    /// Device offset of `offset` within physical block `block`
+    fn physical_offset(&self, block: usize, offset: usize) -> u32 {
+        (block * self.block_size + offset) as u32
+    }
+
+    /// Split `offset..offset + len` at logical block boundaries, checking it
+    /// lies within the logical blocks, and pass each part as
+    /// `(block, offset in block, start in span, len)`
+    fn for_each_block(
+        &mut self,
+        offset: u32,
+        len: usize,
+        mut f: impl FnMut(&mut Self, usize, usize, usize, usize) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let start = offset as usize;
+        match start.checked_add(len) {
+            Some(end) if end <= self.logical_blocks * self.block_size => {}
+            _ => return Err(Error::OutOfBounds),
+        }
+        let mut done = 0;
+        while done < len {
+            let position = start + done;
+            let within = position % self.block_size;
+            let part = (len - done).min(self.block_size - within);
+            f(self, position / self.block_size, within, done, part)?;
+            done += part;
+        }
+        Ok(())
+    }
+
+    /// Run `op` on the physical block of `block`, replacing the block and
+    /// retrying whenever it fails with a program or erase error
+    ///
+    /// `keep` is the number of bytes at the start of the block to carry
+    /// over to a replacement.
+    fn with_retry(
+        &mut self,
+        block: usize,
+        keep: usize,
+        mut op: impl FnMut(&mut S, usize) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        loop {
+            let physical = self.physical_block(block);
+            match op(&mut self.nand, physical) {
+                Ok(()) => return Ok(()),
+                Err(Error::WriteError | Error::EraseError) => self.replace(block, keep)?,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Move logical block `block` to a free reserve block, copying its first
+    /// `keep` bytes, and save the table
+    fn replace(&mut self, block: usize, keep: usize) -> Result<(), Error> {
+        let old = self.physical_block(block);
+        loop {
+            let new = self.free_reserve()?;
+            let start = self.physical_offset(new, 0);
+            let erased = match self.nand.erase(start, start + self.block_size as u32) {
+                Ok(()) => true,
+                Err(Error::EraseError) => false,
+                Err(e) => return Err(e),
+            };
+            if erased && self.copy(old, new, keep)? {
+                let entry = Remap {
+                    logical: block as u32,
+                    physical: new as u32,
+                };
+                match self.remaps.iter_mut().find(|r| r.logical as usize == block) {
+                    Some(remap) => {
+                        self.retired
+                            .push(remap.physical)
+                            .map_err(|_| Error::StorageFault)?;
+                        *remap = entry;
+                    }
+                    None => self.remaps.push(entry).map_err(|_| Error::StorageFault)?,
+                }
+                return self.store();
+            }
+            // The replacement is bad too
+            self.retired
+                .push(new as u32)
+                .map_err(|_| Error::StorageFault)?;
+        }
+    }
+
+    /// A good reserve block that is neither in use nor retired
+    fn free_reserve(&mut self) -> Result<usize, Error> {
+        for block in self.logical_blocks..self.nand.block_count() {
+            let taken = self.table_blocks.contains(&block)
+                || self.remaps.iter().any(|r| r.physical as usize == block)
+                || self.retired.iter().any(|&b| b as usize == block);
+            if !taken && matches!(self.nand.block_status(block)?, BlockStatus::Good) {
+                return Ok(block);
+            }
+        }
+        Err(Error::StorageFault)
+    }
+
+    /// Copy the first `len` bytes of physical block `from` to `to`,
+    /// returning `false` if writing to `to` failed
+    fn copy(&mut self, from: usize, to: usize, len: usize) -> Result<bool, Error> {
+        let mut buf = [0u8; COPY_CHUNK];
+        let mut done = 0;
+        while done < len {
+            let chunk = &mut buf[..(len - done).min(COPY_CHUNK)];
+            self.nand.read(self.physical_offset(from, done), chunk)?;
+            match self.nand.write(self.physical_offset(to, done), chunk) {
+                Ok(()) => {}
+                Err(Error::WriteError) => return Ok(false),
+                Err(e) => return Err(e),
+            }
+            done += chunk.len();
+        }
+        Ok(true)
+    }
+
+    /// Load the newer valid table copy, keeping the table empty if there is
+    /// none
+    fn load(&mut self) -> Result<(), Error> {
+        for copy in 0..2 {
+            if let Some((sequence, table)) = self.read_table(self.table_blocks[copy])? {
+                if self.current.is_none() || sequence > self.sequence {
+                    self.current = Some(copy);
+                    self.sequence = sequence;
+                    (self.remaps, self.retired) = table;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the table copy in `block`, returning `None` if it is not valid
+    fn read_table(&mut self, block: usize) -> Result<Option<(u32, Table<N>)>, Error> {
+        let base = self.physical_offset(block, 0);
+        let mut header = [0u8; HEADER_LEN];
+        self.nand.read(base, &mut header)?;
+        let sequence = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let remaps = u16::from_le_bytes([header[8], header[9]]) as usize;
+        let retired = u16::from_le_bytes([header[10], header[11]]) as usize;
+        if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) != MAGIC
+            || remaps > N
+            || retired > N
+        {
+            return Ok(None);
+        }
+
+        let mut crc = Crc32::new();
+        crc.update(&header);
+        let mut offset = base + HEADER_LEN as u32;
+        let mut word = |nand: &mut S, crc: &mut Crc32| -> Result<u32, Error> {
+            let mut bytes = [0u8; 4];
+            nand.read(offset, &mut bytes)?;
+            crc.update(&bytes);
+            offset += 4;
+            Ok(u32::from_le_bytes(bytes))
+        };
+        let mut table: Table<N> = (Vec::new(), Vec::new());
+        for _ in 0..remaps {
+            let logical = word(&mut self.nand, &mut crc)?;
+            let physical = word(&mut self.nand, &mut crc)?;
+            let _ = table.0.push(Remap { logical, physical });
+        }
+        for _ in 0..retired {
+            let _ = table.1.push(word(&mut self.nand, &mut crc)?);
+        }
+        let expected = crc.finalize();
+        let valid = word(&mut self.nand, &mut Crc32::new())? == expected;
+        Ok(valid.then_some((sequence, table)))
+    }
+
+    /// Write the table to the copy that does not hold the current one
+    fn store(&mut self) -> Result<(), Error> {
+        let copy = match self.current {
+            Some(0) => 1,
+            _ => 0,
+        };
+        let sequence = self.sequence.wrapping_add(1);
+        let base = self.physical_offset(self.table_blocks[copy], 0);
+        self.nand.erase(base, base + self.block_size as u32)?;
+
+        let mut header = [0u8; HEADER_LEN];
+        header[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        header[4..8].copy_from_slice(&sequence.to_le_bytes());
+        header[8..10].copy_from_slice(&(self.remaps.len() as u16).to_le_bytes());
+        header[10..12].copy_from_slice(&(self.retired.len() as u16).to_le_bytes());
+        let mut crc = Crc32::new();
+        crc.update(&header);
+        self.nand.write(base, &header)?;
+
+        let words = self
+            .remaps
+            .iter()
+            .flat_map(|r| [r.logical, r.physical])
+            .chain(self.retired.iter().copied());
+        let mut offset = base + HEADER_LEN as u32;
+        for word in words {
+            let bytes = word.to_le_bytes();
+            crc.update(&bytes);
+            self.nand.write(offset, &bytes)?;
+            offset += 4;
+        }
+        self.nand.write(offset, &crc.finalize().to_le_bytes())?;
+        self.nand.sync()?;
+
+        self.current = Some(copy);
+        self.sequence = sequence;
+        Ok(())
+    }
+}
+
+impl<S, const N: usize> ReadStorage for BadBlockTable<S, N>
+where
+    S: NandFlash<Error = Error> + BlockingErase,
+{
+    type Error = Error;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.for_each_block(offset, bytes.len(), |bbt, block, within, start, len| {
+            let physical = bbt.physical_offset(bbt.physical_block(block), within);
+            bbt.nand.read(physical, &mut bytes[start..start + len])
+        })
+    }
+
+    /// Size of the logical blocks, excluding the reserved ones.
+    fn capacity(&self) -> usize {
+        self.logical_blocks * self.block_size
+    }
+
+    /// The device's write and erase units, with the logical capacity.
+    fn geometry(&self) -> Geometry {
+        Geometry {
+            capacity: self.capacity(),
+            ..self.nand.geometry()
+        }
+    }
+}
+
+impl<S, const N: usize> Storage for BadBlockTable<S, N>
+where
+    S: NandFlash<Error = Error> + BlockingErase,
+{
+    /// Write to the logical blocks, replacing any block the write fails on.
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.for_each_block(offset, bytes.len(), |bbt, block, within, start, len| {
+            let block_size = bbt.block_size;
+            bbt.with_retry(block, within, |nand, physical| {
+                let offset = (physical * block_size + within) as u32;
+                nand.write(offset, &bytes[start..start + len])
+            })
+        })
+    }
+
+    fn sync(&mut self) -> Result<(), Self::Error> {
+        self.nand.sync()
+    }
+}
+
+impl<S, const N: usize> BlockingErase for BadBlockTable<S, N>
+where
+    S: NandFlash<Error = Error> + BlockingErase,
+{
+    /// Erase logical blocks, replacing any block the erase fails on.
+    ///
+    /// Like most NAND drivers, this expects `from` and `to` to be aligned
+    /// to blocks.
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        let len = to.checked_sub(from).ok_or(Error::OutOfBounds)? as usize;
+        self.for_each_block(from, len, |bbt, block, within, _, len| {
+            let block_size = bbt.block_size;
+            bbt.with_retry(block, 0, |nand, physical| {
+                let start = (physical * block_size + within) as u32;
+                nand.erase(start, start + len as u32)
+            })
+        })
+    }
+}
+
+impl<S, const N: usize> BlockStorage for BadBlockTable<S, N> {
+    fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Number of logical blocks.
+    fn block_count(&self) -> usize {
+        self.logical_blocks
+    }
+}
+
+impl<S, const N: usize> NandFlash for BadBlockTable<S, N>
+where
+    S: NandFlash<Error = Error> + BlockingErase,
+{
+    fn read_spare(&mut self, block: usize, page: usize, spare: &mut [u8]) -> Result<(), Error> {
+        if block >= self.logical_blocks {
+            return Err(Error::OutOfBounds);
+        }
+        let physical = self.physical_block(block);
+        self.nand.read_spare(physical, page, spare)
+    }
+
+    /// Write a page and its spare area, replacing the block if this fails.
+    ///
+    /// `data` must be a whole page: the pages before `page` are what is
+    /// copied to the replacement block.
+    fn write_with_spare(
+        &mut self,
+        block: usize,
+        page: usize,
+        data: &[u8],
+        spare: &[u8],
+    ) -> Result<(), Error> {
+        if block >= self.logical_blocks {
+            return Err(Error::OutOfBounds);
+        }
+        self.with_retry(block, page * data.len(), |nand, physical| {
+            nand.write_with_spare(physical, page, data, spare)
+        })
+    }
+
+    /// Always [`BlockStatus::Good`]: bad blocks are remapped.
+    fn block_status(&mut self, block: usize) -> Result<BlockStatus, Error> {
+        if block >= self.logical_blocks {
+            return Err(Error::OutOfBounds);
+        }
+        Ok(BlockStatus::Good)
+    }
+}
@@ -0,0 +1,204 @@
+//! CRC-protected fixed-size records.
+//!
+//! [`ChecksummedStorage`] divides the underlying storage into records of
+//! `RECORD` bytes and stores a CRC32 trailer after each one. Writes update
+//! the trailer of every record they touch, and reads recompute it, failing
+//! with [`Error::ChecksumMismatch`] rather than returning data that decayed
+//! or was only partly written. It is meant for small data that must not be
+//! used when damaged, such as calibration tables.
+//!
+//! # Layout
+//!
+//! Record `i` holds the bytes `i * RECORD .. (i + 1) * RECORD` of the
+//! wrapper's address space. It is stored at `i * (RECORD + 4)` on the
+//! device, followed by the CRC32 of its bytes as a little-endian `u32`:
+//!
+//! ```text
+//! | record 0 | crc 0 | record 1 | crc 1 | ...
+//! ```
+//!
+//! The wrapper's [`capacity`](ReadStorage::capacity) is the number of whole
+//! records that fit on the device, times `RECORD`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::storage::checksum::ChecksummedStorage;
+//! use libiot::storage::error::Error;
+//! use libiot::storage::{ReadStorage, Storage};
+//! # struct Ram([u8; 64]);
+//! # impl ReadStorage for Ram {
+//! #     type Error = Error;
+//! #     fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+//! #         let offset = offset as usize;
+//! #         bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+//! #         Ok(())
+//! #     }
+//! #     fn capacity(&self) -> usize { self.0.len() }
+//! # }
+//! # impl Storage for Ram {
+//! #     fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Error> {
+//! #         let offset = offset as usize;
+//! #         self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+//! #         Ok(())
+//! #     }
+//! # }
+//!
+//! // 12-byte calibration records, each with its own CRC
+//! let mut storage: ChecksummedStorage<_, 12> = ChecksummedStorage::new(Ram([0; 64]));
+//! assert_eq!(storage.capacity(), 48);
+//!
+//! storage.write(0, &[1; 24]).unwrap();
+//! let mut record = [0u8; 12];
+//! storage.read(12, &mut record).unwrap();
+//! assert_eq!(record, [1; 12]);
+//!
+//! // Never written, so its trailer does not match
+//! assert_eq!(storage.read(24, &mut record), Err(Error::ChecksumMismatch));
+//! ```
+
+use super::error::Error;
+use super::{ReadStorage, Storage};
+use crate::util::Crc32;
+
+/// Size of the CRC32 trailer after each record
+pub const TRAILER_LEN: usize = 4;
+
+/// Storage whose `RECORD`-byte records are each protected by a CRC32.
+///
+/// Reads and writes may start anywhere and span several records. A write
+/// that covers only part of a record keeps the rest of it, which therefore
+/// has to pass its check; write every record whole the first time.
+pub struct ChecksummedStorage<S, const RECORD: usize> {
+    storage: S,
+}
+
+impl<S, const RECORD: usize> ChecksummedStorage<S, RECORD> {
+    /// Wrap `storage`; the first record starts at its offset 0.
+    pub const fn new(storage: S) -> Self {
+        const { assert!(RECORD > 0, "records must not be empty") };
+        Self { storage }
+    }
+
+    /// The underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// The underlying storage, mutably, e.g. to inject faults in tests.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Release the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S, const RECORD: usize> ChecksummedStorage<S, RECORD>
+where
+    S: ReadStorage<Error = Error>,
+{
+    /// Device offset of record `index`
+    fn record_offset(index: usize) -> u32 {
+        (index * (RECORD + TRAILER_LEN)) as u32
+    }
+
+    /// Check that `len` bytes at `offset` lie within the records
+    fn check_bounds(&self, offset: u32, len: usize) -> Result<(), Error> {
+        match (offset as usize).checked_add(len) {
+            Some(end) if end <= self.capacity() => Ok(()),
+            _ => Err(Error::OutOfBounds),
+        }
+    }
+
+    /// Read record `index` into `record` and verify its trailer
+    fn load(&mut self, index: usize, record: &mut [u8; RECORD]) -> Result<(), Error> {
+        let offset = Self::record_offset(index);
+        let mut trailer = [0u8; TRAILER_LEN];
+        self.storage.read(offset, record)?;
+        self.storage.read(offset + RECORD as u32, &mut trailer)?;
+        if Crc32::checksum(record) != u32::from_le_bytes(trailer) {
+            return Err(Error::ChecksumMismatch);
+        }
+        Ok(())
+    }
+}
+
+impl<S, const RECORD: usize> ReadStorage for ChecksummedStorage<S, RECORD>
+where
+    S: ReadStorage<Error = Error>,
+{
+    type Error = Error;
+
+    /// Read `bytes` at `offset`, verifying every record they touch.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ChecksumMismatch`] - A record does not match its trailer
+    /// * [`Error::OutOfBounds`] - The span ends past the last record
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.check_bounds(offset, bytes.len())?;
+        let mut record = [0u8; RECORD];
+        let mut offset = offset as usize;
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            let start = offset % RECORD;
+            let len = bytes.len().min(RECORD - start);
+            self.load(offset / RECORD, &mut record)?;
+            let (chunk, rest) = bytes.split_at_mut(len);
+            chunk.copy_from_slice(&record[start..start + len]);
+            offset += len;
+            bytes = rest;
+        }
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.storage.capacity() / (RECORD + TRAILER_LEN) * RECORD
+    }
+}
+
+impl<S, const RECORD: usize> Storage for ChecksummedStorage<S, RECORD>
+where
+    S: Storage<Error = Error>,
+{
+    /// Write `bytes` at `offset`, updating the trailer of every record they
+    /// touch.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ChecksumMismatch`] - The write covers part of a record
+    ///   whose current contents fail their check
+    /// * [`Error::OutOfBounds`] - The span ends past the last record
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.check_bounds(offset, bytes.len())?;
+        let mut record = [0u8; RECORD];
+        let mut offset = offset as usize;
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            let index = offset / RECORD;
+            let start = offset % RECORD;
+            let len = bytes.len().min(RECORD - start);
+            if len < RECORD {
+                self.load(index, &mut record)?;
+            }
+            let (chunk, rest) = bytes.split_at(len);
+            record[start..start + len].copy_from_slice(chunk);
+
+            let record_offset = Self::record_offset(index);
+            let crc = Crc32::checksum(&record);
+            self.storage.write(record_offset, &record)?;
+            self.storage
+                .write(record_offset + RECORD as u32, &crc.to_le_bytes())?;
+            offset += len;
+            bytes = rest;
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<(), Self::Error> {
+        self.storage.sync()
+    }
+}
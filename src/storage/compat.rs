@@ -0,0 +1,116 @@
+//! Async storage traits over blocking storage.
+//!
+//! [`SyncToAsync`] wraps a device implementing the blocking traits so it can
+//! be passed to code written against [`AsyncReadStorage`], [`AsyncStorage`]
+//! and [`AsyncErase`]. Each async method calls the blocking one and returns
+//! its result, so the futures are ready the first time they are polled.
+//!
+//! The adapter never yields: a slow operation still blocks the executor for
+//! its whole duration, and other tasks do not run in the meantime. It suits
+//! tests and devices that are fast anyway, such as RAM or FRAM; a driver for
+//! a slow device should implement the async traits itself.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::storage::compat::SyncToAsync;
+//! use libiot::storage::slice::SliceStorage;
+//! use libiot::storage::AsyncReadStorage;
+//!
+//! async fn checksum<S: AsyncReadStorage>(storage: &mut S) -> Result<u8, S::Error> {
+//!     let mut buf = [0u8; 4];
+//!     storage.read(0, &mut buf).await?;
+//!     Ok(buf.iter().fold(0, |sum, b| sum.wrapping_add(*b)))
+//! }
+//!
+//! let mut storage = SyncToAsync::new(SliceStorage::new(&[1, 2, 3, 4]));
+//! let sum = futures::executor::block_on(checksum(&mut storage)).unwrap();
+//! assert_eq!(sum, 10);
+//! ```
+
+use super::{
+    AsyncErase, AsyncReadStorage, AsyncStorage, BlockStorage, BlockingErase, Geometry, ReadStorage,
+    SectorStorage, Storage,
+};
+
+/// Exposes blocking storage `S` through the async storage traits.
+///
+/// See the [module documentation](self) for why this never yields.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyncToAsync<S> {
+    storage: S,
+}
+
+impl<S> SyncToAsync<S> {
+    /// Wrap `storage`.
+    pub const fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// The underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// The underlying storage, mutably, e.g. to use the blocking traits.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Release the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S: ReadStorage> AsyncReadStorage for SyncToAsync<S> {
+    type Error = S::Error;
+
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.storage.read(offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        self.storage.capacity()
+    }
+
+    fn geometry(&self) -> Geometry {
+        self.storage.geometry()
+    }
+}
+
+impl<S: Storage> AsyncStorage for SyncToAsync<S> {
+    async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.storage.write(offset, bytes)
+    }
+
+    async fn sync(&mut self) -> Result<(), Self::Error> {
+        self.storage.sync()
+    }
+}
+
+impl<S: BlockingErase> AsyncErase for SyncToAsync<S> {
+    async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        self.storage.erase(from, to)
+    }
+}
+
+impl<S: BlockStorage> BlockStorage for SyncToAsync<S> {
+    fn block_size(&self) -> usize {
+        self.storage.block_size()
+    }
+
+    fn block_count(&self) -> usize {
+        self.storage.block_count()
+    }
+}
+
+impl<S: SectorStorage> SectorStorage for SyncToAsync<S> {
+    fn sector_size(&self) -> usize {
+        self.storage.sector_size()
+    }
+
+    fn sector_count(&self) -> usize {
+        self.storage.sector_count()
+    }
+}
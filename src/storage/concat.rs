@@ -0,0 +1,164 @@
+//! Two storage devices joined into one address space.
+//!
+//! [`ConcatStorage`] places the second device right after the first: offsets
+//! below the first device's capacity go to it, and the rest go to the second
+//! device, shifted down by that capacity. Reads, writes and erases that cross
+//! the junction are split in two and forwarded to both devices. A board with
+//! two flash chips can then hold one filesystem or log spanning both.
+//!
+//! Chain adapters to join more devices, e.g.
+//! `ConcatStorage<ConcatStorage<A, B>, C>`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::storage::concat::ConcatStorage;
+//! use libiot::storage::{ReadStorage, Storage};
+//! # struct Ram<const N: usize>([u8; N]);
+//! # impl<const N: usize> ReadStorage for Ram<N> {
+//! #     type Error = ();
+//! #     fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+//! #         let offset = offset as usize;
+//! #         bytes.copy_from_slice(self.0.get(offset..offset + bytes.len()).ok_or(())?);
+//! #         Ok(())
+//! #     }
+//! #     fn capacity(&self) -> usize { N }
+//! # }
+//! # impl<const N: usize> Storage for Ram<N> {
+//! #     fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+//! #         let offset = offset as usize;
+//! #         self.0.get_mut(offset..offset + bytes.len()).ok_or(())?.copy_from_slice(bytes);
+//! #         Ok(())
+//! #     }
+//! # }
+//!
+//! let mut storage = ConcatStorage::new(Ram([0; 16]), Ram([0; 32]));
+//! assert_eq!(storage.capacity(), 48);
+//!
+//! // Bytes 14 and 15 land on the first device, 16 and 17 on the second
+//! storage.write(14, &[1, 2, 3, 4]).unwrap();
+//! let (first, second) = storage.inner();
+//! assert_eq!(first.0[14..], [1, 2]);
+//! assert_eq!(second.0[..2], [3, 4]);
+//! ```
+
+use super::{BlockingErase, ReadStorage, Storage};
+
+/// Storage made of `A` followed by `B`.
+///
+/// Both devices must report the same error type. Accesses past the end of
+/// `B` are forwarded to it, so `B` reports them like any other out-of-bounds
+/// access.
+pub struct ConcatStorage<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> ConcatStorage<A, B> {
+    /// Join `first` and `second`; `second` starts at `first`'s capacity.
+    pub const fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// The underlying devices.
+    pub fn inner(&self) -> (&A, &B) {
+        (&self.first, &self.second)
+    }
+
+    /// The underlying devices, mutably.
+    pub fn inner_mut(&mut self) -> (&mut A, &mut B) {
+        (&mut self.first, &mut self.second)
+    }
+
+    /// Release the underlying devices.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A, B> ConcatStorage<A, B>
+where
+    A: ReadStorage,
+    B: ReadStorage<Error = A::Error>,
+{
+    /// Offset at which the second device starts
+    fn junction(&self) -> u32 {
+        self.first.capacity() as u32
+    }
+}
+
+impl<A, B> ReadStorage for ConcatStorage<A, B>
+where
+    A: ReadStorage,
+    B: ReadStorage<Error = A::Error>,
+{
+    type Error = A::Error;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let junction = self.junction();
+        if offset >= junction {
+            return self.second.read(offset - junction, bytes);
+        }
+        let split = bytes.len().min((junction - offset) as usize);
+        let (head, tail) = bytes.split_at_mut(split);
+        self.first.read(offset, head)?;
+        if !tail.is_empty() {
+            self.second.read(0, tail)?;
+        }
+        Ok(())
+    }
+
+    /// Sum of both devices' capacities.
+    fn capacity(&self) -> usize {
+        self.first.capacity() + self.second.capacity()
+    }
+}
+
+impl<A, B> Storage for ConcatStorage<A, B>
+where
+    A: Storage,
+    B: Storage<Error = A::Error>,
+{
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let junction = self.junction();
+        if offset >= junction {
+            return self.second.write(offset - junction, bytes);
+        }
+        let split = bytes.len().min((junction - offset) as usize);
+        let (head, tail) = bytes.split_at(split);
+        self.first.write(offset, head)?;
+        if !tail.is_empty() {
+            self.second.write(0, tail)?;
+        }
+        Ok(())
+    }
+
+    /// Sync both devices.
+    fn sync(&mut self) -> Result<(), Self::Error> {
+        self.first.sync()?;
+        self.second.sync()
+    }
+}
+
+impl<A, B> BlockingErase for ConcatStorage<A, B>
+where
+    A: BlockingErase,
+    B: BlockingErase<Error = A::Error>,
+{
+    /// Erase `from..to`, forwarding each device its part of the range.
+    ///
+    /// Each part must be aligned to its own device's erase unit; as the
+    /// second device starts at the first one's capacity, a range crossing
+    /// the junction usually is if it is aligned on both ends.
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        let junction = self.junction();
+        if from < junction {
+            self.first.erase(from, to.min(junction))?;
+        }
+        if to > junction {
+            self.second
+                .erase(from.max(junction) - junction, to - junction)?;
+        }
+        Ok(())
+    }
+}
@@ -40,10 +40,15 @@
 ///         Error::StorageFault => {
 ///             println!("Hardware fault detected in storage");
 ///         }
+///         // The enum is non-exhaustive; new variants may be added
+///         _ => {
+///             println!("Other storage error occurred");
+///         }
 ///     }
 /// }
 /// ```
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
 pub enum Error {
     /// An operation was attempted on an address that is out of bounds.
     ///
@@ -110,6 +115,22 @@ pub enum Error {
     /// - Excessive wear that makes area unusable
     /// - Manufacturing defects discovered during operation
     StorageFault,
+
+    /// The device does not support the requested operation.
+    ///
+    /// Returned by read-only backends, such as a
+    /// [`SliceStorage`](crate::storage::slice::SliceStorage) over data baked
+    /// into flash, when asked to write.
+    Unsupported,
+
+    /// Data read back does not match the checksum stored with it.
+    ///
+    /// Returned by [`ChecksummedStorage`](crate::storage::checksum::ChecksummedStorage)
+    /// when a record:
+    /// - Was only partly written, e.g. because power was lost
+    /// - Decayed or was corrupted on the medium
+    /// - Was never written at all
+    ChecksumMismatch,
 }
 
 #[cfg(feature = "defmt")]
@@ -123,6 +144,8 @@ impl defmt::Format for Error {
             Error::NotInitialized => defmt::write!(f, "NotInitialized"),
             Error::CardError => defmt::write!(f, "CardError"),
             Error::StorageFault => defmt::write!(f, "StorageFault"),
+            Error::Unsupported => defmt::write!(f, "Unsupported"),
+            Error::ChecksumMismatch => defmt::write!(f, "ChecksumMismatch"),
         }
     }
 }
@@ -0,0 +1,552 @@
+//! Log-structured key-value store for NOR flash.
+//!
+//! [`KvStore`] keeps small configuration values in a range of flash sectors
+//! without erasing a sector on every update. Records are appended to the
+//! active sector; overwriting a key appends a newer record, and deleting it
+//! appends a tombstone. When the active sector is full, the live records are
+//! copied into the next sector of the range, which then becomes active. The
+//! sectors are used in turn, so erases are spread over the whole range.
+//!
+//! Every record carries a CRC32. A record torn by a power loss fails its
+//! check and ends the log when the store is mounted again; the next write
+//! then compacts into a fresh sector. A compaction only takes over once the
+//! copied records are complete, because the new sector's header is written
+//! last.
+//!
+//! # Layout
+//!
+//! Each sector starts with a 12-byte header: magic `"KVS1"`, a sequence
+//! number that grows with every compaction, and a CRC32 of both. Records
+//! follow back to back:
+//!
+//! ```text
+//! key_len: u8 | kind: u8 | value_len: u16 LE | crc32: u32 LE | key | value
+//! ```
+//!
+//! The CRC covers the first four bytes, the key and the value. An erased
+//! `key_len` (`0xFF`) marks the end of the log.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::storage::kv::KvStore;
+//! # use libiot::storage::{BlockingErase, ReadStorage, SectorStorage, Storage};
+//! # struct Flash([u8; 1024]);
+//! # impl ReadStorage for Flash {
+//! #     type Error = ();
+//! #     fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+//! #         let offset = offset as usize;
+//! #         bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+//! #         Ok(())
+//! #     }
+//! #     fn capacity(&self) -> usize { self.0.len() }
+//! # }
+//! # impl Storage for Flash {
+//! #     fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+//! #         let offset = offset as usize;
+//! #         self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+//! #         Ok(())
+//! #     }
+//! # }
+//! # impl BlockingErase for Flash {
+//! #     fn erase(&mut self, from: u32, to: u32) -> Result<(), ()> {
+//! #         self.0[from as usize..to as usize].fill(0xFF);
+//! #         Ok(())
+//! #     }
+//! # }
+//! # impl SectorStorage for Flash {
+//! #     fn sector_size(&self) -> usize { 256 }
+//! #     fn sector_count(&self) -> usize { 4 }
+//! # }
+//!
+//! // Keys up to 16 bytes, values up to 64 bytes, in sectors 0 to 3
+//! let mut kv: KvStore<_, 16, 64> = KvStore::mount(Flash([0xFF; 1024]), 0..4).unwrap();
+//!
+//! kv.put("interval", b"60").unwrap();
+//! kv.put("interval", b"30").unwrap();
+//!
+//! let mut value = [0u8; 64];
+//! let len = kv.get("interval", &mut value).unwrap();
+//! assert_eq!(len, Some(2));
+//! assert_eq!(&value[..2], b"30");
+//!
+//! kv.delete("interval").unwrap();
+//! assert_eq!(kv.get("interval", &mut value).unwrap(), None);
+//! ```
+
+use super::{BlockingErase, SectorStorage, Storage};
+use crate::util::Crc32;
+use core::ops::Range;
+
+/// Identifies a formatted sector ("KVS1" little-endian)
+const MAGIC: u32 = 0x3153_564B;
+
+/// Size of the header at the start of every sector
+const SECTOR_HEADER_LEN: usize = 12;
+
+/// Size of the header in front of every record's key and value
+const RECORD_HEADER_LEN: usize = 8;
+
+/// Record kinds
+const KIND_VALUE: u8 = 0x01;
+const KIND_TOMBSTONE: u8 = 0x00;
+
+/// Erased flash
+const ERASED: u8 = 0xFF;
+
+/// Errors returned by [`KvStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error<E> {
+    /// The underlying storage failed.
+    Storage(E),
+    /// The key is empty or longer than the store's `MAX_KEY`.
+    KeyTooLong,
+    /// The value is longer than the store's `MAX_VALUE`.
+    ValueTooLong,
+    /// The buffer passed to [`KvStore::get`] is shorter than the value.
+    BufferTooSmall,
+    /// The live records and the new one do not fit in a sector.
+    Full,
+    /// The sector range is unusable: fewer than two sectors, out of the
+    /// device's bounds, or sectors too small for the largest record.
+    InvalidLayout,
+}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for Error<E> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::Storage(e) => defmt::write!(f, "Storage({})", e),
+            Error::KeyTooLong => defmt::write!(f, "KeyTooLong"),
+            Error::ValueTooLong => defmt::write!(f, "ValueTooLong"),
+            Error::BufferTooSmall => defmt::write!(f, "BufferTooSmall"),
+            Error::Full => defmt::write!(f, "Full"),
+            Error::InvalidLayout => defmt::write!(f, "InvalidLayout"),
+        }
+    }
+}
+
+/// Header of a record read back from flash
+#[derive(Debug, Clone, Copy)]
+struct Record {
+    key_len: usize,
+    value_len: usize,
+    tombstone: bool,
+}
+
+impl Record {
+    fn len(&self) -> usize {
+        RECORD_HEADER_LEN + self.key_len + self.value_len
+    }
+}
+
+/// What follows at a position in the log
+enum Entry {
+    /// A valid record, whose key and value were read into the buffers
+    Record(Record),
+    /// Erased flash: the log ends here
+    End,
+    /// A record that fails its checks, e.g. torn by a power loss
+    Torn,
+}
+
+/// A key-value store over a range of flash sectors.
+///
+/// `MAX_KEY` (at most 254) and `MAX_VALUE` bound the key and value lengths
+/// in bytes, and size the stack buffers used while reading records. A
+/// sector must hold its header and at least one record of maximum size,
+/// i.e. `20 + MAX_KEY + MAX_VALUE` bytes.
+///
+/// Lookups scan the active sector, so they take time proportional to the
+/// number of records in it, and compaction takes time quadratic in it. This
+/// suits a few hundred small configuration values, not a database.
+pub struct KvStore<S, const MAX_KEY: usize = 32, const MAX_VALUE: usize = 128> {
+    storage: S,
+    sectors: Range<usize>,
+    sector_size: usize,
+    // Index of the active sector within `sectors`
+    active: usize,
+    sequence: u32,
+    // Offset of the end of the log within the active sector
+    write_pos: usize,
+}
+
+impl<S, const MAX_KEY: usize, const MAX_VALUE: usize> KvStore<S, MAX_KEY, MAX_VALUE>
+where
+    S: Storage + BlockingErase + SectorStorage,
+{
+    /// Open the store in `sectors` of `storage`, formatting it if none of
+    /// them holds a store yet.
+    ///
+    /// The active sector is the one with the newest valid header; its log
+    /// ends at the first erased or torn record.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidLayout`] - See the variant
+    /// * [`Error::Storage`] - Reading, or formatting, failed
+    pub fn mount(storage: S, sectors: Range<usize>) -> Result<Self, Error<S::Error>> {
+        let sector_size = storage.sector_size();
+        let fits = sectors.len() >= 2
+            && sectors.end <= storage.sector_count()
+            && MAX_KEY < ERASED as usize
+            && MAX_VALUE <= u16::MAX as usize
+            && SECTOR_HEADER_LEN + RECORD_HEADER_LEN + MAX_KEY + MAX_VALUE <= sector_size;
+        if !fits {
+            return Err(Error::InvalidLayout);
+        }
+
+        let mut store = Self {
+            storage,
+            sectors,
+            sector_size,
+            active: 0,
+            sequence: 0,
+            write_pos: SECTOR_HEADER_LEN,
+        };
+
+        let mut newest = None;
+        for index in 0..store.sectors.len() {
+            if let Some(sequence) = store.read_sector_header(index)? {
+                if newest.is_none_or(|(_, newest)| sequence > newest) {
+                    newest = Some((index, sequence));
+                }
+            }
+        }
+
+        match newest {
+            Some((index, sequence)) => {
+                store.active = index;
+                store.sequence = sequence;
+                store.write_pos = store.log_end()?;
+            }
+            None => {
+                store.erase_sector(0)?;
+                store.write_sector_header(0, 1)?;
+                store.sequence = 1;
+            }
+        }
+        Ok(store)
+    }
+
+    /// Look up `key`, copying its value into `buf`.
+    ///
+    /// Returns the value's length, or `None` if the key is not stored.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::BufferTooSmall`] - `buf` is shorter than the value
+    /// * [`Error::KeyTooLong`] - The key could not have been stored
+    /// * [`Error::Storage`] - Reading failed
+    pub fn get(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, Error<S::Error>> {
+        check_key::<S::Error, MAX_KEY>(key)?;
+        let Some((pos, record)) = self.find(self.active, SECTOR_HEADER_LEN, key.as_bytes())? else {
+            return Ok(None);
+        };
+        if record.tombstone {
+            return Ok(None);
+        }
+        let value = buf
+            .get_mut(..record.value_len)
+            .ok_or(Error::BufferTooSmall)?;
+        let offset = self.sector_offset(self.active) + pos + RECORD_HEADER_LEN + record.key_len;
+        self.storage
+            .read(offset as u32, value)
+            .map_err(Error::Storage)?;
+        Ok(Some(record.value_len))
+    }
+
+    /// Store `value` under `key`, replacing any previous value.
+    ///
+    /// Nothing is written if the key already holds this value. If the
+    /// active sector is full, the store is compacted first. The storage is
+    /// synced before this returns, so the new value survives a reset.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::KeyTooLong`] / [`Error::ValueTooLong`] - See the variants
+    /// * [`Error::Full`] - The live records leave no room for the value
+    /// * [`Error::Storage`] - Reading, writing or erasing failed
+    pub fn put(&mut self, key: &str, value: &[u8]) -> Result<(), Error<S::Error>> {
+        check_key::<S::Error, MAX_KEY>(key)?;
+        if value.len() > MAX_VALUE {
+            return Err(Error::ValueTooLong);
+        }
+        if let Some((pos, record)) = self.find(self.active, SECTOR_HEADER_LEN, key.as_bytes())? {
+            if !record.tombstone && record.value_len == value.len() {
+                let mut current = [0u8; MAX_VALUE];
+                let offset =
+                    self.sector_offset(self.active) + pos + RECORD_HEADER_LEN + record.key_len;
+                self.storage
+                    .read(offset as u32, &mut current[..value.len()])
+                    .map_err(Error::Storage)?;
+                if current[..value.len()] == *value {
+                    return Ok(());
+                }
+            }
+        }
+        self.append(key.as_bytes(), value, false)
+    }
+
+    /// Remove `key`, returning whether it was stored.
+    ///
+    /// # Errors
+    ///
+    /// As for [`put`](Self::put).
+    pub fn delete(&mut self, key: &str) -> Result<bool, Error<S::Error>> {
+        check_key::<S::Error, MAX_KEY>(key)?;
+        match self.find(self.active, SECTOR_HEADER_LEN, key.as_bytes())? {
+            Some((_, record)) if !record.tombstone => {
+                self.append(key.as_bytes(), &[], true)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Copy the live records into the next sector and make it active.
+    ///
+    /// [`put`](Self::put) does this automatically when the active sector is
+    /// full.
+    pub fn compact(&mut self) -> Result<(), Error<S::Error>> {
+        let from = self.active;
+        let to = (from + 1) % self.sectors.len();
+        self.erase_sector(to)?;
+
+        let mut key = [0u8; MAX_KEY];
+        let mut value = [0u8; MAX_VALUE];
+        let mut pos = SECTOR_HEADER_LEN;
+        let mut write_pos = SECTOR_HEADER_LEN;
+        loop {
+            let Entry::Record(record) = self.load(from, pos, &mut key, &mut value)? else {
+                break;
+            };
+            let next = pos + record.len();
+            // Only the newest record of each key is live
+            let superseded = self.find(from, next, &key[..record.key_len])?.is_some();
+            if !record.tombstone && !superseded {
+                self.write_record(
+                    to,
+                    write_pos,
+                    &key[..record.key_len],
+                    &value[..record.value_len],
+                    false,
+                )?;
+                write_pos += record.len();
+            }
+            pos = next;
+        }
+
+        // Written last, so an interrupted compaction leaves `from` active
+        let sequence = self.sequence.wrapping_add(1);
+        self.write_sector_header(to, sequence)?;
+        self.active = to;
+        self.sequence = sequence;
+        self.write_pos = write_pos;
+        Ok(())
+    }
+
+    /// Index of the active sector on the device.
+    pub fn active_sector(&self) -> usize {
+        self.sectors.start + self.active
+    }
+
+    /// The underlying storage.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Release the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// Append a record to the active sector, compacting first if it is full.
+    fn append(&mut self, key: &[u8], value: &[u8], tombstone: bool) -> Result<(), Error<S::Error>> {
+        let len = RECORD_HEADER_LEN + key.len() + value.len();
+        if self.write_pos + len > self.sector_size {
+            self.compact()?;
+            if self.write_pos + len > self.sector_size {
+                return Err(Error::Full);
+            }
+        }
+        self.write_record(self.active, self.write_pos, key, value, tombstone)?;
+        self.write_pos += len;
+        // The record is committed once it reaches the medium
+        self.storage.sync().map_err(Error::Storage)
+    }
+
+    /// Find the last record for `key` in `sector` from `pos` on.
+    ///
+    /// The record may be a tombstone.
+    fn find(
+        &mut self,
+        sector: usize,
+        mut pos: usize,
+        key: &[u8],
+    ) -> Result<Option<(usize, Record)>, Error<S::Error>> {
+        let mut key_buf = [0u8; MAX_KEY];
+        let mut value_buf = [0u8; MAX_VALUE];
+        let mut found = None;
+        while let Entry::Record(record) = self.load(sector, pos, &mut key_buf, &mut value_buf)? {
+            if key_buf[..record.key_len] == *key {
+                found = Some((pos, record));
+            }
+            pos += record.len();
+        }
+        Ok(found)
+    }
+
+    /// Offset just past the last valid record of the active sector.
+    ///
+    /// A torn record makes the rest of the sector unusable, since it may be
+    /// partly programmed; the sector is then treated as full.
+    fn log_end(&mut self) -> Result<usize, Error<S::Error>> {
+        let mut key = [0u8; MAX_KEY];
+        let mut value = [0u8; MAX_VALUE];
+        let mut pos = SECTOR_HEADER_LEN;
+        loop {
+            match self.load(self.active, pos, &mut key, &mut value)? {
+                Entry::Record(record) => pos += record.len(),
+                Entry::End => return Ok(pos),
+                Entry::Torn => return Ok(self.sector_size),
+            }
+        }
+    }
+
+    /// Read and check the record at `pos` of `sector`.
+    fn load(
+        &mut self,
+        sector: usize,
+        pos: usize,
+        key: &mut [u8; MAX_KEY],
+        value: &mut [u8; MAX_VALUE],
+    ) -> Result<Entry, Error<S::Error>> {
+        if pos + RECORD_HEADER_LEN > self.sector_size {
+            return Ok(Entry::End);
+        }
+        let base = self.sector_offset(sector) + pos;
+        let mut header = [0u8; RECORD_HEADER_LEN];
+        self.storage
+            .read(base as u32, &mut header)
+            .map_err(Error::Storage)?;
+        if header[0] == ERASED {
+            return Ok(Entry::End);
+        }
+
+        let record = Record {
+            key_len: header[0] as usize,
+            value_len: u16::from_le_bytes([header[2], header[3]]) as usize,
+            tombstone: header[1] == KIND_TOMBSTONE,
+        };
+        let valid = (1..=MAX_KEY).contains(&record.key_len)
+            && record.value_len <= MAX_VALUE
+            && matches!(header[1], KIND_VALUE | KIND_TOMBSTONE)
+            && pos + record.len() <= self.sector_size;
+        if !valid {
+            return Ok(Entry::Torn);
+        }
+
+        let key = &mut key[..record.key_len];
+        let value = &mut value[..record.value_len];
+        self.storage
+            .read((base + RECORD_HEADER_LEN) as u32, key)
+            .map_err(Error::Storage)?;
+        self.storage
+            .read((base + RECORD_HEADER_LEN + record.key_len) as u32, value)
+            .map_err(Error::Storage)?;
+        let stored = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if record_crc(&header[..4], key, value) != stored {
+            return Ok(Entry::Torn);
+        }
+        Ok(Entry::Record(record))
+    }
+
+    fn write_record(
+        &mut self,
+        sector: usize,
+        pos: usize,
+        key: &[u8],
+        value: &[u8],
+        tombstone: bool,
+    ) -> Result<(), Error<S::Error>> {
+        let mut header = [0u8; RECORD_HEADER_LEN];
+        header[0] = key.len() as u8;
+        header[1] = if tombstone {
+            KIND_TOMBSTONE
+        } else {
+            KIND_VALUE
+        };
+        header[2..4].copy_from_slice(&(value.len() as u16).to_le_bytes());
+        let crc = record_crc(&header[..4], key, value);
+        header[4..8].copy_from_slice(&crc.to_le_bytes());
+
+        let base = self.sector_offset(sector) + pos;
+        self.storage
+            .write(base as u32, &header)
+            .map_err(Error::Storage)?;
+        self.storage
+            .write((base + RECORD_HEADER_LEN) as u32, key)
+            .map_err(Error::Storage)?;
+        if !value.is_empty() {
+            self.storage
+                .write((base + RECORD_HEADER_LEN + key.len()) as u32, value)
+                .map_err(Error::Storage)?;
+        }
+        Ok(())
+    }
+
+    /// Sequence number of `sector`, or `None` if it holds no valid header.
+    fn read_sector_header(&mut self, sector: usize) -> Result<Option<u32>, Error<S::Error>> {
+        let mut header = [0u8; SECTOR_HEADER_LEN];
+        self.storage
+            .read(self.sector_offset(sector) as u32, &mut header)
+            .map_err(Error::Storage)?;
+        let word =
+            |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+        if word(0) != MAGIC || word(8) != Crc32::checksum(&header[..8]) {
+            return Ok(None);
+        }
+        Ok(Some(word(4)))
+    }
+
+    fn write_sector_header(&mut self, sector: usize, sequence: u32) -> Result<(), Error<S::Error>> {
+        let mut header = [0u8; SECTOR_HEADER_LEN];
+        header[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        header[4..8].copy_from_slice(&sequence.to_le_bytes());
+        let crc = Crc32::checksum(&header[..8]);
+        header[8..12].copy_from_slice(&crc.to_le_bytes());
+        self.storage
+            .write(self.sector_offset(sector) as u32, &header)
+            .map_err(Error::Storage)?;
+        self.storage.sync().map_err(Error::Storage)
+    }
+
+    fn erase_sector(&mut self, sector: usize) -> Result<(), Error<S::Error>> {
+        let start = self.sector_offset(sector);
+        self.storage
+            .erase(start as u32, (start + self.sector_size) as u32)
+            .map_err(Error::Storage)
+    }
+
+    /// Byte offset of `sector` (an index within the range) on the device
+    fn sector_offset(&self, sector: usize) -> usize {
+        (self.sectors.start + sector) * self.sector_size
+    }
+}
+
+fn check_key<E, const MAX_KEY: usize>(key: &str) -> Result<(), Error<E>> {
+    if key.is_empty() || key.len() > MAX_KEY {
+        return Err(Error::KeyTooLong);
+    }
+    Ok(())
+}
+
+fn record_crc(header: &[u8], key: &[u8], value: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(header);
+    crc.update(key);
+    crc.update(value);
+    crc.finalize()
+}
@@ -54,6 +54,7 @@
 //! - [`BlockStorage`]: Block-oriented storage (SD cards, NAND flash)
 //! - [`SectorStorage`]: Sector-oriented storage (NOR flash)
 //! - [`Region`]: Memory region management
+//! - [`Geometry`]: Capacity and write/erase units, queried via [`ReadStorage::geometry`]
 //!
 //! ## Technology-Specific Traits
 //!
@@ -63,6 +64,17 @@
 //! - [`Fram`]: Ferroelectric RAM operations
 //! - [`RamStorage`]: RAM-based storage
 //!
+//! ## Implementations
+//!
+//! - [`slice::SliceStorage`]: Read-only view of a byte slice, e.g. an asset in flash
+//! - [`kv::KvStore`]: Log-structured key-value store over NOR flash sectors
+//! - [`rmw::SectorRmw`]: Byte-addressable writes over erase-before-write flash
+//! - [`checksum::ChecksummedStorage`]: Fixed-size records verified against a CRC32 on read
+//! - [`concat::ConcatStorage`]: Two devices joined into one address space
+//! - [`RingLog`]: Circular event log that overwrites its oldest entries
+//! - `compat::SyncToAsync`: Async traits over blocking storage (with `async` feature)
+//! - [`bbt::BadBlockTable`]: NAND flash with bad blocks remapped to a reserve pool
+//!
 //! # Usage Examples
 //!
 //! ## Basic Storage Operations
@@ -122,6 +134,32 @@
 /// Common error types for storage operations
 pub mod error;
 
+/// Read-only storage over a borrowed byte slice
+pub mod slice;
+
+/// Log-structured key-value store over flash sectors
+pub mod kv;
+
+/// Read-modify-write adapter for erase-before-write flash
+pub mod rmw;
+
+/// Fixed-size records protected by a CRC32 trailer
+pub mod checksum;
+
+/// Two storage devices presented as one contiguous address space
+pub mod concat;
+
+/// Circular log of variable-length entries over a region
+pub mod ring;
+pub use ring::RingLog;
+
+/// Async storage traits over blocking storage
+#[cfg(feature = "async")]
+pub mod compat;
+
+/// Bad-block remapping for NAND flash
+pub mod bbt;
+
 /// Re-exports of common traits for convenient importing
 pub mod prelude {
     #[cfg(feature = "async")]
@@ -206,6 +244,110 @@ pub trait Region {
     }
 }
 
+/// Size and alignment units of a storage device.
+///
+/// Gathers what [`BlockStorage`], [`SectorStorage`] and [`Eeprom`] report
+/// separately, so alignment-aware code can ask any device for its smallest
+/// write and erase units through [`ReadStorage::geometry`].
+///
+/// The constructors derive the units from each trait combination:
+///
+/// | Implemented traits                   | `write_size`      | `erase_size` |
+/// |--------------------------------------|-------------------|--------------|
+/// | none of the below (default)          | 1                 | 1            |
+/// | [`BlockStorage`]                     | block size        | block size   |
+/// | [`SectorStorage`]                    | 1                 | sector size  |
+/// | [`BlockStorage`] + [`SectorStorage`] | block size        | sector size  |
+/// | [`Eeprom`]                           | page size, else 1 | 1            |
+///
+/// Sector devices (NOR flash) program individual bytes but erase whole
+/// sectors; when they also report blocks, the block is taken as the program
+/// page. EEPROM needs no erase, but page writes wrap at page boundaries, so
+/// the page is the write unit.
+///
+/// # Examples
+///
+/// ```rust
+/// use libiot::storage::{BlockStorage, Geometry, ReadStorage};
+/// # struct Card;
+/// # impl BlockStorage for Card {
+/// #     fn block_size(&self) -> usize { 512 }
+/// #     fn block_count(&self) -> usize { 8 }
+/// # }
+///
+/// impl ReadStorage for Card {
+///     type Error = ();
+///     fn read(&mut self, _offset: u32, _bytes: &mut [u8]) -> Result<(), ()> { Ok(()) }
+///     fn capacity(&self) -> usize { 4096 }
+///     fn geometry(&self) -> Geometry {
+///         Geometry::blocks(self)
+///     }
+/// }
+///
+/// let geometry = Card.geometry();
+/// assert_eq!(geometry, Geometry { capacity: 4096, write_size: 512, erase_size: 512 });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Geometry {
+    /// Total capacity in bytes
+    pub capacity: usize,
+    /// Smallest unit, in bytes, that writes should be aligned to
+    pub write_size: usize,
+    /// Smallest unit, in bytes, that can be erased
+    pub erase_size: usize,
+}
+
+impl Geometry {
+    /// Geometry of a byte-addressable device that needs no erase.
+    ///
+    /// This is what [`ReadStorage::geometry`] reports by default.
+    pub fn byte_addressable(capacity: usize) -> Self {
+        Self {
+            capacity,
+            write_size: 1,
+            erase_size: 1,
+        }
+    }
+
+    /// Geometry of a block device, written and erased in whole blocks.
+    pub fn blocks<S: ReadStorage + BlockStorage + ?Sized>(storage: &S) -> Self {
+        Self {
+            capacity: storage.capacity(),
+            write_size: storage.block_size(),
+            erase_size: storage.block_size(),
+        }
+    }
+
+    /// Geometry of a sector device, written per byte and erased per sector.
+    pub fn sectors<S: ReadStorage + SectorStorage + ?Sized>(storage: &S) -> Self {
+        Self {
+            capacity: storage.capacity(),
+            write_size: 1,
+            erase_size: storage.sector_size(),
+        }
+    }
+
+    /// Geometry of a device that programs blocks and erases sectors.
+    pub fn blocks_and_sectors<S: ReadStorage + BlockStorage + SectorStorage + ?Sized>(
+        storage: &S,
+    ) -> Self {
+        Self {
+            capacity: storage.capacity(),
+            write_size: storage.block_size(),
+            erase_size: storage.sector_size(),
+        }
+    }
+
+    /// Geometry of an EEPROM, written per page when page mode is supported.
+    pub fn eeprom<S: Eeprom + ?Sized>(storage: &S) -> Self {
+        Self {
+            capacity: storage.capacity(),
+            write_size: storage.page_size().unwrap_or(1),
+            erase_size: 1,
+        }
+    }
+}
+
 // ========================
 // Core Synchronous Traits
 // ========================
@@ -273,6 +415,15 @@ pub trait ReadStorage {
     /// println!("Storage capacity: {} bytes", storage.capacity());
     /// ```
     fn capacity(&self) -> usize;
+
+    /// Get the capacity and write/erase units of the device.
+    ///
+    /// Defaults to [`Geometry::byte_addressable`]. Devices that implement
+    /// [`BlockStorage`], [`SectorStorage`] or [`Eeprom`] should override it
+    /// with the matching [`Geometry`] constructor.
+    fn geometry(&self) -> Geometry {
+        Geometry::byte_addressable(self.capacity())
+    }
 }
 
 /// Trait for storage devices that support both read and write operations.
@@ -343,6 +494,25 @@ pub trait Storage: ReadStorage {
     /// storage.write(0, data).unwrap();
     /// ```
     fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Make all previous writes durable.
+    ///
+    /// Backends that buffer writes (write-back caches, devices with an
+    /// internal page buffer, file-backed simulators) must flush everything
+    /// still pending to the physical medium before returning. Data written
+    /// before a successful `sync` survives a reset; data written after it
+    /// may not. Callers should sync at transaction boundaries, such as after
+    /// committing a record or finishing an image download.
+    ///
+    /// The default implementation does nothing, which is correct for
+    /// devices that write straight through to the medium.
+    ///
+    /// # Errors
+    ///
+    /// - `WriteError` if pending data could not be written out
+    fn sync(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
 }
 
 /// Trait for storage devices that support erase operations.
@@ -427,6 +597,13 @@ pub trait AsyncReadStorage {
 
     /// Get the total capacity of the storage device in bytes.
     fn capacity(&self) -> usize;
+
+    /// Get the capacity and write/erase units of the device.
+    ///
+    /// See [`ReadStorage::geometry`].
+    fn geometry(&self) -> Geometry {
+        Geometry::byte_addressable(self.capacity())
+    }
 }
 
 /// Trait for storage devices that support both read and write operations asynchronously.
@@ -444,6 +621,13 @@ pub trait AsyncStorage: AsyncReadStorage {
     /// * `Ok(())` - Data written successfully
     /// * `Err(error)` - Write operation failed
     async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Make all previous writes durable asynchronously.
+    ///
+    /// See [`Storage::sync`]. The default implementation does nothing.
+    async fn sync(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
 }
 
 /// Trait for storage devices that support erase operations asynchronously.
@@ -681,6 +865,7 @@ pub trait NandFlash: Storage + BlockStorage {
 /// NAND Flash block status.
 ///
 /// Indicates whether a block is usable or should be avoided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BlockStatus {
     /// Block is good and can be used normally.
     Good,
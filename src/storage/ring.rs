@@ -0,0 +1,354 @@
+//! Circular event log over byte-writable storage.
+//!
+//! [`RingLog`] appends entries to a [`Region`] of EEPROM, FRAM or similar
+//! storage that can be rewritten without an erase. Once the region is full,
+//! each new entry overwrites the oldest ones, so the log always holds the
+//! most recent entries that fit.
+//!
+//! # Layout
+//!
+//! Every entry is stored as a 10-byte header followed by its data:
+//!
+//! ```text
+//! sequence: u32 LE | len: u16 LE | crc32: u32 LE | data
+//! ```
+//!
+//! The sequence number grows by one with each entry, and the CRC32 covers
+//! the sequence, the length and the data. Entries follow each other back to
+//! back from the start of the region.
+//!
+//! # Wraparound
+//!
+//! Entries are never split. An entry that does not fit between the end of
+//! the newest entry and the end of the region is written at the start of
+//! the region instead, and the space it skipped stays unused until the next
+//! lap; entries that were still there drop out of the log together with the
+//! ones the new entry overwrites.
+//!
+//! # Recovery
+//!
+//! [`RingLog::mount`] keeps no state outside the entries themselves. It
+//! scans the region for the valid entry with the highest sequence number,
+//! which is the newest, and then for the oldest entry from which the
+//! sequence numbers run without a gap up to it. An entry torn by a power
+//! loss fails its CRC, so the log ends at the entry before it, and entries
+//! that were partly overwritten are dropped the same way.
+//!
+//! Mounting reads the header at every offset of the region, so it takes
+//! time proportional to the region's size; appends and iteration only read
+//! the entries they touch.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::storage::RingLog;
+//! use libiot::storage::Region;
+//! # use libiot::storage::{ReadStorage, Storage};
+//! # struct Eeprom([u8; 256]);
+//! # impl ReadStorage for Eeprom {
+//! #     type Error = ();
+//! #     fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+//! #         let offset = offset as usize;
+//! #         bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+//! #         Ok(())
+//! #     }
+//! #     fn capacity(&self) -> usize { self.0.len() }
+//! # }
+//! # impl Storage for Eeprom {
+//! #     fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+//! #         let offset = offset as usize;
+//! #         self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+//! #         Ok(())
+//! #     }
+//! # }
+//! struct Events;
+//!
+//! impl Region for Events {
+//!     fn start(&self) -> u32 { 0x40 }
+//!     fn end(&self) -> u32 { 0x100 }
+//! }
+//!
+//! let mut log: RingLog<_> = RingLog::mount(Eeprom([0xFF; 256]), &Events).unwrap();
+//! log.append(b"boot").unwrap();
+//! log.append(b"link up").unwrap();
+//!
+//! let mut entries = log.iter();
+//! assert_eq!(entries.next().unwrap().unwrap().as_slice(), b"boot");
+//! assert_eq!(entries.next().unwrap().unwrap().as_slice(), b"link up");
+//! assert!(entries.next().is_none());
+//! ```
+
+use super::{Region, Storage};
+use crate::util::Crc32;
+use heapless::Vec;
+
+/// Size of the header in front of every entry
+pub const HEADER_LEN: usize = 10;
+
+/// Errors returned by [`RingLog`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error<E> {
+    /// The underlying storage failed.
+    Storage(E),
+    /// The entry is longer than the log's `N`, or than the region minus
+    /// the header.
+    TooLong,
+    /// The region is too small to hold even an empty entry.
+    InvalidLayout,
+}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for Error<E> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::Storage(e) => defmt::write!(f, "Storage({})", e),
+            Error::TooLong => defmt::write!(f, "TooLong"),
+            Error::InvalidLayout => defmt::write!(f, "InvalidLayout"),
+        }
+    }
+}
+
+/// Location of a valid entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cursor {
+    pos: u32,
+    seq: u32,
+    len: usize,
+}
+
+impl Cursor {
+    /// Offset just past the entry
+    fn end(&self) -> u32 {
+        self.pos + (HEADER_LEN + self.len) as u32
+    }
+}
+
+/// An append-only log that overwrites its oldest entries when full.
+///
+/// `N` is the longest entry in bytes, and the size of the buffer each entry
+/// is read into; see the [module documentation](self) for the layout and
+/// the wraparound policy.
+pub struct RingLog<S, const N: usize = 64> {
+    storage: S,
+    start: u32,
+    end: u32,
+    // Newest and oldest entries, both `None` while the log is empty
+    head: Option<Cursor>,
+    tail: Option<Cursor>,
+}
+
+impl<S: Storage, const N: usize> RingLog<S, N> {
+    /// Open the log kept in `region` of `storage`, recovering its entries.
+    ///
+    /// A region that holds no valid entries, such as blank EEPROM, gives an
+    /// empty log.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidLayout`] - See the variant
+    /// * [`Error::Storage`] - Reading failed
+    pub fn mount<R: Region>(storage: S, region: &R) -> Result<Self, Error<S::Error>> {
+        let (start, end) = (region.start(), region.end());
+        if end < start || ((end - start) as usize) < HEADER_LEN {
+            return Err(Error::InvalidLayout);
+        }
+        let mut log = Self {
+            storage,
+            start,
+            end,
+            head: None,
+            tail: None,
+        };
+
+        // The newest entry has the highest sequence number
+        let mut head: Option<Cursor> = None;
+        for pos in start..end {
+            if let Some(entry) = log.load(pos, &mut [0; N])? {
+                if head.is_none_or(|head| entry.seq > head.seq) {
+                    head = Some(entry);
+                }
+            }
+        }
+        let Some(head) = head else {
+            return Ok(log);
+        };
+
+        // The oldest is the first entry after it whose sequence numbers
+        // lead up to it without a gap
+        let mut tail = head;
+        for pos in (head.end()..end).chain(start..head.pos) {
+            if let Some(entry) = log.load(pos, &mut [0; N])? {
+                if entry.seq < head.seq && log.leads_to(entry, head)? {
+                    tail = entry;
+                    break;
+                }
+            }
+        }
+
+        log.head = Some(head);
+        log.tail = Some(tail);
+        Ok(log)
+    }
+
+    /// Add `entry` as the newest entry, dropping the oldest ones it
+    /// overwrites.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::TooLong`] - See the variant
+    /// * [`Error::Storage`] - Reading or writing failed; the entry may be
+    ///   partly written, and is then dropped when the log is mounted again
+    pub fn append(&mut self, entry: &[u8]) -> Result<(), Error<S::Error>> {
+        let size = HEADER_LEN + entry.len();
+        if entry.len() > N.min(u16::MAX as usize) || size > (self.end - self.start) as usize {
+            return Err(Error::TooLong);
+        }
+
+        let (seq, mut pos) = match self.head {
+            Some(head) => (head.seq.wrapping_add(1), head.end()),
+            None => (0, self.start),
+        };
+        if pos as usize + size > self.end as usize {
+            // Entries left in the skipped space drop out of the log
+            while let Some(tail) = self.tail.filter(|tail| tail.pos >= pos) {
+                self.tail = self.next(tail)?;
+            }
+            pos = self.start;
+        }
+        let new_end = pos + size as u32;
+        while let Some(tail) = self
+            .tail
+            .filter(|tail| tail.pos < new_end && pos < tail.end())
+        {
+            self.tail = self.next(tail)?;
+        }
+
+        let mut header = [0u8; HEADER_LEN];
+        header[0..4].copy_from_slice(&seq.to_le_bytes());
+        header[4..6].copy_from_slice(&(entry.len() as u16).to_le_bytes());
+        let crc = entry_crc(&header[..6], entry);
+        header[6..10].copy_from_slice(&crc.to_le_bytes());
+        self.storage.write(pos, &header).map_err(Error::Storage)?;
+        self.storage
+            .write(pos + HEADER_LEN as u32, entry)
+            .map_err(Error::Storage)?;
+        self.storage.sync().map_err(Error::Storage)?;
+
+        let head = Cursor {
+            pos,
+            seq,
+            len: entry.len(),
+        };
+        self.head = Some(head);
+        self.tail.get_or_insert(head);
+        Ok(())
+    }
+
+    /// Iterate over the entries, oldest first.
+    ///
+    /// Each entry is read from storage as the iterator reaches it. After a
+    /// storage error, the iterator yields the error and then stops.
+    pub fn iter(&mut self) -> Iter<'_, S, N> {
+        Iter {
+            next: self.tail,
+            log: self,
+        }
+    }
+
+    /// Whether the log holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Release the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// The entry written after `entry`, unless `entry` is the newest
+    fn next(&mut self, entry: Cursor) -> Result<Option<Cursor>, Error<S::Error>> {
+        if Some(entry) == self.head {
+            return Ok(None);
+        }
+        self.successor(entry)
+    }
+
+    /// The valid entry with the next sequence number, found either right
+    /// after `entry` or, if the writer wrapped around, at the region start
+    fn successor(&mut self, entry: Cursor) -> Result<Option<Cursor>, Error<S::Error>> {
+        let seq = entry.seq.wrapping_add(1);
+        for pos in [entry.end(), self.start] {
+            if let Some(next) = self.load(pos, &mut [0; N])? {
+                if next.seq == seq {
+                    return Ok(Some(next));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether following successors from `entry` reaches `head`
+    fn leads_to(&mut self, mut entry: Cursor, head: Cursor) -> Result<bool, Error<S::Error>> {
+        while entry.seq < head.seq {
+            match self.successor(entry)? {
+                Some(next) => entry = next,
+                None => return Ok(false),
+            }
+        }
+        Ok(entry == head)
+    }
+
+    /// Read the entry at `pos` into `data`, returning `None` if there is no
+    /// valid entry there
+    fn load(&mut self, pos: u32, data: &mut [u8; N]) -> Result<Option<Cursor>, Error<S::Error>> {
+        if pos as usize + HEADER_LEN > self.end as usize {
+            return Ok(None);
+        }
+        let mut header = [0u8; HEADER_LEN];
+        self.storage
+            .read(pos, &mut header)
+            .map_err(Error::Storage)?;
+        let entry = Cursor {
+            pos,
+            seq: u32::from_le_bytes([header[0], header[1], header[2], header[3]]),
+            len: u16::from_le_bytes([header[4], header[5]]) as usize,
+        };
+        if entry.len > N || entry.end() > self.end {
+            return Ok(None);
+        }
+        let data = &mut data[..entry.len];
+        self.storage
+            .read(pos + HEADER_LEN as u32, data)
+            .map_err(Error::Storage)?;
+        let crc = u32::from_le_bytes([header[6], header[7], header[8], header[9]]);
+        Ok((entry_crc(&header[..6], data) == crc).then_some(entry))
+    }
+}
+
+/// Iterator over the entries of a [`RingLog`], oldest first.
+pub struct Iter<'a, S, const N: usize> {
+    log: &'a mut RingLog<S, N>,
+    next: Option<Cursor>,
+}
+
+impl<S: Storage, const N: usize> Iterator for Iter<'_, S, N> {
+    type Item = Result<Vec<u8, N>, Error<S::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.next.take()?;
+        let mut data = [0u8; N];
+        let result = self.log.load(entry.pos, &mut data).and_then(|_| {
+            self.next = self.log.next(entry)?;
+            Ok(Vec::from_slice(&data[..entry.len]).unwrap_or_default())
+        });
+        Some(result)
+    }
+}
+
+fn entry_crc(header: &[u8], data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(header);
+    crc.update(data);
+    crc.finalize()
+}
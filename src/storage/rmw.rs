@@ -0,0 +1,190 @@
+//! Byte-addressable writes over erase-before-write flash.
+//!
+//! NOR flash can only clear bits when programming, so changing bytes that
+//! were written before means erasing their whole sector. [`SectorRmw`]
+//! hides this: each [`write`](Storage::write) reads every sector it touches
+//! into a scratch buffer, patches in the new bytes, erases the sector and
+//! programs it back. Code written against [`Storage`], such as a settings
+//! blob at a fixed offset, then works on raw flash unchanged.
+//!
+//! The scratch buffer holds one sector, so `N` must be at least the
+//! device's [`sector_size`](SectorStorage::sector_size). It lives inside the
+//! adapter; for large sectors, place the adapter in a `static` rather than
+//! on the stack.
+//!
+//! Every write that changes programmed bytes costs an erase of each sector
+//! it touches, and a power loss between the erase and the program loses the
+//! rest of that sector. Batch updates into one write where possible, and use
+//! [`KvStore`](super::kv::KvStore) for data that changes often.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::storage::rmw::SectorRmw;
+//! use libiot::storage::{ReadStorage, Storage};
+//! # use libiot::storage::{BlockingErase, SectorStorage};
+//! # struct Flash([u8; 1024]);
+//! # impl ReadStorage for Flash {
+//! #     type Error = ();
+//! #     fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+//! #         let offset = offset as usize;
+//! #         bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+//! #         Ok(())
+//! #     }
+//! #     fn capacity(&self) -> usize { self.0.len() }
+//! # }
+//! # impl Storage for Flash {
+//! #     fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+//! #         let offset = offset as usize;
+//! #         for (cell, byte) in self.0[offset..offset + bytes.len()].iter_mut().zip(bytes) {
+//! #             *cell &= byte;
+//! #         }
+//! #         Ok(())
+//! #     }
+//! # }
+//! # impl BlockingErase for Flash {
+//! #     fn erase(&mut self, from: u32, to: u32) -> Result<(), ()> {
+//! #         self.0[from as usize..to as usize].fill(0xFF);
+//! #         Ok(())
+//! #     }
+//! # }
+//! # impl SectorStorage for Flash {
+//! #     fn sector_size(&self) -> usize { 256 }
+//! #     fn sector_count(&self) -> usize { 4 }
+//! # }
+//!
+//! // 256-byte sectors
+//! let mut flash: SectorRmw<_, 256> = SectorRmw::new(Flash([0xFF; 1024]));
+//!
+//! flash.write(0x10, b"interval=60").unwrap();
+//! flash.write(0x19, b"30").unwrap();
+//!
+//! let mut setting = [0u8; 11];
+//! flash.read(0x10, &mut setting).unwrap();
+//! assert_eq!(&setting, b"interval=30");
+//! ```
+
+use super::{BlockingErase, Geometry, ReadStorage, SectorStorage, Storage};
+
+/// Erased flash
+const ERASED: u8 = 0xFF;
+
+/// Presents sectored flash as byte-addressable [`Storage`].
+///
+/// `N` is the size of the scratch buffer, which must hold one sector; it
+/// defaults to 4 KiB, the usual sector size of SPI NOR flash.
+pub struct SectorRmw<S, const N: usize = 4096> {
+    storage: S,
+    scratch: [u8; N],
+}
+
+impl<S: SectorStorage, const N: usize> SectorRmw<S, N> {
+    /// Wrap `storage`.
+    ///
+    /// # Panics
+    ///
+    /// If the device's sector size is larger than `N`. This is a
+    /// configuration error, caught the first time the code runs.
+    pub fn new(storage: S) -> Self {
+        assert!(
+            storage.sector_size() <= N,
+            "SectorRmw scratch buffer is smaller than a sector"
+        );
+        Self {
+            storage,
+            scratch: [0; N],
+        }
+    }
+
+    /// The underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Release the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S: ReadStorage, const N: usize> ReadStorage for SectorRmw<S, N> {
+    type Error = S::Error;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.storage.read(offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        self.storage.capacity()
+    }
+
+    /// Byte-addressable: writes need no erase.
+    fn geometry(&self) -> Geometry {
+        Geometry::byte_addressable(self.capacity())
+    }
+}
+
+impl<S, const N: usize> Storage for SectorRmw<S, N>
+where
+    S: Storage + BlockingErase + SectorStorage,
+{
+    /// Write `bytes` at `offset`, preserving the rest of every sector they
+    /// touch.
+    ///
+    /// A sector is only erased if the write changes bytes that are not
+    /// erased; a span that is still blank is programmed directly, and a span
+    /// that already holds `bytes` is left alone.
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let sector_size = self.storage.sector_size();
+        let mut offset = offset as usize;
+        let mut bytes = bytes;
+
+        while !bytes.is_empty() {
+            let sector_start = offset - offset % sector_size;
+            let start = offset - sector_start;
+            let len = bytes.len().min(sector_size - start);
+            let (patch, rest) = bytes.split_at(len);
+
+            let sector = &mut self.scratch[..sector_size];
+            self.storage.read(sector_start as u32, sector)?;
+            let span = &mut sector[start..start + len];
+            if span != patch {
+                if span.iter().all(|&byte| byte == ERASED) {
+                    self.storage.write(offset as u32, patch)?;
+                } else {
+                    span.copy_from_slice(patch);
+                    self.storage
+                        .erase(sector_start as u32, (sector_start + sector_size) as u32)?;
+                    self.storage.write(sector_start as u32, sector)?;
+                }
+            }
+
+            offset += len;
+            bytes = rest;
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<(), Self::Error> {
+        self.storage.sync()
+    }
+}
+
+impl<S, const N: usize> BlockingErase for SectorRmw<S, N>
+where
+    S: Storage + BlockingErase + SectorStorage,
+{
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        self.storage.erase(from, to)
+    }
+}
+
+impl<S: SectorStorage, const N: usize> SectorStorage for SectorRmw<S, N> {
+    fn sector_size(&self) -> usize {
+        self.storage.sector_size()
+    }
+
+    fn sector_count(&self) -> usize {
+        self.storage.sector_count()
+    }
+}
@@ -0,0 +1,71 @@
+//! Read-only storage over a borrowed byte slice.
+//!
+//! [`SliceStorage`] exposes data that is already in memory, such as
+//! certificates or configuration defaults linked into flash, through the
+//! storage traits. Code written against [`ReadStorage`] can then load those
+//! assets the same way it reads an external device, and tests can serve
+//! fixed images without a mock.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::storage::ReadStorage;
+//! use libiot::storage::slice::SliceStorage;
+//!
+//! static DEFAULTS: &[u8] = b"interval=60\n";
+//!
+//! let mut storage = SliceStorage::new(DEFAULTS);
+//! let mut key = [0u8; 8];
+//! storage.read(0, &mut key).unwrap();
+//! assert_eq!(&key, b"interval");
+//! ```
+
+use super::error::Error;
+use super::{ReadStorage, Storage};
+
+/// Read-only storage backed by a byte slice.
+///
+/// Reads are bounds-checked against the slice. The [`Storage`]
+/// implementation exists so the slice can stand in for writable storage
+/// that is only read in practice; every write fails with
+/// [`Error::Unsupported`].
+#[derive(Debug, Clone, Copy)]
+pub struct SliceStorage<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> SliceStorage<'a> {
+    /// Wrap `data` as storage; offset 0 is its first byte.
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// The whole underlying slice, like [`RamStorage::as_slice`](super::RamStorage::as_slice).
+    pub fn as_slice(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl ReadStorage for SliceStorage<'_> {
+    type Error = Error;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let start = offset as usize;
+        let source = start
+            .checked_add(bytes.len())
+            .and_then(|end| self.data.get(start..end))
+            .ok_or(Error::OutOfBounds)?;
+        bytes.copy_from_slice(source);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl Storage for SliceStorage<'_> {
+    fn write(&mut self, _offset: u32, _bytes: &[u8]) -> Result<(), Self::Error> {
+        Err(Error::Unsupported)
+    }
+}
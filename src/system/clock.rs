@@ -0,0 +1,79 @@
+//! Time source abstractions.
+//!
+//! Several components need to know how much time has passed (fix freshness,
+//! timeouts, back-off delays) without depending on a particular timer
+//! peripheral or operating system. This module defines a minimal clock trait
+//! that platforms implement on top of SysTick, an RTC, `std::time::Instant`,
+//! or any other monotonic counter, and a matching [`Delay`] trait for
+//! blocking waits.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use libiot::system::clock::MonotonicClock;
+//! use core::cell::Cell;
+//!
+//! /// A clock advanced manually, e.g. from a 1 ms timer interrupt.
+//! struct TickClock {
+//!     ticks: Cell<u64>,
+//! }
+//!
+//! impl MonotonicClock for TickClock {
+//!     fn now_ms(&self) -> u64 {
+//!         self.ticks.get()
+//!     }
+//! }
+//!
+//! let clock = TickClock { ticks: Cell::new(0) };
+//! let start = clock.now_ms();
+//! clock.ticks.set(250);
+//! assert_eq!(clock.elapsed_ms(start), 250);
+//! ```
+
+/// A monotonic millisecond clock.
+///
+/// Implementations must never go backwards. The epoch is arbitrary (typically
+/// boot time); only differences between readings are meaningful.
+pub trait MonotonicClock {
+    /// Current time in milliseconds since an arbitrary, fixed epoch.
+    fn now_ms(&self) -> u64;
+
+    /// Milliseconds elapsed since an earlier reading of [`now_ms`](Self::now_ms).
+    ///
+    /// Saturates at zero if `since` lies in the future.
+    fn elapsed_ms(&self, since: u64) -> u64 {
+        self.now_ms().saturating_sub(since)
+    }
+}
+
+impl<T: MonotonicClock + ?Sized> MonotonicClock for &T {
+    fn now_ms(&self) -> u64 {
+        (**self).now_ms()
+    }
+}
+
+/// A blocking millisecond delay.
+///
+/// Used by components that need to wait between attempts, such as
+/// [`retry`](crate::network::retry::retry). Implementations typically wrap a
+/// HAL delay provider or `std::thread::sleep`.
+pub trait Delay {
+    /// Block for at least `ms` milliseconds.
+    fn delay_ms(&self, ms: u64);
+}
+
+impl<T: Delay + ?Sized> Delay for &T {
+    fn delay_ms(&self, ms: u64) {
+        (**self).delay_ms(ms)
+    }
+}
+
+/// A [`Delay`] that returns immediately.
+///
+/// Useful for retrying without back-off, and in tests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoDelay;
+
+impl Delay for NoDelay {
+    fn delay_ms(&self, _ms: u64) {}
+}
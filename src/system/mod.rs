@@ -7,6 +7,7 @@
 //! # Available Utilities
 //!
 //! - **[`shell`]**: Command-line interface implementation for embedded systems
+//! - **[`clock`]**: Monotonic time source abstraction used for timing and freshness checks
 //!
 //! # Design Principles
 //!
@@ -42,3 +43,9 @@
 /// Provides a complete command-line interface implementation with support for
 /// command registration, argument parsing, help system, and interactive input processing.
 pub mod shell;
+
+/// Monotonic time source abstraction.
+///
+/// Provides the [`MonotonicClock`](clock::MonotonicClock) trait that platform
+/// code implements so that timing-dependent components stay hardware agnostic.
+pub mod clock;
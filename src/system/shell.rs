@@ -10,7 +10,11 @@
 //! - **Command Registration**: Support for both static and dynamic command registration
 //! - **Argument Parsing**: Handles quoted arguments and escape sequences
 //! - **Help System**: Built-in help for individual commands and command listing
+//! - **Paging**: Optional `-- more --` paging of long command listings
+//! - **Namespaces**: Commands named `group.command` are listed by group, and
+//!   `list <group>` shows a single group
 //! - **Input Processing**: Character-by-character input processing with echo support
+//! - **Context**: [`ContextShell`] passes a user-owned context to every handler
 //! - **Extensible**: Easy to add custom commands and modify behavior
 //!
 //! # Architecture
@@ -76,8 +80,8 @@
 //! }
 //!
 //! const STATIC_COMMANDS: &[Command] = &[
-//!     Command { name: "status", description: "Show system status", handler: status_cmd },
-//!     Command { name: "reset", description: "Reset the system", handler: reset_cmd },
+//!     Command::new("status", "Show system status", status_cmd),
+//!     Command::new("reset", "Reset the system", reset_cmd).with_args(Some(0), Some(1)),
 //! ];
 //!
 //! let mut shell = Shell::new();
@@ -94,6 +98,35 @@
 //! > echo "Line 1\nLine 2"        # Escape sequences within quotes
 //! > path "C:\\Program Files"     # Escaped backslashes
 //! ```
+//!
+//! ## Handlers with Context
+//!
+//! [`ContextShell`] owns a context, such as the GPIO or sensor handles the
+//! commands act on, and passes it to every handler, so commands don't need
+//! global statics to reach device state:
+//!
+//! ```rust
+//! use libiot::system::shell::{ContextShell, ShellResult};
+//!
+//! struct Board {
+//!     led: bool,
+//! }
+//!
+//! fn led_command(board: &mut Board, _argc: usize, argv: &[&str]) -> ShellResult {
+//!     match argv[1] {
+//!         "on" => board.led = true,
+//!         "off" => board.led = false,
+//!         _ => return ShellResult::InvalidParameter,
+//!     }
+//!     ShellResult::Ok
+//! }
+//!
+//! let mut shell = ContextShell::with_context(Board { led: false });
+//! shell.register_command_with_args("led", "led <on|off>", led_command, Some(1), Some(1));
+//!
+//! shell.input(b"led on\r");
+//! assert!(shell.context().led);
+//! ```
 
 use core::str;
 
@@ -113,10 +146,29 @@ pub const MAX_ARGS: usize = 16;
 /// Maximum number of dynamic commands that can be registered.
 ///
 /// This defines how many commands can be registered at runtime using
-/// [`register_command`](Shell::register_command). Static commands registered
-/// with [`register_static_commands`](Shell::register_static_commands) don't count against this limit.
+/// [`register_command`](ContextShell::register_command). Static commands registered
+/// with [`register_static_commands`](ContextShell::register_static_commands) don't count against this limit.
 pub const MAX_DYNAMIC_COMMANDS: usize = 32;
 
+/// Prompt shown when `list` output is paused waiting for a keypress.
+pub const MORE_PROMPT: &str = "-- more --";
+
+/// Marker appended to command descriptions truncated to fit the list width
+pub const LIST_ELLIPSIS: &str = "...";
+
+/// Spaces between the command name column and the description column in `list`
+const LIST_COLUMN_GAP: usize = 2;
+
+/// Separator between a command's namespace and the rest of its name.
+///
+/// Commands such as `gpio.set` and `gpio.get` share the namespace `gpio`.
+/// This is only a naming convention: such commands are registered and
+/// invoked by their full name like any other, but `list` groups them under
+/// their namespace and `list gpio` (or `gpio --help`) shows just that group.
+/// Nested names such as `wifi.ap.start` are grouped by their first segment
+/// and can be listed with `list wifi.ap`.
+pub const NAMESPACE_SEPARATOR: char = '.';
+
 // ASCII control character constants for input processing
 /// ASCII backspace character (0x08).
 pub const ASCII_BACKSPACE: u8 = 0x08;
@@ -151,7 +203,7 @@ pub const ASCII_SPACE: u8 = 0x20;
 ///     ShellResult::Ok
 /// }
 /// ```
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ShellResult {
     /// Operation completed successfully.
     Ok,
@@ -163,6 +215,37 @@ pub enum ShellResult {
     BufferOverflow,
 }
 
+/// Outcome of processing one command line.
+///
+/// Returned by [`ContextShell::execute_line`] and reported for interactive input by
+/// [`ContextShell::last_status`], so callers can tell an unknown command apart from
+/// one that ran, without scraping the shell's output.
+///
+/// # Examples
+///
+/// ```rust
+/// use libiot::system::shell::{LineStatus, Shell, ShellResult};
+///
+/// let mut shell = Shell::new();
+/// shell.register_command("ping", "Reply", |_, _| ShellResult::Ok);
+///
+/// assert_eq!(shell.execute_line("ping"), Ok(LineStatus::Executed(ShellResult::Ok)));
+/// assert_eq!(shell.execute_line("pong"), Ok(LineStatus::NotFound));
+/// assert_eq!(shell.execute_line("   "), Ok(LineStatus::Empty));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineStatus {
+    /// The command was resolved, including built-ins and `-h`/`--help`.
+    ///
+    /// Carries the handler's result, or [`ShellResult::InvalidParameter`] if
+    /// the argument count was rejected before the handler was called.
+    Executed(ShellResult),
+    /// No registered or built-in command has this name.
+    NotFound,
+    /// The line contained no command.
+    Empty,
+}
+
 /// Function signature for command handlers.
 ///
 /// Command handlers receive the argument count and a slice of argument strings.
@@ -192,6 +275,48 @@ pub enum ShellResult {
 /// ```
 pub type CommandFn = fn(argc: usize, argv: &[&str]) -> ShellResult;
 
+/// Function signature for command handlers of a [`ContextShell`].
+///
+/// Like [`CommandFn`], but the handler also receives the shell's context,
+/// through which it can reach device state such as GPIO or sensor handles.
+///
+/// # Examples
+///
+/// ```rust
+/// use libiot::system::shell::{ContextCommandFn, ShellResult};
+///
+/// struct Counter {
+///     hits: u32,
+/// }
+///
+/// let hit_command: ContextCommandFn<Counter> = |counter, _argc, _argv| {
+///     counter.hits += 1;
+///     ShellResult::Ok
+/// };
+/// ```
+pub type ContextCommandFn<Ctx> = fn(ctx: &mut Ctx, argc: usize, argv: &[&str]) -> ShellResult;
+
+/// A command handler that a shell with context `Ctx` can call.
+///
+/// Implemented for [`CommandFn`], the handler type of [`Shell`], and for
+/// [`ContextCommandFn<Ctx>`], the handler type of [`ContextShell<Ctx>`].
+pub trait Handler<Ctx>: Copy + 'static {
+    /// Run the handler with the shell's context and the parsed arguments.
+    fn call(self, ctx: &mut Ctx, argc: usize, argv: &[&str]) -> ShellResult;
+}
+
+impl Handler<()> for CommandFn {
+    fn call(self, _ctx: &mut (), argc: usize, argv: &[&str]) -> ShellResult {
+        self(argc, argv)
+    }
+}
+
+impl<Ctx: 'static> Handler<Ctx> for ContextCommandFn<Ctx> {
+    fn call(self, ctx: &mut Ctx, argc: usize, argv: &[&str]) -> ShellResult {
+        self(ctx, argc, argv)
+    }
+}
+
 /// Function signature for output handlers.
 ///
 /// Output handlers receive text from the shell and are responsible for
@@ -214,28 +339,58 @@ pub type CommandFn = fn(argc: usize, argv: &[&str]) -> ShellResult;
 /// ```
 pub type OutputFn = fn(&str);
 
+/// Boxed command handler that may capture state.
+///
+/// Registered with [`Shell::register_closure`](ContextShell::register_closure). Available with the `alloc`
+/// feature.
+#[cfg(feature = "alloc")]
+pub type BoxedCommandFn = alloc::boxed::Box<dyn FnMut(usize, &[&str]) -> ShellResult>;
+
+/// A command whose handler is a boxed closure.
+///
+/// `command.handler` is a placeholder; dispatch goes through `closure`.
+#[cfg(feature = "alloc")]
+struct ClosureCommand<F> {
+    command: Command<F>,
+    closure: BoxedCommandFn,
+}
+
 /// Command structure containing metadata and handler function.
 ///
 /// Each command consists of a name, description, and handler function.
 /// Commands can be registered statically (at compile time) or dynamically
 /// (at runtime).
 ///
+/// `F` is the handler type: [`CommandFn`] for a [`Shell`], or
+/// [`ContextCommandFn`] for a [`ContextShell`] (see [`ContextCommand`]).
+///
+/// Commands may optionally declare how many arguments they accept (not
+/// counting the command name itself). The shell checks the bounds before
+/// dispatch and prints a usage message built from the description when they
+/// are violated, so handlers don't need to re-check `argc` themselves.
+///
 /// # Examples
 ///
 /// ```rust
 /// use libiot::system::shell::{Command, ShellResult};
 ///
-/// let help_command = Command {
+/// let help_command: Command = Command {
 ///     name: "help",
 ///     description: "Show help information",
 ///     handler: |argc, argv| {
 ///         println!("Help system not implemented");
 ///         ShellResult::Ok
 ///     },
+///     min_args: None,
+///     max_args: None,
 /// };
+///
+/// // Equivalent, using the const constructor
+/// const SET_COMMAND: Command = Command::new("set", "set <key> <value>", |_, _| ShellResult::Ok)
+///     .with_args(Some(2), Some(2));
 /// ```
 #[derive(Clone)]
-pub struct Command {
+pub struct Command<F = CommandFn> {
     /// The command name as typed by the user.
     ///
     /// Command names are case-sensitive and should be unique within
@@ -255,14 +410,117 @@ pub struct Command {
     /// This function is called when the user invokes the command.
     /// It receives the parsed arguments and should return a result
     /// indicating success or failure.
-    pub handler: CommandFn,
+    pub handler: F,
+
+    /// Minimum number of arguments, excluding the command name.
+    ///
+    /// `None` means no lower bound is enforced.
+    pub min_args: Option<usize>,
+
+    /// Maximum number of arguments, excluding the command name.
+    ///
+    /// `None` means no upper bound is enforced.
+    pub max_args: Option<usize>,
 }
 
-/// Main shell structure managing input processing and command execution.
+impl Command {
+    /// Create a command without argument count bounds.
+    ///
+    /// This is a `const fn` so it can be used to build static command tables.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Command name as typed by the user
+    /// * `description` - Help text, also used as the usage message
+    /// * `handler` - Function to handle command execution
+    pub const fn new(name: &'static str, description: &'static str, handler: CommandFn) -> Self {
+        Self {
+            name,
+            description,
+            handler,
+            min_args: None,
+            max_args: None,
+        }
+    }
+}
+
+/// A command of a [`ContextShell`] with context `Ctx`.
+pub type ContextCommand<Ctx> = Command<ContextCommandFn<Ctx>>;
+
+impl<Ctx> Command<ContextCommandFn<Ctx>> {
+    /// Create a context command without argument count bounds.
+    ///
+    /// The counterpart of [`Command::new`] for static command tables of a
+    /// [`ContextShell`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::system::shell::{Command, ContextCommand, ShellResult};
+    ///
+    /// struct Board {
+    ///     resets: u32,
+    /// }
+    ///
+    /// const COMMANDS: &[ContextCommand<Board>] = &[Command::new_with_context(
+    ///     "reset",
+    ///     "Reset the board",
+    ///     |board, _, _| {
+    ///         board.resets += 1;
+    ///         ShellResult::Ok
+    ///     },
+    /// )];
+    /// ```
+    pub const fn new_with_context(
+        name: &'static str,
+        description: &'static str,
+        handler: ContextCommandFn<Ctx>,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            handler,
+            min_args: None,
+            max_args: None,
+        }
+    }
+}
+
+impl<F> Command<F> {
+    /// Set the accepted argument count bounds (excluding the command name).
+    ///
+    /// A command with `min_args > max_args` could never run, so
+    /// [`ContextShell::register_static_commands`] rejects tables containing
+    /// one.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_args` - Minimum number of arguments, or `None` for no minimum
+    /// * `max_args` - Maximum number of arguments, or `None` for no maximum
+    pub const fn with_args(mut self, min_args: Option<usize>, max_args: Option<usize>) -> Self {
+        self.min_args = min_args;
+        self.max_args = max_args;
+        self
+    }
+
+    /// Check whether `count` arguments (excluding the command name) satisfy
+    /// this command's bounds.
+    pub fn accepts_arg_count(&self, count: usize) -> bool {
+        self.min_args.is_none_or(|min| count >= min) && self.max_args.is_none_or(|max| count <= max)
+    }
+
+    /// Check that the bounds leave at least one acceptable argument count.
+    fn has_valid_bounds(&self) -> bool {
+        match (self.min_args, self.max_args) {
+            (Some(min), Some(max)) => min <= max,
+            _ => true,
+        }
+    }
+}
+
+/// Shell whose command handlers don't take a context.
 ///
-/// The shell handles character-by-character input processing, argument parsing,
-/// command lookup, and execution. It maintains both static and dynamic command
-/// registries and provides built-in help functionality.
+/// This is the usual shell; see [`ContextShell`] for the full API.
 ///
 /// # Examples
 ///
@@ -276,7 +534,44 @@ pub struct Command {
 /// // Configure output handler
 /// shell.set_output_function(|text| print!("{}", text));
 /// ```
-pub struct Shell {
+pub type Shell = ContextShell<(), CommandFn>;
+
+/// Main shell structure managing input processing and command execution.
+///
+/// The shell handles character-by-character input processing, argument parsing,
+/// command lookup, and execution. It maintains both static and dynamic command
+/// registries and provides built-in help functionality.
+///
+/// The shell owns a context of type `Ctx` and passes it to every command
+/// handler, so commands can act on device state without global statics.
+/// `F` is the handler type and follows from `Ctx`: [`ContextCommandFn<Ctx>`],
+/// or [`CommandFn`] for the context-free [`Shell`]. The context must not
+/// borrow anything (`Ctx: 'static`), as the shell may hold `'static`
+/// command tables with handlers for it.
+///
+/// # Examples
+///
+/// ```rust
+/// use libiot::system::shell::{ContextShell, ShellResult};
+///
+/// #[derive(Default)]
+/// struct Sensors {
+///     samples: u32,
+/// }
+///
+/// let mut shell: ContextShell<Sensors> = ContextShell::default();
+/// shell.register_command("sample", "Take a sample", |sensors, _argc, _argv| {
+///     sensors.samples += 1;
+///     ShellResult::Ok
+/// });
+///
+/// shell.execute_line("sample").unwrap();
+/// assert_eq!(shell.context().samples, 1);
+/// ```
+pub struct ContextShell<Ctx, F: 'static = ContextCommandFn<Ctx>> {
+    // Context passed to command handlers
+    context: Ctx,
+
     // Input buffer and parsing state
     pub(crate) buffer: [u8; MAX_BUFFER_SIZE],
     pub(crate) buffer_len: usize,
@@ -287,9 +582,11 @@ pub struct Shell {
     argv_lens: [usize; MAX_ARGS],
 
     // Command storage
-    dynamic_commands: [Option<Command>; MAX_DYNAMIC_COMMANDS],
+    dynamic_commands: [Option<Command<F>>; MAX_DYNAMIC_COMMANDS],
     pub(crate) dynamic_command_count: usize,
-    pub(crate) static_commands: Option<&'static [Command]>,
+    pub(crate) static_commands: Option<&'static [Command<F>]>,
+    #[cfg(feature = "alloc")]
+    closure_commands: alloc::vec::Vec<ClosureCommand<F>>,
 
     // Output function
     output_fn: Option<OutputFn>,
@@ -298,6 +595,17 @@ pub struct Shell {
     pub(crate) echo_enabled: bool,
     pub(crate) list_command_enabled: bool,
     pub(crate) help_enabled: bool,
+
+    // Paged `list` output: page size, and the next entry to print while paused
+    list_page_size: Option<usize>,
+    list_resume: Option<usize>,
+    // Namespace the current `list` is restricted to
+    list_namespace: Option<&'static str>,
+    // Maximum `list` line width in characters
+    list_width: Option<usize>,
+
+    // Outcome of the most recently processed line
+    last_status: LineStatus,
 }
 
 impl Default for Shell {
@@ -306,6 +614,12 @@ impl Default for Shell {
     }
 }
 
+impl<Ctx: Default + 'static> Default for ContextShell<Ctx> {
+    fn default() -> Self {
+        Self::with_context(Ctx::default())
+    }
+}
+
 impl Shell {
     /// Create a new shell instance with default settings.
     ///
@@ -325,7 +639,91 @@ impl Shell {
     /// // Configure the shell before use
     /// ```
     pub fn new() -> Self {
+        Self::from_context(())
+    }
+
+    /// Register a command implemented by a closure.
+    ///
+    /// Unlike [`register_command`](Self::register_command), the handler may
+    /// capture state. Closure commands are heap-allocated, so they don't
+    /// count against [`MAX_DYNAMIC_COMMANDS`]; they are looked up after the
+    /// other dynamic commands and before static ones. Available with the
+    /// `alloc` feature.
+    ///
+    /// Only a [`Shell`] takes closures; a [`ContextShell`] keeps such state
+    /// in its context instead.
+    ///
+    /// # Returns
+    ///
+    /// * [`ShellResult::Ok`] - Command registered successfully
+    /// * [`ShellResult::InvalidParameter`] - Empty command name provided
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "alloc")] {
+    /// use libiot::system::shell::{Shell, ShellResult};
+    ///
+    /// let mut shell = Shell::new();
+    /// let mut count = 0;
+    ///
+    /// shell.register_closure("count", "Count invocations", move |_argc, _argv| {
+    ///     count += 1;
+    ///     println!("Called {} times", count);
+    ///     ShellResult::Ok
+    /// });
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn register_closure<F>(
+        &mut self,
+        name: &'static str,
+        description: &'static str,
+        closure: F,
+    ) -> ShellResult
+    where
+        F: FnMut(usize, &[&str]) -> ShellResult + 'static,
+    {
+        if name.is_empty() {
+            return ShellResult::InvalidParameter;
+        }
+
+        self.closure_commands.push(ClosureCommand {
+            command: Command::new(name, description, |_, _| ShellResult::InvalidParameter),
+            closure: alloc::boxed::Box::new(closure),
+        });
+
+        ShellResult::Ok
+    }
+}
+
+impl<Ctx: 'static> ContextShell<Ctx> {
+    /// Create a new shell that passes `context` to its command handlers.
+    ///
+    /// The settings are the same as for [`Shell::new`](Shell::new).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::system::shell::ContextShell;
+    ///
+    /// struct Board {
+    ///     relay: bool,
+    /// }
+    ///
+    /// let shell = ContextShell::with_context(Board { relay: false });
+    /// assert!(!shell.context().relay);
+    /// ```
+    pub fn with_context(context: Ctx) -> Self {
+        Self::from_context(context)
+    }
+}
+
+impl<Ctx, F: Handler<Ctx>> ContextShell<Ctx, F> {
+    /// Shell with default settings and no commands
+    fn from_context(context: Ctx) -> Self {
         Self {
+            context,
             buffer: [0; MAX_BUFFER_SIZE],
             buffer_len: 0,
             argc: 0,
@@ -334,13 +732,35 @@ impl Shell {
             dynamic_commands: core::array::from_fn(|_| None),
             dynamic_command_count: 0,
             static_commands: None,
+            #[cfg(feature = "alloc")]
+            closure_commands: alloc::vec::Vec::new(),
             output_fn: None,
             echo_enabled: true,
             list_command_enabled: true,
             help_enabled: true,
+            list_page_size: None,
+            list_resume: None,
+            list_namespace: None,
+            list_width: None,
+            last_status: LineStatus::Empty,
         }
     }
 
+    /// The context passed to command handlers.
+    pub fn context(&self) -> &Ctx {
+        &self.context
+    }
+
+    /// The context passed to command handlers, mutably.
+    pub fn context_mut(&mut self) -> &mut Ctx {
+        &mut self.context
+    }
+
+    /// Release the context.
+    pub fn into_context(self) -> Ctx {
+        self.context
+    }
+
     /// Set the output function for shell responses.
     ///
     /// The output function is called whenever the shell needs to send
@@ -426,6 +846,66 @@ impl Shell {
         self.list_command_enabled = enabled;
     }
 
+    /// Enable or disable paging of the built-in list command.
+    ///
+    /// With paging enabled, `list` prints at most `page_size` commands and
+    /// then shows [`MORE_PROMPT`]. The listing continues on the next keypress
+    /// received through [`input`](Self::input); pressing `q` or Ctrl-C
+    /// abandons it instead. The keypress is consumed and not added to the
+    /// command line. Paging is disabled by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_size` - Commands per page, or `None` (or `Some(0)`) to disable paging
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::system::shell::Shell;
+    ///
+    /// let mut shell = Shell::new();
+    ///
+    /// // Leave room for the header and prompt on a 24-line terminal
+    /// shell.set_list_paging(Some(22));
+    /// ```
+    pub fn set_list_paging(&mut self, page_size: Option<usize>) {
+        self.list_page_size = page_size.filter(|&size| size > 0);
+        if self.list_page_size.is_none() {
+            self.list_resume = None;
+        }
+    }
+
+    /// Limit the width of `list` output lines.
+    ///
+    /// `list` prints command names in a column padded to the longest name,
+    /// followed by the descriptions. With a width set, descriptions that
+    /// would make a line longer than `width` characters are cut short and
+    /// end in [`LIST_ELLIPSIS`]. Names are never truncated. Lines are not
+    /// limited by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Maximum line width in characters, or `None` for no limit
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::system::shell::Shell;
+    ///
+    /// let mut shell = Shell::new();
+    ///
+    /// // Keep the listing within an 80-column terminal
+    /// shell.set_list_width(Some(80));
+    /// ```
+    pub fn set_list_width(&mut self, width: Option<usize>) {
+        self.list_width = width;
+    }
+
+    /// Check whether `list` output is paused waiting for a keypress.
+    pub fn is_list_paused(&self) -> bool {
+        self.list_resume.is_some()
+    }
+
     /// Enable or disable help functionality.
     ///
     /// When enabled, commands can be invoked with `-h` or `--help` flags
@@ -487,21 +967,78 @@ impl Shell {
         &mut self,
         name: &'static str,
         description: &'static str,
-        handler: CommandFn,
+        handler: F,
+    ) -> ShellResult {
+        self.register_command_with_args(name, description, handler, None, None)
+    }
+
+    /// Register a dynamic command with argument count bounds.
+    ///
+    /// Behaves like [`register_command`](Self::register_command), but the
+    /// shell rejects invocations whose argument count (excluding the command
+    /// name) falls outside `min_args..=max_args` and prints a usage message
+    /// built from `description` instead of calling the handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Command name (must not be empty)
+    /// * `description` - Command description for help and usage text
+    /// * `handler` - Function to handle command execution
+    /// * `min_args` - Minimum number of arguments, or `None` for no minimum
+    /// * `max_args` - Maximum number of arguments, or `None` for no maximum
+    ///
+    /// # Returns
+    ///
+    /// * [`ShellResult::Ok`] - Command registered successfully
+    /// * [`ShellResult::InvalidParameter`] - Empty name or `min_args > max_args`
+    /// * [`ShellResult::OutOfMemory`] - Maximum dynamic commands exceeded
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use libiot::system::shell::{Shell, ShellResult};
+    ///
+    /// let mut shell = Shell::new();
+    ///
+    /// // "led <on|off>" takes exactly one argument
+    /// let result = shell.register_command_with_args(
+    ///     "led",
+    ///     "led <on|off>",
+    ///     |_argc, argv| {
+    ///         println!("LED {}", argv[1]);
+    ///         ShellResult::Ok
+    ///     },
+    ///     Some(1),
+    ///     Some(1),
+    /// );
+    /// assert_eq!(result, ShellResult::Ok);
+    /// ```
+    pub fn register_command_with_args(
+        &mut self,
+        name: &'static str,
+        description: &'static str,
+        handler: F,
+        min_args: Option<usize>,
+        max_args: Option<usize>,
     ) -> ShellResult {
         if name.is_empty() {
             return ShellResult::InvalidParameter;
         }
 
-        if self.dynamic_command_count >= MAX_DYNAMIC_COMMANDS {
-            return ShellResult::OutOfMemory;
-        }
-
         let command = Command {
             name,
             description,
             handler,
+            min_args,
+            max_args,
         };
+        if !command.has_valid_bounds() {
+            return ShellResult::InvalidParameter;
+        }
+
+        if self.dynamic_command_count >= MAX_DYNAMIC_COMMANDS {
+            return ShellResult::OutOfMemory;
+        }
 
         self.dynamic_commands[self.dynamic_command_count] = Some(command);
         self.dynamic_command_count += 1;
@@ -523,6 +1060,8 @@ impl Shell {
     /// # Returns
     ///
     /// * [`ShellResult::Ok`] - Commands registered successfully
+    /// * [`ShellResult::InvalidParameter`] - A command has `min_args >
+    ///   max_args`; nothing is registered
     ///
     /// # Examples
     ///
@@ -530,28 +1069,23 @@ impl Shell {
     /// use libiot::system::shell::{Shell, Command, ShellResult};
     ///
     /// const COMMANDS: &[Command] = &[
-    ///     Command {
-    ///         name: "version",
-    ///         description: "Show firmware version",
-    ///         handler: |_, _| {
-    ///             println!("Firmware v1.0.0");
-    ///             ShellResult::Ok
-    ///         },
-    ///     },
-    ///     Command {
-    ///         name: "info",
-    ///         description: "Show device information",
-    ///         handler: |_, _| {
-    ///             println!("Device: IoT Controller");
-    ///             ShellResult::Ok
-    ///         },
-    ///     },
+    ///     Command::new("version", "Show firmware version", |_, _| {
+    ///         println!("Firmware v1.0.0");
+    ///         ShellResult::Ok
+    ///     }),
+    ///     Command::new("info", "Show device information", |_, _| {
+    ///         println!("Device: IoT Controller");
+    ///         ShellResult::Ok
+    ///     }),
     /// ];
     ///
     /// let mut shell = Shell::new();
     /// shell.register_static_commands(COMMANDS).unwrap();
     /// ```
-    pub fn register_static_commands(&mut self, commands: &'static [Command]) -> ShellResult {
+    pub fn register_static_commands(&mut self, commands: &'static [Command<F>]) -> ShellResult {
+        if !commands.iter().all(Command::has_valid_bounds) {
+            return ShellResult::InvalidParameter;
+        }
         self.static_commands = Some(commands);
         ShellResult::Ok
     }
@@ -598,12 +1132,18 @@ impl Shell {
     /// ```
     pub fn input(&mut self, data: &[u8]) -> ShellResult {
         for &byte in data {
+            // While `list` is paused, every byte is a keypress for the pager
+            if let Some(next) = self.list_resume.take() {
+                self.resume_list(byte, next);
+                continue;
+            }
+
             match byte {
                 ASCII_CR | ASCII_LF => {
                     if self.echo_enabled {
                         self.output(if byte == ASCII_CR { "\r" } else { "\n" });
                     }
-                    self.process_command();
+                    self.last_status = self.process_command();
                     self.reset_buffer();
                 }
                 ASCII_BACKSPACE | ASCII_DEL => {
@@ -639,6 +1179,44 @@ impl Shell {
         ShellResult::Ok
     }
 
+    /// Process a complete command line without echo.
+    ///
+    /// Intended for programmatic callers (remote administration, scripts)
+    /// that need to know whether the command was found. Output is still sent
+    /// through the output function as for interactive input. Any partially
+    /// typed interactive input is discarded.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(status)` - How the line was handled, see [`LineStatus`]
+    /// * `Err(ShellResult::BufferOverflow)` - The line does not fit in the input buffer
+    pub fn execute_line(&mut self, line: &str) -> Result<LineStatus, ShellResult> {
+        if line.len() >= MAX_BUFFER_SIZE {
+            return Err(ShellResult::BufferOverflow);
+        }
+
+        self.reset_buffer();
+        for &byte in line.as_bytes() {
+            // Keep the same character set as interactive input
+            if (0x20..0x7F).contains(&byte) {
+                self.buffer[self.buffer_len] = byte;
+                self.buffer_len += 1;
+            }
+        }
+        self.last_status = self.process_command();
+        self.reset_buffer();
+        Ok(self.last_status)
+    }
+
+    /// Outcome of the most recently processed line.
+    ///
+    /// Updated whenever [`input`](Self::input) completes a line or
+    /// [`execute_line`](Self::execute_line) runs one. [`LineStatus::Empty`]
+    /// before any line has been processed.
+    pub fn last_status(&self) -> LineStatus {
+        self.last_status
+    }
+
     /// Send output through the configured output function.
     ///
     /// This is an internal function used by the shell to send text to
@@ -796,12 +1374,7 @@ impl Shell {
 
         let start = self.argv_starts[index];
         let len = self.argv_lens[index];
-
-        if start + len <= self.buffer_len {
-            str::from_utf8(&self.buffer[start..start + len]).ok()
-        } else {
-            None
-        }
+        arg(&self.buffer[..self.buffer_len], start, len)
     }
 
     /// Process the current command after parsing.
@@ -810,82 +1383,124 @@ impl Shell {
     /// 1. Parse arguments from the input buffer
     /// 2. Check for help flags (`-h`, `--help`)
     /// 3. Look up the command in dynamic and static registries
-    /// 4. Execute the command handler
-    /// 5. Handle built-in commands (like `list`)
-    /// 6. Display error messages for unknown commands
-    fn process_command(&mut self) {
-        if let Err(_) = self.parse_arguments() {
+    /// 4. Validate the argument count against the command's bounds
+    /// 5. Execute the command handler
+    /// 6. Handle built-in commands (like `list`)
+    /// 7. Display error messages for unknown commands
+    fn process_command(&mut self) -> LineStatus {
+        if let Err(result) = self.parse_arguments() {
             self.output("Error parsing command\r\n");
-            return;
+            return LineStatus::Executed(result);
         }
 
         if self.argc == 0 {
-            return;
+            return LineStatus::Empty;
         }
 
         let command_name = match self.get_arg(0) {
             Some(name) => name,
-            None => return,
+            None => return LineStatus::Empty,
         };
 
         // Check for help flag
         if self.help_enabled && self.argc == 2 {
             if let Some(arg) = self.get_arg(1) {
                 if arg == "-h" || arg == "--help" {
-                    self.show_command_help(command_name);
-                    return;
-                }
-            }
-        }
-
-        // Look for command in dynamic commands
-        let mut found = false;
-        for i in 0..self.dynamic_command_count {
-            if let Some(ref cmd) = self.dynamic_commands[i] {
-                if cmd.name == command_name {
-                    let mut argv = [""; MAX_ARGS];
-                    for j in 0..self.argc {
-                        argv[j] = self.get_arg(j).unwrap_or("");
+                    if self.show_command_help(command_name) {
+                        return LineStatus::Executed(ShellResult::Ok);
                     }
-                    (cmd.handler)(self.argc, &argv[..self.argc]);
-                    found = true;
-                    break;
+                    // `gpio --help` lists the `gpio.*` commands
+                    if let Some(namespace) = self.find_namespace(command_name) {
+                        self.list_commands(Some(namespace));
+                        return LineStatus::Executed(ShellResult::Ok);
+                    }
+                    self.output("Command not found.\r\n");
+                    return LineStatus::NotFound;
                 }
             }
         }
 
-        // Look for command in static commands
-        if !found {
-            if let Some(static_commands) = self.static_commands {
-                for cmd in static_commands {
-                    if cmd.name == command_name {
-                        let mut argv = [""; MAX_ARGS];
-                        for j in 0..self.argc {
-                            argv[j] = self.get_arg(j).unwrap_or("");
-                        }
-                        (cmd.handler)(self.argc, &argv[..self.argc]);
-                        found = true;
-                        break;
-                    }
+        // Look for command in dynamic, then static commands
+        if let Some(cmd) = self.find_command(command_name) {
+            if cmd.accepts_arg_count(self.argc - 1) {
+                #[cfg(feature = "alloc")]
+                if let Some(index) = self
+                    .closure_commands
+                    .iter()
+                    .position(|entry| core::ptr::eq(&entry.command, cmd))
+                {
+                    // Move the closures out so the arguments can keep borrowing the buffer
+                    let mut closures = core::mem::take(&mut self.closure_commands);
+                    let argv = split_argv(
+                        &self.buffer[..self.buffer_len],
+                        &self.argv_starts,
+                        &self.argv_lens,
+                        self.argc,
+                    );
+                    let result = (closures[index].closure)(self.argc, &argv[..self.argc]);
+                    self.closure_commands = closures;
+                    return LineStatus::Executed(result);
                 }
+                let handler = cmd.handler;
+                // Borrow the arguments and the context as separate fields
+                let argv = split_argv(
+                    &self.buffer[..self.buffer_len],
+                    &self.argv_starts,
+                    &self.argv_lens,
+                    self.argc,
+                );
+                let result = handler.call(&mut self.context, self.argc, &argv[..self.argc]);
+                return LineStatus::Executed(result);
             }
+            self.show_usage(cmd);
+            return LineStatus::Executed(ShellResult::InvalidParameter);
         }
 
         // Handle built-in commands
-        if !found {
-            if self.list_command_enabled && command_name == "list" {
-                self.list_commands();
-                found = true;
+        if self.list_command_enabled && command_name == "list" {
+            if self.argc > 2 {
+                self.output("Usage: list [namespace]\r\n");
+                return LineStatus::Executed(ShellResult::InvalidParameter);
             }
+            let namespace = match self.get_arg(1) {
+                Some(prefix) => match self.find_namespace(prefix) {
+                    Some(namespace) => Some(namespace),
+                    None => {
+                        self.output("No commands in that namespace.\r\n");
+                        return LineStatus::Executed(ShellResult::InvalidParameter);
+                    }
+                },
+                None => None,
+            };
+            self.list_commands(namespace);
+            return LineStatus::Executed(ShellResult::Ok);
         }
 
-        if !found {
-            if self.list_command_enabled {
-                self.output("Unknown command. Type 'list' to see available commands.\r\n");
-            } else {
-                self.output("Unknown command.\r\n");
-            }
+        if self.list_command_enabled {
+            self.output("Unknown command. Type 'list' to see available commands.\r\n");
+        } else {
+            self.output("Unknown command.\r\n");
         }
+        LineStatus::NotFound
+    }
+
+    /// Find a registered command by name.
+    ///
+    /// Dynamic commands take precedence over static commands with the same name.
+    fn find_command(&self, command_name: &str) -> Option<&Command<F>> {
+        self.all_commands().find(|cmd| cmd.name == command_name)
+    }
+
+    /// Report an argument count violation for a command.
+    ///
+    /// The command's description doubles as its usage text.
+    fn show_usage(&self, cmd: &Command<F>) {
+        self.output("Invalid number of arguments.\r\n");
+        self.output("Usage: ");
+        self.output(cmd.name);
+        self.output(" - ");
+        self.output(cmd.description);
+        self.output("\r\n");
     }
 
     /// Show help for a specific command.
@@ -896,66 +1511,210 @@ impl Shell {
     /// # Arguments
     ///
     /// * `command_name` - Name of the command to show help for
-    fn show_command_help(&self, command_name: &str) {
-        let mut found = false;
-
-        // Check dynamic commands
-        for i in 0..self.dynamic_command_count {
-            if let Some(ref cmd) = self.dynamic_commands[i] {
-                if cmd.name == command_name {
-                    self.output(cmd.description);
-                    self.output("\r\n");
-                    found = true;
-                    break;
-                }
+    ///
+    /// Returns `true` if the command exists.
+    fn show_command_help(&self, command_name: &str) -> bool {
+        match self.find_command(command_name) {
+            Some(cmd) => {
+                self.output(cmd.description);
+                self.output("\r\n");
+                true
             }
+            None => false,
         }
+    }
 
-        // Check static commands
-        if !found {
-            if let Some(static_commands) = self.static_commands {
-                for cmd in static_commands {
-                    if cmd.name == command_name {
-                        self.output(cmd.description);
-                        self.output("\r\n");
-                        found = true;
-                        break;
-                    }
-                }
-            }
+    /// Find the namespace `prefix` (with or without a trailing separator)
+    /// among the registered commands.
+    ///
+    /// The result borrows from a command name, so it outlives the input line.
+    fn find_namespace(&self, prefix: &str) -> Option<&'static str> {
+        let prefix = prefix.strip_suffix(NAMESPACE_SEPARATOR).unwrap_or(prefix);
+        if prefix.is_empty() {
+            return None;
         }
+        self.all_commands()
+            .map(|cmd| cmd.name)
+            .find(|name| in_namespace(name, prefix))
+            .map(|name| &name[..prefix.len()])
+    }
 
-        if !found {
-            self.output("Command not found.\r\n");
-        }
+    /// Every registered command: dynamic, then closure, then static commands.
+    fn all_commands(&self) -> impl Iterator<Item = &Command<F>> + Clone + '_ {
+        self.dynamic_commands[..self.dynamic_command_count]
+            .iter()
+            .flatten()
+            .chain(self.closure_commands())
+            .chain(self.static_commands.unwrap_or(&[]))
     }
 
-    /// List all available commands with descriptions.
+    #[cfg(feature = "alloc")]
+    fn closure_commands(&self) -> impl Iterator<Item = &Command<F>> + Clone + '_ {
+        self.closure_commands.iter().map(|entry| &entry.command)
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn closure_commands(&self) -> impl Iterator<Item = &Command<F>> + Clone + '_ {
+        core::iter::empty()
+    }
+
+    /// The commands shown by the current `list`, in display order.
+    ///
+    /// Commands without a namespace come first, in registration order,
+    /// followed by one group per namespace in order of first registration.
+    fn listed_commands(&self) -> impl Iterator<Item = &Command<F>> + '_ {
+        let all = self.all_commands();
+        let shown = self.list_namespace;
+        let ungrouped = all.clone().filter(|cmd| namespace(cmd.name).is_none());
+        let first_of_group = all.clone().enumerate().filter({
+            let all = all.clone();
+            move |(i, cmd)| {
+                namespace(cmd.name).is_some_and(|ns| {
+                    !all.clone()
+                        .take(*i)
+                        .any(|earlier| namespace(earlier.name) == Some(ns))
+                })
+            }
+        });
+        let grouped = first_of_group.flat_map(move |(_, first)| {
+            let ns = namespace(first.name);
+            all.clone().filter(move |cmd| namespace(cmd.name) == ns)
+        });
+        ungrouped
+            .chain(grouped)
+            .filter(move |cmd| shown.is_none_or(|prefix| in_namespace(cmd.name, prefix)))
+    }
+
+    /// List available commands with descriptions.
     ///
     /// This internal function implements the built-in `list` command that
-    /// displays all registered commands along with their descriptions.
-    /// Commands are displayed in the order they were registered.
-    fn list_commands(&self) {
+    /// displays the registered commands, or only those in `namespace`, along
+    /// with their descriptions. Commands are displayed in the order they were
+    /// registered, dynamic commands first, except that namespaced commands
+    /// are gathered under a `namespace:` heading after the others.
+    /// Descriptions are aligned in a column after the longest command name.
+    fn list_commands(&mut self, namespace: Option<&'static str>) {
+        self.list_namespace = namespace;
         self.output("Available commands:\r\n");
+        self.list_page(0);
+    }
 
-        // List dynamic commands
-        for i in 0..self.dynamic_command_count {
-            if let Some(ref cmd) = self.dynamic_commands[i] {
-                self.output(cmd.name);
-                self.output("\t\t");
-                self.output(cmd.description);
-                self.output("\r\n");
+    /// Print one page of the command list starting at entry `start`.
+    ///
+    /// If entries remain after a full page, the pager prompt is shown and the
+    /// position is saved so the listing can resume on the next keypress.
+    /// Namespace headings are not counted towards the page size, and are
+    /// repeated when a page starts in the middle of a group.
+    fn list_page(&mut self, start: usize) {
+        let page_size = self.list_page_size.unwrap_or(usize::MAX);
+
+        // Align descriptions across all pages, not just this one
+        let column = self
+            .listed_commands()
+            .map(|cmd| cmd.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            + LIST_COLUMN_GAP;
+        let description_width = self
+            .list_width
+            .map_or(usize::MAX, |width| width.saturating_sub(column));
+
+        let mut commands = self.listed_commands().skip(start);
+        let mut group = None;
+        for cmd in commands.by_ref().take(page_size) {
+            let ns = namespace(cmd.name);
+            if let Some(heading) = ns.filter(|_| ns != group) {
+                self.output(heading);
+                self.output(":\r\n");
             }
+            group = ns;
+            self.output(cmd.name);
+            self.output_padding(column - cmd.name.chars().count());
+            self.output_truncated(cmd.description, description_width);
+            self.output("\r\n");
         }
 
-        // List static commands
-        if let Some(static_commands) = self.static_commands {
-            for cmd in static_commands {
-                self.output(cmd.name);
-                self.output("\t\t");
-                self.output(cmd.description);
-                self.output("\r\n");
-            }
+        let more = commands.next().is_some();
+        drop(commands);
+        if more {
+            self.output(MORE_PROMPT);
+            self.list_resume = Some(start + page_size);
+        }
+    }
+
+    /// Output `count` spaces.
+    fn output_padding(&self, mut count: usize) {
+        const SPACES: &str = "                ";
+        while count > 0 {
+            let n = count.min(SPACES.len());
+            self.output(&SPACES[..n]);
+            count -= n;
+        }
+    }
+
+    /// Output `text`, cut to at most `width` characters including a
+    /// trailing [`LIST_ELLIPSIS`] if it does not fit.
+    fn output_truncated(&self, text: &str, width: usize) {
+        if text.chars().count() <= width {
+            self.output(text);
+            return;
+        }
+        let ellipsis = if width >= LIST_ELLIPSIS.len() {
+            LIST_ELLIPSIS
+        } else {
+            ""
+        };
+        let keep = width - ellipsis.len();
+        let end = text.char_indices().nth(keep).map_or(text.len(), |(i, _)| i);
+        self.output(&text[..end]);
+        self.output(ellipsis);
+    }
+
+    /// Handle a keypress while `list` output is paused.
+    ///
+    /// `q` or Ctrl-C ends the listing; any other key shows the next page.
+    fn resume_list(&mut self, key: u8, next: usize) {
+        // Erase the prompt
+        self.output("\r          \r");
+        if key != b'q' && key != 0x03 {
+            self.list_page(next);
         }
     }
 }
+
+/// The argument `start..start + len` of `buffer`, if it is in bounds.
+fn arg(buffer: &[u8], start: usize, len: usize) -> Option<&str> {
+    str::from_utf8(buffer.get(start..start + len)?).ok()
+}
+
+/// The first `argc` parsed arguments in `buffer`, padded with empty strings.
+///
+/// Takes the parser state rather than the shell so that the shell's context
+/// can be borrowed mutably while the arguments are in use.
+fn split_argv<'a>(
+    buffer: &'a [u8],
+    starts: &[usize],
+    lens: &[usize],
+    argc: usize,
+) -> [&'a str; MAX_ARGS] {
+    let mut argv = [""; MAX_ARGS];
+    for (j, slot) in argv.iter_mut().enumerate().take(argc) {
+        *slot = arg(buffer, starts[j], lens[j]).unwrap_or("");
+    }
+    argv
+}
+
+/// The namespace of a command name: the text before the first
+/// [`NAMESPACE_SEPARATOR`], if any.
+fn namespace(name: &str) -> Option<&str> {
+    name.split_once(NAMESPACE_SEPARATOR)
+        .map(|(ns, _)| ns)
+        .filter(|ns| !ns.is_empty())
+}
+
+/// Whether `name` lies in `prefix`, which may itself be a nested namespace
+/// such as `wifi.ap`.
+fn in_namespace(name: &str, prefix: &str) -> bool {
+    name.strip_prefix(prefix)
+        .is_some_and(|rest| rest.len() > 1 && rest.starts_with(NAMESPACE_SEPARATOR))
+}
@@ -0,0 +1,115 @@
+//! CRC32 (IEEE 802.3), as used by zip, PNG and Ethernet
+
+/// Reflected polynomial of CRC32 (IEEE)
+const POLY: u32 = 0xEDB8_8320;
+
+/// Lookup table for one byte at a time, built at compile time
+const TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0u32;
+    while i < 256 {
+        let mut c = i;
+        let mut j = 0;
+        while j < 8 {
+            c = if (c & 1) != 0 {
+                (c >> 1) ^ POLY
+            } else {
+                c >> 1
+            };
+            j += 1;
+        }
+        table[i as usize] = c;
+        i += 1;
+    }
+    table
+};
+
+/// A simple CRC32 (IEEE) hasher implemented without external dependencies
+///
+/// The running state can be read at any point with [`value`](Self::value),
+/// stored as a checkpoint, and picked up later with
+/// [`resume`](Self::resume). The checksum itself is
+/// [`finalize`](Self::finalize), which is always `!value()`.
+///
+/// ```
+/// use libiot::util::Crc32;
+///
+/// let mut crc = Crc32::new();
+/// crc.update(b"1234");
+/// let checkpoint = crc.value();
+///
+/// let mut resumed = Crc32::resume(checkpoint);
+/// resumed.update(b"56789");
+/// assert_eq!(resumed.finalize(), 0xCBF4_3926); // CRC32 of "123456789"
+/// assert_eq!(resumed.finalize(), !resumed.value());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32 {
+    value: u32,
+    // Checked when used as an OTA verifier
+    pub(crate) expected: Option<u32>,
+}
+
+impl Crc32 {
+    /// Start a new checksum
+    pub fn new() -> Self {
+        Self {
+            value: 0xFFFF_FFFF,
+            expected: None,
+        }
+    }
+
+    /// Start a new checksum that, as an OTA [`Verifier`](crate::ota::Verifier),
+    /// requires the image's CRC32 to equal `expected`
+    ///
+    /// A plain [`new`](Self::new) checksum accepts any image.
+    pub fn expecting(expected: u32) -> Self {
+        let mut crc = Self::new();
+        crc.expected = Some(expected);
+        crc
+    }
+
+    /// Continue a checksum from the running [`value`](Self::value) of an
+    /// earlier hasher
+    pub fn resume(value: u32) -> Self {
+        let mut crc = Self::new();
+        crc.value = value;
+        crc
+    }
+
+    /// CRC32 of `data` in one call
+    pub fn checksum(data: &[u8]) -> u32 {
+        let mut crc = Self::new();
+        crc.update(data);
+        crc.finalize()
+    }
+
+    /// Feed more data into the checksum
+    pub fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            let idx = (self.value ^ b as u32) & 0xFF;
+            self.value = TABLE[idx as usize] ^ (self.value >> 8);
+        }
+    }
+
+    /// Running state, before the final XOR
+    ///
+    /// This is not a CRC of the data seen so far; use it only as a
+    /// checkpoint for [`resume`](Self::resume). It starts at `0xFFFF_FFFF`.
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// CRC32 of all data fed so far, equal to `!self.value()`
+    ///
+    /// Does not reset the hasher, so it can also be taken mid-stream.
+    pub fn finalize(&self) -> u32 {
+        self.value ^ 0xFFFF_FFFF
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,8 @@
+//! Small helpers shared between modules
+//!
+//! These are implemented without external dependencies so that every part
+//! of the crate can use them in `no_std` builds.
+
+mod crc32;
+
+pub use crc32::Crc32;
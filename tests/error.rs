@@ -0,0 +1,79 @@
+use libiot::gps::{NmeaError, NmeaParser};
+use libiot::network::application::mcp::McpError;
+use libiot::network::error::Error as NetworkError;
+#[cfg(feature = "defmt")]
+use libiot::network::retry::BreakerError;
+use libiot::storage::error::Error as StorageError;
+use libiot::{Error, Result, ota};
+
+fn parse_hour(line: &str) -> Result<u8> {
+    match NmeaParser::parse(line, true)? {
+        libiot::gps::NmeaSentence::Gprmc(rmc) => Ok(rmc.time.hour),
+        _ => Err(McpError::InvalidArguments.into()),
+    }
+}
+
+#[test]
+fn test_crate_error_conversions() {
+    assert_eq!(
+        Error::from(NetworkError::Timeout),
+        Error::Network(NetworkError::Timeout)
+    );
+    assert_eq!(
+        Error::from(StorageError::OutOfBounds),
+        Error::Storage(StorageError::OutOfBounds)
+    );
+    assert_eq!(
+        Error::from(ota::Error::VerifyFailed),
+        Error::Ota(ota::Error::VerifyFailed)
+    );
+    assert_eq!(
+        Error::from(McpError::Timeout),
+        Error::Mcp(McpError::Timeout)
+    );
+
+    let rmc = "$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n";
+    assert_eq!(parse_hour(rmc), Ok(22));
+    assert_eq!(
+        parse_hour("$GPRMC,225446,A*00\r\n"),
+        Err(Error::Nmea(NmeaError::InvalidChecksum))
+    );
+    let gga = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n";
+    assert_eq!(parse_hour(gga), Err(Error::Mcp(McpError::InvalidArguments)));
+}
+
+#[test]
+fn test_crate_error_display() {
+    let mut text: heapless::String<64> = heapless::String::new();
+    core::fmt::write(
+        &mut text,
+        format_args!("{}", Error::from(NetworkError::Timeout)),
+    )
+    .unwrap();
+    assert_eq!(text, "network: operation timed out");
+
+    text.clear();
+    core::fmt::write(
+        &mut text,
+        format_args!("{}", Error::from(StorageError::ReadError)),
+    )
+    .unwrap();
+    assert_eq!(text, "storage: ReadError");
+}
+
+/// Compiles only if every error type can be logged with defmt
+#[cfg(feature = "defmt")]
+#[test]
+fn test_errors_implement_defmt_format() {
+    fn loggable<T: defmt::Format>() {}
+
+    loggable::<Error>();
+    loggable::<NetworkError>();
+    loggable::<StorageError>();
+    loggable::<ota::Error>();
+    loggable::<McpError>();
+    loggable::<NmeaError>();
+    loggable::<BreakerError<NetworkError>>();
+    #[cfg(feature = "mqtt-session")]
+    loggable::<libiot::network::application::mqtt::session::Error>();
+}
@@ -306,6 +306,44 @@ fn test_gprmc_parsing() {
     }
 }
 
+#[test]
+fn test_gpvtg_parsing() {
+    let parse = |sentence: &str| match NmeaParser::parse(sentence, true).unwrap() {
+        NmeaSentence::Gpvtg(gpvtg) => gpvtg,
+        other => panic!("Expected GPVTG sentence, got {other:?}"),
+    };
+
+    let gpvtg = parse("$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K*48\r\n");
+    assert_eq!(gpvtg.base.sentence_type, NmeaType::Gpvtg);
+    assert_eq!(gpvtg.base.errors, 0);
+    assert!((gpvtg.track_true_degrees - 54.7).abs() < 0.001);
+    assert!((gpvtg.track_magnetic_degrees - 34.4).abs() < 0.001);
+    assert!((gpvtg.speed_knots - 5.5).abs() < 0.001);
+    assert!((gpvtg.speed_kmh - 10.2).abs() < 0.001);
+    assert_eq!(gpvtg.mode, None);
+    assert!(gpvtg.is_valid());
+
+    // NMEA 2.3 mode indicator, with the magnetic track left empty
+    let gpvtg = parse("$GPVTG,054.7,T,,M,005.5,N,010.2,K,A*08\r\n");
+    assert_eq!(gpvtg.base.errors, 0);
+    assert_eq!(gpvtg.track_magnetic_degrees, 0.0);
+    assert_eq!(gpvtg.mode, Some(PositioningMode::Autonomous));
+    let gpvtg = parse("$GNVTG,054.7,T,034.4,M,005.5,N,010.2,K,D*3E\r\n");
+    assert_eq!(gpvtg.mode, Some(PositioningMode::Differential));
+
+    let gpvtg = parse("$GPVTG,,T,,M,0.00,N,0.00,K,N*2C\r\n");
+    assert_eq!(gpvtg.base.errors, 0);
+    assert_eq!(gpvtg.mode, Some(PositioningMode::NotValid));
+    assert!(!gpvtg.is_valid());
+
+    // Malformed fields are counted
+    let gpvtg = parse("$GPVTG,12x.0,T,,M,1.0,N,1.9,K,Q*5F\r\n");
+    assert_eq!(gpvtg.base.errors, 2);
+    assert_eq!(gpvtg.track_true_degrees, 0.0);
+    assert!((gpvtg.speed_knots - 1.0).abs() < 0.001);
+    assert_eq!(gpvtg.mode, None);
+}
+
 #[test]
 fn test_gpgll_parsing() {
     let sentence = "$GPGLL,4916.45,N,12311.12,W,225444,A*1D\r\n";
@@ -446,3 +484,664 @@ fn test_edge_cases() {
         assert!((gpgga.longitude.minutes - 0.0).abs() < 0.001);
     }
 }
+
+struct MockClock {
+    now: core::cell::Cell<u64>,
+}
+
+impl libiot::system::clock::MonotonicClock for MockClock {
+    fn now_ms(&self) -> u64 {
+        self.now.get()
+    }
+}
+
+#[test]
+fn test_gps_state_fix_timing() {
+    let clock = MockClock {
+        now: core::cell::Cell::new(1_000),
+    };
+    let mut state = GpsState::new(&clock);
+
+    assert!(!state.has_fix());
+    assert_eq!(state.time_to_first_fix_ms(), None);
+    assert_eq!(state.fix_age_ms(), None);
+    assert_eq!(state.latitude(), None);
+
+    // A GGA sentence without a fix is ignored
+    let no_fix = NmeaSentence::Gpgga(Gpgga::default());
+    clock.now.set(2_000);
+    assert!(!state.update(&no_fix));
+    assert_eq!(state.time_to_first_fix_ms(), None);
+
+    clock.now.set(4_500);
+    let gga = NmeaParser::parse(
+        "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n",
+        true,
+    )
+    .unwrap();
+    assert!(state.update(&gga));
+    assert!(state.has_fix());
+    assert_eq!(state.time_to_first_fix_ms(), Some(3_500));
+    assert_eq!(state.fix_age_ms(), Some(0));
+    assert_eq!(state.satellites_used(), Some(8));
+    assert_eq!(state.latitude().unwrap().degrees, 48);
+
+    clock.now.set(5_200);
+    assert_eq!(state.fix_age_ms(), Some(700));
+
+    // A later fix refreshes the age but not the time to first fix
+    let rmc = NmeaParser::parse(
+        "$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n",
+        true,
+    )
+    .unwrap();
+    assert!(state.update(&rmc));
+    assert_eq!(state.time_to_first_fix_ms(), Some(3_500));
+    assert_eq!(state.fix_age_ms(), Some(0));
+    assert_eq!(state.date().unwrap().day, 19);
+
+    clock.now.set(6_000);
+    state.reset();
+    assert_eq!(state.time_to_first_fix_ms(), None);
+    assert_eq!(state.fix_age_ms(), None);
+}
+
+#[test]
+fn test_time_date_ordering() {
+    let t = |hour, minute, second| NmeaTime {
+        hour,
+        minute,
+        second,
+    };
+    let d = |day, month, year| NmeaDate { day, month, year };
+
+    assert!(t(9, 59, 59) < t(10, 0, 0));
+    assert!(t(10, 1, 0) > t(10, 0, 59));
+
+    // Later year wins even with an earlier day/month
+    assert!(d(31, 12, 2023) < d(1, 1, 2024));
+    assert!(d(2, 3, 2024) > d(28, 2, 2024));
+
+    let mut samples = [
+        NmeaDateTime::new(d(1, 1, 2024), t(0, 0, 1)),
+        NmeaDateTime::new(d(31, 12, 2023), t(23, 59, 59)),
+        NmeaDateTime::new(d(1, 1, 2024), t(0, 0, 0)),
+    ];
+    samples.sort();
+    assert_eq!(samples[0].date, d(31, 12, 2023));
+    assert_eq!(samples[1].time, t(0, 0, 0));
+    assert_eq!(samples[2].time, t(0, 0, 1));
+
+    let mut seen = std::collections::HashSet::new();
+    assert!(seen.insert(samples[0]));
+    assert!(!seen.insert(samples[0]));
+}
+
+#[test]
+fn test_parse_bytes() {
+    let sentence = b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n";
+    let parsed = NmeaParser::parse_bytes(sentence).unwrap();
+    assert_eq!(parsed.sentence_type(), NmeaType::Gpgga);
+
+    // Invalid UTF-8 is rejected rather than assumed
+    assert_eq!(
+        NmeaParser::parse_bytes(b"$GPGGA,\xff\xfe,*00\r\n"),
+        Err(NmeaError::ParseError)
+    );
+    assert_eq!(
+        NmeaParser::parse_bytes(&[b'$'; NMEA_MAX_LENGTH + 1]),
+        Err(NmeaError::InvalidLength)
+    );
+}
+
+#[test]
+fn test_adversarial_input_does_not_panic() {
+    let inputs: &[&str] = &[
+        "",
+        "$",
+        "\r\n",
+        "$é€GGA,\r\n",
+        "$GPGGA,1é.5,N,\r\n",
+        "$GPGGA,123519,é.038,N,€.000,E,1,08,,,,,,,*47\r\n",
+        "$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*é\r\n",
+        "$GPGLL,.,N,..,W,.,A\r\n",
+        "$GPGGA,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,\r\n",
+    ];
+    for input in inputs {
+        let _ = NmeaParser::parse_bytes(input.as_bytes());
+        let _ = NmeaParser::parse(input, false);
+        let _ = NmeaParser::get_sentence_type(input);
+        let _ = NmeaParser::calculate_checksum(input);
+        let _ = NmeaParser::split_fields(input);
+        let _ = NmeaParser::parse_position(input);
+        let _ = NmeaParser::parse_time(input);
+    }
+    assert_eq!(
+        NmeaParser::parse_position("1é.5"),
+        Err(NmeaError::ParseError)
+    );
+    assert_eq!(NmeaParser::get_sentence_type("$é€GGA"), NmeaType::Unknown);
+}
+
+#[test]
+fn test_motion_detector_hysteresis_and_dwell() {
+    let clock = MockClock {
+        now: core::cell::Cell::new(0),
+    };
+    assert!(MotionDetector::new(&clock, 5.0, 5.0, 1_000).is_none());
+
+    let mut detector = MotionDetector::new(&clock, 10.0, 3.0, 2_000).unwrap();
+    assert_eq!(detector.state(), Motion::Stationary);
+
+    // A brief spike does not count as motion
+    assert_eq!(detector.update_kmh(15.0), None);
+    clock.now.set(1_000);
+    assert_eq!(detector.update_kmh(2.0), None);
+    clock.now.set(2_500);
+    assert_eq!(detector.update_kmh(15.0), None);
+
+    // Sustained speed above the start threshold does
+    clock.now.set(4_500);
+    assert_eq!(detector.update_kmh(12.0), Some(Motion::Moving));
+    assert_eq!(detector.state(), Motion::Moving);
+
+    // Speeds between the thresholds keep the current state
+    clock.now.set(10_000);
+    assert_eq!(detector.update_kmh(5.0), None);
+    clock.now.set(20_000);
+    assert_eq!(detector.update_kmh(5.0), None);
+    assert_eq!(detector.state(), Motion::Moving);
+
+    // Parked: 1 knot is below 3 km/h
+    clock.now.set(21_000);
+    assert_eq!(detector.update_knots(1.0), None);
+    clock.now.set(23_000);
+    assert_eq!(detector.update_knots(1.0), Some(Motion::Stationary));
+
+    // RMC speed (0.5 kn) keeps it stationary
+    let rmc = NmeaParser::parse(
+        "$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n",
+        true,
+    )
+    .unwrap();
+    assert_eq!(detector.update(&rmc), None);
+    assert_eq!(detector.state(), Motion::Stationary);
+}
+
+#[test]
+fn test_horizontal_accuracy_estimate() {
+    assert!((estimated_horizontal_accuracy_m(0.9, DEFAULT_UERE_M) - 4.5).abs() < 1e-6);
+    assert!((estimated_horizontal_accuracy_m(0.9, 3.0) - 2.7).abs() < 1e-6);
+
+    let mut gga = Gpgga::default();
+    assert_eq!(gga.estimated_horizontal_accuracy_m(), None);
+    gga.hdop = 0.9;
+    gga.position_fix = 1;
+    assert!((gga.estimated_horizontal_accuracy_m().unwrap() - 4.5).abs() < 1e-6);
+
+    let clock = MockClock {
+        now: core::cell::Cell::new(0),
+    };
+    let mut state = GpsState::new(&clock);
+    assert_eq!(state.estimated_horizontal_accuracy_m(), None);
+    state.update(&NmeaSentence::Gpgga(gga));
+    assert!((state.estimated_horizontal_accuracy_m().unwrap() - 4.5).abs() < 1e-6);
+}
+
+#[test]
+fn test_build_sentence() {
+    let pmtk = build_sentence("P", "MTK220", &["100"]).unwrap();
+    assert_eq!(pmtk.as_str(), "$PMTK220,100*2F\r\n");
+
+    // Round-trips through the parser with checksum validation
+    let fields = [
+        "123519",
+        "4807.038",
+        "N",
+        "01131.000",
+        "E",
+        "1",
+        "08",
+        "0.9",
+        "545.4",
+        "M",
+        "46.9",
+        "M",
+        "",
+        "",
+    ];
+    let gga = build_sentence("GP", "GGA", &fields).unwrap();
+    assert_eq!(
+        gga.as_str(),
+        "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n"
+    );
+    match NmeaParser::parse(&gga, true).unwrap() {
+        NmeaSentence::Gpgga(parsed) => {
+            assert_eq!(parsed.satellites_used, 8);
+            assert_eq!(parsed.latitude.degrees, 48);
+            assert!((parsed.hdop - 0.9).abs() < 1e-6);
+        }
+        other => panic!("unexpected sentence {:?}", other),
+    }
+
+    assert_eq!(
+        build_sentence("GP", "G-A", &[]),
+        Err(NmeaError::InvalidPrefix)
+    );
+    assert_eq!(
+        build_sentence("GP", "TXT", &["a,b"]),
+        Err(NmeaError::ParseError)
+    );
+    let long = [&"x".repeat(80)[..]];
+    assert_eq!(
+        build_sentence("GP", "TXT", &long),
+        Err(NmeaError::InvalidLength)
+    );
+}
+
+#[test]
+fn test_rmc_telemetry_publish() {
+    use crate::network::application::mqtt::mock::ScriptedConnection;
+    use libiot::gps::telemetry::{Telemetry, publish_rmc};
+    use libiot::network::application::mqtt::client::{Client, QoS};
+
+    let sentence = "$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n";
+    let NmeaSentence::Gprmc(rmc) = NmeaParser::parse_bytes(sentence.as_bytes()).unwrap() else {
+        panic!("expected RMC");
+    };
+
+    let report = Telemetry::from_rmc(&rmc).unwrap();
+    assert_eq!(report.timestamp, 785_285_686);
+    let json = report.to_json().unwrap();
+    assert_eq!(
+        json.as_str(),
+        r#"{"lat":49.274167,"lon":-123.185333,"spd":0.9,"ts":785285686}"#
+    );
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+    assert!(publish_rmc(&mut client, "tracker/1", &rmc, QoS::AtMostOnce).unwrap());
+    let written = conn.take_written();
+    assert_eq!(written[0], 0x30);
+    assert!(written.ends_with(json.as_bytes()));
+
+    // No fix, nothing published
+    let mut void = rmc;
+    void.status = false;
+    assert!(Telemetry::from_rmc(&void).is_none());
+    assert!(!publish_rmc(&mut client, "tracker/1", &void, QoS::AtMostOnce).unwrap());
+    assert!(conn.take_written().is_empty());
+}
+
+#[test]
+fn test_nmea_type_from_str() {
+    let kinds = [
+        NmeaType::Gpgga,
+        NmeaType::Gpgll,
+        NmeaType::Gpgsa,
+        NmeaType::Gpgsv,
+        NmeaType::Gprmc,
+        NmeaType::Gptxt,
+        NmeaType::Gpvtg,
+    ];
+    for kind in kinds {
+        assert_eq!(NmeaType::try_from(kind.as_str()), Ok(kind));
+        assert_eq!(kind.as_str().to_lowercase().parse(), Ok(kind));
+    }
+    assert_eq!(NmeaType::try_from("GNRMC"), Ok(NmeaType::Gprmc));
+    assert_eq!(
+        NmeaType::try_from(NmeaType::Unknown.as_str()),
+        Err(NmeaError::UnsupportedSentence)
+    );
+    assert_eq!(
+        NmeaType::try_from("GPXYZ"),
+        Err(NmeaError::UnsupportedSentence)
+    );
+    assert_eq!(NmeaType::try_from("G"), Err(NmeaError::UnsupportedSentence));
+}
+
+#[test]
+fn test_format_decimal_degrees() {
+    let format = |pos: &Position, decimals: u8| {
+        let mut out: heapless::String<24> = heapless::String::new();
+        format_decimal_degrees(pos, &mut out, decimals).unwrap();
+        out
+    };
+
+    let west = Position::new(123, 11.12, CardinalDirection::West);
+    assert_eq!(format(&west, 6), "-123.185333");
+    assert_eq!(format(&west, 0), "-123");
+    let north = Position::new(49, 16.45, CardinalDirection::North);
+    assert_eq!(format(&north, 6), "49.274167");
+    // Rounding carries into the degrees
+    let carry = Position::new(9, 59.9999999, CardinalDirection::East);
+    assert_eq!(format(&carry, 5), "10.00000");
+    // No sign on a value that rounds to zero
+    let zero = Position::new(0, 0.0000001, CardinalDirection::South);
+    assert_eq!(format(&zero, 4), "0.0000");
+
+    // Agrees with the float path to within the printed precision
+    for (degrees, minutes) in [(0, 0.0), (48, 7.038), (11, 31.0), (179, 59.99), (3, 0.0123)] {
+        for cardinal in [CardinalDirection::North, CardinalDirection::West] {
+            let pos = Position::new(degrees, minutes, cardinal);
+            for decimals in 0..=9 {
+                let printed: f64 = format(&pos, decimals).parse().unwrap();
+                let tolerance = 0.5 * 10f64.powi(-(decimals as i32)) + 1e-9;
+                assert!((printed - pos.to_decimal_degrees()).abs() <= tolerance);
+            }
+        }
+    }
+
+    let mut short: heapless::String<4> = heapless::String::new();
+    assert_eq!(
+        format_decimal_degrees(&west, &mut short, 2),
+        Err(NmeaError::InvalidLength)
+    );
+    let invalid = Position::new(10, 75.0, CardinalDirection::North);
+    assert_eq!(
+        format_decimal_degrees(&invalid, &mut heapless::String::<24>::new(), 2),
+        Err(NmeaError::ParseError)
+    );
+}
+
+fn feed(reader: &mut NmeaReader, bytes: &[u8]) -> Vec<Result<NmeaSentence, NmeaError>> {
+    bytes.iter().filter_map(|&b| reader.push(b)).collect()
+}
+
+#[test]
+fn test_nmea_reader_framing() {
+    let gga = b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n";
+    let rmc = b"$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n";
+    let mut reader = NmeaReader::new();
+
+    // Garbage before the first '$' and split across arbitrary chunks
+    assert!(feed(&mut reader, b"\x00\xff noise\r\n").is_empty());
+    assert!(feed(&mut reader, &gga[..10]).is_empty());
+    assert!(feed(&mut reader, &gga[10..40]).is_empty());
+    let out = feed(&mut reader, &gga[40..]);
+    assert!(matches!(out.as_slice(), [Ok(NmeaSentence::Gpgga(g))] if g.satellites_used == 8));
+
+    // Noise between sentences, two sentences in one chunk
+    let mut chunk = b"junk".to_vec();
+    chunk.extend_from_slice(rmc);
+    chunk.extend_from_slice(b"\r\n\x07");
+    chunk.extend_from_slice(gga);
+    let out = feed(&mut reader, &chunk);
+    assert_eq!(out.len(), 2);
+    assert!(matches!(out[0], Ok(NmeaSentence::Gprmc(_))));
+    assert!(matches!(out[1], Ok(NmeaSentence::Gpgga(_))));
+
+    // A truncated sentence is abandoned when the next one starts
+    let mut chunk = rmc[..30].to_vec();
+    chunk.extend_from_slice(gga);
+    let out = feed(&mut reader, &chunk);
+    assert!(matches!(out.as_slice(), [Ok(NmeaSentence::Gpgga(_))]));
+
+    // Corrupted data is reported and the reader keeps going
+    let mut bad = gga.to_vec();
+    bad[20] = b'9';
+    assert_eq!(feed(&mut reader, &bad), [Err(NmeaError::InvalidChecksum)]);
+    assert_eq!(feed(&mut reader, gga).len(), 1);
+
+    // An over-length line errors once, then the reader resynchronises
+    let mut long = b"$GPGGA,".to_vec();
+    long.extend(std::iter::repeat_n(b'1', NMEA_MAX_LENGTH));
+    long.extend_from_slice(b"\r\n");
+    assert_eq!(feed(&mut reader, &long), [Err(NmeaError::InvalidLength)]);
+    assert!(matches!(
+        feed(&mut reader, rmc).as_slice(),
+        [Ok(NmeaSentence::Gprmc(_))]
+    ));
+
+    // reset() drops a partial sentence
+    feed(&mut reader, &gga[..20]);
+    reader.reset();
+    assert!(feed(&mut reader, &gga[20..]).is_empty());
+}
+
+#[test]
+fn test_nmea_builder_round_trip() {
+    let fields = ["4916.45", "N", "12311.12", "W", "225444", "A"];
+    let gll = NmeaBuilder::build_type(NmeaType::Gpgll, &fields).unwrap();
+    assert_eq!(gll.as_str(), "$GPGLL,4916.45,N,12311.12,W,225444,A*31\r\n");
+    assert_eq!(NmeaBuilder::build("GPGLL", &fields).unwrap(), gll);
+
+    // Parsing the built sentence gives back the same field values
+    let NmeaSentence::Gpgll(parsed) = NmeaParser::parse(&gll, true).unwrap() else {
+        panic!("expected GPGLL");
+    };
+    assert_eq!(parsed.base.errors, 0);
+    let position = |p: &Position, width: usize| {
+        format!("{:0width$}{:05.2}", p.degrees, p.minutes, width = width)
+    };
+    let time = format!(
+        "{:02}{:02}{:02}",
+        parsed.time.hour, parsed.time.minute, parsed.time.second
+    );
+    let round_trip = [
+        position(&parsed.latitude, 2),
+        parsed.latitude.cardinal.to_char().to_string(),
+        position(&parsed.longitude, 3),
+        parsed.longitude.cardinal.to_char().to_string(),
+        time,
+        if parsed.status { "A" } else { "V" }.to_string(),
+    ];
+    assert_eq!(round_trip, fields);
+
+    // Proprietary receiver commands
+    assert_eq!(
+        NmeaBuilder::build("PMTK314", &["0", "1", "0", "1"])
+            .unwrap()
+            .as_str(),
+        "$PMTK314,0,1,0,1*34\r\n"
+    );
+    assert_eq!(
+        NmeaBuilder::build_type(NmeaType::Unknown, &[]),
+        Err(NmeaError::UnsupportedSentence)
+    );
+    assert_eq!(NmeaBuilder::build("", &[]), Err(NmeaError::InvalidPrefix));
+    let long = [&"9".repeat(NMEA_MAX_LENGTH)[..]];
+    assert_eq!(
+        NmeaBuilder::build("PMTK220", &long),
+        Err(NmeaError::InvalidLength)
+    );
+}
+
+#[test]
+fn test_nmea_reader_allow_list() {
+    let gga = b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n";
+    let rmc = b"$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n";
+    // Deliberately bad checksum: a skipped line is never validated
+    let gsv = b"$GPGSV,3,1,11,03,03,111,00,04,15,270,00,06,01,010,00,13,06,292,00*00\r\n";
+    let mut stream = Vec::new();
+    for _ in 0..3 {
+        stream.extend_from_slice(gsv);
+        stream.extend_from_slice(rmc);
+        stream.extend_from_slice(gga);
+    }
+
+    // By default everything is parsed, unsupported types included
+    let mut reader = NmeaReader::new();
+    assert!(reader.is_allowed(NmeaType::Gpgsv));
+    let out = feed(&mut reader, &stream);
+    assert_eq!(out.len(), 9);
+    assert_eq!(
+        reader.stats(),
+        ReaderStats {
+            parsed: 6,
+            errors: 3,
+            skipped: 0
+        }
+    );
+
+    // Only GGA: the others are dropped before validation and parsing
+    reader.reset_stats();
+    reader.set_allowed(&[NmeaType::Gpgga]);
+    assert!(!reader.is_allowed(NmeaType::Gprmc));
+    let out = feed(&mut reader, &stream);
+    assert_eq!(out.len(), 3);
+    assert!(out.iter().all(|r| matches!(r, Ok(NmeaSentence::Gpgga(_)))));
+    assert_eq!(
+        reader.stats(),
+        ReaderStats {
+            parsed: 3,
+            errors: 0,
+            skipped: 6
+        }
+    );
+
+    // Garbage lines classify as Unknown and are skipped too
+    reader.reset_stats();
+    assert!(feed(&mut reader, b"$12345,x*00\r\n").is_empty());
+    assert_eq!(reader.stats().skipped, 1);
+
+    reader.allow_all();
+    assert_eq!(feed(&mut reader, &stream).len(), 9);
+}
+
+#[test]
+fn test_gsv_constellations() {
+    let gsv = "$GPGSV,3,1,11,03,03,111,00,04,15,270,00,06,01,010,00,13,06,292,00*74\r\n";
+    let NmeaSentence::Gpgsv(gps) = NmeaParser::parse(gsv, true).unwrap() else {
+        panic!("expected GSV");
+    };
+    assert_eq!(gps.base.errors, 0);
+    assert_eq!(gps.constellation, Some(Constellation::Gps));
+    assert_eq!(
+        (
+            gps.total_messages,
+            gps.message_number,
+            gps.satellites_in_view
+        ),
+        (3, 1, 11)
+    );
+    assert_eq!(gps.satellites.len(), 4);
+    assert_eq!(
+        gps.satellites[1],
+        GsvSatellite {
+            prn: 4,
+            constellation: Some(Constellation::Gps),
+            elevation_degrees: Some(15),
+            azimuth_degrees: Some(270),
+            snr_db: Some(0),
+        }
+    );
+
+    // GLONASS talker, with empty SNR and an NMEA 4.10 signal ID
+    let gl = build_sentence(
+        "GL",
+        "GSV",
+        &[
+            "1", "1", "2", "65", "42", "035", "", "81", "10", "300", "27", "1",
+        ],
+    )
+    .unwrap();
+    let NmeaSentence::Gpgsv(glonass) = NmeaParser::parse(&gl, true).unwrap() else {
+        panic!("expected GSV");
+    };
+    assert_eq!(glonass.base.errors, 0);
+    assert_eq!(glonass.constellation, Some(Constellation::Glonass));
+    assert_eq!(glonass.satellites.len(), 2);
+    assert!(
+        glonass
+            .satellites
+            .iter()
+            .all(|sat| sat.constellation == Some(Constellation::Glonass))
+    );
+    assert_eq!(glonass.satellites[0].snr_db, None);
+    assert_eq!(glonass.satellites[1].snr_db, Some(27));
+
+    // Combined sentences are split by satellite ID
+    let gn = build_sentence(
+        "GN",
+        "GSV",
+        &[
+            "1", "1", "4", "07", "", "", "30", "46", "1", "", "", "70", "", "", "", "999", "", "",
+            "",
+        ],
+    )
+    .unwrap();
+    let NmeaSentence::Gpgsv(mixed) = NmeaParser::parse(&gn, true).unwrap() else {
+        panic!("expected GSV");
+    };
+    assert_eq!(mixed.constellation, None);
+    let systems: Vec<_> = mixed.satellites.iter().map(|s| s.constellation).collect();
+    assert_eq!(
+        systems,
+        [
+            Some(Constellation::Gps),
+            Some(Constellation::Sbas),
+            Some(Constellation::Glonass),
+            None
+        ]
+    );
+
+    // Other talkers are recognised as GSV too
+    for talker in ["GA", "GB", "BD", "GQ"] {
+        let sentence = build_sentence(talker, "GSV", &["1", "1", "1", "5", "", "", "30"]).unwrap();
+        assert_eq!(NmeaParser::get_sentence_type(&sentence), NmeaType::Gpgsv);
+        let NmeaSentence::Gpgsv(gsv) = NmeaParser::parse(&sentence, true).unwrap() else {
+            panic!("expected GSV");
+        };
+        assert_eq!(gsv.constellation, Constellation::from_talker(talker));
+        assert_eq!(gsv.satellites[0].constellation, gsv.constellation);
+    }
+
+    assert_eq!(Constellation::from_prn(301), Some(Constellation::Galileo));
+    assert_eq!(Constellation::from_prn(401), Some(Constellation::BeiDou));
+    assert_eq!(Constellation::from_prn(0), None);
+    assert_eq!(Constellation::from_talker("GN"), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_gprmc_serde_round_trip() {
+    let sentence = "$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n";
+    let NmeaSentence::Gprmc(rmc) = NmeaParser::parse(sentence, true).unwrap() else {
+        panic!("Expected GPRMC sentence");
+    };
+
+    let json: heapless::String<512> = serde_json_core::to_string(&rmc).unwrap();
+    assert!(json.contains(r#""cardinal":"N""#));
+    assert!(json.contains(r#""cardinal":"W""#));
+
+    let (decoded, _): (Gprmc, _) = serde_json_core::from_str(&json).unwrap();
+    assert_eq!(decoded, rmc);
+
+    let wrapped = NmeaSentence::Gprmc(rmc);
+    let json: heapless::String<512> = serde_json_core::to_string(&wrapped).unwrap();
+    let (decoded, _): (NmeaSentence, _) = serde_json_core::from_str(&json).unwrap();
+    assert_eq!(decoded, wrapped);
+}
+
+#[test]
+fn test_position_f32_matches_f64() {
+    let samples = [
+        ("4807.038", CardinalDirection::North),
+        ("01131.000", CardinalDirection::East),
+        ("4916.45", CardinalDirection::North),
+        ("12311.12", CardinalDirection::West),
+        ("5321.6802", CardinalDirection::North),
+        ("00630.3372", CardinalDirection::West),
+        ("17959.9999", CardinalDirection::East),
+    ];
+
+    for (field, cardinal) in samples {
+        let (degrees, minutes) = NmeaParser::parse_position(field).unwrap();
+        let (degrees_f32, minutes_f32) = NmeaParser::parse_position_f32(field).unwrap();
+        assert_eq!(degrees_f32, degrees);
+
+        let reference = Position::new(degrees, minutes, cardinal).to_decimal_degrees();
+        let single = PositionF32::new(degrees_f32, minutes_f32, cardinal).to_decimal_degrees();
+        assert!(
+            (single as f64 - reference).abs() < 1e-4,
+            "{field}: {single} vs {reference}"
+        );
+    }
+
+    assert_eq!(
+        NmeaParser::parse_position_f32(""),
+        Err(NmeaError::ParseError)
+    );
+    assert_eq!(PositionF32::default().to_decimal_degrees(), 0.0);
+}
@@ -1,3 +1,4 @@
+pub mod error;
 pub mod gps;
 pub mod network;
 pub mod ota;
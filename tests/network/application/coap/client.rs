@@ -0,0 +1,241 @@
+use libiot::network::UdpSocket;
+use libiot::network::application::coap::{Client, CoapOption, Code, Message, MessageType};
+use libiot::network::error::Error;
+use libiot::system::clock::MonotonicClock;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+const SERVER: &str = "192.0.2.1:5683";
+
+type Responder = Box<dyn Fn(&Message) -> Vec<Vec<u8>>>;
+
+/// A server that answers requests with the datagrams from `respond`,
+/// after ignoring the first `ignore` of them
+///
+/// Time advances by 100 ms whenever the client finds nothing to receive.
+struct MockServer {
+    now: Rc<Cell<u64>>,
+    respond: Responder,
+    ignore: usize,
+    queue: VecDeque<Vec<u8>>,
+    sent: Vec<(u64, Vec<u8>)>,
+}
+
+impl MockServer {
+    fn new(respond: impl Fn(&Message) -> Vec<Vec<u8>> + 'static) -> Self {
+        Self {
+            now: Rc::new(Cell::new(0)),
+            respond: Box::new(respond),
+            ignore: 0,
+            queue: VecDeque::new(),
+            sent: Vec::new(),
+        }
+    }
+
+    /// Times at which the client sent requests
+    fn request_times(&self) -> Vec<u64> {
+        self.sent
+            .iter()
+            .filter(|(_, datagram)| Message::decode(datagram).unwrap().code.is_request())
+            .map(|(at, _)| *at)
+            .collect()
+    }
+}
+
+impl UdpSocket for MockServer {
+    type Error = Error;
+
+    fn send_to(&mut self, remote: &str, buf: &[u8]) -> Result<usize, Self::Error> {
+        assert_eq!(remote, SERVER);
+        self.sent.push((self.now.get(), buf.to_vec()));
+        let message = Message::decode(buf).unwrap();
+        if message.message_type == MessageType::Confirmable {
+            if self.ignore > 0 {
+                self.ignore -= 1;
+            } else {
+                self.queue.extend((self.respond)(&message));
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> Result<(usize, &str), Self::Error> {
+        match self.queue.pop_front() {
+            Some(datagram) => {
+                buf[..datagram.len()].copy_from_slice(&datagram);
+                Ok((datagram.len(), SERVER))
+            }
+            None => {
+                self.now.set(self.now.get() + 100);
+                Err(Error::WouldBlock)
+            }
+        }
+    }
+}
+
+struct MockClock(Rc<Cell<u64>>);
+
+impl MonotonicClock for MockClock {
+    fn now_ms(&self) -> u64 {
+        self.0.get()
+    }
+}
+
+fn coap_client(server: MockServer) -> Client<MockServer, MockClock, 256> {
+    let clock = MockClock(server.now.clone());
+    Client::new(server, SERVER, clock, 0x1234_5678)
+}
+
+fn encode(message: &Message) -> Vec<u8> {
+    let mut buf = [0u8; 256];
+    let len = message.encode(&mut buf).unwrap();
+    buf[..len].to_vec()
+}
+
+/// A piggybacked response to `request`
+fn ack(request: &Message, code: Code, payload: &[u8]) -> Vec<u8> {
+    let mut ack = Message::new(MessageType::Acknowledgement, code, request.message_id);
+    ack.token = request.token;
+    ack.payload = payload;
+    encode(&ack)
+}
+
+#[test]
+fn test_coap_piggybacked_response() {
+    let mut client = coap_client(MockServer::new(|request| {
+        let code = match request.code {
+            Code::GET => Code::CONTENT,
+            Code::POST => Code::CREATED,
+            Code::PUT => Code::CHANGED,
+            _ => Code::DELETED,
+        };
+        vec![ack(request, code, request.payload)]
+    }));
+
+    let response = client.get("/sensors/temp?unit=c&precise").unwrap();
+    assert_eq!(response.code, Code::CONTENT);
+    assert_eq!(response.payload, b"");
+
+    let sent = client.socket_mut().sent.clone();
+    let request = Message::decode(&sent[0].1).unwrap();
+    assert_eq!(request.message_type, MessageType::Confirmable);
+    assert_eq!(request.code, Code::GET);
+    assert_eq!(request.token.len(), 4);
+    assert_eq!(
+        request
+            .options_with(CoapOption::URI_PATH)
+            .collect::<Vec<_>>(),
+        [&b"sensors"[..], b"temp"]
+    );
+    assert_eq!(
+        request
+            .options_with(CoapOption::URI_QUERY)
+            .collect::<Vec<_>>(),
+        [&b"unit=c"[..], b"precise"]
+    );
+
+    let response = client.post("/log", b"boot").unwrap();
+    assert_eq!(response.code, Code::CREATED);
+    assert_eq!(response.payload, b"boot");
+    assert_eq!(client.put("/led", b"on").unwrap().code, Code::CHANGED);
+    assert_eq!(client.delete("/log").unwrap().code, Code::DELETED);
+
+    // Every request gets a new message id and token
+    let requests: Vec<_> = client
+        .socket()
+        .sent
+        .iter()
+        .map(|(_, datagram)| Message::decode(datagram).unwrap())
+        .collect();
+    assert_eq!(requests.len(), 4);
+    for pair in requests.windows(2) {
+        assert_eq!(pair[1].message_id, pair[0].message_id.wrapping_add(1));
+        assert_ne!(pair[1].token, pair[0].token);
+    }
+}
+
+#[test]
+fn test_coap_retransmission() {
+    let mut server = MockServer::new(|request| vec![ack(request, Code::CONTENT, b"ok")]);
+    server.ignore = 2;
+    let mut client = coap_client(server);
+
+    assert_eq!(client.get("/status").unwrap().payload, b"ok");
+    assert_eq!(client.socket().request_times(), [0, 2_000, 6_000]);
+    let sent = &client.socket().sent;
+    assert!(sent.iter().all(|(_, datagram)| *datagram == sent[0].1));
+
+    // The timeout doubles after each retransmission, then the client gives up
+    let mut server = MockServer::new(|_| Vec::new());
+    server.ignore = usize::MAX;
+    let mut client = coap_client(server);
+    assert_eq!(client.get("/status").err(), Some(Error::ConnectionClosed));
+    assert_eq!(
+        client.socket().request_times(),
+        [0, 2_000, 6_000, 14_000, 30_000]
+    );
+    assert_eq!(client.socket().now.get(), 62_000);
+
+    // A shorter ACK timeout
+    let server = MockServer::new(|_| Vec::new());
+    let mut client = coap_client(server).with_ack_timeout(500);
+    assert_eq!(client.get("/status").err(), Some(Error::ConnectionClosed));
+    assert_eq!(
+        client.socket().request_times(),
+        [0, 500, 1_500, 3_500, 7_500]
+    );
+}
+
+#[test]
+fn test_coap_separate_response() {
+    let mut client = coap_client(MockServer::new(|request| {
+        let empty_ack = Message::new(
+            MessageType::Acknowledgement,
+            Code::EMPTY,
+            request.message_id,
+        );
+        let mut other = Message::new(MessageType::NonConfirmable, Code::CONTENT, 0x4242);
+        other.token = b"none";
+        other.payload = b"not for us";
+        let mut response = Message::new(MessageType::Confirmable, Code::CONTENT, 0x9999);
+        response.token = request.token;
+        response.payload = b"22.3 C";
+        vec![encode(&empty_ack), encode(&other), encode(&response)]
+    }));
+
+    let response = client.get("/temperature").unwrap();
+    assert_eq!(response.code, Code::CONTENT);
+    assert_eq!(response.payload, b"22.3 C");
+
+    // The Confirmable response is acknowledged
+    let (_, last) = client.socket().sent.last().unwrap();
+    assert_eq!(
+        Message::decode(last).unwrap(),
+        Message::new(MessageType::Acknowledgement, Code::EMPTY, 0x9999)
+    );
+
+    // An acknowledged request is not retransmitted, but the wait is bounded
+    let mut client = coap_client(MockServer::new(|request| {
+        let empty_ack = Message::new(
+            MessageType::Acknowledgement,
+            Code::EMPTY,
+            request.message_id,
+        );
+        vec![encode(&empty_ack)]
+    }));
+    assert_eq!(client.get("/temperature").err(), Some(Error::Timeout));
+    assert_eq!(client.socket().request_times(), [0]);
+}
+
+#[test]
+fn test_coap_reset() {
+    let mut client = coap_client(MockServer::new(|request| {
+        vec![encode(&Message::new(
+            MessageType::Reset,
+            Code::EMPTY,
+            request.message_id,
+        ))]
+    }));
+    assert_eq!(client.get("/status").err(), Some(Error::ConnectionRefused));
+}
@@ -0,0 +1,130 @@
+use libiot::network::application::coap::{CoapOption, Code, Message, MessageType};
+use libiot::network::error::Error;
+
+/// The example CON GET /temperature request from RFC 7252
+const GET_TEMPERATURE: &[u8] = &[
+    0x40, 0x01, 0x7d, 0x34, 0xbb, b't', b'e', b'm', b'p', b'e', b'r', b'a', b't', b'u', b'r', b'e',
+];
+
+/// The piggybacked 2.05 Content response to it
+const CONTENT_RESPONSE: &[u8] = &[
+    0x60, 0x45, 0x7d, 0x34, 0xff, b'2', b'2', b'.', b'3', b' ', b'C',
+];
+
+#[test]
+fn test_coap_rfc_example_round_trip() {
+    let request = Message::decode(GET_TEMPERATURE).unwrap();
+    assert_eq!(request.version, 1);
+    assert_eq!(request.message_type, MessageType::Confirmable);
+    assert_eq!(request.code, Code::GET);
+    assert_eq!(request.message_id, 0x7d34);
+    assert_eq!(request.token, b"");
+    assert_eq!(
+        request.option(CoapOption::URI_PATH),
+        Some(&b"temperature"[..])
+    );
+    assert_eq!(request.payload, b"");
+
+    let mut buf = [0u8; 64];
+    let len = request.encode(&mut buf).unwrap();
+    assert_eq!(&buf[..len], GET_TEMPERATURE);
+
+    let response = Message::decode(CONTENT_RESPONSE).unwrap();
+    assert_eq!(response.message_type, MessageType::Acknowledgement);
+    assert_eq!(response.code, Code::CONTENT);
+    assert_eq!((response.code.class(), response.code.detail()), (2, 5));
+    assert!(response.options.is_empty());
+    assert_eq!(response.payload, b"22.3 C");
+    let len = response.encode(&mut buf).unwrap();
+    assert_eq!(&buf[..len], CONTENT_RESPONSE);
+
+    // Too small a buffer
+    assert_eq!(request.encode(&mut buf[..10]), Err(Error::WriteError));
+}
+
+#[test]
+fn test_coap_extended_options() {
+    let long = [b'x'; 300];
+    let mut message = Message::new(MessageType::NonConfirmable, Code::POST, 1);
+    message.token = &[0xAB, 0xCD];
+    message.payload = b"data";
+    // Added out of order; kept sorted, with repeats in insertion order
+    message
+        .add_option(CoapOption::SIZE1, &[0x01, 0x2C])
+        .unwrap();
+    message.add_option(CoapOption::URI_PATH, b"a").unwrap();
+    message
+        .add_option(CoapOption::URI_PATH, &long[..20])
+        .unwrap();
+    message.add_option(CoapOption::PROXY_URI, &long).unwrap();
+    message.add_option(1000, b"").unwrap();
+
+    let mut buf = [0u8; 512];
+    let len = message.encode(&mut buf).unwrap();
+    let encoded = &buf[..len];
+    assert_eq!(encoded[..6], [0x52, 0x02, 0x00, 0x01, 0xAB, 0xCD]);
+    // Uri-Path (11), 1 byte
+    assert_eq!(encoded[6..8], [0xB1, b'a']);
+    // Repeated Uri-Path: delta 0, 1-byte extended length 20 - 13
+    assert_eq!(encoded[8..10], [0x0D, 7]);
+    // Proxy-Uri (35): 1-byte extended delta 24 - 13, 2-byte length 300 - 269
+    assert_eq!(encoded[30..34], [0xDE, 11, 0x00, 31]);
+    // Size1 (60): 1-byte extended delta 25 - 13
+    assert_eq!(encoded[334..338], [0xD2, 12, 0x01, 0x2C]);
+    // Option 1000: 2-byte extended delta 940 - 269
+    assert_eq!(encoded[338..341], [0xE0, 0x02, 0x9F]);
+    assert_eq!(&encoded[341..], b"\xFFdata");
+
+    let decoded = Message::decode(encoded).unwrap();
+    assert_eq!(decoded, message);
+    assert_eq!(
+        decoded
+            .options_with(CoapOption::URI_PATH)
+            .collect::<Vec<_>>(),
+        [&b"a"[..], &long[..20]]
+    );
+
+    // Options that are not in ascending order cannot be encoded
+    message.options.swap(0, 3);
+    assert_eq!(message.encode(&mut [0; 512]), Err(Error::ProtocolError));
+}
+
+#[test]
+fn test_coap_malformed_messages() {
+    let malformed: [&[u8]; 8] = [
+        // Truncated header
+        &[0x40, 0x01, 0x7d],
+        // Version 2
+        &[0x80, 0x01, 0x7d, 0x34],
+        // Token length 9
+        &[0x49, 0x01, 0x7d, 0x34, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        // Truncated token
+        &[0x44, 0x01, 0x7d, 0x34, 0xAB],
+        // Option value longer than the message
+        &[0x40, 0x01, 0x7d, 0x34, 0xB5, b'a'],
+        // Reserved delta nibble 15
+        &[0x40, 0x01, 0x7d, 0x34, 0xF1, b'a'],
+        // Payload marker without a payload
+        &[0x40, 0x01, 0x7d, 0x34, 0xFF],
+        // Empty message with a token
+        &[0x41, 0x00, 0x7d, 0x34, 0xAB],
+    ];
+    for buf in malformed {
+        assert_eq!(
+            Message::decode(buf),
+            Err(Error::ProtocolError),
+            "{buf:02X?}"
+        );
+    }
+
+    // An empty ACK, and a Reset
+    let ack = Message::decode(&[0x60, 0x00, 0x7d, 0x34]).unwrap();
+    assert_eq!(ack.message_type, MessageType::Acknowledgement);
+    assert_eq!(ack.code, Code::EMPTY);
+    let reset = Message::decode(&[0x70, 0x00, 0x7d, 0x34]).unwrap();
+    assert_eq!(reset.message_type, MessageType::Reset);
+
+    let mut message = Message::new(MessageType::Confirmable, Code::GET, 1);
+    message.token = &[0; 9];
+    assert_eq!(message.encode(&mut [0; 32]), Err(Error::ProtocolError));
+}
@@ -0,0 +1,49 @@
+pub mod client;
+pub mod message;
+
+use libiot::network::application::coap::dedup::*;
+
+#[test]
+fn test_duplicates_suppressed_within_lifetime() {
+    let mut cache = MessageIdCache::<4>::new();
+
+    assert_eq!(cache.receive(1, 0), Delivery::New);
+    assert_eq!(cache.receive(2, 10), Delivery::New);
+    assert_eq!(cache.receive(1, 20), Delivery::Duplicate { acked: false });
+    assert_eq!(cache.pending_acks().collect::<Vec<_>>(), [1, 2]);
+
+    // Once acknowledged, duplicates ask for the ACK to be repeated
+    assert!(cache.mark_acked(1));
+    assert!(!cache.mark_acked(9));
+    assert_eq!(cache.receive(1, 30), Delivery::Duplicate { acked: true });
+    assert_eq!(cache.pending_acks().collect::<Vec<_>>(), [2]);
+
+    // After EXCHANGE_LIFETIME the id may be reused
+    assert_eq!(
+        cache.receive(1, EXCHANGE_LIFETIME_MS - 1),
+        Delivery::Duplicate { acked: true }
+    );
+    assert_eq!(cache.receive(1, EXCHANGE_LIFETIME_MS), Delivery::New);
+    assert_eq!(cache.pending_acks().collect::<Vec<_>>(), [2, 1]);
+    cache.expire(EXCHANGE_LIFETIME_MS + 10);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.pending_acks().collect::<Vec<_>>(), [1]);
+}
+
+#[test]
+fn test_full_cache_evicts_oldest() {
+    let mut cache = MessageIdCache::<3>::with_lifetime(1_000);
+    for id in 0..3 {
+        assert_eq!(cache.receive(id, 0), Delivery::New);
+    }
+    assert_eq!(cache.receive(3, 1), Delivery::New);
+    assert_eq!(cache.len(), 3);
+
+    // The oldest id was forgotten, the others are still recognised
+    assert_eq!(cache.receive(1, 2), Delivery::Duplicate { acked: false });
+    assert_eq!(cache.receive(0, 3), Delivery::New);
+    assert_eq!(cache.pending_acks().collect::<Vec<_>>(), [2, 3, 0]);
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
@@ -1,5 +1,8 @@
 use dotenvy::dotenv;
-use libiot::network::application::http::client::{Client, Method, Request};
+use libiot::network::application::http::client::{
+    Client, Conditional, ETag, Header, Method, Request, Response, ResponseStats, StatusCode,
+};
+use libiot::network::application::http::stream::StreamingResponse;
 use libiot::network::{Close, Connection, Read, Write};
 use std::env;
 use std::io::{Read as StdRead, Write as StdWrite};
@@ -118,3 +121,808 @@ fn test_http_post() {
     let response = response.unwrap();
     assert_eq!(response.status_code, 200);
 }
+
+/// In-memory connection that replays a canned response.
+struct CannedConnection {
+    response: std::vec::Vec<u8>,
+    pos: usize,
+    max_read: usize,
+    written: std::rc::Rc<std::cell::RefCell<std::vec::Vec<u8>>>,
+}
+
+impl CannedConnection {
+    fn new(response: impl Into<std::vec::Vec<u8>>) -> Self {
+        Self {
+            response: response.into(),
+            pos: 0,
+            max_read: usize::MAX,
+            written: Default::default(),
+        }
+    }
+
+    /// Return at most `max_read` bytes from each read.
+    fn with_max_read(mut self, max_read: usize) -> Self {
+        self.max_read = max_read;
+        self
+    }
+}
+
+impl Read for CannedConnection {
+    type Error = libiot::network::error::Error;
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = buf
+            .len()
+            .min(self.max_read)
+            .min(self.response.len() - self.pos);
+        buf[..n].copy_from_slice(&self.response[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Write for CannedConnection {
+    type Error = libiot::network::error::Error;
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.written.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Close for CannedConnection {
+    type Error = libiot::network::error::Error;
+    fn close(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Connection for CannedConnection {}
+
+#[test]
+fn test_http_header_overflow_is_flagged() {
+    let mut raw = String::from("HTTP/1.1 302 Found\r\n");
+    for i in 0..24 {
+        raw.push_str(&format!("X-Filler-{i}: {i}\r\n"));
+    }
+    raw.push_str(&format!("X-Long: {}\r\n", "v".repeat(300)));
+    raw.push_str("Location: /fw/app.bin\r\n");
+    raw.push_str("Content-Length: 2\r\n\r\nok");
+
+    let conn = CannedConnection::new(raw);
+    let mut client = Client::new(conn);
+    let request = Request {
+        method: Method::Get,
+        path: "/fw",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+
+    let response = client.request(&request).unwrap();
+    assert_eq!(response.status_code, 302);
+    assert!(response.headers_truncated);
+    assert_eq!(response.headers.len(), response.headers.capacity());
+
+    // Critical headers arriving after the capacity is exhausted are retained
+    let find = |name: &str| response.header(name);
+    assert_eq!(find("Location"), Some("/fw/app.bin"));
+    assert_eq!(find("Content-Length"), Some("2"));
+    assert_eq!(find("X-Filler-0"), Some("0"));
+    assert_eq!(find("X-Filler-20"), None);
+    assert_eq!(response.body.as_slice(), b"ok");
+}
+
+#[test]
+fn test_http_long_header_value_is_truncated() {
+    let raw = format!(
+        "HTTP/1.1 200 OK\r\nX-Long: {}\r\nContent-Length: 0\r\n\r\n",
+        "v".repeat(300)
+    );
+    let conn = CannedConnection::new(raw);
+    let mut client = Client::new(conn);
+    let request = Request {
+        method: Method::Get,
+        path: "/",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+
+    let response = client.request(&request).unwrap();
+    assert!(response.headers_truncated);
+    assert_eq!(response.headers[0].value.len(), 256);
+    assert_eq!(response.headers.len(), 2);
+}
+
+#[test]
+fn test_http_custom_method() {
+    let conn = CannedConnection::new("HTTP/1.1 207 Multi-Status\r\nContent-Length: 0\r\n\r\n");
+    let written = conn.written.clone();
+    let mut client = Client::new(conn);
+
+    let request = Request {
+        method: Method::custom("PROPFIND").unwrap(),
+        path: "/dav/",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+    let response = client.request(&request).unwrap();
+    assert_eq!(response.status_code, 207);
+    assert!(written.borrow().starts_with(b"PROPFIND /dav/ HTTP/1.1\r\n"));
+
+    // Invalid tokens never reach the wire
+    written.borrow_mut().clear();
+    let request = Request {
+        method: Method::Custom("GET /evil"),
+        path: "/",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+    assert_eq!(
+        client.request(&request).unwrap_err(),
+        libiot::network::error::Error::ProtocolError
+    );
+    assert!(written.borrow().is_empty());
+}
+
+#[test]
+fn test_http_conditional_request() {
+    let request = Request {
+        method: Method::Get,
+        path: "/config.json",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+    let mut etag: Option<ETag> = None;
+
+    // First fetch: no validator sent, the new ETag is captured
+    let conn =
+        CannedConnection::new("HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 2\r\n\r\n{}");
+    let written = conn.written.clone();
+    let mut client = Client::new(conn);
+    match client.request_conditional(&request, &mut etag).unwrap() {
+        Conditional::Modified(response) => assert_eq!(response.body.as_slice(), b"{}"),
+        other => panic!("unexpected {other:?}"),
+    }
+    assert_eq!(etag.as_deref(), Some("\"v1\""));
+    assert!(!String::from_utf8_lossy(&written.borrow()).contains("If-None-Match"));
+
+    // Unchanged resource: the stored ETag is sent and 304 is reported as such
+    let conn = CannedConnection::new("HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\n\r\n");
+    let written = conn.written.clone();
+    let mut client = Client::new(conn);
+    assert!(matches!(
+        client.request_conditional(&request, &mut etag).unwrap(),
+        Conditional::NotModified
+    ));
+    assert!(String::from_utf8_lossy(&written.borrow()).contains("If-None-Match: \"v1\"\r\n"));
+    assert_eq!(etag.as_deref(), Some("\"v1\""));
+
+    // Errors are passed through without touching the stored ETag
+    let conn =
+        CannedConnection::new("HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n");
+    let mut client = Client::new(conn);
+    match client.request_conditional(&request, &mut etag).unwrap() {
+        Conditional::Modified(response) => assert_eq!(response.status_code, 503),
+        other => panic!("unexpected {other:?}"),
+    }
+    assert_eq!(etag.as_deref(), Some("\"v1\""));
+}
+
+#[test]
+fn test_http_method_from_str() {
+    for name in ["GET", "POST", "PUT", "DELETE", "HEAD", "PATCH", "OPTIONS"] {
+        let method = Method::try_from(name).unwrap();
+        assert_eq!(method.as_str(), name);
+        assert!(method.is_valid());
+        assert_eq!(name.to_lowercase().parse::<Method>(), Ok(method));
+    }
+    assert_eq!(Method::try_from("post"), Ok(Method::Post));
+    assert_eq!(
+        Method::try_from("PROPFIND"),
+        Err(libiot::network::error::Error::ProtocolError)
+    );
+    assert!(Method::try_from("").is_err());
+}
+
+#[test]
+fn test_http_request_response_compare() {
+    let mut headers = heapless::Vec::new();
+    headers
+        .push(Header {
+            name: heapless::String::try_from("Accept").unwrap(),
+            value: heapless::String::try_from("application/json").unwrap(),
+        })
+        .unwrap();
+    let request = Request {
+        method: Method::Get,
+        path: "/status",
+        headers,
+        body: None,
+    };
+    let retry = request.clone();
+    assert_eq!(retry, request);
+    assert_ne!(
+        Request {
+            method: Method::Post,
+            ..retry.clone()
+        },
+        request
+    );
+
+    let conn =
+        CannedConnection::new("HTTP/1.1 200 OK\r\nETag: \"v2\"\r\nContent-Length: 2\r\n\r\nok");
+    let mut client = Client::new(conn);
+    let response = client.request(&retry).unwrap();
+
+    let mut expected = Response {
+        status_code: 200,
+        headers: heapless::Vec::new(),
+        headers_truncated: false,
+        body: heapless::Vec::from_slice(b"ok").unwrap(),
+    };
+    for (name, value) in [("ETag", "\"v2\""), ("Content-Length", "2")] {
+        expected
+            .headers
+            .push(Header {
+                name: heapless::String::try_from(name).unwrap(),
+                value: heapless::String::try_from(value).unwrap(),
+            })
+            .unwrap();
+    }
+    assert_eq!(response, expected);
+    assert_eq!(
+        Conditional::Modified(response.clone()),
+        Conditional::Modified(expected)
+    );
+}
+
+/// Advances by 15 ms on every reading
+struct StepClock(std::cell::Cell<u64>);
+
+impl libiot::system::clock::MonotonicClock for StepClock {
+    fn now_ms(&self) -> u64 {
+        self.0.set(self.0.get() + 15);
+        self.0.get()
+    }
+}
+
+#[test]
+fn test_http_response_stats() {
+    let request = Request {
+        method: Method::Get,
+        path: "/fw.bin",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+    let head = "HTTP/1.1 200 OK\r\nContent-Length: 600\r\n\r\n";
+    let canned = format!("{head}{}", "x".repeat(600));
+
+    let mut client = Client::new(CannedConnection::new(canned.clone()));
+    assert_eq!(client.last_stats(), None);
+    client.request(&request).unwrap();
+    assert_eq!(
+        client.last_stats(),
+        Some(ResponseStats {
+            body_bytes: 600,
+            header_bytes: head.len(),
+            elapsed_ms: None,
+        })
+    );
+
+    // With a clock the request is timed
+    let clock = StepClock(std::cell::Cell::new(0));
+    let mut client = Client::new(CannedConnection::new(canned));
+    client.request_timed(&request, &clock).unwrap();
+    let stats = client.last_stats().unwrap();
+    assert_eq!(stats.body_bytes, 600);
+    assert_eq!(stats.elapsed_ms, Some(15));
+
+    // A failed request clears the previous stats
+    assert!(client.request_timed(&request, &clock).is_err());
+    assert_eq!(client.last_stats(), None);
+}
+
+/// Read a streamed body to the end with a small caller buffer.
+fn read_streamed_body<C: Connection>(response: &mut StreamingResponse<'_, C>) -> Vec<u8> {
+    let mut body = Vec::new();
+    let mut chunk = [0u8; 100];
+    loop {
+        let n = response.read_body_chunk(&mut chunk).unwrap();
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body
+}
+
+#[test]
+fn test_http_streaming_large_body() {
+    let firmware: Vec<u8> = (0..10 * 1024).map(|i| (i % 251) as u8).collect();
+    let get = Request {
+        method: Method::Get,
+        path: "/fw.bin",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+
+    // Content-Length framing, delivered 7 bytes per read
+    let mut raw = format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+        firmware.len()
+    )
+    .into_bytes();
+    raw.extend_from_slice(&firmware);
+    raw.extend_from_slice(b"HTTP/1.1 next response");
+    let mut client = Client::new(CannedConnection::new(raw).with_max_read(7));
+    let mut response = client.request_streaming(&get).unwrap();
+    assert_eq!(response.status_code, 200);
+    assert_eq!(response.header("content-length"), Some("10240"));
+    assert_eq!(read_streamed_body(&mut response), firmware);
+    assert!(response.is_complete());
+    // Bytes after the body are left unread
+    assert_eq!(response.read_body_chunk(&mut [0; 8]).unwrap(), 0);
+
+    // Chunked framing
+    let mut raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+    for piece in firmware.chunks(3000) {
+        raw.extend_from_slice(format!("{:x}\r\n", piece.len()).as_bytes());
+        raw.extend_from_slice(piece);
+        raw.extend_from_slice(b"\r\n");
+    }
+    raw.extend_from_slice(b"0\r\n\r\n");
+    let mut client = Client::new(CannedConnection::new(raw).with_max_read(7));
+    let mut response = client.request_streaming(&get).unwrap();
+    assert_eq!(read_streamed_body(&mut response), firmware);
+
+    // No framing: the body runs until the connection closes
+    let mut raw = b"HTTP/1.1 200 OK\r\n\r\n".to_vec();
+    raw.extend_from_slice(&firmware);
+    let mut client = Client::new(CannedConnection::new(raw).with_max_read(7));
+    let mut response = client.request_streaming(&get).unwrap();
+    assert_eq!(read_streamed_body(&mut response), firmware);
+
+    // A body cut short by the server is an error
+    let raw = "HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort";
+    let mut client = Client::new(CannedConnection::new(raw));
+    let mut response = client.request_streaming(&get).unwrap();
+    assert_eq!(response.read_body_chunk(&mut [0; 16]), Ok(5));
+    assert_eq!(
+        response.read_body_chunk(&mut [0; 16]),
+        Err(libiot::network::error::Error::ConnectionClosed)
+    );
+}
+
+#[test]
+fn test_http_chunked_response() {
+    let get = Request {
+        method: Method::Get,
+        path: "/status",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+    let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+               5\r\nhello\r\n\
+               1;ext=\"x\"\r\n \r\n\
+               1A \r\nabcdefghijklmnopqrstuvwxyz\r\n\
+               0\r\nX-Checksum: 1234\r\n\r\n";
+
+    // Whole response in one read, then split so chunk-size lines and
+    // delimiters straddle reads
+    for max_read in [usize::MAX, 60, 3, 1] {
+        let mut client = Client::new(CannedConnection::new(raw).with_max_read(max_read));
+        let response = client.request(&get).unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(
+            response.body.as_slice(),
+            b"hello abcdefghijklmnopqrstuvwxyz",
+            "max_read {max_read}"
+        );
+        // Trailers are not merged into the headers
+        assert_eq!(response.header("X-Checksum"), None);
+    }
+
+    // A body that fills the buffer exactly is accepted, one byte more is not
+    let chunked = |len: usize| {
+        format!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n\
+             400\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+            "a".repeat(0x400),
+            len - 0x400,
+            "b".repeat(len - 0x400)
+        )
+    };
+    let mut client = Client::new(CannedConnection::new(chunked(2048)));
+    assert_eq!(client.request(&get).unwrap().body.len(), 2048);
+    let mut client = Client::new(CannedConnection::new(chunked(2049)));
+    assert_eq!(
+        client.request(&get),
+        Err(libiot::network::error::Error::ProtocolError)
+    );
+
+    // Malformed chunk-size lines and delimiters
+    for body in [
+        "xyz\r\nabc\r\n0\r\n\r\n",
+        "\r\nabc\r\n0\r\n\r\n",
+        "3\r\nabcX\r\n0\r\n\r\n",
+        "ffffffffffffffffffffffffffffffffffff\r\n",
+    ] {
+        let raw = format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{body}");
+        let mut client = Client::new(CannedConnection::new(raw));
+        assert_eq!(
+            client.request(&get),
+            Err(libiot::network::error::Error::ProtocolError),
+            "{body:?}"
+        );
+    }
+
+    // Missing final chunk
+    let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n";
+    let mut client = Client::new(CannedConnection::new(raw));
+    assert_eq!(
+        client.request(&get),
+        Err(libiot::network::error::Error::ConnectionClosed)
+    );
+}
+
+#[test]
+fn test_http_response_header_lookup() {
+    let raw = "HTTP/1.1 200 OK\r\ncOnTeNt-TyPe: text/plain\r\nCONTENT-LENGTH: 2\r\n\
+               X-Dup: first\r\nx-dup: second\r\n\r\nok";
+    let mut client = Client::new(CannedConnection::new(raw));
+    let request = Request {
+        method: Method::Get,
+        path: "/",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+
+    let response = client.request(&request).unwrap();
+    assert_eq!(response.header("Content-Type"), Some("text/plain"));
+    assert_eq!(response.header("content-type"), Some("text/plain"));
+    assert_eq!(response.header("X-DUP"), Some("first"));
+    assert_eq!(response.header("Content-Range"), None);
+    assert_eq!(response.header("Content-Typ"), None);
+    assert_eq!(response.content_length(), Some(2));
+
+    let raw = "HTTP/1.1 204 No Content\r\nContent-Length: lots\r\n\r\n";
+    let mut client = Client::new(CannedConnection::new(raw));
+    assert_eq!(client.request(&request).unwrap().content_length(), None);
+    let raw = "HTTP/1.1 204 No Content\r\n\r\n";
+    let mut client = Client::new(CannedConnection::new(raw));
+    assert_eq!(client.request(&request).unwrap().content_length(), None);
+}
+
+#[test]
+fn test_http_follows_redirects() {
+    let host = |value: &str| {
+        let mut headers = heapless::Vec::new();
+        headers
+            .push(Header {
+                name: heapless::String::try_from("Host").unwrap(),
+                value: heapless::String::try_from(value).unwrap(),
+            })
+            .unwrap();
+        headers
+    };
+    let post = Request {
+        method: Method::Post,
+        path: "/upload",
+        headers: host("example.com"),
+        body: Some(b"data"),
+    };
+    // One byte per read so no response is read past its end
+    let setup = |raw: &str, max_redirects| {
+        let conn = CannedConnection::new(raw).with_max_read(1);
+        let written = conn.written.clone();
+        (Client::new(conn).with_max_redirects(max_redirects), written)
+    };
+    let ok = "HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\ndone";
+
+    // 307 keeps the method and body
+    let raw = format!(
+        "HTTP/1.1 307 Temporary Redirect\r\nLocation: /v2/upload\r\nContent-Length: 5\r\n\r\nmoved{ok}"
+    );
+    let (mut client, written) = setup(&raw, 1);
+    let response = client.request(&post).unwrap();
+    assert_eq!(response.status_code, 200);
+    assert_eq!(response.body.as_slice(), b"done");
+    let sent = String::from_utf8(written.borrow().clone()).unwrap();
+    let second = &sent[sent.rfind("POST /v2/upload").unwrap()..];
+    assert!(second.ends_with("Content-Length: 4\r\n\r\ndata"));
+
+    // 303 switches to a body-less GET; absolute URLs on the same host are followed
+    let raw = format!(
+        "HTTP/1.1 303 See Other\r\nLocation: http://EXAMPLE.com?id=7\r\nContent-Length: 0\r\n\r\n{ok}"
+    );
+    let (mut client, written) = setup(&raw, 1);
+    assert_eq!(client.request(&post).unwrap().status_code, 200);
+    let sent = String::from_utf8(written.borrow().clone()).unwrap();
+    let second = &sent[sent.find("GET ").unwrap()..];
+    assert!(second.starts_with("GET /?id=7 HTTP/1.1\r\n"));
+    assert!(!second.contains("Content-Length"));
+
+    // Redirects to another server cannot be followed over this connection
+    let raw = format!(
+        "HTTP/1.1 302 Found\r\nLocation: https://cdn.example.net/fw.bin\r\nContent-Length: 0\r\n\r\n{ok}"
+    );
+    let (mut client, _) = setup(&raw, 5);
+    let response = client.request(&post).unwrap();
+    assert_eq!(response.status_code, 302);
+    assert_eq!(
+        response.header("Location"),
+        Some("https://cdn.example.net/fw.bin")
+    );
+
+    // A loop stops at the limit with the last redirect response
+    let hop = "HTTP/1.1 301 Moved Permanently\r\nLocation: /upload\r\nContent-Length: 0\r\n\r\n";
+    let (mut client, written) = setup(&hop.repeat(4), 2);
+    assert_eq!(client.request(&post).unwrap().status_code, 301);
+    let sent = String::from_utf8(written.borrow().clone()).unwrap();
+    assert_eq!(sent.matches("POST /upload HTTP/1.1").count(), 3);
+
+    // Redirects are not followed by default
+    let raw = format!("HTTP/1.1 302 Found\r\nLocation: /v2\r\nContent-Length: 0\r\n\r\n{ok}");
+    let (mut client, _) = setup(&raw, 0);
+    assert_eq!(client.request(&post).unwrap().status_code, 302);
+
+    // Streaming requests discard the redirect body before following
+    let raw = format!(
+        "HTTP/1.1 302 Found\r\nLocation: /cdn/fw.bin\r\nTransfer-Encoding: chunked\r\n\r\n\
+         5\r\nmoved\r\n0\r\n\r\n{ok}"
+    );
+    let (mut client, written) = setup(&raw, 1);
+    let get = Request {
+        method: Method::Get,
+        path: "/fw.bin",
+        headers: host("example.com"),
+        body: None,
+    };
+    let mut response = client.request_streaming(&get).unwrap();
+    assert_eq!(response.status_code, 200);
+    assert_eq!(read_streamed_body(&mut response), b"done");
+    drop(response);
+    assert!(String::from_utf8_lossy(&written.borrow()).contains("GET /cdn/fw.bin HTTP/1.1"));
+}
+
+#[test]
+fn test_http_auth_headers() {
+    let basic = |user, password| Header::basic_auth(user, password).unwrap().value;
+
+    assert_eq!(
+        basic("Aladdin", "open sesame"),
+        "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+    );
+    assert_eq!(basic("user", "pass"), "Basic dXNlcjpwYXNz");
+    // Every padding length: ":" "f:" "fo:" "foo:"
+    assert_eq!(basic("", ""), "Basic Og==");
+    assert_eq!(basic("f", ""), "Basic Zjo=");
+    assert_eq!(basic("fo", ""), "Basic Zm86");
+    assert_eq!(basic("foo", ""), "Basic Zm9vOg==");
+    // Non-ASCII input is encoded as UTF-8 bytes
+    assert_eq!(basic("test", "123£"), "Basic dGVzdDoxMjPCow==");
+    // Characters from the top of the alphabet
+    assert_eq!(basic("\u{FBFF}", ""), "Basic 76+/Og==");
+
+    let header = Header::basic_auth("Aladdin", "open sesame").unwrap();
+    assert_eq!(header.name.as_str(), "Authorization");
+
+    // 185 bytes of credentials are the most that fit
+    assert!(Header::basic_auth(&"u".repeat(100), &"p".repeat(85)).is_ok());
+    assert_eq!(
+        Header::basic_auth(&"u".repeat(100), &"p".repeat(86)),
+        Err(libiot::network::error::Error::WriteError)
+    );
+
+    let header = Header::bearer("mF_9.B5f-4.1JqM").unwrap();
+    assert_eq!(header.name.as_str(), "Authorization");
+    assert_eq!(header.value.as_str(), "Bearer mF_9.B5f-4.1JqM");
+    assert!(Header::bearer(&"t".repeat(249)).is_ok());
+    assert_eq!(
+        Header::bearer(&"t".repeat(250)),
+        Err(libiot::network::error::Error::WriteError)
+    );
+}
+
+#[test]
+fn test_http_status_code_classes() {
+    // (code, informational, success, redirect, client error, server error)
+    let cases = [
+        (100, true, false, false, false, false),
+        (101, true, false, false, false, false),
+        (200, false, true, false, false, false),
+        (204, false, true, false, false, false),
+        (206, false, true, false, false, false),
+        (299, false, true, false, false, false),
+        (301, false, false, true, false, false),
+        (304, false, false, true, false, false),
+        (308, false, false, true, false, false),
+        (400, false, false, false, true, false),
+        (404, false, false, false, true, false),
+        (429, false, false, false, true, false),
+        (500, false, false, false, false, true),
+        (503, false, false, false, false, true),
+        (599, false, false, false, false, true),
+        (0, false, false, false, false, false),
+        (99, false, false, false, false, false),
+        (600, false, false, false, false, false),
+    ];
+    for (code, info, success, redirect, client, server) in cases {
+        let status = StatusCode::from(code);
+        assert_eq!(status.as_u16(), code);
+        assert_eq!(status.is_informational(), info, "{code}");
+        assert_eq!(status.is_success(), success, "{code}");
+        assert_eq!(status.is_redirect(), redirect, "{code}");
+        assert_eq!(status.is_client_error(), client, "{code}");
+        assert_eq!(status.is_server_error(), server, "{code}");
+    }
+
+    assert_eq!(StatusCode::OK.as_u16(), 200);
+    assert_eq!(StatusCode::PARTIAL_CONTENT, 206);
+    assert_eq!(StatusCode::NOT_FOUND, StatusCode::from(404));
+    assert_eq!(u16::from(StatusCode::SERVICE_UNAVAILABLE), 503);
+    assert_eq!(StatusCode::NOT_MODIFIED.to_string(), "304");
+
+    // The parsed response exposes both forms
+    let raw = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+    let mut client = Client::new(CannedConnection::new(raw));
+    let request = Request {
+        method: Method::Get,
+        path: "/missing",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+    let response = client.request(&request).unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.status().as_u16(), response.status_code);
+    assert!(response.status().is_client_error());
+}
+
+/// Connection that plays the server side of a keep-alive session: each
+/// flushed request releases the next canned response.
+#[derive(Default)]
+struct KeepAliveConnection {
+    responses: std::collections::VecDeque<&'static str>,
+    incoming: std::collections::VecDeque<u8>,
+    requests: std::rc::Rc<std::cell::RefCell<std::vec::Vec<String>>>,
+    pending_request: std::vec::Vec<u8>,
+}
+
+impl Read for KeepAliveConnection {
+    type Error = libiot::network::error::Error;
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = buf.len().min(self.incoming.len());
+        for (dst, src) in buf.iter_mut().zip(self.incoming.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for KeepAliveConnection {
+    type Error = libiot::network::error::Error;
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.pending_request.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        let request = std::mem::take(&mut self.pending_request);
+        self.requests
+            .borrow_mut()
+            .push(String::from_utf8(request).unwrap());
+        if let Some(response) = self.responses.pop_front() {
+            self.incoming.extend(response.bytes());
+        }
+        Ok(())
+    }
+}
+
+impl Close for KeepAliveConnection {
+    type Error = libiot::network::error::Error;
+    fn close(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Connection for KeepAliveConnection {}
+
+#[test]
+fn test_http_keep_alive() {
+    let conn = KeepAliveConnection {
+        responses: [
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nfirst",
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nsecond\r\n0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 4\r\n\r\nlast",
+        ]
+        .into(),
+        ..Default::default()
+    };
+    let requests = conn.requests.clone();
+    let mut client = Client::new(conn);
+    let get = |path| Request {
+        method: Method::Get,
+        path,
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+
+    // Two GETs over one connection, each response read to its end
+    let response = client.request(&get("/one")).unwrap();
+    assert_eq!(response.body.as_slice(), b"first");
+    assert!(client.is_reusable());
+    let response = client.request(&get("/two")).unwrap();
+    assert_eq!(response.body.as_slice(), b"second");
+    assert!(client.is_reusable());
+    for request in requests.borrow().iter() {
+        assert!(
+            request.contains("\r\nConnection: keep-alive\r\n"),
+            "{request}"
+        );
+    }
+
+    // A HEAD response has no body, whatever its Content-Length says
+    let head = Request {
+        method: Method::Custom("HEAD"),
+        ..get("/three")
+    };
+    let response = client.request(&head).unwrap();
+    assert!(response.body.is_empty());
+    assert_eq!(response.content_length(), Some(100));
+    assert!(client.is_reusable());
+
+    // The server closes the connection after the fourth response
+    let response = client.request(&get("/four")).unwrap();
+    assert_eq!(response.body.as_slice(), b"last");
+    assert!(!client.is_reusable());
+    assert_eq!(
+        client.request(&get("/five")),
+        Err(libiot::network::error::Error::ConnectionClosed)
+    );
+    assert_eq!(requests.borrow().len(), 4);
+}
+
+#[test]
+fn test_http_reuse_after_streaming() {
+    let conn = KeepAliveConnection {
+        responses: [
+            "HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nstream",
+            "HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\nok",
+        ]
+        .into(),
+        ..Default::default()
+    };
+    let mut client = Client::new(conn);
+    let get = Request {
+        method: Method::Get,
+        path: "/",
+        headers: heapless::Vec::new(),
+        body: None,
+    };
+
+    // A streamed body read to its end releases the connection
+    let mut response = client.request_streaming(&get).unwrap();
+    assert_eq!(read_streamed_body(&mut response), b"stream");
+    assert!(client.is_reusable());
+
+    // HTTP/1.0 closes by default
+    assert_eq!(client.request(&get).unwrap().body.as_slice(), b"ok");
+    assert!(!client.is_reusable());
+    // Until the transport has been reopened and the client reset
+    client.reset();
+    assert!(client.is_reusable());
+
+    // A streamed body abandoned part way leaves the stream position unknown
+    let conn = KeepAliveConnection {
+        responses: ["HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nstream"].into(),
+        ..Default::default()
+    };
+    let mut client = Client::new(conn);
+    let mut response = client.request_streaming(&get).unwrap();
+    assert_eq!(response.read_body_chunk(&mut [0; 3]), Ok(3));
+    drop(response);
+    assert!(!client.is_reusable());
+}
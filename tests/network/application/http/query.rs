@@ -0,0 +1,50 @@
+use libiot::network::application::http::query::QueryBuilder;
+use libiot::network::error::Error;
+
+#[test]
+fn test_query_encodes_reserved_characters() {
+    let mut query = QueryBuilder::<64>::new("/search").unwrap();
+    query.append("q", "a b&c=d").unwrap();
+    assert_eq!(query.as_str(), "/search?q=a%20b%26c%3Dd");
+
+    // Keys are encoded too, and later pairs are joined with `&`
+    query.append("sort by", "-date.~_").unwrap();
+    assert_eq!(query.as_str(), "/search?q=a%20b%26c%3Dd&sort%20by=-date.~_");
+
+    // An existing query is extended
+    let mut query = QueryBuilder::<64>::new("/v1?page=2").unwrap();
+    query.append("tag", "100%/+?#").unwrap();
+    assert_eq!(query.as_str(), "/v1?page=2&tag=100%25%2F%2B%3F%23");
+    assert_eq!(query.clone().into_string().as_str(), query.as_str());
+}
+
+#[test]
+fn test_query_encodes_utf8_bytes() {
+    let mut query = QueryBuilder::<64>::new("/city").unwrap();
+    query.append("name", "Zürich €").unwrap();
+    assert_eq!(query.as_str(), "/city?name=Z%C3%BCrich%20%E2%82%AC");
+
+    let mut query = QueryBuilder::<64>::new("").unwrap();
+    query.append("emoji", "🌡").unwrap();
+    assert_eq!(query.as_str(), "?emoji=%F0%9F%8C%A1");
+}
+
+#[test]
+fn test_query_overflow_is_an_error() {
+    assert_eq!(
+        QueryBuilder::<4>::new("/too-long").unwrap_err(),
+        Error::WriteError
+    );
+
+    // "/a?k=v" fits exactly; a pair that does not fit leaves the builder unchanged
+    let mut query = QueryBuilder::<6>::new("/a").unwrap();
+    query.append("k", "v").unwrap();
+    assert_eq!(query.as_str(), "/a?k=v");
+    assert_eq!(query.append("x", "").unwrap_err(), Error::WriteError);
+    assert_eq!(query.as_str(), "/a?k=v");
+
+    // Encoding expands a byte to three characters, which must all fit
+    let mut query = QueryBuilder::<6>::new("/").unwrap();
+    assert_eq!(query.append("k", " ").unwrap_err(), Error::WriteError);
+    assert_eq!(query.as_str(), "/");
+}
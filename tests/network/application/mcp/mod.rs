@@ -59,7 +59,7 @@ mod tests {
 
         let response = result.unwrap();
         assert!(response.is_some());
-        assert!(response.unwrap().contains("pong"));
+        assert!(response.unwrap().as_str().unwrap().contains("pong"));
     }
 
     #[test]
@@ -80,12 +80,85 @@ mod tests {
         assert!(response.error.is_some());
     }
 
+    #[test]
+    fn test_enable_disable_function() {
+        let mut registry = FunctionRegistry::new();
+        registry.register("ping", PingHandler).unwrap();
+
+        // Registration defaults to enabled
+        assert_eq!(registry.is_enabled("ping"), Some(true));
+
+        // Disabled functions are rejected without invoking the handler
+        registry.set_enabled("ping", false).unwrap();
+        assert_eq!(registry.is_enabled("ping"), Some(false));
+        let response = registry.execute("ping", "");
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert_eq!(response.error.as_deref(), Some("disabled"));
+        assert!(response.result.is_none());
+
+        // Re-enabling restores normal execution
+        registry.set_enabled("ping", true).unwrap();
+        let response = registry.execute("ping", "");
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert!(response.result.is_some());
+
+        // Unknown functions cannot be toggled
+        assert_eq!(
+            registry.set_enabled("unknown", false),
+            Err(McpError::FunctionNotFound)
+        );
+        assert_eq!(registry.is_enabled("unknown"), None);
+    }
+
+    #[test]
+    fn test_unregister_and_replace() {
+        let mut registry = FunctionRegistry::new();
+        for i in 0..MAX_FUNCTIONS {
+            registry
+                .register(&format!("ping_{i}"), PingHandler)
+                .unwrap();
+        }
+        assert!(registry.is_registered("ping_3"));
+        assert_eq!(
+            registry.register("ping_3", PingHandler),
+            Err(McpError::AlreadyRegistered)
+        );
+        assert_eq!(
+            registry.register("extra", PingHandler),
+            Err(McpError::BufferOverflow)
+        );
+
+        // Replacing needs no free slot and keeps the function's place
+        registry.set_enabled("ping_3", false).unwrap();
+        registry.register_or_replace("ping_3", PingHandler).unwrap();
+        assert_eq!(registry.is_enabled("ping_3"), Some(true));
+        assert_eq!(registry.list_names().nth(3), Some("ping_3"));
+        assert_eq!(
+            registry.register_or_replace("extra", PingHandler),
+            Err(McpError::BufferOverflow)
+        );
+
+        // Unregistering frees a slot and keeps the order of the rest
+        assert!(registry.unregister("ping_3"));
+        assert!(!registry.unregister("ping_3"));
+        assert!(!registry.is_registered("ping_3"));
+        assert_eq!(registry.list_names().nth(3), Some("ping_4"));
+        assert_eq!(
+            registry.execute("ping_3", "").status,
+            ResponseStatus::NotFound
+        );
+        registry.register("extra", PingHandler).unwrap();
+        assert_eq!(registry.execute("extra", "").status, ResponseStatus::Ok);
+    }
+
     #[test]
     fn test_response_serialization() {
         let response = McpResponse {
             status: ResponseStatus::Ok,
             error: None,
-            result: Some(heapless::String::try_from(r#"{"message":"test"}"#).unwrap()),
+            result: Some(HandlerValue::Json(
+                heapless::String::try_from(r#"{"message":"test"}"#).unwrap(),
+            )),
         };
 
         let mut buf = [0u8; 256];
@@ -98,6 +171,64 @@ mod tests {
         assert!(json_str.contains("\"result\""));
     }
 
+    struct ValueHandler;
+
+    impl McpHandler for ValueHandler {
+        fn call(&mut self, args: &str) -> HandlerResult {
+            Ok(match args {
+                "null" => Some(HandlerValue::Null),
+                "bool" => Some(true.into()),
+                "int" => Some((-42).into()),
+                "float" => Some(21.5f32.into()),
+                "str" => Some("a \"quoted\" word".into()),
+                _ => None,
+            })
+        }
+    }
+
+    #[test]
+    fn test_typed_result_serialization() {
+        let mut registry = FunctionRegistry::new();
+        registry.register("value", ValueHandler).unwrap();
+
+        let mut serialize = |args: &str| {
+            let response = registry.execute("value", args);
+            let mut buf = [0u8; 128];
+            let len = serde_json_core::to_slice(&response, &mut buf).unwrap();
+            std::string::String::from_utf8(buf[..len].to_vec()).unwrap()
+        };
+        assert_eq!(serialize("null"), r#"{"status":"ok","result":null}"#);
+        assert_eq!(serialize("bool"), r#"{"status":"ok","result":true}"#);
+        assert_eq!(serialize("int"), r#"{"status":"ok","result":-42}"#);
+        assert_eq!(serialize("float"), r#"{"status":"ok","result":21.5}"#);
+        assert_eq!(
+            serialize("str"),
+            r#"{"status":"ok","result":"a \"quoted\" word"}"#
+        );
+        assert_eq!(serialize("none"), r#"{"status":"ok"}"#);
+
+        // Built-in handlers return JSON objects rather than escaped strings
+        let connection = MockConnection::new(b"{\"function\": \"ping\", \"arguments\": \"{}\"}");
+        let mut ping_registry = FunctionRegistry::new();
+        ping_registry.register("ping", PingHandler).unwrap();
+        let mut client = McpClient::new(connection, ping_registry);
+        client.process_message().unwrap();
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert_eq!(written, r#"{"status":"ok","result":{"message":"pong"}}"#);
+    }
+
+    #[test]
+    fn test_handler_value_from_long_str_truncates() {
+        let long = "é".repeat(MAX_RESPONSE_LEN);
+        match HandlerValue::from(long.as_str()) {
+            HandlerValue::Str(s) => {
+                assert!(s.len() <= MAX_RESPONSE_LEN);
+                assert!(long.starts_with(s.as_str()));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
     #[test]
     fn test_macro_registration() {
         let mut gpio_registry = FunctionRegistry::new();
@@ -173,4 +304,428 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_back_to_back_messages() {
+        let mut registry = FunctionRegistry::new();
+        registry.register("ping", PingHandler).unwrap();
+
+        // Everything arrives in a single read
+        let connection = MockConnection::new(
+            b"{\"function\": \"ping\", \"arguments\": \"{}\"}\
+              {\"function\": \"missing\", \"arguments\": \"{}\"}\
+              {\"function\": \"ping\", \"argu",
+        );
+        let mut client = McpClient::new(connection, registry);
+
+        client.process_message().unwrap();
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert_eq!(written, r#"{"status":"ok","result":{"message":"pong"}}"#);
+
+        client.process_message().unwrap();
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert!(written.ends_with(r#"{"status":"notfound","error":"Function not found"}"#));
+
+        // The partial message stays buffered and is not answered
+        let len = client.connection().written_data().len();
+        client.process_message().unwrap();
+        assert_eq!(client.connection().written_data().len(), len);
+    }
+
+    #[test]
+    fn test_line_framing() {
+        let mut registry = FunctionRegistry::new();
+        registry.register("ping", PingHandler).unwrap();
+
+        let connection = MockConnection::new(
+            b"}{{\"function\": \"ping\"\r\n\
+              \n\
+              {\"function\": \"ping\", \"arguments\": \"{}\"}\r\n\
+              {\"jsonrpc\": \"2.0\", \"id\": 9, \"method\": \"ping\"}\n\
+              {\"function\": \"ping\", \"arguments\": \"{}\"}",
+        );
+        let mut client = McpClient::new(connection, registry).with_framing(Framing::Lines);
+
+        // The malformed line is rejected on its own
+        client.process_message().unwrap();
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert_eq!(written, r#"{"status":"error","error":"JSON parse error"}"#);
+
+        client.process_message().unwrap();
+        client.process_message().unwrap();
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert!(written.ends_with(
+            r#"{"status":"ok","result":{"message":"pong"}}{"jsonrpc":"2.0","id":9,"result":{"message":"pong"}}"#
+        ));
+
+        // The last message has no newline yet
+        let len = client.connection().written_data().len();
+        client.process_message().unwrap();
+        assert_eq!(client.connection().written_data().len(), len);
+    }
+
+    #[test]
+    fn test_initialize_handshake() {
+        let mut registry = FunctionRegistry::new();
+        registry.register("ping", PingHandler).unwrap();
+
+        let connection =
+            MockConnection::new(b"{\"function\": \"initialize\", \"arguments\": \"{}\"}");
+        let mut client =
+            McpClient::new(connection, registry).with_handshake(Capabilities { streaming: true });
+        assert!(!client.is_initialized());
+
+        client.process_message().unwrap();
+        assert!(client.is_initialized());
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert_eq!(
+            written,
+            r#"{"status":"ok","protocolVersion":"2024-11-05","capabilities":{"streaming":true}}"#
+        );
+    }
+
+    #[test]
+    fn test_function_call_without_handshake() {
+        let mut registry = FunctionRegistry::new();
+        registry.register("ping", PingHandler).unwrap();
+
+        // Handshake enabled, but bare function calls still work
+        let connection = MockConnection::new(b"{\"function\": \"ping\", \"arguments\": \"{}\"}");
+        let mut client =
+            McpClient::new(connection, registry).with_handshake(Capabilities::default());
+        client.process_message().unwrap();
+        assert!(!client.is_initialized());
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert!(written.contains("pong"));
+    }
+
+    #[test]
+    fn test_initialize_without_handshake_is_a_function_call() {
+        let registry: FunctionRegistry<PingHandler> = FunctionRegistry::new();
+        let connection =
+            MockConnection::new(b"{\"function\": \"initialize\", \"arguments\": \"{}\"}");
+        let mut client = McpClient::new(connection, registry);
+        client.process_message().unwrap();
+        assert!(!client.is_initialized());
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert!(written.contains("\"status\":\"notfound\""));
+    }
+
+    #[test]
+    fn test_typed_handler() {
+        #[derive(serde::Deserialize)]
+        struct SetPin {
+            pin: u8,
+            state: bool,
+        }
+
+        let mut calls = Vec::new();
+        let mut registry = FunctionRegistry::new();
+        registry
+            .register(
+                "set_pin",
+                TypedHandler::new(|args: SetPin| {
+                    calls.push((args.pin, args.state));
+                    Ok(Some(u32::from(args.pin).into()))
+                })
+                .with_description("Drive a GPIO pin"),
+            )
+            .unwrap();
+        assert!(registry.tools().eq([("set_pin", "Drive a GPIO pin")]));
+
+        let response = registry.execute("set_pin", r#"{"pin": 13, "state": true}"#);
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert_eq!(response.result, Some(HandlerValue::Int(13)));
+
+        // Wrong types and missing fields never reach the closure
+        for args in [
+            r#"{"pin": 300, "state": true}"#,
+            r#"{"pin": 13}"#,
+            "",
+            "not json",
+        ] {
+            let response = registry.execute("set_pin", args);
+            assert_eq!(response.status, ResponseStatus::InvalidArgs, "{args}");
+        }
+
+        drop(registry);
+        assert_eq!(calls, [(13, true)]);
+    }
+
+    #[test]
+    fn test_typed_handler_unescapes_strings() {
+        #[derive(serde::Deserialize)]
+        struct Label {
+            text: heapless::String<16>,
+            color: Option<u32>,
+        }
+
+        let mut registry = FunctionRegistry::new();
+        registry
+            .register(
+                "label",
+                TypedHandler::new(|args: Label| {
+                    assert_eq!(args.color, None);
+                    Ok(Some(args.text.as_str().into()))
+                }),
+            )
+            .unwrap();
+
+        let response = registry.execute("label", r#"{"text": "say \"hi\""}"#);
+        assert_eq!(response.result, Some(HandlerValue::from("say \"hi\"")));
+
+        let response = registry.execute("label", r#"{"text": "far too long for sixteen bytes"}"#);
+        assert_eq!(response.status, ResponseStatus::InvalidArgs);
+    }
+
+    #[test]
+    fn test_tools_list() {
+        struct Described(&'static str);
+
+        impl McpHandler for Described {
+            fn description(&self) -> &str {
+                self.0
+            }
+
+            fn call(&mut self, _args: &str) -> HandlerResult {
+                Ok(None)
+            }
+        }
+
+        let mut registry = FunctionRegistry::new();
+        registry
+            .register("ping", Described("Check that the \"device\" is up"))
+            .unwrap();
+        registry.register("value", Described("")).unwrap();
+        registry.register("gpio", Described("Drive a pin")).unwrap();
+        registry.set_enabled("gpio", false).unwrap();
+        assert_eq!(
+            registry.list_names().collect::<Vec<_>>(),
+            ["ping", "value", "gpio"]
+        );
+
+        // Listing needs no handshake, and disabled functions are left out
+        let connection =
+            MockConnection::new(b"{\"function\": \"tools/list\", \"arguments\": \"\"}");
+        let mut client =
+            McpClient::new(connection, registry).with_handshake(Capabilities::default());
+        client.process_message().unwrap();
+        assert!(!client.is_initialized());
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert_eq!(
+            written,
+            r#"{"status":"ok","tools":[{"name":"ping","description":"Check that the \"device\" is up"},{"name":"value"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_jsonrpc_request() {
+        fn answer(request: &'static [u8]) -> String {
+            let mut registry = FunctionRegistry::new();
+            registry.register("ping", PingHandler).unwrap();
+            let mut client = McpClient::new(MockConnection::new(request), registry)
+                .with_handshake(Capabilities::default());
+            client.process_message().unwrap();
+            String::from_utf8(client.connection().written_data().to_vec()).unwrap()
+        }
+
+        assert_eq!(
+            answer(br#"{"jsonrpc":"2.0","id":1,"method":"ping","params":{}}"#),
+            r#"{"jsonrpc":"2.0","id":1,"result":{"message":"pong"}}"#
+        );
+        assert_eq!(
+            answer(br#"{"jsonrpc": "2.0", "method": "missing", "id": "req-2"}"#),
+            r#"{"jsonrpc":"2.0","id":"req-2","error":{"code":-32601,"message":"Function not found"}}"#
+        );
+        assert_eq!(
+            answer(br#"{"jsonrpc":"2.0","id":3,"method":"initialize","params":{}}"#),
+            r#"{"jsonrpc":"2.0","id":3,"result":{"protocolVersion":"2024-11-05","capabilities":{"streaming":false}}}"#
+        );
+        assert_eq!(
+            answer(br#"{"jsonrpc":"2.0","id":4,"method":"tools/list"}"#),
+            r#"{"jsonrpc":"2.0","id":4,"result":{"tools":[{"name":"ping","description":"Check that the device is responsive"}]}}"#
+        );
+        assert_eq!(
+            answer(br#"{"jsonrpc":"1.0","id":5,"method":"ping"}"#),
+            r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"Invalid request"}}"#
+        );
+        assert_eq!(
+            answer(br#"{"jsonrpc":"2.0","id":6,"method":}"#),
+            r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"Parse error"}}"#
+        );
+
+        // Notifications are executed but not answered
+        assert_eq!(answer(br#"{"jsonrpc":"2.0","method":"ping"}"#), "");
+    }
+
+    #[test]
+    fn test_jsonrpc_params_reach_handler() {
+        struct Echo;
+
+        impl McpHandler for Echo {
+            fn call(&mut self, args: &str) -> HandlerResult {
+                Ok(Some(args.into()))
+            }
+        }
+
+        let mut registry = FunctionRegistry::new();
+        registry.register("echo", Echo).unwrap();
+        let connection = MockConnection::new(
+            br#"{"jsonrpc":"2.0","id":1,"method":"echo","params":{"pin": 13, "state": true}}"#,
+        );
+        let mut client = McpClient::new(connection, registry);
+        client.process_message().unwrap();
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert_eq!(
+            written,
+            r#"{"jsonrpc":"2.0","id":1,"result":"{\"pin\": 13, \"state\": true}"}"#
+        );
+    }
+
+    #[test]
+    fn test_tools_list_pagination() {
+        struct Described;
+
+        impl McpHandler for Described {
+            fn description(&self) -> &str {
+                "A function with a description long enough to need several pages"
+            }
+
+            fn call(&mut self, _args: &str) -> HandlerResult {
+                Ok(None)
+            }
+        }
+
+        fn list(cursor: &str) -> String {
+            let mut registry = FunctionRegistry::new();
+            for i in 0..MAX_FUNCTIONS {
+                registry
+                    .register(&format!("function_{i}"), Described)
+                    .unwrap();
+            }
+            let message = format!(r#"{{"function": "tools/list", "arguments": "{cursor}"}}"#);
+            let connection =
+                MockConnection::new(Box::leak(message.into_bytes().into_boxed_slice()));
+            let mut client = McpClient::new(connection, registry);
+            client.process_message().unwrap();
+            String::from_utf8(client.connection().written_data().to_vec()).unwrap()
+        }
+
+        // Follow the cursors until every function has been listed once
+        let mut cursor = String::new();
+        let mut listed = 0;
+        let mut pages = 0;
+        loop {
+            let page = list(&cursor);
+            pages += 1;
+            listed += page.matches("\"name\"").count();
+            match page.split_once(r#""nextCursor":""#) {
+                Some((_, rest)) => cursor = rest.split('"').next().unwrap().to_string(),
+                None => break,
+            }
+        }
+        assert_eq!(listed, MAX_FUNCTIONS);
+        assert!(pages > 1);
+
+        let page = list("x");
+        assert!(page.contains(r#""status":"invalidargs""#));
+    }
+
+    /// Advances by 10 ms on every reading
+    struct StepClock(core::cell::Cell<u64>);
+
+    impl libiot::system::clock::MonotonicClock for StepClock {
+        fn now_ms(&self) -> u64 {
+            self.0.set(self.0.get() + 10);
+            self.0.get()
+        }
+    }
+
+    static KICKS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    fn kick() {
+        KICKS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Polls `polls` times with a checkpoint each time, then answers
+    struct PollingHandler {
+        polls: usize,
+    }
+
+    impl McpHandler for PollingHandler {
+        fn call(&mut self, _args: &str) -> HandlerResult {
+            Ok(Some(true.into()))
+        }
+
+        fn call_with_deadline(&mut self, args: &str, deadline: &Deadline<'_>) -> HandlerResult {
+            for _ in 0..self.polls {
+                deadline.check()?;
+            }
+            self.call(args)
+        }
+    }
+
+    #[test]
+    fn test_handler_watchdog() {
+        let clock = StepClock(core::cell::Cell::new(0));
+        let watchdog = Watchdog::new(&clock, 100).with_kick(kick);
+        let mut registry = FunctionRegistry::new();
+        registry
+            .register("quick", PollingHandler { polls: 2 })
+            .unwrap();
+        registry
+            .register("stuck", PollingHandler { polls: usize::MAX })
+            .unwrap();
+
+        // Within the limit the result comes through, kicking at each checkpoint
+        let response = registry.execute_with_deadline("quick", "", &watchdog.start());
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert_eq!(KICKS.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+        // A looping handler is stopped at its first checkpoint past the limit
+        let response = registry.execute_with_deadline("stuck", "", &watchdog.start());
+        assert_eq!(response.status, ResponseStatus::Error);
+        assert_eq!(response.error.as_deref(), Some("timeout"));
+        assert!(response.result.is_none());
+
+        // Without a watchdog the plain `call` runs
+        assert_eq!(registry.execute("stuck", "").status, ResponseStatus::Ok);
+
+        // Handlers without checkpoints are judged when they return
+        let slow = Watchdog::new(&clock, 5);
+        let mut pings = FunctionRegistry::new();
+        pings.register("ping", PingHandler).unwrap();
+        let deadline = slow.start();
+        assert_eq!(deadline.remaining_ms(), 0);
+        let response = pings.execute_with_deadline("ping", "", &deadline);
+        assert_eq!(response.error.as_deref(), Some("timeout"));
+
+        // The client applies a fresh deadline to each call
+        let connection = MockConnection::new(b"{\"function\": \"stuck\", \"arguments\": \"{}\"}");
+        let mut client = McpClient::new(connection, registry);
+        client.process_message_with_watchdog(&watchdog).unwrap();
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert_eq!(written, r#"{"status":"error","error":"timeout"}"#);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_dyn_function_registry() {
+        let mut registry = DynFunctionRegistry::new();
+        registry.register("ping", Box::new(PingHandler)).unwrap();
+        registry.register("value", Box::new(ValueHandler)).unwrap();
+
+        let response = registry.execute("ping", "");
+        assert_eq!(response.status, ResponseStatus::Ok);
+        assert!(response.result.is_some());
+        let response = registry.execute("value", "bool");
+        assert_eq!(response.result, Some(true.into()));
+
+        // A boxed registry drives the client like any other
+        let connection = MockConnection::new(b"{\"function\": \"value\", \"arguments\": \"int\"}");
+        let mut client = McpClient::new(connection, registry);
+        client.process_message().unwrap();
+        let written = core::str::from_utf8(client.connection().written_data()).unwrap();
+        assert_eq!(written, r#"{"status":"ok","result":-42}"#);
+    }
 }
@@ -1,3 +1,5 @@
+pub mod coap;
 pub mod http;
 pub mod mcp;
 pub mod mqtt;
+pub mod websocket;
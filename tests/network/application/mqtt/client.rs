@@ -60,6 +60,7 @@ fn test_connect_to_public_broker() {
         client_id: "libiot-test-client-12345",
         keep_alive_seconds: 10,
         clean_session: true,
+        max_packet_size: None,
     };
 
     let client = Client::connect(conn, opts);
@@ -80,6 +81,7 @@ fn test_publish_and_subscribe() {
         client_id: "libiot-test-client-67890",
         keep_alive_seconds: 10,
         clean_session: true,
+        max_packet_size: None,
     };
 
     let mut client = Client::connect(conn, opts).expect("Failed to connect");
@@ -102,3 +104,798 @@ fn test_publish_and_subscribe() {
     assert_eq!(publish_packet.topic.as_str(), topic);
     assert_eq!(publish_packet.payload, payload);
 }
+
+#[test]
+fn test_from_connected_skips_handshake() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::QoS;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+    assert!(client.is_connected());
+
+    // No CONNECT packet is sent
+    assert!(conn.take_written().is_empty());
+
+    client.publish("a/b", b"hi", QoS::AtMostOnce).unwrap();
+    assert_eq!(
+        conn.take_written(),
+        [0x30, 0x07, 0x00, 0x03, b'a', b'/', b'b', b'h', b'i']
+    );
+
+    // Incoming messages are received without a CONNACK having been read
+    conn.push_incoming(&[0x30, 0x05, 0x00, 0x01, b'x', b'o', b'k']);
+    let packet = client.poll().unwrap().unwrap();
+    assert_eq!(packet.topic.as_str(), "x");
+    assert_eq!(&packet.payload[..], b"ok");
+}
+
+#[test]
+fn test_zero_length_read_marks_disconnected() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::QoS;
+    use libiot::network::error::Error;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    // The accessors hand out the same connection the client writes to
+    client
+        .connection_mut()
+        .push_incoming(&[0x30, 0x03, 0x00, 0x01, b'x']);
+    assert!(client.poll().unwrap().is_some());
+    client.connection().push_incoming(&[0x30]);
+
+    // The peer closes in the middle of a packet: the next read returns 0
+    assert_eq!(client.poll().unwrap_err(), Error::ConnectionClosed);
+    assert!(!client.is_connected());
+
+    // The same holds for a read while waiting on an acknowledgement
+    let mut client = Client::from_connected(conn.clone());
+    assert_eq!(
+        client.publish("a", b"", QoS::AtLeastOnce).unwrap_err(),
+        Error::ConnectionClosed
+    );
+    assert!(!client.is_connected());
+}
+
+#[test]
+fn test_inbound_qos2_exchange() {
+    use super::mock::ScriptedConnection;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    // PUBLISH, QoS 2, DUP clear, topic "cmd", packet id 0x1234, payload "go"
+    let publish = [
+        0x34, 0x09, 0x00, 0x03, b'c', b'm', b'd', 0x12, 0x34, b'g', b'o',
+    ];
+    conn.push_incoming(&publish);
+    assert_eq!(client.poll().unwrap(), None);
+    assert_eq!(conn.take_written(), [0x50, 0x02, 0x12, 0x34]);
+
+    // A retransmission (DUP set) is acknowledged again but not duplicated
+    let mut duplicate = publish;
+    duplicate[0] |= 0x08;
+    conn.push_incoming(&duplicate);
+    assert_eq!(client.poll().unwrap(), None);
+    assert_eq!(conn.take_written(), [0x50, 0x02, 0x12, 0x34]);
+
+    // PUBREL releases the message exactly once
+    conn.push_incoming(&[0x62, 0x02, 0x12, 0x34]);
+    let packet = client.poll().unwrap().unwrap();
+    assert_eq!(packet.topic.as_str(), "cmd");
+    assert_eq!(&packet.payload[..], b"go");
+    assert_eq!(conn.take_written(), [0x70, 0x02, 0x12, 0x34]);
+
+    // A repeated PUBREL is completed again without redelivering
+    conn.push_incoming(&[0x62, 0x02, 0x12, 0x34]);
+    assert_eq!(client.poll().unwrap(), None);
+    assert_eq!(conn.take_written(), [0x70, 0x02, 0x12, 0x34]);
+}
+
+#[test]
+fn test_poll_ref_borrows_message() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::PublishRef;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    conn.push_incoming(&[0x30, 0x07, 0x00, 0x03, b'a', b'/', b'b', b'h', b'i']);
+    let message = client.poll_ref().unwrap().unwrap();
+    assert_eq!(
+        message,
+        PublishRef {
+            topic: "a/b",
+            payload: b"hi"
+        }
+    );
+
+    // QoS 2 messages are lent from the pending slot on PUBREL, then freed
+    conn.push_incoming(&[0x34, 0x08, 0x00, 0x01, b'q', 0x00, 0x07, b'o', b'n', b'e']);
+    assert_eq!(client.poll_ref().unwrap(), None);
+    conn.push_incoming(&[0x62, 0x02, 0x00, 0x07]);
+    let message = client.poll_ref().unwrap().unwrap();
+    assert_eq!(message.topic, "q");
+    assert_eq!(message.payload, b"one");
+
+    conn.push_incoming(&[0x62, 0x02, 0x00, 0x07]);
+    assert_eq!(client.poll_ref().unwrap(), None);
+    conn.take_written();
+
+    // Non-PUBLISH packets are consumed whole and ignored
+    conn.push_incoming(&[0xD0, 0x00]);
+    assert_eq!(client.poll_ref().unwrap(), None);
+    conn.push_incoming(&[0x30, 0x04, 0x00, 0x01, b'z', b'!']);
+    let packet = client.poll().unwrap().unwrap();
+    assert_eq!(packet.topic.as_str(), "z");
+    assert_eq!(&packet.payload[..], b"!");
+}
+
+#[test]
+fn test_publish_qos1_waits_for_puback() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::QoS;
+    use libiot::network::error::Error;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    conn.push_incoming(&[0x40, 0x02, 0x00, 0x01]);
+    client.publish("t", b"hi", QoS::AtLeastOnce).unwrap();
+    assert_eq!(
+        conn.take_written(),
+        [0x32, 0x07, 0x00, 0x01, b't', 0x00, 0x01, b'h', b'i']
+    );
+
+    // QoS 0 carries no identifier and does not consume one
+    client.publish("t", b"hi", QoS::AtMostOnce).unwrap();
+    assert_eq!(
+        conn.take_written(),
+        [0x30, 0x05, 0x00, 0x01, b't', b'h', b'i']
+    );
+
+    // A PUBACK for another identifier is a protocol error
+    conn.push_incoming(&[0x40, 0x02, 0x00, 0x07]);
+    assert_eq!(
+        client.publish("t", b"hi", QoS::AtLeastOnce),
+        Err(Error::ProtocolError)
+    );
+    assert_eq!(&conn.take_written()[5..7], [0x00, 0x02]);
+
+    // So is any other packet type
+    conn.push_incoming(&[0x50, 0x02, 0x00, 0x03]);
+    assert_eq!(
+        client.publish("t", b"hi", QoS::AtLeastOnce),
+        Err(Error::ProtocolError)
+    );
+
+    // No PUBACK at all means the broker went away
+    assert_eq!(
+        client.publish("t", b"hi", QoS::AtLeastOnce),
+        Err(Error::ConnectionClosed)
+    );
+    assert!(!client.is_connected());
+}
+
+#[test]
+fn test_ping_and_keep_alive() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::error::Error;
+
+    let conn = ScriptedConnection::new();
+    conn.push_incoming(&[0x20, 0x02, 0x00, 0x00]);
+    let opts = Options {
+        client_id: "pinger",
+        keep_alive_seconds: 30,
+        clean_session: true,
+        max_packet_size: None,
+    };
+    let mut client = Client::connect(conn.clone(), opts).unwrap();
+    assert_eq!(client.keep_alive_seconds(), 30);
+    conn.take_written();
+
+    conn.push_incoming(&[0xD0, 0x00]);
+    client.ping().unwrap();
+    assert_eq!(conn.take_written(), [0xC0, 0x00]);
+
+    // Anything but a PINGRESP is rejected
+    conn.push_incoming(&[0xD0, 0x01]);
+    assert_eq!(client.ping(), Err(Error::ProtocolError));
+    conn.push_incoming(&[0x01]);
+    assert_eq!(client.ping(), Err(Error::ConnectionClosed));
+    assert!(!client.is_connected());
+
+    assert_eq!(Client::from_connected(conn).keep_alive_seconds(), 0);
+}
+
+#[test]
+fn test_disconnect_returns_connection() {
+    use super::mock::ScriptedConnection;
+
+    let conn = ScriptedConnection::new();
+    let client = Client::from_connected(conn.clone());
+    let returned = client.disconnect().unwrap();
+    assert_eq!(conn.take_written(), [0xE0, 0x00]);
+
+    // The same connection comes back and can carry a new session
+    conn.push_incoming(&[0x20, 0x02, 0x00, 0x00]);
+    let opts = Options {
+        client_id: "sleeper",
+        keep_alive_seconds: 0,
+        clean_session: true,
+        max_packet_size: None,
+    };
+    let mut client = Client::connect(returned, opts).unwrap();
+    assert_eq!(conn.take_written()[0], 0x10);
+
+    // A session that is already gone is not ended again
+    assert!(client.poll().is_err());
+    client.disconnect().unwrap();
+    assert!(conn.take_written().is_empty());
+}
+
+#[test]
+fn test_unsubscribe_uses_shared_packet_ids() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::QoS;
+    use libiot::network::error::Error;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    conn.push_incoming(&[0x40, 0x02, 0x00, 0x01]);
+    client.publish("t", b"", QoS::AtLeastOnce).unwrap();
+    conn.take_written();
+
+    conn.push_incoming(&[0xB0, 0x02, 0x00, 0x02]);
+    client.unsubscribe("t").unwrap();
+    assert_eq!(
+        conn.take_written(),
+        [0xA2, 0x05, 0x00, 0x02, 0x00, 0x01, b't']
+    );
+
+    // The UNSUBACK must carry the UNSUBSCRIBE's identifier
+    conn.push_incoming(&[0xB0, 0x02, 0x00, 0x02]);
+    assert_eq!(client.unsubscribe("t"), Err(Error::ProtocolError));
+    // And be an UNSUBACK
+    conn.push_incoming(&[0x90, 0x02, 0x00, 0x04]);
+    assert_eq!(client.unsubscribe("t"), Err(Error::ProtocolError));
+}
+
+#[test]
+fn test_publish_retained_sets_retain_flag() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::QoS;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    client
+        .publish_retained("s", b"on", QoS::AtMostOnce)
+        .unwrap();
+    assert_eq!(
+        conn.take_written(),
+        [0x31, 0x05, 0x00, 0x01, b's', b'o', b'n']
+    );
+
+    // QoS bits sit above the retain bit
+    conn.push_incoming(&[0x40, 0x02, 0x00, 0x01]);
+    client
+        .publish_retained("s", b"on", QoS::AtLeastOnce)
+        .unwrap();
+    assert_eq!(conn.take_written()[0], 0x33);
+    conn.push_incoming(&[0x50, 0x02, 0x00, 0x02, 0x70, 0x02, 0x00, 0x02]);
+    client
+        .publish_retained("s", b"on", QoS::ExactlyOnce)
+        .unwrap();
+    assert_eq!(conn.take_written()[0], 0x35);
+
+    // Plain publish leaves it clear
+    client.publish("s", b"on", QoS::AtMostOnce).unwrap();
+    assert_eq!(conn.take_written()[0], 0x30);
+}
+
+#[test]
+fn test_poll_acknowledges_qos1_publish() {
+    use super::mock::ScriptedConnection;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    // QoS 1 PUBLISH on "t" with packet identifier 0x1234
+    conn.push_incoming(&[0x32, 0x07, 0x00, 0x01, b't', 0x12, 0x34, b'h', b'i']);
+    let packet = client.poll().unwrap().unwrap();
+    assert_eq!(packet.topic.as_str(), "t");
+    assert_eq!(&packet.payload[..], b"hi");
+    assert_eq!(conn.take_written(), [0x40, 0x02, 0x12, 0x34]);
+
+    // QoS 0 messages are not acknowledged
+    conn.push_incoming(&[0x30, 0x05, 0x00, 0x01, b't', b'h', b'i']);
+    assert_eq!(&client.poll().unwrap().unwrap().payload[..], b"hi");
+    assert!(conn.take_written().is_empty());
+}
+
+#[test]
+fn test_client_buffer_sizes() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::{PublishPacket, QoS};
+    use libiot::network::error::Error;
+
+    // A tiny sensor: 64-byte packets, 16-byte topics
+    let conn = ScriptedConnection::new();
+    let mut small = Client::<_, 64, 16>::from_connected_with_buffers(conn.clone());
+
+    small.publish("s/t", &[7; 40], QoS::AtMostOnce).unwrap();
+    let written = conn.take_written();
+    conn.push_incoming(&written);
+    let packet: PublishPacket<64, 16> = small.poll().unwrap().unwrap();
+    assert_eq!(packet.topic.as_str(), "s/t");
+    assert_eq!(&packet.payload[..], [7; 40]);
+
+    // Too big for the publish buffer: an error, not a panic
+    assert_eq!(
+        small.publish("s/t", &[0; 64], QoS::AtMostOnce),
+        Err(Error::ProtocolError)
+    );
+    // A topic longer than 16 bytes does not fit a received packet
+    conn.push_incoming(&[0x30, 0x13, 0x00, 0x11]);
+    conn.push_incoming(&[b'x'; 17]);
+    assert_eq!(small.poll(), Err(Error::ProtocolError));
+    // Nor can such a filter be unsubscribed
+    conn.take_written();
+    assert_eq!(
+        small.unsubscribe(&"x".repeat(17)),
+        Err(Error::ProtocolError)
+    );
+    assert!(conn.take_written().is_empty());
+    conn.push_incoming(&[0xB0, 0x02, 0x00, 0x01]);
+    small.unsubscribe(&"x".repeat(16)).unwrap();
+    assert_eq!(
+        conn.take_written()[..6],
+        [0xA2, 0x14, 0x00, 0x01, 0x00, 0x10]
+    );
+
+    // A gateway: 4096-byte packets, 512-byte topics
+    let conn = ScriptedConnection::new();
+    let mut large = Client::<_, 4096, 512>::from_connected_with_buffers(conn.clone());
+
+    let topic = "g/".repeat(200);
+    let payload: Vec<u8> = (0..3000).map(|i| i as u8).collect();
+    conn.push_incoming(&[0x40, 0x02, 0x00, 0x01]);
+    large.publish(&topic, &payload, QoS::AtLeastOnce).unwrap();
+    let written = conn.take_written();
+    assert_eq!(written[0], 0x32);
+
+    // Echo it back as an inbound QoS 1 message
+    conn.push_incoming(&written);
+    let packet = large.poll().unwrap().unwrap();
+    assert_eq!(packet.topic.as_str(), topic);
+    assert_eq!(&packet.payload[..], &payload[..]);
+    assert_eq!(conn.take_written(), [0x40, 0x02, 0x00, 0x01]);
+}
+
+#[test]
+fn test_publish_qos2_exchange() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::QoS;
+    use libiot::network::error::Error;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    // PUBLISH -> PUBREC, PUBREL -> PUBCOMP
+    conn.push_incoming(&[0x50, 0x02, 0x00, 0x01]);
+    conn.push_incoming(&[0x70, 0x02, 0x00, 0x01]);
+    client.publish("t", b"hi", QoS::ExactlyOnce).unwrap();
+    assert_eq!(
+        conn.take_written(),
+        [
+            0x34, 0x07, 0x00, 0x01, b't', 0x00, 0x01, b'h', b'i', // PUBLISH
+            0x62, 0x02, 0x00, 0x01, // PUBREL
+        ]
+    );
+
+    // Identifiers are shared with QoS 1
+    conn.push_incoming(&[0x40, 0x02, 0x00, 0x02]);
+    client.publish("t", b"hi", QoS::AtLeastOnce).unwrap();
+    conn.take_written();
+
+    // A PUBACK where the PUBREC belongs is rejected before any PUBREL
+    conn.push_incoming(&[0x40, 0x02, 0x00, 0x03]);
+    assert_eq!(
+        client.publish("t", b"hi", QoS::ExactlyOnce),
+        Err(Error::ProtocolError)
+    );
+    assert_eq!(conn.take_written().len(), 9);
+
+    // So is a PUBCOMP for another identifier
+    conn.push_incoming(&[0x50, 0x02, 0x00, 0x04]);
+    conn.push_incoming(&[0x70, 0x02, 0x00, 0x09]);
+    assert_eq!(
+        client.publish_chunks("t", [&b"hi"[..]].into_iter(), QoS::ExactlyOnce),
+        Err(Error::ProtocolError)
+    );
+    assert_eq!(&conn.take_written()[9..], [0x62, 0x02, 0x00, 0x04]);
+}
+
+#[test]
+fn test_publish_packet_id_wraps_past_zero() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::QoS;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    for id in 1..=u16::MAX {
+        conn.push_incoming(&[0x40, 0x02]);
+        conn.push_incoming(&id.to_be_bytes());
+        client.publish("t", b"", QoS::AtLeastOnce).unwrap();
+    }
+    conn.take_written();
+
+    conn.push_incoming(&[0x40, 0x02, 0x00, 0x01]);
+    client.publish("t", b"", QoS::AtLeastOnce).unwrap();
+    assert_eq!(&conn.take_written()[5..7], [0x00, 0x01]);
+}
+
+#[test]
+fn test_publish_chunks_matches_publish() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::QoS;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    let payload = [b'x'; 200];
+    conn.push_incoming(&[0x40, 0x02, 0x00, 0x01]);
+    client.publish("a/b", &payload, QoS::AtLeastOnce).unwrap();
+    let mut expected = conn.take_written();
+
+    // Two remaining-length bytes, computed from all chunks up front
+    let chunks: [&[u8]; 3] = [&payload[..50], &[], &payload[50..]];
+    conn.push_incoming(&[0x40, 0x02, 0x00, 0x02]);
+    client
+        .publish_chunks("a/b", chunks.iter().copied(), QoS::AtLeastOnce)
+        .unwrap();
+    let written = conn.take_written();
+    assert_eq!(&written[..3], [0x32, 0xCF, 0x01]);
+    // Identical apart from the packet identifier after the topic
+    assert_eq!(&written[8..10], [0x00, 0x02]);
+    expected[9] = 0x02;
+    assert_eq!(written, expected);
+
+    // An empty iterator publishes an empty payload
+    client
+        .publish_chunks("t", core::iter::empty(), QoS::AtMostOnce)
+        .unwrap();
+    assert_eq!(conn.take_written(), [0x30, 0x03, 0x00, 0x01, b't']);
+}
+
+#[test]
+fn test_remaining_length_round_trip() {
+    use libiot::network::application::mqtt::client::{
+        MAX_REMAINING_LENGTH, decode_remaining_length, encode_remaining_length,
+    };
+
+    fn check(len: usize) {
+        let encoded = encode_remaining_length(len).unwrap();
+        let expected_size = match len {
+            0..=127 => 1,
+            128..=16_383 => 2,
+            16_384..=2_097_151 => 3,
+            _ => 4,
+        };
+        assert_eq!(encoded.len(), expected_size, "length {len}");
+        assert_eq!(
+            decode_remaining_length(&encoded),
+            Ok(Some((len, encoded.len()))),
+            "length {len}"
+        );
+
+        // Trailing bytes are not consumed; a truncated field needs more input
+        let mut extended = encoded.to_vec();
+        extended.push(0xFF);
+        assert_eq!(
+            decode_remaining_length(&extended),
+            Ok(Some((len, encoded.len())))
+        );
+        assert_eq!(
+            decode_remaining_length(&encoded[..encoded.len() - 1]),
+            Ok(None)
+        );
+    }
+
+    // Every value around each byte-count boundary
+    for boundary in [0, 128, 16_384, 2_097_152, MAX_REMAINING_LENGTH] {
+        for len in boundary.saturating_sub(64)..=(boundary + 64).min(MAX_REMAINING_LENGTH) {
+            check(len);
+        }
+    }
+
+    // A deterministic pseudo-random sweep over the whole range
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    for _ in 0..100_000 {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        check((state >> 33) as usize % (MAX_REMAINING_LENGTH + 1));
+    }
+
+    // Out of range lengths and fields longer than four bytes are rejected
+    assert!(encode_remaining_length(MAX_REMAINING_LENGTH + 1).is_err());
+    assert!(decode_remaining_length(&[0xFF, 0xFF, 0xFF, 0xFF]).is_err());
+    assert!(decode_remaining_length(&[0x80, 0x80, 0x80, 0x80, 0x00]).is_err());
+}
+
+#[test]
+fn test_poll_rejects_overlong_remaining_length() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::error::Error;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    conn.push_incoming(&[0x30, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+    assert_eq!(client.poll(), Err(Error::ProtocolError));
+}
+
+#[test]
+fn test_poll_rejects_packet_over_max_size() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::error::Error;
+
+    let conn = ScriptedConnection::new();
+    conn.push_incoming(&[0x20, 0x02, 0x00, 0x00]);
+    let options = Options {
+        client_id: "dev",
+        keep_alive_seconds: 60,
+        clean_session: true,
+        max_packet_size: Some(8),
+    };
+    let mut client = Client::connect(conn.clone(), options).unwrap();
+
+    // Exactly at the limit: topic "a/b" and a 1-byte payload
+    conn.push_incoming(&[0x30, 0x06, 0x00, 0x03, b'a', b'/', b'b', b'!']);
+    assert_eq!(&client.poll().unwrap().unwrap().payload[..], b"!");
+
+    // A PUBLISH announcing 9 bytes is refused from its fixed header alone
+    conn.push_incoming(&[0x30, 0x09]);
+    assert_eq!(client.poll(), Err(Error::ProtocolError));
+    assert!(!client.is_connected());
+
+    // Without a limit the receive buffer still bounds what is accepted
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+    conn.push_incoming(&[0x30, 0x81, 0x08]); // remaining length 1025
+    assert_eq!(client.poll(), Err(Error::ProtocolError));
+    assert!(!client.is_connected());
+}
+
+#[test]
+fn test_subscribe_many_in_one_packet() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::{MAX_SUBSCRIPTIONS, QoS};
+    use libiot::network::error::Error;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    let topics = [
+        ("a", QoS::AtMostOnce),
+        ("b/+", QoS::AtLeastOnce),
+        ("c/#", QoS::ExactlyOnce),
+    ];
+    conn.push_incoming(&[0x90, 0x05, 0x00, 0x01, 0x00, 0x01, 0x02]);
+    client.subscribe_many(&topics).unwrap();
+    assert_eq!(
+        conn.take_written(),
+        [
+            0x82, 0x12, 0x00, 0x01, // fixed header, packet identifier
+            0x00, 0x01, b'a', 0x00, // "a" at QoS 0
+            0x00, 0x03, b'b', b'/', b'+', 0x01, // "b/+" at QoS 1
+            0x00, 0x03, b'c', b'/', b'#', 0x02, // "c/#" at QoS 2
+        ]
+    );
+    assert_eq!(client.subscriptions().collect::<Vec<_>>(), topics);
+
+    // A rejected filter fails the call but the accepted ones are kept
+    conn.push_incoming(&[0x90, 0x04, 0x00, 0x02, 0x80, 0x01]);
+    assert_eq!(
+        client.subscribe_many(&[("d", QoS::AtLeastOnce), ("e", QoS::AtLeastOnce)]),
+        Err(Error::ProtocolError)
+    );
+    let stored: Vec<_> = client.subscriptions().map(|(f, _)| f).collect();
+    assert_eq!(stored, ["a", "b/+", "c/#", "e"]);
+
+    // A SUBACK with the wrong number of return codes is rejected
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x03, 0x00]);
+    assert_eq!(
+        client.subscribe_many(&[("f", QoS::AtMostOnce), ("g", QoS::AtMostOnce)]),
+        Err(Error::ProtocolError)
+    );
+
+    // So is one acknowledging another packet identifier
+    conn.take_written();
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x00]);
+    assert_eq!(
+        client.subscribe("h", QoS::AtMostOnce),
+        Err(Error::ProtocolError)
+    );
+    assert_eq!(conn.take_written()[2..4], [0x00, 0x04]);
+
+    // Too many filters are refused before anything is sent
+    conn.take_written();
+    let many = [("x", QoS::AtMostOnce); MAX_SUBSCRIPTIONS + 1];
+    assert_eq!(client.subscribe_many(&many), Err(Error::ProtocolError));
+    assert!(conn.take_written().is_empty());
+}
+
+#[test]
+fn test_resubscribe_after_reconnect() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::QoS;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    let suback = |id: u8, code: u8| [0x90, 0x03, 0x00, id, code];
+    conn.push_incoming(&suback(1, 1));
+    client.subscribe("a/+", QoS::AtLeastOnce).unwrap();
+    conn.push_incoming(&suback(2, 0));
+    client.subscribe("b/#", QoS::AtMostOnce).unwrap();
+    // Subscribing again updates the stored QoS instead of adding an entry
+    conn.push_incoming(&suback(3, 2));
+    client.subscribe("a/+", QoS::ExactlyOnce).unwrap();
+    conn.take_written();
+
+    conn.push_incoming(&[0xB0, 0x02, 0x00, 0x04]);
+    client.unsubscribe("b/#").unwrap();
+    assert_eq!(
+        conn.take_written(),
+        [0xA2, 0x07, 0x00, 0x04, 0x00, 0x03, b'b', b'/', b'#']
+    );
+    conn.push_incoming(&suback(5, 1));
+    client.subscribe("c", QoS::AtLeastOnce).unwrap();
+    conn.take_written();
+
+    let stored: Vec<_> = client.subscriptions().collect();
+    assert_eq!(stored, [("a/+", QoS::ExactlyOnce), ("c", QoS::AtLeastOnce)]);
+
+    // The link drops
+    assert!(client.poll().is_err());
+    assert!(!client.is_connected());
+
+    // A new connection without a resumed session restores everything at once
+    let options = Options {
+        client_id: "dev",
+        keep_alive_seconds: 60,
+        clean_session: true,
+        max_packet_size: None,
+    };
+    let fresh = ScriptedConnection::new();
+    fresh.push_incoming(&[0x20, 0x02, 0x00, 0x00]);
+    fresh.push_incoming(&[0x90, 0x04, 0x00, 0x06, 0x02, 0x01]);
+    client.reconnect(fresh.clone(), options.clone()).unwrap();
+    assert!(client.is_connected());
+
+    let written = fresh.take_written();
+    let connect_len = 2 + written[1] as usize;
+    assert_eq!(written[0], 0x10);
+    assert_eq!(
+        written[connect_len..],
+        [
+            0x82, 0x0C, 0x00, 0x06, // SUBSCRIBE, packet id 6
+            0x00, 0x03, b'a', b'/', b'+', 0x02, // "a/+" at QoS 2
+            0x00, 0x01, b'c', 0x01, // "c" at QoS 1
+        ]
+    );
+    let stored: Vec<_> = client.subscriptions().collect();
+    assert_eq!(stored, [("a/+", QoS::ExactlyOnce), ("c", QoS::AtLeastOnce)]);
+
+    // A resumed session keeps its subscriptions on the broker
+    let resumed = ScriptedConnection::new();
+    resumed.push_incoming(&[0x20, 0x02, 0x01, 0x00]);
+    client.reconnect(resumed.clone(), options).unwrap();
+    let written = resumed.take_written();
+    assert_eq!(written.len(), 2 + written[1] as usize);
+}
+
+#[test]
+fn test_subscription_table_size() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::application::mqtt::client::{MAX_SUBSCRIPTIONS, QoS};
+    use libiot::network::error::Error;
+
+    // A client that remembers two filters
+    let conn = ScriptedConnection::new();
+    let mut client = Client::<_, 1024, 256, 2>::from_connected_with_buffers(conn.clone());
+    conn.push_incoming(&[0x90, 0x04, 0x00, 0x01, 0x00, 0x00]);
+    client
+        .subscribe_many(&[("a", QoS::AtMostOnce), ("b", QoS::AtMostOnce)])
+        .unwrap();
+    conn.take_written();
+    assert_eq!(
+        client.subscribe("c", QoS::AtMostOnce),
+        Err(Error::ProtocolError)
+    );
+    assert!(conn.take_written().is_empty());
+
+    // A full table of the longest filters is restored in one packet
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+    let filters: Vec<String> = (0..MAX_SUBSCRIPTIONS)
+        .map(|i| format!("{i}").repeat(256))
+        .collect();
+    for (i, filter) in filters.iter().enumerate() {
+        conn.push_incoming(&[0x90, 0x03, 0x00, i as u8 + 1, 0x01]);
+        client.subscribe(filter, QoS::AtLeastOnce).unwrap();
+    }
+    assert!(client.poll().is_err());
+
+    let fresh = ScriptedConnection::new();
+    fresh.push_incoming(&[0x20, 0x02, 0x00, 0x00]);
+    fresh.push_incoming(&[0x90, 0x0A, 0x00, MAX_SUBSCRIPTIONS as u8 + 1]);
+    fresh.push_incoming(&[0x01; MAX_SUBSCRIPTIONS]);
+    let options = Options {
+        client_id: "dev",
+        keep_alive_seconds: 60,
+        clean_session: true,
+        max_packet_size: None,
+    };
+    client.reconnect(fresh.clone(), options).unwrap();
+
+    let written = fresh.take_written();
+    let connect_len = 2 + written[1] as usize;
+    let subscribe = &written[connect_len..];
+    // Remaining length 2 + 8 * (2 + 256 + 1) = 2074, in two bytes
+    assert_eq!(subscribe[..5], [0x82, 0x9A, 0x10, 0x00, 0x09]);
+    let mut expected = Vec::new();
+    for filter in &filters {
+        expected.extend_from_slice(&[0x01, 0x00]);
+        expected.extend_from_slice(filter.as_bytes());
+        expected.push(0x01);
+    }
+    assert_eq!(subscribe[5..], expected[..]);
+}
+
+#[test]
+fn test_poll_rejects_invalid_topic() {
+    use super::mock::ScriptedConnection;
+    use libiot::network::error::Error;
+
+    let conn = ScriptedConnection::new();
+    let mut client = Client::from_connected(conn.clone());
+
+    // Invalid UTF-8 (a lone continuation byte) in the topic
+    conn.push_incoming(&[0x30, 0x06, 0x00, 0x02, b'a', 0x80, b'h', b'i']);
+    assert_eq!(client.poll(), Err(Error::ProtocolError));
+
+    // U+0000 is not allowed in topic names either
+    conn.push_incoming(&[0x30, 0x06, 0x00, 0x02, b'a', 0x00, b'h', b'i']);
+    assert_eq!(client.poll_ref(), Err(Error::ProtocolError));
+
+    // The packets were consumed whole, so the client keeps working
+    conn.push_incoming(&[0x30, 0x05, 0x00, 0x01, b'x', b'o', b'k']);
+    let packet = client.poll().unwrap().unwrap();
+    assert_eq!(packet.topic.as_str(), "x");
+    assert_eq!(conn.take_written(), []);
+}
+
+#[test]
+fn test_qos_from_str() {
+    use libiot::network::application::mqtt::client::QoS;
+
+    for qos in [QoS::AtMostOnce, QoS::AtLeastOnce, QoS::ExactlyOnce] {
+        assert_eq!(QoS::try_from((qos as u8).to_string().as_str()), Ok(qos));
+        assert_eq!(format!("{qos:?}").parse(), Ok(qos));
+        assert_eq!(format!("{qos:?}").to_lowercase().parse(), Ok(qos));
+    }
+    assert_eq!(QoS::try_from("at_least_once"), Ok(QoS::AtLeastOnce));
+    assert_eq!(
+        QoS::try_from("3"),
+        Err(libiot::network::error::Error::ProtocolError)
+    );
+    assert!(QoS::try_from("").is_err());
+}
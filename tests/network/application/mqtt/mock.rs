@@ -0,0 +1,75 @@
+//! Scripted broker connection for offline MQTT client testing
+
+use libiot::network::error::Error;
+use libiot::network::{Close, Connection, Read, Write};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+#[derive(Default)]
+struct Inner {
+    incoming: VecDeque<u8>,
+    written: std::vec::Vec<u8>,
+}
+
+/// Mock connection whose state is shared between clones, so a test can keep
+/// a handle after moving the connection into a client.
+#[derive(Clone, Default)]
+pub struct ScriptedConnection {
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl ScriptedConnection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue bytes to be returned by subsequent reads
+    pub fn push_incoming(&self, data: &[u8]) {
+        self.inner
+            .borrow_mut()
+            .incoming
+            .extend(data.iter().copied());
+    }
+
+    /// Take everything written so far
+    pub fn take_written(&self) -> std::vec::Vec<u8> {
+        std::mem::take(&mut self.inner.borrow_mut().written)
+    }
+}
+
+impl Read for ScriptedConnection {
+    type Error = Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut inner = self.inner.borrow_mut();
+        let n = buf.len().min(inner.incoming.len());
+        for (slot, byte) in buf.iter_mut().zip(inner.incoming.drain(..n)) {
+            *slot = byte;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for ScriptedConnection {
+    type Error = Error;
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.inner.borrow_mut().written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Close for ScriptedConnection {
+    type Error = Error;
+
+    fn close(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Connection for ScriptedConnection {}
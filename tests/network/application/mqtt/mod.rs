@@ -1 +1,5 @@
 pub mod client;
+pub mod mock;
+#[cfg(feature = "mqtt-session")]
+pub mod session;
+pub mod topic;
@@ -0,0 +1,255 @@
+use super::mock::ScriptedConnection;
+use libiot::network::application::mqtt::client::{Options, QoS};
+use libiot::network::application::mqtt::session::{
+    Error, MAX_SESSION_LEN, PersistentClient, SessionStore,
+};
+use libiot::storage::{ReadStorage, Storage};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// RAM-backed storage that counts writes, standing in for an EEPROM
+struct Ram {
+    buf: std::vec::Vec<u8>,
+    writes: usize,
+    // Writes fail once this many have been made, as if power was lost;
+    // shared so a test can change it while the store owns the RAM
+    write_limit: Rc<Cell<Option<usize>>>,
+}
+
+impl Ram {
+    fn new() -> Self {
+        Self {
+            buf: vec![0xFF; MAX_SESSION_LEN],
+            writes: 0,
+            write_limit: Rc::default(),
+        }
+    }
+}
+
+impl ReadStorage for Ram {
+    type Error = ();
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+        let off = offset as usize;
+        bytes.copy_from_slice(self.buf.get(off..off + bytes.len()).ok_or(())?);
+        Ok(())
+    }
+    fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+}
+
+impl Storage for Ram {
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+        if self
+            .write_limit
+            .get()
+            .is_some_and(|limit| self.writes >= limit)
+        {
+            return Err(());
+        }
+        let off = offset as usize;
+        self.buf
+            .get_mut(off..off + bytes.len())
+            .ok_or(())?
+            .copy_from_slice(bytes);
+        self.writes += 1;
+        Ok(())
+    }
+}
+
+fn options(clean_session: bool) -> Options<'static> {
+    Options {
+        client_id: "dev",
+        keep_alive_seconds: 60,
+        clean_session,
+        max_packet_size: None,
+    }
+}
+
+/// Connect over a fresh scripted connection with the given session-present flag
+fn boot(
+    store: SessionStore<Ram>,
+    session_present: bool,
+) -> (
+    PersistentClient<ScriptedConnection, Ram>,
+    ScriptedConnection,
+) {
+    let conn = ScriptedConnection::new();
+    conn.push_incoming(&[0x20, 0x02, session_present as u8, 0x00]);
+    let client = PersistentClient::connect(conn.clone(), options(false), store).unwrap();
+    conn.take_written();
+    (client, conn)
+}
+
+#[test]
+fn test_qos2_message_survives_reset() {
+    let (mut client, conn) = boot(SessionStore::new(Ram::new(), 0, MAX_SESSION_LEN), false);
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x02]);
+    client.subscribe("cmd/#", QoS::ExactlyOnce).unwrap();
+    conn.take_written();
+
+    // PUBLISH, QoS 2, topic "cmd/a", packet id 7, payload "go"
+    let publish = [
+        0x34, 0x0B, 0x00, 0x05, b'c', b'm', b'd', b'/', b'a', 0x00, 0x07, b'g', b'o',
+    ];
+    conn.push_incoming(&publish);
+    assert_eq!(client.poll().unwrap(), None);
+    assert_eq!(conn.take_written(), [0x50, 0x02, 0x00, 0x07]);
+
+    // A retransmission changes nothing, so the store is not rewritten
+    let (_, store) = client.into_parts();
+    let writes = store.storage().writes;
+    let (mut client, conn) = boot(store, true);
+    conn.push_incoming(&publish);
+    assert_eq!(client.poll().unwrap(), None);
+    assert_eq!(conn.take_written(), [0x50, 0x02, 0x00, 0x07]);
+
+    // The reset above restored the subscription without resubscribing, and
+    // the broker's PUBREL delivers the message held across it
+    let stored: Vec<_> = client.client().subscriptions().collect();
+    assert_eq!(stored, [("cmd/#", QoS::ExactlyOnce)]);
+    let (_, store) = client.into_parts();
+    assert_eq!(store.storage().writes, writes);
+
+    let (mut client, conn) = boot(store, true);
+    conn.push_incoming(&[0x62, 0x02, 0x00, 0x07]);
+    let packet = client.poll().unwrap().unwrap();
+    assert_eq!(packet.topic.as_str(), "cmd/a");
+    assert_eq!(&packet.payload[..], b"go");
+    assert_eq!(conn.take_written(), [0x70, 0x02, 0x00, 0x07]);
+
+    // Released before PUBCOMP, so it is not delivered again after a reset
+    let (_, store) = client.into_parts();
+    let (mut client, conn) = boot(store, true);
+    conn.push_incoming(&[0x62, 0x02, 0x00, 0x07]);
+    assert_eq!(client.poll().unwrap(), None);
+    assert_eq!(conn.take_written(), [0x70, 0x02, 0x00, 0x07]);
+
+    // A broker that lost the session gets the subscriptions replayed
+    let (_, store) = client.into_parts();
+    let conn = ScriptedConnection::new();
+    conn.push_incoming(&[0x20, 0x02, 0x00, 0x00]);
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x02]);
+    PersistentClient::connect(conn.clone(), options(false), store).unwrap();
+    let written = conn.take_written();
+    let connect_len = 2 + written[1] as usize;
+    assert_eq!(
+        written[connect_len..],
+        [
+            0x82, 0x0A, 0x00, 0x01, 0x00, 0x05, b'c', b'm', b'd', b'/', b'#', 0x02
+        ]
+    );
+}
+
+#[test]
+fn test_qos2_message_not_acknowledged_until_stored() {
+    let ram = Ram::new();
+    let write_limit = ram.write_limit.clone();
+    let (mut client, conn) = boot(SessionStore::new(ram, 0, MAX_SESSION_LEN), false);
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x02]);
+    client.subscribe("cmd/#", QoS::ExactlyOnce).unwrap();
+    conn.take_written();
+
+    // PUBLISH, QoS 2, topic "cmd/a", packet id 7, payload "go"
+    let mut publish = [
+        0x34, 0x0B, 0x00, 0x05, b'c', b'm', b'd', b'/', b'a', 0x00, 0x07, b'g', b'o',
+    ];
+    write_limit.set(Some(0));
+    conn.push_incoming(&publish);
+    assert!(matches!(client.poll(), Err(Error::Storage(_))));
+    assert!(conn.take_written().is_empty());
+
+    // The broker retransmits with DUP set; this time the message is stored
+    // before the PUBREC goes out
+    write_limit.set(None);
+    publish[0] |= 0x08;
+    conn.push_incoming(&publish);
+    assert_eq!(client.poll().unwrap(), None);
+    assert_eq!(conn.take_written(), [0x50, 0x02, 0x00, 0x07]);
+
+    let (_, store) = client.into_parts();
+    let (mut client, conn) = boot(store, true);
+    conn.push_incoming(&[0x62, 0x02, 0x00, 0x07]);
+    let packet = client.poll().unwrap().unwrap();
+    assert_eq!(packet.topic.as_str(), "cmd/a");
+    assert_eq!(&packet.payload[..], b"go");
+}
+
+#[test]
+fn test_session_store_ignored_or_rejected() {
+    // A clean session neither reads nor writes the store
+    let conn = ScriptedConnection::new();
+    conn.push_incoming(&[0x20, 0x02, 0x00, 0x00]);
+    let store = SessionStore::new(Ram::new(), 0, MAX_SESSION_LEN);
+    let mut client = PersistentClient::connect(conn.clone(), options(true), store).unwrap();
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x01]);
+    client.subscribe("a", QoS::AtLeastOnce).unwrap();
+    let (_, store) = client.into_parts();
+    assert_eq!(store.storage().writes, 0);
+
+    // A corrupted record is skipped in favour of the previous one, here the
+    // empty session saved on connect
+    let (mut client, conn) = boot(store, false);
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x01]);
+    client.subscribe("a", QoS::AtLeastOnce).unwrap();
+    let (_, store) = client.into_parts();
+    let mut ram = store.into_inner();
+    let last = ram.buf.iter().rposition(|&b| b == b'a').unwrap();
+    ram.buf[last] = b'b';
+    let (client, _) = boot(SessionStore::new(ram, 0, MAX_SESSION_LEN), true);
+    assert_eq!(client.client().subscriptions().count(), 0);
+
+    // A session that does not fit its region is reported, not truncated
+    let (mut client, conn) = boot(SessionStore::new(Ram::new(), 0, 40), false);
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x01]);
+    assert_eq!(
+        client.subscribe("sensors/+/temperature", QoS::AtLeastOnce),
+        Err(Error::Storage(libiot::storage::error::Error::OutOfBounds))
+    );
+}
+
+#[test]
+fn test_interrupted_save_keeps_previous_session() {
+    let (mut client, conn) = boot(SessionStore::new(Ram::new(), 0, MAX_SESSION_LEN), false);
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x01]);
+    client.subscribe("a", QoS::AtLeastOnce).unwrap();
+    let (_, store) = client.into_parts();
+    let ram = store.into_inner();
+
+    // Lose power at every point of the next save in turn
+    for budget in 0..100 {
+        let mut torn = Ram::new();
+        torn.buf = ram.buf.clone();
+        torn.write_limit.set(Some(budget));
+        let (mut client, conn) = boot(SessionStore::new(torn, 0, MAX_SESSION_LEN), true);
+        conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x01]);
+        let saved = client.subscribe("b", QoS::AtLeastOnce).is_ok();
+        let (_, store) = client.into_parts();
+
+        // After the reset, either the old or the new session is restored
+        let restored = store.into_inner();
+        restored.write_limit.set(None);
+        let (client, _) = boot(SessionStore::new(restored, 0, MAX_SESSION_LEN), true);
+        let filters: Vec<_> = client.client().subscriptions().map(|(f, _)| f).collect();
+        if saved {
+            assert_eq!(filters, ["a", "b"]);
+            break;
+        }
+        assert_eq!(filters, ["a"], "write budget {budget}");
+        assert!(budget < 99, "save never completed");
+    }
+
+    // Later saves keep alternating between the slots
+    ram.write_limit.set(None);
+    let (mut client, conn) = boot(SessionStore::new(ram, 0, MAX_SESSION_LEN), true);
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x01]);
+    client.subscribe("b", QoS::AtLeastOnce).unwrap();
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x02, 0x01]);
+    client.subscribe("c", QoS::AtLeastOnce).unwrap();
+    let (_, store) = client.into_parts();
+    let (client, _) = boot(
+        SessionStore::new(store.into_inner(), 0, MAX_SESSION_LEN),
+        true,
+    );
+    assert_eq!(client.client().subscriptions().count(), 3);
+}
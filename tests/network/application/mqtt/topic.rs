@@ -0,0 +1,63 @@
+use libiot::network::application::mqtt::topic_matches;
+
+#[test]
+fn test_topic_matches_spec_examples() {
+    let cases = [
+        // Multi-level wildcard (4.7.1.2)
+        ("sport/tennis/player1/#", "sport/tennis/player1", true),
+        (
+            "sport/tennis/player1/#",
+            "sport/tennis/player1/ranking",
+            true,
+        ),
+        (
+            "sport/tennis/player1/#",
+            "sport/tennis/player1/score/wimbledon",
+            true,
+        ),
+        ("sport/#", "sport", true),
+        ("#", "sport/tennis/player1", true),
+        ("#", "/", true),
+        ("sport/tennis#", "sport/tennis", false),
+        (
+            "sport/tennis/#/ranking",
+            "sport/tennis/player1/ranking",
+            false,
+        ),
+        // Single-level wildcard (4.7.1.3)
+        ("sport/tennis/+", "sport/tennis/player1", true),
+        ("sport/tennis/+", "sport/tennis/player2", true),
+        ("sport/tennis/+", "sport/tennis/player1/ranking", false),
+        ("sport/+", "sport", false),
+        ("sport/+", "sport/", true),
+        ("+", "sport", true),
+        ("+/+", "sport", false),
+        ("+/tennis/#", "sport/tennis", true),
+        ("sport+", "sport", false),
+        ("+/+", "/finance", true),
+        ("/+", "/finance", true),
+        ("+", "/finance", false),
+        // Topics beginning with $ (4.7.2)
+        ("#", "$SYS/broker/load", false),
+        ("+/monitor/Clients", "$SYS/monitor/Clients", false),
+        ("$SYS/#", "$SYS/monitor/Clients", true),
+        ("$SYS/monitor/+", "$SYS/monitor/Clients", true),
+        // Exact and case-sensitive comparison (4.7.3)
+        ("sport/tennis", "sport/tennis", true),
+        ("sport/tennis", "Sport/Tennis", false),
+        ("sport/tennis", "sport/tennis/", false),
+        ("sport/tennis/", "sport/tennis", false),
+        // Invalid input
+        ("", "sport", false),
+        ("sport", "", false),
+        ("sport/+", "sport/+", false),
+    ];
+
+    for (filter, topic, expected) in cases {
+        assert_eq!(
+            topic_matches(filter, topic),
+            expected,
+            "filter {filter:?} against topic {topic:?}"
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use libiot::network::application::websocket::{Client, Frame, accept_key};
+use libiot::network::error::Error;
+use libiot::network::{Close, Connection, Read, Write};
+use std::collections::VecDeque;
+
+/// A server that answers the upgrade request once it has been flushed,
+/// then sends `frames`
+struct MockServer {
+    status: &'static str,
+    accept: Option<&'static str>,
+    frames: Vec<u8>,
+    incoming: VecDeque<u8>,
+    written: Vec<u8>,
+    upgraded: bool,
+}
+
+impl MockServer {
+    fn new(frames: Vec<u8>) -> Self {
+        Self {
+            status: "101 Switching Protocols",
+            accept: None,
+            frames,
+            incoming: VecDeque::new(),
+            written: Vec::new(),
+            upgraded: false,
+        }
+    }
+
+    /// Take the bytes written since the handshake
+    fn take_written(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.written)
+    }
+}
+
+impl Read for MockServer {
+    type Error = Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = buf.len().min(self.incoming.len());
+        for (out, byte) in buf.iter_mut().zip(self.incoming.drain(..n)) {
+            *out = byte;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for MockServer {
+    type Error = Error;
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        if self.upgraded {
+            return Ok(());
+        }
+        self.upgraded = true;
+        let request = String::from_utf8(self.take_written()).unwrap();
+        assert!(request.starts_with("GET /chat HTTP/1.1\r\n"), "{request}");
+        assert!(request.contains("\r\nHost: example.com\r\n"));
+        assert!(request.contains("\r\nUpgrade: websocket\r\n"));
+        assert!(request.contains("\r\nSec-WebSocket-Version: 13\r\n"));
+        assert!(request.ends_with("\r\n\r\n"));
+        let key = request
+            .split("\r\n")
+            .find_map(|line| line.strip_prefix("Sec-WebSocket-Key: "))
+            .unwrap();
+        assert_eq!(key.len(), 24);
+
+        let accept = match self.accept {
+            Some(accept) => accept.to_string(),
+            None => accept_key(key).to_string(),
+        };
+        let response = format!(
+            "HTTP/1.1 {}\r\nupgrade: WebSocket\r\nConnection: keep-alive, Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n",
+            self.status
+        );
+        self.incoming.extend(response.as_bytes());
+        self.incoming.extend(self.frames.drain(..));
+        Ok(())
+    }
+}
+
+impl Close for MockServer {
+    type Error = Error;
+
+    fn close(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Connection for MockServer {}
+
+/// An unmasked frame as a server sends it
+fn server_frame(head: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![head];
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Split a client frame into its first header byte and unmasked payload
+fn client_frame(frame: &[u8]) -> (u8, Vec<u8>) {
+    assert_ne!(frame[1] & 0x80, 0, "client frames must be masked");
+    let (len, start) = match frame[1] & 0x7F {
+        126 => (u16::from_be_bytes([frame[2], frame[3]]) as usize, 4),
+        127 => (
+            u64::from_be_bytes(frame[2..10].try_into().unwrap()) as usize,
+            10,
+        ),
+        len => (len as usize, 2),
+    };
+    let mask = &frame[start..start + 4];
+    let payload = &frame[start + 4..];
+    assert_eq!(payload.len(), len);
+    let payload = payload
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| byte ^ mask[i % 4])
+        .collect();
+    (frame[0], payload)
+}
+
+fn connect(server: MockServer) -> Result<Client<MockServer, 256>, Error> {
+    Client::connect(server, "example.com", "/chat", 0x1234_5678)
+}
+
+#[test]
+fn test_websocket_handshake() {
+    assert!(connect(MockServer::new(Vec::new())).is_ok());
+
+    let mut server = MockServer::new(Vec::new());
+    server.accept = Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    assert_eq!(connect(server).err(), Some(Error::ProtocolError));
+
+    let mut server = MockServer::new(Vec::new());
+    server.status = "403 Forbidden";
+    assert_eq!(connect(server).err(), Some(Error::ConnectionRefused));
+
+    // Each seed gives a different key
+    let key = |seed| {
+        let mut client: Client<MockServer, 16> =
+            Client::connect(MockServer::new(Vec::new()), "example.com", "/chat", seed).unwrap();
+        client.send_text("").unwrap();
+        client.connection_mut().take_written()[2..6].to_vec()
+    };
+    assert_ne!(key(1), key(2));
+}
+
+#[test]
+fn test_websocket_send_frames() {
+    let mut client = connect(MockServer::new(Vec::new())).unwrap();
+
+    client.send_text("hello").unwrap();
+    let written = client.connection_mut().take_written();
+    assert_eq!(client_frame(&written), (0x81, b"hello".to_vec()));
+
+    // 16-bit extended length
+    let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+    client.send_binary(&data).unwrap();
+    let written = client.connection_mut().take_written();
+    assert_eq!(written[1], 0x80 | 126);
+    assert_eq!(client_frame(&written), (0x82, data));
+
+    // Masks change from frame to frame
+    client.send_text("a").unwrap();
+    let first = client.connection_mut().take_written();
+    client.send_text("a").unwrap();
+    let second = client.connection_mut().take_written();
+    assert_ne!(first[2..6], second[2..6]);
+}
+
+#[test]
+fn test_websocket_read_frames() {
+    let large: Vec<u8> = (0..200).map(|i| i as u8).collect();
+    let mut frames = Vec::new();
+    frames.extend(server_frame(0x81, "héllo".as_bytes()));
+    frames.extend(server_frame(0x82, &large));
+    frames.extend(server_frame(0x89, b"are you there"));
+    frames.extend(server_frame(0x8A, b""));
+    frames.extend(server_frame(0x88, &[0x03, 0xE8]));
+    let mut client = connect(MockServer::new(frames)).unwrap();
+
+    assert_eq!(client.read_frame(), Ok(Frame::Text("héllo")));
+    assert_eq!(client.read_frame(), Ok(Frame::Binary(&large[..])));
+    assert_eq!(client.read_frame(), Ok(Frame::Ping(b"are you there")));
+    assert_eq!(client.read_frame(), Ok(Frame::Pong(b"")));
+    assert_eq!(
+        client.read_frame(),
+        Ok(Frame::Close {
+            code: 1000,
+            reason: ""
+        })
+    );
+    assert_eq!(client.read_frame(), Err(Error::ConnectionClosed));
+}
+
+#[test]
+fn test_websocket_ping_pong() {
+    let mut frames = Vec::new();
+    frames.extend(server_frame(0x89, b"are you there"));
+    frames.extend(server_frame(0x8A, b"still here?"));
+    let mut client = connect(MockServer::new(frames)).unwrap();
+
+    // A ping from the server is answered with the same payload
+    assert_eq!(client.read_frame(), Ok(Frame::Ping(b"are you there")));
+    let written = client.connection_mut().take_written();
+    assert_eq!(client_frame(&written), (0x8A, b"are you there".to_vec()));
+
+    client.ping(b"still here?").unwrap();
+    let written = client.connection_mut().take_written();
+    assert_eq!(client_frame(&written), (0x89, b"still here?".to_vec()));
+    assert_eq!(client.read_frame(), Ok(Frame::Pong(b"still here?")));
+
+    assert_eq!(client.ping(&[0; 126]), Err(Error::WriteError));
+}
+
+#[test]
+fn test_websocket_close() {
+    // Data still in flight is discarded while waiting for the echo
+    let mut frames = Vec::new();
+    frames.extend(server_frame(0x81, b"late"));
+    frames.extend(server_frame(0x88, &[0x03, 0xE8]));
+    let client = connect(MockServer::new(frames)).unwrap();
+
+    let mut server = client.close(1000, "bye").unwrap();
+    let (head, payload) = client_frame(&server.take_written());
+    assert_eq!(head, 0x88);
+    assert_eq!(payload, b"\x03\xE8bye");
+    assert!(server.incoming.is_empty());
+
+    // Codes that may not be sent
+    for code in [999, 1004, 1005, 1006, 1015, 2000, 5000] {
+        let client = connect(MockServer::new(Vec::new())).unwrap();
+        assert_eq!(client.close(code, "").err(), Some(Error::ProtocolError));
+    }
+}
+
+#[test]
+fn test_websocket_server_close() {
+    let mut frames = Vec::new();
+    frames.extend(server_frame(0x88, b"\x03\xE9going away"));
+    let mut client = connect(MockServer::new(frames)).unwrap();
+
+    assert_eq!(
+        client.read_frame(),
+        Ok(Frame::Close {
+            code: 1001,
+            reason: "going away"
+        })
+    );
+    // The status code is echoed, and nothing may be sent afterwards
+    let written = client.connection_mut().take_written();
+    assert_eq!(client_frame(&written), (0x88, vec![0x03, 0xE9]));
+    assert_eq!(client.send_text("hello"), Err(Error::ConnectionClosed));
+    assert!(client.close(1000, "").is_ok());
+
+    // A Close without a status code is echoed empty
+    let mut client = connect(MockServer::new(server_frame(0x88, b""))).unwrap();
+    assert_eq!(
+        client.read_frame(),
+        Ok(Frame::Close {
+            code: 1005,
+            reason: ""
+        })
+    );
+    let written = client.connection_mut().take_written();
+    assert_eq!(client_frame(&written), (0x88, Vec::new()));
+
+    // Malformed Close frames
+    for payload in [&[0x03][..], &[0x03, 0xED], &[0x03, 0xE8, 0xFF]] {
+        let mut client = connect(MockServer::new(server_frame(0x88, payload))).unwrap();
+        assert_eq!(client.read_frame(), Err(Error::ProtocolError));
+    }
+}
+
+#[test]
+fn test_websocket_invalid_frames() {
+    let mut frames = Vec::new();
+    // Larger than the 256-byte receive buffer
+    frames.extend(server_frame(0x82, &[0; 300]));
+    // Fragmented message
+    frames.extend(server_frame(0x01, b"part"));
+    // Invalid UTF-8 text
+    frames.extend(server_frame(0x81, &[0xFF]));
+    // Control frame with a 16-bit length
+    frames.extend(server_frame(0x89, &[0; 126]));
+    let mut client = connect(MockServer::new(frames)).unwrap();
+
+    assert_eq!(client.read_frame(), Err(Error::ProtocolError));
+    assert_eq!(client.read_frame(), Err(Error::ProtocolError));
+    assert_eq!(client.read_frame(), Err(Error::ProtocolError));
+    assert_eq!(client.read_frame(), Err(Error::ProtocolError));
+
+    // A frame masked by the server
+    let mut client = connect(MockServer::new(vec![0x81, 0x81, 1, 2, 3, 4, b'a'])).unwrap();
+    assert_eq!(client.read_frame(), Err(Error::ProtocolError));
+}
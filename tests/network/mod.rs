@@ -2,6 +2,7 @@ use libiot::network::error::Error;
 use libiot::network::*;
 
 pub mod application;
+mod retry;
 pub mod transport;
 
 const MOCK_BUFFER_SIZE: usize = 256;
@@ -111,7 +112,7 @@ fn test_connect_and_close() {
     let mut network = MockNetwork;
     let conn = network.connect("mock://server").unwrap();
     assert!(conn.is_open);
-    conn.close().unwrap();
+    Close::close(conn).unwrap();
 }
 
 #[test]
@@ -120,7 +121,7 @@ fn test_read_write() {
     let write_data = [1, 2, 3, 4];
 
     // Test write
-    let bytes_written = conn.write(&write_data).unwrap();
+    let bytes_written = Write::write(&mut conn, &write_data).unwrap();
     assert_eq!(bytes_written, write_data.len());
     assert_eq!(&conn.write_buffer[..write_data.len()], &write_data);
 
@@ -128,7 +129,7 @@ fn test_read_write() {
     let read_data = [5, 6, 7, 8];
     conn.set_read_data(&read_data);
     let mut read_buf = [0; 4];
-    let bytes_read = conn.read(&mut read_buf).unwrap();
+    let bytes_read = Read::read(&mut conn, &mut read_buf).unwrap();
     assert_eq!(bytes_read, read_data.len());
     assert_eq!(read_buf, read_data);
 }
@@ -137,7 +138,7 @@ fn test_read_write() {
 fn test_read_empty() {
     let mut conn = MockConnection::new();
     let mut read_buf = [0; 4];
-    let bytes_read = conn.read(&mut read_buf).unwrap();
+    let bytes_read = Read::read(&mut conn, &mut read_buf).unwrap();
     assert_eq!(bytes_read, 0);
 }
 
@@ -145,7 +146,7 @@ fn test_read_empty() {
 fn test_write_full() {
     let mut conn = MockConnection::new();
     let large_data = [0xAA; MOCK_BUFFER_SIZE + 1];
-    let bytes_written = conn.write(&large_data).unwrap();
+    let bytes_written = Write::write(&mut conn, &large_data).unwrap();
     // Should only write up to the buffer size
     assert_eq!(bytes_written, MOCK_BUFFER_SIZE);
 }
@@ -156,9 +157,24 @@ fn test_op_on_closed_connection() {
     conn.is_open = false; // Manually set for test purposes.
 
     let mut buf = [0; 4];
-    assert_eq!(conn.read(&mut buf), Err(Error::NotOpen));
-    assert_eq!(conn.write(&[1, 2]), Err(Error::NotOpen));
-    assert_eq!(conn.flush(), Err(Error::NotOpen));
+    assert_eq!(Read::read(&mut conn, &mut buf), Err(Error::NotOpen));
+    assert_eq!(Write::write(&mut conn, &[1, 2]), Err(Error::NotOpen));
+    assert_eq!(Write::flush(&mut conn), Err(Error::NotOpen));
+}
+
+#[test]
+fn test_timeout_error() {
+    assert_eq!(std::format!("{}", Error::Timeout), "operation timed out");
+    assert_eq!(
+        std::format!("{}", Error::WouldBlock),
+        "operation would block"
+    );
+
+    // OTA reports network timeouts as-is
+    assert_eq!(
+        libiot::ota::Error::from(Error::Timeout),
+        libiot::ota::Error::Network(Error::Timeout)
+    );
 }
 
 #[cfg(feature = "async")]
@@ -217,7 +233,7 @@ mod async_tests {
             let mut conn = network.connect("mock://server").await.unwrap();
 
             let write_data = [10, 20, 30, 40];
-            let bytes_written = conn.write(&write_data).await.unwrap();
+            let bytes_written = AsyncWrite::write(&mut conn, &write_data).await.unwrap();
             assert_eq!(bytes_written, write_data.len());
 
             // Since our mock isn't a real network, we have to manually
@@ -227,11 +243,11 @@ mod async_tests {
             conn.set_read_data(&temp_buf[..bytes_written]);
 
             let mut read_buf = [0; 4];
-            let bytes_read = conn.read(&mut read_buf).await.unwrap();
+            let bytes_read = AsyncRead::read(&mut conn, &mut read_buf).await.unwrap();
             assert_eq!(bytes_read, write_data.len());
             assert_eq!(read_buf, write_data);
 
-            conn.close().await.unwrap();
+            AsyncClose::close(conn).await.unwrap();
         });
     }
 }
@@ -0,0 +1,122 @@
+use core::cell::{Cell, RefCell};
+
+use libiot::network::error::Error;
+use libiot::network::retry::*;
+use libiot::system::clock::{Delay, MonotonicClock, NoDelay};
+
+struct MockClock {
+    now: Cell<u64>,
+}
+
+impl MonotonicClock for MockClock {
+    fn now_ms(&self) -> u64 {
+        self.now.get()
+    }
+}
+
+#[derive(Default)]
+struct RecordingDelay {
+    waits: RefCell<Vec<u64>>,
+}
+
+impl Delay for RecordingDelay {
+    fn delay_ms(&self, ms: u64) {
+        self.waits.borrow_mut().push(ms);
+    }
+}
+
+#[test]
+fn test_retry_stops_after_max_attempts() {
+    let delay = RecordingDelay::default();
+    let policy = RetryPolicy::new(4).with_backoff(100, 250, 2);
+    let mut calls = 0;
+    let result: Result<(), Error> = retry(
+        || {
+            calls += 1;
+            Err(Error::Timeout)
+        },
+        &policy,
+        &delay,
+    );
+
+    assert_eq!(result, Err(Error::Timeout));
+    assert_eq!(calls, 4);
+    // Backs off exponentially, capped at the maximum, and not after the last attempt
+    assert_eq!(*delay.waits.borrow(), [100, 200, 250]);
+}
+
+#[test]
+fn test_retry_returns_first_success() {
+    let delay = RecordingDelay::default();
+    let mut calls = 0;
+    let result = retry(
+        || {
+            calls += 1;
+            if calls == 2 {
+                Ok(calls)
+            } else {
+                Err(Error::ReadError)
+            }
+        },
+        &RetryPolicy::default(),
+        &delay,
+    );
+
+    assert_eq!(result, Ok(2));
+    assert_eq!(*delay.waits.borrow(), [100]);
+
+    // Zero attempts still runs the operation once
+    let mut calls = 0;
+    let result: Result<(), Error> = retry(
+        || {
+            calls += 1;
+            Err(Error::WriteError)
+        },
+        &RetryPolicy::new(0),
+        &NoDelay,
+    );
+    assert_eq!(result, Err(Error::WriteError));
+    assert_eq!(calls, 1);
+}
+
+#[test]
+fn test_circuit_breaker_cooldown() {
+    let clock = MockClock { now: Cell::new(0) };
+    let mut breaker = CircuitBreaker::new(&clock, 3, 1_000);
+    let calls = Cell::new(0u32);
+    let failing = || -> Result<(), Error> {
+        calls.set(calls.get() + 1);
+        Err(Error::ConnectionRefused)
+    };
+
+    // Retrying stops as soon as the breaker trips, despite attempts left
+    let result = breaker.retry(failing, &RetryPolicy::new(10), &NoDelay);
+    assert_eq!(result, Err(BreakerError::Failed(Error::ConnectionRefused)));
+    assert_eq!(breaker.state(), BreakerState::Open);
+    assert_eq!(breaker.consecutive_failures(), 3);
+
+    // While open, nothing is attempted
+    assert_eq!(breaker.call(failing), Err(BreakerError::Open));
+    clock.now.set(999);
+    assert_eq!(
+        breaker.retry(failing, &RetryPolicy::new(10), &NoDelay),
+        Err(BreakerError::Open)
+    );
+    assert_eq!(calls.get(), 3);
+
+    // After the cooldown a single failed trial re-opens it
+    clock.now.set(1_000);
+    assert_eq!(breaker.state(), BreakerState::HalfOpen);
+    assert_eq!(
+        breaker.call(failing),
+        Err(BreakerError::Failed(Error::ConnectionRefused))
+    );
+    assert_eq!(calls.get(), 4);
+    assert_eq!(breaker.state(), BreakerState::Open);
+
+    // A successful trial closes it
+    clock.now.set(2_000);
+    assert_eq!(breaker.call(|| Ok::<_, Error>(7)), Ok(7));
+    assert_eq!(breaker.state(), BreakerState::Closed);
+    assert_eq!(breaker.consecutive_failures(), 0);
+}
@@ -0,0 +1,128 @@
+use heapless::Vec;
+use libiot::network::error::Error;
+use libiot::network::transport::at_modem::{AtCommands, AtModem};
+use libiot::network::{Close, Connect, Connection, Read, Write};
+
+/// Scripted modem UART: serves `rx` and records everything written.
+struct MockModem {
+    rx: &'static [u8],
+    pos: usize,
+    tx: Vec<u8, 512>,
+}
+
+impl MockModem {
+    fn new(rx: &'static [u8]) -> Self {
+        Self {
+            rx,
+            pos: 0,
+            tx: Vec::new(),
+        }
+    }
+
+    fn written(&self) -> &str {
+        core::str::from_utf8(&self.tx).unwrap()
+    }
+}
+
+impl Read for MockModem {
+    type Error = Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = buf.len().min(self.rx.len() - self.pos);
+        buf[..n].copy_from_slice(&self.rx[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Write for MockModem {
+    type Error = Error;
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.tx
+            .extend_from_slice(buf)
+            .map_err(|_| Error::WriteError)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Close for MockModem {
+    type Error = Error;
+
+    fn close(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Connection for MockModem {}
+
+#[test]
+fn test_at_modem_connect_and_data_mode() {
+    let uart = MockModem::new(b"OK\r\n\r\nCONNECT\r\nHTTP/1.0 200 OK\r\n\r\nOK\r\nOK\r\n");
+    let mut modem = AtModem::new(uart, AtCommands::QUECTEL);
+
+    let mut socket = modem.connect("example.com:80").unwrap();
+    assert!(!modem.is_attached());
+
+    // Payload after CONNECT is passed through untouched
+    let mut buf = [0u8; 19];
+    let n = socket.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"HTTP/1.0 200 OK\r\n\r\n");
+    socket.write(b"ping").unwrap();
+
+    socket.hang_up().unwrap();
+    let uart = socket.into_inner();
+    assert_eq!(
+        uart.written(),
+        "ATE0\r\nAT+QIOPEN=1,0,\"TCP\",\"example.com\",80,0,2\r\nping+++AT+QICLOSE=0\r\n"
+    );
+
+    modem.attach(uart);
+    assert!(modem.is_attached());
+}
+
+#[test]
+fn test_at_modem_custom_commands() {
+    let commands = AtCommands {
+        init: &[],
+        open: "AT+OPEN={host}|{port}",
+        connected: "READY",
+        escape: "+++",
+        close: "AT+CLOSE",
+    };
+    let mut modem = AtModem::new(MockModem::new(b"READY\r\n"), commands);
+
+    let socket = modem.connect("10.0.0.1:1883").unwrap();
+    assert_eq!(socket.into_inner().written(), "AT+OPEN=10.0.0.1|1883\r\n");
+}
+
+#[test]
+fn test_at_modem_connect_failure_keeps_serial() {
+    let uart = MockModem::new(b"OK\r\nOK\r\nOK\r\nCONNECT FAIL\r\n");
+    let mut modem = AtModem::new(uart, AtCommands::SIMCOM);
+
+    assert_eq!(
+        modem.connect("example.com:80").err(),
+        Some(Error::ConnectionRefused)
+    );
+    assert!(modem.is_attached());
+}
+
+#[test]
+fn test_at_modem_invalid_address_and_timeout() {
+    let mut modem = AtModem::new(MockModem::new(b""), AtCommands::SIMCOM).with_max_idle_reads(3);
+
+    assert_eq!(
+        modem.connect("example.com").err(),
+        Some(Error::InvalidAddress)
+    );
+    assert_eq!(
+        modem.connect("example.com:http").err(),
+        Some(Error::InvalidAddress)
+    );
+    assert_eq!(modem.connect("example.com:80").err(), Some(Error::Timeout));
+}
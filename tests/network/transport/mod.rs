@@ -1 +1,3 @@
-
+mod at_modem;
+#[cfg(feature = "serial")]
+mod serial;
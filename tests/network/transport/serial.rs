@@ -0,0 +1,104 @@
+use heapless::Vec;
+use libiot::network::error::Error;
+use libiot::network::transport::serial::SerialConnection;
+use libiot::network::{Close, Read, Write};
+
+/// A scripted UART: serves `rx` byte by byte and records everything written.
+struct MockUart {
+    rx: &'static [u8],
+    pos: usize,
+    tx: Vec<u8, 256>,
+    fail_with: Option<embedded_io::ErrorKind>,
+}
+
+impl MockUart {
+    fn new(rx: &'static [u8]) -> Self {
+        Self {
+            rx,
+            pos: 0,
+            tx: Vec::new(),
+            fail_with: None,
+        }
+    }
+}
+
+impl embedded_io::ErrorType for MockUart {
+    type Error = embedded_io::ErrorKind;
+}
+
+impl embedded_io::Read for MockUart {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if let Some(kind) = self.fail_with {
+            return Err(kind);
+        }
+        let n = buf.len().min(self.rx.len() - self.pos);
+        buf[..n].copy_from_slice(&self.rx[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl embedded_io::ReadReady for MockUart {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.pos < self.rx.len())
+    }
+}
+
+impl embedded_io::Write for MockUart {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if let Some(kind) = self.fail_with {
+            return Err(kind);
+        }
+        self.tx
+            .extend_from_slice(buf)
+            .map_err(|_| embedded_io::ErrorKind::OutOfMemory)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_serial_read_write() {
+    let mut serial = SerialConnection::new(MockUart::new(b"OK\r\n"));
+
+    assert_eq!(serial.write(b"AT\r\n"), Ok(4));
+    assert!(serial.flush().is_ok());
+    assert_eq!(serial.inner().tx.as_slice(), b"AT\r\n");
+
+    let mut buf = [0u8; 8];
+    let n = serial.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"OK\r\n");
+
+    assert!(serial.close().is_ok());
+}
+
+#[test]
+fn test_serial_try_read_would_block() {
+    let mut serial = SerialConnection::new(MockUart::new(b"$"));
+    let mut buf = [0u8; 4];
+
+    assert_eq!(serial.try_read(&mut buf), Ok(1));
+    assert_eq!(serial.try_read(&mut buf), Err(Error::WouldBlock));
+}
+
+#[test]
+fn test_serial_error_mapping() {
+    let mut uart = MockUart::new(b"");
+    uart.fail_with = Some(embedded_io::ErrorKind::TimedOut);
+    let mut serial = SerialConnection::new(uart);
+    let mut buf = [0u8; 4];
+    assert_eq!(serial.read(&mut buf), Err(Error::Timeout));
+
+    serial.inner_mut().fail_with = Some(embedded_io::ErrorKind::Interrupted);
+    assert_eq!(serial.write(b"x"), Err(Error::WouldBlock));
+
+    serial.inner_mut().fail_with = Some(embedded_io::ErrorKind::BrokenPipe);
+    assert_eq!(serial.write(b"x"), Err(Error::ConnectionClosed));
+
+    serial.inner_mut().fail_with = Some(embedded_io::ErrorKind::Other);
+    assert_eq!(serial.read(&mut buf), Err(Error::ReadError));
+    assert_eq!(serial.write(b"x"), Err(Error::WriteError));
+}
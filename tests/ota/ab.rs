@@ -0,0 +1,181 @@
+use super::{ChaosConnection, FIRMWARE_SHA256, Partition, RamStorage, crc32, digest};
+use libiot::network::application::http::client::Client as HttpClient;
+use libiot::ota::ab::{AbPartitions, BootState, RECORD_LEN, Slot};
+use libiot::ota::{Config, Error, HttpSource, Ota, State};
+use libiot::storage::{ReadStorage, Region, Storage};
+
+const METADATA: u32 = 0x2000;
+const HALF: u32 = 0x100;
+
+fn banks() -> AbPartitions<Partition> {
+    AbPartitions::new(
+        Partition {
+            start: 0x0000,
+            end: 0x1000,
+        },
+        Partition {
+            start: 0x1000,
+            end: 0x2000,
+        },
+        Partition {
+            start: METADATA,
+            end: METADATA + 2 * HALF,
+        },
+    )
+}
+
+#[test]
+fn ab_blank_metadata_defaults_to_slot_a() {
+    let mut storage = RamStorage::<0x2200>::new();
+    let banks = banks();
+    assert_eq!(banks.read(&mut storage).unwrap(), BootState::default());
+    assert_eq!(banks.inactive_slot(&mut storage).unwrap(), Slot::B);
+    assert_eq!(banks.slot(Slot::B).start(), 0x1000);
+
+    // Confirming with nothing pending writes nothing
+    banks.confirm(&mut storage).unwrap();
+    assert_eq!(storage.bytes_written, 0);
+}
+
+#[test]
+fn ab_pending_then_confirm_cycle() {
+    let mut storage = RamStorage::<0x2200>::new();
+    let banks = banks();
+
+    // The confirmed slot can't be overwritten
+    assert_eq!(
+        banks.mark_pending(&mut storage, Slot::A),
+        Err(Error::InvalidConfig)
+    );
+
+    banks.mark_pending(&mut storage, Slot::B).unwrap();
+    let state = banks.read(&mut storage).unwrap();
+    assert_eq!(state.confirmed, Slot::A);
+    assert_eq!(state.pending, Some(Slot::B));
+    assert_eq!(state.sequence, 1);
+    // A pending image may be replaced by another download
+    assert_eq!(banks.inactive_slot(&mut storage).unwrap(), Slot::B);
+
+    banks.confirm(&mut storage).unwrap();
+    let state = banks.read(&mut storage).unwrap();
+    assert_eq!(state.confirmed, Slot::B);
+    assert_eq!(state.pending, None);
+    assert_eq!(state.sequence, 2);
+    assert_eq!(banks.inactive_slot(&mut storage).unwrap(), Slot::A);
+
+    // Records alternate between the two halves
+    let mut first = [0u8; RECORD_LEN];
+    let mut second = [0u8; RECORD_LEN];
+    storage.read(METADATA, &mut first).unwrap();
+    storage.read(METADATA + HALF, &mut second).unwrap();
+    assert_eq!(BootState::decode(&first).unwrap().sequence, 1);
+    assert_eq!(BootState::decode(&second).unwrap().sequence, 2);
+
+    // The next update overwrites the older copy
+    banks.mark_pending(&mut storage, Slot::A).unwrap();
+    storage.read(METADATA, &mut first).unwrap();
+    assert_eq!(
+        BootState::decode(&first),
+        Some(BootState {
+            confirmed: Slot::B,
+            pending: Some(Slot::A),
+            sequence: 3,
+        })
+    );
+}
+
+#[test]
+fn ab_corrupted_metadata_falls_back() {
+    let mut storage = RamStorage::<0x2200>::new();
+    let banks = banks();
+    banks.mark_pending(&mut storage, Slot::B).unwrap();
+    banks.confirm(&mut storage).unwrap();
+
+    // A torn write of the newest copy leaves the previous state
+    storage.write(METADATA + HALF + 5, &[0x00]).unwrap();
+    let state = banks.read(&mut storage).unwrap();
+    assert_eq!(state.confirmed, Slot::A);
+    assert_eq!(state.pending, Some(Slot::B));
+
+    // With both copies unreadable, slot A is assumed
+    storage.write(METADATA + 12, &[0x00]).unwrap();
+    assert_eq!(banks.read(&mut storage).unwrap(), BootState::default());
+
+    // Unknown versions are ignored too
+    let mut record = BootState {
+        confirmed: Slot::B,
+        pending: None,
+        sequence: 9,
+    }
+    .encode();
+    assert!(BootState::decode(&record).is_some());
+    record[4] = 2;
+    assert_eq!(BootState::decode(&record), None);
+}
+
+#[test]
+fn ab_rejects_tiny_metadata_region() {
+    let mut storage = RamStorage::<0x2200>::new();
+    let banks = AbPartitions::new(
+        Partition {
+            start: 0x0000,
+            end: 0x1000,
+        },
+        Partition {
+            start: 0x1000,
+            end: 0x2000,
+        },
+        Partition {
+            start: METADATA,
+            end: METADATA + RECORD_LEN as u32,
+        },
+    );
+    assert_eq!(banks.read(&mut storage), Err(Error::InvalidConfig));
+    assert_eq!(
+        banks.mark_pending(&mut storage, Slot::B),
+        Err(Error::InvalidConfig)
+    );
+}
+
+#[test]
+fn ab_http_update_flips_active_slot() {
+    let firmware: std::vec::Vec<u8> = (0..4 * 1024).map(|i| (i % 251) as u8).collect();
+    let mut storage = RamStorage::<0x2200>::new();
+    // The running image in slot A must survive the update
+    storage.write(0x0000, &[0x5A; 64]).unwrap();
+    let banks = banks();
+
+    let target = banks.inactive_slot(&mut storage).unwrap();
+    assert_eq!(target, Slot::B);
+
+    let src = HttpSource {
+        host: "example.com",
+        path: "/fw.bin",
+        size: firmware.len(),
+        crc32: Some(crc32(&firmware)),
+        expected_sha256: Some(digest(FIRMWARE_SHA256)),
+    };
+    let mut http = HttpClient::new(ChaosConnection::new(&firmware, 4, 300));
+    let mut ota = Ota::new(Config::default()).unwrap();
+    ota.run_http_in_region(
+        &mut http,
+        &mut storage,
+        banks.slot(target),
+        banks.slot(target).start(),
+        &src,
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(ota.state(), State::Completed);
+
+    banks.mark_pending(&mut storage, target).unwrap();
+    banks.confirm(&mut storage).unwrap();
+
+    let state = banks.read(&mut storage).unwrap();
+    assert_eq!(state.confirmed, Slot::B);
+    assert_eq!(state.pending, None);
+    assert_eq!(&storage.buf[0x1000..0x2000], &firmware[..]);
+    assert_eq!(&storage.buf[..64], &[0x5A; 64]);
+    assert_eq!(banks.inactive_slot(&mut storage).unwrap(), Slot::A);
+}
@@ -0,0 +1,126 @@
+use libiot::ota::Error;
+use libiot::ota::job::Job;
+
+const JOB_DOC: &str = r#"{
+    "afr_ota": {
+        "protocols": ["HTTP"],
+        "streamname": "AFR_OTA-42",
+        "files": [
+            {
+                "filepath": "/fw/bootloader.bin",
+                "filesize": 4096,
+                "fileid": 1,
+                "update_data_url": "http://cdn.example.com:8080/boot.bin"
+            },
+            {
+                "filepath": "/fw/app.bin",
+                "filesize": 65536,
+                "fileid": 7,
+                "update_data_url": "https://updates.example.com/fw/app.bin?sig=abc",
+                "crc32": 3735928559,
+                "sha256": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+            }
+        ]
+    }
+}"#;
+
+#[test]
+fn ota_job_parses_first_file() {
+    let job = Job::parse(JOB_DOC).unwrap();
+    assert_eq!(job.stream, Some("AFR_OTA-42"));
+    assert_eq!(job.file_id, 1);
+    assert_eq!(job.target_path, "/fw/bootloader.bin");
+    assert_eq!(job.source.host, "cdn.example.com:8080");
+    assert_eq!(job.source.path, "/boot.bin");
+    assert_eq!(job.source.size, 4096);
+    assert_eq!(job.source.crc32, None);
+    assert!(!job.config.verify_crc32);
+    assert_eq!(job.sha256, None);
+    assert!(!job.tls);
+}
+
+#[test]
+fn ota_job_selects_file_by_id() {
+    let job = Job::parse_file_id_tls(JOB_DOC, 7).unwrap();
+    assert!(job.tls);
+    assert_eq!(job.target_path, "/fw/app.bin");
+    assert_eq!(job.source.host, "updates.example.com");
+    assert_eq!(job.source.path, "/fw/app.bin?sig=abc");
+    assert_eq!(job.source.size, 65536);
+    assert_eq!(job.source.crc32, Some(0xDEAD_BEEF));
+    assert!(job.config.verify_crc32);
+    assert_eq!(job.config.chunk_size, 1024);
+    assert!(job.sha256.unwrap().starts_with("9f86d081"));
+    let expected_sha256 = job.source.expected_sha256.unwrap();
+    assert_eq!(expected_sha256[..4], [0x9f, 0x86, 0xd0, 0x81]);
+    assert_eq!(expected_sha256[31], 0x08);
+
+    assert_eq!(
+        Job::parse_file_id(JOB_DOC, 3).err(),
+        Some(Error::InvalidConfig)
+    );
+}
+
+#[test]
+fn ota_job_rejects_https_without_tls() {
+    // The plain parsers never downgrade an https:// URL to plain HTTP
+    assert_eq!(
+        Job::parse_file_id(JOB_DOC, 7).err(),
+        Some(Error::InvalidConfig)
+    );
+    let payload = br#"{"afr_ota":{"files":[{"filepath":"/a","filesize":10,"update_data_url":"https://h/a"}]}}"#;
+    assert_eq!(Job::from_bytes(payload).err(), Some(Error::InvalidConfig));
+
+    // Plain URLs still parse with TLS available
+    let job = Job::parse_tls(JOB_DOC).unwrap();
+    assert_eq!(job.file_id, 1);
+    assert!(!job.tls);
+}
+
+#[test]
+fn ota_job_from_mqtt_payload() {
+    let payload =
+        br#"{"afr_ota":{"files":[{"filepath":"/a","filesize":10,"update_data_url":"http://h"}]}}"#;
+    let job = Job::from_bytes(payload).unwrap();
+    assert_eq!(job.stream, None);
+    assert_eq!(job.source.host, "h");
+    assert_eq!(job.source.path, "/");
+}
+
+#[test]
+fn ota_job_rejects_invalid_documents() {
+    assert_eq!(Job::parse("not json").err(), Some(Error::Protocol));
+    assert_eq!(
+        Job::parse(r#"{"afr_ota":{"files":[]}}"#).err(),
+        Some(Error::InvalidConfig)
+    );
+    // No download URL
+    assert_eq!(
+        Job::parse(r#"{"afr_ota":{"files":[{"filepath":"/a","filesize":10}]}}"#).err(),
+        Some(Error::InvalidConfig)
+    );
+    // Unsupported scheme
+    assert_eq!(
+        Job::parse(
+            r#"{"afr_ota":{"files":[{"filepath":"/a","filesize":10,"update_data_url":"ftp://h/a"}]}}"#
+        )
+        .err(),
+        Some(Error::InvalidConfig)
+    );
+    // Zero-sized image
+    assert_eq!(
+        Job::parse(
+            r#"{"afr_ota":{"files":[{"filepath":"/a","filesize":0,"update_data_url":"http://h/a"}]}}"#
+        )
+        .err(),
+        Some(Error::InvalidConfig)
+    );
+    // Digest that is not 64 hex digits
+    assert_eq!(
+        Job::parse(
+            r#"{"afr_ota":{"files":[{"filepath":"/a","filesize":10,"update_data_url":"http://h/a","sha256":"9f86d081"}]}}"#
+        )
+        .err(),
+        Some(Error::InvalidConfig)
+    );
+}
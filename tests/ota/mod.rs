@@ -1,6 +1,15 @@
+mod ab;
+mod job;
+
+use crate::network::application::mqtt::mock::ScriptedConnection;
 use libiot::network::application::http::client::Client as HttpClient;
+use libiot::network::application::mqtt::client::{
+    Client as MqttClient, QoS, encode_remaining_length,
+};
 use libiot::network::{Close, Connection, Read, Write};
-use libiot::ota::{Config, HttpSource, Ota};
+use libiot::ota::{
+    Config, Error as OtaError, HttpSource, MqttProgress, MqttSource, Ota, Progress, State, Verifier,
+};
 use libiot::storage::{BlockingErase, Storage};
 
 // -------------------------
@@ -10,11 +19,17 @@ use libiot::storage::{BlockingErase, Storage};
 #[derive(Debug)]
 struct RamStorage<const N: usize> {
     buf: [u8; N],
+    syncs: usize,
+    bytes_written: usize,
 }
 
 impl<const N: usize> RamStorage<N> {
     fn new() -> Self {
-        Self { buf: [0xFF; N] }
+        Self {
+            buf: [0xFF; N],
+            syncs: 0,
+            bytes_written: 0,
+        }
     }
 }
 
@@ -40,6 +55,12 @@ impl<const N: usize> Storage for RamStorage<N> {
             return Err(libiot::storage::error::Error::OutOfBounds);
         }
         self.buf[off..off + bytes.len()].copy_from_slice(bytes);
+        self.bytes_written += bytes.len();
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<(), Self::Error> {
+        self.syncs += 1;
         Ok(())
     }
 }
@@ -71,6 +92,16 @@ struct ChaosConnection {
     partial_max: usize,
     read_count: usize,
     delivered_in_phase: usize,
+    /// Number of upcoming writes that fail, simulating a dropped link
+    fail_writes: usize,
+    /// Answer every request with the whole object, like a server without
+    /// Range support
+    ignore_range: bool,
+    /// Whether full-body responses declare their Content-Length
+    content_length: bool,
+    /// Fail a read once this many bytes of the current response have been
+    /// delivered, dropping the rest of it like a broken link
+    fail_read_at: Option<usize>,
 }
 
 impl ChaosConnection {
@@ -83,6 +114,10 @@ impl ChaosConnection {
             partial_max,
             read_count: 0,
             delivered_in_phase: 0,
+            fail_writes: 0,
+            ignore_range: false,
+            content_length: true,
+            fail_read_at: None,
         }
     }
 
@@ -114,12 +149,16 @@ impl ChaosConnection {
                 }
             }
         }
+        if self.ignore_range {
+            range = None;
+        }
         let (status, body, content_range) = if let Some((s, e)) = range {
             (206u16, &self.object[s..=e], Some((s, e, self.object.len())))
         } else {
             (200u16, &self.object[..], None)
         };
-        let resp = build_http_response(status, body, true, content_range);
+        let content_length = self.content_length || status == 206;
+        let resp = build_http_response(status, body, content_length, content_range);
         self.incoming.extend_from_slice(&resp);
         self.written.clear();
         self.delivered_in_phase = 0;
@@ -133,10 +172,18 @@ impl Read for ChaosConnection {
         if self.incoming.is_empty() {
             return Ok(0);
         }
+        if self.fail_read_at == Some(self.delivered_in_phase) {
+            self.fail_read_at = None;
+            self.incoming.clear();
+            return Err(libiot::network::error::Error::ReadError);
+        }
         let mut n = core::cmp::min(
             buf.len(),
             core::cmp::min(self.incoming.len(), self.partial_max.max(1)),
         );
+        if let Some(at) = self.fail_read_at {
+            n = n.min(at - self.delivered_in_phase);
+        }
         // Simulate jitter by occasionally limiting to 1 byte instead of returning 0
         if self.drop_every > 0
             && self.read_count % self.drop_every == 0
@@ -154,6 +201,10 @@ impl Read for ChaosConnection {
 impl Write for ChaosConnection {
     type Error = libiot::network::error::Error;
     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if self.fail_writes > 0 {
+            self.fail_writes -= 1;
+            return Err(libiot::network::error::Error::WriteError);
+        }
         self.written.extend_from_slice(buf);
         self.prepare_response_for_request();
         Ok(buf.len())
@@ -212,6 +263,7 @@ fn ota_http_download_with_jittery_network() {
         chunk_size: 1024,
         erase_before_write: true,
         verify_crc32: false,
+        ..Config::default()
     };
     let mut ota = Ota::new(cfg).unwrap();
 
@@ -220,19 +272,16 @@ fn ota_http_download_with_jittery_network() {
         path: "/fw.bin",
         size: firmware.len(),
         crc32: None,
+        expected_sha256: None,
     };
-    ota.run_http(
-        &mut http,
-        &mut storage,
-        0,
-        &src,
-        None::<&mut libiot::ota::MqttProgress<'_, ChaosConnection>>,
-    )
-    .unwrap();
+    ota.run_http(&mut http, &mut storage, 0, &src, None, None)
+        .unwrap();
 
     let mut read_back = vec![0u8; firmware.len()];
     libiot::storage::ReadStorage::read(&mut storage, 0, &mut read_back).unwrap();
     assert_eq!(read_back, firmware);
+    // The image is made durable once, before reporting completion
+    assert_eq!(storage.syncs, 1);
 }
 
 #[test]
@@ -254,6 +303,7 @@ fn ota_http_download_large_hex_like_payload_with_jitter() {
         chunk_size: 1024,
         erase_before_write: true,
         verify_crc32: false,
+        ..Config::default()
     };
     let mut ota = Ota::new(cfg).unwrap();
     let src = HttpSource {
@@ -261,16 +311,11 @@ fn ota_http_download_large_hex_like_payload_with_jitter() {
         path: "/resources/firmware/STM32F4DISC-20250415-v1.25.0.hex",
         size: body_bytes.len(),
         crc32: None,
+        expected_sha256: None,
     };
 
-    ota.run_http(
-        &mut http,
-        &mut *storage,
-        0,
-        &src,
-        None::<&mut libiot::ota::MqttProgress<'_, ChaosConnection>>,
-    )
-    .unwrap();
+    ota.run_http(&mut http, &mut *storage, 0, &src, None, None)
+        .unwrap();
 
     let mut read_back = vec![0u8; body_bytes.len()];
     libiot::storage::ReadStorage::read(&mut *storage, 0, &mut read_back).unwrap();
@@ -281,3 +326,820 @@ fn ota_http_download_large_hex_like_payload_with_jitter() {
         &body_bytes[body_bytes.len() - 256..]
     );
 }
+
+struct Partition {
+    start: u32,
+    end: u32,
+}
+
+impl libiot::storage::Region for Partition {
+    fn start(&self) -> u32 {
+        self.start
+    }
+
+    fn end(&self) -> u32 {
+        self.end
+    }
+}
+
+#[test]
+fn ota_http_refuses_to_cross_region_boundary() {
+    let firmware = vec![0xA5u8; 4 * 1024];
+    let mut storage = RamStorage::<{ 16 * 1024 }>::new();
+    // Pretend the running image lives right after the target partition
+    storage.write(0x2000, &[0x00; 16]).unwrap();
+
+    let partition = Partition {
+        start: 0x1000,
+        end: 0x2000,
+    };
+    let src = HttpSource {
+        host: "example.com",
+        path: "/fw.bin",
+        size: firmware.len(),
+        crc32: None,
+        expected_sha256: None,
+    };
+    let cfg = Config {
+        chunk_size: 1024,
+        erase_before_write: true,
+        verify_crc32: false,
+        ..Config::default()
+    };
+
+    // 0x1800 + 4 KiB fits the device but overruns the partition
+    let mut http = HttpClient::new(ChaosConnection::new(&firmware, 0, 512));
+    let mut ota = Ota::new(cfg).unwrap();
+    let result = ota.run_http_in_region(
+        &mut http,
+        &mut storage,
+        &partition,
+        0x1800,
+        &src,
+        None,
+        None,
+    );
+    assert_eq!(result, Err(libiot::ota::Error::InvalidConfig));
+    assert_eq!(ota.state(), libiot::ota::State::Failed);
+
+    // Nothing was erased
+    let mut guard = [0xFFu8; 16];
+    libiot::storage::ReadStorage::read(&mut storage, 0x2000, &mut guard).unwrap();
+    assert_eq!(guard, [0x00; 16]);
+
+    // Starting before the partition is rejected too
+    let mut ota = Ota::new(cfg).unwrap();
+    let result = ota.run_http_in_region(
+        &mut http,
+        &mut storage,
+        &partition,
+        0x0800,
+        &src,
+        None,
+        None,
+    );
+    assert_eq!(result, Err(libiot::ota::Error::InvalidConfig));
+
+    // An image that exactly fills the partition is accepted
+    let mut ota = Ota::new(cfg).unwrap();
+    ota.run_http_in_region(
+        &mut http,
+        &mut storage,
+        &partition,
+        0x1000,
+        &src,
+        None,
+        None,
+    )
+    .unwrap();
+    let mut read_back = vec![0u8; firmware.len()];
+    libiot::storage::ReadStorage::read(&mut storage, 0x1000, &mut read_back).unwrap();
+    assert_eq!(read_back, firmware);
+    libiot::storage::ReadStorage::read(&mut storage, 0x2000, &mut guard).unwrap();
+    assert_eq!(guard, [0x00; 16]);
+}
+
+/// Bitwise CRC32 (IEEE) reference for checking the driver's running checksum
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// SHA-256 of the 4 KiB image whose byte `i` is `i % 251`
+const FIRMWARE_SHA256: &str = "d67c656e01756650d77717b0839985a056ec28ffe174601d690fc407a2ceffca";
+
+fn digest(hex: &str) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    for (i, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+    }
+    digest
+}
+
+static PAUSE_POLLS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// Ask for a pause on the third poll: once at the start of `run_http`, once
+/// before the first chunk and once before the second.
+fn pause_after_first_chunk() -> bool {
+    PAUSE_POLLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 2
+}
+
+#[test]
+fn ota_http_pause_and_resume_keeps_progress() {
+    let firmware: std::vec::Vec<u8> = (0..4 * 1024).map(|i| (i % 251) as u8).collect();
+    let mut storage = RamStorage::<{ 8 * 1024 }>::new();
+    let src = HttpSource {
+        host: "example.com",
+        path: "/fw.bin",
+        size: firmware.len(),
+        crc32: Some(crc32(&firmware)),
+        expected_sha256: Some(digest(FIRMWARE_SHA256)),
+    };
+    let cfg = Config {
+        chunk_size: 1024,
+        erase_before_write: true,
+        verify_crc32: true,
+        ..Config::default()
+    };
+    let mut ota = Ota::new(cfg).unwrap();
+    ota.set_pause_check(Some(pause_after_first_chunk));
+
+    let mut http = HttpClient::new(ChaosConnection::new(&firmware, 0, 512));
+    let result = ota.run_http(&mut http, &mut storage, 0, &src, None, None);
+    assert_eq!(result, Err(OtaError::Paused));
+    assert_eq!(ota.state(), State::Paused);
+    assert_eq!(ota.paused_at(), Some(1024));
+    assert_eq!(&storage.buf[..1024], &firmware[..1024]);
+    assert!(storage.buf[1024..].iter().all(|&b| b == 0xFF));
+
+    // A manual pause holds the session until resumed
+    ota.pause();
+    let result = ota.run_http(&mut http, &mut storage, 0, &src, None, None);
+    assert_eq!(result, Err(OtaError::Paused));
+    assert!(ota.resume());
+
+    // Continuing must not erase the first chunk and must finish the CRC
+    ota.run_http(&mut http, &mut storage, 0, &src, None, None)
+        .unwrap();
+    assert_eq!(ota.state(), State::Completed);
+    assert_eq!(ota.paused_at(), None);
+    assert_eq!(&storage.buf[..firmware.len()], &firmware[..]);
+    assert!(!ota.resume());
+}
+
+#[test]
+fn ota_http_accepts_full_body_when_allowed() {
+    let firmware: std::vec::Vec<u8> = (0..4 * 1024).map(|i| (i % 251) as u8).collect();
+    let src = HttpSource {
+        host: "example.com",
+        path: "/fw.bin",
+        size: firmware.len(),
+        crc32: Some(crc32(&firmware)),
+        expected_sha256: Some(digest(FIRMWARE_SHA256)),
+    };
+    let cfg = Config {
+        chunk_size: 512,
+        allow_full_body: true,
+        ..Config::default()
+    };
+    let full_body = |content_length: bool| {
+        let mut chaos = ChaosConnection::new(&firmware, 3, 200);
+        chaos.ignore_range = true;
+        chaos.content_length = content_length;
+        HttpClient::new(chaos)
+    };
+
+    // Rejected by default
+    let mut storage = RamStorage::<{ 8 * 1024 }>::new();
+    let mut ota = Ota::new(Config {
+        allow_full_body: false,
+        ..cfg
+    })
+    .unwrap();
+    assert_eq!(
+        ota.run_http(&mut full_body(true), &mut storage, 0, &src, None, None),
+        Err(OtaError::Network(
+            libiot::network::error::Error::ProtocolError
+        ))
+    );
+
+    // Streamed in chunk_size slices, with or without a Content-Length
+    for content_length in [true, false] {
+        let mut storage = RamStorage::<{ 8 * 1024 }>::new();
+        let mut slices = 0;
+        let mut ota = Ota::new(cfg).unwrap();
+        ota.run_http(
+            &mut full_body(content_length),
+            &mut storage,
+            0x100,
+            &src,
+            None,
+            Some(&mut |progress: Progress| {
+                if progress.state == State::Downloading && progress.bytes_downloaded > 0 {
+                    slices += 1;
+                }
+            }),
+        )
+        .unwrap();
+        assert_eq!(ota.state(), State::Completed);
+        assert_eq!(&storage.buf[0x100..0x100 + firmware.len()], &firmware[..]);
+        assert_eq!(slices, firmware.len() / cfg.chunk_size);
+    }
+
+    // Ranged responses are still used when the server honors the range
+    let mut storage = RamStorage::<{ 8 * 1024 }>::new();
+    let mut http = HttpClient::new(ChaosConnection::new(&firmware, 3, 200));
+    let mut ota = Ota::new(cfg).unwrap();
+    ota.run_http(&mut http, &mut storage, 0, &src, None, None)
+        .unwrap();
+    assert_eq!(&storage.buf[..firmware.len()], &firmware[..]);
+    assert!(http.is_reusable());
+}
+
+#[test]
+fn ota_http_full_body_must_match_size() {
+    let firmware: std::vec::Vec<u8> = (0..4 * 1024).map(|i| (i % 251) as u8).collect();
+    let cfg = Config {
+        chunk_size: 512,
+        verify_crc32: false,
+        allow_full_body: true,
+        ..Config::default()
+    };
+
+    for content_length in [true, false] {
+        for size in [firmware.len() - 100, firmware.len() + 100] {
+            let src = HttpSource {
+                host: "example.com",
+                path: "/fw.bin",
+                size,
+                crc32: None,
+                expected_sha256: None,
+            };
+            let mut chaos = ChaosConnection::new(&firmware, 0, 512);
+            chaos.ignore_range = true;
+            chaos.content_length = content_length;
+            let mut http = HttpClient::new(chaos);
+            let mut storage = RamStorage::<{ 8 * 1024 }>::new();
+            let mut ota = Ota::new(cfg).unwrap();
+            assert_eq!(
+                ota.run_http(&mut http, &mut storage, 0, &src, None, None),
+                Err(OtaError::Network(
+                    libiot::network::error::Error::ProtocolError
+                )),
+                "size {size}, content_length {content_length}"
+            );
+            assert_eq!(ota.state(), State::Failed);
+        }
+    }
+}
+
+#[test]
+fn ota_http_retries_body_read_errors() {
+    let firmware: std::vec::Vec<u8> = (0..4 * 1024).map(|i| (i % 251) as u8).collect();
+    let src = HttpSource {
+        host: "example.com",
+        path: "/fw.bin",
+        size: firmware.len(),
+        crc32: Some(crc32(&firmware)),
+        expected_sha256: Some(digest(FIRMWARE_SHA256)),
+    };
+    let cfg = Config {
+        chunk_size: 512,
+        allow_full_body: true,
+        ..Config::default()
+    };
+
+    // The link drops two slices into a full body, and partway through a
+    // ranged body
+    for (ignore_range, fail_at) in [(true, 1500), (false, 300)] {
+        let mut chaos = ChaosConnection::new(&firmware, 0, 512);
+        chaos.ignore_range = ignore_range;
+        chaos.fail_read_at = Some(fail_at);
+        let mut http = HttpClient::new(chaos);
+        let mut storage = RamStorage::<{ 8 * 1024 }>::new();
+        let mut ota = Ota::new(cfg).unwrap();
+        ota.run_http(&mut http, &mut storage, 0, &src, None, None)
+            .unwrap();
+        assert_eq!(ota.state(), State::Completed);
+        assert_eq!(&storage.buf[..firmware.len()], &firmware[..]);
+        // The retry picks up where the stored bytes end
+        assert_eq!(storage.bytes_written, firmware.len());
+    }
+}
+
+static RETRY_DELAYS: std::sync::Mutex<std::vec::Vec<(u8, u32)>> =
+    std::sync::Mutex::new(std::vec::Vec::new());
+
+fn record_retry_delay(retry: u8, delay_ms: u32) {
+    RETRY_DELAYS.lock().unwrap().push((retry, delay_ms));
+}
+
+#[test]
+fn ota_http_retries_with_delay() {
+    let firmware: std::vec::Vec<u8> = (0..2 * 1024).map(|i| (i % 251) as u8).collect();
+    let src = HttpSource {
+        host: "example.com",
+        path: "/fw.bin",
+        size: firmware.len(),
+        crc32: Some(crc32(&firmware)),
+        expected_sha256: None,
+    };
+    let cfg = Config {
+        max_retries: 2,
+        retry_delay_ms: Some(250),
+        ..Config::default()
+    };
+
+    // The first two attempts fail, the third succeeds
+    let mut chaos = ChaosConnection::new(&firmware, 0, 512);
+    chaos.fail_writes = 2;
+    let mut http = HttpClient::new(chaos);
+    let mut storage = RamStorage::<{ 4 * 1024 }>::new();
+    let mut ota = Ota::new(cfg).unwrap();
+    ota.set_retry_delay(Some(record_retry_delay));
+    ota.run_http(&mut http, &mut storage, 0, &src, None, None)
+        .unwrap();
+    assert_eq!(ota.state(), State::Completed);
+    assert_eq!(&storage.buf[..firmware.len()], &firmware[..]);
+    assert_eq!(*RETRY_DELAYS.lock().unwrap(), [(1, 250), (2, 250)]);
+
+    // Once the retries are used up the last network error is returned
+    RETRY_DELAYS.lock().unwrap().clear();
+    let mut chaos = ChaosConnection::new(&firmware, 0, 512);
+    chaos.fail_writes = 3;
+    let mut http = HttpClient::new(chaos);
+    let mut ota = Ota::new(cfg).unwrap();
+    ota.set_retry_delay(Some(record_retry_delay));
+    assert_eq!(
+        ota.run_http(&mut http, &mut storage, 0, &src, None, None),
+        Err(OtaError::Network(libiot::network::error::Error::WriteError))
+    );
+    assert_eq!(ota.state(), State::Failed);
+    assert_eq!(RETRY_DELAYS.lock().unwrap().len(), 2);
+
+    // Without a delay configured the hook is not called
+    RETRY_DELAYS.lock().unwrap().clear();
+    let mut chaos = ChaosConnection::new(&firmware, 0, 512);
+    chaos.fail_writes = 1;
+    let mut http = HttpClient::new(chaos);
+    let mut ota = Ota::new(Config::default()).unwrap();
+    ota.set_retry_delay(Some(record_retry_delay));
+    ota.run_http(&mut http, &mut storage, 0, &src, None, None)
+        .unwrap();
+    assert!(RETRY_DELAYS.lock().unwrap().is_empty());
+}
+
+#[test]
+fn ota_crc32_checkpoints() {
+    use libiot::ota::Crc32;
+
+    let data: std::vec::Vec<u8> = (0..1000u32).map(|i| (i * 7 % 256) as u8).collect();
+    let mut whole = Crc32::new();
+    assert_eq!(whole.value(), 0xFFFF_FFFF);
+    assert_eq!(whole.finalize(), 0);
+    whole.update(&data);
+    assert_eq!(whole.finalize(), crc32(&data));
+    assert_eq!(whole.finalize(), !whole.value());
+
+    // Any split point, checkpointed through `value`, gives the same result
+    for split in [0, 1, 333, 999, 1000] {
+        let mut head = Crc32::new();
+        head.update(&data[..split]);
+        assert_eq!(head.finalize(), crc32(&data[..split]));
+
+        let mut tail = Crc32::resume(head.value());
+        tail.update(&data[split..]);
+        assert_eq!(tail.value(), whole.value());
+        assert_eq!(tail.finalize(), whole.finalize());
+    }
+}
+
+#[test]
+fn ota_sha256_known_digests() {
+    use libiot::ota::Sha256;
+
+    assert_eq!(
+        Sha256::new().finalize(),
+        digest("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+    );
+
+    // Two blocks once padded
+    let mut sha = Sha256::new();
+    sha.update(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq");
+    assert_eq!(
+        sha.finalize(),
+        digest("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1")
+    );
+
+    // Fed in uneven pieces, with the digest also taken mid-stream
+    let mut sha = Sha256::new();
+    for piece in [1, 63, 64, 65, 300, 507] {
+        sha.update(&[b'a'; 507][..piece]);
+        sha.finalize();
+    }
+    assert_eq!(
+        sha.finalize(),
+        digest("41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3")
+    );
+}
+
+#[test]
+fn ota_http_verifies_sha256() {
+    let firmware: std::vec::Vec<u8> = (0..4 * 1024).map(|i| (i % 251) as u8).collect();
+    let mut tampered = digest(FIRMWARE_SHA256);
+    tampered[31] ^= 1;
+    let cfg = Config {
+        chunk_size: 1024,
+        erase_before_write: true,
+        verify_crc32: true,
+        ..Config::default()
+    };
+
+    // CRC32 and SHA-256 are checked independently; both must match
+    let cases = [
+        (None, Some(digest(FIRMWARE_SHA256)), Ok(())),
+        (None, Some(tampered), Err(OtaError::VerifyFailed)),
+        (
+            Some(crc32(&firmware)),
+            Some(digest(FIRMWARE_SHA256)),
+            Ok(()),
+        ),
+        (
+            Some(crc32(&firmware)),
+            Some(tampered),
+            Err(OtaError::VerifyFailed),
+        ),
+        (
+            Some(!crc32(&firmware)),
+            Some(digest(FIRMWARE_SHA256)),
+            Err(OtaError::VerifyFailed),
+        ),
+    ];
+    for (crc, sha256, expected) in cases {
+        let src = HttpSource {
+            host: "example.com",
+            path: "/fw.bin",
+            size: firmware.len(),
+            crc32: crc,
+            expected_sha256: sha256,
+        };
+        let mut storage = RamStorage::<{ 8 * 1024 }>::new();
+        let mut http = HttpClient::new(ChaosConnection::new(&firmware, 3, 300));
+        let mut ota = Ota::new(cfg).unwrap();
+        let result = ota.run_http(&mut http, &mut storage, 0, &src, None, None);
+        assert_eq!(result, expected);
+        let state = if expected.is_ok() {
+            State::Completed
+        } else {
+            State::Failed
+        };
+        assert_eq!(ota.state(), state);
+    }
+}
+
+/// Counts the bytes it is fed and checks them against the image
+struct CountingVerifier<'a> {
+    image: &'a [u8],
+    bytes: usize,
+    finalized: bool,
+}
+
+impl Verifier for CountingVerifier<'_> {
+    fn update(&mut self, data: &[u8]) {
+        // Chunks arrive in ascending offset order
+        assert_eq!(data, &self.image[self.bytes..self.bytes + data.len()]);
+        self.bytes += data.len();
+    }
+
+    fn finalize(&mut self) -> Result<(), OtaError> {
+        self.finalized = true;
+        if self.bytes == self.image.len() {
+            Ok(())
+        } else {
+            Err(OtaError::VerifyFailed)
+        }
+    }
+}
+
+#[test]
+fn ota_http_custom_verifier() {
+    use libiot::ota::Crc32;
+
+    let firmware: std::vec::Vec<u8> = (0..4 * 1024).map(|i| (i % 251) as u8).collect();
+    let src = HttpSource {
+        host: "example.com",
+        path: "/fw.bin",
+        size: firmware.len(),
+        crc32: None,
+        expected_sha256: None,
+    };
+    let download = |verifier: &mut dyn Verifier| {
+        let mut storage = RamStorage::<{ 8 * 1024 }>::new();
+        let mut http = HttpClient::new(ChaosConnection::new(&firmware, 3, 300));
+        let mut ota = Ota::new(Config {
+            chunk_size: 1000,
+            ..Config::default()
+        })
+        .unwrap();
+        let result = ota.run_http(&mut http, &mut storage, 0, &src, Some(verifier), None);
+        (result, ota.state())
+    };
+
+    let mut counter = CountingVerifier {
+        image: &firmware,
+        bytes: 0,
+        finalized: false,
+    };
+    assert_eq!(download(&mut counter), (Ok(()), State::Completed));
+    assert_eq!(counter.bytes, firmware.len());
+    assert!(counter.finalized);
+
+    // The verifier's error fails the download
+    struct Reject;
+    impl Verifier for Reject {
+        fn update(&mut self, _data: &[u8]) {}
+        fn finalize(&mut self) -> Result<(), OtaError> {
+            Err(OtaError::Protocol)
+        }
+    }
+    assert_eq!(
+        download(&mut Reject),
+        (Err(OtaError::Protocol), State::Failed)
+    );
+
+    // The built-in CRC32 as a verifier
+    assert_eq!(
+        download(&mut Crc32::expecting(crc32(&firmware))),
+        (Ok(()), State::Completed)
+    );
+    assert_eq!(
+        download(&mut Crc32::expecting(!crc32(&firmware))),
+        (Err(OtaError::VerifyFailed), State::Failed)
+    );
+    assert_eq!(download(&mut Crc32::new()), (Ok(()), State::Completed));
+}
+
+#[test]
+fn ota_http_reports_progress_per_chunk() {
+    let firmware: std::vec::Vec<u8> = (0..4 * 1024).map(|i| (i % 251) as u8).collect();
+    let src = HttpSource {
+        host: "example.com",
+        path: "/fw.bin",
+        size: firmware.len(),
+        crc32: Some(crc32(&firmware)),
+        expected_sha256: None,
+    };
+    let cfg = Config {
+        chunk_size: 512,
+        erase_before_write: true,
+        verify_crc32: true,
+        ..Config::default()
+    };
+
+    let mut reports = std::vec::Vec::new();
+    let mut storage = RamStorage::<{ 8 * 1024 }>::new();
+    let mut http = HttpClient::new(ChaosConnection::new(&firmware, 3, 200));
+    let mut ota = Ota::new(cfg).unwrap();
+    ota.run_http(
+        &mut http,
+        &mut storage,
+        0,
+        &src,
+        None,
+        Some(&mut |progress: Progress| reports.push(progress)),
+    )
+    .unwrap();
+
+    let chunks = firmware.len() / cfg.chunk_size;
+    let downloading: std::vec::Vec<_> = reports
+        .iter()
+        .filter(|p| p.state == State::Downloading)
+        .map(|p| p.bytes_downloaded)
+        .collect();
+    // One report as the download starts, then one per chunk
+    assert_eq!(downloading.len(), chunks + 1);
+    assert!(
+        downloading
+            .windows(2)
+            .all(|w| w[1] == w[0] + cfg.chunk_size)
+    );
+    let states: std::vec::Vec<_> = reports
+        .iter()
+        .map(|p| p.state)
+        .filter(|&s| s != State::Downloading)
+        .collect();
+    assert_eq!(
+        states,
+        [
+            State::Erasing,
+            State::Verifying,
+            State::Finalizing,
+            State::Completed
+        ]
+    );
+    assert!(reports.iter().all(|p| p.bytes_total == firmware.len()));
+
+    // A failure is reported with the progress made so far
+    let bad = HttpSource {
+        crc32: Some(!crc32(&firmware)),
+        ..src
+    };
+    let mut last = None;
+    let mut http = HttpClient::new(ChaosConnection::new(&firmware, 0, 512));
+    let mut ota = Ota::new(cfg).unwrap();
+    let result = ota.run_http(
+        &mut http,
+        &mut storage,
+        0,
+        &bad,
+        None,
+        Some(&mut |progress: Progress| last = Some(progress)),
+    );
+    assert_eq!(result, Err(OtaError::VerifyFailed));
+    assert_eq!(
+        last,
+        Some(Progress {
+            bytes_total: firmware.len(),
+            bytes_downloaded: firmware.len(),
+            state: State::Failed,
+        })
+    );
+}
+
+#[test]
+fn ota_http_publishes_progress_over_mqtt() {
+    let firmware = vec![0x3Cu8; 1024];
+    let src = HttpSource {
+        host: "example.com",
+        path: "/fw.bin",
+        size: firmware.len(),
+        crc32: None,
+        expected_sha256: None,
+    };
+    let conn = ScriptedConnection::new();
+    let mut mqtt = MqttClient::<_>::from_connected(conn.clone());
+    let mut progress = MqttProgress::new(&mut mqtt, "devices/42/ota");
+
+    let mut storage = RamStorage::<{ 2 * 1024 }>::new();
+    let mut http = HttpClient::new(ChaosConnection::new(&firmware, 0, 512));
+    let mut ota = Ota::new(Config::default()).unwrap();
+    ota.run_http(
+        &mut http,
+        &mut storage,
+        0,
+        &src,
+        None,
+        Some(&mut progress.callback()),
+    )
+    .unwrap();
+
+    let written = String::from_utf8_lossy(&conn.take_written()).into_owned();
+    assert_eq!(written.matches("devices/42/ota").count(), 6);
+    assert!(written.contains(r#"{"bytes":1024,"total":1024,"state":"completed"}"#));
+}
+
+/// A QoS 0 PUBLISH of one firmware chunk at `offset`
+fn chunk_publish(topic: &str, offset: u32, data: &[u8]) -> std::vec::Vec<u8> {
+    let mut body = std::vec::Vec::new();
+    body.extend_from_slice(&(topic.len() as u16).to_be_bytes());
+    body.extend_from_slice(topic.as_bytes());
+    body.extend_from_slice(&offset.to_be_bytes());
+    body.extend_from_slice(data);
+    let mut packet = vec![0x30];
+    packet.extend_from_slice(&encode_remaining_length(body.len()).unwrap());
+    packet.extend_from_slice(&body);
+    packet
+}
+
+#[test]
+fn ota_mqtt_reassembles_chunks() {
+    const TOPIC: &str = "devices/42/firmware";
+    let firmware: std::vec::Vec<u8> = (0..2000).map(|i| (i % 251) as u8).collect();
+    let chunk =
+        |start: usize, end: usize| chunk_publish(TOPIC, start as u32, &firmware[start..end]);
+
+    let conn = ScriptedConnection::new();
+    // SUBACK granting QoS 1
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x01]);
+    for packet in [
+        chunk(300, 600),
+        chunk(0, 300),
+        chunk_publish("devices/42/other", 0, &[0; 16]),
+        // Duplicate
+        chunk(0, 300),
+        chunk(900, 1200),
+        // Overlaps the chunks on both sides
+        chunk(500, 1000),
+        chunk(1800, 2000),
+        chunk(1200, 1800),
+    ] {
+        conn.push_incoming(&packet);
+    }
+    let mut mqtt = MqttClient::<_>::from_connected(conn.clone());
+    let mut storage = RamStorage::<{ 4 * 1024 }>::new();
+    let src = MqttSource {
+        topic: TOPIC,
+        size: firmware.len(),
+        crc32: Some(crc32(&firmware)),
+        expected_sha256: None,
+    };
+    let mut counter = CountingVerifier {
+        image: &firmware,
+        bytes: 0,
+        finalized: false,
+    };
+
+    let mut ota = Ota::new(Config::default()).unwrap();
+    ota.run_mqtt(&mut mqtt, &mut storage, 0x100, &src, Some(&mut counter))
+        .unwrap();
+    assert_eq!(ota.state(), State::Completed);
+    assert_eq!(&storage.buf[0x100..0x100 + firmware.len()], &firmware[..]);
+    // Bytes received twice are written once
+    assert_eq!(storage.bytes_written, firmware.len());
+    assert_eq!(storage.syncs, 1);
+    assert!(counter.finalized);
+    assert_eq!(
+        mqtt.subscriptions().collect::<Vec<_>>(),
+        [(TOPIC, QoS::AtLeastOnce)]
+    );
+
+    // A corrupted chunk fails verification
+    let conn = ScriptedConnection::new();
+    conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x01]);
+    let mut corrupted = firmware.clone();
+    corrupted[1000] ^= 0xFF;
+    for start in (0..2000).step_by(500) {
+        conn.push_incoming(&chunk_publish(
+            TOPIC,
+            start as u32,
+            &corrupted[start..start + 500],
+        ));
+    }
+    let mut mqtt = MqttClient::<_>::from_connected(conn);
+    let mut storage = RamStorage::<{ 4 * 1024 }>::new();
+    let mut ota = Ota::new(Config::default()).unwrap();
+    assert_eq!(
+        ota.run_mqtt(&mut mqtt, &mut storage, 0, &src, None),
+        Err(OtaError::VerifyFailed)
+    );
+    assert_eq!(ota.state(), State::Failed);
+}
+
+#[test]
+fn ota_mqtt_rejects_bad_chunks() {
+    const TOPIC: &str = "fw";
+    let src = MqttSource {
+        topic: TOPIC,
+        size: 100,
+        crc32: None,
+        expected_sha256: None,
+    };
+    let run = |packets: &[std::vec::Vec<u8>]| {
+        let conn = ScriptedConnection::new();
+        conn.push_incoming(&[0x90, 0x03, 0x00, 0x01, 0x00]);
+        for packet in packets {
+            conn.push_incoming(packet);
+        }
+        let mut mqtt = MqttClient::<_>::from_connected(conn);
+        let mut storage = RamStorage::<256>::new();
+        let mut ota = Ota::new(Config::default()).unwrap();
+        let result = ota.run_mqtt(&mut mqtt, &mut storage, 0, &src, None);
+        (result, ota.state())
+    };
+
+    // Past the end of the image
+    assert_eq!(
+        run(&[chunk_publish(TOPIC, 90, &[0; 20])]),
+        (Err(OtaError::Protocol), State::Failed)
+    );
+    // Shorter than the offset prefix
+    let mut short = chunk_publish(TOPIC, 0, &[]);
+    short.truncate(short.len() - 1);
+    short[1] -= 1;
+    assert_eq!(run(&[short]), (Err(OtaError::Protocol), State::Failed));
+    // Too many gaps between the chunks received so far
+    let scattered: std::vec::Vec<_> = (0..9)
+        .map(|i| chunk_publish(TOPIC, i * 10 + 1, &[0; 5]))
+        .collect();
+    assert_eq!(run(&scattered), (Err(OtaError::Protocol), State::Failed));
+    // The connection ends before the image is complete
+    assert_eq!(
+        run(&[chunk_publish(TOPIC, 0, &[0; 50])]),
+        (
+            Err(OtaError::Network(
+                libiot::network::error::Error::ConnectionClosed
+            )),
+            State::Failed
+        )
+    );
+}
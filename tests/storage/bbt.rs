@@ -0,0 +1,290 @@
+use libiot::storage::bbt::BadBlockTable;
+use libiot::storage::error::Error;
+use libiot::storage::{
+    BlockStatus, BlockStorage, BlockingErase, Geometry, NandFlash, ReadStorage, Storage,
+};
+
+const BLOCK_SIZE: usize = 128;
+const BLOCKS: usize = 16;
+const PAGE_SIZE: usize = 32;
+const SPARE_SIZE: usize = 8;
+const PAGES: usize = BLOCKS * BLOCK_SIZE / PAGE_SIZE;
+
+/// NAND with factory-marked bad blocks and blocks that wear out on demand
+#[derive(Clone)]
+struct MockNand {
+    memory: [u8; BLOCKS * BLOCK_SIZE],
+    spare: [[u8; SPARE_SIZE]; PAGES],
+    factory_bad: Vec<usize>,
+    // Writes and erases to these blocks fail
+    worn: Vec<usize>,
+}
+
+impl MockNand {
+    fn new(factory_bad: &[usize]) -> Self {
+        Self {
+            memory: [0xFF; BLOCKS * BLOCK_SIZE],
+            spare: [[0xFF; SPARE_SIZE]; PAGES],
+            factory_bad: factory_bad.to_vec(),
+            worn: Vec::new(),
+        }
+    }
+
+    fn block(&self, block: usize) -> &[u8] {
+        &self.memory[block * BLOCK_SIZE..(block + 1) * BLOCK_SIZE]
+    }
+
+    /// Sequence number of the table copy in `block`
+    fn table_sequence(&self, block: usize) -> u32 {
+        let header = &self.block(block)[4..8];
+        u32::from_le_bytes(header.try_into().unwrap())
+    }
+
+    fn check(&self, offset: usize, len: usize) -> Result<(), Error> {
+        if offset + len > self.memory.len() {
+            return Err(Error::OutOfBounds);
+        }
+        let blocks = offset / BLOCK_SIZE..(offset + len).div_ceil(BLOCK_SIZE);
+        if blocks.clone().any(|block| self.worn.contains(&block)) {
+            return Err(Error::WriteError);
+        }
+        Ok(())
+    }
+}
+
+impl ReadStorage for MockNand {
+    type Error = Error;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let offset = offset as usize;
+        let source = self.memory.get(offset..offset + bytes.len());
+        bytes.copy_from_slice(source.ok_or(Error::OutOfBounds)?);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.memory.len()
+    }
+
+    fn geometry(&self) -> Geometry {
+        Geometry {
+            capacity: self.memory.len(),
+            write_size: PAGE_SIZE,
+            erase_size: BLOCK_SIZE,
+        }
+    }
+}
+
+impl Storage for MockNand {
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let offset = offset as usize;
+        self.check(offset, bytes.len())?;
+        self.memory[offset..offset + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl BlockingErase for MockNand {
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        let (from, to) = (from as usize, to as usize);
+        if from % BLOCK_SIZE != 0 || to % BLOCK_SIZE != 0 || to < from {
+            return Err(Error::OutOfBounds);
+        }
+        self.check(from, to - from).map_err(|_| Error::EraseError)?;
+        self.memory[from..to].fill(0xFF);
+        for page in from / PAGE_SIZE..to / PAGE_SIZE {
+            self.spare[page] = [0xFF; SPARE_SIZE];
+        }
+        Ok(())
+    }
+}
+
+impl BlockStorage for MockNand {
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+    fn block_count(&self) -> usize {
+        BLOCKS
+    }
+}
+
+impl NandFlash for MockNand {
+    fn read_spare(&mut self, block: usize, page: usize, spare: &mut [u8]) -> Result<(), Error> {
+        let page = block * BLOCK_SIZE / PAGE_SIZE + page;
+        spare.copy_from_slice(&self.spare[page][..spare.len()]);
+        Ok(())
+    }
+
+    fn write_with_spare(
+        &mut self,
+        block: usize,
+        page: usize,
+        data: &[u8],
+        spare: &[u8],
+    ) -> Result<(), Error> {
+        let offset = block * BLOCK_SIZE + page * PAGE_SIZE;
+        self.write(offset as u32, data)?;
+        self.spare[offset / PAGE_SIZE][..spare.len()].copy_from_slice(spare);
+        Ok(())
+    }
+
+    fn block_status(&mut self, block: usize) -> Result<BlockStatus, Error> {
+        Ok(if self.factory_bad.contains(&block) {
+            BlockStatus::Bad
+        } else {
+            BlockStatus::Good
+        })
+    }
+}
+
+#[test]
+fn bbt_remaps_factory_bad_blocks() {
+    // Blocks 11 to 15 are reserved; 15 is bad, so 14 and 13 hold the table
+    let mut bbt: BadBlockTable<_, 3> = BadBlockTable::new(MockNand::new(&[2, 15])).unwrap();
+    assert_eq!(bbt.block_count(), 11);
+    assert_eq!(bbt.capacity(), 11 * BLOCK_SIZE);
+    assert_eq!(
+        bbt.geometry(),
+        Geometry {
+            capacity: 11 * BLOCK_SIZE,
+            write_size: PAGE_SIZE,
+            erase_size: BLOCK_SIZE,
+        }
+    );
+    assert_eq!(bbt.block_status(2), Ok(BlockStatus::Good));
+    assert_eq!(bbt.block_status(11), Err(Error::OutOfBounds));
+    assert_eq!(bbt.physical_block(1), 1);
+    assert_eq!(bbt.physical_block(2), 11);
+    assert_eq!(bbt.reserve_used(), 1);
+
+    // A write spanning logical blocks 1 to 3 skips the bad block
+    let data: Vec<u8> = (0..=255).collect();
+    bbt.write(BLOCK_SIZE as u32 + 64, &data).unwrap();
+    let mut buf = [0u8; 256];
+    bbt.read(BLOCK_SIZE as u32 + 64, &mut buf).unwrap();
+    assert_eq!(buf[..], data[..]);
+    let nand = bbt.inner();
+    assert_eq!(nand.block(2), [0xFF; BLOCK_SIZE]);
+    assert_eq!(nand.block(11), &data[64..192]);
+    assert_eq!(nand.block(3)[..64], data[192..]);
+
+    // The table survives a restart
+    let mut bbt: BadBlockTable<_, 3> = BadBlockTable::new(bbt.into_inner()).unwrap();
+    assert_eq!(bbt.physical_block(2), 11);
+    assert_eq!(bbt.reserve_used(), 1);
+    bbt.read(BLOCK_SIZE as u32 + 64, &mut buf).unwrap();
+    assert_eq!(buf[..], data[..]);
+
+    assert_eq!(
+        bbt.write(11 * BLOCK_SIZE as u32, &[0]),
+        Err(Error::OutOfBounds)
+    );
+}
+
+#[test]
+fn bbt_moves_block_that_goes_bad_mid_write() {
+    let mut bbt: BadBlockTable<_, 2> = BadBlockTable::new(MockNand::new(&[])).unwrap();
+    let page = |n: u8| [n; PAGE_SIZE];
+    bbt.write_with_spare(5, 0, &page(1), b"ecc1").unwrap();
+    bbt.write_with_spare(5, 1, &page(2), b"ecc2").unwrap();
+    assert_eq!(bbt.physical_block(5), 5);
+
+    // Block 5 wears out; the third page goes to a reserve block instead,
+    // together with the pages already written
+    bbt.inner_mut().worn.push(5);
+    bbt.write_with_spare(5, 2, &page(3), b"ecc3").unwrap();
+    assert_eq!(bbt.physical_block(5), 12);
+    let nand = bbt.inner();
+    assert_eq!(nand.block(12)[..32], page(1));
+    assert_eq!(nand.block(12)[32..64], page(2));
+    assert_eq!(nand.block(12)[64..96], page(3));
+    assert_eq!(nand.block(12)[96..], [0xFF; 32]);
+
+    let mut spare = [0u8; 4];
+    bbt.read_spare(5, 2, &mut spare).unwrap();
+    assert_eq!(&spare, b"ecc3");
+    let mut buf = [0u8; 96];
+    bbt.read(5 * BLOCK_SIZE as u32, &mut buf).unwrap();
+    assert_eq!(buf[..32], page(1));
+    assert_eq!(buf[64..], page(3));
+
+    // The reserve block wears out as well: it is retired, and the data
+    // moves on to the last free reserve block
+    bbt.inner_mut().worn.push(12);
+    bbt.write(5 * BLOCK_SIZE as u32 + 96, &page(4)).unwrap();
+    assert_eq!(bbt.physical_block(5), 13);
+    assert_eq!(bbt.reserve_used(), 2);
+    assert_eq!(bbt.inner().block(13)[..64], bbt.inner().block(12)[..64]);
+    assert_eq!(bbt.inner().block(13)[96..], page(4));
+
+    // Erase failures remap too, and with the pool used up they are fatal
+    let mut bbt: BadBlockTable<_, 2> = BadBlockTable::new(bbt.into_inner()).unwrap();
+    assert_eq!(bbt.physical_block(5), 13);
+    bbt.inner_mut().worn.push(0);
+    assert_eq!(bbt.erase(0, BLOCK_SIZE as u32), Err(Error::StorageFault));
+    bbt.erase(BLOCK_SIZE as u32, 3 * BLOCK_SIZE as u32).unwrap();
+}
+
+#[test]
+fn bbt_keeps_reserve_on_other_errors() {
+    let mut bbt: BadBlockTable<_, 2> = BadBlockTable::new(MockNand::new(&[])).unwrap();
+
+    // A misaligned erase is the caller's mistake, not a worn block
+    assert_eq!(bbt.erase(0, 64), Err(Error::OutOfBounds));
+    assert_eq!(bbt.erase(64, BLOCK_SIZE as u32), Err(Error::OutOfBounds));
+    assert_eq!(bbt.reserve_used(), 0);
+    assert_eq!(bbt.physical_block(0), 0);
+
+    // So is a spare area that doesn't fit the page
+    assert_eq!(
+        bbt.write_with_spare(12, 0, &[0; PAGE_SIZE], b"ecc"),
+        Err(Error::OutOfBounds)
+    );
+    assert_eq!(bbt.reserve_used(), 0);
+
+    bbt.erase(0, BLOCK_SIZE as u32).unwrap();
+}
+
+#[test]
+fn bbt_survives_power_loss_while_saving_table() {
+    // Blocks 14 and 15 hold the table copies
+    let mut bbt: BadBlockTable<_, 2> = BadBlockTable::new(MockNand::new(&[3])).unwrap();
+    assert_eq!(bbt.physical_block(3), 12);
+
+    // Block 7 wears out, and the table is saved to the other copy
+    bbt.inner_mut().worn.push(7);
+    bbt.write(7 * BLOCK_SIZE as u32, &[0x5A; 16]).unwrap();
+    assert_eq!(bbt.physical_block(7), 13);
+
+    // Both copies are valid now: the older one only remaps block 3
+    let nand = bbt.into_inner();
+    let newer = if nand.table_sequence(14) > nand.table_sequence(15) {
+        14
+    } else {
+        15
+    };
+    let older = 29 - newer;
+
+    // Power is lost right after erasing the older copy for the next save;
+    // the newer copy still holds both remaps
+    let mut lost = nand.clone();
+    lost.erase(
+        (older * BLOCK_SIZE) as u32,
+        ((older + 1) * BLOCK_SIZE) as u32,
+    )
+    .unwrap();
+    let mut bbt: BadBlockTable<_, 2> = BadBlockTable::new(lost).unwrap();
+    assert_eq!(bbt.physical_block(3), 12);
+    assert_eq!(bbt.physical_block(7), 13);
+    let mut buf = [0u8; 16];
+    bbt.read(7 * BLOCK_SIZE as u32, &mut buf).unwrap();
+    assert_eq!(buf, [0x5A; 16]);
+
+    // Power is lost halfway through writing the newer copy instead; the
+    // older copy is used
+    let mut torn = nand;
+    torn.memory[newer * BLOCK_SIZE + 20] ^= 0xFF;
+    let bbt: BadBlockTable<_, 2> = BadBlockTable::new(torn).unwrap();
+    assert_eq!(bbt.physical_block(3), 12);
+    assert_eq!(bbt.physical_block(7), 7);
+}
@@ -0,0 +1,184 @@
+use super::{ERASED_BYTE, MockStorage, WriteBack};
+use libiot::storage::error::Error;
+use libiot::storage::kv::{self, KvStore};
+
+type Store = KvStore<MockStorage, 16, 32>;
+
+fn value(store: &mut Store, key: &str) -> Option<Vec<u8>> {
+    let mut buf = [0u8; 32];
+    let len = store.get(key, &mut buf).unwrap()?;
+    Some(buf[..len].to_vec())
+}
+
+#[test]
+fn kv_overwrite_survives_remount() {
+    let mut store = Store::mount(MockStorage::new(), 0..8).unwrap();
+    assert_eq!(value(&mut store, "mode"), None);
+
+    store.put("mode", b"eco").unwrap();
+    store.put("name", b"node-7").unwrap();
+    store.put("mode", b"performance").unwrap();
+    assert_eq!(value(&mut store, "mode").unwrap(), b"performance");
+    assert_eq!(value(&mut store, "name").unwrap(), b"node-7");
+
+    let mut store = Store::mount(store.into_inner(), 0..8).unwrap();
+    assert_eq!(value(&mut store, "mode").unwrap(), b"performance");
+    assert_eq!(value(&mut store, "name").unwrap(), b"node-7");
+
+    let mut small = [0u8; 4];
+    assert_eq!(
+        store.get("mode", &mut small),
+        Err(kv::Error::BufferTooSmall)
+    );
+    assert_eq!(
+        store.put("a-key-that-is-too-long", b"x"),
+        Err(kv::Error::KeyTooLong)
+    );
+    assert_eq!(store.put("big", &[0; 33]), Err(kv::Error::ValueTooLong));
+}
+
+#[test]
+fn kv_delete_writes_tombstone() {
+    let mut store = Store::mount(MockStorage::new(), 0..8).unwrap();
+    store.put("token", b"secret").unwrap();
+    store.put("empty", b"").unwrap();
+    assert_eq!(value(&mut store, "empty").unwrap(), b"");
+
+    assert_eq!(store.delete("token"), Ok(true));
+    assert_eq!(store.delete("token"), Ok(false));
+    assert_eq!(store.delete("missing"), Ok(false));
+    assert_eq!(value(&mut store, "token"), None);
+
+    let mut store = Store::mount(store.into_inner(), 0..8).unwrap();
+    assert_eq!(value(&mut store, "token"), None);
+    assert_eq!(value(&mut store, "empty").unwrap(), b"");
+
+    // A deleted key can be stored again
+    store.put("token", b"rotated").unwrap();
+    assert_eq!(value(&mut store, "token").unwrap(), b"rotated");
+}
+
+#[test]
+fn kv_compacts_into_next_sector() {
+    let mut store = Store::mount(MockStorage::new(), 2..6).unwrap();
+    assert_eq!(store.active_sector(), 2);
+    store.put("keep", b"forever").unwrap();
+    store.put("gone", b"soon").unwrap();
+    store.delete("gone").unwrap();
+
+    // Rewriting the same value does not use any space
+    let start = store.storage().memory;
+    store.put("keep", b"forever").unwrap();
+    assert_eq!(store.storage().memory, start);
+
+    let mut visited = Vec::new();
+    for i in 0u32..40 {
+        store.put("counter", &i.to_le_bytes()).unwrap();
+        if visited.last() != Some(&store.active_sector()) {
+            visited.push(store.active_sector());
+        }
+    }
+    // Every compaction moves on to the next sector of the range
+    assert!(visited.len() > 4);
+    for pair in visited.windows(2) {
+        assert_eq!(pair[1], if pair[0] == 5 { 2 } else { pair[0] + 1 });
+    }
+
+    assert_eq!(value(&mut store, "counter").unwrap(), 39u32.to_le_bytes());
+    assert_eq!(value(&mut store, "keep").unwrap(), b"forever");
+    assert_eq!(value(&mut store, "gone"), None);
+
+    // Sectors outside the range are never touched
+    let flash = store.into_inner();
+    assert!(flash.memory[..256].iter().all(|&b| b == ERASED_BYTE));
+    assert!(flash.memory[768..].iter().all(|&b| b == ERASED_BYTE));
+
+    let mut store = Store::mount(flash, 2..6).unwrap();
+    assert_eq!(value(&mut store, "counter").unwrap(), 39u32.to_le_bytes());
+    assert_eq!(value(&mut store, "keep").unwrap(), b"forever");
+
+    // Compacting only keeps the newest record of each key
+    store.compact().unwrap();
+    let active = store.active_sector() * 128;
+    let flash = store.into_inner();
+    let used = flash.memory[active..active + 128]
+        .iter()
+        .rposition(|&b| b != ERASED_BYTE)
+        .unwrap();
+    assert_eq!(used + 1, 12 + (8 + 4 + 7) + (8 + 7 + 4));
+}
+
+#[test]
+fn kv_skips_torn_record_on_mount() {
+    let mut store = Store::mount(MockStorage::new(), 0..2).unwrap();
+    store.put("ssid", b"home").unwrap();
+    store.put("ssid", b"office").unwrap();
+
+    // Power lost while the second record's value was being written
+    let mut flash = store.into_inner();
+    let value_start = 12 + (8 + 4 + 4) + 8 + 4;
+    flash.memory[value_start + 2..value_start + 6].fill(ERASED_BYTE);
+
+    let mut store = Store::mount(flash, 0..2).unwrap();
+    assert_eq!(value(&mut store, "ssid").unwrap(), b"home");
+
+    // The partly written sector is not appended to; the next write compacts
+    store.put("channel", b"6").unwrap();
+    assert_eq!(store.active_sector(), 1);
+    assert_eq!(value(&mut store, "ssid").unwrap(), b"home");
+    assert_eq!(value(&mut store, "channel").unwrap(), b"6");
+
+    let mut store = Store::mount(store.into_inner(), 0..2).unwrap();
+    assert_eq!(value(&mut store, "ssid").unwrap(), b"home");
+    assert_eq!(value(&mut store, "channel").unwrap(), b"6");
+}
+
+#[test]
+fn kv_interrupted_compaction_keeps_old_sector() {
+    let mut store = Store::mount(MockStorage::new(), 0..2).unwrap();
+    store.put("ssid", b"home").unwrap();
+
+    // Records copied into sector 1, but power lost before its header
+    let mut flash = store.into_inner();
+    let (old, new) = flash.memory.split_at_mut(128);
+    new[12..28].copy_from_slice(&old[12..28]);
+
+    let mut store = Store::mount(flash, 0..2).unwrap();
+    assert_eq!(store.active_sector(), 0);
+    assert_eq!(value(&mut store, "ssid").unwrap(), b"home");
+
+    // The half-written sector is erased before it is used
+    store.compact().unwrap();
+    assert_eq!(store.active_sector(), 1);
+    assert_eq!(value(&mut store, "ssid").unwrap(), b"home");
+}
+
+#[test]
+fn kv_rejects_unusable_layout() {
+    let mount = |sectors| Store::mount(MockStorage::new(), sectors).err();
+    assert_eq!(mount(0..1), Some(kv::Error::InvalidLayout));
+    assert_eq!(mount(6..9), Some(kv::Error::InvalidLayout));
+    assert_eq!(
+        KvStore::<_, 16, 128>::mount(MockStorage::new(), 0..2).err(),
+        Some(kv::Error::<Error>::InvalidLayout)
+    );
+}
+
+#[test]
+fn kv_put_and_delete_reach_the_medium() {
+    let cached = WriteBack {
+        medium: MockStorage::new(),
+        pending: None,
+    };
+    let mut store: KvStore<WriteBack, 16, 16> = KvStore::mount(cached, 0..2).unwrap();
+    store.put("mode", b"eco").unwrap();
+    store.delete("mode").unwrap();
+    store.put("fan", b"on").unwrap();
+
+    // Nothing is left in the cache, so a reset now loses nothing
+    let cached = store.into_inner();
+    assert!(cached.pending.is_none());
+    let mut store = Store::mount(cached.medium, 0..2).unwrap();
+    assert_eq!(value(&mut store, "mode"), None);
+    assert_eq!(value(&mut store, "fan").unwrap(), b"on");
+}
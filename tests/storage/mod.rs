@@ -1,3 +1,7 @@
+mod bbt;
+mod kv;
+mod ring;
+
 use libiot::storage::error::Error;
 use libiot::storage::*;
 
@@ -40,6 +44,10 @@ impl ReadStorage for MockStorage {
     fn capacity(&self) -> usize {
         MOCK_CAPACITY
     }
+
+    fn geometry(&self) -> Geometry {
+        Geometry::blocks_and_sectors(self)
+    }
 }
 
 impl Storage for MockStorage {
@@ -85,6 +93,21 @@ impl SectorStorage for MockStorage {
     }
 }
 
+impl RamStorage for MockStorage {
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.memory = [0; MOCK_CAPACITY];
+        Ok(())
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.memory
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+}
+
 impl UnifiedStorage for MockStorage {
     fn is_non_volatile(&self) -> bool {
         self.non_volatile
@@ -129,6 +152,78 @@ fn test_out_of_bounds() {
     );
 }
 
+/// Buffers writes until `sync`, like a write-back cache in front of flash
+struct WriteBack {
+    medium: MockStorage,
+    pending: Option<(u32, [u8; 16], usize)>,
+}
+
+impl ReadStorage for WriteBack {
+    type Error = Error;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        ReadStorage::read(&mut self.medium, offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        ReadStorage::capacity(&self.medium)
+    }
+}
+
+impl Storage for WriteBack {
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.sync()?;
+        let mut data = [0u8; 16];
+        data[..bytes.len()].copy_from_slice(bytes);
+        self.pending = Some((offset, data, bytes.len()));
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<(), Self::Error> {
+        if let Some((offset, data, len)) = self.pending.take() {
+            Storage::write(&mut self.medium, offset, &data[..len])?;
+        }
+        Ok(())
+    }
+}
+
+impl BlockingErase for WriteBack {
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        self.sync()?;
+        BlockingErase::erase(&mut self.medium, from, to)
+    }
+}
+
+impl SectorStorage for WriteBack {
+    fn sector_size(&self) -> usize {
+        self.medium.sector_size()
+    }
+    fn sector_count(&self) -> usize {
+        self.medium.sector_count()
+    }
+}
+
+#[test]
+fn test_sync() {
+    // Write-through devices need no sync
+    let mut storage = MockStorage::new();
+    Storage::write(&mut storage, 0, b"abc").unwrap();
+    Storage::sync(&mut storage).unwrap();
+    let mut buf = [0u8; 3];
+    ReadStorage::read(&mut storage, 0, &mut buf).unwrap();
+    assert_eq!(&buf, b"abc");
+
+    // Buffered writes only reach the medium on sync
+    let mut cached = WriteBack {
+        medium: MockStorage::new(),
+        pending: None,
+    };
+    cached.write(8, b"xyz").unwrap();
+    assert_eq!(cached.medium.memory[8..11], [ERASED_BYTE; 3]);
+    cached.sync().unwrap();
+    assert_eq!(&cached.medium.memory[8..11], b"xyz");
+}
+
 #[test]
 fn test_block_and_sector() {
     let storage = MockStorage::new();
@@ -138,12 +233,280 @@ fn test_block_and_sector() {
     assert_eq!(storage.sector_count(), MOCK_CAPACITY / 128);
 }
 
+#[test]
+fn test_geometry() {
+    let storage = MockStorage::new();
+    assert_eq!(
+        ReadStorage::geometry(&storage),
+        Geometry {
+            capacity: MOCK_CAPACITY,
+            write_size: 64,
+            erase_size: 128,
+        }
+    );
+    assert_eq!(
+        Geometry::sectors(&storage),
+        Geometry {
+            capacity: MOCK_CAPACITY,
+            write_size: 1,
+            erase_size: 128,
+        }
+    );
+
+    // Devices without block or sector information default to bytes
+    let write_back = WriteBack {
+        medium: MockStorage::new(),
+        pending: None,
+    };
+    assert_eq!(
+        write_back.geometry(),
+        Geometry::byte_addressable(MOCK_CAPACITY)
+    );
+}
+
 #[test]
 fn test_unified_storage() {
     let storage = MockStorage::new();
     assert!(storage.is_non_volatile());
 }
 
+#[test]
+fn test_slice_storage() {
+    use libiot::storage::slice::SliceStorage;
+
+    static BLOB: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+    let mut storage = SliceStorage::new(&BLOB);
+    assert_eq!(storage.capacity(), 8);
+    assert_eq!(storage.geometry(), Geometry::byte_addressable(8));
+    assert_eq!(storage.as_slice(), &BLOB);
+
+    let mut buf = [0u8; 3];
+    storage.read(5, &mut buf).unwrap();
+    assert_eq!(buf, [6, 7, 8]);
+    storage.read(8, &mut []).unwrap();
+
+    // Reads past the end, including offsets that would overflow, are rejected
+    assert_eq!(storage.read(6, &mut buf), Err(Error::OutOfBounds));
+    assert_eq!(storage.read(9, &mut []), Err(Error::OutOfBounds));
+    assert_eq!(storage.read(u32::MAX, &mut buf), Err(Error::OutOfBounds));
+
+    assert_eq!(storage.write(0, &[0]), Err(Error::Unsupported));
+    assert_eq!(storage.as_slice()[0], 1);
+}
+
+/// NOR flash semantics: programming only clears bits
+struct NorFlash {
+    medium: MockStorage,
+    erases: usize,
+}
+
+impl ReadStorage for NorFlash {
+    type Error = Error;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        ReadStorage::read(&mut self.medium, offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        ReadStorage::capacity(&self.medium)
+    }
+}
+
+impl Storage for NorFlash {
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let mut programmed = [0u8; MOCK_CAPACITY];
+        let programmed = &mut programmed[..bytes.len()];
+        ReadStorage::read(&mut self.medium, offset, programmed)?;
+        for (cell, byte) in programmed.iter_mut().zip(bytes) {
+            *cell &= byte;
+        }
+        Storage::write(&mut self.medium, offset, programmed)
+    }
+}
+
+impl BlockingErase for NorFlash {
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        self.erases += 1;
+        BlockingErase::erase(&mut self.medium, from, to)
+    }
+}
+
+impl SectorStorage for NorFlash {
+    fn sector_size(&self) -> usize {
+        self.medium.sector_size()
+    }
+    fn sector_count(&self) -> usize {
+        self.medium.sector_count()
+    }
+}
+
+#[test]
+fn test_sector_rmw() {
+    use libiot::storage::rmw::SectorRmw;
+
+    let mut medium = MockStorage::new();
+    for (i, byte) in medium.memory.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    let mut flash: SectorRmw<_, 128> = SectorRmw::new(NorFlash { medium, erases: 0 });
+    assert_eq!(flash.geometry(), Geometry::byte_addressable(MOCK_CAPACITY));
+
+    // Unaligned span straddling the boundary between sectors 1 and 2
+    flash.write(250, b"0123456789").unwrap();
+    assert_eq!(flash.inner().erases, 2);
+
+    let mut span = [0u8; 10];
+    flash.read(250, &mut span).unwrap();
+    assert_eq!(&span, b"0123456789");
+
+    // Everything else in both sectors, and the sectors around them, is kept
+    let memory = &flash.inner().medium.memory;
+    for i in (0..250).chain(260..MOCK_CAPACITY) {
+        assert_eq!(memory[i], i as u8, "byte {i}");
+    }
+
+    // Rewriting the same bytes needs no erase, nor does writing blank flash
+    flash.write(250, b"0123456789").unwrap();
+    flash.erase(512, 640).unwrap();
+    flash.write(600, b"abc").unwrap();
+    assert_eq!(flash.inner().erases, 3);
+    flash.read(600, &mut span[..3]).unwrap();
+    assert_eq!(&span[..3], b"abc");
+
+    // Spans covering more than two sectors
+    let long = [0x5A; 300];
+    flash.write(100, &long).unwrap();
+    let mut buf = [0u8; 302];
+    flash.read(99, &mut buf).unwrap();
+    assert_eq!(buf[0], 99);
+    assert_eq!(buf[1..301], long);
+    assert_eq!(buf[301], 400u32 as u8);
+}
+
+#[test]
+#[should_panic(expected = "smaller than a sector")]
+fn test_sector_rmw_needs_sector_buffer() {
+    use libiot::storage::rmw::SectorRmw;
+
+    let _: SectorRmw<_, 64> = SectorRmw::new(MockStorage::new());
+}
+
+#[test]
+fn test_checksummed_storage() {
+    use libiot::storage::checksum::ChecksummedStorage;
+
+    // 1024 / (60 + 4) = 16 records
+    let mut storage: ChecksummedStorage<_, 60> = ChecksummedStorage::new(MockStorage::new());
+    assert_eq!(storage.capacity(), 16 * 60);
+
+    let calibration: Vec<u8> = (0..120).collect();
+    storage.write(0, &calibration).unwrap();
+    let mut buf = [0u8; 120];
+    storage.read(0, &mut buf).unwrap();
+    assert_eq!(buf[..], calibration[..]);
+
+    // Partial updates keep the rest of the record, across the boundary too
+    storage.write(58, &[0xAA; 4]).unwrap();
+    storage.read(50, &mut buf[..20]).unwrap();
+    assert_eq!(buf[..8], calibration[50..58]);
+    assert_eq!(buf[8..12], [0xAA; 4]);
+    assert_eq!(buf[12..20], calibration[62..70]);
+
+    // The trailer follows each record on the device
+    let memory = &storage.inner().memory;
+    assert_eq!(memory[..58], calibration[..58]);
+    assert_eq!(memory[64..66], [0xAA; 2]);
+    assert_eq!(memory[66..124], calibration[62..120]);
+
+    // A flipped bit in record 1 only fails reads that touch it
+    storage.inner_mut().memory[100] ^= 0x01;
+    assert_eq!(storage.read(0, &mut buf[..60]), Ok(()));
+    assert_eq!(
+        storage.read(59, &mut buf[..2]),
+        Err(Error::ChecksumMismatch)
+    );
+    assert_eq!(
+        storage.read(60, &mut buf[..1]),
+        Err(Error::ChecksumMismatch)
+    );
+    assert_eq!(storage.write(60, &[0; 1]), Err(Error::ChecksumMismatch));
+
+    // Rewriting the whole record repairs it
+    storage.write(60, &calibration[60..]).unwrap();
+    storage.read(60, &mut buf[..60]).unwrap();
+    assert_eq!(buf[..60], calibration[60..]);
+
+    // Never-written records do not pass, and the address space ends at the
+    // last whole record
+    assert_eq!(
+        storage.read(120, &mut buf[..1]),
+        Err(Error::ChecksumMismatch)
+    );
+    assert_eq!(storage.read(959, &mut buf[..2]), Err(Error::OutOfBounds));
+    assert_eq!(storage.write(u32::MAX, &[0]), Err(Error::OutOfBounds));
+}
+
+#[test]
+fn test_concat_storage() {
+    use libiot::storage::concat::ConcatStorage;
+
+    let mut first = MockStorage::new();
+    let mut second = MockStorage::new();
+    first.clear().unwrap();
+    second.clear().unwrap();
+    let mut storage = ConcatStorage::new(first, second);
+    assert_eq!(storage.capacity(), 2 * MOCK_CAPACITY);
+
+    // A write straddling the junction is split between the devices
+    let junction = MOCK_CAPACITY as u32;
+    storage.write(junction - 3, b"abcdef").unwrap();
+    let (first, second) = storage.inner();
+    assert_eq!(&first.as_slice()[MOCK_CAPACITY - 3..], b"abc");
+    assert_eq!(&second.as_slice()[..3], b"def");
+
+    let mut buf = [0u8; 8];
+    storage.read(junction - 4, &mut buf).unwrap();
+    assert_eq!(&buf, b"\0abcdef\0");
+    storage.write(junction + 10, b"xy").unwrap();
+    storage.read(junction + 10, &mut buf[..2]).unwrap();
+    assert_eq!(&buf[..2], b"xy");
+    assert_eq!(&storage.inner().1.as_slice()[10..12], b"xy");
+
+    // So is an erase, with the second part shifted to the second device
+    storage.erase(junction - 128, junction + 128).unwrap();
+    let (first, second) = storage.inner();
+    assert!(
+        first.as_slice()[MOCK_CAPACITY - 128..]
+            .iter()
+            .all(|&b| b == ERASED_BYTE)
+    );
+    assert!(second.as_slice()[..128].iter().all(|&b| b == ERASED_BYTE));
+    assert_eq!(first.as_slice()[MOCK_CAPACITY - 129], 0);
+    assert_eq!(second.as_slice()[128], 0);
+
+    // Ranges on one side only reach that device
+    storage.erase(0, 64).unwrap();
+    storage.erase(junction + 256, junction + 320).unwrap();
+    let (first, second) = storage.inner();
+    assert!(first.as_slice()[..64].iter().all(|&b| b == ERASED_BYTE));
+    assert!(
+        second.as_slice()[256..320]
+            .iter()
+            .all(|&b| b == ERASED_BYTE)
+    );
+    assert_eq!(second.as_slice()[255], 0);
+
+    // Past the end, the second device reports the error
+    assert_eq!(
+        storage.write(2 * junction - 1, b"zz"),
+        Err(Error::OutOfBounds)
+    );
+    assert_eq!(
+        storage.read(2 * junction, &mut buf[..1]),
+        Err(Error::OutOfBounds)
+    );
+}
+
 #[cfg(feature = "async")]
 mod async_tests {
     use super::*;
@@ -194,4 +557,47 @@ mod async_tests {
             assert_eq!(buf, [ERASED_BYTE; 4]);
         });
     }
+
+    #[test]
+    fn test_sync_to_async() {
+        use libiot::storage::compat::SyncToAsync;
+
+        let mut ram = MockStorage::new();
+        ram.clear().unwrap();
+        let mut storage = SyncToAsync::new(ram);
+        assert_eq!(AsyncReadStorage::capacity(&storage), MOCK_CAPACITY);
+        assert_eq!(
+            storage.geometry(),
+            Geometry::blocks_and_sectors(storage.inner())
+        );
+        assert_eq!(storage.sector_size(), 128);
+
+        block_on(async {
+            AsyncStorage::write(&mut storage, 100, b"async")
+                .await
+                .unwrap();
+            storage.sync().await.unwrap();
+
+            let mut buf = [0u8; 7];
+            AsyncReadStorage::read(&mut storage, 99, &mut buf)
+                .await
+                .unwrap();
+            assert_eq!(&buf, b"\0async\0");
+
+            AsyncErase::erase(&mut storage, 96, 104).await.unwrap();
+            AsyncReadStorage::read(&mut storage, 99, &mut buf)
+                .await
+                .unwrap();
+            assert_eq!(&buf, b"\xff\xff\xff\xff\xffc\0");
+
+            // Errors from the wrapped device come through unchanged
+            assert_eq!(
+                AsyncStorage::write(&mut storage, MOCK_CAPACITY as u32, b"x").await,
+                Err(Error::OutOfBounds)
+            );
+        });
+
+        // The blocking traits still work on the wrapped device
+        assert_eq!(&storage.into_inner().as_slice()[104..106], b"c\0");
+    }
 }
@@ -0,0 +1,147 @@
+use super::{ERASED_BYTE, MockStorage};
+use libiot::storage::error::Error;
+use libiot::storage::ring::{self, HEADER_LEN};
+use libiot::storage::{ReadStorage, Region, RingLog, Storage};
+
+struct Span(u32, u32);
+
+impl Region for Span {
+    fn start(&self) -> u32 {
+        self.0
+    }
+    fn end(&self) -> u32 {
+        self.1
+    }
+}
+
+/// EEPROM that loses power after accepting `budget` more bytes
+struct Brownout {
+    medium: MockStorage,
+    budget: usize,
+}
+
+impl ReadStorage for Brownout {
+    type Error = Error;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.medium.read(offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        self.medium.capacity()
+    }
+}
+
+impl Storage for Brownout {
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let len = bytes.len().min(self.budget);
+        self.budget -= len;
+        self.medium.write(offset, &bytes[..len])?;
+        if len < bytes.len() {
+            return Err(Error::WriteError);
+        }
+        Ok(())
+    }
+}
+
+fn entries<S: Storage>(log: &mut RingLog<S, 32>) -> Vec<Vec<u8>> {
+    log.iter().map(|entry| entry.unwrap().to_vec()).collect()
+}
+
+fn event(i: usize) -> Vec<u8> {
+    format!("event {i}{}", "!".repeat(i % 7)).into_bytes()
+}
+
+#[test]
+fn ring_log_appends_and_remounts() {
+    let region = Span(64, 320);
+    let mut log: RingLog<_, 32> = RingLog::mount(MockStorage::new(), &region).unwrap();
+    assert!(log.is_empty());
+    assert!(entries(&mut log).is_empty());
+
+    log.append(b"boot").unwrap();
+    log.append(b"").unwrap();
+    log.append(b"link up").unwrap();
+    let expected = [b"boot".to_vec(), vec![], b"link up".to_vec()];
+    assert_eq!(entries(&mut log), expected);
+
+    let flash = log.into_inner();
+    assert!(flash.memory[..64].iter().all(|&b| b == ERASED_BYTE));
+    let mut log: RingLog<_, 32> = RingLog::mount(flash, &region).unwrap();
+    assert_eq!(entries(&mut log), expected);
+
+    assert_eq!(log.append(&[0; 33]), Err(ring::Error::TooLong));
+    assert!(matches!(
+        RingLog::<_, 32>::mount(MockStorage::new(), &Span(0, HEADER_LEN as u32 - 1)),
+        Err(ring::Error::InvalidLayout)
+    ));
+}
+
+#[test]
+fn ring_log_overwrites_oldest_on_wraparound() {
+    let region = Span(0, 200);
+    let mut log: RingLog<_, 32> = RingLog::mount(MockStorage::new(), &region).unwrap();
+
+    for i in 0..50 {
+        log.append(&event(i)).unwrap();
+        // The log is always the newest entries, oldest first
+        let kept = entries(&mut log);
+        let first = i + 1 - kept.len();
+        let expected: Vec<_> = (first..=i).map(event).collect();
+        assert_eq!(kept, expected, "after appending {i}");
+        // At most the space skipped at the end and the rest of a partly
+        // overwritten entry go unused
+        let used: usize = kept.iter().map(|e| HEADER_LEN + e.len()).sum();
+        assert!(first == 0 || used + 2 * (HEADER_LEN + 15) >= 200);
+    }
+
+    // Recovery after wrapping finds the same entries
+    let kept = entries(&mut log);
+    assert!(kept.len() > 4);
+    let mut log: RingLog<_, 32> = RingLog::mount(log.into_inner(), &region).unwrap();
+    assert_eq!(entries(&mut log), kept);
+
+    log.append(b"after remount").unwrap();
+    let kept = entries(&mut log);
+    assert_eq!(kept.last().unwrap(), b"after remount");
+    assert_eq!(kept[kept.len() - 2], event(49));
+}
+
+#[test]
+fn ring_log_recovers_from_power_loss_mid_append() {
+    let region = Span(0, 200);
+    for budget in [0, 3, HEADER_LEN, HEADER_LEN + 4] {
+        let mut log: RingLog<_, 32> = RingLog::mount(
+            Brownout {
+                medium: MockStorage::new(),
+                budget: usize::MAX,
+            },
+            &region,
+        )
+        .unwrap();
+        for i in 0..12 {
+            log.append(&event(i)).unwrap();
+        }
+        let before = entries(&mut log);
+
+        // The next entry overwrites the oldest ones, but is cut short
+        let mut flash = log.into_inner();
+        flash.budget = budget;
+        let mut log: RingLog<_, 32> = RingLog::mount(flash, &region).unwrap();
+        assert!(log.append(b"interrupted entry").is_err());
+
+        let mut flash = log.into_inner();
+        flash.budget = usize::MAX;
+        let mut log: RingLog<_, 32> = RingLog::mount(flash, &region).unwrap();
+        let after = entries(&mut log);
+        assert!(!after.is_empty(), "budget {budget}");
+        assert_eq!(after.last(), before.last(), "budget {budget}");
+        assert!(before.ends_with(&after), "budget {budget}");
+
+        // The log carries on from the last complete entry
+        log.append(b"resumed").unwrap();
+        let resumed = entries(&mut log);
+        assert_eq!(resumed.last().unwrap(), b"resumed");
+        assert_eq!(resumed[resumed.len() - 2], event(11), "budget {budget}");
+    }
+}
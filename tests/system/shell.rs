@@ -1,5 +1,6 @@
 use libiot::system::shell::*;
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 
 /// Thread-safe test output capture
@@ -43,6 +44,14 @@ fn fail_command_handler(_argc: usize, _argv: &[&str]) -> ShellResult {
     ShellResult::InvalidParameter
 }
 
+/// Test command handler that counts how often it was dispatched
+static BOUNDED_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+fn bounded_command_handler(_argc: usize, _argv: &[&str]) -> ShellResult {
+    BOUNDED_CALLS.fetch_add(1, Ordering::SeqCst);
+    ShellResult::Ok
+}
+
 /// Test command handler that captures arguments for verification
 static CAPTURED_ARGS: OnceLock<Arc<Mutex<Option<Vec<String>>>>> = OnceLock::new();
 
@@ -158,11 +167,15 @@ mod tests {
                 name: "static1",
                 description: "Static command 1",
                 handler: test_command_handler,
+                min_args: None,
+                max_args: None,
             },
             Command {
                 name: "static2",
                 description: "Static command 2",
                 handler: test_command_handler,
+                min_args: None,
+                max_args: None,
             },
         ];
 
@@ -328,6 +341,8 @@ mod tests {
             name: "static_test",
             description: "Static test command",
             handler: test_command_handler,
+            min_args: None,
+            max_args: None,
         }];
 
         shell.register_static_commands(&COMMANDS);
@@ -595,6 +610,87 @@ mod tests {
         assert_ne!(ShellResult::Ok, ShellResult::InvalidParameter);
     }
 
+    #[test]
+    fn test_command_argument_bounds() {
+        let mut shell = Shell::new();
+        clear_test_output();
+        shell.set_output_function(test_output_fn);
+
+        let result = shell.register_command_with_args(
+            "bounded",
+            "bounded <a> [b]",
+            bounded_command_handler,
+            Some(1),
+            Some(2),
+        );
+        assert_eq!(result, ShellResult::Ok);
+        BOUNDED_CALLS.store(0, Ordering::SeqCst);
+
+        // Under the minimum: usage is printed and the handler is not called
+        shell.input(b"bounded\r");
+        assert_eq!(BOUNDED_CALLS.load(Ordering::SeqCst), 0);
+        let out = get_test_output();
+        assert!(out.contains("Usage: bounded - bounded <a> [b]"));
+
+        // Within bounds: dispatched
+        shell.input(b"bounded one\r");
+        shell.input(b"bounded one two\r");
+        assert_eq!(BOUNDED_CALLS.load(Ordering::SeqCst), 2);
+
+        // Over the maximum: rejected
+        clear_test_output();
+        shell.input(b"bounded one two three\r");
+        assert_eq!(BOUNDED_CALLS.load(Ordering::SeqCst), 2);
+        assert!(get_test_output().contains("Invalid number of arguments"));
+    }
+
+    #[test]
+    fn test_command_argument_bounds_static_and_invalid() {
+        static COMMANDS: [Command; 1] = [
+            Command::new("exact", "exact <x>", test_command_handler).with_args(Some(1), Some(1))
+        ];
+
+        let cmd = &COMMANDS[0];
+        assert!(!cmd.accepts_arg_count(0));
+        assert!(cmd.accepts_arg_count(1));
+        assert!(!cmd.accepts_arg_count(2));
+
+        // Unbounded commands accept anything, as before
+        let unbounded = Command::new("any", "any", test_command_handler);
+        assert!(unbounded.accepts_arg_count(0));
+        assert!(unbounded.accepts_arg_count(MAX_ARGS));
+
+        let mut shell = Shell::new();
+        assert_eq!(
+            shell.register_command_with_args("bad", "bad", test_command_handler, Some(2), Some(1)),
+            ShellResult::InvalidParameter
+        );
+        assert_eq!(shell.register_static_commands(&COMMANDS), ShellResult::Ok);
+
+        // Static tables get the same check, whether built with `with_args`
+        // or by setting the fields
+        static BAD: [Command; 2] = [
+            Command::new("exact", "exact <x>", test_command_handler).with_args(Some(1), Some(1)),
+            Command::new("bad", "bad", test_command_handler).with_args(Some(2), Some(1)),
+        ];
+        static BAD_FIELDS: [Command; 1] = [Command {
+            name: "bad",
+            description: "bad",
+            handler: test_command_handler,
+            min_args: Some(3),
+            max_args: Some(0),
+        }];
+        let mut shell = Shell::new();
+        assert_eq!(
+            shell.register_static_commands(&BAD),
+            ShellResult::InvalidParameter
+        );
+        assert_eq!(
+            shell.register_static_commands(&BAD_FIELDS),
+            ShellResult::InvalidParameter
+        );
+    }
+
     #[test]
     fn test_command_clone() {
         // Test that Command implements Clone
@@ -602,6 +698,8 @@ mod tests {
             name: "test",
             description: "Test command",
             handler: test_command_handler,
+            min_args: None,
+            max_args: None,
         };
 
         let cloned = cmd.clone();
@@ -688,4 +786,317 @@ mod tests {
             "Mixed escape sequences should be handled correctly"
         );
     }
+
+    #[test]
+    fn test_list_paging() {
+        let mut shell = Shell::new();
+        clear_test_output();
+        shell.set_output_function(test_output_fn);
+        shell.set_echo(false);
+        shell.set_list_paging(Some(2));
+
+        for name in ["page_a", "page_b", "page_c", "page_d", "page_e"] {
+            shell.register_command(name, "Paged command", test_command_handler);
+        }
+
+        // First page stops at the prompt
+        assert_eq!(shell.input(b"list\r"), ShellResult::Ok);
+        assert!(shell.is_list_paused());
+        let out = get_test_output();
+        assert!(out.contains("page_a"));
+        assert!(out.contains("page_b"));
+        assert!(!out.contains("page_c"));
+        assert!(out.contains(MORE_PROMPT));
+
+        // Any key shows the next page; the key is not added to the line
+        shell.input(b" ");
+        assert!(shell.is_list_paused());
+        let out = get_test_output();
+        assert!(out.contains("page_c"));
+        assert!(out.contains("page_d"));
+        assert!(!out.contains("page_e"));
+
+        shell.input(b"x");
+        assert!(!shell.is_list_paused());
+        assert!(get_test_output().contains("page_e"));
+
+        // 'q' abandons the listing, and input resumes normally
+        shell.input(b"list\r");
+        assert!(shell.is_list_paused());
+        shell.input(b"q");
+        assert!(!shell.is_list_paused());
+        clear_test_output();
+        shell.input(b"list\r");
+        assert!(shell.is_list_paused());
+
+        // Disabling paging clears any paused listing
+        shell.set_list_paging(None);
+        assert!(!shell.is_list_paused());
+        clear_test_output();
+        shell.input(b"list\r");
+        assert!(!shell.is_list_paused());
+        assert!(get_test_output().contains("page_e"));
+    }
+
+    /// Separate capture so concurrently running tests cannot interleave output
+    static LIST_OUTPUT: Mutex<String> = Mutex::new(String::new());
+
+    fn list_output_fn(text: &str) {
+        LIST_OUTPUT.lock().unwrap().push_str(text);
+    }
+
+    #[test]
+    fn test_list_alignment_and_width() {
+        let mut shell = Shell::new();
+        shell.set_output_function(list_output_fn);
+        shell.set_echo(false);
+
+        shell.register_command("ls", "List files", test_command_handler);
+        shell.register_command("reboot_now", "Restart the device", test_command_handler);
+        shell.register_command("ver", "Print the firmware version", test_command_handler);
+
+        LIST_OUTPUT.lock().unwrap().clear();
+        shell.input(b"list\r");
+        let out = std::mem::take(&mut *LIST_OUTPUT.lock().unwrap());
+        assert!(out.contains("\r\nls          List files\r\n"));
+        assert!(out.contains("\r\nreboot_now  Restart the device\r\n"));
+        assert!(out.contains("\r\nver         Print the firmware version\r\n"));
+
+        // Over-long descriptions are cut to the width with an ellipsis
+        shell.set_list_width(Some(24));
+        shell.input(b"list\r");
+        let out = std::mem::take(&mut *LIST_OUTPUT.lock().unwrap());
+        assert!(out.contains("\r\nls          List files\r\n"));
+        assert!(out.contains("\r\nreboot_now  Restart t...\r\n"));
+        assert!(out.contains("\r\nver         Print the...\r\n"));
+        for line in out.lines().filter(|line| line.contains("  ")) {
+            assert!(line.trim_end_matches('\r').chars().count() <= 24);
+        }
+    }
+
+    #[test]
+    fn test_line_status() {
+        let mut shell = Shell::new();
+        shell.set_echo(false);
+        shell.register_command("ok", "Succeeds", test_command_handler);
+        shell.register_command("fail", "Fails", fail_command_handler);
+        assert_eq!(shell.last_status(), LineStatus::Empty);
+
+        assert_eq!(
+            shell.execute_line("ok"),
+            Ok(LineStatus::Executed(ShellResult::Ok))
+        );
+        assert_eq!(
+            shell.execute_line("fail now"),
+            Ok(LineStatus::Executed(ShellResult::InvalidParameter))
+        );
+        assert_eq!(
+            shell.execute_line("list"),
+            Ok(LineStatus::Executed(ShellResult::Ok))
+        );
+        assert_eq!(shell.execute_line("missing"), Ok(LineStatus::NotFound));
+        assert_eq!(
+            shell.execute_line("missing --help"),
+            Ok(LineStatus::NotFound)
+        );
+        assert_eq!(shell.execute_line(""), Ok(LineStatus::Empty));
+        assert_eq!(
+            shell.execute_line(&"x".repeat(MAX_BUFFER_SIZE)),
+            Err(ShellResult::BufferOverflow)
+        );
+
+        // Interactive input keeps returning Ok but records the outcome
+        assert_eq!(shell.input(b"missing\r"), ShellResult::Ok);
+        assert_eq!(shell.last_status(), LineStatus::NotFound);
+        assert_eq!(shell.input(b"ok\r"), ShellResult::Ok);
+        assert_eq!(shell.last_status(), LineStatus::Executed(ShellResult::Ok));
+    }
+
+    static NAMESPACE_OUTPUT: Mutex<String> = Mutex::new(String::new());
+
+    fn namespace_output_fn(text: &str) {
+        NAMESPACE_OUTPUT.lock().unwrap().push_str(text);
+    }
+
+    fn take_namespace_output() -> String {
+        std::mem::take(&mut *NAMESPACE_OUTPUT.lock().unwrap())
+    }
+
+    #[test]
+    fn test_list_namespaces() {
+        let mut shell = Shell::new();
+        shell.set_output_function(namespace_output_fn);
+        shell.set_echo(false);
+
+        shell.register_command("gpio.set", "Drive a pin", test_command_handler);
+        shell.register_command("reboot", "Restart", test_command_handler);
+        shell.register_command("wifi.scan", "Scan networks", test_command_handler);
+        shell.register_command("gpio.get", "Read a pin", test_command_handler);
+        shell.register_command("wifi.ap.start", "Start the AP", test_command_handler);
+        take_namespace_output();
+
+        // Ungrouped commands first, then one heading per namespace
+        assert_eq!(
+            shell.execute_line("list"),
+            Ok(LineStatus::Executed(ShellResult::Ok))
+        );
+        assert_eq!(
+            take_namespace_output(),
+            "Available commands:\r\n\
+             reboot         Restart\r\n\
+             gpio:\r\n\
+             gpio.set       Drive a pin\r\n\
+             gpio.get       Read a pin\r\n\
+             wifi:\r\n\
+             wifi.scan      Scan networks\r\n\
+             wifi.ap.start  Start the AP\r\n"
+        );
+
+        // A single group, aligned on its own names
+        shell.execute_line("list gpio").unwrap();
+        assert_eq!(
+            take_namespace_output(),
+            "Available commands:\r\n\
+             gpio:\r\n\
+             gpio.set  Drive a pin\r\n\
+             gpio.get  Read a pin\r\n"
+        );
+        shell.execute_line("list wifi.ap.").unwrap();
+        assert!(take_namespace_output().ends_with("wifi:\r\nwifi.ap.start  Start the AP\r\n"));
+        shell.execute_line("wifi --help").unwrap();
+        let out = take_namespace_output();
+        assert!(out.contains("wifi.scan") && !out.contains("gpio"));
+
+        // Namespaced commands run by their full name
+        assert_eq!(
+            shell.execute_line("gpio.get 4"),
+            Ok(LineStatus::Executed(ShellResult::Ok))
+        );
+        assert_eq!(
+            shell.execute_line("list gp"),
+            Ok(LineStatus::Executed(ShellResult::InvalidParameter))
+        );
+        assert_eq!(
+            shell.execute_line("list gpio wifi"),
+            Ok(LineStatus::Executed(ShellResult::InvalidParameter))
+        );
+
+        // Paging counts commands only and repeats the heading mid-group
+        shell.set_list_paging(Some(2));
+        take_namespace_output();
+        shell.execute_line("list").unwrap();
+        assert!(shell.is_list_paused());
+        shell.input(b" ");
+        let out = take_namespace_output();
+        assert!(out.ends_with("gpio:\r\ngpio.get       Read a pin\r\nwifi:\r\nwifi.scan      Scan networks\r\n-- more --"));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_closure_commands() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut shell = Shell::new();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let log = seen.clone();
+        assert_eq!(
+            shell.register_closure("log", "Record arguments", move |argc, argv| {
+                log.borrow_mut().push(argv[1..argc].join(" "));
+                ShellResult::Ok
+            }),
+            ShellResult::Ok
+        );
+        assert_eq!(
+            shell.register_closure("", "Nameless", |_, _| ShellResult::Ok),
+            ShellResult::InvalidParameter
+        );
+
+        // Closures keep their state across invocations
+        assert_eq!(
+            shell.execute_line("log one"),
+            Ok(LineStatus::Executed(ShellResult::Ok))
+        );
+        assert_eq!(
+            shell.execute_line("log \"two words\" three"),
+            Ok(LineStatus::Executed(ShellResult::Ok))
+        );
+        assert_eq!(*seen.borrow(), ["one", "two words three"]);
+
+        // Help and lookup treat them like any other command
+        assert_eq!(
+            shell.execute_line("log --help"),
+            Ok(LineStatus::Executed(ShellResult::Ok))
+        );
+        assert_eq!(seen.borrow().len(), 2);
+
+        // Fixed dynamic commands win over closures of the same name
+        shell.register_command("log", "Fixed", |_, _| ShellResult::BufferOverflow);
+        assert_eq!(
+            shell.execute_line("log three"),
+            Ok(LineStatus::Executed(ShellResult::BufferOverflow))
+        );
+        assert_eq!(seen.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_context_shell() {
+        #[derive(Default)]
+        struct Counter {
+            count: usize,
+            last_arg: Option<String>,
+        }
+
+        fn count_handler(counter: &mut Counter, argc: usize, argv: &[&str]) -> ShellResult {
+            counter.count += 1;
+            counter.last_arg = (argc > 1).then(|| argv[1].to_string());
+            ShellResult::Ok
+        }
+
+        fn reset_handler(counter: &mut Counter, _argc: usize, _argv: &[&str]) -> ShellResult {
+            counter.count = 0;
+            ShellResult::Ok
+        }
+
+        static COMMANDS: [ContextCommand<Counter>; 1] =
+            [
+                Command::new_with_context("reset", "Reset the counter", reset_handler)
+                    .with_args(Some(0), Some(0)),
+            ];
+
+        let mut shell: ContextShell<Counter> = ContextShell::default();
+        shell.set_echo(false);
+        assert_eq!(
+            shell.register_command("count", "Count invocations", count_handler),
+            ShellResult::Ok
+        );
+        assert_eq!(shell.register_static_commands(&COMMANDS), ShellResult::Ok);
+
+        // Interactive input and execute_line both reach the context
+        assert_eq!(shell.input(b"count\r"), ShellResult::Ok);
+        assert_eq!(
+            shell.execute_line("count \"two words\""),
+            Ok(LineStatus::Executed(ShellResult::Ok))
+        );
+        assert_eq!(shell.context().count, 2);
+        assert_eq!(shell.context().last_arg.as_deref(), Some("two words"));
+
+        // Rejected argument counts and help don't call the handler
+        assert_eq!(
+            shell.execute_line("reset now"),
+            Ok(LineStatus::Executed(ShellResult::InvalidParameter))
+        );
+        assert_eq!(
+            shell.execute_line("count --help"),
+            Ok(LineStatus::Executed(ShellResult::Ok))
+        );
+        assert_eq!(shell.context().count, 2);
+
+        assert_eq!(
+            shell.execute_line("reset"),
+            Ok(LineStatus::Executed(ShellResult::Ok))
+        );
+        shell.context_mut().count += 5;
+        assert_eq!(shell.into_context().count, 5);
+    }
 }